@@ -0,0 +1,28 @@
+#!/usr/bin/env bash
+
+# builds kong_lp_ledger and sets its wasm in kong_backend
+# kong_backend creates a kong_lp_ledger canister for each new LP token once the wasm is set
+# ledgers of existing LP tokens are created with: dfx canister call kong_backend create_lp_token_ledger '("<LP token symbol>")'
+
+original_dir=$(pwd)
+root_dir="${original_dir}"/..
+
+if [ -z "$1" ]
+	then
+		NETWORK=""
+		KONG_BUILDENV="local"
+	else
+		NETWORK="--network $1"
+		KONG_BUILDENV="$1"
+fi
+IDENTITY="--identity kong"
+
+KONG_CANISTER=$(dfx canister id ${NETWORK} kong_backend)
+
+bash build_kong_lp_ledger.sh "${KONG_BUILDENV}"
+WASM="${root_dir}"/target/wasm32-unknown-unknown/release/kong_lp_ledger.wasm
+ARGUMENT_FILE=$(mktemp)
+echo "(blob \"$(hexdump -ve '1/1 "\\%.2x"' "${WASM}")\")" > "${ARGUMENT_FILE}"
+
+dfx canister call ${NETWORK} ${IDENTITY} ${KONG_CANISTER} set_lp_token_ledger_wasm --argument-file "${ARGUMENT_FILE}"
+rm "${ARGUMENT_FILE}"
@@ -0,0 +1,26 @@
+-- ============================================================================
+-- 0002 fee_sweep_tx
+-- ============================================================================
+-- Kong fee sweeps to the treasury and KONG buyback and burn.
+-- ============================================================================
+
+ALTER TYPE tx_type ADD VALUE IF NOT EXISTS 'fee_sweep';
+
+-- Fee Sweep Transaction table (no foreign keys)
+CREATE TABLE IF NOT EXISTS fee_sweep_tx (
+    tx_id BIGINT PRIMARY KEY,
+    token_id INT NOT NULL,
+    request_id BIGINT NOT NULL,
+    user_id INT NOT NULL,
+    status tx_status NOT NULL,
+    amount DOUBLE PRECISION NOT NULL,
+    pool_ids INT[] NOT NULL,
+    treasury_amount DOUBLE PRECISION NOT NULL,
+    buyback_amount DOUBLE PRECISION NOT NULL,
+    burn_amount DOUBLE PRECISION NOT NULL,
+    transfer_ids BIGINT[] NOT NULL,
+    ts TIMESTAMP NOT NULL
+);
+
+-- Create indexes on fee_sweep_tx
+CREATE INDEX IF NOT EXISTS fee_sweep_tx_token_id_idx ON fee_sweep_tx USING btree (token_id);
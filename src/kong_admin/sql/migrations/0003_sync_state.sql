@@ -0,0 +1,12 @@
+-- ============================================================================
+-- 0003 sync_state
+-- ============================================================================
+-- Last db_update_id processed by the --db_updates loop.
+-- ============================================================================
+
+CREATE TABLE IF NOT EXISTS sync_state (
+    id INTEGER PRIMARY KEY DEFAULT 1,
+    last_db_update_id BIGINT NOT NULL,
+    last_updated_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    CONSTRAINT single_row CHECK (id = 1)
+);
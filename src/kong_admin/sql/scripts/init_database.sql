@@ -26,7 +26,7 @@ CREATE TYPE token_type AS ENUM ('IC', 'LP');
 CREATE TYPE request_type AS ENUM ('add_pool', 'add_liquidity', 'remove_liquidity', 'swap', 'claim', 'send');
 
 -- Transaction types and status
-CREATE TYPE tx_type AS ENUM ('add_pool', 'add_liquidity', 'remove_liquidity', 'swap', 'send');
+CREATE TYPE tx_type AS ENUM ('add_pool', 'add_liquidity', 'remove_liquidity', 'swap', 'send', 'fee_sweep');
 CREATE TYPE tx_status AS ENUM ('Success', 'Failed');
 
 -- Claim status
@@ -308,6 +308,25 @@ CREATE INDEX send_tx_to_user_id_idx ON send_tx USING btree (to_user_id);
 CREATE INDEX send_tx_token_id_idx ON send_tx USING btree (token_id);
 CREATE INDEX send_tx_user_id_idx ON send_tx USING btree (user_id);
 
+-- Fee Sweep Transaction table (no foreign keys)
+CREATE TABLE fee_sweep_tx (
+    tx_id BIGINT PRIMARY KEY,
+    token_id INT NOT NULL,
+    request_id BIGINT NOT NULL,
+    user_id INT NOT NULL,
+    status tx_status NOT NULL,
+    amount DOUBLE PRECISION NOT NULL,
+    pool_ids INT[] NOT NULL,
+    treasury_amount DOUBLE PRECISION NOT NULL,
+    buyback_amount DOUBLE PRECISION NOT NULL,
+    burn_amount DOUBLE PRECISION NOT NULL,
+    transfer_ids BIGINT[] NOT NULL,
+    ts TIMESTAMP NOT NULL
+);
+
+-- Create indexes on fee_sweep_tx
+CREATE INDEX fee_sweep_tx_token_id_idx ON fee_sweep_tx USING btree (token_id);
+
 -- ============================================================================
 -- COMMIT TRANSACTION
 -- ============================================================================
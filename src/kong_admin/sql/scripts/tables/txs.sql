@@ -1,4 +1,4 @@
-CREATE TYPE tx_type AS ENUM ('add_pool', 'add_liquidity', 'remove_liquidity', 'swap', 'send');
+CREATE TYPE tx_type AS ENUM ('add_pool', 'add_liquidity', 'remove_liquidity', 'swap', 'send', 'fee_sweep');
 
 CREATE TYPE tx_status AS ENUM ('Success', 'Failed');
 
@@ -97,6 +97,21 @@ CREATE TABLE send_tx (
     ts TIMESTAMP NOT NULL
 );
 
+CREATE TABLE fee_sweep_tx (
+    tx_id BIGINT REFERENCES txs(tx_id) PRIMARY KEY,
+    token_id INT REFERENCES tokens(token_id) NOT NULL,
+    request_id BIGINT REFERENCES requests(request_id) NOT NULL,
+    user_id INT REFERENCES users(user_id) NOT NULL,
+    status tx_status NOT NULL,
+    amount DOUBLE PRECISION NOT NULL,
+    pool_ids INT[] NOT NULL,
+    treasury_amount DOUBLE PRECISION NOT NULL,
+    buyback_amount DOUBLE PRECISION NOT NULL,
+    burn_amount DOUBLE PRECISION NOT NULL,
+    transfer_ids BIGINT[] NOT NULL,
+    ts TIMESTAMP NOT NULL
+);
+
 -- public.txs definition
 
 -- Drop table
@@ -14,6 +14,7 @@ use tokio_postgres::Client;
 
 use super::kong_update::KongUpdate;
 use super::math_helpers::round_f64;
+use super::sink::KongSink;
 
 #[derive(Debug, ToSql, FromSql)]
 #[postgres(name = "claim_status")]
@@ -78,7 +79,7 @@ pub fn serialize_claim(claim: &StableClaim) -> serde_json::Value {
     })
 }
 
-pub async fn update_claims_on_database(db_client: &Client, tokens_map: &BTreeMap<u32, u8>) -> Result<(), Box<dyn std::error::Error>> {
+pub async fn update_claims_on_database<T: KongSink>(sink: &T, tokens_map: &BTreeMap<u32, u8>) -> Result<(), Box<dyn std::error::Error>> {
     let dir_path = "./backups";
     let re_pattern = Regex::new(r"^claims.*.json$").unwrap();
     let mut files = fs::read_dir(dir_path)?
@@ -107,7 +108,7 @@ pub async fn update_claims_on_database(db_client: &Client, tokens_map: &BTreeMap
         let claim_map: BTreeMap<StableClaimId, StableClaim> = serde_json::from_reader(reader)?;
 
         for v in claim_map.values() {
-            insert_claim_on_database(v, db_client, tokens_map).await?;
+            sink.insert_claim(v, tokens_map).await?;
         }
     }
 
@@ -1,87 +1,91 @@
+use super::kong_data::KongData;
+use super::sink::KongSink;
 use chrono::Local;
-use kong_lib::stable_db_update::stable_db_update::{StableDBUpdate, StableMemory};
+use kong_lib::stable_db_update::stable_db_update::StableMemory;
 use kong_lib::stable_token::token::Token;
 use std::collections::BTreeMap;
-use tokio_postgres::Client;
 
-use crate::claims::insert_claim_on_database;
-use crate::lp_tokens::insert_lp_token_on_database;
-use crate::pools::{insert_pool_on_database, load_pools_from_database, query_pool_token_ids};
-use crate::requests::insert_request_on_database;
-use crate::tokens::{insert_token_on_database, load_tokens_from_database, query_token_decimals};
-use crate::transfers::insert_transfer_on_database;
-use crate::txs::insert_tx_on_database;
-use crate::users::insert_user_on_database;
+/// position of the db_updates loop in the db updates of kong_data. the sync state is saved, and the db updates
+/// acknowledged, every save_interval pages rather than after every page to reduce the I/O of the sink
+pub struct SyncCursor {
+    pub last_db_update_id: Option<u64>, // last db_update_id written to the sink. None before the first db update
+    pages_since_save: u64,
+    save_interval: u64,
+}
 
-use super::kong_data::KongData;
+impl SyncCursor {
+    pub fn new(last_db_update_id: Option<u64>, save_interval: u64) -> Self {
+        SyncCursor {
+            last_db_update_id,
+            pages_since_save: 0,
+            save_interval,
+        }
+    }
 
-pub async fn get_db_updates(
+    /// move to the last db_update_id of a page written to the sink. returns whether the sync state should be saved
+    pub fn advance(&mut self, db_update_id: u64) -> bool {
+        self.last_db_update_id = Some(db_update_id);
+        self.pages_since_save += 1;
+        if self.pages_since_save < self.save_interval {
+            return false;
+        }
+        self.pages_since_save = 0;
+        true
+    }
+}
+
+/// write the next page of db updates after last_db_update_id to the sink. returns the last db_update_id written and
+/// whether kong_data has more db updates after it
+pub async fn get_db_updates<T: KongSink>(
     last_db_update_id: Option<u64>,
     kong_data: &KongData,
-    db_client: &Client,
+    sink: &T,
     tokens_map: &mut BTreeMap<u32, u8>,
     pools_map: &mut BTreeMap<u32, (u32, u32)>,
-) -> Result<u64, Box<dyn std::error::Error>> {
-    // start from last_db_update_id + 1 as last_db_update is already processed
-    let db_update_id = last_db_update_id.map(|id| id + 1);
-    let json = kong_data.backup_db_updates(db_update_id).await?;
-    let db_updates: Vec<StableDBUpdate> = serde_json::from_str(&json)?;
+) -> Result<(u64, bool), Box<dyn std::error::Error>> {
+    // last_db_update_id is the cursor as it is already processed
+    let reply = kong_data.db_updates(last_db_update_id, None).await?;
+    let db_updates = reply.db_updates;
     if db_updates.is_empty() {
-        return Ok(last_db_update_id.unwrap_or(0));
+        return Ok((reply.next_cursor, reply.has_more));
     }
 
     let current_time = Local::now();
     let formatted_time = current_time.format("%Y-%m-%d %H:%M:%S").to_string();
-    println!("\n--- processing db_update_id={:?} @ {} ---", db_update_id, formatted_time);
+    println!(
+        "\n--- processing db_update_id={} @ {} ---",
+        db_updates[0].db_update_id, formatted_time
+    );
 
-    let mut last_update_id = last_db_update_id.unwrap_or(0);
     for db_update in db_updates.iter() {
-        last_update_id = db_update.db_update_id;
         let stable_memory = &db_update.stable_memory;
         match stable_memory {
             StableMemory::KongSettings(_) => (),
-            StableMemory::UserMap(user) => insert_user_on_database(user, db_client)
-                .await
-                .unwrap_or_else(|e| eprintln!("{}", e)),
-            StableMemory::TokenMap(token) => match insert_token_on_database(token, db_client).await {
+            StableMemory::UserMap(user) => sink.insert_user(user).await.unwrap_or_else(|e| eprintln!("{}", e)),
+            StableMemory::TokenMap(token) => match sink.insert_token(token).await {
                 Ok(()) => {
-                    if !tokens_map.contains_key(&token.token_id()) {
-                        match query_token_decimals(db_client, token.token_id()).await {
-                            Ok(decimals) => {
-                                tokens_map.insert(token.token_id(), decimals);
-                            }
-                            Err(e) => eprintln!("Failed to query token decimals: {}", e),
-                        }
-                    }
+                    tokens_map.insert(token.token_id(), token.decimals());
                 }
                 Err(e) => eprintln!("{}", e),
             },
-            StableMemory::PoolMap(pool) => match insert_pool_on_database(pool, db_client, tokens_map).await {
+            StableMemory::PoolMap(pool) => match sink.insert_pool(pool, tokens_map).await {
                 Ok(()) => {
-                    if !pools_map.contains_key(&pool.pool_id) {
-                        match query_pool_token_ids(db_client, pool.pool_id).await {
-                            Ok(token_ids) => {
-                                pools_map.insert(pool.pool_id, token_ids);
-                            }
-                            Err(e) => eprintln!("Failed to query pool token IDs: {}", e),
-                        }
-                    }
+                    pools_map.insert(pool.pool_id, (pool.token_id_0, pool.token_id_1));
                 }
                 Err(e) => eprintln!("{}", e),
             },
-            StableMemory::TxMap(tx) => insert_tx_on_database(tx, db_client, tokens_map, pools_map)
-                .await
-                .unwrap_or_else(|e| eprintln!("{}", e)),
-            StableMemory::RequestMap(request) => insert_request_on_database(request, db_client)
-                .await
-                .unwrap_or_else(|e| eprintln!("{}", e)),
-            StableMemory::TransferMap(transfer) => insert_transfer_on_database(transfer, db_client, tokens_map)
+            StableMemory::TxMap(tx) => sink
+                .insert_tx(tx, tokens_map, pools_map)
                 .await
                 .unwrap_or_else(|e| eprintln!("{}", e)),
-            StableMemory::ClaimMap(claim) => insert_claim_on_database(claim, db_client, tokens_map)
+            StableMemory::RequestMap(request) => sink.insert_request(request).await.unwrap_or_else(|e| eprintln!("{}", e)),
+            StableMemory::TransferMap(transfer) => sink
+                .insert_transfer(transfer, tokens_map)
                 .await
                 .unwrap_or_else(|e| eprintln!("{}", e)),
-            StableMemory::LPTokenMap(lptoken) => insert_lp_token_on_database(lptoken, db_client, tokens_map)
+            StableMemory::ClaimMap(claim) => sink.insert_claim(claim, tokens_map).await.unwrap_or_else(|e| eprintln!("{}", e)),
+            StableMemory::LPTokenMap(lptoken) => sink
+                .insert_lp_token(lptoken, tokens_map)
                 .await
                 .unwrap_or_else(|e| eprintln!("{}", e)),
         }
@@ -89,9 +93,32 @@ pub async fn get_db_updates(
 
     println!(
         "--- processed db_update_id={} - {} records updated ---",
-        last_update_id,
+        reply.next_cursor,
         db_updates.len()
     );
 
-    Ok(last_update_id)
+    Ok((reply.next_cursor, reply.has_more))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_sync_cursor() {
+        // resumes from the sync state of the sink
+        let mut cursor = SyncCursor::new(Some(5), 3);
+        assert!(!cursor.advance(8));
+        assert_eq!(cursor.last_db_update_id, Some(8));
+        // an empty page keeps the cursor but still counts towards the save interval
+        assert!(!cursor.advance(8));
+        assert!(cursor.advance(12));
+        assert_eq!(cursor.last_db_update_id, Some(12));
+        assert!(!cursor.advance(13));
+
+        let mut cursor = SyncCursor::new(None, 1);
+        assert_eq!(cursor.last_db_update_id, None);
+        assert!(cursor.advance(1));
+        assert!(cursor.advance(2));
+    }
 }
@@ -0,0 +1,423 @@
+use arrow::array::{ArrayRef, BooleanArray, Float64Array, Int64Array, StringArray, TimestampNanosecondArray};
+use arrow::datatypes::{DataType, Field, Schema, TimeUnit};
+use arrow::record_batch::RecordBatch;
+use chrono::{DateTime, Utc};
+use kong_lib::stable_claim::stable_claim::StableClaim;
+use kong_lib::stable_lp_token::stable_lp_token::StableLPToken;
+use kong_lib::stable_pool::stable_pool::StablePool;
+use kong_lib::stable_request::stable_request::StableRequest;
+use kong_lib::stable_token::stable_token::StableToken;
+use kong_lib::stable_token::token::Token;
+use kong_lib::stable_transfer::stable_transfer::StableTransfer;
+use kong_lib::stable_tx::stable_tx::StableTx;
+use kong_lib::stable_user::stable_user::StableUser;
+use parquet::arrow::ArrowWriter;
+use parquet::basic::Compression;
+use parquet::file::properties::WriterProperties;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fs::{self, File};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex, MutexGuard};
+use tracing::info;
+
+use crate::sink::{
+    to_claim_record, to_lp_token_record, to_pool_record, to_request_record, to_token_record, to_transfer_record, to_tx_record,
+    to_user_record, ColumnType, KongSink, SinkRecord, SinkTable, SinkValue,
+};
+
+// records are buffered in memory and written out when there are this many, and on flush
+const MAX_BUFFERED_RECORDS: usize = 100_000;
+
+#[derive(Debug, Clone, Copy)]
+pub enum FileFormat {
+    Parquet,
+    Csv,
+}
+
+impl FileFormat {
+    fn extension(&self) -> &'static str {
+        match self {
+            FileFormat::Parquet => "parquet",
+            FileFormat::Csv => "csv",
+        }
+    }
+}
+
+/// tokens and pools written to the sink. kept in {dir}/tokens_pools.json as the files are not read back, so amounts
+/// can still be converted to token units after a restart
+#[derive(Default, Serialize, Deserialize)]
+struct SinkMaps {
+    tokens_map: BTreeMap<u32, u8>,
+    symbols_map: BTreeMap<u32, String>,
+    pools_map: BTreeMap<u32, (u32, u32)>,
+}
+
+#[derive(Default)]
+struct FileBuffer {
+    partitions: BTreeMap<(&'static str, String), Vec<SinkRecord>>, // (table, date) -> records
+    len: usize,
+    maps: SinkMaps,
+}
+
+/// partitioned Parquet or CSV files with the columns of the SinkTable, one file per table and date on every flush
+///
+/// {dir}/{table}/date=YYYY-MM-DD/part-{flush_ns}.{parquet|csv}
+///
+/// the date is of ts, or of when the record was written for users, tokens and pools. files are append-only, so a record
+/// updated later is in more than one file and readers should keep the last row of each id
+pub struct FileSink {
+    dir: PathBuf,
+    format: FileFormat,
+    buffer: Mutex<FileBuffer>,
+}
+
+impl FileSink {
+    pub fn new(dir: &str, format: FileFormat) -> Result<Self, Box<dyn std::error::Error>> {
+        fs::create_dir_all(dir)?;
+        let dir = PathBuf::from(dir);
+        let maps_path = dir.join("tokens_pools.json");
+        let maps = if maps_path.exists() {
+            serde_json::from_str(&fs::read_to_string(&maps_path)?)?
+        } else {
+            SinkMaps::default()
+        };
+        info!("{:?} sink writing to {}", format, dir.display());
+        Ok(FileSink {
+            dir,
+            format,
+            buffer: Mutex::new(FileBuffer {
+                maps,
+                ..Default::default()
+            }),
+        })
+    }
+
+    fn lock(&self) -> Result<MutexGuard<'_, FileBuffer>, Box<dyn std::error::Error>> {
+        Ok(self.buffer.lock().map_err(|_| "File sink buffer lock poisoned")?)
+    }
+
+    fn insert(&self, record: SinkRecord) -> Result<(), Box<dyn std::error::Error>> {
+        self.push(&mut *self.lock()?, record)
+    }
+
+    fn push(&self, buffer: &mut FileBuffer, record: SinkRecord) -> Result<(), Box<dyn std::error::Error>> {
+        let date = match record.ts {
+            Some(ts) => DateTime::from_timestamp_nanos(ts as i64),
+            None => Utc::now(),
+        }
+        .format("%Y-%m-%d")
+        .to_string();
+        buffer.partitions.entry((record.table.name, date)).or_default().push(record);
+        buffer.len += 1;
+        if buffer.len >= MAX_BUFFERED_RECORDS {
+            self.write_partitions(buffer)?;
+        }
+        Ok(())
+    }
+
+    fn write_partitions(&self, buffer: &mut FileBuffer) -> Result<(), Box<dyn std::error::Error>> {
+        let flush_ns = Utc::now().timestamp_nanos_opt().unwrap_or_default();
+        for ((table, date), records) in std::mem::take(&mut buffer.partitions) {
+            let partition_dir = self.dir.join(table).join(format!("date={}", date));
+            fs::create_dir_all(&partition_dir)?;
+            let path = partition_dir.join(format!("part-{}.{}", flush_ns, self.format.extension()));
+            self.write_file(&path, &records)?;
+            info!("{} {} records written to {}", records.len(), table, path.display());
+        }
+        buffer.len = 0;
+        write_atomic(&self.dir.join("tokens_pools.json"), &serde_json::to_string(&buffer.maps)?)
+    }
+
+    // written to a hidden file first so readers never see a partial file
+    fn write_file(&self, path: &Path, records: &[SinkRecord]) -> Result<(), Box<dyn std::error::Error>> {
+        let batch = to_record_batch(records[0].table, records)?;
+        let file_name = path.file_name().ok_or("Invalid file name")?.to_string_lossy();
+        let tmp_path = path.with_file_name(format!(".{}.tmp", file_name));
+        let file = File::create(&tmp_path)?;
+        match self.format {
+            FileFormat::Parquet => {
+                let props = WriterProperties::builder().set_compression(Compression::SNAPPY).build();
+                let mut writer = ArrowWriter::try_new(file, batch.schema(), Some(props))?;
+                writer.write(&batch)?;
+                writer.close()?;
+            }
+            FileFormat::Csv => {
+                let mut writer = arrow::csv::WriterBuilder::new().with_header(true).build(file);
+                writer.write(&batch)?;
+            }
+        }
+        fs::rename(&tmp_path, path)?;
+        Ok(())
+    }
+
+    fn sync_state_path(&self) -> PathBuf {
+        self.dir.join("sync_state.json")
+    }
+}
+
+fn write_atomic(path: &Path, contents: &str) -> Result<(), Box<dyn std::error::Error>> {
+    let file_name = path.file_name().ok_or("Invalid file name")?.to_string_lossy();
+    let tmp_path = path.with_file_name(format!(".{}.tmp", file_name));
+    fs::write(&tmp_path, contents)?;
+    fs::rename(&tmp_path, path)?;
+    Ok(())
+}
+
+fn to_record_batch(table: &SinkTable, records: &[SinkRecord]) -> Result<RecordBatch, Box<dyn std::error::Error>> {
+    let mut fields = Vec::new();
+    let mut columns: Vec<ArrayRef> = Vec::new();
+    for (i, (name, column_type)) in table.columns.iter().enumerate() {
+        let values = records.iter().map(|record| &record.values[i]);
+        let (data_type, column): (DataType, ArrayRef) = match column_type {
+            ColumnType::Int => (
+                DataType::Int64,
+                Arc::new(
+                    values
+                        .map(|v| if let SinkValue::Int(v) = v { Some(*v) } else { None })
+                        .collect::<Int64Array>(),
+                ),
+            ),
+            ColumnType::Float => (
+                DataType::Float64,
+                Arc::new(
+                    values
+                        .map(|v| if let SinkValue::Float(v) = v { Some(*v) } else { None })
+                        .collect::<Float64Array>(),
+                ),
+            ),
+            ColumnType::Text => (
+                DataType::Utf8,
+                Arc::new(
+                    values
+                        .map(|v| if let SinkValue::Text(v) = v { Some(v.as_str()) } else { None })
+                        .collect::<StringArray>(),
+                ),
+            ),
+            ColumnType::Bool => (
+                DataType::Boolean,
+                Arc::new(
+                    values
+                        .map(|v| if let SinkValue::Bool(v) = v { Some(*v) } else { None })
+                        .collect::<BooleanArray>(),
+                ),
+            ),
+            ColumnType::Timestamp => (
+                DataType::Timestamp(TimeUnit::Nanosecond, Some("+00:00".into())),
+                Arc::new(
+                    values
+                        .map(|v| if let SinkValue::Int(v) = v { Some(*v) } else { None })
+                        .collect::<TimestampNanosecondArray>()
+                        .with_timezone("+00:00"),
+                ),
+            ),
+        };
+        // the first column is the primary key
+        fields.push(Field::new(*name, data_type, i > 0));
+        columns.push(column);
+    }
+    Ok(RecordBatch::try_new(Arc::new(Schema::new(fields)), columns)?)
+}
+
+impl KongSink for FileSink {
+    async fn insert_user(&self, user: &StableUser) -> Result<(), Box<dyn std::error::Error>> {
+        self.insert(to_user_record(user))
+    }
+
+    async fn insert_token(&self, token: &StableToken) -> Result<(), Box<dyn std::error::Error>> {
+        let mut buffer = self.lock()?;
+        buffer.maps.tokens_map.insert(token.token_id(), token.decimals());
+        buffer.maps.symbols_map.insert(token.token_id(), token.symbol());
+        self.push(&mut buffer, to_token_record(token))
+    }
+
+    async fn insert_pool(&self, pool: &StablePool, tokens_map: &BTreeMap<u32, u8>) -> Result<(), Box<dyn std::error::Error>> {
+        let mut buffer = self.lock()?;
+        let record = to_pool_record(pool, tokens_map, &buffer.maps.symbols_map)?;
+        buffer.maps.pools_map.insert(pool.pool_id, (pool.token_id_0, pool.token_id_1));
+        self.push(&mut buffer, record)
+    }
+
+    async fn insert_lp_token(&self, lp_token: &StableLPToken, tokens_map: &BTreeMap<u32, u8>) -> Result<(), Box<dyn std::error::Error>> {
+        let mut buffer = self.lock()?;
+        let record = to_lp_token_record(lp_token, tokens_map, &buffer.maps.symbols_map)?;
+        self.push(&mut buffer, record)
+    }
+
+    async fn insert_request(&self, request: &StableRequest) -> Result<(), Box<dyn std::error::Error>> {
+        self.insert(to_request_record(request))
+    }
+
+    async fn insert_claim(&self, claim: &StableClaim, tokens_map: &BTreeMap<u32, u8>) -> Result<(), Box<dyn std::error::Error>> {
+        let mut buffer = self.lock()?;
+        let record = to_claim_record(claim, tokens_map, &buffer.maps.symbols_map)?;
+        self.push(&mut buffer, record)
+    }
+
+    async fn insert_transfer(&self, transfer: &StableTransfer, tokens_map: &BTreeMap<u32, u8>) -> Result<(), Box<dyn std::error::Error>> {
+        let mut buffer = self.lock()?;
+        let record = to_transfer_record(transfer, tokens_map, &buffer.maps.symbols_map)?;
+        self.push(&mut buffer, record)
+    }
+
+    async fn insert_tx(
+        &self,
+        tx: &StableTx,
+        tokens_map: &BTreeMap<u32, u8>,
+        pools_map: &BTreeMap<u32, (u32, u32)>,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        let mut buffer = self.lock()?;
+        let record = to_tx_record(tx, tokens_map, pools_map, &buffer.maps.symbols_map)?;
+        self.push(&mut buffer, record)
+    }
+
+    async fn load_tokens(&self) -> Result<BTreeMap<u32, u8>, Box<dyn std::error::Error>> {
+        Ok(self.lock()?.maps.tokens_map.clone())
+    }
+
+    async fn load_pools(&self) -> Result<BTreeMap<u32, (u32, u32)>, Box<dyn std::error::Error>> {
+        Ok(self.lock()?.maps.pools_map.clone())
+    }
+
+    async fn load_sync_state(&self) -> Result<Option<u64>, Box<dyn std::error::Error>> {
+        let path = self.sync_state_path();
+        if !path.exists() {
+            info!("No previous sync state found in {}, starting from beginning", path.display());
+            return Ok(None);
+        }
+        let sync_state: serde_json::Value = serde_json::from_str(&fs::read_to_string(&path)?)?;
+        let last_id = sync_state["last_db_update_id"]
+            .as_u64()
+            .ok_or(format!("Invalid sync state in {}", path.display()))?;
+        info!("Loaded sync state from {}: last_db_update_id={}", path.display(), last_id);
+        Ok(Some(last_id))
+    }
+
+    async fn save_sync_state(&self, last_db_update_id: u64) -> Result<(), Box<dyn std::error::Error>> {
+        self.write_partitions(&mut *self.lock()?)?;
+        let sync_state = serde_json::json!({
+            "last_db_update_id": last_db_update_id,
+            "last_updated_at": Utc::now().to_rfc3339(),
+        });
+        write_atomic(&self.sync_state_path(), &serde_json::to_string_pretty(&sync_state)?)
+    }
+
+    async fn flush(&self) -> Result<(), Box<dyn std::error::Error>> {
+        self.write_partitions(&mut *self.lock()?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::sink::tests::{ckusdt, icp, pool, pools_map, swap_tx, tokens_map};
+    use arrow::array::Float64Array;
+    use parquet::arrow::arrow_reader::ParquetRecordBatchReaderBuilder;
+
+    const JAN_31: u64 = 1_706_745_599_000_000_000; // 2024-01-31 23:59:59
+    const FEB_1: u64 = 1_706_745_600_000_000_000; // 2024-02-01 00:00:00
+
+    fn sink_dir(name: &str) -> String {
+        let dir = std::env::temp_dir().join(format!("kong_admin_{}_{}", name, std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        dir.to_string_lossy().to_string()
+    }
+
+    // files of the partition, ignoring the hidden temporary files
+    fn partition_files(dir: &str, table: &str, date: &str) -> Vec<PathBuf> {
+        fs::read_dir(Path::new(dir).join(table).join(format!("date={}", date)))
+            .unwrap()
+            .map(|entry| entry.unwrap().path())
+            .filter(|path| !path.file_name().unwrap().to_string_lossy().starts_with('.'))
+            .collect()
+    }
+
+    async fn insert_records(sink: &FileSink) {
+        sink.insert_token(&icp()).await.unwrap();
+        sink.insert_token(&ckusdt()).await.unwrap();
+        sink.insert_pool(&pool(), &tokens_map()).await.unwrap();
+        sink.insert_tx(&swap_tx(1, JAN_31), &tokens_map(), &pools_map()).await.unwrap();
+        sink.insert_tx(&swap_tx(2, FEB_1), &tokens_map(), &pools_map()).await.unwrap();
+        sink.insert_tx(&swap_tx(3, FEB_1), &tokens_map(), &pools_map()).await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_csv_partitions() {
+        let dir = sink_dir("csv_partitions");
+        let sink = FileSink::new(&dir, FileFormat::Csv).unwrap();
+        insert_records(&sink).await;
+        sink.flush().await.unwrap();
+
+        let files = partition_files(&dir, "txs", "2024-01-31");
+        assert_eq!(files.len(), 1);
+        assert_eq!(files[0].extension().unwrap(), "csv");
+        let csv = fs::read_to_string(&files[0]).unwrap();
+        let mut lines = csv.lines();
+        assert!(lines
+            .next()
+            .unwrap()
+            .starts_with("tx_id,request_id,user_id,tx_type,status,pool_id,token_id_0,symbol_0,amount_0"));
+        assert!(lines.next().unwrap().starts_with("1,1,100,swap,Success,,1,ICP,1.0,"));
+        assert_eq!(lines.next(), None);
+        let files = partition_files(&dir, "txs", "2024-02-01");
+        assert_eq!(fs::read_to_string(&files[0]).unwrap().lines().count(), 3);
+
+        // every flush writes a new file to the partition
+        sink.insert_tx(&swap_tx(4, FEB_1), &tokens_map(), &pools_map()).await.unwrap();
+        sink.flush().await.unwrap();
+        assert_eq!(partition_files(&dir, "txs", "2024-02-01").len(), 2);
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[tokio::test]
+    async fn test_parquet_partitions() {
+        let dir = sink_dir("parquet_partitions");
+        let sink = FileSink::new(&dir, FileFormat::Parquet).unwrap();
+        insert_records(&sink).await;
+        sink.flush().await.unwrap();
+
+        let files = partition_files(&dir, "txs", "2024-02-01");
+        assert_eq!(files.len(), 1);
+        let batches = ParquetRecordBatchReaderBuilder::try_new(File::open(&files[0]).unwrap())
+            .unwrap()
+            .build()
+            .unwrap()
+            .collect::<Result<Vec<_>, _>>()
+            .unwrap();
+        assert_eq!(batches.len(), 1);
+        let batch = &batches[0];
+        assert_eq!(batch.num_rows(), 2);
+        assert_eq!(
+            batch.schema().field_with_name("ts").unwrap().data_type(),
+            &DataType::Timestamp(TimeUnit::Nanosecond, Some("+00:00".into()))
+        );
+        let amount_1 = batch
+            .column_by_name("amount_1")
+            .unwrap()
+            .as_any()
+            .downcast_ref::<Float64Array>()
+            .unwrap();
+        assert_eq!(amount_1.value(0), 8.123456);
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[tokio::test]
+    async fn test_sync_state() {
+        let dir = sink_dir("file_sync_state");
+        let sink = FileSink::new(&dir, FileFormat::Csv).unwrap();
+        assert_eq!(sink.load_sync_state().await.unwrap(), None);
+        insert_records(&sink).await;
+        // saving the sync state writes the buffered records first
+        sink.save_sync_state(3).await.unwrap();
+        assert_eq!(partition_files(&dir, "txs", "2024-01-31").len(), 1);
+        sink.save_sync_state(5).await.unwrap();
+        drop(sink);
+
+        // the sync state, tokens and pools are restored for the next run
+        let sink = FileSink::new(&dir, FileFormat::Csv).unwrap();
+        assert_eq!(sink.load_sync_state().await.unwrap(), Some(5));
+        assert_eq!(sink.load_tokens().await.unwrap(), tokens_map());
+        assert_eq!(sink.load_pools().await.unwrap(), pools_map());
+        let _ = fs::remove_dir_all(&dir);
+    }
+}
@@ -1,6 +1,7 @@
 use anyhow::Result;
-use candid::{Decode, Encode, Principal};
+use candid::{encode_args, Decode, Encode, Principal};
 use ic_agent::Agent;
+use kong_lib::db_updates::db_updates_reply::{DBUpdateConsumerReply, DBUpdatesReply};
 use kong_lib::ic::canister_address::KONG_DATA;
 
 use super::kong_update::KongUpdate;
@@ -37,6 +38,37 @@ impl KongData {
         call_result.map_err(|e| anyhow::anyhow!(e))
     }
 
+    /// db updates after cursor, the last db_update_id already processed
+    pub async fn db_updates(&self, cursor: Option<u64>, limit: Option<u32>) -> Result<DBUpdatesReply> {
+        let result = self
+            .agent
+            .query(&self.canister_id, "db_updates")
+            .with_arg(encode_args((cursor, limit))?)
+            .await?;
+        let call_result = Decode!(result.as_slice(), Result<DBUpdatesReply, String>)?;
+        call_result.map_err(|e| anyhow::anyhow!(e))
+    }
+
+    pub async fn register_db_update_consumer(&self, consumer_id: &str) -> Result<DBUpdateConsumerReply> {
+        let result = self
+            .agent
+            .update(&self.canister_id, "register_db_update_consumer")
+            .with_arg(Encode!(&consumer_id)?)
+            .await?;
+        let call_result = Decode!(result.as_slice(), Result<DBUpdateConsumerReply, String>)?;
+        call_result.map_err(|e| anyhow::anyhow!(e))
+    }
+
+    pub async fn ack_db_updates(&self, consumer_id: &str, db_update_id: u64) -> Result<DBUpdateConsumerReply> {
+        let result = self
+            .agent
+            .update(&self.canister_id, "ack_db_updates")
+            .with_arg(encode_args((consumer_id, db_update_id))?)
+            .await?;
+        let call_result = Decode!(result.as_slice(), Result<DBUpdateConsumerReply, String>)?;
+        call_result.map_err(|e| anyhow::anyhow!(e))
+    }
+
     #[allow(dead_code)]
     pub async fn remove_db_updates(&self, ts: u64) -> Result<String> {
         let result = self
@@ -11,6 +11,7 @@ use tokio_postgres::Client;
 
 use super::kong_update::KongUpdate;
 use super::math_helpers::round_f64;
+use super::sink::KongSink;
 
 pub fn serialize_lp_tokens(lp_token: &StableLPToken) -> serde_json::Value {
     json!({
@@ -24,7 +25,7 @@ pub fn serialize_lp_tokens(lp_token: &StableLPToken) -> serde_json::Value {
     })
 }
 
-pub async fn update_lp_tokens_on_database(db_client: &Client, tokens_map: &BTreeMap<u32, u8>) -> Result<(), Box<dyn std::error::Error>> {
+pub async fn update_lp_tokens_on_database<T: KongSink>(sink: &T, tokens_map: &BTreeMap<u32, u8>) -> Result<(), Box<dyn std::error::Error>> {
     let dir_path = "./backups";
     let re_pattern = Regex::new(r"^lp_tokens.*.json$").unwrap();
     let mut files = fs::read_dir(dir_path)?
@@ -53,7 +54,7 @@ pub async fn update_lp_tokens_on_database(db_client: &Client, tokens_map: &BTree
         let lp_token_ledger_map: BTreeMap<StableLPTokenId, StableLPToken> = serde_json::from_reader(reader)?;
 
         for v in lp_token_ledger_map.values() {
-            insert_lp_token_on_database(v, db_client, tokens_map).await?;
+            sink.insert_lp_token(v, tokens_map).await?;
         }
     }
 
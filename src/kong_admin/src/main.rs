@@ -11,24 +11,33 @@ use tracing_subscriber::EnvFilter;
 
 use agent::create_agent_from_identity;
 use agent::{create_anonymous_identity, create_identity_from_pem_file};
-use db_updates::get_db_updates;
+use db_updates::{get_db_updates, SyncCursor};
+use file_sink::{FileFormat, FileSink};
 use kong_backend::KongBackend;
 use kong_data::KongData;
-use settings::Settings;
+use postgres_sink::PostgresSink;
+use settings::{Settings, Sink};
+use sink::KongSink;
+use sqlite_sink::SqliteSink;
 
 mod agent;
 mod claims;
 mod db_updates;
+mod file_sink;
 mod kong_backend;
 mod kong_data;
 mod kong_settings;
 mod kong_update;
 mod lp_tokens;
 mod math_helpers;
+mod migrations;
 mod nat_helpers;
 mod pools;
+mod postgres_sink;
 mod requests;
 mod settings;
+mod sink;
+mod sqlite_sink;
 mod tokens;
 mod transfers;
 mod txs;
@@ -37,60 +46,6 @@ mod users;
 const LOCAL_REPLICA: &str = "http://localhost:8000";
 const MAINNET_REPLICA: &str = "https://ic0.app";
 
-async fn load_sync_state(pool: &Pool) -> Result<Option<u64>, Box<dyn std::error::Error>> {
-    let client = pool.get().await?;
-
-    // Create sync_state table if it doesn't exist
-    client
-        .execute(
-            "CREATE TABLE IF NOT EXISTS sync_state (
-                id INTEGER PRIMARY KEY DEFAULT 1,
-                last_db_update_id BIGINT NOT NULL,
-                last_updated_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
-                CONSTRAINT single_row CHECK (id = 1)
-            )",
-            &[],
-        )
-        .await?;
-
-    // Load current sync state using prepared statement cache
-    let stmt = client
-        .prepare_cached("SELECT last_db_update_id FROM sync_state WHERE id = 1")
-        .await?;
-    let row = client.query_opt(&stmt, &[]).await?;
-
-    match row {
-        Some(row) => {
-            let last_id: i64 = row.get(0);
-            info!("Loaded sync state from database: last_db_update_id={}", last_id);
-            Ok(Some(last_id as u64))
-        }
-        None => {
-            info!("No previous sync state found in database, starting from beginning");
-            Ok(None)
-        }
-    }
-}
-
-async fn save_sync_state(pool: &Pool, last_db_update_id: u64) -> Result<(), Box<dyn std::error::Error>> {
-    let client = pool.get().await?;
-
-    // Use prepared statement cache for better performance
-    let stmt = client
-        .prepare_cached(
-            "INSERT INTO sync_state (id, last_db_update_id, last_updated_at)
-            VALUES (1, $1, CURRENT_TIMESTAMP)
-            ON CONFLICT (id) DO UPDATE SET
-                last_db_update_id = $1,
-                last_updated_at = CURRENT_TIMESTAMP"
-        )
-        .await?;
-
-    client.execute(&stmt, &[&(last_db_update_id as i64)]).await?;
-
-    Ok(())
-}
-
 #[tokio::main]
 async fn main() -> Result<(), Box<dyn std::error::Error>> {
     // Initialize tracing with environment filter
@@ -112,6 +67,19 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
         (LOCAL_REPLICA, false)
     };
 
+    // show the applied and pending database migrations
+    if args.contains(&"--migrate-status".to_string()) {
+        let pool = create_pool(&settings).await?;
+        migrations::migrate_status(&pool).await?;
+    }
+
+    // apply the pending database migrations
+    if args.contains(&"--migrate".to_string()) {
+        info!("Starting database migrations");
+        let pool = create_pool(&settings).await?;
+        migrations::migrate(&pool).await?;
+    }
+
     // read from flat files (./backups) and update kong_data
     if args.contains(&"--kong_data".to_string()) {
         info!("Starting kong_data update");
@@ -164,127 +132,173 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
         info!("Kong backend updates completed in {:?}", start.elapsed());
     }
 
-    // read from flat files (./backups) and update database
+    // read from flat files (./backups) and/or kong_data and update the sink
     if args.contains(&"--database".to_string()) || args.contains(&"--db_updates".to_string()) {
-        let mut tokens_map;
-        let mut pools_map;
-        let pool = create_pool(&settings).await?;
-
-        if args.contains(&"--database".to_string()) {
-            info!("Starting database update");
-            let start = std::time::Instant::now();
-
-            // Sequential execution due to dependencies
-            let db_client = pool.get().await?;
-            tokens_map = tokens::update_tokens_on_database(&db_client).await?;
-            users::update_users_on_database(&db_client).await?;
-            pools_map = pools::update_pools_on_database(&db_client, &tokens_map).await?;
-
-            // Sequential execution to prevent database deadlocks
-            // Parallel execution was causing deadlocks when multiple transactions
-            // tried to update the same metrics rows simultaneously
-            lp_tokens::update_lp_tokens_on_database(&db_client, &tokens_map).await?;
-            requests::update_requests_on_database(&db_client).await?;
-            claims::update_claims_on_database(&db_client, &tokens_map).await?;
-            transfers::update_transfers_on_database(&db_client, &tokens_map).await?;
-            txs::update_txs_on_database(&db_client, &tokens_map, &pools_map).await?;
-
-            info!("Database updates completed in {:?}", start.elapsed());
-        } else {
-            info!("Loading tokens and pools from database");
-            let db_client = pool.get().await?;
-            tokens_map = tokens::load_tokens_from_database(&db_client).await?;
-            pools_map = pools::load_pools_from_database(&db_client).await?;
+        match &settings.sink {
+            Sink::Postgres => {
+                let pool = create_pool(&settings).await?;
+                // bring the schema up to date before writing to the database
+                migrations::migrate(&pool).await?;
+                let sink = PostgresSink::new(pool).await?;
+                update_sink(&sink, &args, &settings, replica_url, is_mainnet).await?;
+            }
+            Sink::Sqlite { path } => {
+                let sink = SqliteSink::open(path)?;
+                update_sink(&sink, &args, &settings, replica_url, is_mainnet).await?;
+            }
+            Sink::Parquet { dir } => {
+                let sink = FileSink::new(dir, FileFormat::Parquet)?;
+                update_sink(&sink, &args, &settings, replica_url, is_mainnet).await?;
+            }
+            Sink::Csv { dir } => {
+                let sink = FileSink::new(dir, FileFormat::Csv)?;
+                update_sink(&sink, &args, &settings, replica_url, is_mainnet).await?;
+            }
         }
+    }
 
-        if args.contains(&"--db_updates".to_string()) {
-            info!("Starting db_updates loop with delay of {}s", settings.db_updates_delay_secs.unwrap_or(60));
-
-            // read from kong_data and update database
-            let identity = create_anonymous_identity();
-            let agent = create_agent_from_identity(replica_url, identity, is_mainnet).await?;
-            let kong_data = KongData::new(&agent).await;
-            let base_delay_secs = settings.db_updates_delay_secs.unwrap_or(10);
-            let mut retry_delay_secs = base_delay_secs;
-            const MAX_RETRY_DELAY_SECS: u64 = 300; // 5 minutes max
-            const OPERATION_TIMEOUT_SECS: u64 = 300;
-            const SYNC_STATE_SAVE_INTERVAL: u64 = 10; // Save sync state every 10 updates
-
-            // Load last sync point from database, or start from beginning
-            let mut last_db_update_id = match load_sync_state(&pool).await {
-                Ok(state) => state,
-                Err(e) => {
-                    warn!("Failed to load sync state from database: {}", e);
-                    None
-                }
-            };
+    Ok(())
+}
 
-            if let Some(id) = last_db_update_id {
-                info!("Resuming from last sync point: db_update_id={}", id);
-            } else {
-                info!("Starting fresh sync (no previous state found)");
+/// read from flat files (./backups) with --database and/or from kong_data with --db_updates and write to the sink
+async fn update_sink<T: KongSink>(
+    sink: &T,
+    args: &[String],
+    settings: &Settings,
+    replica_url: &str,
+    is_mainnet: bool,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let mut tokens_map;
+    let mut pools_map;
+    if args.contains(&"--database".to_string()) {
+        info!("Starting {} update", sink_name(settings));
+        let start = std::time::Instant::now();
+
+        // Sequential execution due to dependencies
+        tokens_map = tokens::update_tokens_on_database(sink).await?;
+        users::update_users_on_database(sink).await?;
+        pools_map = pools::update_pools_on_database(sink, &tokens_map).await?;
+
+        // Sequential execution to prevent database deadlocks
+        // Parallel execution was causing deadlocks when multiple transactions
+        // tried to update the same metrics rows simultaneously
+        lp_tokens::update_lp_tokens_on_database(sink, &tokens_map).await?;
+        requests::update_requests_on_database(sink).await?;
+        claims::update_claims_on_database(sink, &tokens_map).await?;
+        transfers::update_transfers_on_database(sink, &tokens_map).await?;
+        txs::update_txs_on_database(sink, &tokens_map, &pools_map).await?;
+        sink.flush().await?;
+
+        info!("{} updates completed in {:?}", sink_name(settings), start.elapsed());
+    } else {
+        info!("Loading tokens and pools from {}", sink_name(settings));
+        tokens_map = sink.load_tokens().await?;
+        pools_map = sink.load_pools().await?;
+    }
+
+    if args.contains(&"--db_updates".to_string()) {
+        info!("Starting db_updates loop with delay of {}s", settings.db_updates_delay_secs.unwrap_or(60));
+
+        // read from kong_data and update the sink
+        // a registered consumer needs the dfx identity to acknowledge db updates, otherwise read anonymously
+        let consumer_id = settings.db_updates_consumer_id.as_deref();
+        let agent = match consumer_id {
+            Some(_) => {
+                let dfx_pem_file = settings
+                    .dfx_pem_file
+                    .as_ref()
+                    .ok_or("dfx identity required for db_updates consumer")?;
+                let identity = create_identity_from_pem_file(dfx_pem_file)?;
+                create_agent_from_identity(replica_url, identity, is_mainnet).await?
+            }
+            None => create_agent_from_identity(replica_url, create_anonymous_identity(), is_mainnet).await?,
+        };
+        let kong_data = KongData::new(&agent).await;
+        if let Some(consumer_id) = consumer_id {
+            let consumer = kong_data.register_db_update_consumer(consumer_id).await?;
+            info!(
+                "Registered db_updates consumer {}, acknowledged db_update_id={}",
+                consumer.consumer_id, consumer.acked_db_update_id
+            );
+        }
+        let base_delay_secs = settings.db_updates_delay_secs.unwrap_or(10);
+        let mut retry_delay_secs = base_delay_secs;
+        const MAX_RETRY_DELAY_SECS: u64 = 300; // 5 minutes max
+        const OPERATION_TIMEOUT_SECS: u64 = 300;
+        const SYNC_STATE_SAVE_INTERVAL: u64 = 10; // Save sync state every 10 updates
+
+        // Load last sync point from the sink, or start from beginning
+        let last_db_update_id = match sink.load_sync_state().await {
+            Ok(state) => state,
+            Err(e) => {
+                warn!("Failed to load sync state from {}: {}", sink_name(settings), e);
+                None
             }
+        };
 
-            // Counter for batching sync_state saves
-            let mut updates_since_save = 0u64;
-
-            // Get database connection once and reuse it
-            let mut client = pool.get().await?;
-
-            // loop forever and update database
-            loop {
-                tokio::select! {
-                    _ = tokio::signal::ctrl_c() => {
-                        info!("Shutdown signal received, gracefully stopping db_updates loop");
-                        // Final sync state save before exit
-                        if let Some(id) = last_db_update_id {
-                            if let Err(e) = save_sync_state(&pool, id).await {
-                                error!("Failed to save final sync state: {}", e);
-                            } else {
-                                info!("Final sync state saved: db_update_id={}", id);
-                            }
+        if let Some(id) = last_db_update_id {
+            info!("Resuming from last sync point: db_update_id={}", id);
+        } else {
+            info!("Starting fresh sync (no previous state found)");
+        }
+
+        let mut cursor = SyncCursor::new(last_db_update_id, SYNC_STATE_SAVE_INTERVAL);
+
+        // loop forever and update the sink
+        loop {
+            tokio::select! {
+                _ = tokio::signal::ctrl_c() => {
+                    info!("Shutdown signal received, gracefully stopping db_updates loop");
+                    // Final sync state save before exit
+                    if let Some(id) = cursor.last_db_update_id {
+                        if let Err(e) = sink.save_sync_state(id).await {
+                            error!("Failed to save final sync state: {}", e);
+                        } else {
+                            info!("Final sync state saved: db_update_id={}", id);
+                            ack_db_updates(&kong_data, consumer_id, id).await;
                         }
-                        break;
                     }
-                    result = timeout(
-                        Duration::from_secs(OPERATION_TIMEOUT_SECS),
-                        get_db_updates(last_db_update_id, &kong_data, &mut client, &mut tokens_map, &mut pools_map)
-                    ) => {
-                        match result {
-                            Ok(Ok(db_update_id)) => {
-                                last_db_update_id = Some(db_update_id);
-                                retry_delay_secs = base_delay_secs; // Reset delay on success
-                                updates_since_save += 1;
-
-                                // Batch save sync state every N updates to reduce database I/O
-                                if updates_since_save >= SYNC_STATE_SAVE_INTERVAL {
-                                    if let Err(e) = save_sync_state(&pool, db_update_id).await {
-                                        warn!("Failed to save sync state: {}", e);
-                                    } else {
-                                        info!("Sync state saved: db_update_id={}", db_update_id);
-                                    }
-                                    updates_since_save = 0;
+                    break;
+                }
+                result = timeout(
+                    Duration::from_secs(OPERATION_TIMEOUT_SECS),
+                    get_db_updates(cursor.last_db_update_id, &kong_data, sink, &mut tokens_map, &mut pools_map)
+                ) => {
+                    match result {
+                        Ok(Ok((db_update_id, has_more))) => {
+                            retry_delay_secs = base_delay_secs; // Reset delay on success
+
+                            // Batch save sync state every N updates to reduce sink I/O
+                            if cursor.advance(db_update_id) {
+                                if let Err(e) = sink.save_sync_state(db_update_id).await {
+                                    warn!("Failed to save sync state: {}", e);
+                                } else {
+                                    info!("Sync state saved: db_update_id={}", db_update_id);
+                                    ack_db_updates(&kong_data, consumer_id, db_update_id).await;
                                 }
-
-                                info!("DB update successful, last_id: {}", db_update_id);
                             }
-                            Ok(Err(err)) => {
-                                error!("DB update failed: {}", err);
-                                retry_delay_secs = (retry_delay_secs * 2).min(MAX_RETRY_DELAY_SECS);
-                                warn!("Retrying in {}s (exponential backoff)", retry_delay_secs);
-                                // Pool's RecyclingMethod::Fast handles connection health automatically
-                            }
-                            Err(_) => {
-                                error!("DB update timed out after {}s", OPERATION_TIMEOUT_SECS);
-                                retry_delay_secs = (retry_delay_secs * 2).min(MAX_RETRY_DELAY_SECS);
-                                warn!("Retrying in {}s (exponential backoff)", retry_delay_secs);
-                                // Pool's RecyclingMethod::Fast handles connection health automatically
+
+                            info!("DB update successful, last_id: {}", db_update_id);
+
+                            // catch up without waiting if kong_data has more db updates
+                            if has_more {
+                                continue;
                             }
                         }
-
-                        // Async sleep instead of blocking thread::sleep
-                        tokio::time::sleep(Duration::from_secs(retry_delay_secs)).await;
+                        Ok(Err(err)) => {
+                            error!("DB update failed: {}", err);
+                            retry_delay_secs = (retry_delay_secs * 2).min(MAX_RETRY_DELAY_SECS);
+                            warn!("Retrying in {}s (exponential backoff)", retry_delay_secs);
+                        }
+                        Err(_) => {
+                            error!("DB update timed out after {}s", OPERATION_TIMEOUT_SECS);
+                            retry_delay_secs = (retry_delay_secs * 2).min(MAX_RETRY_DELAY_SECS);
+                            warn!("Retrying in {}s (exponential backoff)", retry_delay_secs);
+                        }
                     }
+
+                    // Async sleep instead of blocking thread::sleep
+                    tokio::time::sleep(Duration::from_secs(retry_delay_secs)).await;
                 }
             }
         }
@@ -293,16 +307,38 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
     Ok(())
 }
 
+fn sink_name(settings: &Settings) -> &'static str {
+    match settings.sink {
+        Sink::Postgres => "database",
+        Sink::Sqlite { .. } => "SQLite",
+        Sink::Parquet { .. } => "Parquet files",
+        Sink::Csv { .. } => "CSV files",
+    }
+}
+
+/// acknowledge db updates to kong_data only once they are saved in the sync state, so they are never removed before
+/// kong_admin would resume past them
+async fn ack_db_updates(kong_data: &KongData, consumer_id: Option<&str>, db_update_id: u64) {
+    let Some(consumer_id) = consumer_id else {
+        return;
+    };
+    match kong_data.ack_db_updates(consumer_id, db_update_id).await {
+        Ok(consumer) => info!("Acknowledged db_update_id={}", consumer.acked_db_update_id),
+        Err(e) => warn!("Failed to acknowledge db updates: {}", e),
+    }
+}
+
 async fn create_pool(settings: &Settings) -> Result<Pool, Box<dyn std::error::Error>> {
-    let db_host = &settings.database.host;
-    let db_port = &settings.database.port;
-    let db_user = &settings.database.user;
-    let db_password = &settings.database.password;
-    let db_name = &settings.database.db_name;
+    let database = settings.database.as_ref().ok_or("database settings required for Postgres")?;
+    let db_host = &database.host;
+    let db_port = &database.port;
+    let db_user = &database.user;
+    let db_password = &database.password;
+    let db_name = &database.db_name;
 
     // Configure TLS
     let mut builder = SslConnector::builder(SslMethod::tls()).map_err(|e| format!("SSL error: {}", e))?;
-    if let Some(ca_cert) = &settings.database.ca_cert {
+    if let Some(ca_cert) = &database.ca_cert {
         builder
             .set_ca_file(ca_cert)
             .map_err(|e| format!("CA file error: {}", e))?;
@@ -324,14 +360,14 @@ async fn create_pool(settings: &Settings) -> Result<Pool, Box<dyn std::error::Er
     let mgr = Manager::from_config(pg_config, tls, mgr_config);
 
     let pool = Pool::builder(mgr)
-        .max_size(settings.database.max_connections)
-        .wait_timeout(Some(Duration::from_secs(settings.database.connection_timeout_secs)))
-        .create_timeout(Some(Duration::from_secs(settings.database.connection_timeout_secs)))
-        .recycle_timeout(Some(Duration::from_secs(settings.database.connection_timeout_secs)))
+        .max_size(database.max_connections)
+        .wait_timeout(Some(Duration::from_secs(database.connection_timeout_secs)))
+        .create_timeout(Some(Duration::from_secs(database.connection_timeout_secs)))
+        .recycle_timeout(Some(Duration::from_secs(database.connection_timeout_secs)))
         .runtime(Runtime::Tokio1)
         .build()?;
 
-    info!("Database pool created with max_connections: {}", settings.database.max_connections);
+    info!("Database pool created with max_connections: {}", database.max_connections);
 
     Ok(pool)
 }
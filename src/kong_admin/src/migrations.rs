@@ -0,0 +1,141 @@
+use deadpool_postgres::Pool;
+use tracing::info;
+
+/// an embedded schema migration. migrations are applied in order of version, each in its own transaction, and recorded
+/// in the schema_version table. once released, a migration must never change - add a new one instead
+struct Migration {
+    version: i32,
+    name: &'static str,
+    sql: &'static str,
+}
+
+const MIGRATIONS: &[Migration] = &[
+    Migration {
+        version: 1,
+        name: "baseline",
+        sql: include_str!("../sql/migrations/0001_baseline.sql"),
+    },
+    Migration {
+        version: 2,
+        name: "fee_sweep_tx",
+        sql: include_str!("../sql/migrations/0002_fee_sweep_tx.sql"),
+    },
+    Migration {
+        version: 3,
+        name: "sync_state",
+        sql: include_str!("../sql/migrations/0003_sync_state.sql"),
+    },
+];
+
+// key of the advisory lock so only one kong_admin migrates the database at a time
+const MIGRATION_LOCK_ID: i64 = 0x6b6f6e67;
+
+async fn create_schema_version_table(pool: &Pool) -> Result<(), Box<dyn std::error::Error>> {
+    let client = pool.get().await?;
+    client
+        .execute(
+            "CREATE TABLE IF NOT EXISTS schema_version (
+                version INTEGER PRIMARY KEY,
+                name TEXT NOT NULL,
+                applied_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP
+            )",
+            &[],
+        )
+        .await?;
+    Ok(())
+}
+
+async fn query_schema_version(pool: &Pool) -> Result<i32, Box<dyn std::error::Error>> {
+    let client = pool.get().await?;
+    let row = client.query_one("SELECT COALESCE(MAX(version), 0) FROM schema_version", &[]).await?;
+    Ok(row.get(0))
+}
+
+/// apply the migrations not yet recorded in schema_version. returns the schema version
+pub async fn migrate(pool: &Pool) -> Result<i32, Box<dyn std::error::Error>> {
+    create_schema_version_table(pool).await?;
+
+    let mut client = pool.get().await?;
+    for migration in MIGRATIONS {
+        let tx = client.transaction().await?;
+        // re-check the version under the lock in case another kong_admin applied the migration first
+        tx.execute("SELECT pg_advisory_xact_lock($1)", &[&MIGRATION_LOCK_ID]).await?;
+        let applied = tx
+            .query_opt("SELECT 1 FROM schema_version WHERE version = $1", &[&migration.version])
+            .await?
+            .is_some();
+        if applied {
+            continue;
+        }
+
+        info!("Applying migration {:04}_{}", migration.version, migration.name);
+        tx.batch_execute(migration.sql)
+            .await
+            .map_err(|e| format!("Migration {:04}_{} failed: {}", migration.version, migration.name, e))?;
+        tx.execute(
+            "INSERT INTO schema_version (version, name) VALUES ($1, $2)",
+            &[&migration.version, &migration.name],
+        )
+        .await?;
+        tx.commit().await?;
+    }
+
+    let version = query_schema_version(pool).await?;
+    info!("Database schema is at version {}", version);
+    Ok(version)
+}
+
+/// print the applied and pending migrations
+pub async fn migrate_status(pool: &Pool) -> Result<(), Box<dyn std::error::Error>> {
+    create_schema_version_table(pool).await?;
+
+    let client = pool.get().await?;
+    let rows = client
+        .query(
+            "SELECT version, name, to_char(applied_at, 'YYYY-MM-DD HH24:MI:SS') FROM schema_version ORDER BY version",
+            &[],
+        )
+        .await?;
+    let applied = rows
+        .iter()
+        .map(|row| (row.get::<_, i32>(0), (row.get::<_, String>(1), row.get::<_, String>(2))))
+        .collect::<std::collections::BTreeMap<i32, (String, String)>>();
+
+    for migration in MIGRATIONS {
+        match applied.get(&migration.version) {
+            Some((_, applied_at)) => println!("{:04}_{} - applied {}", migration.version, migration.name, applied_at),
+            None => println!("{:04}_{} - pending", migration.version, migration.name),
+        }
+    }
+    // versions recorded by a newer kong_admin
+    for (version, (name, applied_at)) in applied.iter() {
+        if !MIGRATIONS.iter().any(|migration| migration.version == *version) {
+            println!("{:04}_{} - applied {} (unknown to this kong_admin)", version, name, applied_at);
+        }
+    }
+    println!("schema version: {}", applied.keys().max().copied().unwrap_or(0));
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_migrations() {
+        // versions start at 1 and have no gaps, so a database is always at the version of its last migration
+        for (i, migration) in MIGRATIONS.iter().enumerate() {
+            assert_eq!(migration.version, i as i32 + 1);
+            // each file has a header with its version and name, which catches a migration pointing at the wrong file
+            let header = format!("-- {:04} {}", migration.version, migration.name);
+            assert!(
+                migration.sql.lines().any(|line| line == header),
+                "{:04}_{} has no header {}",
+                migration.version,
+                migration.name,
+                header
+            );
+        }
+    }
+}
@@ -11,6 +11,7 @@ use tokio_postgres::Client;
 
 use super::kong_update::KongUpdate;
 use super::math_helpers::round_f64;
+use super::sink::KongSink;
 
 pub fn serialize_pool(pool: &StablePool) -> serde_json::Value {
     json!({
@@ -32,8 +33,8 @@ pub fn serialize_pool(pool: &StablePool) -> serde_json::Value {
     })
 }
 
-pub async fn update_pools_on_database(
-    db_client: &Client,
+pub async fn update_pools_on_database<T: KongSink>(
+    sink: &T,
     tokens_map: &BTreeMap<u32, u8>,
 ) -> Result<BTreeMap<u32, (u32, u32)>, Box<dyn std::error::Error>> {
     let dir_path = "./backups";
@@ -64,11 +65,11 @@ pub async fn update_pools_on_database(
         let pools_map: BTreeMap<StablePoolId, StablePool> = serde_json::from_reader(reader)?;
 
         for v in pools_map.values() {
-            insert_pool_on_database(v, db_client, tokens_map).await?;
+            sink.insert_pool(v, tokens_map).await?;
         }
     }
 
-    load_pools_from_database(db_client).await
+    sink.load_pools().await
 }
 
 pub async fn insert_pool_on_database(
@@ -140,23 +141,6 @@ pub async fn insert_pool_on_database(
     Ok(())
 }
 
-pub async fn query_pool_token_ids(
-    db_client: &Client,
-    pool_id: u32,
-) -> Result<(u32, u32), Box<dyn std::error::Error>> {
-    let stmt = db_client
-        .prepare("SELECT token_id_0, token_id_1 FROM pools WHERE pool_id = $1")
-        .await?;
-
-    let row = db_client
-        .query_one(&stmt, &[&(pool_id as i32)])
-        .await?;
-
-    let token_id_0: i32 = row.get(0);
-    let token_id_1: i32 = row.get(1);
-    Ok((token_id_0 as u32, token_id_1 as u32))
-}
-
 pub async fn load_pools_from_database(db_client: &Client) -> Result<BTreeMap<u32, (u32, u32)>, Box<dyn std::error::Error>> {
     let mut pools_map = BTreeMap::new();
     let rows = db_client.query("SELECT pool_id, token_id_0, token_id_1 FROM pools", &[]).await?;
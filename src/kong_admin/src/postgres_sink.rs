@@ -0,0 +1,135 @@
+use deadpool_postgres::{Object, Pool};
+use kong_lib::stable_claim::stable_claim::StableClaim;
+use kong_lib::stable_lp_token::stable_lp_token::StableLPToken;
+use kong_lib::stable_pool::stable_pool::StablePool;
+use kong_lib::stable_request::stable_request::StableRequest;
+use kong_lib::stable_token::stable_token::StableToken;
+use kong_lib::stable_transfer::stable_transfer::StableTransfer;
+use kong_lib::stable_tx::stable_tx::StableTx;
+use kong_lib::stable_user::stable_user::StableUser;
+use std::collections::BTreeMap;
+use tracing::info;
+
+use crate::claims::insert_claim_on_database;
+use crate::lp_tokens::insert_lp_token_on_database;
+use crate::pools::{insert_pool_on_database, load_pools_from_database};
+use crate::requests::insert_request_on_database;
+use crate::sink::KongSink;
+use crate::tokens::{insert_token_on_database, load_tokens_from_database};
+use crate::transfers::insert_transfer_on_database;
+use crate::txs::insert_tx_on_database;
+use crate::users::insert_user_on_database;
+
+/// Postgres database with the schema of the migrations. amounts are stored in token units
+pub struct PostgresSink {
+    pool: Pool,
+    client: Object, // connection reused for all the inserts
+}
+
+impl PostgresSink {
+    pub async fn new(pool: Pool) -> Result<Self, Box<dyn std::error::Error>> {
+        let client = pool.get().await?;
+        Ok(PostgresSink { pool, client })
+    }
+}
+
+impl KongSink for PostgresSink {
+    async fn insert_user(&self, user: &StableUser) -> Result<(), Box<dyn std::error::Error>> {
+        insert_user_on_database(user, &self.client).await
+    }
+
+    async fn insert_token(&self, token: &StableToken) -> Result<(), Box<dyn std::error::Error>> {
+        insert_token_on_database(token, &self.client).await
+    }
+
+    async fn insert_pool(&self, pool: &StablePool, tokens_map: &BTreeMap<u32, u8>) -> Result<(), Box<dyn std::error::Error>> {
+        insert_pool_on_database(pool, &self.client, tokens_map).await
+    }
+
+    async fn insert_lp_token(&self, lp_token: &StableLPToken, tokens_map: &BTreeMap<u32, u8>) -> Result<(), Box<dyn std::error::Error>> {
+        insert_lp_token_on_database(lp_token, &self.client, tokens_map).await
+    }
+
+    async fn insert_request(&self, request: &StableRequest) -> Result<(), Box<dyn std::error::Error>> {
+        insert_request_on_database(request, &self.client).await
+    }
+
+    async fn insert_claim(&self, claim: &StableClaim, tokens_map: &BTreeMap<u32, u8>) -> Result<(), Box<dyn std::error::Error>> {
+        insert_claim_on_database(claim, &self.client, tokens_map).await
+    }
+
+    async fn insert_transfer(&self, transfer: &StableTransfer, tokens_map: &BTreeMap<u32, u8>) -> Result<(), Box<dyn std::error::Error>> {
+        insert_transfer_on_database(transfer, &self.client, tokens_map).await
+    }
+
+    async fn insert_tx(
+        &self,
+        tx: &StableTx,
+        tokens_map: &BTreeMap<u32, u8>,
+        pools_map: &BTreeMap<u32, (u32, u32)>,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        insert_tx_on_database(tx, &self.client, tokens_map, pools_map).await
+    }
+
+    async fn load_tokens(&self) -> Result<BTreeMap<u32, u8>, Box<dyn std::error::Error>> {
+        load_tokens_from_database(&self.client).await
+    }
+
+    async fn load_pools(&self) -> Result<BTreeMap<u32, (u32, u32)>, Box<dyn std::error::Error>> {
+        load_pools_from_database(&self.client).await
+    }
+
+    async fn load_sync_state(&self) -> Result<Option<u64>, Box<dyn std::error::Error>> {
+        load_sync_state(&self.pool).await
+    }
+
+    async fn save_sync_state(&self, last_db_update_id: u64) -> Result<(), Box<dyn std::error::Error>> {
+        save_sync_state(&self.pool, last_db_update_id).await
+    }
+
+    // every insert is committed on its own
+    async fn flush(&self) -> Result<(), Box<dyn std::error::Error>> {
+        Ok(())
+    }
+}
+
+async fn load_sync_state(pool: &Pool) -> Result<Option<u64>, Box<dyn std::error::Error>> {
+    let client = pool.get().await?;
+
+    // Load current sync state using prepared statement cache
+    let stmt = client
+        .prepare_cached("SELECT last_db_update_id FROM sync_state WHERE id = 1")
+        .await?;
+    let row = client.query_opt(&stmt, &[]).await?;
+
+    match row {
+        Some(row) => {
+            let last_id: i64 = row.get(0);
+            info!("Loaded sync state from database: last_db_update_id={}", last_id);
+            Ok(Some(last_id as u64))
+        }
+        None => {
+            info!("No previous sync state found in database, starting from beginning");
+            Ok(None)
+        }
+    }
+}
+
+async fn save_sync_state(pool: &Pool, last_db_update_id: u64) -> Result<(), Box<dyn std::error::Error>> {
+    let client = pool.get().await?;
+
+    // Use prepared statement cache for better performance
+    let stmt = client
+        .prepare_cached(
+            "INSERT INTO sync_state (id, last_db_update_id, last_updated_at)
+            VALUES (1, $1, CURRENT_TIMESTAMP)
+            ON CONFLICT (id) DO UPDATE SET
+                last_db_update_id = $1,
+                last_updated_at = CURRENT_TIMESTAMP",
+        )
+        .await?;
+
+    client.execute(&stmt, &[&(last_db_update_id as i64)]).await?;
+
+    Ok(())
+}
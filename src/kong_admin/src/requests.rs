@@ -14,6 +14,7 @@ use tokio_postgres::Client;
 
 use super::kong_update::KongUpdate;
 use super::nat_helpers::nat_option_to_string;
+use super::sink::KongSink;
 use super::transfers::serialize_option_tx_id;
 
 #[derive(Debug, ToSql, FromSql)]
@@ -280,7 +281,7 @@ pub fn serialize_reply(reply: &Reply) -> serde_json::Value {
     }
 }
 
-pub async fn update_requests_on_database(db_client: &Client) -> Result<(), Box<dyn std::error::Error>> {
+pub async fn update_requests_on_database<T: KongSink>(sink: &T) -> Result<(), Box<dyn std::error::Error>> {
     let dir_path = "./backups";
     let re_pattern = Regex::new(r"^requests.*.json$").unwrap();
     let mut files = fs::read_dir(dir_path)?
@@ -309,7 +310,7 @@ pub async fn update_requests_on_database(db_client: &Client) -> Result<(), Box<d
         let request_map: BTreeMap<StableRequestId, StableRequest> = serde_json::from_reader(reader)?;
 
         for v in request_map.values() {
-            insert_request_on_database(v, db_client).await?;
+            sink.insert_request(v).await?;
         }
     }
 
@@ -23,11 +23,31 @@ fn default_connection_timeout_secs() -> u64 {
     5
 }
 
+/// where --database and --db_updates write the records to
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+#[serde(tag = "type", rename_all = "lowercase")]
+pub enum Sink {
+    #[default]
+    Postgres, // uses database
+    Sqlite {
+        path: String,
+    },
+    Parquet {
+        dir: String,
+    },
+    Csv {
+        dir: String,
+    },
+}
+
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct Settings {
     pub dfx_pem_file: Option<String>,
     pub db_updates_delay_secs: Option<u64>,
-    pub database: Database,
+    pub db_updates_consumer_id: Option<String>, // registered with kong_data to acknowledge processed db updates
+    pub database: Option<Database>,             // required for the postgres sink
+    #[serde(default)]
+    pub sink: Sink,
 }
 
 pub fn read_settings() -> Result<Settings, Box<dyn std::error::Error>> {
@@ -0,0 +1,793 @@
+use candid::Nat;
+use kong_lib::stable_claim::stable_claim::{ClaimStatus, StableClaim};
+use kong_lib::stable_lp_token::stable_lp_token::StableLPToken;
+use kong_lib::stable_pool::stable_pool::StablePool;
+use kong_lib::stable_request::request::Request;
+use kong_lib::stable_request::stable_request::StableRequest;
+use kong_lib::stable_token::stable_token::StableToken;
+use kong_lib::stable_transfer::stable_transfer::StableTransfer;
+use kong_lib::stable_transfer::tx_id::TxId;
+use kong_lib::stable_tx::stable_tx::StableTx;
+use kong_lib::stable_tx::status_tx::StatusTx;
+use kong_lib::stable_user::stable_user::StableUser;
+use num_traits::ToPrimitive;
+use serde_json::json;
+use std::collections::BTreeMap;
+
+use crate::claims::serialize_claim;
+use crate::lp_tokens::serialize_lp_tokens;
+use crate::math_helpers::round_f64;
+use crate::pools::serialize_pool;
+use crate::requests::{serialize_reply, serialize_request};
+use crate::tokens::serialize_token;
+use crate::transfers::serialize_transfer;
+use crate::txs::serialize_tx;
+
+/// destination of the records read from ./backups (--database) and from the db updates of kong_data (--db_updates)
+/// - tokens_map is token_id -> decimals and pools_map is pool_id -> (token_id_0, token_id_1), for sinks that store
+///   amounts in token units
+/// - inserting a record that is already in the sink replaces it
+pub trait KongSink {
+    async fn insert_user(&self, user: &StableUser) -> Result<(), Box<dyn std::error::Error>>;
+    async fn insert_token(&self, token: &StableToken) -> Result<(), Box<dyn std::error::Error>>;
+    async fn insert_pool(&self, pool: &StablePool, tokens_map: &BTreeMap<u32, u8>) -> Result<(), Box<dyn std::error::Error>>;
+    async fn insert_lp_token(&self, lp_token: &StableLPToken, tokens_map: &BTreeMap<u32, u8>) -> Result<(), Box<dyn std::error::Error>>;
+    async fn insert_request(&self, request: &StableRequest) -> Result<(), Box<dyn std::error::Error>>;
+    async fn insert_claim(&self, claim: &StableClaim, tokens_map: &BTreeMap<u32, u8>) -> Result<(), Box<dyn std::error::Error>>;
+    async fn insert_transfer(&self, transfer: &StableTransfer, tokens_map: &BTreeMap<u32, u8>) -> Result<(), Box<dyn std::error::Error>>;
+    async fn insert_tx(
+        &self,
+        tx: &StableTx,
+        tokens_map: &BTreeMap<u32, u8>,
+        pools_map: &BTreeMap<u32, (u32, u32)>,
+    ) -> Result<(), Box<dyn std::error::Error>>;
+    /// tokens_map of the tokens already in the sink
+    async fn load_tokens(&self) -> Result<BTreeMap<u32, u8>, Box<dyn std::error::Error>>;
+    /// pools_map of the pools already in the sink
+    async fn load_pools(&self) -> Result<BTreeMap<u32, (u32, u32)>, Box<dyn std::error::Error>>;
+    /// last db_update_id written to the sink
+    async fn load_sync_state(&self) -> Result<Option<u64>, Box<dyn std::error::Error>>;
+    /// also makes the records inserted so far durable, so the sync state never gets ahead of the records
+    async fn save_sync_state(&self, last_db_update_id: u64) -> Result<(), Box<dyn std::error::Error>>;
+    /// make the records inserted so far durable
+    async fn flush(&self) -> Result<(), Box<dyn std::error::Error>>;
+}
+
+/// type of a column of the sink tables
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ColumnType {
+    Int,
+    Float,
+    Text,
+    Bool,
+    Timestamp, // nanoseconds, as SinkValue::Int
+}
+
+/// value of a column of a SinkRecord
+#[derive(Debug, Clone, PartialEq)]
+pub enum SinkValue {
+    Null,
+    Int(i64),
+    Float(f64),
+    Text(String),
+    Bool(bool),
+}
+
+/// table of the sinks other than Postgres. the first column is the primary key
+#[derive(Debug)]
+pub struct SinkTable {
+    pub name: &'static str,
+    pub columns: &'static [(&'static str, ColumnType)],
+}
+
+// the columns follow the tables of the Postgres schema, with amounts in token units and arrays and JSON as text. tables
+// with token ids also have the symbols of the tokens, and txs has the columns of the tx tables flattened into one
+pub const USERS: SinkTable = SinkTable {
+    name: "users",
+    columns: &[
+        ("user_id", ColumnType::Int),
+        ("principal_id", ColumnType::Text),
+        ("my_referral_code", ColumnType::Text),
+        ("referred_by", ColumnType::Int),
+        ("referred_by_expires_at", ColumnType::Timestamp),
+        ("fee_level", ColumnType::Int),
+        ("fee_level_expires_at", ColumnType::Timestamp),
+        ("raw_json", ColumnType::Text),
+    ],
+};
+
+pub const TOKENS: SinkTable = SinkTable {
+    name: "tokens",
+    columns: &[
+        ("token_id", ColumnType::Int),
+        ("token_type", ColumnType::Text),
+        ("name", ColumnType::Text),
+        ("symbol", ColumnType::Text),
+        ("canister_id", ColumnType::Text),
+        ("address", ColumnType::Text),
+        ("decimals", ColumnType::Int),
+        ("fee", ColumnType::Float),
+        ("icrc1", ColumnType::Bool),
+        ("icrc2", ColumnType::Bool),
+        ("icrc3", ColumnType::Bool),
+        ("is_removed", ColumnType::Bool),
+        ("raw_json", ColumnType::Text),
+    ],
+};
+
+pub const POOLS: SinkTable = SinkTable {
+    name: "pools",
+    columns: &[
+        ("pool_id", ColumnType::Int),
+        ("token_id_0", ColumnType::Int),
+        ("symbol_0", ColumnType::Text),
+        ("balance_0", ColumnType::Float),
+        ("lp_fee_0", ColumnType::Float),
+        ("kong_fee_0", ColumnType::Float),
+        ("token_id_1", ColumnType::Int),
+        ("symbol_1", ColumnType::Text),
+        ("balance_1", ColumnType::Float),
+        ("lp_fee_1", ColumnType::Float),
+        ("kong_fee_1", ColumnType::Float),
+        ("lp_fee_bps", ColumnType::Int),
+        ("kong_fee_bps", ColumnType::Int),
+        ("lp_token_id", ColumnType::Int),
+        ("is_removed", ColumnType::Bool),
+        ("raw_json", ColumnType::Text),
+    ],
+};
+
+pub const LP_TOKENS: SinkTable = SinkTable {
+    name: "lp_tokens",
+    columns: &[
+        ("lp_token_id", ColumnType::Int),
+        ("user_id", ColumnType::Int),
+        ("token_id", ColumnType::Int),
+        ("symbol", ColumnType::Text),
+        ("amount", ColumnType::Float),
+        ("ts", ColumnType::Timestamp),
+        ("raw_json", ColumnType::Text),
+    ],
+};
+
+pub const REQUESTS: SinkTable = SinkTable {
+    name: "requests",
+    columns: &[
+        ("request_id", ColumnType::Int),
+        ("user_id", ColumnType::Int),
+        ("request_type", ColumnType::Text),
+        ("request", ColumnType::Text),
+        ("reply", ColumnType::Text),
+        ("statuses", ColumnType::Text),
+        ("ts", ColumnType::Timestamp),
+    ],
+};
+
+pub const CLAIMS: SinkTable = SinkTable {
+    name: "claims",
+    columns: &[
+        ("claim_id", ColumnType::Int),
+        ("user_id", ColumnType::Int),
+        ("token_id", ColumnType::Int),
+        ("symbol", ColumnType::Text),
+        ("status", ColumnType::Text),
+        ("amount", ColumnType::Float),
+        ("request_id", ColumnType::Int),
+        ("to_address", ColumnType::Text),
+        ("desc", ColumnType::Text),
+        ("attempt_request_id", ColumnType::Text),
+        ("transfer_ids", ColumnType::Text),
+        ("ts", ColumnType::Timestamp),
+        ("raw_json", ColumnType::Text),
+    ],
+};
+
+pub const TRANSFERS: SinkTable = SinkTable {
+    name: "transfers",
+    columns: &[
+        ("transfer_id", ColumnType::Int),
+        ("request_id", ColumnType::Int),
+        ("token_id", ColumnType::Int),
+        ("symbol", ColumnType::Text),
+        ("is_send", ColumnType::Bool),
+        ("amount", ColumnType::Float),
+        ("block_index", ColumnType::Float),
+        ("tx_hash", ColumnType::Text),
+        ("ts", ColumnType::Timestamp),
+        ("raw_json", ColumnType::Text),
+    ],
+};
+
+/// token 0 and 1 are the tokens of the pool for add_pool, add_liquidity and remove_liquidity, the pay and receive
+/// tokens for swap, and token 0 is the token for send and fee_sweep
+pub const TXS: SinkTable = SinkTable {
+    name: "txs",
+    columns: &[
+        ("tx_id", ColumnType::Int),
+        ("request_id", ColumnType::Int),
+        ("user_id", ColumnType::Int),
+        ("tx_type", ColumnType::Text),
+        ("status", ColumnType::Text),
+        ("pool_id", ColumnType::Int),
+        ("token_id_0", ColumnType::Int),
+        ("symbol_0", ColumnType::Text),
+        ("amount_0", ColumnType::Float),
+        ("lp_fee_0", ColumnType::Float),
+        ("token_id_1", ColumnType::Int),
+        ("symbol_1", ColumnType::Text),
+        ("amount_1", ColumnType::Float),
+        ("lp_fee_1", ColumnType::Float),
+        ("lp_token_amount", ColumnType::Float),
+        ("price", ColumnType::Float),
+        ("mid_price", ColumnType::Float),
+        ("slippage", ColumnType::Float),
+        ("to_user_id", ColumnType::Int),
+        ("transfer_ids", ColumnType::Text),
+        ("claim_ids", ColumnType::Text),
+        ("ts", ColumnType::Timestamp),
+        ("raw_json", ColumnType::Text),
+    ],
+};
+
+/// tables of the sinks other than Postgres
+pub const SINK_TABLES: [&SinkTable; 8] = [&USERS, &TOKENS, &POOLS, &LP_TOKENS, &REQUESTS, &CLAIMS, &TRANSFERS, &TXS];
+
+/// a row of a SinkTable. values are in the order of the columns of the table
+#[derive(Debug, Clone)]
+pub struct SinkRecord {
+    pub table: &'static SinkTable,
+    pub ts: Option<u64>, // nanoseconds. None for records that are state rather than events (users, tokens and pools)
+    pub values: Vec<SinkValue>,
+}
+
+impl From<u64> for SinkValue {
+    fn from(v: u64) -> Self {
+        SinkValue::Int(v as i64)
+    }
+}
+
+impl From<u32> for SinkValue {
+    fn from(v: u32) -> Self {
+        SinkValue::Int(v as i64)
+    }
+}
+
+impl From<u8> for SinkValue {
+    fn from(v: u8) -> Self {
+        SinkValue::Int(v as i64)
+    }
+}
+
+impl From<f64> for SinkValue {
+    fn from(v: f64) -> Self {
+        SinkValue::Float(v)
+    }
+}
+
+impl From<bool> for SinkValue {
+    fn from(v: bool) -> Self {
+        SinkValue::Bool(v)
+    }
+}
+
+impl From<String> for SinkValue {
+    fn from(v: String) -> Self {
+        SinkValue::Text(v)
+    }
+}
+
+impl From<&str> for SinkValue {
+    fn from(v: &str) -> Self {
+        SinkValue::Text(v.to_string())
+    }
+}
+
+impl From<serde_json::Value> for SinkValue {
+    fn from(v: serde_json::Value) -> Self {
+        SinkValue::Text(v.to_string())
+    }
+}
+
+impl<T: Into<SinkValue>> From<Option<T>> for SinkValue {
+    fn from(v: Option<T>) -> Self {
+        v.map_or(SinkValue::Null, Into::into)
+    }
+}
+
+/// amount in token units, rounded to the decimals of the token as in Postgres
+fn to_token_units(amount: &Nat, token_id: u32, tokens_map: &BTreeMap<u32, u8>) -> Result<SinkValue, Box<dyn std::error::Error>> {
+    let decimals = tokens_map.get(&token_id).ok_or(format!("token_id={} not found", token_id))?;
+    Ok(to_units(amount, *decimals))
+}
+
+fn to_units(amount: &Nat, decimals: u8) -> SinkValue {
+    SinkValue::Float(round_f64(
+        amount.0.to_f64().unwrap_or_default() / 10_u64.pow(decimals as u32) as f64,
+        decimals,
+    ))
+}
+
+fn to_symbol(token_id: u32, symbols_map: &BTreeMap<u32, String>) -> SinkValue {
+    symbols_map.get(&token_id).map(String::as_str).into()
+}
+
+fn to_ids<T: serde::Serialize>(ids: &[T]) -> SinkValue {
+    json!(ids).into()
+}
+
+fn to_status(status: &StatusTx) -> SinkValue {
+    match status {
+        StatusTx::Success => "Success",
+        StatusTx::Failed => "Failed",
+    }
+    .into()
+}
+
+pub fn to_user_record(user: &StableUser) -> SinkRecord {
+    SinkRecord {
+        table: &USERS,
+        ts: None,
+        values: vec![
+            user.user_id.into(),
+            user.principal_id.as_str().into(),
+            user.my_referral_code.as_str().into(),
+            user.referred_by.into(),
+            user.referred_by_expires_at.into(),
+            user.fee_level.into(),
+            user.fee_level_expires_at.into(),
+            json!({ "StableUser": user }).into(),
+        ],
+    }
+}
+
+pub fn to_token_record(token: &StableToken) -> SinkRecord {
+    let values = match token {
+        StableToken::IC(ic_token) => vec![
+            ic_token.token_id.into(),
+            "IC".into(),
+            ic_token.name.as_str().into(),
+            ic_token.symbol.as_str().into(),
+            ic_token.canister_id.to_string().into(),
+            SinkValue::Null,
+            ic_token.decimals.into(),
+            to_units(&ic_token.fee, ic_token.decimals),
+            ic_token.icrc1.into(),
+            ic_token.icrc2.into(),
+            ic_token.icrc3.into(),
+            ic_token.is_removed.into(),
+            serialize_token(token).into(),
+        ],
+        StableToken::LP(lp_token) => vec![
+            lp_token.token_id.into(),
+            "LP".into(),
+            SinkValue::Null,
+            lp_token.symbol.as_str().into(),
+            SinkValue::Null,
+            lp_token.address.as_str().into(),
+            lp_token.decimals.into(),
+            SinkValue::Null,
+            SinkValue::Null,
+            SinkValue::Null,
+            SinkValue::Null,
+            lp_token.is_removed.into(),
+            serialize_token(token).into(),
+        ],
+    };
+    SinkRecord {
+        table: &TOKENS,
+        ts: None,
+        values,
+    }
+}
+
+pub fn to_pool_record(
+    pool: &StablePool,
+    tokens_map: &BTreeMap<u32, u8>,
+    symbols_map: &BTreeMap<u32, String>,
+) -> Result<SinkRecord, Box<dyn std::error::Error>> {
+    Ok(SinkRecord {
+        table: &POOLS,
+        ts: None,
+        values: vec![
+            pool.pool_id.into(),
+            pool.token_id_0.into(),
+            to_symbol(pool.token_id_0, symbols_map),
+            to_token_units(&pool.balance_0, pool.token_id_0, tokens_map)?,
+            to_token_units(&pool.lp_fee_0, pool.token_id_0, tokens_map)?,
+            to_token_units(&pool.kong_fee_0, pool.token_id_0, tokens_map)?,
+            pool.token_id_1.into(),
+            to_symbol(pool.token_id_1, symbols_map),
+            to_token_units(&pool.balance_1, pool.token_id_1, tokens_map)?,
+            to_token_units(&pool.lp_fee_1, pool.token_id_1, tokens_map)?,
+            to_token_units(&pool.kong_fee_1, pool.token_id_1, tokens_map)?,
+            pool.lp_fee_bps.into(),
+            pool.kong_fee_bps.into(),
+            pool.lp_token_id.into(),
+            pool.is_removed.into(),
+            serialize_pool(pool).into(),
+        ],
+    })
+}
+
+pub fn to_lp_token_record(
+    lp_token: &StableLPToken,
+    tokens_map: &BTreeMap<u32, u8>,
+    symbols_map: &BTreeMap<u32, String>,
+) -> Result<SinkRecord, Box<dyn std::error::Error>> {
+    Ok(SinkRecord {
+        table: &LP_TOKENS,
+        ts: Some(lp_token.ts),
+        values: vec![
+            lp_token.lp_token_id.into(),
+            lp_token.user_id.into(),
+            lp_token.token_id.into(),
+            to_symbol(lp_token.token_id, symbols_map),
+            to_token_units(&lp_token.amount, lp_token.token_id, tokens_map)?,
+            lp_token.ts.into(),
+            serialize_lp_tokens(lp_token).into(),
+        ],
+    })
+}
+
+pub fn to_request_record(request: &StableRequest) -> SinkRecord {
+    let request_type = match request.request {
+        Request::AddPool(_) => "add_pool",
+        Request::AddLiquidity(_) => "add_liquidity",
+        Request::RemoveLiquidity(_) => "remove_liquidity",
+        Request::Swap(_) => "swap",
+        Request::Claim(_) => "claim",
+        Request::Send(_) => "send",
+    };
+    SinkRecord {
+        table: &REQUESTS,
+        ts: Some(request.ts),
+        values: vec![
+            request.request_id.into(),
+            request.user_id.into(),
+            request_type.into(),
+            serialize_request(&request.request).into(),
+            serialize_reply(&request.reply).into(),
+            json!(&request.statuses).into(),
+            request.ts.into(),
+        ],
+    }
+}
+
+pub fn to_claim_record(
+    claim: &StableClaim,
+    tokens_map: &BTreeMap<u32, u8>,
+    symbols_map: &BTreeMap<u32, String>,
+) -> Result<SinkRecord, Box<dyn std::error::Error>> {
+    let status = match claim.status {
+        ClaimStatus::Unclaimed => "Unclaimed",
+        ClaimStatus::Claiming => "Claiming",
+        ClaimStatus::Claimed => "Claimed",
+        ClaimStatus::TooManyAttempts => "TooManyAttempts",
+        ClaimStatus::UnclaimedOverride => "UnclaimedOverride",
+        ClaimStatus::Claimable => "Claimable",
+    };
+    Ok(SinkRecord {
+        table: &CLAIMS,
+        ts: Some(claim.ts),
+        values: vec![
+            claim.claim_id.into(),
+            claim.user_id.into(),
+            claim.token_id.into(),
+            to_symbol(claim.token_id, symbols_map),
+            status.into(),
+            to_token_units(&claim.amount, claim.token_id, tokens_map)?,
+            claim.request_id.into(),
+            claim.to_address.as_ref().map(|address| address.to_string()).into(),
+            claim.desc.clone().into(),
+            to_ids(&claim.attempt_request_id),
+            to_ids(&claim.transfer_ids),
+            claim.ts.into(),
+            serialize_claim(claim).into(),
+        ],
+    })
+}
+
+pub fn to_transfer_record(
+    transfer: &StableTransfer,
+    tokens_map: &BTreeMap<u32, u8>,
+    symbols_map: &BTreeMap<u32, String>,
+) -> Result<SinkRecord, Box<dyn std::error::Error>> {
+    let (block_index, tx_hash) = match &transfer.tx_id {
+        TxId::BlockIndex(block_index) => (block_index.0.to_f64().into(), SinkValue::Null),
+        TxId::TransactionHash(tx_hash) => (SinkValue::Null, tx_hash.as_str().into()),
+    };
+    Ok(SinkRecord {
+        table: &TRANSFERS,
+        ts: Some(transfer.ts),
+        values: vec![
+            transfer.transfer_id.into(),
+            transfer.request_id.into(),
+            transfer.token_id.into(),
+            to_symbol(transfer.token_id, symbols_map),
+            transfer.is_send.into(),
+            to_token_units(&transfer.amount, transfer.token_id, tokens_map)?,
+            block_index,
+            tx_hash,
+            transfer.ts.into(),
+            serialize_transfer(transfer).into(),
+        ],
+    })
+}
+
+/// columns of a tx that depend on the tx type
+#[derive(Default)]
+struct TxColumns {
+    pool_id: Option<u32>,
+    token_0: Option<(u32, SinkValue, SinkValue)>, // (token_id, amount, lp_fee)
+    token_1: Option<(u32, SinkValue, SinkValue)>,
+    lp_token_amount: Option<f64>,
+    prices: Option<(f64, f64, f64)>, // (price, mid_price, slippage)
+    to_user_id: Option<u32>,
+    transfer_ids: Option<SinkValue>,
+    claim_ids: Option<SinkValue>,
+}
+
+pub fn to_tx_record(
+    tx: &StableTx,
+    tokens_map: &BTreeMap<u32, u8>,
+    pools_map: &BTreeMap<u32, (u32, u32)>,
+    symbols_map: &BTreeMap<u32, String>,
+) -> Result<SinkRecord, Box<dyn std::error::Error>> {
+    let pool_tokens = |pool_id: u32| pools_map.get(&pool_id).copied().ok_or(format!("pool_id={} not found", pool_id));
+    // LP token amounts of the txs have 8 decimals
+    let lp_token_units = |amount: &Nat| match to_units(amount, 8) {
+        SinkValue::Float(amount) => Some(amount),
+        _ => None,
+    };
+    let (tx_id, request_id, user_id, tx_type, status, ts, columns) = match tx {
+        StableTx::AddPool(v) => {
+            let (token_id_0, token_id_1) = pool_tokens(v.pool_id)?;
+            let columns = TxColumns {
+                pool_id: Some(v.pool_id),
+                token_0: Some((token_id_0, to_token_units(&v.amount_0, token_id_0, tokens_map)?, SinkValue::Null)),
+                token_1: Some((token_id_1, to_token_units(&v.amount_1, token_id_1, tokens_map)?, SinkValue::Null)),
+                lp_token_amount: lp_token_units(&v.add_lp_token_amount),
+                transfer_ids: Some(to_ids(&v.transfer_ids)),
+                claim_ids: Some(to_ids(&v.claim_ids)),
+                ..Default::default()
+            };
+            (v.tx_id, v.request_id, v.user_id, "add_pool", &v.status, v.ts, columns)
+        }
+        StableTx::AddLiquidity(v) => {
+            let (token_id_0, token_id_1) = pool_tokens(v.pool_id)?;
+            let columns = TxColumns {
+                pool_id: Some(v.pool_id),
+                token_0: Some((token_id_0, to_token_units(&v.amount_0, token_id_0, tokens_map)?, SinkValue::Null)),
+                token_1: Some((token_id_1, to_token_units(&v.amount_1, token_id_1, tokens_map)?, SinkValue::Null)),
+                lp_token_amount: lp_token_units(&v.add_lp_token_amount),
+                transfer_ids: Some(to_ids(&v.transfer_ids)),
+                claim_ids: Some(to_ids(&v.claim_ids)),
+                ..Default::default()
+            };
+            (v.tx_id, v.request_id, v.user_id, "add_liquidity", &v.status, v.ts, columns)
+        }
+        StableTx::RemoveLiquidity(v) => {
+            let (token_id_0, token_id_1) = pool_tokens(v.pool_id)?;
+            let columns = TxColumns {
+                pool_id: Some(v.pool_id),
+                token_0: Some((
+                    token_id_0,
+                    to_token_units(&v.amount_0, token_id_0, tokens_map)?,
+                    to_token_units(&v.lp_fee_0, token_id_0, tokens_map)?,
+                )),
+                token_1: Some((
+                    token_id_1,
+                    to_token_units(&v.amount_1, token_id_1, tokens_map)?,
+                    to_token_units(&v.lp_fee_1, token_id_1, tokens_map)?,
+                )),
+                lp_token_amount: lp_token_units(&v.remove_lp_token_amount),
+                transfer_ids: Some(to_ids(&v.transfer_ids)),
+                claim_ids: Some(to_ids(&v.claim_ids)),
+                ..Default::default()
+            };
+            (v.tx_id, v.request_id, v.user_id, "remove_liquidity", &v.status, v.ts, columns)
+        }
+        StableTx::Swap(v) => {
+            let columns = TxColumns {
+                token_0: Some((
+                    v.pay_token_id,
+                    to_token_units(&v.pay_amount, v.pay_token_id, tokens_map)?,
+                    SinkValue::Null,
+                )),
+                token_1: Some((
+                    v.receive_token_id,
+                    to_token_units(&v.receive_amount, v.receive_token_id, tokens_map)?,
+                    SinkValue::Null,
+                )),
+                prices: Some((v.price, v.mid_price, v.slippage)),
+                transfer_ids: Some(to_ids(&v.transfer_ids)),
+                claim_ids: Some(to_ids(&v.claim_ids)),
+                ..Default::default()
+            };
+            (v.tx_id, v.request_id, v.user_id, "swap", &v.status, v.ts, columns)
+        }
+        StableTx::Send(v) => {
+            let columns = TxColumns {
+                token_0: Some((v.token_id, to_token_units(&v.amount, v.token_id, tokens_map)?, SinkValue::Null)),
+                to_user_id: Some(v.to_user_id),
+                ..Default::default()
+            };
+            (v.tx_id, v.request_id, v.user_id, "send", &v.status, v.ts, columns)
+        }
+        StableTx::FeeSweep(v) => {
+            let columns = TxColumns {
+                token_0: Some((v.token_id, to_token_units(&v.amount, v.token_id, tokens_map)?, SinkValue::Null)),
+                transfer_ids: Some(to_ids(&v.transfer_ids)),
+                ..Default::default()
+            };
+            (v.tx_id, v.request_id, v.user_id, "fee_sweep", &v.status, v.ts, columns)
+        }
+    };
+
+    let mut values = vec![
+        tx_id.into(),
+        request_id.into(),
+        user_id.into(),
+        tx_type.into(),
+        to_status(status),
+        columns.pool_id.into(),
+    ];
+    for token in [columns.token_0, columns.token_1] {
+        match token {
+            Some((token_id, amount, lp_fee)) => {
+                values.extend([token_id.into(), to_symbol(token_id, symbols_map), amount, lp_fee]);
+            }
+            None => values.extend([SinkValue::Null, SinkValue::Null, SinkValue::Null, SinkValue::Null]),
+        }
+    }
+    let (price, mid_price, slippage) = match columns.prices {
+        Some((price, mid_price, slippage)) => (Some(price), Some(mid_price), Some(slippage)),
+        None => (None, None, None),
+    };
+    values.extend([
+        columns.lp_token_amount.into(),
+        price.into(),
+        mid_price.into(),
+        slippage.into(),
+        columns.to_user_id.into(),
+        columns.transfer_ids.unwrap_or(SinkValue::Null),
+        columns.claim_ids.unwrap_or(SinkValue::Null),
+        ts.into(),
+        serialize_tx(tx).into(),
+    ]);
+    Ok(SinkRecord {
+        table: &TXS,
+        ts: Some(ts),
+        values,
+    })
+}
+
+#[cfg(test)]
+pub mod tests {
+    use super::*;
+    use candid::Principal;
+    use kong_lib::stable_token::ic_token::ICToken;
+    use kong_lib::stable_tx::swap_tx::SwapTx;
+
+    pub fn icp() -> StableToken {
+        StableToken::IC(ICToken {
+            token_id: 1,
+            name: "Internet Computer".to_string(),
+            symbol: "ICP".to_string(),
+            canister_id: Principal::from_text("ryjl3-tyaaa-aaaaa-aaaba-cai").unwrap(),
+            decimals: 8,
+            fee: Nat::from(10_000_u64),
+            icrc1: true,
+            icrc2: true,
+            icrc3: true,
+            is_removed: false,
+        })
+    }
+
+    pub fn ckusdt() -> StableToken {
+        StableToken::IC(ICToken {
+            token_id: 2,
+            name: "ckUSDT".to_string(),
+            symbol: "ckUSDT".to_string(),
+            canister_id: Principal::from_text("cngnf-vqaaa-aaaar-qag4q-cai").unwrap(),
+            decimals: 6,
+            fee: Nat::from(10_000_u64),
+            icrc1: true,
+            icrc2: true,
+            icrc3: false,
+            is_removed: false,
+        })
+    }
+
+    pub fn pool() -> StablePool {
+        StablePool {
+            pool_id: 1,
+            token_id_0: 1,
+            balance_0: Nat::from(250_000_000_u64),
+            lp_fee_0: Nat::from(0_u64),
+            kong_fee_0: Nat::from(0_u64),
+            token_id_1: 2,
+            balance_1: Nat::from(20_500_000_u64),
+            lp_fee_1: Nat::from(30_000_u64),
+            kong_fee_1: Nat::from(0_u64),
+            lp_fee_bps: 30,
+            kong_fee_bps: 0,
+            lp_token_id: 3,
+            is_removed: false,
+        }
+    }
+
+    pub fn swap_tx(tx_id: u64, ts: u64) -> StableTx {
+        StableTx::Swap(SwapTx {
+            tx_id,
+            user_id: 100,
+            request_id: tx_id,
+            status: StatusTx::Success,
+            pay_token_id: 1,
+            pay_amount: Nat::from(100_000_000_u64),
+            receive_token_id: 2,
+            receive_amount: Nat::from(8_123_456_u64),
+            mid_price: 8.2,
+            price: 8.123456,
+            slippage: 0.94,
+            txs: vec![],
+            transfer_ids: vec![1, 2],
+            claim_ids: vec![],
+            to_address: None,
+            ts,
+        })
+    }
+
+    pub fn tokens_map() -> BTreeMap<u32, u8> {
+        BTreeMap::from([(1, 8), (2, 6)])
+    }
+
+    pub fn pools_map() -> BTreeMap<u32, (u32, u32)> {
+        BTreeMap::from([(1, (1, 2))])
+    }
+
+    pub fn symbols_map() -> BTreeMap<u32, String> {
+        BTreeMap::from([(1, "ICP".to_string()), (2, "ckUSDT".to_string())])
+    }
+
+    fn value<'a>(record: &'a SinkRecord, column: &str) -> &'a SinkValue {
+        let i = record.table.columns.iter().position(|(name, _)| *name == column).unwrap();
+        &record.values[i]
+    }
+
+    #[test]
+    fn test_records_match_columns() {
+        let records = [
+            to_token_record(&icp()),
+            to_pool_record(&pool(), &tokens_map(), &symbols_map()).unwrap(),
+            to_tx_record(&swap_tx(1, 0), &tokens_map(), &pools_map(), &symbols_map()).unwrap(),
+        ];
+        for record in records {
+            assert_eq!(record.values.len(), record.table.columns.len(), "{}", record.table.name);
+        }
+    }
+
+    #[test]
+    fn test_amounts_in_token_units() {
+        let record = to_pool_record(&pool(), &tokens_map(), &symbols_map()).unwrap();
+        assert_eq!(value(&record, "pool_id"), &SinkValue::Int(1));
+        assert_eq!(value(&record, "symbol_0"), &SinkValue::Text("ICP".to_string()));
+        assert_eq!(value(&record, "balance_0"), &SinkValue::Float(2.5));
+        assert_eq!(value(&record, "balance_1"), &SinkValue::Float(20.5));
+        assert_eq!(value(&record, "lp_fee_1"), &SinkValue::Float(0.03));
+
+        let record = to_tx_record(&swap_tx(1, 0), &tokens_map(), &pools_map(), &symbols_map()).unwrap();
+        assert_eq!(value(&record, "tx_type"), &SinkValue::Text("swap".to_string()));
+        assert_eq!(value(&record, "pool_id"), &SinkValue::Null);
+        assert_eq!(value(&record, "symbol_1"), &SinkValue::Text("ckUSDT".to_string()));
+        assert_eq!(value(&record, "amount_0"), &SinkValue::Float(1.0));
+        assert_eq!(value(&record, "amount_1"), &SinkValue::Float(8.123456));
+        assert_eq!(value(&record, "transfer_ids"), &SinkValue::Text("[1,2]".to_string()));
+
+        // amounts of unknown tokens can not be converted
+        assert!(to_pool_record(&pool(), &BTreeMap::new(), &symbols_map()).is_err());
+        // unknown symbols are null
+        let record = to_pool_record(&pool(), &tokens_map(), &BTreeMap::new()).unwrap();
+        assert_eq!(value(&record, "symbol_0"), &SinkValue::Null);
+    }
+}
@@ -0,0 +1,318 @@
+use kong_lib::stable_claim::stable_claim::StableClaim;
+use kong_lib::stable_lp_token::stable_lp_token::StableLPToken;
+use kong_lib::stable_pool::stable_pool::StablePool;
+use kong_lib::stable_request::stable_request::StableRequest;
+use kong_lib::stable_token::stable_token::StableToken;
+use kong_lib::stable_token::token::Token;
+use kong_lib::stable_transfer::stable_transfer::StableTransfer;
+use kong_lib::stable_tx::stable_tx::StableTx;
+use kong_lib::stable_user::stable_user::StableUser;
+use rusqlite::types::Value;
+use rusqlite::{params, params_from_iter, Connection, OptionalExtension};
+use std::collections::BTreeMap;
+use std::sync::{Mutex, MutexGuard};
+use tracing::info;
+
+use crate::sink::{
+    to_claim_record, to_lp_token_record, to_pool_record, to_request_record, to_token_record, to_transfer_record, to_tx_record,
+    to_user_record, ColumnType, KongSink, SinkRecord, SinkValue, SINK_TABLES,
+};
+
+// inserts are batched in a transaction and committed every COMMIT_INTERVAL records and on flush
+const COMMIT_INTERVAL: usize = 10_000;
+
+struct SqliteConnection {
+    conn: Connection,
+    pending: usize, // records inserted since the last commit
+}
+
+/// local SQLite file. each table has the columns of its SinkTable with the first column as primary key, so records are
+/// upserted. timestamps are in nanoseconds and booleans are 0 or 1
+pub struct SqliteSink {
+    inner: Mutex<SqliteConnection>,
+    symbols_map: Mutex<BTreeMap<u32, String>>, // token_id -> symbol of the tokens in the sink
+}
+
+impl SqliteSink {
+    pub fn open(path: &str) -> Result<Self, Box<dyn std::error::Error>> {
+        let conn = Connection::open(path)?;
+        conn.execute_batch("PRAGMA journal_mode = WAL; PRAGMA synchronous = NORMAL;")?;
+        for table in SINK_TABLES {
+            let columns = table
+                .columns
+                .iter()
+                .enumerate()
+                .map(|(i, (name, column_type))| {
+                    let sql_type = match column_type {
+                        ColumnType::Int | ColumnType::Bool | ColumnType::Timestamp => "INTEGER",
+                        ColumnType::Float => "REAL",
+                        ColumnType::Text => "TEXT",
+                    };
+                    if i == 0 {
+                        format!("\"{}\" {} PRIMARY KEY", name, sql_type)
+                    } else {
+                        format!("\"{}\" {}", name, sql_type)
+                    }
+                })
+                .collect::<Vec<_>>()
+                .join(", ");
+            conn.execute_batch(&format!("CREATE TABLE IF NOT EXISTS {} ({});", table.name, columns))?;
+            if table.columns.iter().any(|(name, _)| *name == "ts") {
+                conn.execute_batch(&format!("CREATE INDEX IF NOT EXISTS {0}_ts_idx ON {0} (ts);", table.name))?;
+            }
+        }
+        conn.execute_batch(
+            "CREATE TABLE IF NOT EXISTS sync_state (
+                id INTEGER PRIMARY KEY CHECK (id = 1),
+                last_db_update_id INTEGER NOT NULL,
+                last_updated_at TEXT NOT NULL DEFAULT CURRENT_TIMESTAMP
+            )",
+        )?;
+        let symbols_map = conn
+            .prepare("SELECT token_id, symbol FROM tokens WHERE symbol IS NOT NULL")?
+            .query_map([], |row| Ok((row.get::<_, u32>(0)?, row.get::<_, String>(1)?)))?
+            .collect::<Result<BTreeMap<u32, String>, _>>()?;
+        info!("SQLite sink opened at {}", path);
+
+        Ok(SqliteSink {
+            inner: Mutex::new(SqliteConnection { conn, pending: 0 }),
+            symbols_map: Mutex::new(symbols_map),
+        })
+    }
+
+    fn lock(&self) -> Result<MutexGuard<'_, SqliteConnection>, Box<dyn std::error::Error>> {
+        Ok(self.inner.lock().map_err(|_| "SQLite connection lock poisoned")?)
+    }
+
+    fn symbols_map(&self) -> Result<MutexGuard<'_, BTreeMap<u32, String>>, Box<dyn std::error::Error>> {
+        Ok(self.symbols_map.lock().map_err(|_| "SQLite symbols lock poisoned")?)
+    }
+
+    fn insert(&self, record: SinkRecord) -> Result<(), Box<dyn std::error::Error>> {
+        let mut inner = self.lock()?;
+        if inner.conn.is_autocommit() {
+            inner.conn.execute_batch("BEGIN")?;
+        }
+        let columns = record.table.columns;
+        inner
+            .conn
+            .prepare_cached(&format!(
+                "INSERT OR REPLACE INTO {} ({}) VALUES ({})",
+                record.table.name,
+                columns
+                    .iter()
+                    .map(|(name, _)| format!("\"{}\"", name))
+                    .collect::<Vec<_>>()
+                    .join(", "),
+                (1..=columns.len()).map(|i| format!("?{}", i)).collect::<Vec<_>>().join(", ")
+            ))?
+            .execute(params_from_iter(record.values.into_iter().map(to_sql_value)))?;
+        inner.pending += 1;
+        if inner.pending >= COMMIT_INTERVAL {
+            commit(&mut inner)?;
+        }
+        Ok(())
+    }
+}
+
+fn to_sql_value(value: SinkValue) -> Value {
+    match value {
+        SinkValue::Null => Value::Null,
+        SinkValue::Int(v) => Value::Integer(v),
+        SinkValue::Float(v) => Value::Real(v),
+        SinkValue::Text(v) => Value::Text(v),
+        SinkValue::Bool(v) => Value::Integer(v as i64),
+    }
+}
+
+fn commit(inner: &mut SqliteConnection) -> Result<(), Box<dyn std::error::Error>> {
+    if !inner.conn.is_autocommit() {
+        inner.conn.execute_batch("COMMIT")?;
+    }
+    inner.pending = 0;
+    Ok(())
+}
+
+impl KongSink for SqliteSink {
+    async fn insert_user(&self, user: &StableUser) -> Result<(), Box<dyn std::error::Error>> {
+        self.insert(to_user_record(user))
+    }
+
+    async fn insert_token(&self, token: &StableToken) -> Result<(), Box<dyn std::error::Error>> {
+        self.insert(to_token_record(token))?;
+        self.symbols_map()?.insert(token.token_id(), token.symbol());
+        Ok(())
+    }
+
+    async fn insert_pool(&self, pool: &StablePool, tokens_map: &BTreeMap<u32, u8>) -> Result<(), Box<dyn std::error::Error>> {
+        let record = to_pool_record(pool, tokens_map, &*self.symbols_map()?)?;
+        self.insert(record)
+    }
+
+    async fn insert_lp_token(&self, lp_token: &StableLPToken, tokens_map: &BTreeMap<u32, u8>) -> Result<(), Box<dyn std::error::Error>> {
+        let record = to_lp_token_record(lp_token, tokens_map, &*self.symbols_map()?)?;
+        self.insert(record)
+    }
+
+    async fn insert_request(&self, request: &StableRequest) -> Result<(), Box<dyn std::error::Error>> {
+        self.insert(to_request_record(request))
+    }
+
+    async fn insert_claim(&self, claim: &StableClaim, tokens_map: &BTreeMap<u32, u8>) -> Result<(), Box<dyn std::error::Error>> {
+        let record = to_claim_record(claim, tokens_map, &*self.symbols_map()?)?;
+        self.insert(record)
+    }
+
+    async fn insert_transfer(&self, transfer: &StableTransfer, tokens_map: &BTreeMap<u32, u8>) -> Result<(), Box<dyn std::error::Error>> {
+        let record = to_transfer_record(transfer, tokens_map, &*self.symbols_map()?)?;
+        self.insert(record)
+    }
+
+    async fn insert_tx(
+        &self,
+        tx: &StableTx,
+        tokens_map: &BTreeMap<u32, u8>,
+        pools_map: &BTreeMap<u32, (u32, u32)>,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        let record = to_tx_record(tx, tokens_map, pools_map, &*self.symbols_map()?)?;
+        self.insert(record)
+    }
+
+    async fn load_tokens(&self) -> Result<BTreeMap<u32, u8>, Box<dyn std::error::Error>> {
+        let inner = self.lock()?;
+        let tokens_map = inner
+            .conn
+            .prepare("SELECT token_id, decimals FROM tokens")?
+            .query_map([], |row| Ok((row.get::<_, u32>(0)?, row.get::<_, u8>(1)?)))?
+            .collect::<Result<BTreeMap<u32, u8>, _>>()?;
+        Ok(tokens_map)
+    }
+
+    async fn load_pools(&self) -> Result<BTreeMap<u32, (u32, u32)>, Box<dyn std::error::Error>> {
+        let inner = self.lock()?;
+        let pools_map = inner
+            .conn
+            .prepare("SELECT pool_id, token_id_0, token_id_1 FROM pools")?
+            .query_map([], |row| {
+                Ok((row.get::<_, u32>(0)?, (row.get::<_, u32>(1)?, row.get::<_, u32>(2)?)))
+            })?
+            .collect::<Result<BTreeMap<u32, (u32, u32)>, _>>()?;
+        Ok(pools_map)
+    }
+
+    async fn load_sync_state(&self) -> Result<Option<u64>, Box<dyn std::error::Error>> {
+        let inner = self.lock()?;
+        let last_db_update_id = inner
+            .conn
+            .query_row("SELECT last_db_update_id FROM sync_state WHERE id = 1", [], |row| {
+                row.get::<_, i64>(0)
+            })
+            .optional()?;
+        match last_db_update_id {
+            Some(last_id) => info!("Loaded sync state from SQLite: last_db_update_id={}", last_id),
+            None => info!("No previous sync state found in SQLite, starting from beginning"),
+        }
+        Ok(last_db_update_id.map(|last_id| last_id as u64))
+    }
+
+    async fn save_sync_state(&self, last_db_update_id: u64) -> Result<(), Box<dyn std::error::Error>> {
+        let mut inner = self.lock()?;
+        inner.conn.execute(
+            "INSERT INTO sync_state (id, last_db_update_id, last_updated_at)
+            VALUES (1, ?1, CURRENT_TIMESTAMP)
+            ON CONFLICT (id) DO UPDATE SET
+                last_db_update_id = ?1,
+                last_updated_at = CURRENT_TIMESTAMP",
+            params![last_db_update_id as i64],
+        )?;
+        commit(&mut inner)
+    }
+
+    async fn flush(&self) -> Result<(), Box<dyn std::error::Error>> {
+        commit(&mut *self.lock()?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::sink::tests::{ckusdt, icp, pool, pools_map, swap_tx, tokens_map};
+
+    fn sqlite_path(name: &str) -> String {
+        let path = std::env::temp_dir().join(format!("kong_admin_{}_{}.db", name, std::process::id()));
+        let path = path.to_string_lossy().to_string();
+        remove_sqlite_files(&path);
+        path
+    }
+
+    fn remove_sqlite_files(path: &str) {
+        for suffix in ["", "-wal", "-shm"] {
+            let _ = std::fs::remove_file(format!("{}{}", path, suffix));
+        }
+    }
+
+    #[tokio::test]
+    async fn test_round_trip() {
+        let path = sqlite_path("sqlite_round_trip");
+        let sink = SqliteSink::open(&path).unwrap();
+        sink.insert_token(&icp()).await.unwrap();
+        sink.insert_token(&ckusdt()).await.unwrap();
+        sink.insert_pool(&pool(), &tokens_map()).await.unwrap();
+        sink.insert_tx(&swap_tx(1, 1), &tokens_map(), &pools_map()).await.unwrap();
+        // inserting the same tx again replaces it
+        sink.insert_tx(&swap_tx(1, 2), &tokens_map(), &pools_map()).await.unwrap();
+        sink.flush().await.unwrap();
+        drop(sink);
+
+        let sink = SqliteSink::open(&path).unwrap();
+        assert_eq!(sink.load_tokens().await.unwrap(), tokens_map());
+        assert_eq!(sink.load_pools().await.unwrap(), pools_map());
+        // symbols of the tokens already in the file are used after reopening it
+        sink.insert_pool(&pool(), &tokens_map()).await.unwrap();
+        sink.flush().await.unwrap();
+        let inner = sink.lock().unwrap();
+        let (symbol_0, balance_0, balance_1) = inner
+            .conn
+            .query_row("SELECT symbol_0, balance_0, balance_1 FROM pools WHERE pool_id = 1", [], |row| {
+                Ok((row.get::<_, String>(0)?, row.get::<_, f64>(1)?, row.get::<_, f64>(2)?))
+            })
+            .unwrap();
+        assert_eq!((symbol_0.as_str(), balance_0, balance_1), ("ICP", 2.5, 20.5));
+        let txs = inner
+            .conn
+            .prepare("SELECT tx_type, symbol_0, amount_0, amount_1, ts FROM txs")
+            .unwrap()
+            .query_map([], |row| {
+                Ok((
+                    row.get::<_, String>(0)?,
+                    row.get::<_, String>(1)?,
+                    row.get::<_, f64>(2)?,
+                    row.get::<_, f64>(3)?,
+                    row.get::<_, i64>(4)?,
+                ))
+            })
+            .unwrap()
+            .collect::<Result<Vec<_>, _>>()
+            .unwrap();
+        assert_eq!(txs, vec![("swap".to_string(), "ICP".to_string(), 1.0, 8.123456, 2)]);
+        drop(inner);
+        remove_sqlite_files(&path);
+    }
+
+    #[tokio::test]
+    async fn test_sync_state() {
+        let path = sqlite_path("sqlite_sync_state");
+        let sink = SqliteSink::open(&path).unwrap();
+        assert_eq!(sink.load_sync_state().await.unwrap(), None);
+        sink.insert_token(&icp()).await.unwrap();
+        sink.save_sync_state(5).await.unwrap();
+        sink.save_sync_state(7).await.unwrap();
+        drop(sink);
+
+        // saving the sync state also commits the records inserted before it
+        let sink = SqliteSink::open(&path).unwrap();
+        assert_eq!(sink.load_sync_state().await.unwrap(), Some(7));
+        assert_eq!(sink.load_tokens().await.unwrap(), BTreeMap::from([(1, 8)]));
+        remove_sqlite_files(&path);
+    }
+}
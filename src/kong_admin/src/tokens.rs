@@ -12,6 +12,7 @@ use std::path::Path;
 use tokio_postgres::Client;
 
 use super::kong_update::KongUpdate;
+use super::sink::KongSink;
 
 #[derive(Debug, ToSql, FromSql)]
 #[postgres(name = "token_type")]
@@ -50,7 +51,7 @@ pub fn serialize_token(token: &StableToken) -> serde_json::Value {
     }
 }
 
-pub async fn update_tokens_on_database(db_client: &Client) -> Result<BTreeMap<u32, u8>, Box<dyn std::error::Error>> {
+pub async fn update_tokens_on_database<T: KongSink>(sink: &T) -> Result<BTreeMap<u32, u8>, Box<dyn std::error::Error>> {
     let dir_path = "./backups";
     let re_pattern = Regex::new(r"^tokens.*.json$").unwrap();
     let mut files = fs::read_dir(dir_path)?
@@ -79,11 +80,11 @@ pub async fn update_tokens_on_database(db_client: &Client) -> Result<BTreeMap<u3
         let tokens_map: BTreeMap<StableTokenId, StableToken> = serde_json::from_reader(reader)?;
 
         for v in tokens_map.values() {
-            insert_token_on_database(v, db_client).await?;
+            sink.insert_token(v).await?;
         }
     }
 
-    load_tokens_from_database(db_client).await
+    sink.load_tokens().await
 }
 
 pub async fn insert_token_on_database(v: &StableToken, db_client: &Client) -> Result<(), Box<dyn std::error::Error>> {
@@ -171,22 +172,6 @@ pub async fn insert_token_on_database(v: &StableToken, db_client: &Client) -> Re
     Ok(())
 }
 
-pub async fn query_token_decimals(
-    db_client: &Client,
-    token_id: u32,
-) -> Result<u8, Box<dyn std::error::Error>> {
-    let stmt = db_client
-        .prepare("SELECT decimals FROM tokens WHERE token_id = $1")
-        .await?;
-
-    let row = db_client
-        .query_one(&stmt, &[&(token_id as i32)])
-        .await?;
-
-    let decimals: i16 = row.get(0);
-    Ok(decimals as u8)
-}
-
 pub async fn load_tokens_from_database(db_client: &Client) -> Result<BTreeMap<u32, u8>, Box<dyn std::error::Error>> {
     let mut tokens_map = BTreeMap::new();
     let rows = db_client.query("SELECT token_id, decimals FROM tokens", &[]).await?;
@@ -12,6 +12,7 @@ use tokio_postgres::Client;
 
 use super::kong_update::KongUpdate;
 use super::math_helpers::round_f64;
+use super::sink::KongSink;
 
 pub fn serialize_option_tx_id(tx_id: Option<&TxId>) -> serde_json::Value {
     match tx_id {
@@ -44,7 +45,7 @@ pub fn serialize_transfer(transfer: &StableTransfer) -> serde_json::Value {
     })
 }
 
-pub async fn update_transfers_on_database(db_client: &Client, tokens_map: &BTreeMap<u32, u8>) -> Result<(), Box<dyn std::error::Error>> {
+pub async fn update_transfers_on_database<T: KongSink>(sink: &T, tokens_map: &BTreeMap<u32, u8>) -> Result<(), Box<dyn std::error::Error>> {
     let dir_path = "./backups";
     let re_pattern = Regex::new(r"^transfers.*.json$").unwrap();
     let mut files = fs::read_dir(dir_path)?
@@ -73,7 +74,7 @@ pub async fn update_transfers_on_database(db_client: &Client, tokens_map: &BTree
         let transfer_map: BTreeMap<StableTransferId, StableTransfer> = serde_json::from_reader(reader)?;
 
         for v in transfer_map.values() {
-            insert_transfer_on_database(v, db_client, tokens_map).await?;
+            sink.insert_transfer(v, tokens_map).await?;
         }
     }
 
@@ -14,6 +14,7 @@ use tokio_postgres::Client;
 
 use super::kong_update::KongUpdate;
 use super::math_helpers::round_f64;
+use super::sink::KongSink;
 
 #[derive(Debug, ToSql, FromSql)]
 #[postgres(name = "tx_type")]
@@ -28,6 +29,8 @@ enum TxType {
     Swap,
     #[postgres(name = "send")]
     Send,
+    #[postgres(name = "fee_sweep")]
+    FeeSweep,
 }
 
 #[derive(Debug, ToSql, FromSql)]
@@ -127,11 +130,35 @@ pub fn serialize_tx(tx: &StableTx) -> serde_json::Value {
                 "ts": tx.ts,
             }
         }),
+        StableTx::FeeSweep(tx) => json!({
+            "FeeSweepTx": {
+                "tx_id": tx.tx_id,
+                "token_id": tx.token_id,
+                "request_id": tx.request_id,
+                "user_id": tx.user_id,
+                "status": tx.status,
+                "amount": tx.amount.to_string(),
+                "pool_ids": tx.pool_ids,
+                "treasury_amount": tx.treasury_amount.to_string(),
+                "buyback_amount": tx.buyback_amount.to_string(),
+                "burn_amount": tx.burn_amount.to_string(),
+                "txs": tx.txs.iter().map(|x| json!({
+                    "pay_token_id": x.pay_token_id,
+                    "pay_amount": x.pay_amount.to_string(),
+                    "receive_token_id": x.receive_token_id,
+                    "receive_amount": x.receive_amount.to_string(),
+                    "lp_fee": x.lp_fee.to_string(),
+                    "gas_fee": x.gas_fee.to_string(),
+                })).collect::<Vec<serde_json::Value>>(),
+                "transfer_ids": tx.transfer_ids,
+                "ts": tx.ts,
+            }
+        }),
     }
 }
 
-pub async fn update_txs_on_database(
-    db_client: &Client,
+pub async fn update_txs_on_database<T: KongSink>(
+    sink: &T,
     tokens_map: &BTreeMap<u32, u8>,
     pools_map: &BTreeMap<u32, (u32, u32)>,
 ) -> Result<(), Box<dyn std::error::Error>> {
@@ -163,7 +190,7 @@ pub async fn update_txs_on_database(
         let txs_map: BTreeMap<StableTxId, StableTx> = serde_json::from_reader(reader)?;
 
         for (_, v) in txs_map.iter() {
-            insert_tx_on_database(v, db_client, tokens_map, pools_map)
+            sink.insert_tx(v, tokens_map, pools_map)
                 .await
                 .unwrap_or_else(|e| eprintln!("{}", e));
         }
@@ -579,6 +606,81 @@ pub async fn insert_tx_on_database(
                 )
                 .await?;
         }
+        StableTx::FeeSweep(v) => {
+            let tx_id = v.tx_id as i64;
+            let token_id = v.token_id as i32;
+            let request_id = v.request_id as i64;
+            let user_id = v.user_id as i32;
+            let tx_type = TxType::FeeSweep;
+            let status = match v.status {
+                StatusTx::Success => TxStatus::Success,
+                StatusTx::Failed => TxStatus::Failed,
+            };
+            let decimals = tokens_map.get(&v.token_id).ok_or(format!("token_id={} not found", v.token_id))?;
+            let amount = round_f64(v.amount.0.to_f64().unwrap() / 10_u64.pow(*decimals as u32) as f64, *decimals);
+            let pool_ids = v.pool_ids.iter().map(|x| *x as i32).collect::<Vec<i32>>();
+            let treasury_amount = round_f64(v.treasury_amount.0.to_f64().unwrap() / 10_u64.pow(*decimals as u32) as f64, *decimals);
+            let buyback_amount = round_f64(v.buyback_amount.0.to_f64().unwrap() / 10_u64.pow(*decimals as u32) as f64, *decimals);
+            // KONG is the receive token of the last buyback swap, or the swept token if it is KONG
+            let kong_token_id = v.txs.last().map_or(v.token_id, |swap| swap.receive_token_id);
+            let kong_decimals = tokens_map.get(&kong_token_id).ok_or(format!("token_id={} not found", kong_token_id))?;
+            let burn_amount = round_f64(
+                v.burn_amount.0.to_f64().unwrap() / 10_u64.pow(*kong_decimals as u32) as f64,
+                *kong_decimals,
+            );
+            let transfer_ids = v.transfer_ids.iter().map(|x| *x as i64).collect::<Vec<i64>>();
+            let ts = v.ts as f64 / 1_000_000_000.0;
+
+            db_client
+                .execute(
+                    "INSERT INTO txs
+                        (tx_id, request_id, user_id, tx_type, status, ts, raw_json)
+                        VALUES ($1, $2, $3, $4, $5, to_timestamp($6), $7)
+                        ON CONFLICT (tx_id) DO UPDATE SET
+                            request_id = $2,
+                            user_id = $3,
+                            tx_type = $4,
+                            status = $5,
+                            ts = to_timestamp($6),
+                            raw_json = $7",
+                    &[&tx_id, &request_id, &user_id, &tx_type, &status, &ts, &raw_json],
+                )
+                .await?;
+
+            db_client
+                .execute(
+                    "INSERT INTO fee_sweep_tx
+                    (tx_id, token_id, request_id, user_id, status, amount, pool_ids, treasury_amount, buyback_amount, burn_amount, transfer_ids, ts)
+                    VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, to_timestamp($12))
+                    ON CONFLICT (tx_id) DO UPDATE SET
+                        token_id = $2,
+                        request_id = $3,
+                        user_id = $4,
+                        status = $5,
+                        amount = $6,
+                        pool_ids = $7,
+                        treasury_amount = $8,
+                        buyback_amount = $9,
+                        burn_amount = $10,
+                        transfer_ids = $11,
+                        ts = to_timestamp($12)",
+                    &[
+                        &tx_id,
+                        &token_id,
+                        &request_id,
+                        &user_id,
+                        &status,
+                        &amount,
+                        &pool_ids,
+                        &treasury_amount,
+                        &buyback_amount,
+                        &burn_amount,
+                        &transfer_ids,
+                        &ts,
+                    ],
+                )
+                .await?;
+        }
     };
 
     println!("tx_id={} saved", v.tx_id());
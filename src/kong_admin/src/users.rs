@@ -9,8 +9,9 @@ use std::path::Path;
 use tokio_postgres::Client;
 
 use super::kong_update::KongUpdate;
+use super::sink::KongSink;
 
-pub async fn update_users_on_database(db_client: &Client) -> Result<(), Box<dyn std::error::Error>> {
+pub async fn update_users_on_database<T: KongSink>(sink: &T) -> Result<(), Box<dyn std::error::Error>> {
     let dir_path = "./backups";
     let re_pattern = Regex::new(r"^users.*.json$").unwrap();
     let mut files = fs::read_dir(dir_path)?
@@ -39,7 +40,7 @@ pub async fn update_users_on_database(db_client: &Client) -> Result<(), Box<dyn
         let user_map: BTreeMap<StableUserId, StableUser> = serde_json::from_reader(reader)?;
 
         for v in user_map.values() {
-            insert_user_on_database(v, db_client).await?;
+            sink.insert_user(v).await?;
         }
     }
 
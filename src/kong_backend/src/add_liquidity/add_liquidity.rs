@@ -1,11 +1,14 @@
 use ic_cdk::update;
 
 use super::add_liquidity_args::AddLiquidityArgs;
+use super::add_liquidity_deposit::add_liquidity_deposit;
 use super::add_liquidity_reply::AddLiquidityReply;
 use super::add_liquidity_transfer::{add_liquidity_transfer, add_liquidity_transfer_async};
 use super::add_liquidity_transfer_from::{add_liquidity_transfer_from, add_liquidity_transfer_from_async};
 
 use crate::ic::guards::not_in_maintenance_mode;
+use crate::stable_lp_token::lp_token_lock;
+use crate::stable_pool::pool_map;
 
 pub enum TokenIndex {
     Token0,
@@ -24,6 +27,9 @@ pub enum TokenIndex {
 ///  amount_0: amount of token_0 to add (nat) eg. 100_000_000 is 1 ICP
 ///  symbol_1: symbol of token_1 eg. "ckUSDT". Currently only ckUSDT as all pools against ckUSDT
 ///  amount_1: amount of token_1 to add (nat) eg. 1_000_000 is 1 ckUSDT
+///  lock_secs: optional lock period of the LP tokens, up to 1 year. locked LP tokens can not be removed or transferred
+///             until they unlock and earn a boosted share of the LP fees, up to 2x for 1 year
+///  use_deposit: optional, take token_0 and token_1 from the user's deposit instead of the ledgers
 ///
 /// Returns: AddLiquidityReply
 ///  pay_symbol: name of the pool eg. "ckBTC_ckUSDT"
@@ -54,6 +60,11 @@ pub enum TokenIndex {
 /// 9. return_tokens() - otherwise if any errors occurred, return tokens
 #[update(guard = "not_in_maintenance_mode")]
 pub async fn add_liquidity(args: AddLiquidityArgs) -> Result<AddLiquidityReply, String> {
+    check_lock_secs(&args)?;
+
+    if args.use_deposit == Some(true) {
+        return add_liquidity_deposit(args);
+    }
     // determine if using icrc2_approve or irc1_transfer method
     if args.tx_id_0.is_none() && args.tx_id_1.is_none() {
         add_liquidity_transfer_from(args).await
@@ -73,6 +84,12 @@ pub async fn add_liquidity(args: AddLiquidityArgs) -> Result<AddLiquidityReply,
 /// Returns: u64 - request_id. poll requests(request_id) to return the current status of the request
 #[update(guard = "not_in_maintenance_mode")]
 pub async fn add_liquidity_async(args: AddLiquidityArgs) -> Result<u64, String> {
+    check_lock_secs(&args)?;
+
+    // adding liquidity from the deposit completes immediately
+    if args.use_deposit == Some(true) {
+        return add_liquidity_deposit(args).map(|reply| reply.request_id);
+    }
     // determine if using icrc2_approve or irc1_transfer method
     if args.tx_id_0.is_none() && args.tx_id_1.is_none() {
         add_liquidity_transfer_from_async(args).await
@@ -81,6 +98,20 @@ pub async fn add_liquidity_async(args: AddLiquidityArgs) -> Result<u64, String>
     }
 }
 
+/// LP tokens can only be locked in pools with LP tokens
+fn check_lock_secs(args: &AddLiquidityArgs) -> Result<(), String> {
+    if let Some(lock_secs) = args.lock_secs {
+        lp_token_lock::check_lock_secs(lock_secs)?;
+        if !pool_map::get_by_tokens_and_fee_tier(&args.token_0, &args.token_1, args.fee_tier)?
+            .pool_type
+            .has_lp_token()
+        {
+            Err("Concentrated pools do not support locks".to_string())?
+        }
+    }
+    Ok(())
+}
+
 /// api to validate add_liquidity for SNS proposals
 #[update]
 fn validate_add_liquidity() -> Result<String, String> {
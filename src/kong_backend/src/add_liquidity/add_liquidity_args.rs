@@ -13,4 +13,11 @@ pub struct AddLiquidityArgs {
     pub token_1: String,
     pub amount_1: Nat,
     pub tx_id_1: Option<TxId>,
+    pub min_price: Option<f64>, // price range for concentrated pools, in token_1 per token_0. None for full range
+    pub max_price: Option<f64>,
+    pub lock_secs: Option<u64>,           // lock the LP tokens for lock_secs to boost their share of the LP fees
+    pub use_deposit: Option<bool>,        // pay token_0 and token_1 from the user's deposit instead of ledger transfers
+    pub fee_tier: Option<u8>,             // fee tier of the pool. None for the first pool of the pair
+    pub min_lp_token_amount: Option<Nat>, // minimum LP tokens (liquidity for concentrated pools) to receive
+    pub deadline: Option<u64>,            // nanoseconds since the Unix epoch. rejected and refunded after the deadline
 }
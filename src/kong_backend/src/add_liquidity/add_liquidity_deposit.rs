@@ -0,0 +1,129 @@
+use candid::Nat;
+
+use super::add_liquidity_args::AddLiquidityArgs;
+use super::add_liquidity_reply::AddLiquidityReply;
+use super::add_liquidity_reply_helpers::to_add_liquidity_reply;
+use super::add_liquidity_transfer_from::{archive_to_kong_data, calculate_amounts, update_liquidity_pool};
+
+use crate::deposits::deposits::{check_deposit, credit_deposits, debit_deposits};
+use crate::helpers::nat_helpers::{nat_is_zero, nat_subtract, nat_zero};
+use crate::ic::deadline::check_deadline;
+use crate::ic::get_time::get_time;
+use crate::stable_pool::stable_pool::StablePool;
+use crate::stable_request::{reply::Reply, request::Request, request_map, stable_request::StableRequest, status::StatusCode};
+use crate::stable_token::token::Token;
+use crate::stable_tx::{add_liquidity_tx::AddLiquidityTx, stable_tx::StableTx, tx_map};
+use crate::stable_user::user_map;
+
+/// add liquidity with token_0 and token_1 from the user's deposit, so there are no ledger transfers, no gas fees
+/// and no inter-canister calls. any amount not added to the pool stays in the deposit
+pub fn add_liquidity_deposit(args: AddLiquidityArgs) -> Result<AddLiquidityReply, String> {
+    let (user_id, pool, add_amount_0, add_amount_1) = check_arguments(&args)?;
+    let ts = get_time();
+    let request_id = request_map::insert(&StableRequest::new(user_id, &Request::AddLiquidity(args.clone()), ts));
+
+    let result = match process_add_liquidity(request_id, user_id, &pool, &add_amount_0, &add_amount_1, &args, ts) {
+        Ok(reply) => {
+            request_map::update_status(request_id, StatusCode::Success, None);
+            Ok(reply)
+        }
+        Err(e) => {
+            request_map::update_status(request_id, StatusCode::Failed, None);
+            Err(e)
+        }
+    };
+    _ = archive_to_kong_data(request_id);
+
+    result
+}
+
+fn check_arguments(args: &AddLiquidityArgs) -> Result<(u32, StablePool, Nat, Nat), String> {
+    if nat_is_zero(&args.amount_0) || nat_is_zero(&args.amount_1) {
+        Err("Invalid zero amounts".to_string())?
+    }
+
+    // deposits are moved internally, so no transfers
+    if args.tx_id_0.is_some() || args.tx_id_1.is_some() {
+        Err("Tx_id_0 and Tx_id_1 not supported with use_deposit".to_string())?
+    }
+    check_deadline(args.deadline)?;
+
+    let (pool, add_amount_0, add_amount_1, _) = calculate_amounts(
+        &args.token_0,
+        &args.amount_0,
+        &args.token_1,
+        &args.amount_1,
+        args.fee_tier,
+        args.min_price,
+        args.max_price,
+    )?;
+
+    let token_0 = pool.token_0();
+    if token_0.is_removed() {
+        Err("Token_0 is suspended or removed".to_string())?
+    }
+    let token_1 = pool.token_1();
+    if token_1.is_removed() {
+        Err("Token_1 is suspended or removed".to_string())?
+    }
+
+    // make sure user is registered, if not create a new user
+    let user_id = user_map::insert(None)?;
+    check_deposit(user_id, &token_0, &add_amount_0)?;
+    check_deposit(user_id, &token_1, &add_amount_1)?;
+
+    Ok((user_id, pool, add_amount_0, add_amount_1))
+}
+
+fn process_add_liquidity(
+    request_id: u64,
+    user_id: u32,
+    pool: &StablePool,
+    add_amount_0: &Nat,
+    add_amount_1: &Nat,
+    args: &AddLiquidityArgs,
+    ts: u64,
+) -> Result<AddLiquidityReply, String> {
+    request_map::update_status(request_id, StatusCode::Start, None);
+
+    let token_id_0 = pool.token_id_0;
+    let token_id_1 = pool.token_id_1;
+    debit_deposits(request_id, user_id, &[(token_id_0, add_amount_0), (token_id_1, add_amount_1)], ts)?;
+
+    let (pool, amount_0, amount_1, add_lp_token_amount) =
+        match update_liquidity_pool(request_id, user_id, pool, add_amount_0, add_amount_1, args, ts) {
+            Ok(amounts) => amounts,
+            Err(e) => {
+                // return the tokens to the deposit
+                credit_deposits(request_id, user_id, &[(token_id_0, add_amount_0), (token_id_1, add_amount_1)], ts);
+                Err(format!("Req #{} failed. {}", request_id, e))?
+            }
+        };
+
+    // return any amount not added to the pool to the deposit
+    let unused_amount_0 = nat_subtract(add_amount_0, &amount_0).unwrap_or(nat_zero());
+    let unused_amount_1 = nat_subtract(add_amount_1, &amount_1).unwrap_or(nat_zero());
+    credit_deposits(
+        request_id,
+        user_id,
+        &[(token_id_0, &unused_amount_0), (token_id_1, &unused_amount_1)],
+        ts,
+    );
+
+    let add_liquidity_tx = AddLiquidityTx::new_success(
+        pool.pool_id,
+        user_id,
+        request_id,
+        &amount_0,
+        &amount_1,
+        &add_lp_token_amount,
+        &[],
+        &[],
+        ts,
+    );
+    let tx_id = tx_map::insert(&StableTx::AddLiquidity(add_liquidity_tx.clone()));
+    let reply = to_add_liquidity_reply(&AddLiquidityTx { tx_id, ..add_liquidity_tx });
+    request_map::update_reply(request_id, Reply::AddLiquidity(reply.clone()));
+
+    Ok(reply)
+}
@@ -1,6 +1,7 @@
 use candid::{CandidType, Nat};
 use serde::{Deserialize, Serialize};
 
+use crate::swap::swap_reply::SwapTxReply;
 use crate::transfers::transfer_reply::TransferIdReply;
 
 #[derive(CandidType, Debug, Clone, Serialize, Deserialize)]
@@ -20,6 +21,8 @@ pub struct AddLiquidityReply {
     pub symbol_1: String,
     pub amount_1: Nat,
     pub add_lp_token_amount: Nat,
+    #[serde(default)]
+    pub swap_txs: Vec<SwapTxReply>,
     pub transfer_ids: Vec<TransferIdReply>,
     pub claim_ids: Vec<u64>,
     pub ts: u64,
@@ -4,6 +4,7 @@ use crate::helpers::nat_helpers::nat_zero;
 use crate::stable_pool::pool_map;
 use crate::stable_tx::add_liquidity_tx::AddLiquidityTx;
 use crate::stable_tx::status_tx::StatusTx;
+use crate::swap::swap_reply_helpers::to_txs;
 use crate::transfers::transfer_reply_helpers::to_transfer_ids;
 
 fn get_pool_info(pool_id: u32) -> (String, String, String, String, String, String, String) {
@@ -49,6 +50,7 @@ pub fn to_add_liquidity_reply(add_liquidity_tx: &AddLiquidityTx) -> AddLiquidity
         symbol_1,
         amount_1: add_liquidity_tx.amount_1.clone(),
         add_lp_token_amount: add_liquidity_tx.add_lp_token_amount.clone(),
+        swap_txs: to_txs(&add_liquidity_tx.swap_txs, add_liquidity_tx.ts),
         transfer_ids: to_transfer_ids(&add_liquidity_tx.transfer_ids),
         claim_ids: add_liquidity_tx.claim_ids.clone(),
         ts: add_liquidity_tx.ts,
@@ -71,6 +73,7 @@ pub fn to_add_liquidity_reply_failed(pool_id: u32, request_id: u64, transfer_ids
         symbol_1,
         amount_1: nat_zero(),
         add_lp_token_amount: nat_zero(),
+        swap_txs: Vec::new(),
         transfer_ids: to_transfer_ids(transfer_ids),
         claim_ids: claim_ids.to_vec(),
         ts,
@@ -11,14 +11,15 @@ use crate::helpers::nat_helpers::{
 };
 use crate::ic::{
     address::Address,
+    deadline::{check_deadline, check_request_deadline},
     get_time::get_time,
     id::caller_id,
     transfer::{icrc1_transfer, icrc2_transfer_from},
 };
 use crate::stable_claim::{claim_map, stable_claim::StableClaim};
 use crate::stable_kong_settings::kong_settings_map;
-use crate::stable_lp_token::{lp_token_map, stable_lp_token::StableLPToken};
-use crate::stable_pool::{pool_map, stable_pool::StablePool};
+use crate::stable_lp_token::{lp_block_map, lp_token_lock, lp_token_lock::StableLPTokenLock, lp_token_map, stable_lp_token::StableLPToken};
+use crate::stable_pool::{pool_map, pool_type::PoolType, stable_pool::StablePool};
 use crate::stable_request::{reply::Reply, request::Request, request_map, stable_request::StableRequest, status::StatusCode};
 use crate::stable_token::stable_token::StableToken;
 use crate::stable_token::token::Token;
@@ -29,9 +30,9 @@ use crate::stable_user::user_map;
 pub async fn add_liquidity_transfer_from(args: AddLiquidityArgs) -> Result<AddLiquidityReply, String> {
     let (user_id, pool, add_amount_0, add_amount_1) = check_arguments(&args).await?;
     let ts = get_time();
-    let request_id = request_map::insert(&StableRequest::new(user_id, &Request::AddLiquidity(args), ts));
+    let request_id = request_map::insert(&StableRequest::new(user_id, &Request::AddLiquidity(args.clone()), ts));
 
-    let result = match process_add_liquidity(request_id, user_id, &pool, &add_amount_0, &add_amount_1, ts).await {
+    let result = match process_add_liquidity(request_id, user_id, &pool, &add_amount_0, &add_amount_1, &args, ts).await {
         Ok(reply) => {
             request_map::update_status(request_id, StatusCode::Success, None);
             Ok(reply)
@@ -52,7 +53,7 @@ pub async fn add_liquidity_transfer_from_async(args: AddLiquidityArgs) -> Result
     let request_id = request_map::insert(&StableRequest::new(user_id, &Request::AddLiquidity(args.clone()), ts));
 
     ic_cdk::spawn(async move {
-        match process_add_liquidity(request_id, user_id, &pool, &add_amount_0, &add_amount_1, ts).await {
+        match process_add_liquidity(request_id, user_id, &pool, &add_amount_0, &add_amount_1, &args, ts).await {
             Ok(_) => request_map::update_status(request_id, StatusCode::Success, None),
             Err(_) => request_map::update_status(request_id, StatusCode::Failed, None),
         };
@@ -72,9 +73,22 @@ async fn check_arguments(args: &AddLiquidityArgs) -> Result<(u32, StablePool, Na
         Err("Tx_id_0 and Tx_id_1 not supported".to_string())?
     }
 
+    // reject before transferring the tokens. deadline and minimum LP token amount are checked again once the
+    // transfer_from's are done
+    check_deadline(args.deadline)?;
+
     // add_amount_0 and add_amount_1 are the amounts to be added to the pool with the current state
     // these are the amounts that will be transferred to the pool
-    let (pool, add_amount_0, add_amount_1, _) = calculate_amounts(&args.token_0, &args.amount_0, &args.token_1, &args.amount_1)?;
+    let (pool, add_amount_0, add_amount_1, add_lp_token_amount) = calculate_amounts(
+        &args.token_0,
+        &args.amount_0,
+        &args.token_1,
+        &args.amount_1,
+        args.fee_tier,
+        args.min_price,
+        args.max_price,
+    )?;
+    check_min_lp_token_amount(&add_lp_token_amount, args.min_lp_token_amount.as_ref())?;
 
     let token_0 = pool.token_0();
     if token_0.is_removed() {
@@ -100,11 +114,34 @@ async fn check_arguments(args: &AddLiquidityArgs) -> Result<(u32, StablePool, Na
 
 /// calculate the ratio of amounts (amount_0 and amount_1) to be added to the pool to maintain constant K
 /// calculate the LP token amount for the user
+/// for concentrated pools, calculate the amounts for the price range and the liquidity of the position
 ///
 /// returns (pool, amount_0, amount_1, add_lp_token_amount)
-pub fn calculate_amounts(token_0: &str, amount_0: &Nat, token_1: &str, amount_1: &Nat) -> Result<(StablePool, Nat, Nat, Nat), String> {
+pub fn calculate_amounts(
+    token_0: &str,
+    amount_0: &Nat,
+    token_1: &str,
+    amount_1: &Nat,
+    fee_tier: Option<u8>,
+    min_price: Option<f64>,
+    max_price: Option<f64>,
+) -> Result<(StablePool, Nat, Nat, Nat), String> {
     // Pool - make sure pool exists, refresh balances of the pool to make sure we have the latest state
-    let pool = pool_map::get_by_tokens(token_0, token_1)?;
+    let pool = pool_map::get_by_tokens_and_fee_tier(token_0, token_1, fee_tier)?;
+    if let PoolType::Concentrated(concentrated_pool) = &pool.pool_type {
+        let (tick_lower, tick_upper) =
+            concentrated_pool.ticks_for_price_range(min_price, max_price, pool.token_0().decimals(), pool.token_1().decimals())?;
+        let liquidity = concentrated_pool.liquidity_for_amounts(tick_lower, tick_upper, amount_0, amount_1);
+        if nat_is_zero(&liquidity) {
+            Err("Insufficient amounts for price range".to_string())?
+        }
+        // amounts are rounded up so the position is fully funded
+        let (add_amount_0, add_amount_1) = concentrated_pool.amounts_for_liquidity(tick_lower, tick_upper, &liquidity, true);
+        return Ok((pool, add_amount_0, add_amount_1, liquidity));
+    }
+    if min_price.is_some() || max_price.is_some() {
+        Err("Price range only supported for concentrated pools".to_string())?
+    }
     // Token0
     let token_0 = pool.token_0();
     // reserve_0 is the total balance of token_0 in the pool = balance_0 + lp_fee_0
@@ -191,6 +228,7 @@ async fn process_add_liquidity(
     pool: &StablePool,
     add_amount_0: &Nat,
     add_amount_1: &Nat,
+    args: &AddLiquidityArgs,
     ts: u64,
 ) -> Result<AddLiquidityReply, String> {
     // Token0
@@ -247,7 +285,7 @@ async fn process_add_liquidity(
 
     // re-calculate with latest pool state and make sure amounts are valid
     let (pool, amount_0, amount_1, add_lp_token_amount) =
-        match update_liquidity_pool(request_id, user_id, pool, add_amount_0, add_amount_1, ts) {
+        match update_liquidity_pool(request_id, user_id, pool, add_amount_0, add_amount_1, args, ts) {
             Ok((pool, amount_0, amount_1, add_lp_token_amount)) => (pool, amount_0, amount_1, add_lp_token_amount),
             Err(e) => {
                 // LP amounts are incorrect. return token_0 and token_1 back to user
@@ -301,6 +339,11 @@ pub async fn transfer_from_token(
 ) -> Result<(), String> {
     let token_id = token.token_id();
 
+    // concentrated positions out of the current price range only need one of the tokens
+    if nat_is_zero(amount) {
+        return Ok(());
+    }
+
     match token_index {
         TokenIndex::Token0 => request_map::update_status(request_id, StatusCode::SendToken0, None),
         TokenIndex::Token1 => request_map::update_status(request_id, StatusCode::SendToken1, None),
@@ -344,8 +387,12 @@ pub fn update_liquidity_pool(
     pool: &StablePool,
     add_amount_0: &Nat,
     add_amount_1: &Nat,
+    args: &AddLiquidityArgs,
     ts: u64,
 ) -> Result<(StablePool, Nat, Nat, Nat), String> {
+    // tokens have been received by now and are returned by the caller on failure
+    check_request_deadline(request_id, args.deadline)?;
+
     request_map::update_status(request_id, StatusCode::CalculatePoolAmounts, None);
 
     let token_0 = pool.token_0().address_with_chain();
@@ -353,19 +400,55 @@ pub fn update_liquidity_pool(
     // re-calculate the amounts to be added to the pool with new state (after token_0 and token_1 transfers)
     // add_amount_0 and add_amount_1 are the transferred amounts from the initial calculations
     // amount_0, amount_1 and add_lp_token_amount will be the actual amounts to be added to the pool
-    match calculate_amounts(&token_0, add_amount_0, &token_1, add_amount_1) {
-        Ok((mut pool, amount_0, amount_1, add_lp_token_amount)) => {
+    match calculate_amounts(
+        &token_0,
+        add_amount_0,
+        &token_1,
+        add_amount_1,
+        pool.fee_tier,
+        args.min_price,
+        args.max_price,
+    ) {
+        Ok((pool, amount_0, amount_1, add_lp_token_amount)) => {
             request_map::update_status(request_id, StatusCode::CalculatePoolAmountsSuccess, None);
 
+            check_min_lp_token_amount(&add_lp_token_amount, args.min_lp_token_amount.as_ref()).inspect_err(|e| {
+                request_map::update_status(request_id, StatusCode::MinLPTokenAmountNotMet, Some(e));
+            })?;
+
             request_map::update_status(request_id, StatusCode::UpdatePoolAmounts, None);
 
+            let mut pool = lp_token_map::save_expired_locks(pool, ts);
+            pool.update_price_cumulative(ts);
             pool.balance_0 = nat_add(&pool.balance_0, &amount_0);
             pool.balance_1 = nat_add(&pool.balance_1, &amount_1);
+            let decimals_0 = pool.token_0().decimals();
+            let decimals_1 = pool.token_1().decimals();
+            if let PoolType::Concentrated(ref mut concentrated_pool) = pool.pool_type {
+                // concentrated pools do not mint LP tokens, the user gets a position with the liquidity instead
+                let (tick_lower, tick_upper) =
+                    concentrated_pool.ticks_for_price_range(args.min_price, args.max_price, decimals_0, decimals_1)?;
+                concentrated_pool.add_position(user_id, tick_lower, tick_upper, &add_lp_token_amount, ts);
+            }
+            // locking adds to the boost of the pool's locks
+            let lp_token = lp_token_map::get_by_token_id_by_user_id(pool.lp_token_id, user_id);
+            let lock = match args.lock_secs {
+                Some(lock_secs) => Some(lp_token_lock::add_lock(
+                    &mut pool,
+                    lp_token.as_ref().and_then(|lp_token| lp_token.lock.as_ref()),
+                    &add_lp_token_amount,
+                    lock_secs,
+                    ts,
+                )),
+                None => lp_token.as_ref().and_then(|lp_token| lp_token.lock.clone()),
+            };
             pool_map::update(&pool);
             request_map::update_status(request_id, StatusCode::UpdatePoolAmountsSuccess, None);
 
             // update user's LP token amount
-            update_lp_token(request_id, user_id, pool.lp_token_id, &add_lp_token_amount, ts);
+            if pool.pool_type.has_lp_token() {
+                update_lp_token(request_id, user_id, pool.lp_token_id, &add_lp_token_amount, lock, ts);
+            }
 
             Ok((pool, amount_0, amount_1, add_lp_token_amount))
         }
@@ -376,28 +459,46 @@ pub fn update_liquidity_pool(
     }
 }
 
-/// update the user's LP token amount
+fn check_min_lp_token_amount(add_lp_token_amount: &Nat, min_lp_token_amount: Option<&Nat>) -> Result<(), String> {
+    match min_lp_token_amount {
+        Some(min_lp_token_amount) if add_lp_token_amount < min_lp_token_amount => Err(format!(
+            "LP token amount {} is less than minimum {}",
+            add_lp_token_amount, min_lp_token_amount
+        )),
+        _ => Ok(()),
+    }
+}
+
+/// update the user's LP token amount and lock
 /// ensure we have the latest state of the LP token before adding the new amounts
-fn update_lp_token(request_id: u64, user_id: u32, lp_token_id: u32, add_lp_token_amount: &Nat, ts: u64) {
+fn update_lp_token(request_id: u64, user_id: u32, lp_token_id: u32, add_lp_token_amount: &Nat, lock: Option<StableLPTokenLock>, ts: u64) {
     request_map::update_status(request_id, StatusCode::UpdateUserLPTokenAmount, None);
 
     // refresh with the latest state if the entry exists
-    match lp_token_map::get_by_token_id(lp_token_id) {
+    match lp_token_map::get_by_token_id_by_user_id(lp_token_id, user_id) {
         Some(lp_token) => {
             // update adding the new deposit amount
             let new_user_lp_token = StableLPToken {
                 amount: nat_add(&lp_token.amount, add_lp_token_amount),
                 ts,
+                lock,
                 ..lp_token.clone()
             };
             lp_token_map::update(&new_user_lp_token);
+            lp_block_map::log_mint(lp_token_id, user_id, add_lp_token_amount, ts);
             request_map::update_status(request_id, StatusCode::UpdateUserLPTokenAmountSuccess, None);
         }
         None => {
             // new entry
-            let new_user_lp_token = StableLPToken::new(user_id, lp_token_id, add_lp_token_amount.clone(), ts);
+            let new_user_lp_token = StableLPToken {
+                lock,
+                ..StableLPToken::new(user_id, lp_token_id, add_lp_token_amount.clone(), ts)
+            };
             match lp_token_map::insert(&new_user_lp_token) {
-                Ok(_) => request_map::update_status(request_id, StatusCode::UpdateUserLPTokenAmountSuccess, None),
+                Ok(_) => {
+                    lp_block_map::log_mint(lp_token_id, user_id, add_lp_token_amount, ts);
+                    request_map::update_status(request_id, StatusCode::UpdateUserLPTokenAmountSuccess, None)
+                }
                 Err(e) => request_map::update_status(request_id, StatusCode::UpdateUserLPTokenAmountFailed, Some(&e)),
             };
         }
@@ -405,7 +506,7 @@ fn update_lp_token(request_id: u64, user_id: u32, lp_token_id: u32, add_lp_token
 }
 
 #[allow(clippy::too_many_arguments)]
-async fn return_tokens(
+pub async fn return_tokens(
     request_id: u64,
     user_id: u32,
     to_principal_id: &Account,
@@ -454,7 +555,7 @@ async fn return_tokens(
 }
 
 #[allow(clippy::too_many_arguments)]
-async fn return_token(
+pub async fn return_token(
     request_id: u64,
     user_id: u32,
     to_principal_id: &Account,
@@ -1,6 +1,7 @@
 #[allow(clippy::module_inception)]
 pub mod add_liquidity;
 pub mod add_liquidity_args;
+pub mod add_liquidity_deposit;
 pub mod add_liquidity_reply;
 pub mod add_liquidity_reply_helpers;
 pub mod add_liquidity_transfer;
@@ -7,6 +7,8 @@ use crate::helpers::nat_helpers::{nat_add, nat_divide, nat_is_zero, nat_multiply
 use crate::ic::guards::not_in_maintenance_mode;
 use crate::stable_lp_token::lp_token_map;
 use crate::stable_pool::pool_map;
+use crate::stable_pool::pool_type::PoolType;
+use crate::stable_pool::stable_pool::StablePool;
 use crate::stable_token::token::Token;
 
 /// Add liquidity to a pool
@@ -15,119 +17,108 @@ use crate::stable_token::token::Token;
 ///
 /// The output of amount_0 and amount_1 should be passed to add_liquidity() to execute the actual transaction
 /// Also calculate the amount of LP token user will receive
+/// For concentrated pools, the amounts are for a full range position and the LP token amount is the liquidity
 #[query(guard = "not_in_maintenance_mode")]
 fn add_liquidity_amounts(token_0: String, amount: Nat, token_1: String) -> Result<AddLiquidityAmountsReply, String> {
     if let Ok(pool) = pool_map::get_by_tokens(&token_0, &token_1) {
-        // Pool
-        let symbol = pool.symbol();
-        // Token0
-        let token_0 = pool.token_0();
-        let chain_0 = token_0.chain();
-        let address_0 = token_0.address();
-        let symbol_0 = token_0.symbol();
-        let reserve_0 = nat_add(&pool.balance_0, &pool.lp_fee_0);
-        let fee_0 = token_0.fee();
-        // Token1
-        let token_1 = pool.token_1();
-        let chain_1 = token_1.chain();
-        let address_1 = token_1.address();
-        let symbol_1 = token_1.symbol();
-        let reserve_1 = nat_add(&pool.balance_1, &pool.lp_fee_1);
-        let fee_1 = token_1.fee();
-        // LP token
-        let lp_token = pool.lp_token();
-        let lp_token_id = lp_token.token_id();
-        let lp_total_supply = lp_token_map::get_total_supply(lp_token_id);
+        // amount is amount_0 in this case
+        return calculate_amounts(&pool, &amount, true);
+    } else if let Ok(pool) = pool_map::get_by_tokens(&token_1, &token_0) {
+        // amount is amount_1 in this case
+        return calculate_amounts(&pool, &amount, false);
+    }
 
-        if nat_is_zero(&reserve_0) || nat_is_zero(&reserve_1) {
-            Err(format!("Zero balances in pool {}", symbol))?
-        }
+    Err("Pool not found".to_string())
+}
 
-        // amount is amount_0 in this case. calculate amount_1 using amount_0
-        // amount_1 = amount_0 * reserve_1 / reserve_0 - for NAT numbers, we need to multiple first and then divide otherwise we lose precision
-        // convert amount, reserve_0 to token_1 precision
-        let amount_0_in_token_1_decimals = nat_to_decimal_precision(&amount, token_0.decimals(), token_1.decimals());
-        let reserve_0_in_token_1_decimals = nat_to_decimal_precision(&reserve_0, token_0.decimals(), token_1.decimals());
-        let numerator_in_token_1_decimals = nat_multiply(&amount_0_in_token_1_decimals, &reserve_1);
-        let amount_1 = nat_divide(&numerator_in_token_1_decimals, &reserve_0_in_token_1_decimals).ok_or("Invalid amount_1")?;
+/// calculate the amounts to add to the pool given an amount of token_0 (is_token_0) or token_1
+pub fn calculate_amounts(pool: &StablePool, amount: &Nat, is_token_0: bool) -> Result<AddLiquidityAmountsReply, String> {
+    // Pool
+    let symbol = pool.symbol();
+    // Token0
+    let token_0 = pool.token_0();
+    let chain_0 = token_0.chain();
+    let address_0 = token_0.address();
+    let symbol_0 = token_0.symbol();
+    let reserve_0 = nat_add(&pool.balance_0, &pool.lp_fee_0);
+    let fee_0 = token_0.fee();
+    // Token1
+    let token_1 = pool.token_1();
+    let chain_1 = token_1.chain();
+    let address_1 = token_1.address();
+    let symbol_1 = token_1.symbol();
+    let reserve_1 = nat_add(&pool.balance_1, &pool.lp_fee_1);
+    let fee_1 = token_1.fee();
+    // LP token
+    let lp_token = pool.lp_token();
+    let lp_token_id = lp_token.token_id();
+    let lp_total_supply = lp_token_map::get_total_supply(lp_token_id);
 
-        // calculate the amount of LP token user will receive
-        // add_lp_token_amount = lp_total_supply * amount_0 / reserve_0
-        let amount_0_in_lp_token_decimals = nat_to_decimal_precision(&amount, token_0.decimals(), lp_token.decimals());
-        let reserve_0_in_lp_token_decimals = nat_to_decimal_precision(&reserve_0, token_0.decimals(), lp_token.decimals());
-        let numerator_in_lp_token_decimals = nat_multiply(&lp_total_supply, &amount_0_in_lp_token_decimals);
-        let add_lp_token_amount =
-            nat_divide(&numerator_in_lp_token_decimals, &reserve_0_in_lp_token_decimals).ok_or("Invalid LP token amount")?;
+    if nat_is_zero(&reserve_0) || nat_is_zero(&reserve_1) {
+        Err(format!("Zero balances in pool {}", symbol))?
+    }
 
-        return Ok(AddLiquidityAmountsReply {
-            symbol,
-            chain_0,
-            address_0,
-            symbol_0,
-            amount_0: amount,
-            fee_0,
-            chain_1,
-            address_1,
-            symbol_1,
-            amount_1,
-            fee_1,
-            add_lp_token_amount,
-        });
-    } else if let Ok(pool) = pool_map::get_by_tokens(&token_1, &token_0) {
-        let symbol = pool.symbol();
-        // Token0
-        let token_0 = pool.token_0();
-        let chain_0 = token_0.chain();
-        let address_0 = token_0.address();
-        let symbol_0 = token_0.symbol();
-        let reserve_0 = nat_add(&pool.balance_0, &pool.lp_fee_0);
-        let fee_0 = token_0.fee();
-        // Token1
-        let token_1 = pool.token_1();
-        let chain_1 = token_1.chain();
-        let address_1 = token_1.address();
-        let symbol_1 = token_1.symbol();
-        let reserve_1 = nat_add(&pool.balance_1, &pool.lp_fee_1);
-        let fee_1 = token_1.fee();
-        // LP token
-        let lp_token = pool.lp_token();
-        let lp_token_id = lp_token.token_id();
-        let lp_total_supply = lp_token_map::get_total_supply(lp_token_id);
+    let (amount_0, amount_1, add_lp_token_amount) = match &pool.pool_type {
+        PoolType::ConstantProduct | PoolType::StableSwap(_) if is_token_0 => {
+            // calculate amount_1 using amount_0
+            // amount_1 = amount_0 * reserve_1 / reserve_0 - for NAT numbers, we need to multiple first and then divide otherwise we lose precision
+            // convert amount, reserve_0 to token_1 precision
+            let amount_0_in_token_1_decimals = nat_to_decimal_precision(amount, token_0.decimals(), token_1.decimals());
+            let reserve_0_in_token_1_decimals = nat_to_decimal_precision(&reserve_0, token_0.decimals(), token_1.decimals());
+            let numerator_in_token_1_decimals = nat_multiply(&amount_0_in_token_1_decimals, &reserve_1);
+            let amount_1 = nat_divide(&numerator_in_token_1_decimals, &reserve_0_in_token_1_decimals).ok_or("Invalid amount_1")?;
 
-        if nat_is_zero(&reserve_0) || nat_is_zero(&reserve_1) {
-            Err(format!("Zero balances in pool {}", symbol))?
+            // calculate the amount of LP token user will receive
+            // add_lp_token_amount = lp_total_supply * amount_0 / reserve_0
+            let amount_0_in_lp_token_decimals = nat_to_decimal_precision(amount, token_0.decimals(), lp_token.decimals());
+            let reserve_0_in_lp_token_decimals = nat_to_decimal_precision(&reserve_0, token_0.decimals(), lp_token.decimals());
+            let numerator_in_lp_token_decimals = nat_multiply(&lp_total_supply, &amount_0_in_lp_token_decimals);
+            let add_lp_token_amount =
+                nat_divide(&numerator_in_lp_token_decimals, &reserve_0_in_lp_token_decimals).ok_or("Invalid LP token amount")?;
+            (amount.clone(), amount_1, add_lp_token_amount)
         }
+        PoolType::ConstantProduct | PoolType::StableSwap(_) => {
+            // calculate amount_0 using amount_1
+            // amount_0 = amount_1 * reserve_0 / reserve_1
+            // convert amount, reserve_1 to token_0 precision
+            let amount_1_in_token_0_decimals = nat_to_decimal_precision(amount, token_1.decimals(), token_0.decimals());
+            let reserve_1_in_token_0_decimals = nat_to_decimal_precision(&reserve_1, token_1.decimals(), token_0.decimals());
+            let numerator_in_token_0_decimals = nat_multiply(&amount_1_in_token_0_decimals, &reserve_0);
+            let amount_0 = nat_divide(&numerator_in_token_0_decimals, &reserve_1_in_token_0_decimals).ok_or("Invalid amount_0")?;
 
-        // amount is amount_1 in this case. calculate amount_0 using amount_1
-        // amount_0 = amount_1 * reserve_0 / reserve_1
-        // convert amount, reserve_1 to token_0 precision
-        let amount_1_in_token_0_decimals = nat_to_decimal_precision(&amount, token_1.decimals(), token_0.decimals());
-        let reserve_1_in_token_0_decimals = nat_to_decimal_precision(&reserve_1, token_1.decimals(), token_0.decimals());
-        let numerator_in_token_0_decimals = nat_multiply(&amount_1_in_token_0_decimals, &reserve_0);
-        let amount_0 = nat_divide(&numerator_in_token_0_decimals, &reserve_1_in_token_0_decimals).ok_or("Invalid amount_0")?;
-
-        // add_lp_token_amount = lp_total_supply * amount_1 / reserve_1
-        let amount_1_in_lp_token_decimals = nat_to_decimal_precision(&amount, token_1.decimals(), lp_token.decimals());
-        let reserve_1_in_lp_token_decimals = nat_to_decimal_precision(&reserve_1, token_1.decimals(), lp_token.decimals());
-        let numerator_in_lp_token_decimals = nat_multiply(&lp_total_supply, &amount_1_in_lp_token_decimals);
-        let add_lp_token_amount =
-            nat_divide(&numerator_in_lp_token_decimals, &reserve_1_in_lp_token_decimals).ok_or("Invalid LP token amount")?;
-
-        return Ok(AddLiquidityAmountsReply {
-            symbol,
-            chain_0,
-            address_0,
-            symbol_0,
-            amount_0,
-            fee_0,
-            chain_1,
-            address_1,
-            symbol_1,
-            amount_1: amount,
-            fee_1,
-            add_lp_token_amount,
-        });
-    }
+            // add_lp_token_amount = lp_total_supply * amount_1 / reserve_1
+            let amount_1_in_lp_token_decimals = nat_to_decimal_precision(amount, token_1.decimals(), lp_token.decimals());
+            let reserve_1_in_lp_token_decimals = nat_to_decimal_precision(&reserve_1, token_1.decimals(), lp_token.decimals());
+            let numerator_in_lp_token_decimals = nat_multiply(&lp_total_supply, &amount_1_in_lp_token_decimals);
+            let add_lp_token_amount =
+                nat_divide(&numerator_in_lp_token_decimals, &reserve_1_in_lp_token_decimals).ok_or("Invalid LP token amount")?;
+            (amount_0, amount.clone(), add_lp_token_amount)
+        }
+        PoolType::Concentrated(concentrated_pool) => {
+            // amounts for a full range position. add_lp_token_amount is the liquidity of the position
+            let (tick_lower, tick_upper) = (concentrated_pool.min_tick(), concentrated_pool.max_tick());
+            let liquidity = concentrated_pool.liquidity_for_amount(tick_lower, tick_upper, amount, is_token_0);
+            let (amount_0, amount_1) = concentrated_pool.amounts_for_liquidity(tick_lower, tick_upper, &liquidity, true);
+            if is_token_0 {
+                (amount.clone(), amount_1, liquidity)
+            } else {
+                (amount_0, amount.clone(), liquidity)
+            }
+        }
+    };
 
-    Err("Pool not found".to_string())
+    Ok(AddLiquidityAmountsReply {
+        symbol,
+        chain_0,
+        address_0,
+        symbol_0,
+        amount_0,
+        fee_0,
+        chain_1,
+        address_1,
+        symbol_1,
+        amount_1,
+        fee_1,
+        add_lp_token_amount,
+    })
 }
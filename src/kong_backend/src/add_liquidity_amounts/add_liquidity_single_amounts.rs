@@ -0,0 +1,51 @@
+use candid::Nat;
+use ic_cdk::query;
+
+use super::add_liquidity_amounts::calculate_amounts as calculate_add_liquidity_amounts;
+use super::add_liquidity_single_amounts_reply::AddLiquiditySingleAmountsReply;
+
+use crate::add_liquidity_single::add_liquidity_single::{calculate_amounts, is_token_0};
+use crate::helpers::nat_helpers::{nat_subtract, nat_zero};
+use crate::ic::{get_time::get_time, guards::not_in_maintenance_mode};
+use crate::stable_pool::pool_map;
+use crate::stable_token::token::Token;
+use crate::swap::update_liquidity_pool::apply_swap;
+
+/// Add liquidity to a pool with a single token
+///
+/// Given pay_amount of pay_token, calculate the amount swapped through the pool for the other token
+/// and the amounts of add_liquidity_amounts() added to the pool after the swap
+///
+/// The arguments should be passed to add_liquidity_single() to execute the actual transaction
+#[query(guard = "not_in_maintenance_mode")]
+fn add_liquidity_single_amounts(
+    token_0: String,
+    token_1: String,
+    pay_token: String,
+    pay_amount: Nat,
+) -> Result<AddLiquiditySingleAmountsReply, String> {
+    let mut pool = pool_map::get_by_tokens(&token_0, &token_1)?;
+    let pay_token_0 = is_token_0(&pool, &pay_token)?;
+    let pay_token = if pay_token_0 { pool.token_0() } else { pool.token_1() };
+
+    let (swap_amount, swap_receive_amount, slippage, swaps) = calculate_amounts(&pool, pay_token_0, &pay_amount, None)?;
+
+    // amounts to add to the pool after the swap
+    let ts = get_time();
+    for swap in &swaps {
+        apply_swap(&mut pool, swap, None, ts)?;
+    }
+    let rest_amount = nat_subtract(&pay_amount, &swap_amount).unwrap_or(nat_zero());
+    let add_liquidity = calculate_add_liquidity_amounts(&pool, &rest_amount, pay_token_0)?;
+
+    Ok(AddLiquiditySingleAmountsReply {
+        pay_chain: pay_token.chain(),
+        pay_address: pay_token.address(),
+        pay_symbol: pay_token.symbol(),
+        pay_amount,
+        swap_amount,
+        swap_receive_amount,
+        slippage,
+        add_liquidity,
+    })
+}
@@ -0,0 +1,16 @@
+use candid::{CandidType, Nat};
+use serde::{Deserialize, Serialize};
+
+use super::add_liquidity_amounts_reply::AddLiquidityAmountsReply;
+
+#[derive(CandidType, Debug, Clone, Serialize, Deserialize)]
+pub struct AddLiquiditySingleAmountsReply {
+    pub pay_chain: String,
+    pub pay_address: String,
+    pub pay_symbol: String,
+    pub pay_amount: Nat,
+    pub swap_amount: Nat,         // amount of pay token swapped for the other token
+    pub swap_receive_amount: Nat, // amount of the other token received from the swap
+    pub slippage: f64,
+    pub add_liquidity: AddLiquidityAmountsReply, // amounts added to the pool after the swap
+}
@@ -1,3 +1,5 @@
 #[allow(clippy::module_inception)]
 pub mod add_liquidity_amounts;
 pub mod add_liquidity_amounts_reply;
+pub mod add_liquidity_single_amounts;
+pub mod add_liquidity_single_amounts_reply;
@@ -0,0 +1,411 @@
+use candid::Nat;
+use ic_cdk::update;
+
+use super::add_liquidity_single_args::AddLiquiditySingleArgs;
+
+use crate::add_liquidity::add_liquidity::TokenIndex;
+use crate::add_liquidity::add_liquidity_args::AddLiquidityArgs;
+use crate::add_liquidity::add_liquidity_reply::AddLiquidityReply;
+use crate::add_liquidity::add_liquidity_reply_helpers::{to_add_liquidity_reply, to_add_liquidity_reply_failed};
+use crate::add_liquidity::add_liquidity_transfer_from::{
+    archive_to_kong_data, return_token, return_tokens, transfer_from_token, update_liquidity_pool,
+};
+use crate::helpers::nat_helpers::{nat_add, nat_divide, nat_is_zero, nat_multiply, nat_sqrt, nat_subtract, nat_zero};
+use crate::ic::{get_time::get_time, guards::not_in_maintenance_mode, id::caller_id};
+use crate::stable_kong_settings::kong_settings_map;
+use crate::stable_lp_token::lp_token_lock;
+use crate::stable_pool::{pool_map, stable_pool::StablePool};
+use crate::stable_request::{reply::Reply, request::Request, request_map, stable_request::StableRequest, status::StatusCode};
+use crate::stable_token::{token::Token, token_map};
+use crate::stable_tx::{add_liquidity_tx::AddLiquidityTx, stable_tx::StableTx, tx_map};
+use crate::stable_user::user_map;
+use crate::swap::swap_amounts::pool_swap_amounts;
+use crate::swap::swap_calc::SwapCalc;
+use crate::swap::update_liquidity_pool::update_pools;
+
+/// Add liquidity to a pool with a single token (zap in)
+///
+/// - before calling add_liquidity_single, the user must create an icrc2_approve_transaction for pay_amount + gas fee of
+///   pay_token to allow the backend canister to icrc2_transfer_from
+/// - part of pay_amount is swapped through the pool for the other token so the rest of pay_amount and the received tokens
+///   are at the pool ratio after the swap. both are then added to the pool as with add_liquidity()
+/// - any amount left over from rounding is returned to the user
+///
+/// Arguments: AddLiquiditySingleArgs
+///  token_0, token_1: tokens of the pool
+///  pay_token: token_0 or token_1, the token paid by the user
+///  pay_amount: amount of pay_token
+///  max_slippage: max slippage of the internal swap, default_max_slippage if not specified
+///  lock_secs: optional lock period of the LP tokens
+///
+/// Returns: AddLiquidityReply, with the internal swap in swap_txs
+#[update(guard = "not_in_maintenance_mode")]
+pub async fn add_liquidity_single(args: AddLiquiditySingleArgs) -> Result<AddLiquidityReply, String> {
+    let (user_id, pool, pay_token_0, max_slippage) = check_arguments(&args).await?;
+    let ts = get_time();
+    let request_id = request_map::insert(&StableRequest::new(user_id, &Request::AddLiquiditySingle(args.clone()), ts));
+
+    let result = match process_add_liquidity_single(
+        request_id,
+        user_id,
+        &pool,
+        pay_token_0,
+        &args.pay_amount,
+        max_slippage,
+        args.lock_secs,
+        ts,
+    )
+    .await
+    {
+        Ok(reply) => {
+            request_map::update_status(request_id, StatusCode::Success, None);
+            Ok(reply)
+        }
+        Err(e) => {
+            request_map::update_status(request_id, StatusCode::Failed, None);
+            Err(e)
+        }
+    };
+    _ = archive_to_kong_data(request_id);
+
+    result
+}
+
+/// add liquidity with a single token asynchronously. same as add_liquidity_single() but returns the request_id immediately
+#[update(guard = "not_in_maintenance_mode")]
+pub async fn add_liquidity_single_async(args: AddLiquiditySingleArgs) -> Result<u64, String> {
+    let (user_id, pool, pay_token_0, max_slippage) = check_arguments(&args).await?;
+    let ts = get_time();
+    let request_id = request_map::insert(&StableRequest::new(user_id, &Request::AddLiquiditySingle(args.clone()), ts));
+
+    ic_cdk::spawn(async move {
+        match process_add_liquidity_single(
+            request_id,
+            user_id,
+            &pool,
+            pay_token_0,
+            &args.pay_amount,
+            max_slippage,
+            args.lock_secs,
+            ts,
+        )
+        .await
+        {
+            Ok(_) => request_map::update_status(request_id, StatusCode::Success, None),
+            Err(_) => request_map::update_status(request_id, StatusCode::Failed, None),
+        };
+        _ = archive_to_kong_data(request_id);
+    });
+
+    Ok(request_id)
+}
+
+/// returns (user_id, pool, pay_token_0, max_slippage)
+async fn check_arguments(args: &AddLiquiditySingleArgs) -> Result<(u32, StablePool, bool, f64), String> {
+    if nat_is_zero(&args.pay_amount) {
+        Err("Invalid zero amount".to_string())?
+    }
+
+    if let Some(lock_secs) = args.lock_secs {
+        lp_token_lock::check_lock_secs(lock_secs)?;
+    }
+
+    let pool = pool_map::get_by_tokens_and_fee_tier(&args.token_0, &args.token_1, args.fee_tier)?;
+    let pay_token_0 = is_token_0(&pool, &args.pay_token)?;
+
+    let token_0 = pool.token_0();
+    if token_0.is_removed() {
+        Err("Token_0 is suspended or removed".to_string())?
+    }
+    let token_1 = pool.token_1();
+    if token_1.is_removed() {
+        Err("Token_1 is suspended or removed".to_string())?
+    }
+    let pay_token = if pay_token_0 { token_0 } else { token_1 };
+    if !pay_token.is_icrc2() {
+        Err("Pay token must support ICRC2".to_string())?
+    }
+
+    // make sure the swap is valid with the current state of the pool
+    let max_slippage = args.max_slippage.unwrap_or(kong_settings_map::get().default_max_slippage);
+    calculate_amounts(&pool, pay_token_0, &args.pay_amount, Some(max_slippage))?;
+
+    // make sure user is registered, if not create a new user
+    let user_id = user_map::insert(None)?;
+
+    Ok((user_id, pool, pay_token_0, max_slippage))
+}
+
+/// returns true if token is token_0 of the pool and false if token_1
+/// only pools with LP tokens are supported
+pub fn is_token_0(pool: &StablePool, token: &str) -> Result<bool, String> {
+    if !pool.pool_type.has_lp_token() {
+        Err("Single token liquidity not supported for concentrated pools".to_string())?
+    }
+    let token_id = token_map::get_by_token(token)?.token_id();
+    if token_id == pool.token_id_0 {
+        Ok(true)
+    } else if token_id == pool.token_id_1 {
+        Ok(false)
+    } else {
+        Err(format!("Token must be {} or {}", pool.symbol_0(), pool.symbol_1()))
+    }
+}
+
+/// calculate the internal swap of a single token add liquidity
+/// max_slippage - if None, slippage is not checked
+///
+/// returns (swap_amount, receive_amount, slippage, swaps). swap_amount of the pay token is swapped for receive_amount of the other token
+pub fn calculate_amounts(
+    pool: &StablePool,
+    pay_token_0: bool,
+    pay_amount: &Nat,
+    max_slippage: Option<f64>,
+) -> Result<(Nat, Nat, f64, Vec<SwapCalc>), String> {
+    let (pay_reserve, receive_reserve) = if pay_token_0 {
+        (nat_add(&pool.balance_0, &pool.lp_fee_0), nat_add(&pool.balance_1, &pool.lp_fee_1))
+    } else {
+        (nat_add(&pool.balance_1, &pool.lp_fee_1), nat_add(&pool.balance_0, &pool.lp_fee_0))
+    };
+    if nat_is_zero(&pay_reserve) || nat_is_zero(&receive_reserve) {
+        Err(format!("Zero balances in pool {}", pool.symbol()))?
+    }
+
+    let lp_fee_bps = pool.swap_lp_fee_bps(pay_token_0, get_time());
+    let swap_amount = optimal_swap_amount(&pay_reserve, pay_amount, lp_fee_bps);
+    if nat_is_zero(&swap_amount) {
+        Err("Pay amount is too small".to_string())?
+    }
+    let (receive_amount, _, _, slippage, swaps) = pool_swap_amounts(pool, pay_token_0, &swap_amount)?;
+    if nat_is_zero(&receive_amount) {
+        Err("Receive amount is zero".to_string())?
+    }
+    if let Some(max_slippage) = max_slippage {
+        if slippage > max_slippage {
+            Err(format!("Slippage exceeded. Swap of {} has {}% slippage", pool.symbol(), slippage))?
+        }
+    }
+
+    Ok((swap_amount, receive_amount, slippage, swaps))
+}
+
+/// amount of the pay token to swap so the rest of amount and the received tokens are at the pool ratio after the swap
+/// for a constant product pool with LP fee f, solving (amount - s) / (reserve + s) = receive / (reserve_out - receive) gives
+/// s = (sqrt(reserve^2 * (2 - f)^2 + 4 * (1 - f) * amount * reserve) - reserve * (2 - f)) / (2 * (1 - f))
+/// StableSwap pools use the same approximation, the amount left over is returned to the user
+pub fn optimal_swap_amount(reserve: &Nat, amount: &Nat, lp_fee_bps: u8) -> Nat {
+    // (1 - f) and (2 - f) scaled by 10_000
+    let one_minus_fee = Nat::from(10_000_u32 - lp_fee_bps as u32);
+    let two_minus_fee = nat_add(&Nat::from(10_000_u32), &one_minus_fee);
+    let reserve_two_minus_fee = nat_multiply(reserve, &two_minus_fee);
+    let discriminant = nat_add(
+        &nat_multiply(&reserve_two_minus_fee, &reserve_two_minus_fee),
+        &nat_multiply(
+            &nat_multiply(&Nat::from(40_000_u32), &one_minus_fee),
+            &nat_multiply(amount, reserve),
+        ),
+    );
+    let numerator = nat_subtract(&nat_sqrt(&discriminant), &reserve_two_minus_fee).unwrap_or(nat_zero());
+    let swap_amount = nat_divide(&numerator, &nat_multiply(&Nat::from(2_u8), &one_minus_fee)).unwrap_or(nat_zero());
+    std::cmp::min(swap_amount, amount.clone())
+}
+
+#[allow(clippy::too_many_arguments)]
+async fn process_add_liquidity_single(
+    request_id: u64,
+    user_id: u32,
+    pool: &StablePool,
+    pay_token_0: bool,
+    pay_amount: &Nat,
+    max_slippage: f64,
+    lock_secs: Option<u64>,
+    ts: u64,
+) -> Result<AddLiquidityReply, String> {
+    let (pay_token_index, pay_token) = if pay_token_0 {
+        (TokenIndex::Token0, pool.token_0())
+    } else {
+        (TokenIndex::Token1, pool.token_1())
+    };
+
+    let caller_id = caller_id();
+    let kong_backend = kong_settings_map::get().kong_backend;
+    let mut transfer_ids = Vec::new();
+
+    request_map::update_status(request_id, StatusCode::Start, None);
+
+    // transfer_from pay token. if this fails, nothing to return so just return the error
+    transfer_from_token(
+        request_id,
+        &caller_id,
+        &pay_token_index,
+        &pay_token,
+        pay_amount,
+        &kong_backend,
+        &mut transfer_ids,
+        ts,
+    )
+    .await
+    .map_err(|e| format!("Pay token transfer_from failed. {}", e))?;
+
+    // swap part of the pay token for the other token with the latest state of the pool. if this fails, return the pay token
+    let (swap_amount, receive_amount, swaps) = match swap_pay_token(request_id, pool.pool_id, pay_token_0, pay_amount, max_slippage) {
+        Ok(swap) => swap,
+        Err(e) => {
+            let (amount_0, amount_1) = if pay_token_0 {
+                (Some(pay_amount), None)
+            } else {
+                (None, Some(pay_amount))
+            };
+            return_tokens(request_id, user_id, &caller_id, pool, amount_0, amount_1, &mut transfer_ids, ts).await;
+            return Err(format!("Req #{} failed. {}", request_id, e));
+        }
+    };
+
+    let rest_amount = nat_subtract(pay_amount, &swap_amount).unwrap_or(nat_zero());
+    let (add_amount_0, add_amount_1) = if pay_token_0 {
+        (rest_amount, receive_amount)
+    } else {
+        (receive_amount, rest_amount)
+    };
+    let add_liquidity_args = AddLiquidityArgs {
+        token_0: pool.token_0().address_with_chain(),
+        amount_0: add_amount_0.clone(),
+        tx_id_0: None,
+        token_1: pool.token_1().address_with_chain(),
+        amount_1: add_amount_1.clone(),
+        tx_id_1: None,
+        min_price: None,
+        max_price: None,
+        lock_secs,
+        use_deposit: None,
+        fee_tier: pool.fee_tier,
+        min_lp_token_amount: None,
+        deadline: None,
+    };
+
+    // add the rest of the pay token and the received token to the pool. if this fails, return both tokens
+    let (pool, amount_0, amount_1, add_lp_token_amount) =
+        match update_liquidity_pool(request_id, user_id, pool, &add_amount_0, &add_amount_1, &add_liquidity_args, ts) {
+            Ok(add_liquidity) => add_liquidity,
+            Err(e) => {
+                return_tokens(
+                    request_id,
+                    user_id,
+                    &caller_id,
+                    pool,
+                    Some(&add_amount_0),
+                    Some(&add_amount_1),
+                    &mut transfer_ids,
+                    ts,
+                )
+                .await;
+                return Err(format!("Req #{} failed. {}", request_id, e));
+            }
+        };
+
+    // return any amounts left over from rounding, if more than the gas fee
+    let mut claim_ids = Vec::new();
+    let token_0 = pool.token_0();
+    let unused_amount_0 = nat_subtract(&add_amount_0, &amount_0).unwrap_or(nat_zero());
+    if unused_amount_0 > token_0.fee() {
+        return_token(
+            request_id,
+            user_id,
+            &caller_id,
+            &TokenIndex::Token0,
+            &token_0,
+            &unused_amount_0,
+            &mut transfer_ids,
+            &mut claim_ids,
+            ts,
+        )
+        .await;
+    }
+    let token_1 = pool.token_1();
+    let unused_amount_1 = nat_subtract(&add_amount_1, &amount_1).unwrap_or(nat_zero());
+    if unused_amount_1 > token_1.fee() {
+        return_token(
+            request_id,
+            user_id,
+            &caller_id,
+            &TokenIndex::Token1,
+            &token_1,
+            &unused_amount_1,
+            &mut transfer_ids,
+            &mut claim_ids,
+            ts,
+        )
+        .await;
+    }
+
+    // succcesful, add tx with the internal swap and update request with reply
+    let add_liquidity_tx = AddLiquidityTx {
+        swap_txs: swaps,
+        ..AddLiquidityTx::new_success(
+            pool.pool_id,
+            user_id,
+            request_id,
+            &amount_0,
+            &amount_1,
+            &add_lp_token_amount,
+            &transfer_ids,
+            &claim_ids,
+            ts,
+        )
+    };
+    let tx_id = tx_map::insert(&StableTx::AddLiquidity(add_liquidity_tx));
+    let reply = match tx_map::get_by_user_and_token_id(Some(tx_id), None, None, None).first() {
+        Some(StableTx::AddLiquidity(add_liquidity_tx)) => to_add_liquidity_reply(add_liquidity_tx),
+        _ => to_add_liquidity_reply_failed(pool.pool_id, request_id, &transfer_ids, &claim_ids, ts),
+    };
+    request_map::update_reply(request_id, Reply::AddLiquidity(reply.clone()));
+
+    Ok(reply)
+}
+
+/// swap part of pay_amount through the pool for the other token
+/// returns (swap_amount, receive_amount, swaps)
+fn swap_pay_token(
+    request_id: u64,
+    pool_id: u32,
+    pay_token_0: bool,
+    pay_amount: &Nat,
+    max_slippage: f64,
+) -> Result<(Nat, Nat, Vec<SwapCalc>), String> {
+    request_map::update_status(request_id, StatusCode::SwapSingleToken, None);
+
+    let result = pool_map::get_by_pool_id(pool_id)
+        .ok_or(format!("Pool #{} not found", pool_id))
+        .and_then(|pool| calculate_amounts(&pool, pay_token_0, pay_amount, Some(max_slippage)))
+        .and_then(|(swap_amount, receive_amount, _, swaps)| {
+            update_pools(request_id, &swaps)?;
+            Ok((swap_amount, receive_amount, swaps))
+        });
+    match result {
+        Ok(_) => request_map::update_status(request_id, StatusCode::SwapSingleTokenSuccess, None),
+        Err(ref e) => request_map::update_status(request_id, StatusCode::SwapSingleTokenFailed, Some(e)),
+    };
+
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_optimal_swap_amount() {
+        // no fee: s = sqrt(reserve^2 + amount * reserve) - reserve
+        assert_eq!(
+            optimal_swap_amount(&Nat::from(1_000_000_u64), &Nat::from(1_000_u64), 0),
+            Nat::from(499_u64)
+        );
+        // a fee swaps slightly more to make up for the fee
+        assert_eq!(
+            optimal_swap_amount(&Nat::from(1_000_000_000_u64), &Nat::from(1_000_000_u64), 30),
+            Nat::from(500_626_u64)
+        );
+        // dust amounts round down to no swap
+        assert_eq!(optimal_swap_amount(&Nat::from(1_u64), &Nat::from(1_u64), 30), Nat::from(0_u64));
+    }
+}
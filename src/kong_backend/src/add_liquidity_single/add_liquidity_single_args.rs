@@ -0,0 +1,15 @@
+use candid::{CandidType, Nat};
+use serde::{Deserialize, Serialize};
+
+/// Data structure for the arguments of the `add_liquidity_single` function.
+/// Used in StableRequest
+#[derive(CandidType, Debug, Clone, Serialize, Deserialize)]
+pub struct AddLiquiditySingleArgs {
+    pub token_0: String,
+    pub token_1: String,
+    pub pay_token: String, // token_0 or token_1. part of pay_amount is swapped through the pool for the other token
+    pub pay_amount: Nat,
+    pub max_slippage: Option<f64>, // max slippage of the internal swap
+    pub lock_secs: Option<u64>,    // lock the LP tokens for lock_secs to boost their share of the LP fees
+    pub fee_tier: Option<u8>,      // fee tier of the pool. None for the first pool of the pair
+}
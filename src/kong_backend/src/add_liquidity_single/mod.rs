@@ -0,0 +1,3 @@
+#[allow(clippy::module_inception)]
+pub mod add_liquidity_single;
+pub mod add_liquidity_single_args;
@@ -6,26 +6,32 @@ use super::add_pool_args::AddPoolArgs;
 use super::add_pool_reply::AddPoolReply;
 use super::add_pool_reply_helpers::{to_add_pool_reply, to_add_pool_reply_failed};
 
-use crate::add_token::add_token::{add_ic_token, add_lp_token};
+use crate::add_token::add_token::{add_lp_token, insert_ic_token, vet_ic_token};
 use crate::chains::chains::IC_CHAIN;
 use crate::helpers::nat_helpers::{nat_add, nat_is_zero, nat_multiply, nat_sqrt, nat_subtract, nat_to_decimal_precision, nat_zero};
 use crate::ic::{
     address::Address,
     ckusdt::is_ckusdt,
     get_time::get_time,
-    guards::not_in_maintenance_mode,
+    guards::{caller_is_kingkong, not_in_maintenance_mode},
     icp::is_icp,
     id::caller_id,
     transfer::{icrc1_transfer, icrc2_transfer_from},
     verify_transfer::verify_transfer,
 };
+use crate::lp_ledger::lp_ledger_canister;
 use crate::stable_claim::{claim_map, stable_claim::StableClaim};
 use crate::stable_kong_settings::kong_settings_map;
+use crate::stable_lp_token::lp_block_map;
 use crate::stable_lp_token::lp_token_map;
 use crate::stable_lp_token::stable_lp_token::StableLPToken;
+use crate::stable_pool::concentrated_pool::ConcentratedPool;
 use crate::stable_pool::pool_map;
+use crate::stable_pool::pool_type::PoolType;
 use crate::stable_pool::stable_pool::StablePool;
+use crate::stable_pool::stable_swap_pool::StableSwapPool;
 use crate::stable_request::{reply::Reply, request::Request, request_map, stable_request::StableRequest, status::StatusCode};
+use crate::stable_token::ic_token::{ICToken, ListingDeposit};
 use crate::stable_token::lp_token::LP_DECIMALS;
 use crate::stable_token::stable_token::StableToken;
 use crate::stable_token::token;
@@ -54,8 +60,21 @@ enum TokenIndex {
 /// * `Err(String)` - An error message if the operation fails.
 #[update(guard = "not_in_maintenance_mode")]
 pub async fn add_pool(args: AddPoolArgs) -> Result<AddPoolReply, String> {
-    let (user_id, token_0, add_amount_0, tx_id_0, token_1, add_amount_1, tx_id_1, lp_fee_bps, kong_fee_bps, add_lp_token_amount) =
-        check_arguments(&args).await?;
+    let (
+        user_id,
+        token_0,
+        is_new_token_0,
+        add_amount_0,
+        tx_id_0,
+        token_1,
+        add_amount_1,
+        tx_id_1,
+        lp_fee_bps,
+        fee_tier,
+        kong_fee_bps,
+        pool_type,
+        add_lp_token_amount,
+    ) = check_arguments(&args).await?;
     let ts = get_time();
     let request_id = request_map::insert(&StableRequest::new(user_id, &Request::AddPool(args), ts));
 
@@ -63,13 +82,16 @@ pub async fn add_pool(args: AddPoolArgs) -> Result<AddPoolReply, String> {
         request_id,
         user_id,
         &token_0,
+        is_new_token_0,
         &add_amount_0,
         tx_id_0.as_ref(),
         &token_1,
         &add_amount_1,
         tx_id_1.as_ref(),
         lp_fee_bps,
+        fee_tier,
         kong_fee_bps,
+        &pool_type,
         &add_lp_token_amount,
         ts,
     )
@@ -77,6 +99,10 @@ pub async fn add_pool(args: AddPoolArgs) -> Result<AddPoolReply, String> {
     {
         Ok(reply) => {
             request_map::update_status(request_id, StatusCode::Success, None);
+            // the LP token of the new pool gets its own ICRC-1 ledger
+            if let Some(pool) = pool_map::get_by_pool_id(reply.pool_id).filter(|pool| pool.pool_type.has_lp_token()) {
+                lp_ledger_canister::spawn_create_ledger(pool.lp_token_id);
+            }
             Ok(reply)
         }
         Err(e) => {
@@ -89,7 +115,7 @@ pub async fn add_pool(args: AddPoolArgs) -> Result<AddPoolReply, String> {
     result
 }
 
-/// Check the arguments are valid, create new token_0 if it does not exist and calculate the amounts to be added to the pool
+/// Check the arguments are valid, vet new token_0 if it does not exist and calculate the amounts to be added to the pool
 ///
 /// # Arguments
 ///
@@ -97,21 +123,42 @@ pub async fn add_pool(args: AddPoolArgs) -> Result<AddPoolReply, String> {
 ///
 /// # Returns
 ///
-/// * `Ok((user_id, token_0, amount_0, tx_id_0, token_1, add_amount_1, tx_id_1, lp_fee_bps))`
+/// * `Ok((user_id, token_0, is_new_token_0, amount_0, tx_id_0, token_1, add_amount_1, tx_id_1, lp_fee_bps, fee_tier, kong_fee_bps, pool_type, add_lp_token_amount))`
 /// *   `user_id` - The user id.
 /// *   `token_0` - The first token.
+/// *   `is_new_token_0` - The first token is new and needs to be listed. It is vetted but not yet in the token map.
 /// *   `amount_0` - The amount of the first token.
 /// *   `tx_id_0` - The transaction id of the first token for icrc1_transfer.
 /// *   `token_1` - The second token.
 /// *   `add_amount_1` - The amount of the second token.
 /// *   `tx_id_1` - The transaction id of the second token for icrc1_transfer.
 /// *   `lp_fee_bps` - The liquidity pool fee basis points.
+/// *   `fee_tier` - The fee tier if the pair already has a pool, None for the first pool of the pair.
 /// *   `kong_fee_bps` - The liquidity pool Kong fee basis points.
-/// *   `add_lp_token_amount` - The amount of LP token to be added to the pool.
+/// *   `pool_type` - The type of the pool, concentrated liquidity pool if tick_spacing is specified, StableSwap pool if amp is specified.
+/// *   `add_lp_token_amount` - The amount of LP token to be added to the pool, or the liquidity of the position for concentrated pools.
 /// * `Err(String)` - An error message if the operation fails.
+#[allow(clippy::type_complexity)]
 async fn check_arguments(
     args: &AddPoolArgs,
-) -> Result<(u32, StableToken, Nat, Option<Nat>, StableToken, Nat, Option<Nat>, u8, u8, Nat), String> {
+) -> Result<
+    (
+        u32,
+        StableToken,
+        bool,
+        Nat,
+        Option<Nat>,
+        StableToken,
+        Nat,
+        Option<Nat>,
+        u8,
+        Option<u8>,
+        u8,
+        PoolType,
+        Nat,
+    ),
+    String,
+> {
     if nat_is_zero(&args.amount_0) || nat_is_zero(&args.amount_1) {
         Err("Invalid zero amounts".to_string())?
     }
@@ -154,31 +201,59 @@ async fn check_arguments(
         ))?,
     };
 
-    // token_0, check if it exists already or needs to be added
-    // leave token_0 check latest as possible as token will be added to the system
-    let token_0 = match token_map::get_by_token(&args.token_0) {
-        Ok(token) => token, // token_0 exists already
+    // token_0, check if it exists already or needs to be listed
+    // new token_0 is only vetted here. it is listed in process_add_pool() once the listing deposit is paid
+    let (token_0, is_new_token_0) = match token_map::get_by_token(&args.token_0) {
+        Ok(token) if token.is_removed() => Err(format!("Token {} is suspended", token.symbol()))?,
+        Ok(token) => (token, false), // token_0 exists already
         Err(_) => {
-            // token_0 needs to be added. Only IC tokens of format IC.CanisterId supported
+            // token_0 needs to be listed. Only IC tokens of format IC.CanisterId supported
             match token_map::get_chain(&args.token_0) {
-                Some(chain) if chain == IC_CHAIN => add_ic_token(&args.token_0).await?,
+                Some(chain) if chain == IC_CHAIN => (StableToken::IC(vet_ic_token(&args.token_0).await?), true),
                 Some(_) | None => Err("Token_0 chain not supported")?,
             }
         }
     };
 
+    // a pair can have a pool for each fee tier, where lp_fee_bps is the fee tier
+    // the first pool of the pair has no fee tier, so its symbol and LP token are Symbol_Symbol
+    let fee_tier = if pool_map::exists(&token_0, &token_1) {
+        // additional fee tiers must be in the same token order as the first pool and not duplicate a fee tier
+        if pool_map::get_by_token_ids(token_0.token_id(), token_1.token_id()).is_none()
+            || pool_map::get_by_token_ids_and_fee_tier(token_0.token_id(), token_1.token_id(), Some(lp_fee_bps)).is_some()
+        {
+            Err(format!(
+                "Pool {}{} already exists",
+                pool_map::symbol(&token_0, &token_1),
+                token::fee_tier_suffix(Some(lp_fee_bps))
+            ))?
+        }
+        Some(lp_fee_bps)
+    } else {
+        None
+    };
+
     // make sure LP token does not already exist
-    let lp_token_address = token::address(&token_0, &token_1);
+    let lp_token_address = format!("{}{}", token::address(&token_0, &token_1), token::fee_tier_suffix(fee_tier));
     if token_map::exists(&lp_token_address) {
-        Err(format!("LP token {} already exists", token::symbol(&token_0, &token_1)))?
+        Err(format!(
+            "LP token {}{} already exists",
+            token::symbol(&token_0, &token_1),
+            token::fee_tier_suffix(fee_tier)
+        ))?
     }
 
-    // make sure pool does not already exist
-    if pool_map::exists(&token_0, &token_1) {
-        Err(format!("Pool {} already exists", pool_map::symbol(&token_0, &token_1)))?
-    }
+    // concentrated liquidity pool if tick_spacing is specified, the initial price is amount_1 / amount_0
+    // StableSwap pool if amp is specified
+    let pool_type = match (args.tick_spacing, args.amp) {
+        (Some(_), Some(_)) => Err("Only one of tick_spacing or amp can be specified")?,
+        (Some(tick_spacing), None) => PoolType::Concentrated(ConcentratedPool::new(tick_spacing, &args.amount_0, &args.amount_1)?),
+        (None, Some(amp)) => PoolType::StableSwap(StableSwapPool::new(amp)?),
+        (None, None) => PoolType::ConstantProduct,
+    };
 
-    let (add_amount_0, add_amount_1, add_lp_token_amount) = calculate_amounts(&token_0, &args.amount_0, &token_1, &args.amount_1)?;
+    let (add_amount_0, add_amount_1, add_lp_token_amount) =
+        calculate_amounts(&token_0, &args.amount_0, &token_1, &args.amount_1, &pool_type)?;
 
     // make sure user is registered, if not create a new user
     let user_id = user_map::insert(None)?;
@@ -186,18 +261,37 @@ async fn check_arguments(
     Ok((
         user_id,
         token_0,
+        is_new_token_0,
         add_amount_0,
         tx_id_0,
         token_1,
         add_amount_1,
         tx_id_1,
         lp_fee_bps,
+        fee_tier,
         kong_fee_bps,
+        pool_type,
         add_lp_token_amount,
     ))
 }
 
-pub fn calculate_amounts(token_0: &StableToken, amount_0: &Nat, token_1: &StableToken, amount_1: &Nat) -> Result<(Nat, Nat, Nat), String> {
+pub fn calculate_amounts(
+    token_0: &StableToken,
+    amount_0: &Nat,
+    token_1: &StableToken,
+    amount_1: &Nat,
+    pool_type: &PoolType,
+) -> Result<(Nat, Nat, Nat), String> {
+    if let PoolType::Concentrated(concentrated_pool) = pool_type {
+        // initial position is full range with the liquidity of amount_0 and amount_1
+        let liquidity =
+            concentrated_pool.liquidity_for_amounts(concentrated_pool.min_tick(), concentrated_pool.max_tick(), amount_0, amount_1);
+        if nat_is_zero(&liquidity) {
+            Err("Insufficient amounts for liquidity")?
+        }
+        return Ok((amount_0.clone(), amount_1.clone(), liquidity));
+    }
+
     // new pool as there are no balances - take user amounts as initial ratio
     // initialize LP tokens as sqrt(amount_0 * amount_1)
     // convert the amounts to the same decimal precision as the LP token
@@ -213,13 +307,16 @@ async fn process_add_pool(
     request_id: u64,
     user_id: u32,
     token_0: &StableToken,
+    is_new_token_0: bool,
     amount_0: &Nat,
     tx_id_0: Option<&Nat>,
     token_1: &StableToken,
     amount_1: &Nat,
     tx_id_1: Option<&Nat>,
     lp_fee_bps: u8,
+    fee_tier: Option<u8>,
     kong_fee_bps: u8,
+    pool_type: &PoolType,
     add_lp_token_amount: &Nat,
     ts: u64,
 ) -> Result<AddPoolReply, String> {
@@ -229,6 +326,33 @@ async fn process_add_pool(
 
     request_map::update_status(request_id, StatusCode::Start, None);
 
+    // list new token_0 before any of the pool amounts are transferred
+    let listed_token_0;
+    let token_0 = if is_new_token_0 {
+        listed_token_0 = match list_token(request_id, user_id, &caller_id, token_0, &kong_backend, &mut transfer_ids, ts).await {
+            Ok(token) => token,
+            Err((e, claim_ids)) => {
+                let reply = to_add_pool_reply_failed(
+                    request_id,
+                    &token_0.chain(),
+                    &token_0.address(),
+                    &token_0.symbol(),
+                    &token_1.chain(),
+                    &token_1.address(),
+                    &token_1.symbol(),
+                    &transfer_ids,
+                    &claim_ids,
+                    ts,
+                );
+                request_map::update_reply(request_id, Reply::AddPool(reply));
+                return Err(format!("Req #{} failed. {}", request_id, e));
+            }
+        };
+        &listed_token_0
+    } else {
+        token_0
+    };
+
     let transfer_0 = match tx_id_0 {
         Some(block_id) => verify_transfer_token(request_id, &TokenIndex::Token0, token_0, block_id, amount_0, &mut transfer_ids, ts).await,
         None => {
@@ -276,6 +400,7 @@ async fn process_add_pool(
             &caller_id,
             &transfer_0,
             token_0,
+            is_new_token_0,
             amount_0,
             &transfer_1,
             token_1,
@@ -294,7 +419,7 @@ async fn process_add_pool(
     // add LP token
     request_map::update_status(request_id, StatusCode::AddLPToken, None);
     // default to None for LP token metadata
-    let lp_token = match add_lp_token(token_0, token_1) {
+    let lp_token = match add_lp_token(token_0, token_1, fee_tier) {
         Ok(lp_token) => {
             request_map::update_status(request_id, StatusCode::AddLPTokenSuccess, None);
             lp_token
@@ -307,6 +432,7 @@ async fn process_add_pool(
                 &caller_id,
                 &transfer_0,
                 token_0,
+                is_new_token_0,
                 amount_0,
                 &transfer_1,
                 token_1,
@@ -327,6 +453,8 @@ async fn process_add_pool(
         lp_fee_bps,
         kong_fee_bps,
         lp_token.token_id(),
+        pool_type,
+        fee_tier,
     ) {
         Ok(pool) => {
             request_map::update_status(request_id, StatusCode::AddPoolSuccess, None);
@@ -340,6 +468,7 @@ async fn process_add_pool(
                 &caller_id,
                 &transfer_0,
                 token_0,
+                is_new_token_0,
                 amount_0,
                 &transfer_1,
                 token_1,
@@ -388,6 +517,90 @@ async fn process_add_pool(
     Ok(reply)
 }
 
+/// lists new token_0. King Kong lists directly, other users pay a refundable listing deposit in ckUSDT
+/// and the token is on probation until King Kong approves or rejects it
+///
+/// returns the listed token, or the error and the claim_ids of any deposit that could not be kept
+#[allow(clippy::too_many_arguments)]
+async fn list_token(
+    request_id: u64,
+    user_id: u32,
+    caller_id: &Account,
+    token: &StableToken,
+    kong_backend: &Account,
+    transfer_ids: &mut Vec<u64>,
+    ts: u64,
+) -> Result<StableToken, (String, Vec<u64>)> {
+    let StableToken::IC(ic_token) = token else {
+        return Err(("Token_0 chain not supported".to_string(), Vec::new()));
+    };
+
+    let ic_token = if caller_is_kingkong().is_ok() {
+        ic_token.clone()
+    } else {
+        let ckusdt = token_map::get_ckusdt().map_err(|e| (e, Vec::new()))?;
+        let deposit = kong_settings_map::get().listing_deposit;
+        request_map::update_status(request_id, StatusCode::PayListingDeposit, None);
+        match icrc2_transfer_from(&ckusdt, &deposit, caller_id, kong_backend).await {
+            Ok(block_id) => {
+                let transfer_id = transfer_map::insert(&StableTransfer {
+                    transfer_id: 0,
+                    request_id,
+                    is_send: true,
+                    amount: deposit.clone(),
+                    token_id: ckusdt.token_id(),
+                    tx_id: TxId::BlockIndex(block_id),
+                    ts,
+                });
+                transfer_ids.push(transfer_id);
+                request_map::update_status(request_id, StatusCode::PayListingDepositSuccess, None);
+            }
+            Err(e) => {
+                request_map::update_status(request_id, StatusCode::PayListingDepositFailed, Some(&e));
+                return Err((e, Vec::new()));
+            }
+        }
+        ICToken {
+            on_probation: true,
+            listing_deposit: Some(ListingDeposit {
+                user_id,
+                token_id: ckusdt.token_id(),
+                amount: deposit,
+                ts,
+            }),
+            ..ic_token.clone()
+        }
+    };
+
+    request_map::update_status(request_id, StatusCode::AddToken0, None);
+    match insert_ic_token(&ic_token) {
+        Ok(token) => {
+            request_map::update_status(request_id, StatusCode::AddToken0Success, None);
+            Ok(token)
+        }
+        Err(e) => {
+            // token was listed by another request in the meantime. return the deposit as a claim
+            let mut claim_ids = Vec::new();
+            let mut message = e.clone();
+            if let Some(deposit) = ic_token.listing_deposit {
+                let claim = StableClaim::new(
+                    user_id,
+                    deposit.token_id,
+                    &deposit.amount,
+                    Some(request_id),
+                    Some(Address::PrincipalId(*caller_id)),
+                    ts,
+                );
+                let claim_id = claim_map::insert(&claim);
+                claim_ids.push(claim_id);
+                message = format!("Listing deposit saved as claim #{}. {}", claim_id, e);
+            }
+            request_map::update_status(request_id, StatusCode::AddToken0Failed, Some(&message));
+            Err((e, claim_ids))
+        }
+    }
+}
+
 async fn verify_transfer_token(
     request_id: u64,
     token_index: &TokenIndex,
@@ -500,16 +713,25 @@ fn update_liquidity_pool(
 ) {
     request_map::update_status(request_id, StatusCode::UpdatePoolAmounts, None);
 
-    let update_pool = StablePool {
+    let mut update_pool = StablePool {
         balance_0: nat_add(&pool.balance_0, amount_0),
         balance_1: nat_add(&pool.balance_1, amount_1),
         ..pool.clone()
     };
+    // starts the cumulative prices of the new pool
+    update_pool.update_price_cumulative(ts);
+    if let PoolType::Concentrated(ref mut concentrated_pool) = update_pool.pool_type {
+        // concentrated pools do not mint LP tokens, the user gets a full range position instead
+        let (tick_lower, tick_upper) = (concentrated_pool.min_tick(), concentrated_pool.max_tick());
+        concentrated_pool.add_position(user_id, tick_lower, tick_upper, add_lp_token_amount, ts);
+    }
     pool_map::update(&update_pool);
     request_map::update_status(request_id, StatusCode::UpdatePoolAmountsSuccess, None);
 
     // update user's LP token amount
-    update_lp_token(request_id, user_id, pool.lp_token_id, add_lp_token_amount, ts);
+    if pool.pool_type.has_lp_token() {
+        update_lp_token(request_id, user_id, pool.lp_token_id, add_lp_token_amount, ts);
+    }
 }
 
 fn update_lp_token(request_id: u64, user_id: u32, lp_token_id: u32, add_lp_token_amount: &Nat, ts: u64) {
@@ -525,13 +747,17 @@ fn update_lp_token(request_id: u64, user_id: u32, lp_token_id: u32, add_lp_token
                 ..lp_token.clone()
             };
             lp_token_map::update(&new_user_lp_token);
+            lp_block_map::log_mint(lp_token_id, user_id, add_lp_token_amount, ts);
             request_map::update_status(request_id, StatusCode::UpdateUserLPTokenAmountSuccess, None);
         }
         None => {
             // new entry
             let new_user_lp_token = StableLPToken::new(user_id, lp_token_id, add_lp_token_amount.clone(), ts);
             match lp_token_map::insert(&new_user_lp_token) {
-                Ok(_) => request_map::update_status(request_id, StatusCode::UpdateUserLPTokenAmountSuccess, None),
+                Ok(_) => {
+                    lp_block_map::log_mint(lp_token_id, user_id, add_lp_token_amount, ts);
+                    request_map::update_status(request_id, StatusCode::UpdateUserLPTokenAmountSuccess, None)
+                }
                 Err(e) => request_map::update_status(request_id, StatusCode::UpdateUserLPTokenAmountFailed, Some(&e)),
             };
         }
@@ -545,6 +771,7 @@ async fn return_tokens(
     to_principal_id: &Account,
     transfer_from_token_0: &Result<(), String>,
     token_0: &StableToken,
+    is_new_token_0: bool,
     amount_0: &Nat,
     transfer_from_token_1: &Result<(), String>,
     token_1: &StableToken,
@@ -584,6 +811,11 @@ async fn return_tokens(
         .await;
     }
 
+    // the pool of new token_0 was not added, so its listing is rolled back
+    if is_new_token_0 {
+        return_listing_deposit(request_id, token_0, to_principal_id, transfer_ids, &mut claim_ids, ts).await;
+    }
+
     let reply = to_add_pool_reply_failed(
         request_id,
         &token_0.chain(),
@@ -654,9 +886,90 @@ async fn return_token(
     }
 }
 
+/// delist new token_0 on probation and return its listing deposit. any failure to send the deposit is saved as a claim
+/// King Kong may have approved or rejected the token while the pool was added, in which case the deposit is already settled
+async fn return_listing_deposit(
+    request_id: u64,
+    token: &StableToken,
+    to_principal_id: &Account,
+    transfer_ids: &mut Vec<u64>,
+    claim_ids: &mut Vec<u64>,
+    ts: u64,
+) {
+    let Some(StableToken::IC(ic_token)) = token_map::get_by_token_id(token.token_id()) else {
+        return;
+    };
+    let Some(deposit) = ic_token.listing_deposit.clone().filter(|_| ic_token.on_probation) else {
+        return;
+    };
+    // delist the token before the deposit is returned so it can not be approved in the meantime
+    token_map::update(&StableToken::IC(ICToken {
+        is_removed: true,
+        on_probation: false,
+        listing_deposit: None,
+        ..ic_token
+    }));
+
+    request_map::update_status(request_id, StatusCode::ReturnListingDeposit, None);
+    let send_result = match token_map::get_by_token_id(deposit.token_id) {
+        Some(deposit_token) => {
+            let amount_with_gas = nat_subtract(&deposit.amount, &deposit_token.fee()).unwrap_or(nat_zero());
+            icrc1_transfer(&amount_with_gas, to_principal_id, &deposit_token, None)
+                .await
+                .map(|block_id| (block_id, amount_with_gas))
+        }
+        None => Err("Listing deposit token not found".to_string()),
+    };
+    match send_result {
+        Ok((block_id, amount)) => {
+            let transfer_id = transfer_map::insert(&StableTransfer {
+                transfer_id: 0,
+                request_id,
+                is_send: false,
+                amount,
+                token_id: deposit.token_id,
+                tx_id: TxId::BlockIndex(block_id),
+                ts,
+            });
+            transfer_ids.push(transfer_id);
+            request_map::update_status(request_id, StatusCode::ReturnListingDepositSuccess, None);
+        }
+        Err(e) => {
+            let claim = StableClaim::new(
+                deposit.user_id,
+                deposit.token_id,
+                &deposit.amount,
+                Some(request_id),
+                Some(Address::PrincipalId(*to_principal_id)),
+                ts,
+            );
+            let claim_id = claim_map::insert(&claim);
+            claim_ids.push(claim_id);
+            let message = format!("Saved as claim #{}. {}", claim_id, e);
+            request_map::update_status(request_id, StatusCode::ReturnListingDepositFailed, Some(&message));
+        }
+    }
+}
+
 // add_pool() taken
-fn add_new_pool(token_id_0: u32, token_id_1: u32, lp_fee_bps: u8, kong_fee_bps: u8, lp_token_id: u32) -> Result<StablePool, String> {
-    let pool = StablePool::new(token_id_0, token_id_1, lp_fee_bps, kong_fee_bps, lp_token_id);
+fn add_new_pool(
+    token_id_0: u32,
+    token_id_1: u32,
+    lp_fee_bps: u8,
+    kong_fee_bps: u8,
+    lp_token_id: u32,
+    pool_type: &PoolType,
+    fee_tier: Option<u8>,
+) -> Result<StablePool, String> {
+    let pool = StablePool::new(
+        token_id_0,
+        token_id_1,
+        lp_fee_bps,
+        kong_fee_bps,
+        lp_token_id,
+        pool_type.clone(),
+        fee_tier,
+    );
     let pool_id = pool_map::insert(&pool)?;
 
     // Retrieves the inserted pool by its pool_id
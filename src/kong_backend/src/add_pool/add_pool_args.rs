@@ -13,5 +13,7 @@ pub struct AddPoolArgs {
     pub token_1: String,
     pub amount_1: Nat,
     pub tx_id_1: Option<TxId>,
-    pub lp_fee_bps: Option<u8>,
+    pub lp_fee_bps: Option<u8>,    // fee tier of the pool. a pair can have one pool per fee tier
+    pub tick_spacing: Option<u16>, // creates a concentrated liquidity pool if specified
+    pub amp: Option<u64>,          // creates a StableSwap pool with amplification coefficient amp if specified
 }
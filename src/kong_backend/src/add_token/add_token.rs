@@ -6,13 +6,13 @@ use super::add_token_reply::AddTokenReply;
 use super::add_token_reply_helpers::to_add_token_reply;
 
 use crate::chains::chains::IC_CHAIN;
-use crate::ic::guards::not_in_maintenance_mode;
+use crate::ic::guards::{caller_is_kingkong, not_in_maintenance_mode};
 use crate::stable_token::ic_token::ICToken;
 use crate::stable_token::lp_token::LPToken;
 use crate::stable_token::stable_token::StableToken;
 use crate::stable_token::token_map;
 
-/// Adds a token to Kong
+/// Adds a token to Kong. Users list new tokens through add_pool() with a listing deposit
 ///
 /// # Arguments
 ///
@@ -26,10 +26,13 @@ use crate::stable_token::token_map;
 /// # Errors
 ///
 /// This function returns an error if:
-/// - The caller is not a controller.
+/// - The caller is not King Kong.
 /// - The token already exists.
-#[update(guard = "not_in_maintenance_mode")]
+/// - The token's ledger fails the metadata and standards checks.
+#[update(guard = "caller_is_kingkong")]
 async fn add_token(args: AddTokenArgs) -> Result<AddTokenReply, String> {
+    not_in_maintenance_mode()?;
+
     if token_map::get_by_address(&args.token).is_ok() {
         Err(format!("Token {} already exists", args.token))?
     }
@@ -55,27 +58,47 @@ async fn add_token(args: AddTokenArgs) -> Result<AddTokenReply, String> {
 /// # Errors
 ///
 /// This function returns an error if:
-/// - The address of the token is not found.
-/// - The address cannot be converted to a `Principal`.
-/// - Creating the `ICToken` fails.
+/// - The token fails vetting.
 /// - Inserting the token into the token map fails.
 /// - Retrieving the inserted token fails.
 pub async fn add_ic_token(token: &str) -> Result<StableToken, String> {
+    let ic_token = vet_ic_token(token).await?;
+    insert_ic_token(&ic_token)
+}
+
+/// Vets an Internet Computer (IC) token before listing. The token is not inserted into the token map.
+///
+/// # Errors
+///
+/// This function returns an error if:
+/// - The address of the token is not found.
+/// - The address cannot be converted to a `Principal`.
+/// - The ledger's metadata or supported standards fail the checks in `ICToken::new`.
+/// - A token with the same symbol is already listed.
+pub async fn vet_ic_token(token: &str) -> Result<ICToken, String> {
     // Retrieves the address of the token.
     let address = token_map::get_address(token).ok_or_else(|| format!("Invalid address {}", token))?;
 
     // Converts the address to a `Principal`.
     let canister_id = Principal::from_text(address).map_err(|e| format!("Invalid canister id {}: {}", token, e))?;
 
-    let ic_token = StableToken::IC(ICToken::new(&canister_id).await?);
-    let token_id = token_map::insert(&ic_token)?;
+    let ic_token = ICToken::new(&canister_id).await?;
+    if token_map::symbol_exists(&format!("{}.{}", ic_token.chain(), ic_token.symbol)) {
+        Err(format!("Token symbol {} already exists", ic_token.symbol))?
+    }
+
+    Ok(ic_token)
+}
+
+pub fn insert_ic_token(ic_token: &ICToken) -> Result<StableToken, String> {
+    let token_id = token_map::insert(&StableToken::IC(ic_token.clone()))?;
 
     // Retrieves the inserted token by its token_id
-    token_map::get_by_token_id(token_id).ok_or_else(|| format!("Failed to add token {}", token))
+    token_map::get_by_token_id(token_id).ok_or_else(|| format!("Failed to add token {}", ic_token.symbol))
 }
 
-pub fn add_lp_token(token_0: &StableToken, token_1: &StableToken) -> Result<StableToken, String> {
-    let lp_token = StableToken::LP(LPToken::new(token_0, token_1));
+pub fn add_lp_token(token_0: &StableToken, token_1: &StableToken, fee_tier: Option<u8>) -> Result<StableToken, String> {
+    let lp_token = StableToken::LP(LPToken::new(token_0, token_1, fee_tier));
     let token_id = token_map::insert(&lp_token)?;
 
     // Retrieves the inserted token by its token_id
@@ -7,6 +7,7 @@ use super::update_token_reply_helpers::to_update_token_reply;
 
 use crate::chains::chains::IC_CHAIN;
 use crate::ic::guards::not_in_maintenance_mode;
+use crate::stable_pool::pool_map;
 use crate::stable_token::ic_token::ICToken;
 use crate::stable_token::stable_token::StableToken;
 use crate::stable_token::token;
@@ -28,27 +29,32 @@ pub async fn update_ic_token(token: &str) -> Result<StableToken, String> {
     let stable_token = token_map::get_by_token(token)?;
     let address = stable_token.address();
     let token_id = stable_token.token_id();
-    let symbol = stable_token.symbol();
 
     let canister_id = Principal::from_text(address).map_err(|e| format!("Invalid canister id {}: {}", token, e))?;
 
-    let mut ic_token = ICToken::new(&canister_id).await?;
+    let mut ic_token = ICToken::refresh(&canister_id).await?;
     ic_token.token_id = token_id;
+    // keep the listing state so updating the metadata cannot unsuspend or approve the token
+    if let StableToken::IC(current_token) = &stable_token {
+        ic_token.is_removed = current_token.is_removed;
+        ic_token.on_probation = current_token.on_probation;
+        ic_token.listing_deposit = current_token.listing_deposit.clone();
+    }
 
     token_map::update(&StableToken::IC(ic_token.clone()));
 
-    // update _ckUSDT pool for symbol
-    let ckusdt = token_map::get_ckusdt()?;
-    if let Ok(StableToken::LP(mut lp_token)) = token_map::get_by_token(&format!("LP.{}_{}", symbol, ckusdt.symbol())) {
-        lp_token.symbol = token::symbol(&StableToken::IC(ic_token.clone()), &ckusdt);
-        token_map::update(&StableToken::LP(lp_token));
-    }
-
-    // update _ICP pool for symbol
-    let icp = token_map::get_icp()?;
-    if let Ok(StableToken::LP(mut lp_token)) = token_map::get_by_token(&format!("LP.{}_{}", symbol, icp.symbol())) {
-        lp_token.symbol = token::symbol(&StableToken::IC(ic_token), &icp);
-        token_map::update(&StableToken::LP(lp_token));
+    // update the LP token symbols of the _ckUSDT and _ICP pools, in all fee tiers
+    for token_1 in [token_map::get_ckusdt()?, token_map::get_icp()?] {
+        for pool in pool_map::get_all_by_token_ids(token_id, token_1.token_id()) {
+            if let Some(StableToken::LP(mut lp_token)) = token_map::get_by_token_id(pool.lp_token_id) {
+                lp_token.symbol = format!(
+                    "{}{}",
+                    token::symbol(&StableToken::IC(ic_token.clone()), &token_1),
+                    token::fee_tier_suffix(pool.fee_tier)
+                );
+                token_map::update(&StableToken::LP(lp_token));
+            }
+        }
     }
 
     token_map::get_by_token_id(token_id).ok_or_else(|| format!("Failed to update token {}", token))
@@ -18,6 +18,7 @@ pub fn to_update_token_reply(token: &StableToken) -> Result<UpdateTokenReply, St
             icrc2: ic_token.icrc2,
             icrc3: ic_token.icrc3,
             is_removed: token.is_removed(),
+            on_probation: ic_token.on_probation,
         })),
         _ => Err("Unsupported token type".to_string()),
     }
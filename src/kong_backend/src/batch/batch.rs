@@ -0,0 +1,831 @@
+use candid::Nat;
+use ic_cdk::update;
+use icrc_ledger_types::icrc1::account::Account;
+use std::collections::BTreeMap;
+use std::time::Duration;
+
+use super::batch_args::{BatchArgs, BatchOp};
+use super::batch_reply::{BatchReply, BatchSendReply, BatchStepReply};
+use super::batch_reply_helpers::to_batch_reply;
+
+use crate::add_liquidity::add_liquidity_args::AddLiquidityArgs;
+use crate::add_liquidity::add_liquidity_reply_helpers::to_add_liquidity_reply;
+use crate::add_liquidity::add_liquidity_transfer_from::update_liquidity_pool;
+use crate::helpers::nat_helpers::{nat_add, nat_is_zero, nat_subtract, nat_zero};
+use crate::ic::{
+    address::Address,
+    address_helpers::get_address,
+    deadline::{check_deadline, check_request_deadline},
+    get_time::get_time,
+    guards::not_in_maintenance_mode,
+    id::caller_id,
+    transfer::{icp_transfer, icrc1_transfer, icrc2_transfer_from},
+};
+use crate::remove_liquidity::remove_liquidity::{check_arguments_with_user, remove_from_pool};
+use crate::remove_liquidity::remove_liquidity_args::RemoveLiquidityArgs;
+use crate::remove_liquidity::remove_liquidity_reply_helpers::to_remove_liquidity_reply;
+use crate::stable_claim::{claim_map, stable_claim::StableClaim};
+use crate::stable_kong_settings::kong_settings_map;
+use crate::stable_lp_token::{lp_block_map, lp_token_map};
+use crate::stable_pool::{pool_map, stable_pool::StablePool};
+use crate::stable_request::{reply::Reply, request::Request, request_map, stable_request::StableRequest, status::StatusCode};
+use crate::stable_token::{stable_token::StableToken, token::Token, token_map};
+use crate::stable_transfer::{stable_transfer::StableTransfer, transfer_map, tx_id::TxId};
+use crate::stable_tx::{
+    add_liquidity_tx::AddLiquidityTx, remove_liquidity_tx::RemoveLiquidityTx, stable_tx::StableTx, status_tx::StatusTx, swap_tx::SwapTx,
+    tx_map,
+};
+use crate::stable_user::banned_user_map::is_banned_user;
+use crate::stable_user::{referral_reward_map, user_map};
+use crate::swap::calculate_amounts::calculate_internal_amounts;
+use crate::swap::swap_reply_helpers::to_swap_reply;
+use crate::swap::update_liquidity_pool::update_pools;
+
+const MAX_BATCH_OPS: usize = 10;
+
+/// balances of the tokens the batch holds for the user, by token_id
+type BatchBalances = BTreeMap<u32, Nat>;
+
+/// operation of a batch with its tokens and pool resolved
+enum BatchStep {
+    Swap {
+        pay_token: StableToken,
+        pay_amount: Option<Nat>,
+        receive_token: StableToken,
+        receive_amount: Option<Nat>,
+        max_slippage: f64,
+        deadline: Option<u64>,
+    },
+    AddLiquidity {
+        pool: Box<StablePool>,
+        amount_0: Option<Nat>,
+        amount_1: Option<Nat>,
+        lock_secs: Option<u64>,
+        deadline: Option<u64>,
+    },
+    RemoveLiquidity(RemoveLiquidityArgs),
+    Send {
+        token: StableToken,
+        amount: Option<Nat>,
+        to_address: Address,
+        to_address_text: String,
+    },
+}
+
+impl BatchStep {
+    fn name(&self) -> &str {
+        match self {
+            BatchStep::Swap { .. } => "Swap",
+            BatchStep::AddLiquidity { .. } => "Add liquidity",
+            BatchStep::RemoveLiquidity(_) => "Remove liquidity",
+            BatchStep::Send { .. } => "Send",
+        }
+    }
+}
+
+/// result of an executed operation. the txs are inserted once the operations of the batch are committed
+enum BatchStepResult {
+    Swap(SwapTx),
+    AddLiquidity(AddLiquidityTx),
+    RemoveLiquidity(RemoveLiquidityTx),
+    Send(BatchSendReply),
+}
+
+/// tokens queued to be sent out once all the operations of the batch are executed
+struct BatchSend {
+    token: StableToken,
+    amount: Nat,
+    to_address: Address,
+}
+
+/// Execute an ordered list of swap, add_liquidity, remove_liquidity and send operations in a single request
+///
+/// - before calling batch, the user must icrc2_approve every specified amount + gas fee of its token
+/// - all specified amounts are transferred from the user first. amounts that are not specified use the whole balance of
+///   the token held by the batch, i.e. the outputs of the previous operations. intermediate outputs never leave Kong
+/// - the operations are then executed in order without any inter-canister calls in between, so no other request can
+///   change the pools while the batch is executed. their changes to the pools, LP tokens and batch balances are staged
+///   and only committed once every operation succeeds
+/// - tokens of send operations are sent to their addresses and any remaining balances are returned to the user
+/// - if any operation fails, none of the operations are applied and all the transferred amounts are returned to the
+///   user as claims
+#[update(guard = "not_in_maintenance_mode")]
+pub async fn batch(args: BatchArgs) -> Result<BatchReply, String> {
+    let (user_id, steps, pay_amounts) = check_arguments(&args)?;
+    let ts = get_time();
+    let request_id = request_map::insert(&StableRequest::new(user_id, &Request::Batch(args), ts));
+
+    let result = match process_batch(request_id, user_id, &steps, &pay_amounts, ts).await {
+        Ok(reply) => {
+            request_map::update_status(request_id, StatusCode::Success, None);
+            Ok(reply)
+        }
+        Err(e) => {
+            request_map::update_status(request_id, StatusCode::Failed, None);
+            Err(e)
+        }
+    };
+    _ = archive_to_kong_data(request_id);
+
+    result
+}
+
+/// returns (user_id, steps, pay_amounts) where pay_amounts are the total specified amounts of each token
+#[allow(clippy::type_complexity)]
+fn check_arguments(args: &BatchArgs) -> Result<(u32, Vec<BatchStep>, Vec<(StableToken, Nat)>), String> {
+    if args.ops.is_empty() {
+        Err("No operations in batch".to_string())?
+    }
+    if args.ops.len() > MAX_BATCH_OPS {
+        Err(format!("Batch can have at most {} operations", MAX_BATCH_OPS))?
+    }
+
+    let mut steps = Vec::with_capacity(args.ops.len());
+    let mut pay_amounts: Vec<(StableToken, Nat)> = Vec::new();
+    for (op_idx, op) in args.ops.iter().enumerate() {
+        let step = check_op(op).map_err(|e| format!("Operation #{}: {}", op_idx + 1, e))?;
+        for (token, amount) in step_pay_amounts(&step) {
+            if nat_is_zero(amount) {
+                Err(format!("Operation #{}: Invalid zero amount", op_idx + 1))?
+            }
+            if !token.is_icrc2() {
+                Err(format!("Operation #{}: {} must support ICRC2", op_idx + 1, token.symbol()))?
+            }
+            match pay_amounts
+                .iter_mut()
+                .find(|(pay_token, _)| pay_token.token_id() == token.token_id())
+            {
+                Some((_, pay_amount)) => *pay_amount = nat_add(pay_amount, amount),
+                None => pay_amounts.push((token.clone(), amount.clone())),
+            }
+        }
+        steps.push(step);
+    }
+
+    // make sure user is registered, if not create a new user
+    let user_id = user_map::insert(None)?;
+
+    // check if user is banned
+    if let Some(banned_until) = is_banned_user(user_id) {
+        let now = get_time();
+        if banned_until > now {
+            let duration_ns = Duration::from_nanos(banned_until - now);
+            let duration_min = duration_ns.as_secs() / 60;
+            Err(format!("Too many consecutive errors. User is banned for {} minutes", duration_min))?;
+        }
+    }
+
+    Ok((user_id, steps, pay_amounts))
+}
+
+fn check_op(op: &BatchOp) -> Result<BatchStep, String> {
+    let step = match op {
+        BatchOp::Swap(args) => {
+            check_deadline(args.deadline)?;
+            BatchStep::Swap {
+                pay_token: get_token(&args.pay_token)?,
+                pay_amount: args.pay_amount.clone(),
+                receive_token: get_token(&args.receive_token)?,
+                receive_amount: args.receive_amount.clone(),
+                max_slippage: args.max_slippage.unwrap_or(kong_settings_map::get().default_max_slippage),
+                deadline: args.deadline,
+            }
+        }
+        BatchOp::AddLiquidity(args) => {
+            check_deadline(args.deadline)?;
+            let pool = pool_map::get_by_tokens_and_fee_tier(&args.token_0, &args.token_1, args.fee_tier)?;
+            if pool.token_0().is_removed() || pool.token_1().is_removed() {
+                Err(format!("Pool {} has a suspended or removed token", pool.symbol()))?
+            }
+            BatchStep::AddLiquidity {
+                pool: Box::new(pool),
+                amount_0: args.amount_0.clone(),
+                amount_1: args.amount_1.clone(),
+                lock_secs: args.lock_secs,
+                deadline: args.deadline,
+            }
+        }
+        BatchOp::RemoveLiquidity(args) => {
+            check_deadline(args.deadline)?;
+            // pool and LP balance are checked when the operation is executed, as previous operations can change them
+            pool_map::get_by_tokens_and_fee_tier(&args.token_0, &args.token_1, args.fee_tier)?;
+            BatchStep::RemoveLiquidity(RemoveLiquidityArgs {
+                token_0: args.token_0.clone(),
+                token_1: args.token_1.clone(),
+                remove_lp_token_amount: args.remove_lp_token_amount.clone(),
+                position_id: args.position_id,
+                use_deposit: None,
+                receive_address: None,
+                fee_tier: args.fee_tier,
+                min_amount_0: None,
+                min_amount_1: None,
+                deadline: args.deadline,
+            })
+        }
+        BatchOp::Send(args) => {
+            let token = get_token(&args.token)?;
+            BatchStep::Send {
+                to_address: get_address(&token, &args.to_address)?,
+                to_address_text: args.to_address.clone(),
+                token,
+                amount: args.amount.clone(),
+            }
+        }
+    };
+
+    Ok(step)
+}
+
+/// only IC tokens can be held by a batch, LP tokens are minted and burned by the liquidity operations
+fn get_token(token: &str) -> Result<StableToken, String> {
+    let stable_token = token_map::get_by_token(token)?;
+    match stable_token {
+        StableToken::IC(_) if stable_token.is_removed() => Err(format!("Token {} is suspended or removed", token)),
+        StableToken::IC(_) => Ok(stable_token),
+        StableToken::LP(_) => Err(format!("Token {} not supported", token)),
+    }
+}
+
+/// the specified amounts of a step, which are transferred from the user
+fn step_pay_amounts(step: &BatchStep) -> Vec<(StableToken, &Nat)> {
+    match step {
+        BatchStep::Swap {
+            pay_token,
+            pay_amount: Some(pay_amount),
+            ..
+        } => vec![(pay_token.clone(), pay_amount)],
+        BatchStep::AddLiquidity {
+            pool, amount_0, amount_1, ..
+        } => {
+            let mut pay_amounts = Vec::new();
+            if let Some(amount_0) = amount_0 {
+                pay_amounts.push((pool.token_0(), amount_0));
+            }
+            if let Some(amount_1) = amount_1 {
+                pay_amounts.push((pool.token_1(), amount_1));
+            }
+            pay_amounts
+        }
+        BatchStep::Send {
+            token,
+            amount: Some(amount),
+            ..
+        } => vec![(token.clone(), amount)],
+        _ => Vec::new(),
+    }
+}
+
+async fn process_batch(
+    request_id: u64,
+    user_id: u32,
+    steps: &[BatchStep],
+    pay_amounts: &[(StableToken, Nat)],
+    ts: u64,
+) -> Result<BatchReply, String> {
+    let caller_id = caller_id();
+    let kong_backend = kong_settings_map::get().kong_backend;
+    let mut balances = BatchBalances::new();
+    let mut transfer_ids = Vec::new();
+
+    request_map::update_status(request_id, StatusCode::Start, None);
+
+    // transfer all the specified amounts from the user before any operation is executed
+    for (token, amount) in pay_amounts {
+        if let Err(e) = transfer_from_token(request_id, &caller_id, token, amount, &kong_backend, &mut transfer_ids, ts).await {
+            let claim_ids = return_balances(request_id, user_id, &caller_id, &balances, ts);
+            let reply = to_batch_reply(request_id, &StatusTx::Failed, &[], &transfer_ids, &claim_ids, ts);
+            request_map::update_reply(request_id, Reply::Batch(reply));
+            Err(format!("Req #{} failed. {}", request_id, e))?
+        }
+        credit(&mut balances, token.token_id(), amount);
+    }
+
+    // execute the operations. nothing is applied if one fails, so all the transferred amounts are returned
+    let (step_replies, sends, balances) = match execute_steps(request_id, user_id, steps, &balances, ts) {
+        Ok(executed) => executed,
+        Err(e) => {
+            let claim_ids = return_balances(request_id, user_id, &caller_id, &balances, ts);
+            let reply = to_batch_reply(request_id, &StatusTx::Failed, &[], &transfer_ids, &claim_ids, ts);
+            request_map::update_reply(request_id, Reply::Batch(reply));
+            Err(format!("Req #{} failed. {}", request_id, e))?
+        }
+    };
+
+    // send the tokens of the send operations and return the remaining balances to the user
+    let mut claim_ids = Vec::new();
+    for send in sends.iter() {
+        transfer_token(
+            request_id,
+            user_id,
+            &send.token,
+            &send.amount,
+            &send.to_address,
+            &mut transfer_ids,
+            &mut claim_ids,
+            ts,
+        )
+        .await;
+    }
+    let to_address = Address::PrincipalId(caller_id);
+    for (token_id, amount) in balances.iter().filter(|(_, amount)| !nat_is_zero(amount)) {
+        let Some(token) = token_map::get_by_token_id(*token_id) else {
+            continue;
+        };
+        transfer_token(
+            request_id,
+            user_id,
+            &token,
+            amount,
+            &to_address,
+            &mut transfer_ids,
+            &mut claim_ids,
+            ts,
+        )
+        .await;
+    }
+
+    let reply = to_batch_reply(request_id, &StatusTx::Success, &step_replies, &transfer_ids, &claim_ids, ts);
+    request_map::update_reply(request_id, Reply::Batch(reply.clone()));
+
+    Ok(reply)
+}
+
+/// execute the operations in order on staged pools, LP tokens and a copy of the batch balances. the staged changes
+/// are committed only if every operation succeeds, otherwise they are discarded and nothing is applied
+/// there are no inter-canister calls while the changes are staged, so the batch is atomic with respect to other requests
+/// returns (step_replies, sends, balances) where balances are what is left after the operations
+fn execute_steps(
+    request_id: u64,
+    user_id: u32,
+    steps: &[BatchStep],
+    balances: &BatchBalances,
+    ts: u64,
+) -> Result<(Vec<BatchStepReply>, Vec<BatchSend>, BatchBalances), String> {
+    let mut balances = balances.clone();
+    let mut sends: Vec<BatchSend> = Vec::new();
+    let mut step_results = Vec::new();
+
+    stage();
+    for (step_idx, step) in steps.iter().enumerate() {
+        let step_name = format!("#{} {}", step_idx + 1, step.name());
+        request_map::update_status(request_id, StatusCode::BatchStep, Some(&step_name));
+        match execute_step(request_id, user_id, step, &mut balances, &mut sends, ts) {
+            Ok(step_result) => {
+                request_map::update_status(request_id, StatusCode::BatchStepSuccess, Some(&step_name));
+                step_results.push(step_result);
+            }
+            Err(e) => {
+                discard_staged();
+                request_map::update_status(request_id, StatusCode::BatchStepFailed, Some(&format!("{}. {}", step_name, e)));
+                Err(format!("{} {}", step_name, e))?
+            }
+        }
+    }
+    commit_staged();
+
+    // txs are only inserted once the operations are committed
+    let step_replies = step_results.into_iter().map(insert_step_tx).collect();
+    Ok((step_replies, sends, balances))
+}
+
+fn stage() {
+    pool_map::stage();
+    lp_token_map::stage();
+    lp_block_map::stage();
+    referral_reward_map::stage();
+}
+
+fn commit_staged() {
+    pool_map::commit_staged();
+    lp_token_map::commit_staged();
+    lp_block_map::commit_staged();
+    referral_reward_map::commit_staged();
+}
+
+fn discard_staged() {
+    pool_map::discard_staged();
+    lp_token_map::discard_staged();
+    lp_block_map::discard_staged();
+    referral_reward_map::discard_staged();
+}
+
+fn insert_step_tx(step_result: BatchStepResult) -> BatchStepReply {
+    match step_result {
+        BatchStepResult::Swap(swap_tx) => {
+            let tx_id = tx_map::insert(&StableTx::Swap(swap_tx.clone()));
+            BatchStepReply::Swap(to_swap_reply(&SwapTx { tx_id, ..swap_tx }))
+        }
+        BatchStepResult::AddLiquidity(add_liquidity_tx) => {
+            let tx_id = tx_map::insert(&StableTx::AddLiquidity(add_liquidity_tx.clone()));
+            BatchStepReply::AddLiquidity(to_add_liquidity_reply(&AddLiquidityTx { tx_id, ..add_liquidity_tx }))
+        }
+        BatchStepResult::RemoveLiquidity(remove_liquidity_tx) => {
+            let tx_id = tx_map::insert(&StableTx::RemoveLiquidity(remove_liquidity_tx.clone()));
+            BatchStepReply::RemoveLiquidity(to_remove_liquidity_reply(&RemoveLiquidityTx {
+                tx_id,
+                ..remove_liquidity_tx
+            }))
+        }
+        BatchStepResult::Send(send_reply) => BatchStepReply::Send(send_reply),
+    }
+}
+
+/// execute an operation of the batch against the batch balances, with the changes staged
+fn execute_step(
+    request_id: u64,
+    user_id: u32,
+    step: &BatchStep,
+    balances: &mut BatchBalances,
+    sends: &mut Vec<BatchSend>,
+    ts: u64,
+) -> Result<BatchStepResult, String> {
+    match step {
+        BatchStep::Swap {
+            pay_token,
+            pay_amount,
+            receive_token,
+            receive_amount,
+            max_slippage,
+            deadline,
+        } => {
+            check_request_deadline(request_id, *deadline)?;
+            let pay_amount = use_amount(balances, pay_token.token_id(), pay_amount.as_ref())?;
+
+            request_map::update_status(request_id, StatusCode::CalculatePoolAmounts, None);
+            let (receive_amount, price, mid_price, slippage, swaps) =
+                calculate_internal_amounts(pay_token, &pay_amount, receive_token, receive_amount.as_ref(), *max_slippage).inspect_err(
+                    |e| {
+                        request_map::update_status(request_id, StatusCode::CalculatePoolAmountsFailed, Some(e));
+                    },
+                )?;
+            request_map::update_status(request_id, StatusCode::CalculatePoolAmountsSuccess, None);
+            update_pools(request_id, &swaps)?;
+            debit(balances, pay_token.token_id(), &pay_amount);
+            credit(balances, receive_token.token_id(), &receive_amount);
+            request_map::update_status(request_id, StatusCode::SwapSuccess, None);
+
+            let swap_tx = SwapTx::new_success(
+                user_id,
+                request_id,
+                pay_token.token_id(),
+                &pay_amount,
+                receive_token.token_id(),
+                &receive_amount,
+                mid_price,
+                price,
+                slippage,
+                &swaps,
+                &[],
+                &[],
+                ts,
+            );
+            Ok(BatchStepResult::Swap(swap_tx))
+        }
+        BatchStep::AddLiquidity {
+            pool,
+            amount_0,
+            amount_1,
+            lock_secs,
+            deadline,
+        } => {
+            let amount_0 = use_amount(balances, pool.token_id_0, amount_0.as_ref())?;
+            let amount_1 = use_amount(balances, pool.token_id_1, amount_1.as_ref())?;
+            let args = AddLiquidityArgs {
+                token_0: pool.token_0().address_with_chain(),
+                amount_0: amount_0.clone(),
+                tx_id_0: None,
+                token_1: pool.token_1().address_with_chain(),
+                amount_1: amount_1.clone(),
+                tx_id_1: None,
+                min_price: None,
+                max_price: None,
+                lock_secs: *lock_secs,
+                use_deposit: None,
+                fee_tier: pool.fee_tier,
+                min_lp_token_amount: None,
+                deadline: *deadline,
+            };
+            // amounts are re-calculated with the latest state of the pool, any unused amount stays in the batch balances
+            let (pool, add_amount_0, add_amount_1, add_lp_token_amount) =
+                update_liquidity_pool(request_id, user_id, pool, &amount_0, &amount_1, &args, ts)?;
+            debit(balances, pool.token_id_0, &add_amount_0);
+            debit(balances, pool.token_id_1, &add_amount_1);
+
+            let add_liquidity_tx = AddLiquidityTx::new_success(
+                pool.pool_id,
+                user_id,
+                request_id,
+                &add_amount_0,
+                &add_amount_1,
+                &add_lp_token_amount,
+                &[],
+                &[],
+                ts,
+            );
+            Ok(BatchStepResult::AddLiquidity(add_liquidity_tx))
+        }
+        BatchStep::RemoveLiquidity(args) => {
+            request_map::update_status(request_id, StatusCode::RemoveLiquidityFromPool, None);
+            let (pool, remove_lp_token_amount, payout_amount_0, payout_lp_fee_0, payout_amount_1, payout_lp_fee_1) =
+                check_arguments_with_user(args, user_id, true)?;
+            remove_from_pool(
+                request_id,
+                user_id,
+                &pool,
+                args.position_id,
+                &remove_lp_token_amount,
+                &payout_amount_0,
+                &payout_lp_fee_0,
+                &payout_amount_1,
+                &payout_lp_fee_1,
+                ts,
+            )?;
+            credit(balances, pool.token_id_0, &nat_add(&payout_amount_0, &payout_lp_fee_0));
+            credit(balances, pool.token_id_1, &nat_add(&payout_amount_1, &payout_lp_fee_1));
+
+            let remove_liquidity_tx = RemoveLiquidityTx::new_success(
+                pool.pool_id,
+                user_id,
+                request_id,
+                &payout_amount_0,
+                &payout_lp_fee_0,
+                &payout_amount_1,
+                &payout_lp_fee_1,
+                &remove_lp_token_amount,
+                &[],
+                &[],
+                ts,
+            );
+            Ok(BatchStepResult::RemoveLiquidity(remove_liquidity_tx))
+        }
+        BatchStep::Send {
+            token,
+            amount,
+            to_address,
+            to_address_text,
+        } => {
+            let amount = use_amount(balances, token.token_id(), amount.as_ref())?;
+            debit(balances, token.token_id(), &amount);
+            sends.push(BatchSend {
+                token: token.clone(),
+                amount: amount.clone(),
+                to_address: to_address.clone(),
+            });
+            Ok(BatchStepResult::Send(BatchSendReply {
+                chain: token.chain(),
+                symbol: token.symbol(),
+                address: token.address(),
+                amount,
+                to_address: to_address_text.clone(),
+            }))
+        }
+    }
+}
+
+/// amount of token_id an operation uses. the specified amount, or the whole batch balance if not specified
+fn use_amount(balances: &BatchBalances, token_id: u32, amount: Option<&Nat>) -> Result<Nat, String> {
+    let balance = balances.get(&token_id).cloned().unwrap_or(nat_zero());
+    let amount = amount.cloned().unwrap_or_else(|| balance.clone());
+    if nat_is_zero(&amount) {
+        Err("Zero batch balance".to_string())?
+    }
+    if amount > balance {
+        Err("Insufficient batch balance".to_string())?
+    }
+    Ok(amount)
+}
+
+fn credit(balances: &mut BatchBalances, token_id: u32, amount: &Nat) {
+    let balance = balances.entry(token_id).or_insert_with(nat_zero);
+    *balance = nat_add(balance, amount);
+}
+
+fn debit(balances: &mut BatchBalances, token_id: u32, amount: &Nat) {
+    let balance = balances.entry(token_id).or_insert_with(nat_zero);
+    *balance = nat_subtract(balance, amount).unwrap_or(nat_zero());
+}
+
+async fn transfer_from_token(
+    request_id: u64,
+    from_principal_id: &Account,
+    token: &StableToken,
+    amount: &Nat,
+    to_principal_id: &Account,
+    transfer_ids: &mut Vec<u64>,
+    ts: u64,
+) -> Result<(), String> {
+    let symbol = token.symbol();
+
+    request_map::update_status(request_id, StatusCode::SendPayToken, Some(&symbol));
+
+    match icrc2_transfer_from(token, amount, from_principal_id, to_principal_id).await {
+        Ok(block_id) => {
+            // insert_transfer() will use the latest state of TRANSFER_MAP so no reentrancy issues after icrc2_transfer_from()
+            let transfer_id = transfer_map::insert(&StableTransfer {
+                transfer_id: 0,
+                request_id,
+                is_send: true,
+                amount: amount.clone(),
+                token_id: token.token_id(),
+                tx_id: TxId::BlockIndex(block_id),
+                ts,
+            });
+            transfer_ids.push(transfer_id);
+            request_map::update_status(request_id, StatusCode::SendPayTokenSuccess, Some(&symbol));
+            Ok(())
+        }
+        Err(e) => {
+            request_map::update_status(request_id, StatusCode::SendPayTokenFailed, Some(&format!("{}. {}", symbol, e)));
+            Err(e)
+        }
+    }
+}
+
+/// send amount less the gas fee to to_address. failed transfers are saved as claims
+#[allow(clippy::too_many_arguments)]
+async fn transfer_token(
+    request_id: u64,
+    user_id: u32,
+    token: &StableToken,
+    amount: &Nat,
+    to_address: &Address,
+    transfer_ids: &mut Vec<u64>,
+    claim_ids: &mut Vec<u64>,
+    ts: u64,
+) {
+    let symbol = token.symbol();
+    let amount_with_gas = nat_subtract(amount, &token.fee()).unwrap_or(nat_zero());
+    if nat_is_zero(&amount_with_gas) {
+        // not enough to cover the gas fee
+        return;
+    }
+
+    request_map::update_status(request_id, StatusCode::SendBatchToken, Some(&symbol));
+
+    match match to_address {
+        Address::AccountId(to_account_id) => icp_transfer(&amount_with_gas, to_account_id, token, None).await,
+        Address::PrincipalId(to_principal_id) => icrc1_transfer(&amount_with_gas, to_principal_id, token, None).await,
+    } {
+        Ok(block_id) => {
+            let transfer_id = transfer_map::insert(&StableTransfer {
+                transfer_id: 0,
+                request_id,
+                is_send: false,
+                amount: amount_with_gas,
+                token_id: token.token_id(),
+                tx_id: TxId::BlockIndex(block_id),
+                ts,
+            });
+            transfer_ids.push(transfer_id);
+            request_map::update_status(request_id, StatusCode::SendBatchTokenSuccess, Some(&symbol));
+        }
+        Err(e) => {
+            let claim = StableClaim::new(user_id, token.token_id(), amount, Some(request_id), Some(to_address.clone()), ts);
+            let claim_id = claim_map::insert(&claim);
+            claim_ids.push(claim_id);
+            let message = format!("{} saved as claim #{}. {}", symbol, claim_id, e);
+            request_map::update_status(request_id, StatusCode::SendBatchTokenFailed, Some(&message));
+        }
+    }
+}
+
+/// return the balances of a failed batch to the user as claims
+fn return_balances(request_id: u64, user_id: u32, to_principal_id: &Account, balances: &BatchBalances, ts: u64) -> Vec<u64> {
+    balances
+        .iter()
+        .filter(|(_, amount)| !nat_is_zero(amount))
+        .map(|(token_id, amount)| {
+            let claim = StableClaim::new(
+                user_id,
+                *token_id,
+                amount,
+                Some(request_id),
+                Some(Address::PrincipalId(*to_principal_id)),
+                ts,
+            );
+            let claim_id = claim_map::insert(&claim);
+            request_map::update_status(
+                request_id,
+                StatusCode::ReturnBatchToken,
+                Some(&format!("Saved as claim #{}", claim_id)),
+            );
+            claim_id
+        })
+        .collect()
+}
+
+fn archive_to_kong_data(request_id: u64) -> Result<(), String> {
+    if !kong_settings_map::get().archive_to_kong_data {
+        return Ok(());
+    }
+
+    let request = request_map::get_by_request_id(request_id).ok_or(format!("Failed to archive. request_id #{} not found", request_id))?;
+    request_map::archive_to_kong_data(&request)?;
+
+    match request.reply {
+        Reply::Batch(ref reply) => {
+            // archive claims
+            reply
+                .claim_ids
+                .iter()
+                .try_for_each(|&claim_id| claim_map::archive_to_kong_data(claim_id))?;
+            // archive transfers
+            reply
+                .transfer_ids
+                .iter()
+                .try_for_each(|transfer_id_reply| transfer_map::archive_to_kong_data(transfer_id_reply.transfer_id))?;
+            // archive txs
+            reply.steps.iter().try_for_each(|step| match step {
+                BatchStepReply::Swap(reply) => tx_map::archive_to_kong_data(reply.tx_id),
+                BatchStepReply::AddLiquidity(reply) => tx_map::archive_to_kong_data(reply.tx_id),
+                BatchStepReply::RemoveLiquidity(reply) => tx_map::archive_to_kong_data(reply.tx_id),
+                BatchStepReply::Send(_) => Ok(()),
+            })?;
+        }
+        _ => return Err("Invalid reply type".to_string()),
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::stable_pool::pool_map::tests::insert_pool;
+
+    const USER_ID: u32 = 1;
+
+    fn remove_liquidity(remove_lp_token_amount: u64) -> BatchStep {
+        BatchStep::RemoveLiquidity(RemoveLiquidityArgs {
+            token_0: "T1".to_string(),
+            token_1: "T2".to_string(),
+            remove_lp_token_amount: Nat::from(remove_lp_token_amount),
+            position_id: None,
+            use_deposit: None,
+            receive_address: None,
+            fee_tier: None,
+            min_amount_0: None,
+            min_amount_1: None,
+            deadline: None,
+        })
+    }
+
+    #[test]
+    fn test_batch_balances() {
+        let mut balances = BatchBalances::new();
+        credit(&mut balances, 1, &Nat::from(100_u64));
+        credit(&mut balances, 1, &Nat::from(50_u64));
+        assert_eq!(use_amount(&balances, 1, None), Ok(Nat::from(150_u64)));
+        assert_eq!(use_amount(&balances, 1, Some(&Nat::from(40_u64))), Ok(Nat::from(40_u64)));
+        assert!(use_amount(&balances, 1, Some(&Nat::from(151_u64))).is_err());
+        assert!(use_amount(&balances, 2, None).is_err());
+
+        debit(&mut balances, 1, &Nat::from(40_u64));
+        assert_eq!(balances.get(&1), Some(&Nat::from(110_u64)));
+    }
+
+    #[test]
+    fn test_failed_step_applies_nothing() {
+        let pool = insert_pool(0);
+        let ts = get_time();
+        let mut balances = BatchBalances::new();
+        credit(&mut balances, 1, &Nat::from(1_000_000_u64));
+        credit(&mut balances, 2, &Nat::from(1_000_000_u64));
+        let add_liquidity = BatchStep::AddLiquidity {
+            pool: Box::new(pool.clone()),
+            amount_0: None,
+            amount_1: None,
+            lock_secs: None,
+            deadline: None,
+        };
+
+        // the first removal works on the staged LP tokens of the add, the second removes more than is left
+        let steps = vec![add_liquidity, remove_liquidity(1_000), remove_liquidity(u64::MAX)];
+        let lp_token_map_idx = kong_settings_map::get().lp_token_map_idx;
+        let Err(e) = execute_steps(0, USER_ID, &steps, &balances, ts) else {
+            panic!("batch should fail");
+        };
+        assert!(e.starts_with("#3 Remove liquidity"), "{}", e);
+        let failed_pool = pool_map::get_by_pool_id(pool.pool_id).unwrap();
+        assert!(nat_is_zero(&failed_pool.balance_0) && nat_is_zero(&failed_pool.balance_1));
+        assert!(nat_is_zero(&lp_token_map::get_total_supply(pool.lp_token_id)));
+        assert!(lp_token_map::get_by_token_id_by_user_id(pool.lp_token_id, USER_ID).is_none());
+        assert_eq!(lp_block_map::get_log_length(pool.lp_token_id), 0);
+        assert!(tx_map::get_by_user_and_token_id(None, Some(USER_ID), None, Some(10)).is_empty());
+        // no LP token id is allocated for the discarded LP token
+        assert_eq!(kong_settings_map::get().lp_token_map_idx, lp_token_map_idx);
+
+        // without the failing step, all the operations are committed
+        let (step_replies, _, balances) = execute_steps(0, USER_ID, &steps[..2], &balances, ts).unwrap();
+        assert_eq!(step_replies.len(), 2);
+        let pool = pool_map::get_by_pool_id(pool.pool_id).unwrap();
+        assert!(!nat_is_zero(&pool.balance_0) && !nat_is_zero(&pool.balance_1));
+        let lp_token = lp_token_map::get_by_token_id_by_user_id(pool.lp_token_id, USER_ID).unwrap();
+        assert_eq!(lp_token.lp_token_id, lp_token_map_idx + 1);
+        assert_eq!(kong_settings_map::get().lp_token_map_idx, lp_token_map_idx + 1);
+        assert_eq!(lp_token_map::get_total_supply(pool.lp_token_id), lp_token.amount);
+        assert_eq!(lp_block_map::get_log_length(pool.lp_token_id), 2);
+        // the removed liquidity is back in the batch balances
+        assert!(balances.values().all(|amount| !nat_is_zero(amount)));
+    }
+}
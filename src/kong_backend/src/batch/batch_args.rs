@@ -0,0 +1,59 @@
+use candid::{CandidType, Nat};
+use serde::{Deserialize, Serialize};
+
+/// Data structure for the arguments of the `batch` function.
+/// Used in StableRequest
+///
+/// amounts that are specified are transferred from the user with icrc2_transfer_from before any operation is executed
+/// amounts that are None use the whole balance of the token the batch holds at that point, i.e. the outputs of the
+/// previous operations
+#[derive(CandidType, Debug, Clone, Serialize, Deserialize)]
+pub struct BatchArgs {
+    pub ops: Vec<BatchOp>,
+}
+
+#[derive(CandidType, Debug, Clone, Serialize, Deserialize)]
+pub enum BatchOp {
+    Swap(BatchSwapArgs),
+    AddLiquidity(BatchAddLiquidityArgs),
+    RemoveLiquidity(BatchRemoveLiquidityArgs),
+    Send(BatchSendArgs),
+}
+
+#[derive(CandidType, Debug, Clone, Serialize, Deserialize)]
+pub struct BatchSwapArgs {
+    pub pay_token: String,
+    pub pay_amount: Option<Nat>,
+    pub receive_token: String,
+    pub receive_amount: Option<Nat>, // min. receive amount
+    pub max_slippage: Option<f64>,
+    pub deadline: Option<u64>, // nanoseconds since the Unix epoch. the batch is rejected and refunded after the deadline
+}
+
+#[derive(CandidType, Debug, Clone, Serialize, Deserialize)]
+pub struct BatchAddLiquidityArgs {
+    pub token_0: String,
+    pub amount_0: Option<Nat>,
+    pub token_1: String,
+    pub amount_1: Option<Nat>,
+    pub lock_secs: Option<u64>,
+    pub fee_tier: Option<u8>,  // fee tier of the pool. None for the first pool of the pair
+    pub deadline: Option<u64>, // nanoseconds since the Unix epoch. the batch is rejected and refunded after the deadline
+}
+
+#[derive(CandidType, Debug, Clone, Serialize, Deserialize)]
+pub struct BatchRemoveLiquidityArgs {
+    pub token_0: String,
+    pub token_1: String,
+    pub remove_lp_token_amount: Nat,
+    pub position_id: Option<u64>, // position to remove liquidity from for concentrated pools
+    pub fee_tier: Option<u8>,     // fee tier of the pool. None for the first pool of the pair
+    pub deadline: Option<u64>,    // nanoseconds since the Unix epoch. the batch is rejected and refunded after the deadline
+}
+
+#[derive(CandidType, Debug, Clone, Serialize, Deserialize)]
+pub struct BatchSendArgs {
+    pub token: String,
+    pub amount: Option<Nat>,
+    pub to_address: String,
+}
@@ -0,0 +1,34 @@
+use candid::{CandidType, Nat};
+use serde::{Deserialize, Serialize};
+
+use crate::add_liquidity::add_liquidity_reply::AddLiquidityReply;
+use crate::remove_liquidity::remove_liquidity_reply::RemoveLiquidityReply;
+use crate::swap::swap_reply::SwapReply;
+use crate::transfers::transfer_reply::TransferIdReply;
+
+#[derive(CandidType, Debug, Clone, Serialize, Deserialize)]
+pub struct BatchReply {
+    pub request_id: u64,
+    pub status: String,
+    pub steps: Vec<BatchStepReply>, // executed operations, in order
+    pub transfer_ids: Vec<TransferIdReply>,
+    pub claim_ids: Vec<u64>,
+    pub ts: u64,
+}
+
+#[derive(CandidType, Debug, Clone, Serialize, Deserialize)]
+pub enum BatchStepReply {
+    Swap(SwapReply),
+    AddLiquidity(AddLiquidityReply),
+    RemoveLiquidity(RemoveLiquidityReply),
+    Send(BatchSendReply),
+}
+
+#[derive(CandidType, Debug, Clone, Serialize, Deserialize)]
+pub struct BatchSendReply {
+    pub chain: String,
+    pub symbol: String,
+    pub address: String,
+    pub amount: Nat,
+    pub to_address: String,
+}
@@ -3,6 +3,7 @@ use ic_cdk::api::call::{accept_message, method_name};
 use ic_cdk::{init, post_upgrade, pre_upgrade, query, update};
 use ic_cdk_macros::inspect_message;
 use ic_cdk_timers::set_timer_interval;
+use icrc_ledger_types::icrc1::account::Account;
 use icrc_ledger_types::icrc21::errors::ErrorInfo;
 use icrc_ledger_types::icrc21::requests::{ConsentMessageMetadata, ConsentMessageRequest};
 use icrc_ledger_types::icrc21::responses::{ConsentInfo, ConsentMessage};
@@ -14,17 +15,28 @@ use super::{APP_NAME, APP_VERSION};
 use crate::add_liquidity::add_liquidity_args::AddLiquidityArgs;
 use crate::add_liquidity::add_liquidity_reply::AddLiquidityReply;
 use crate::add_liquidity_amounts::add_liquidity_amounts_reply::AddLiquidityAmountsReply;
+use crate::add_liquidity_amounts::add_liquidity_single_amounts_reply::AddLiquiditySingleAmountsReply;
+use crate::add_liquidity_single::add_liquidity_single_args::AddLiquiditySingleArgs;
 use crate::add_pool::add_pool_args::AddPoolArgs;
 use crate::add_pool::add_pool_reply::AddPoolReply;
 use crate::add_token::add_token_args::AddTokenArgs;
 use crate::add_token::add_token_reply::AddTokenReply;
 use crate::add_token::update_token_args::UpdateTokenArgs;
 use crate::add_token::update_token_reply::UpdateTokenReply;
+use crate::batch::batch_args::BatchArgs;
+use crate::batch::batch_reply::BatchReply;
+use crate::certified::certified_tree;
 use crate::claims::claims_timer::process_claims_timer;
+use crate::fee_sweep::fee_sweep_timer::process_fee_sweep_timer;
 use crate::helpers::nat_helpers::{nat_to_decimals_f64, nat_to_f64};
+use crate::ic::address::Address;
+use crate::ic::address_helpers::get_address;
 use crate::ic::canister_address::KONG_BACKEND;
+use crate::ic::deadline::deadline_to_string;
 use crate::ic::id::caller_principal_id;
 use crate::ic::logging::info_log;
+use crate::limit_orders::limit_orders_timer::process_limit_orders_timer;
+use crate::remove_liquidity::remove_liquidity_args::RemoveLiquidityArgs;
 use crate::stable_kong_settings::kong_settings_map;
 use crate::stable_request::request_archive::archive_request_map;
 use crate::stable_token::token::Token;
@@ -33,21 +45,38 @@ use crate::stable_transfer::transfer_archive::archive_transfer_map;
 use crate::stable_tx::tx_archive::archive_tx_map;
 use crate::stable_user::principal_id_map::create_principal_id_map;
 use crate::swap::swap_args::SwapArgs;
+use crate::user_fee_tiers::user_fee_tiers_timer::process_user_fee_tiers_timer;
 
 // list of query calls
 // a bit hard-coded but shouldn't change often
-static QUERY_METHODS: [&str; 11] = [
+static QUERY_METHODS: [&str; 27] = [
     "icrc1_name",
     "icrc10_supported_standards",
     "tokens",
+    "tokens_certified",
     "pools",
+    "pools_certified",
     "get_user",
     "user_balances",
+    "user_balances_certified",
     "requests",
     "add_liquidity_amounts",
+    "add_liquidity_single_amounts",
     "remove_liquidity_amounts",
+    "remove_liquidity_single_amounts",
     "swap_amounts",
+    "swap_amounts_certified",
     "claims",
+    "limit_orders",
+    "twap",
+    "reward_campaigns",
+    "lp_ledger_metadata",
+    "lp_ledger_balance_of",
+    "lp_ledger_total_supply",
+    "lp_ledger_allowance",
+    "lp_ledger_get_blocks",
+    "lp_ledger_snapshot",
+    "lp_ledger_blocks",
 ];
 
 #[init]
@@ -55,6 +84,7 @@ async fn init() {
     info_log(&format!("{} canister has been initialized", APP_NAME));
 
     create_principal_id_map();
+    certified_tree::init();
 
     set_timer_processes().await;
 }
@@ -67,6 +97,7 @@ fn pre_upgrade() {
 #[post_upgrade]
 async fn post_upgrade() {
     create_principal_id_map();
+    certified_tree::init();
 
     set_timer_processes().await;
 
@@ -81,6 +112,27 @@ async fn set_timer_processes() {
         });
     });
 
+    // start the background timer to fill and expire limit orders
+    let _ = set_timer_interval(Duration::from_secs(kong_settings_map::get().limit_orders_interval_secs), || {
+        ic_cdk::spawn(async {
+            process_limit_orders_timer().await;
+        });
+    });
+
+    // start the background timer to sweep Kong fees to the fee treasury and KONG buyback
+    let _ = set_timer_interval(Duration::from_secs(kong_settings_map::get().fee_sweep_interval_secs), || {
+        ic_cdk::spawn(async {
+            process_fee_sweep_timer().await;
+        });
+    });
+
+    // start the background timer to refresh the fee levels of users from the fee tiers
+    let _ = set_timer_interval(Duration::from_secs(kong_settings_map::get().fee_tiers_interval_secs), || {
+        ic_cdk::spawn(async {
+            process_user_fee_tiers_timer();
+        });
+    });
+
     // start the background timer to archive request map
     let _ = set_timer_interval(Duration::from_secs(kong_settings_map::get().requests_archive_interval_secs), || {
         ic_cdk::spawn(async {
@@ -167,7 +219,15 @@ fn icrc21_canister_call_consent_message(consent_msg_request: ConsentMessageReque
                 description: "Failed to convert pay amount to f64".to_string(),
             })?;
             let to_address = match swap_args.receive_address {
-                Some(address) => address,
+                Some(ref address) => {
+                    let Ok(receive_token) = token_map::get_by_token(swap_args.receive_token.as_str()) else {
+                        Err(ErrorInfo {
+                            description: "Failed to get receive token".to_string(),
+                        })?
+                    };
+                    let address = get_address(&receive_token, address).map_err(|e| ErrorInfo { description: e })?;
+                    consent_address(&address)
+                }
                 None => caller_principal_id(),
             };
             let receive_token = match swap_args.receive_amount {
@@ -193,8 +253,12 @@ fn icrc21_canister_call_consent_message(consent_msg_request: ConsentMessageReque
 {}
 
 **Receive address:**
-{}",
-                pay_amount, swap_args.pay_token, receive_token, to_address
+{}{}",
+                pay_amount,
+                swap_args.pay_token,
+                receive_token,
+                to_address,
+                consent_deadline(swap_args.deadline)
             ))
         }
         "add_liquidity" | "add_liquidity_async" => {
@@ -221,6 +285,10 @@ fn icrc21_canister_call_consent_message(consent_msg_request: ConsentMessageReque
             let amount_1 = nat_to_decimals_f64(decimals_1, &add_liquidity_args.amount_1).ok_or_else(|| ErrorInfo {
                 description: "Failed to convert token_1 amount to f64".to_string(),
             })?;
+            let min_lp_token_amount = match add_liquidity_args.min_lp_token_amount {
+                Some(amount) => format!("\n\n**Min. LP token amount:**\n{}", amount),
+                None => String::new(),
+            };
             ConsentMessage::GenericDisplayMessage(format!(
                 "# Approve KongSwap add liquidity
 
@@ -228,8 +296,70 @@ fn icrc21_canister_call_consent_message(consent_msg_request: ConsentMessageReque
 {} {}
 
 **Token 1:**
-{} {}",
-                amount_0, add_liquidity_args.token_0, amount_1, add_liquidity_args.token_1
+{} {}{}{}",
+                amount_0,
+                add_liquidity_args.token_0,
+                amount_1,
+                add_liquidity_args.token_1,
+                min_lp_token_amount,
+                consent_deadline(add_liquidity_args.deadline)
+            ))
+        }
+        "remove_liquidity" | "remove_liquidity_async" => {
+            let Ok(remove_liquidity_args) = decode_one::<RemoveLiquidityArgs>(&consent_msg_request.arg) else {
+                Err(ErrorInfo {
+                    description: "Failed to decode RemoveLiquidityArgs".to_string(),
+                })?
+            };
+            let min_amount = |token: &str, min_amount: Option<Nat>| -> Result<String, ErrorInfo> {
+                let Some(min_amount) = min_amount else {
+                    return Ok(String::new());
+                };
+                let Ok(token) = token_map::get_by_token(token) else {
+                    Err(ErrorInfo {
+                        description: format!("Failed to get {}", token),
+                    })?
+                };
+                let min_amount = nat_to_decimals_f64(token.decimals(), &min_amount).ok_or_else(|| ErrorInfo {
+                    description: "Failed to convert min. amount to f64".to_string(),
+                })?;
+                Ok(format!(" (min. {})", min_amount))
+            };
+            let min_amount_0 = min_amount(&remove_liquidity_args.token_0, remove_liquidity_args.min_amount_0)?;
+            let min_amount_1 = min_amount(&remove_liquidity_args.token_1, remove_liquidity_args.min_amount_1)?;
+            let to_address = match remove_liquidity_args.receive_address {
+                Some(ref address) => {
+                    // the address must be supported by both tokens
+                    let to_addresses = [&remove_liquidity_args.token_0, &remove_liquidity_args.token_1]
+                        .into_iter()
+                        .map(|token| token_map::get_by_token(token).and_then(|token| get_address(&token, address)))
+                        .collect::<Result<Vec<Address>, String>>()
+                        .map_err(|e| ErrorInfo { description: e })?;
+                    consent_address(&to_addresses[0])
+                }
+                None => caller_principal_id(),
+            };
+            ConsentMessage::GenericDisplayMessage(format!(
+                "# Approve KongSwap remove liquidity
+
+**LP token amount:**
+{}
+
+**Token 0:**
+{}{}
+
+**Token 1:**
+{}{}
+
+**Receive address:**
+{}{}",
+                remove_liquidity_args.remove_lp_token_amount,
+                remove_liquidity_args.token_0,
+                min_amount_0,
+                remove_liquidity_args.token_1,
+                min_amount_1,
+                to_address,
+                consent_deadline(remove_liquidity_args.deadline)
             ))
         }
         "add_pool" => {
@@ -278,6 +408,28 @@ fn icrc21_canister_call_consent_message(consent_msg_request: ConsentMessageReque
     Ok(ConsentInfo { metadata, consent_message })
 }
 
+/// resolved destination for consent messages, with the subaccount shown apart from the owner
+fn consent_address(address: &Address) -> String {
+    match address {
+        Address::AccountId(account_id) => format!("{} (account id)", account_id),
+        Address::PrincipalId(Account {
+            owner,
+            subaccount: Some(subaccount),
+        }) => {
+            let subaccount = subaccount.iter().map(|byte| format!("{:02x}", byte)).collect::<String>();
+            format!("{} (subaccount {})", owner, subaccount)
+        }
+        Address::PrincipalId(account) => account.owner.to_text(),
+    }
+}
+
+fn consent_deadline(deadline: Option<u64>) -> String {
+    match deadline {
+        Some(deadline) => format!("\n\n**Deadline:**\n{}", deadline_to_string(deadline)),
+        None => String::new(),
+    }
+}
+
 #[derive(CandidType, Clone, Debug, Deserialize)]
 pub struct Icrc28TrustedOriginsResponse {
     pub trusted_origins: Vec<String>,
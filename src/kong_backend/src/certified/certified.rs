@@ -0,0 +1,117 @@
+use candid::Nat;
+use ic_cdk::api::data_certificate;
+use ic_cdk::query;
+use std::collections::BTreeSet;
+
+use super::certified_reply::{CertifiedPoolsReply, CertifiedSwapAmountsReply, CertifiedTokensReply, CertifiedUserBalancesReply};
+use super::certified_reply_helpers::{to_certified_lp_token_reply, to_certified_pool_reply, to_certified_token_reply};
+use super::certified_tree;
+
+use crate::helpers::nat_helpers::nat_is_zero;
+use crate::ic::guards::not_in_maintenance_mode;
+use crate::stable_lp_token::lp_token_map;
+use crate::stable_pool::pool_map;
+use crate::stable_token::token_map;
+use crate::stable_user::user_map;
+use crate::swap_amounts::swap_amounts::calculate_swap_amounts;
+
+/// the certificate is only available in non-replicated query calls
+fn get_certificate() -> Result<Vec<u8>, String> {
+    data_certificate().ok_or("Certificate not available. Must be called as a query".to_string())
+}
+
+/// pools(opt wildcard) with the certificate and a witness of all the pools
+#[query(guard = "not_in_maintenance_mode")]
+fn pools_certified(symbol: Option<String>) -> Result<CertifiedPoolsReply, String> {
+    let certificate = get_certificate()?;
+    let pools = match symbol.as_deref() {
+        Some(symbol) => pool_map::get_by_token_wildcard(symbol),
+        None => pool_map::get(),
+    }
+    .iter()
+    .map(to_certified_pool_reply)
+    .collect();
+    let witness = certified_tree::witness(true, false, None)?;
+
+    Ok(CertifiedPoolsReply {
+        pools,
+        certificate,
+        witness,
+    })
+}
+
+/// tokens(opt wildcard) with the certificate and a witness of all the tokens
+#[query(guard = "not_in_maintenance_mode")]
+fn tokens_certified(symbol: Option<String>) -> Result<CertifiedTokensReply, String> {
+    let certificate = get_certificate()?;
+    let tokens = match symbol.as_deref() {
+        Some(symbol) => token_map::get_by_token_wildcard(symbol),
+        None => token_map::get(),
+    }
+    .iter()
+    .map(to_certified_token_reply)
+    .collect();
+    let witness = certified_tree::witness(false, true, None)?;
+
+    Ok(CertifiedTokensReply {
+        tokens,
+        certificate,
+        witness,
+    })
+}
+
+/// LP token balances of the user and their pools with the certificate and a witness of all the LP token balances of
+/// the user and all the pools. the witness proves absence if the user has no LP tokens
+#[query(guard = "not_in_maintenance_mode")]
+fn user_balances_certified(principal_id: String) -> Result<CertifiedUserBalancesReply, String> {
+    let certificate = get_certificate()?;
+    let lp_tokens = match user_map::get_by_principal_id(&principal_id)? {
+        Some(user) => lp_token_map::get_by_user_id(user.user_id)
+            .into_iter()
+            .filter(|lp_token| !nat_is_zero(&lp_token.amount))
+            .collect(),
+        None => Vec::new(),
+    };
+    let pools = lp_tokens
+        .iter()
+        .filter_map(|lp_token| pool_map::get_by_lp_token_id(lp_token.token_id))
+        .map(|pool| to_certified_pool_reply(&pool))
+        .collect();
+    let witness = certified_tree::witness(true, false, Some(&principal_id))?;
+
+    Ok(CertifiedUserBalancesReply {
+        lp_tokens: lp_tokens.iter().map(to_certified_lp_token_reply).collect(),
+        pools,
+        certificate,
+        witness,
+    })
+}
+
+/// swap_amounts() with the pools and tokens it was calculated from, the certificate and a witness of all the pools and
+/// tokens. the caller can verify the pools and tokens and recalculate the swap amounts from them
+#[query(guard = "not_in_maintenance_mode")]
+fn swap_amounts_certified(pay_token: String, pay_amount: Nat, receive_token: String) -> Result<CertifiedSwapAmountsReply, String> {
+    let certificate = get_certificate()?;
+    let (swap_amounts, txs) = calculate_swap_amounts(&pay_token, pay_amount, &receive_token)?;
+    let pools = txs
+        .iter()
+        .map(|tx| tx.pool_id)
+        .collect::<BTreeSet<u32>>()
+        .into_iter()
+        .filter_map(pool_map::get_by_pool_id)
+        .map(|pool| to_certified_pool_reply(&pool))
+        .collect();
+    let tokens = [token_map::get_by_token(&pay_token)?, token_map::get_by_token(&receive_token)?]
+        .iter()
+        .map(to_certified_token_reply)
+        .collect();
+    let witness = certified_tree::witness(true, true, None)?;
+
+    Ok(CertifiedSwapAmountsReply {
+        swap_amounts,
+        pools,
+        tokens,
+        certificate,
+        witness,
+    })
+}
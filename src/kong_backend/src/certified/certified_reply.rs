@@ -0,0 +1,71 @@
+use candid::{CandidType, Nat};
+use serde::{Deserialize, Serialize};
+
+use crate::swap_amounts::swap_amounts_reply::SwapAmountsReply;
+
+/// pool reserves as certified in the tree at pools/{pool_id}
+#[derive(CandidType, Debug, Clone, Serialize, Deserialize)]
+pub struct CertifiedPoolReply {
+    pub pool_id: u32,
+    pub token_id_0: u32,
+    pub balance_0: Nat,
+    pub lp_fee_0: Nat,
+    pub token_id_1: u32,
+    pub balance_1: Nat,
+    pub lp_fee_1: Nat,
+    pub lp_fee_bps: u8,
+    pub lp_token_id: u32,
+    pub is_removed: bool,
+}
+
+/// token as certified in the tree at tokens/{token_id}
+#[derive(CandidType, Debug, Clone, Serialize, Deserialize)]
+pub struct CertifiedTokenReply {
+    pub token_id: u32,
+    pub chain: String,
+    pub address: String,
+    pub symbol: String,
+    pub decimals: u8,
+    pub fee: Nat,
+    pub is_removed: bool,
+}
+
+/// LP token balance as certified in the tree at lp_tokens/{principal_id}/{token_id}
+#[derive(CandidType, Debug, Clone, Serialize, Deserialize)]
+pub struct CertifiedLPTokenReply {
+    pub token_id: u32,
+    pub amount: Nat,
+}
+
+/// certificate is the CBOR encoded certificate of the subnet with the canister's certified data
+/// witness is the CBOR encoded hash tree of the certified data with the records of the reply
+#[derive(CandidType, Debug, Clone, Serialize, Deserialize)]
+pub struct CertifiedPoolsReply {
+    pub pools: Vec<CertifiedPoolReply>,
+    pub certificate: Vec<u8>,
+    pub witness: Vec<u8>,
+}
+
+#[derive(CandidType, Debug, Clone, Serialize, Deserialize)]
+pub struct CertifiedTokensReply {
+    pub tokens: Vec<CertifiedTokenReply>,
+    pub certificate: Vec<u8>,
+    pub witness: Vec<u8>,
+}
+
+#[derive(CandidType, Debug, Clone, Serialize, Deserialize)]
+pub struct CertifiedUserBalancesReply {
+    pub lp_tokens: Vec<CertifiedLPTokenReply>,
+    pub pools: Vec<CertifiedPoolReply>, // pools of the LP tokens
+    pub certificate: Vec<u8>,
+    pub witness: Vec<u8>,
+}
+
+#[derive(CandidType, Debug, Clone, Serialize, Deserialize)]
+pub struct CertifiedSwapAmountsReply {
+    pub swap_amounts: SwapAmountsReply,
+    pub pools: Vec<CertifiedPoolReply>,   // pools the swap amounts were calculated from
+    pub tokens: Vec<CertifiedTokenReply>, // pay and receive tokens
+    pub certificate: Vec<u8>,
+    pub witness: Vec<u8>,
+}
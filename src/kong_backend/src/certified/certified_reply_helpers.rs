@@ -0,0 +1,40 @@
+use super::certified_reply::{CertifiedLPTokenReply, CertifiedPoolReply, CertifiedTokenReply};
+
+use crate::stable_lp_token::stable_lp_token::StableLPToken;
+use crate::stable_pool::stable_pool::StablePool;
+use crate::stable_token::stable_token::StableToken;
+use crate::stable_token::token::Token;
+
+pub fn to_certified_pool_reply(pool: &StablePool) -> CertifiedPoolReply {
+    CertifiedPoolReply {
+        pool_id: pool.pool_id,
+        token_id_0: pool.token_id_0,
+        balance_0: pool.balance_0.clone(),
+        lp_fee_0: pool.lp_fee_0.clone(),
+        token_id_1: pool.token_id_1,
+        balance_1: pool.balance_1.clone(),
+        lp_fee_1: pool.lp_fee_1.clone(),
+        lp_fee_bps: pool.lp_fee_bps,
+        lp_token_id: pool.lp_token_id,
+        is_removed: pool.is_removed,
+    }
+}
+
+pub fn to_certified_token_reply(token: &StableToken) -> CertifiedTokenReply {
+    CertifiedTokenReply {
+        token_id: token.token_id(),
+        chain: token.chain(),
+        address: token.address(),
+        symbol: token.symbol(),
+        decimals: token.decimals(),
+        fee: token.fee(),
+        is_removed: token.is_removed(),
+    }
+}
+
+pub fn to_certified_lp_token_reply(lp_token: &StableLPToken) -> CertifiedLPTokenReply {
+    CertifiedLPTokenReply {
+        token_id: lp_token.token_id,
+        amount: lp_token.amount.clone(),
+    }
+}
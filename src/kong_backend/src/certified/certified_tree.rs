@@ -0,0 +1,173 @@
+use ic_cdk::api::set_certified_data;
+use ic_certified_map::{fork, fork_hash, labeled, labeled_hash, AsHashTree, Hash, HashTree, RbTree};
+use serde::Serialize;
+use serde_cbor::Serializer;
+use std::cell::RefCell;
+
+use super::certified_hash::{hash_lp_token, hash_pool, hash_token};
+use super::certified_reply_helpers::{to_certified_lp_token_reply, to_certified_pool_reply, to_certified_token_reply};
+
+use crate::helpers::nat_helpers::nat_is_zero;
+use crate::stable_lp_token::stable_lp_token::StableLPToken;
+use crate::stable_memory::{LP_TOKEN_MAP, POOL_MAP, TOKEN_MAP};
+use crate::stable_pool::stable_pool::StablePool;
+use crate::stable_token::stable_token::StableToken;
+use crate::stable_token::token::Token;
+use crate::stable_user::user_map;
+
+const LABEL_LP_TOKENS: &[u8] = b"lp_tokens";
+const LABEL_POOLS: &[u8] = b"pools";
+const LABEL_TOKENS: &[u8] = b"tokens";
+
+/// Merkle tree of the pool reserves, the token registry and the LP token balances. its root hash is the certified data
+/// of the canister, so query replies with a witness of the tree and the certificate can be verified by the caller
+///
+/// lp_tokens/{principal_id}/{token_id} - LP token balance of a user
+/// pools/{pool_id} - pool reserves
+/// tokens/{token_id} - token
+///
+/// ids are big-endian bytes and principal_id is its text. the tree is on the heap and rebuilt on init and post_upgrade
+#[derive(Default)]
+struct CertifiedTree {
+    lp_tokens: RbTree<Vec<u8>, RbTree<Vec<u8>, Hash>>,
+    pools: RbTree<Vec<u8>, Hash>,
+    tokens: RbTree<Vec<u8>, Hash>,
+}
+
+impl CertifiedTree {
+    // labels are in order, lp_tokens < pools < tokens
+    fn root_hash(&self) -> Hash {
+        fork_hash(
+            &labeled_hash(LABEL_LP_TOKENS, &self.lp_tokens.root_hash()),
+            &fork_hash(
+                &labeled_hash(LABEL_POOLS, &self.pools.root_hash()),
+                &labeled_hash(LABEL_TOKENS, &self.tokens.root_hash()),
+            ),
+        )
+    }
+}
+
+thread_local! {
+    static CERTIFIED_TREE: RefCell<CertifiedTree> = RefCell::default();
+}
+
+/// build the tree from stable memory and certify it
+pub fn init() {
+    CERTIFIED_TREE.with(|t| {
+        let mut tree = t.borrow_mut();
+        *tree = CertifiedTree::default();
+        POOL_MAP.with(|m| {
+            m.borrow().iter().for_each(|(_, pool)| {
+                tree.pools.insert(pool_key(pool.pool_id), hash_pool(&to_certified_pool_reply(&pool)));
+            })
+        });
+        TOKEN_MAP.with(|m| {
+            m.borrow().iter().for_each(|(_, token)| {
+                tree.tokens.insert(token_key(token.token_id()), hash_token(&to_certified_token_reply(&token)));
+            })
+        });
+        LP_TOKEN_MAP.with(|m| {
+            m.borrow().iter().for_each(|(_, lp_token)| insert_lp_token(&mut tree, &lp_token));
+        });
+    });
+    certify();
+}
+
+pub fn update_pool(pool: &StablePool) {
+    CERTIFIED_TREE.with(|t| {
+        t.borrow_mut()
+            .pools
+            .insert(pool_key(pool.pool_id), hash_pool(&to_certified_pool_reply(pool)))
+    });
+    certify();
+}
+
+pub fn update_token(token: &StableToken) {
+    CERTIFIED_TREE.with(|t| {
+        t.borrow_mut()
+            .tokens
+            .insert(token_key(token.token_id()), hash_token(&to_certified_token_reply(token)))
+    });
+    certify();
+}
+
+pub fn update_lp_token(lp_token: &StableLPToken) {
+    CERTIFIED_TREE.with(|t| insert_lp_token(&mut t.borrow_mut(), lp_token));
+    certify();
+}
+
+/// zero balances are not in the tree
+fn insert_lp_token(tree: &mut CertifiedTree, lp_token: &StableLPToken) {
+    let Some(user) = user_map::get_by_user_id(lp_token.user_id) else {
+        return;
+    };
+    let user_key = user.principal_id.into_bytes();
+    let key = token_key(lp_token.token_id);
+    if nat_is_zero(&lp_token.amount) {
+        if tree.lp_tokens.get(&user_key).is_some() {
+            tree.lp_tokens.modify(&user_key, |lp_tokens| lp_tokens.delete(&key));
+            if tree.lp_tokens.get(&user_key).is_some_and(|lp_tokens| lp_tokens.iter().next().is_none()) {
+                tree.lp_tokens.delete(&user_key);
+            }
+        }
+        return;
+    }
+
+    let hash = hash_lp_token(&to_certified_lp_token_reply(lp_token));
+    if tree.lp_tokens.get(&user_key).is_some() {
+        tree.lp_tokens.modify(&user_key, |lp_tokens| lp_tokens.insert(key, hash));
+    } else {
+        let mut lp_tokens = RbTree::new();
+        lp_tokens.insert(key, hash);
+        tree.lp_tokens.insert(user_key, lp_tokens);
+    }
+}
+
+fn certify() {
+    let root_hash = CERTIFIED_TREE.with(|t| t.borrow().root_hash());
+    // certified data can only be set inside a canister
+    if cfg!(target_arch = "wasm32") {
+        set_certified_data(&root_hash);
+    }
+}
+
+/// CBOR encoded witness of the tree with all the pools and/or tokens, and the LP token balances of principal_id
+/// the parts of the tree not asked for are pruned
+pub fn witness(with_pools: bool, with_tokens: bool, principal_id: Option<&str>) -> Result<Vec<u8>, String> {
+    CERTIFIED_TREE.with(|t| {
+        let tree = t.borrow();
+        let lp_tokens = match principal_id {
+            Some(principal_id) => labeled(
+                LABEL_LP_TOKENS,
+                tree.lp_tokens.nested_witness(principal_id.as_bytes(), |lp_tokens| lp_tokens.as_hash_tree()),
+            ),
+            None => HashTree::Pruned(labeled_hash(LABEL_LP_TOKENS, &tree.lp_tokens.root_hash())),
+        };
+        let pools = if with_pools {
+            labeled(LABEL_POOLS, tree.pools.as_hash_tree())
+        } else {
+            HashTree::Pruned(labeled_hash(LABEL_POOLS, &tree.pools.root_hash()))
+        };
+        let tokens = if with_tokens {
+            labeled(LABEL_TOKENS, tree.tokens.as_hash_tree())
+        } else {
+            HashTree::Pruned(labeled_hash(LABEL_TOKENS, &tree.tokens.root_hash()))
+        };
+        let witness = fork(lp_tokens, fork(pools, tokens));
+
+        let mut serializer = Serializer::new(Vec::new());
+        serializer.self_describe().map_err(|e| format!("Failed to serialize witness: {}", e))?;
+        witness
+            .serialize(&mut serializer)
+            .map_err(|e| format!("Failed to serialize witness: {}", e))?;
+        Ok(serializer.into_inner())
+    })
+}
+
+fn pool_key(pool_id: u32) -> Vec<u8> {
+    pool_id.to_be_bytes().to_vec()
+}
+
+fn token_key(token_id: u32) -> Vec<u8> {
+    token_id.to_be_bytes().to_vec()
+}
@@ -0,0 +1,6 @@
+#[allow(clippy::module_inception)]
+pub mod certified;
+pub mod certified_hash;
+pub mod certified_reply;
+pub mod certified_reply_helpers;
+pub mod certified_tree;
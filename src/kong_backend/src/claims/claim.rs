@@ -5,6 +5,7 @@ use super::claim_reply::ClaimReply;
 use super::process_claim::process_claim;
 
 use crate::ic::address::Address;
+use crate::ic::address_helpers::get_address;
 use crate::ic::get_time::get_time;
 use crate::ic::guards::not_in_maintenance_mode;
 use crate::ic::id::{caller_id, caller_principal_id};
@@ -19,8 +20,9 @@ use crate::stable_user::user_map;
 
 /// Claim a claimable claim
 /// used by user to claim a claimable claim which exists in CLAIM_MAP
+/// to_address, if specified, overrides the address of the claim
 #[update(guard = "not_in_maintenance_mode")]
-async fn claim(claim_id: u64) -> Result<ClaimReply, String> {
+async fn claim(claim_id: u64, to_address: Option<String>) -> Result<ClaimReply, String> {
     let claim = claim_map::get_by_claim_id(claim_id).ok_or("Claim not found")?;
     let token = token_map::get_by_token_id(claim.token_id).ok_or("Token not found")?;
     // make sure the caller is the owner of the claim
@@ -39,10 +41,13 @@ async fn claim(claim_id: u64) -> Result<ClaimReply, String> {
     };
 
     let ts = get_time();
-    // if to_address is not provided, use the caller's principal id
-    let to_address = match &claim.to_address {
-        Some(address) => address.clone(),
-        None => Address::PrincipalId(caller_id()),
+    // use specified address, or the address of the claim. if neither, use the caller's principal id
+    let to_address = match to_address {
+        Some(ref address) => get_address(&token, address)?,
+        None => match &claim.to_address {
+            Some(address) => address.clone(),
+            None => Address::PrincipalId(caller_id()),
+        },
     };
 
     // register new request for this claim
@@ -1,5 +1,6 @@
 use candid::Nat;
 
+use super::archive_to_kong_data::archive_to_kong_data;
 use super::claim_reply::ClaimReply;
 
 use crate::helpers::nat_helpers::{nat_subtract, nat_zero};
@@ -9,8 +10,8 @@ use crate::ic::{
 };
 use crate::stable_claim::claim_map;
 use crate::stable_claim::stable_claim::{ClaimStatus, StableClaim};
-use crate::stable_request::{reply::Reply, request_map, status::StatusCode};
-use crate::stable_token::{stable_token::StableToken, token::Token};
+use crate::stable_request::{reply::Reply, request::Request, request_map, stable_request::StableRequest, status::StatusCode};
+use crate::stable_token::{stable_token::StableToken, token::Token, token_map};
 use crate::stable_transfer::{stable_transfer::StableTransfer, transfer_map, tx_id::TxId};
 use crate::transfers::transfer_reply_helpers::to_transfer_ids;
 
@@ -67,6 +68,36 @@ pub async fn process_claim(
     Ok(reply)
 }
 
+/// process newly created claims of a user, such as rewards, and return their replies
+/// failed claims stay unclaimed and are retried by the claims timer
+pub async fn process_new_claims(user_id: u32, claim_ids: &[u64], to_address: &Address, ts: u64) -> Vec<ClaimReply> {
+    let mut replies = Vec::new();
+    for &claim_id in claim_ids {
+        let Some(claim) = claim_map::get_by_claim_id(claim_id) else {
+            continue;
+        };
+        let Some(token) = token_map::get_by_token_id(claim.token_id) else {
+            continue;
+        };
+        let request_id = request_map::insert(&StableRequest::new(user_id, &Request::Claim(claim_id), ts));
+        match process_claim(request_id, &claim, &token, &claim.amount, to_address, ts).await {
+            Ok(reply) => {
+                request_map::update_status(request_id, StatusCode::Success, None);
+                replies.push(reply);
+            }
+            Err(_) => {
+                request_map::update_status(request_id, StatusCode::Failed, None);
+                // failed reply is saved in the request
+                if let Some(Reply::Claim(reply)) = request_map::get_by_request_id(request_id).map(|request| request.reply) {
+                    replies.push(reply);
+                }
+            }
+        }
+        let _ = archive_to_kong_data(request_id);
+    }
+    replies
+}
+
 async fn send_claim(
     request_id: u64,
     claim: &StableClaim,
@@ -33,6 +33,7 @@ fn to_check_pool_reply(
 }
 
 /// check integrity of the pools. compare the expected balances stored in stable memory versus the actual balances in the canister.
+/// StableSwap pools also check their balances satisfy the invariant
 /// maybe not be 100% accurate if in use as canister balances can change
 ///
 /// # Returns
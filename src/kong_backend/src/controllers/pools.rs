@@ -6,12 +6,16 @@ use std::collections::BTreeMap;
 
 use crate::helpers::json_helpers;
 use crate::helpers::nat_helpers::{nat_add, nat_subtract, nat_zero};
+use crate::ic::address::Address;
+use crate::ic::get_time::get_time;
 use crate::ic::guards::caller_is_kingkong;
 use crate::remove_liquidity::remove_liquidity::remove_liquidity_from_pool;
 use crate::remove_liquidity::remove_liquidity_args::RemoveLiquidityArgs;
 use crate::stable_lp_token::lp_token_map;
 use crate::stable_memory::{LP_TOKEN_MAP, POOL_MAP};
+use crate::stable_pool::dynamic_fee::DynamicFee;
 use crate::stable_pool::pool_map;
+use crate::stable_pool::pool_type::PoolType;
 use crate::stable_pool::stable_pool::{StablePool, StablePoolId};
 use crate::stable_token::token::Token;
 use crate::stable_user::user_map;
@@ -90,26 +94,38 @@ async fn remove_lps_from_pool(symbol: String) -> Result<String, String> {
     let lp_token_id = pool.lp_token_id;
 
     // list of all LP positions to remove
-    // (user_id, principal_id, lp token amount)
-    let lp_users = LP_TOKEN_MAP.with(|m| {
+    // (user_id, principal_id, lp token amount, position_id)
+    let mut lp_users = LP_TOKEN_MAP.with(|m| {
         m.borrow()
             .iter()
             .filter_map(|(_, v)| {
                 if v.token_id == lp_token_id {
                     let user = user_map::get_by_user_id(v.user_id)?;
-                    Some((user.user_id, user.principal_id, v.amount))
+                    Some((user.user_id, user.principal_id, v.amount, None))
                 } else {
                     None
                 }
             })
             .collect::<Vec<_>>()
     });
+    // concentrated pools have positions instead of LP tokens
+    if let PoolType::Concentrated(concentrated_pool) = &pool.pool_type {
+        lp_users.extend(concentrated_pool.get_positions().into_iter().filter_map(|position| {
+            let user = user_map::get_by_user_id(position.user_id)?;
+            Some((
+                user.user_id,
+                user.principal_id,
+                position.liquidity.clone(),
+                Some(position.position_id),
+            ))
+        }));
+    }
 
     // remove_liquidity for each user
     let token_0 = pool.token_0().address_with_chain();
     let token_1 = pool.token_1().address_with_chain();
     let mut results = Vec::new();
-    for (user_id, principal_id, remove_lp_token_amount) in lp_users {
+    for (user_id, principal_id, remove_lp_token_amount, position_id) in lp_users {
         // skip if user has no LP position
         if remove_lp_token_amount == nat_zero() {
             continue;
@@ -118,11 +134,18 @@ async fn remove_lps_from_pool(symbol: String) -> Result<String, String> {
             token_0: token_0.clone(),
             token_1: token_1.clone(),
             remove_lp_token_amount,
+            position_id,
+            use_deposit: None,
+            receive_address: None,
+            fee_tier: pool.fee_tier,
+            min_amount_0: None,
+            min_amount_1: None,
+            deadline: None,
         };
         match Principal::from_text(principal_id) {
             Ok(principal) => {
-                let to_principal_id = Account::from(principal);
-                match remove_liquidity_from_pool(args, user_id, &to_principal_id).await {
+                let to_address = Address::PrincipalId(Account::from(principal));
+                match remove_liquidity_from_pool(args, user_id, &to_address).await {
                     Ok(_) => {
                         results.push(format!("Removed user_id {} LP position", user_id));
                     }
@@ -184,6 +207,7 @@ fn unsuspend_pool(symbol: String) -> Result<String, String> {
 #[update(hidden = true, guard = "caller_is_kingkong")]
 fn adjust_pool_balances(symbol: String, direction: String, amount_0: Nat, amount_1: Nat) -> Result<String, String> {
     let mut pool = pool_map::get_by_token(&symbol)?;
+    pool.update_price_cumulative(get_time());
     if direction == "add" {
         pool.balance_0 = nat_add(&pool.balance_0, &amount_0);
         pool.balance_1 = nat_add(&pool.balance_1, &amount_1);
@@ -201,3 +225,58 @@ fn adjust_pool_balances(symbol: String, direction: String, amount_0: Nat, amount
         symbol, pool.balance_0, pool.balance_1
     ))
 }
+
+/// ramp the amp of a StableSwap pool
+/// symbol = pool token symbol
+/// future_amp = amp to ramp to, at most 10x the current amp in either direction
+/// ramp_secs = seconds to linearly ramp from the current amp to future_amp, 0 to change immediately
+#[update(hidden = true, guard = "caller_is_kingkong")]
+fn ramp_pool_amp(symbol: String, future_amp: u64, ramp_secs: u64) -> Result<String, String> {
+    let mut pool = pool_map::get_by_token(&symbol)?;
+    let ts = get_time();
+    match pool.pool_type {
+        PoolType::StableSwap(ref mut stable_swap) => {
+            stable_swap.ramp_amp(future_amp, ts + ramp_secs * 1_000_000_000, ts)?;
+        }
+        _ => Err(format!("Pool {} is not a StableSwap pool", symbol))?,
+    }
+
+    pool_map::update(&pool);
+
+    Ok(format!("Pool {} amp ramping to {} in {} secs", symbol, future_amp, ramp_secs))
+}
+
+/// set a dynamic LP fee on a pool that follows the recent volatility of each trade direction
+/// symbol = pool token symbol
+/// min_fee_bps, max_fee_bps = bounds of the LP fee in basis points
+/// volatility_multiplier_pct = percent of the decayed volatility (in basis points) added to min_fee_bps
+/// decay_secs = half-life of the volatility accumulators
+#[update(hidden = true, guard = "caller_is_kingkong")]
+fn set_pool_dynamic_fee(
+    symbol: String,
+    min_fee_bps: u8,
+    max_fee_bps: u8,
+    volatility_multiplier_pct: u32,
+    decay_secs: u64,
+) -> Result<String, String> {
+    let mut pool = pool_map::get_by_token(&symbol)?;
+    if min_fee_bps < pool.kong_fee_bps {
+        Err(format!("Min fee must be at least Kong's fee of {} bps", pool.kong_fee_bps))?
+    }
+    pool.dynamic_fee = Some(DynamicFee::new(min_fee_bps, max_fee_bps, volatility_multiplier_pct, decay_secs)?);
+
+    pool_map::update(&pool);
+
+    Ok(format!("Pool {} dynamic fee set to {}-{} bps", symbol, min_fee_bps, max_fee_bps))
+}
+
+/// remove the dynamic LP fee of a pool, reverting to its fixed lp_fee_bps
+#[update(hidden = true, guard = "caller_is_kingkong")]
+fn remove_pool_dynamic_fee(symbol: String) -> Result<String, String> {
+    let mut pool = pool_map::get_by_token(&symbol)?;
+    pool.dynamic_fee = None;
+
+    pool_map::update(&pool);
+
+    Ok(format!("Pool {} dynamic fee removed. LP fee is {} bps", symbol, pool.lp_fee_bps))
+}
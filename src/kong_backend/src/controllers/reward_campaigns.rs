@@ -0,0 +1,48 @@
+use candid::Nat;
+use ic_cdk::update;
+
+use crate::helpers::nat_helpers::nat_is_zero;
+use crate::ic::get_time::get_time;
+use crate::ic::guards::caller_is_kingkong;
+use crate::stable_deposit::deposit_map;
+use crate::stable_pool::pool_map;
+use crate::stable_reward_campaign::reward_campaign_map;
+use crate::stable_reward_campaign::stable_reward_campaign::StableRewardCampaign;
+use crate::stable_token::token::Token;
+use crate::stable_token::token_map;
+use crate::stable_user::user_map;
+
+/// create a liquidity mining campaign streaming reward_amount of reward_token to the LPs of pool
+/// between start_ts and end_ts (nanoseconds). the campaign is funded from the caller's deposit, so reward_amount of
+/// reward_token must be deposited first with deposit()
+#[update(hidden = true, guard = "caller_is_kingkong")]
+fn create_reward_campaign(pool: String, reward_token: String, reward_amount: Nat, start_ts: u64, end_ts: u64) -> Result<String, String> {
+    let pool = pool_map::get_by_token(&pool)?;
+    let reward_token = token_map::get_by_token(&reward_token)?;
+    if reward_token.is_removed() {
+        Err("Reward token is suspended or removed".to_string())?
+    }
+    if nat_is_zero(&reward_amount) {
+        Err("Reward amount is zero".to_string())?
+    }
+    let ts = get_time();
+    if end_ts <= start_ts {
+        Err("End time must be after start time".to_string())?
+    }
+    if end_ts <= ts {
+        Err("End time is in the past".to_string())?
+    }
+    let user_id = user_map::get_by_caller()?.ok_or("Insufficient deposit balance")?.user_id;
+    deposit_map::debit(user_id, reward_token.token_id(), &reward_amount, ts).map_err(|e| format!("Reward campaign not funded. {}", e))?;
+
+    let campaign_id = reward_campaign_map::insert(&StableRewardCampaign::new(
+        pool.lp_token_id,
+        reward_token.token_id(),
+        &reward_amount,
+        start_ts,
+        end_ts,
+        ts,
+    ));
+
+    Ok(format!("Reward campaign #{} created for pool {}", campaign_id, pool.symbol()))
+}
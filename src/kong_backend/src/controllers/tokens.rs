@@ -1,8 +1,14 @@
+use candid::Principal;
 use ic_cdk::{query, update};
 use std::collections::BTreeMap;
 
+use crate::ic::get_time::get_time;
 use crate::ic::guards::caller_is_kingkong;
+use crate::lp_ledger::lp_ledger_canister;
+use crate::stable_claim::{claim_map, stable_claim::StableClaim};
 use crate::stable_memory::TOKEN_MAP;
+use crate::stable_token::ic_token::ICToken;
+use crate::stable_token::lp_token::LPToken;
 use crate::stable_token::stable_token::{StableToken, StableTokenId};
 use crate::stable_token::token_map;
 
@@ -52,6 +58,10 @@ fn unsuspend_token(symbol: String) -> Result<String, String> {
     match token {
         StableToken::LP(_) => return Err("Cannot unsuspend LP tokens".to_string()),
         StableToken::IC(token) => {
+            // symbols of removed tokens can be reused, so another listed token may have taken it
+            if token.is_removed && token_map::symbol_exists(&format!("{}.{}", token.chain(), token.symbol)) {
+                Err(format!("Token symbol {} already exists", token.symbol))?
+            }
             token_map::unremove(token.token_id)?;
         }
     }
@@ -59,6 +69,90 @@ fn unsuspend_token(symbol: String) -> Result<String, String> {
     Ok(format!("Token {} unsuspended", symbol))
 }
 
+/// set the canister id of the ICRC-1/ICRC-2/ICRC-3 ledger of an LP token, a kong_lp_ledger canister deployed for the LP token
+/// only the ledger can move the LP tokens through the lp_ledger endpoints. None removes the ledger
+#[update(hidden = true, guard = "caller_is_kingkong")]
+fn set_lp_token_ledger(symbol: String, ledger_id: Option<String>) -> Result<String, String> {
+    let token = match token_map::get_by_token(&symbol)? {
+        StableToken::LP(token) => token,
+        StableToken::IC(_) => Err("Token is not an LP token".to_string())?,
+    };
+    if let Some(ref ledger_id) = ledger_id {
+        Principal::from_text(ledger_id).map_err(|e| format!("Invalid ledger id: {}", e))?;
+    }
+    token_map::update(&StableToken::LP(LPToken {
+        ledger_id: ledger_id.clone(),
+        ..token
+    }));
+
+    Ok(match ledger_id {
+        Some(ledger_id) => format!("Token {} ledger set to {}", symbol, ledger_id),
+        None => format!("Token {} ledger removed", symbol),
+    })
+}
+
+/// approve a token on probation. the listing deposit is returned to the user as a claim
+#[update(hidden = true, guard = "caller_is_kingkong")]
+fn approve_token(symbol: String) -> Result<String, String> {
+    let token = get_token_on_probation(&symbol)?;
+    let message = match token.listing_deposit {
+        Some(ref deposit) => {
+            let mut claim = StableClaim::new(deposit.user_id, deposit.token_id, &deposit.amount, None, None, get_time());
+            claim.desc = Some(format!("Listing deposit refund of {}", token.symbol));
+            let claim_id = claim_map::insert(&claim);
+            format!("Token {} approved. Listing deposit returned as claim #{}", symbol, claim_id)
+        }
+        None => format!("Token {} approved", symbol),
+    };
+    token_map::update(&StableToken::IC(ICToken {
+        on_probation: false,
+        listing_deposit: None,
+        ..token
+    }));
+
+    Ok(message)
+}
+
+/// reject a token on probation. the token is suspended and the listing deposit is forfeited
+#[update(hidden = true, guard = "caller_is_kingkong")]
+fn reject_token(symbol: String) -> Result<String, String> {
+    let token = get_token_on_probation(&symbol)?;
+    token_map::update(&StableToken::IC(ICToken {
+        is_removed: true,
+        on_probation: false,
+        listing_deposit: None,
+        ..token
+    }));
+
+    Ok(format!("Token {} rejected. Listing deposit forfeited", symbol))
+}
+
+fn get_token_on_probation(symbol: &str) -> Result<ICToken, String> {
+    match token_map::get_by_token(symbol)? {
+        StableToken::IC(token) if token.on_probation => Ok(token),
+        _ => Err(format!("Token {} is not on probation", symbol)),
+    }
+}
+
+/// set the wasm module of kong_lp_ledger. ledgers are created with it for new LP tokens and by create_lp_token_ledger
+#[update(hidden = true, guard = "caller_is_kingkong")]
+fn set_lp_token_ledger_wasm(wasm: Vec<u8>) -> Result<String, String> {
+    let size = wasm.len();
+    lp_ledger_canister::set_wasm(wasm)?;
+    Ok(format!("LP ledger wasm set to {} bytes", size))
+}
+
+/// create the kong_lp_ledger canister of an LP token and set it as the LP token's ledger
+#[update(hidden = true, guard = "caller_is_kingkong")]
+async fn create_lp_token_ledger(symbol: String) -> Result<String, String> {
+    let token = match token_map::get_by_token(&symbol)? {
+        StableToken::LP(token) => token,
+        StableToken::IC(_) => Err("Token is not an LP token".to_string())?,
+    };
+    let ledger_id = lp_ledger_canister::create_ledger(token.token_id).await?;
+    Ok(format!("Token {} ledger set to {}", symbol, ledger_id))
+}
+
 /// deserialize TOKEN_MAP and update stable memory
 #[update(hidden = true, guard = "caller_is_kingkong")]
 fn update_tokens(stable_tokens: String) -> Result<String, String> {
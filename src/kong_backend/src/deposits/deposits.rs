@@ -0,0 +1,284 @@
+use candid::Nat;
+use ic_cdk::update;
+
+use super::archive_to_kong_data::archive_to_kong_data;
+use super::deposits_args::{DepositArgs, WithdrawArgs};
+use super::deposits_reply::DepositReply;
+use super::deposits_reply_helpers::to_deposit_reply;
+
+use crate::helpers::nat_helpers::{nat_is_zero, nat_subtract};
+use crate::ic::{
+    address::Address,
+    address_helpers::get_address,
+    get_time::get_time,
+    guards::not_in_maintenance_mode,
+    id::caller_id,
+    transfer::{icp_transfer, icrc1_transfer, icrc2_transfer_from},
+};
+use crate::stable_claim::{claim_map, stable_claim::StableClaim};
+use crate::stable_deposit::deposit_map;
+use crate::stable_kong_settings::kong_settings_map;
+use crate::stable_request::{reply::Reply, request::Request, request_map, stable_request::StableRequest, status::StatusCode};
+use crate::stable_token::{stable_token::StableToken, token::Token, token_map};
+use crate::stable_transfer::{stable_transfer::StableTransfer, transfer_map, tx_id::TxId};
+use crate::stable_tx::status_tx::StatusTx;
+use crate::stable_user::user_map;
+
+/// Deposit tokens to the caller's internal balance
+///
+/// - before calling deposit, the user must icrc2_approve the amount + gas fee of the token
+/// - the deposit can then be used by swap, add_liquidity and remove_liquidity with use_deposit, which need no ledger
+///   transfers and pay no gas fees
+/// - withdraw() sends the deposit back out
+#[update(guard = "not_in_maintenance_mode")]
+pub async fn deposit(args: DepositArgs) -> Result<DepositReply, String> {
+    let token = get_token(&args.token)?;
+    if !token.is_icrc2() {
+        Err("Token must support ICRC2".to_string())?
+    }
+    if nat_is_zero(&args.amount) {
+        Err("Amount is zero".to_string())?
+    }
+
+    // make sure user is registered, if not create a new user
+    let user_id = user_map::insert(None)?;
+    let ts = get_time();
+    let request_id = request_map::insert(&StableRequest::new(user_id, &Request::Deposit(args.clone()), ts));
+    let caller_id = caller_id();
+    let kong_backend = kong_settings_map::get().kong_backend;
+    let symbol = token.symbol();
+
+    request_map::update_status(request_id, StatusCode::Start, None);
+    request_map::update_status(request_id, StatusCode::DepositToken, Some(&symbol));
+
+    let result = match icrc2_transfer_from(&token, &args.amount, &caller_id, &kong_backend).await {
+        Ok(block_id) => {
+            // insert_transfer() will use the latest state of TRANSFER_MAP so no reentrancy issues after icrc2_transfer_from()
+            let transfer_id = transfer_map::insert(&StableTransfer {
+                transfer_id: 0,
+                request_id,
+                is_send: true,
+                amount: args.amount.clone(),
+                token_id: token.token_id(),
+                tx_id: TxId::BlockIndex(block_id),
+                ts,
+            });
+            deposit_map::credit(user_id, token.token_id(), &args.amount, ts);
+            request_map::update_status(request_id, StatusCode::DepositTokenSuccess, Some(&symbol));
+            let reply = to_deposit_reply(
+                request_id,
+                &StatusTx::Success,
+                user_id,
+                &token,
+                &args.amount,
+                &[transfer_id],
+                &[],
+                ts,
+            );
+            request_map::update_reply(request_id, Reply::Deposit(reply.clone()));
+            request_map::update_status(request_id, StatusCode::Success, None);
+            Ok(reply)
+        }
+        Err(e) => {
+            request_map::update_status(request_id, StatusCode::DepositTokenFailed, Some(&e));
+            request_map::update_status(request_id, StatusCode::Failed, None);
+            Err(format!("Req #{} failed. {}", request_id, e))
+        }
+    };
+    _ = archive_to_kong_data(request_id);
+
+    result
+}
+
+/// Withdraw tokens from the caller's internal balance
+///
+/// - amount is debited from the deposit and amount - gas fee is sent to to_address, or the caller if not specified
+/// - if the transfer fails, the tokens are saved as a claim
+#[update(guard = "not_in_maintenance_mode")]
+pub async fn withdraw(args: WithdrawArgs) -> Result<DepositReply, String> {
+    let token = get_token(&args.token)?;
+    let caller_id = caller_id();
+    let to_address = match args.to_address {
+        Some(ref address) => get_address(&token, address)?,
+        None => Address::PrincipalId(caller_id),
+    };
+    let amount_with_gas = nat_subtract(&args.amount, &token.fee()).ok_or("Amount must be greater than the gas fee")?;
+    if nat_is_zero(&amount_with_gas) {
+        Err("Amount must be greater than the gas fee".to_string())?
+    }
+
+    let user_id = user_map::get_by_caller()?.ok_or("Insufficient deposit balance")?.user_id;
+    if deposit_map::get_balance(user_id, token.token_id()) < args.amount {
+        Err("Insufficient deposit balance".to_string())?
+    }
+
+    let ts = get_time();
+    let request_id = request_map::insert(&StableRequest::new(user_id, &Request::Withdraw(args.clone()), ts));
+    request_map::update_status(request_id, StatusCode::Start, None);
+
+    // debit the deposit before the transfer so it can not be withdrawn twice
+    request_map::update_status(request_id, StatusCode::DebitDeposit, None);
+    if let Err(e) = deposit_map::debit(user_id, token.token_id(), &args.amount, ts) {
+        request_map::update_status(request_id, StatusCode::DebitDepositFailed, Some(&e));
+        request_map::update_status(request_id, StatusCode::Failed, None);
+        _ = archive_to_kong_data(request_id);
+        Err(format!("Req #{} failed. {}", request_id, e))?
+    }
+    request_map::update_status(request_id, StatusCode::DebitDepositSuccess, None);
+
+    let (transfer_ids, claim_ids) = withdraw_token(request_id, user_id, &token, &args.amount, &amount_with_gas, &to_address, ts).await;
+
+    let reply = to_deposit_reply(
+        request_id,
+        &StatusTx::Success,
+        user_id,
+        &token,
+        &args.amount,
+        &transfer_ids,
+        &claim_ids,
+        ts,
+    );
+    request_map::update_reply(request_id, Reply::Withdraw(reply.clone()));
+    request_map::update_status(request_id, StatusCode::Success, None);
+    _ = archive_to_kong_data(request_id);
+
+    Ok(reply)
+}
+
+/// only IC tokens can be deposited, LP tokens are already held by Kong
+fn get_token(token: &str) -> Result<StableToken, String> {
+    let stable_token = token_map::get_by_token(token)?;
+    match stable_token {
+        StableToken::IC(_) if stable_token.is_removed() => Err("Token is suspended or removed".to_string()),
+        StableToken::IC(_) => Ok(stable_token),
+        StableToken::LP(_) => Err("Token not supported".to_string()),
+    }
+}
+
+/// returns (transfer_ids, claim_ids)
+async fn withdraw_token(
+    request_id: u64,
+    user_id: u32,
+    token: &StableToken,
+    amount: &Nat,
+    amount_with_gas: &Nat,
+    to_address: &Address,
+    ts: u64,
+) -> (Vec<u64>, Vec<u64>) {
+    let symbol = token.symbol();
+
+    request_map::update_status(request_id, StatusCode::WithdrawToken, Some(&symbol));
+
+    match match to_address {
+        Address::AccountId(to_account_id) => icp_transfer(amount_with_gas, to_account_id, token, None).await,
+        Address::PrincipalId(to_principal_id) => icrc1_transfer(amount_with_gas, to_principal_id, token, None).await,
+    } {
+        Ok(block_id) => {
+            let transfer_id = transfer_map::insert(&StableTransfer {
+                transfer_id: 0,
+                request_id,
+                is_send: false,
+                amount: amount_with_gas.clone(),
+                token_id: token.token_id(),
+                tx_id: TxId::BlockIndex(block_id),
+                ts,
+            });
+            request_map::update_status(request_id, StatusCode::WithdrawTokenSuccess, Some(&symbol));
+            (vec![transfer_id], Vec::new())
+        }
+        Err(e) => {
+            let claim = StableClaim::new(user_id, token.token_id(), amount, Some(request_id), Some(to_address.clone()), ts);
+            let claim_id = claim_map::insert(&claim);
+            let message = format!("{} saved as claim #{}. {}", symbol, claim_id, e);
+            request_map::update_status(request_id, StatusCode::WithdrawTokenFailed, Some(&message));
+            (Vec::new(), vec![claim_id])
+        }
+    }
+}
+
+/// debit the amounts a request uses from the user's deposit. nothing is debited if any deposit is insufficient
+pub fn debit_deposits(request_id: u64, user_id: u32, amounts: &[(u32, &Nat)], ts: u64) -> Result<(), String> {
+    request_map::update_status(request_id, StatusCode::DebitDeposit, None);
+    for (token_id, amount) in amounts {
+        if deposit_map::get_balance(user_id, *token_id) < **amount {
+            let e = "Insufficient deposit balance".to_string();
+            request_map::update_status(request_id, StatusCode::DebitDepositFailed, Some(&e));
+            Err(e)?
+        }
+    }
+    for (token_id, amount) in amounts {
+        deposit_map::debit(user_id, *token_id, amount, ts)?;
+    }
+    request_map::update_status(request_id, StatusCode::DebitDepositSuccess, None);
+    Ok(())
+}
+
+/// credit the amounts a request receives to the user's deposit
+pub fn credit_deposits(request_id: u64, user_id: u32, amounts: &[(u32, &Nat)], ts: u64) {
+    for (token_id, amount) in amounts.iter().filter(|(_, amount)| !nat_is_zero(amount)) {
+        deposit_map::credit(user_id, *token_id, amount, ts);
+    }
+    request_map::update_status(request_id, StatusCode::CreditDepositSuccess, None);
+}
+
+/// the deposit of a user in a token, for checking a request before it is made
+pub fn check_deposit(user_id: u32, token: &StableToken, amount: &Nat) -> Result<(), String> {
+    if deposit_map::get_balance(user_id, token.token_id()) < *amount {
+        Err(format!("Insufficient {} deposit balance", token.symbol()))?
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::stable_pool::pool_map::tests::{ic_token, insert_pool, insert_token};
+    use crate::stable_token::ic_token::ICToken;
+
+    const USER_ID: u32 = 100;
+
+    #[test]
+    fn test_debit_deposits_all_or_nothing() {
+        let ts = get_time();
+        deposit_map::credit(USER_ID, 1, &Nat::from(100_u32), ts);
+        deposit_map::credit(USER_ID, 2, &Nat::from(50_u32), ts);
+        let balances = || (deposit_map::get_balance(USER_ID, 1), deposit_map::get_balance(USER_ID, 2));
+
+        // nothing is debited if any deposit is insufficient
+        let amount_1 = Nat::from(80_u32);
+        assert!(debit_deposits(0, USER_ID, &[(1, &amount_1), (2, &Nat::from(60_u32))], ts).is_err());
+        assert_eq!(balances(), (Nat::from(100_u32), Nat::from(50_u32)));
+
+        debit_deposits(0, USER_ID, &[(1, &amount_1), (2, &Nat::from(50_u32))], ts).unwrap();
+        assert_eq!(balances(), (Nat::from(20_u32), Nat::from(0_u32)));
+        assert!(check_deposit(USER_ID, &ic_token(1), &Nat::from(20_u32)).is_ok());
+        assert!(check_deposit(USER_ID, &ic_token(1), &Nat::from(21_u32)).is_err());
+
+        // zero amounts do not open a deposit
+        credit_deposits(0, USER_ID, &[(1, &Nat::from(5_u32)), (3, &Nat::from(0_u32))], ts);
+        assert_eq!(deposit_map::get_balance(USER_ID, 1), Nat::from(25_u32));
+        assert_eq!(
+            deposit_map::get_by_user_id(USER_ID)
+                .iter()
+                .map(|deposit| deposit.token_id)
+                .collect::<Vec<_>>(),
+            vec![1, 2]
+        );
+    }
+
+    #[test]
+    fn test_get_token() {
+        insert_pool(0);
+        let StableToken::IC(ic_token_4) = ic_token(4) else { unreachable!() };
+        insert_token(StableToken::IC(ICToken {
+            is_removed: true,
+            ..ic_token_4
+        }));
+
+        assert!(get_token("T1").is_ok());
+        // LP tokens are held by Kong and removed tokens can no longer be deposited
+        assert_eq!(get_token("T1_T2").unwrap_err(), "Token not supported");
+        assert_eq!(get_token("T4").unwrap_err(), "Token is suspended or removed");
+    }
+}
@@ -0,0 +1,19 @@
+use candid::{CandidType, Nat};
+use serde::{Deserialize, Serialize};
+
+/// Data structure for the arguments of the `deposit` function.
+/// Used in StableRequest
+#[derive(CandidType, Debug, Clone, Serialize, Deserialize)]
+pub struct DepositArgs {
+    pub token: String,
+    pub amount: Nat,
+}
+
+/// Data structure for the arguments of the `withdraw` function.
+/// Used in StableRequest
+#[derive(CandidType, Debug, Clone, Serialize, Deserialize)]
+pub struct WithdrawArgs {
+    pub token: String,
+    pub amount: Nat,                // amount debited from the deposit, the gas fee is taken out of it
+    pub to_address: Option<String>, // defaults to the caller's principal id
+}
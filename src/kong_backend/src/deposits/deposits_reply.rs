@@ -0,0 +1,18 @@
+use candid::{CandidType, Nat};
+use serde::{Deserialize, Serialize};
+
+use crate::transfers::transfer_reply::TransferIdReply;
+
+#[derive(CandidType, Debug, Clone, Serialize, Deserialize)]
+pub struct DepositReply {
+    pub request_id: u64,
+    pub status: String,
+    pub chain: String,
+    pub symbol: String,
+    pub address: String,
+    pub amount: Nat,
+    pub balance: Nat, // deposit balance after the request
+    pub transfer_ids: Vec<TransferIdReply>,
+    pub claim_ids: Vec<u64>,
+    pub ts: u64,
+}
@@ -0,0 +1,34 @@
+use candid::Nat;
+
+use super::deposits_reply::DepositReply;
+
+use crate::stable_deposit::deposit_map;
+use crate::stable_token::stable_token::StableToken;
+use crate::stable_token::token::Token;
+use crate::stable_tx::status_tx::StatusTx;
+use crate::transfers::transfer_reply_helpers::to_transfer_ids;
+
+#[allow(clippy::too_many_arguments)]
+pub fn to_deposit_reply(
+    request_id: u64,
+    status: &StatusTx,
+    user_id: u32,
+    token: &StableToken,
+    amount: &Nat,
+    transfer_ids: &[u64],
+    claim_ids: &[u64],
+    ts: u64,
+) -> DepositReply {
+    DepositReply {
+        request_id,
+        status: status.to_string(),
+        chain: token.chain(),
+        symbol: token.symbol(),
+        address: token.address(),
+        amount: amount.clone(),
+        balance: deposit_map::get_balance(user_id, token.token_id()),
+        transfer_ids: to_transfer_ids(transfer_ids),
+        claim_ids: claim_ids.to_vec(),
+        ts,
+    }
+}
@@ -0,0 +1,6 @@
+pub mod archive_to_kong_data;
+#[allow(clippy::module_inception)]
+pub mod deposits;
+pub mod deposits_args;
+pub mod deposits_reply;
+pub mod deposits_reply_helpers;
@@ -0,0 +1,22 @@
+use crate::stable_kong_settings::kong_settings_map;
+use crate::stable_request::request_map;
+use crate::stable_transfer::transfer_map;
+use crate::stable_tx::fee_sweep_tx::FeeSweepTx;
+use crate::stable_tx::tx_map;
+
+pub fn archive_to_kong_data(fee_sweep_tx: &FeeSweepTx) -> Result<(), String> {
+    if !kong_settings_map::get().archive_to_kong_data {
+        return Ok(());
+    }
+
+    let request_id = fee_sweep_tx.request_id;
+    let request = request_map::get_by_request_id(request_id).ok_or(format!("Failed to archive. request_id #{} not found", request_id))?;
+    request_map::archive_to_kong_data(&request)?;
+    fee_sweep_tx
+        .transfer_ids
+        .iter()
+        .try_for_each(|&transfer_id| transfer_map::archive_to_kong_data(transfer_id))?;
+    tx_map::archive_to_kong_data(fee_sweep_tx.tx_id)?;
+
+    Ok(())
+}
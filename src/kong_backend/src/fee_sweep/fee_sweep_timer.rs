@@ -0,0 +1,440 @@
+use candid::Nat;
+use icrc_ledger_types::icrc1::account::Account;
+use std::cmp::{max, min};
+use std::collections::BTreeSet;
+
+use super::archive_to_kong_data::archive_to_kong_data;
+
+use crate::helpers::nat_helpers::{
+    nat_add, nat_divide, nat_is_zero, nat_multiply, nat_multiply_f64, nat_multiply_rational, nat_subtract, nat_to_decimal_precision,
+    nat_zero,
+};
+use crate::ic::{
+    get_time::get_time,
+    guards::not_in_maintenance_mode,
+    transfer::{icrc1_burn, icrc1_transfer},
+};
+use crate::stable_kong_settings::kong_settings_map;
+use crate::stable_pool::pool_map;
+use crate::stable_request::{request::Request, request_map, stable_request::StableRequest, status::StatusCode};
+use crate::stable_token::{stable_token::StableToken, token::Token, token_map};
+use crate::stable_transfer::{stable_transfer::StableTransfer, transfer_map, tx_id::TxId};
+use crate::stable_tx::{fee_sweep_tx::FeeSweepTx, stable_tx::StableTx, status_tx::StatusTx, tx_map};
+use crate::stable_user::stable_user::SYSTEM_USER_ID;
+use crate::swap::swap_amounts::system_swap_amounts;
+use crate::swap::swap_calc::SwapCalc;
+use crate::swap::update_liquidity_pool::update_pools;
+use crate::twap::twap::get_twap;
+
+// the buyback must receive at least the KONG amount at the TWAP price over this window less default_max_slippage, so the
+// pools can not be moved right before a sweep to sandwich the buyback
+const BUYBACK_TWAP_WINDOW_SECS: u64 = 3_600;
+
+/// Sweep Kong's share of the swap fees accumulated in the pools
+///
+/// - fee_buyback_pct of the Kong fees of each token is swapped into KONG through the pools and burned, the rest is sent
+///   to fee_treasury. sweeps are off until fee_treasury is set
+/// - the KONG received by the buyback is bounded by the TWAP price of the pools it swaps through
+/// - the Kong fees are taken out of the pools before any transfer, so the same fees can not be swept twice
+/// - any amount that could not be sent or burned is returned to the Kong fees of a pool and swept again next time
+pub async fn process_fee_sweep_timer() {
+    if not_in_maintenance_mode().is_err() {
+        return;
+    }
+
+    let kong_settings = kong_settings_map::get();
+    let Some(fee_treasury) = kong_settings.fee_treasury else {
+        return;
+    };
+    // without KONG everything goes to the treasury
+    let kong_token = kong_settings.kong_token_id.and_then(token_map::get_by_token_id);
+    let buyback_pct = match kong_token {
+        Some(_) => min(kong_settings.fee_buyback_pct, 100),
+        None => 0,
+    };
+    let ts = get_time();
+
+    let token_ids = pool_map::get()
+        .iter()
+        .flat_map(|pool| {
+            [(pool.token_id_0, &pool.kong_fee_0), (pool.token_id_1, &pool.kong_fee_1)]
+                .into_iter()
+                .filter(|(_, kong_fee)| !nat_is_zero(kong_fee))
+                .map(|(token_id, _)| token_id)
+                .collect::<Vec<u32>>()
+        })
+        .collect::<BTreeSet<u32>>();
+    for token_id in token_ids {
+        let Some(token) = token_map::get_by_token_id(token_id) else {
+            continue;
+        };
+        sweep_kong_fees(&token, &fee_treasury, buyback_pct, kong_token.as_ref(), ts).await;
+    }
+}
+
+async fn sweep_kong_fees(token: &StableToken, fee_treasury: &Account, buyback_pct: u8, kong_token: Option<&StableToken>, ts: u64) {
+    let token_id = token.token_id();
+    // the Kong fees are read and taken out of the pools synchronously, swaps in between sweeps of other tokens are included
+    let kong_fees = get_kong_fees(token_id);
+    let amount = kong_fees.iter().fold(nat_zero(), |acc, (_, kong_fee)| nat_add(&acc, kong_fee));
+    // not worth sweeping less than the gas fee
+    if amount <= token.fee() {
+        return;
+    }
+
+    let symbol = token.symbol();
+    let request_id = request_map::insert(&StableRequest::new(SYSTEM_USER_ID, &Request::FeeSweep(token_id), ts));
+    request_map::update_status(request_id, StatusCode::Start, None);
+    request_map::update_status(request_id, StatusCode::SweepKongFee, Some(&symbol));
+    take_kong_fees(token_id, &kong_fees);
+    request_map::update_status(request_id, StatusCode::SweepKongFeeSuccess, Some(&symbol));
+
+    // unswept amounts are returned to the first pool
+    let return_pool_id = kong_fees[0].0;
+    let (treasury_amount, buyback_amount) = split_kong_fees(&amount, buyback_pct);
+    let mut transfer_ids = Vec::new();
+    let mut is_failed = false;
+
+    // amount - gas fee is sent to the treasury
+    let mut treasury_sent_amount = nat_zero();
+    if !nat_is_zero(&treasury_amount) {
+        request_map::update_status(request_id, StatusCode::SendTreasuryToken, Some(&symbol));
+        match nat_subtract(&treasury_amount, &token.fee()).filter(|amount| !nat_is_zero(amount)) {
+            Some(amount_with_gas) => match icrc1_transfer(&amount_with_gas, fee_treasury, token, None).await {
+                Ok(block_id) => {
+                    transfer_ids.push(insert_transfer(request_id, token, &amount_with_gas, block_id, ts));
+                    request_map::update_status(request_id, StatusCode::SendTreasuryTokenSuccess, Some(&symbol));
+                    treasury_sent_amount = amount_with_gas;
+                }
+                Err(e) => {
+                    request_map::update_status(request_id, StatusCode::SendTreasuryTokenFailed, Some(&e));
+                    return_kong_fee(request_id, return_pool_id, token, &treasury_amount);
+                    is_failed = true;
+                }
+            },
+            None => {
+                request_map::update_status(request_id, StatusCode::SendTreasuryTokenFailed, Some("Amount less than gas fee"));
+                return_kong_fee(request_id, return_pool_id, token, &treasury_amount);
+                is_failed = true;
+            }
+        }
+    }
+
+    let mut swaps = Vec::new();
+    let mut swapped_amount = nat_zero();
+    let mut burn_amount = nat_zero();
+    if let Some(kong_token) = kong_token.filter(|_| !nat_is_zero(&buyback_amount)) {
+        match buyback_kong(request_id, token, &buyback_amount, kong_token, return_pool_id, ts) {
+            Ok((kong_amount, buyback_swaps)) => {
+                swaps = buyback_swaps;
+                swapped_amount = buyback_amount.clone();
+                match burn_kong(request_id, kong_token, &kong_amount, ts).await {
+                    Ok(transfer_id) => {
+                        transfer_ids.push(transfer_id);
+                        burn_amount = kong_amount;
+                    }
+                    Err(_) => {
+                        // KONG from the buyback is returned to the Kong fees of the last pool of the swap
+                        let kong_pool_id = swaps.last().map_or(return_pool_id, |swap| swap.pool_id);
+                        return_kong_fee(request_id, kong_pool_id, kong_token, &kong_amount);
+                        is_failed = true;
+                    }
+                }
+            }
+            Err(_) => is_failed = true,
+        }
+    }
+
+    let status = if is_failed { StatusTx::Failed } else { StatusTx::Success };
+    let pool_ids = kong_fees.iter().map(|(pool_id, _)| *pool_id).collect::<Vec<u32>>();
+    let fee_sweep_tx = FeeSweepTx::new(
+        SYSTEM_USER_ID,
+        request_id,
+        status,
+        token_id,
+        &amount,
+        &pool_ids,
+        &treasury_sent_amount,
+        &swapped_amount,
+        &burn_amount,
+        &swaps,
+        &transfer_ids,
+        ts,
+    );
+    let tx_id = tx_map::insert(&StableTx::FeeSweep(fee_sweep_tx.clone()));
+    let status_code = if is_failed { StatusCode::Failed } else { StatusCode::Success };
+    request_map::update_status(request_id, status_code, None);
+    _ = archive_to_kong_data(&FeeSweepTx { tx_id, ..fee_sweep_tx });
+}
+
+/// Kong fees of the token in each pool as (pool_id, kong_fee)
+fn get_kong_fees(token_id: u32) -> Vec<(u32, Nat)> {
+    pool_map::get()
+        .into_iter()
+        .filter_map(|pool| {
+            let kong_fee = if pool.token_id_0 == token_id {
+                pool.kong_fee_0
+            } else if pool.token_id_1 == token_id {
+                pool.kong_fee_1
+            } else {
+                return None;
+            };
+            (!nat_is_zero(&kong_fee)).then_some((pool.pool_id, kong_fee))
+        })
+        .collect()
+}
+
+/// Kong fees split into (treasury_amount, buyback_amount). any rounding goes to the treasury
+fn split_kong_fees(amount: &Nat, buyback_pct: u8) -> (Nat, Nat) {
+    let buyback_amount = nat_divide(&nat_multiply(amount, &Nat::from(buyback_pct)), &Nat::from(100_u32)).unwrap_or(nat_zero());
+    let treasury_amount = nat_subtract(amount, &buyback_amount).unwrap_or(nat_zero());
+    (treasury_amount, buyback_amount)
+}
+
+fn take_kong_fees(token_id: u32, kong_fees: &[(u32, Nat)]) {
+    for (pool_id, kong_fee) in kong_fees {
+        let Some(mut pool) = pool_map::get_by_pool_id(*pool_id) else {
+            continue;
+        };
+        if pool.token_id_0 == token_id {
+            pool.kong_fee_0 = nat_subtract(&pool.kong_fee_0, kong_fee).unwrap_or(nat_zero());
+        } else {
+            pool.kong_fee_1 = nat_subtract(&pool.kong_fee_1, kong_fee).unwrap_or(nat_zero());
+        }
+        pool_map::update(&pool);
+    }
+}
+
+fn return_kong_fee(request_id: u64, pool_id: u32, token: &StableToken, amount: &Nat) {
+    let token_id = token.token_id();
+    if let Some(mut pool) = pool_map::get_by_pool_id(pool_id) {
+        if pool.token_id_0 == token_id {
+            pool.kong_fee_0 = nat_add(&pool.kong_fee_0, amount);
+        } else {
+            pool.kong_fee_1 = nat_add(&pool.kong_fee_1, amount);
+        }
+        pool_map::update(&pool);
+    }
+    let message = format!("{} {} returned to pool #{}", amount, token.symbol(), pool_id);
+    request_map::update_status(request_id, StatusCode::ReturnKongFee, Some(&message));
+}
+
+/// swap the buyback amount into KONG through the pools. Kong fees in KONG are burned as is
+/// returns (KONG amount, swaps)
+fn buyback_kong(
+    request_id: u64,
+    token: &StableToken,
+    buyback_amount: &Nat,
+    kong_token: &StableToken,
+    return_pool_id: u32,
+    ts: u64,
+) -> Result<(Nat, Vec<SwapCalc>), String> {
+    if token.token_id() == kong_token.token_id() {
+        return Ok((buyback_amount.clone(), Vec::new()));
+    }
+
+    request_map::update_status(request_id, StatusCode::BuybackToken, None);
+    let (kong_amount, swaps) = match system_swap_amounts(token, buyback_amount, kong_token).and_then(|(kong_amount, _, _, _, swaps)| {
+        check_twap_amount(&swaps, buyback_amount, &kong_amount, kong_token, ts)?;
+        update_pools(request_id, &swaps)?;
+        Ok((kong_amount, swaps))
+    }) {
+        Ok(amounts) => amounts,
+        Err(e) => {
+            request_map::update_status(request_id, StatusCode::BuybackTokenFailed, Some(&e));
+            return_kong_fee(request_id, return_pool_id, token, buyback_amount);
+            return Err(e);
+        }
+    };
+    request_map::update_status(request_id, StatusCode::BuybackTokenSuccess, None);
+
+    Ok((kong_amount, swaps))
+}
+
+/// check the KONG amount of the buyback is at least the amount at the TWAP prices of the pools less default_max_slippage
+fn check_twap_amount(swaps: &[SwapCalc], buyback_amount: &Nat, kong_amount: &Nat, kong_token: &StableToken, ts: u64) -> Result<(), String> {
+    let twap_amount = get_twap_amount(swaps, buyback_amount, ts)?;
+    if nat_is_zero(kong_amount) {
+        Err("Receive amount is zero".to_string())?
+    }
+    let max_slippage = kong_settings_map::get().default_max_slippage;
+    let min_kong_amount = nat_multiply_f64(&twap_amount, (100.0 - max_slippage) / 100.0).ok_or("Invalid max slippage")?;
+    if *kong_amount < min_kong_amount {
+        Err(format!(
+            "Buyback of {} {} below {} {} at TWAP price with {}% slippage",
+            kong_amount,
+            kong_token.symbol(),
+            min_kong_amount,
+            kong_token.symbol(),
+            max_slippage
+        ))?
+    }
+    Ok(())
+}
+
+/// amount received for pay_amount through the swaps at the TWAP prices of their pools, before fees
+fn get_twap_amount(swaps: &[SwapCalc], pay_amount: &Nat, ts: u64) -> Result<Nat, String> {
+    swaps.iter().try_fold(pay_amount.clone(), |amount, swap| {
+        let pool = pool_map::get_by_pool_id(swap.pool_id).ok_or(format!("Pool #{} not found", swap.pool_id))?;
+        let (price, inverse_price) = get_twap(&pool, BUYBACK_TWAP_WINDOW_SECS, ts)?;
+        let (pay_decimals, receive_decimals, price) = if swap.pay_token_id == pool.token_id_0 {
+            (pool.token_0().decimals(), pool.token_1().decimals(), price)
+        } else {
+            (pool.token_1().decimals(), pool.token_0().decimals(), inverse_price)
+        };
+        // TWAP prices are at the larger decimal precision of the pool's tokens
+        let max_decimals = max(pay_decimals, receive_decimals);
+        let amount = nat_to_decimal_precision(&amount, pay_decimals, max_decimals);
+        let amount = nat_multiply_rational(&amount, &price).ok_or("Invalid TWAP price")?;
+        Ok(nat_to_decimal_precision(&amount, max_decimals, receive_decimals))
+    })
+}
+
+/// returns the transfer_id of the burn
+async fn burn_kong(request_id: u64, kong_token: &StableToken, kong_amount: &Nat, ts: u64) -> Result<u64, String> {
+    request_map::update_status(request_id, StatusCode::BurnToken, None);
+    match icrc1_burn(kong_amount, kong_token).await {
+        Ok(block_id) => {
+            let transfer_id = insert_transfer(request_id, kong_token, kong_amount, block_id, ts);
+            request_map::update_status(request_id, StatusCode::BurnTokenSuccess, None);
+            Ok(transfer_id)
+        }
+        Err(e) => {
+            request_map::update_status(request_id, StatusCode::BurnTokenFailed, Some(&e));
+            Err(e)
+        }
+    }
+}
+
+fn insert_transfer(request_id: u64, token: &StableToken, amount: &Nat, block_id: Nat, ts: u64) -> u64 {
+    transfer_map::insert(&StableTransfer {
+        transfer_id: 0,
+        request_id,
+        is_send: false,
+        amount: amount.clone(),
+        token_id: token.token_id(),
+        tx_id: TxId::BlockIndex(block_id),
+        ts,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use candid::Principal;
+    use futures::executor::block_on;
+
+    use crate::stable_pool::pool_map::tests::{ic_token, insert_token, pool};
+    use crate::stable_pool::stable_pool::StablePool;
+    use crate::stable_token::ic_token::ICToken;
+
+    const KONG_TOKEN_ID: u32 = 9;
+
+    /// pool of token_id_0 and token_id_1 with balance of each token and the Kong fees, inserted in POOL_MAP
+    fn insert_pool(token_id_0: u32, token_id_1: u32, balance: u64, kong_fee_0: u64, kong_fee_1: u64) -> StablePool {
+        let pool_id = pool_map::insert(&StablePool {
+            kong_fee_0: Nat::from(kong_fee_0),
+            kong_fee_1: Nat::from(kong_fee_1),
+            ..pool(0, token_id_0, token_id_1, balance)
+        })
+        .unwrap();
+        pool_map::get_by_pool_id(pool_id).unwrap()
+    }
+
+    /// pool of token_id and KONG priced at 1 over the whole TWAP window
+    fn insert_kong_pool(token_id: u32, balance: u64, ts: u64) -> StablePool {
+        let mut pool = insert_pool(token_id, KONG_TOKEN_ID, balance, 0, 0);
+        pool.price_cumulative_secs = ts / 1_000_000_000 - BUYBACK_TWAP_WINDOW_SECS;
+        pool_map::update(&pool);
+        pool.update_price_cumulative(ts);
+        pool_map::update(&pool);
+        pool
+    }
+
+    fn kong_fees(pool_id: u32) -> (Nat, Nat) {
+        let pool = pool_map::get_by_pool_id(pool_id).unwrap();
+        (pool.kong_fee_0, pool.kong_fee_1)
+    }
+
+    #[test]
+    fn test_split_kong_fees() {
+        assert_eq!(split_kong_fees(&Nat::from(1_001_u32), 50), (Nat::from(501_u32), Nat::from(500_u32)));
+        assert_eq!(split_kong_fees(&Nat::from(1_000_u32), 0), (Nat::from(1_000_u32), nat_zero()));
+        assert_eq!(split_kong_fees(&Nat::from(1_000_u32), 100), (nat_zero(), Nat::from(1_000_u32)));
+    }
+
+    #[test]
+    fn test_take_kong_fees() {
+        let pool_a = insert_pool(1, 2, 1_000_000, 100, 7);
+        let pool_b = insert_pool(3, 1, 1_000_000, 0, 50);
+        let kong_fees_1 = get_kong_fees(1);
+        assert_eq!(
+            kong_fees_1,
+            vec![(pool_a.pool_id, Nat::from(100_u32)), (pool_b.pool_id, Nat::from(50_u32))]
+        );
+
+        // Kong fees of swaps after the fees are read are left in the pool to be swept next time
+        pool_map::update(&StablePool {
+            kong_fee_0: Nat::from(110_u32),
+            ..pool_a.clone()
+        });
+        take_kong_fees(1, &kong_fees_1);
+        assert_eq!(kong_fees(pool_a.pool_id), (Nat::from(10_u32), Nat::from(7_u32)));
+        assert_eq!(kong_fees(pool_b.pool_id), (nat_zero(), nat_zero()));
+        assert!(get_kong_fees(3).is_empty());
+
+        return_kong_fee(0, pool_b.pool_id, &ic_token(1), &Nat::from(30_u32));
+        assert_eq!(kong_fees(pool_b.pool_id), (nat_zero(), Nat::from(30_u32)));
+    }
+
+    #[test]
+    fn test_failed_sweep_returns_kong_fees() {
+        let StableToken::IC(ic_token_1) = ic_token(1) else { unreachable!() };
+        let token = StableToken::IC(ICToken {
+            fee: Nat::from(100_u32),
+            ..ic_token_1
+        });
+        insert_token(token.clone());
+        let kong_token = ic_token(KONG_TOKEN_ID);
+        insert_token(kong_token.clone());
+        let pool = insert_pool(1, 2, 1_000_000, 150, 0);
+        let fee_treasury = Account::from(Principal::anonymous());
+        let ts = get_time();
+
+        // the treasury half is less than the gas fee and there is no pool to buy back KONG with the other half. both are
+        // returned to the pool without any transfer
+        block_on(sweep_kong_fees(&token, &fee_treasury, 50, Some(&kong_token), ts));
+        assert_eq!(kong_fees(pool.pool_id), (Nat::from(150_u32), nat_zero()));
+        let Some(StableTx::FeeSweep(fee_sweep_tx)) = tx_map::get_by_user_and_token_id(None, Some(SYSTEM_USER_ID), None, Some(1)).pop()
+        else {
+            panic!("fee sweep tx not found");
+        };
+        assert_eq!(fee_sweep_tx.status, StatusTx::Failed);
+        assert_eq!(fee_sweep_tx.amount, Nat::from(150_u32));
+        assert!(nat_is_zero(&fee_sweep_tx.treasury_amount) && nat_is_zero(&fee_sweep_tx.buyback_amount));
+    }
+
+    #[test]
+    fn test_buyback_bounded_by_twap() {
+        let ts = get_time();
+        let pool = insert_kong_pool(1, 1_000_000_000, ts);
+        let token = pool.token_0();
+        let kong_token = pool.token_1();
+        let buyback_amount = Nat::from(1_000_000_u32);
+
+        let (kong_amount, swaps) = buyback_kong(0, &token, &buyback_amount, &kong_token, pool.pool_id, ts).unwrap();
+        assert_eq!(swaps.len(), 1);
+        assert!(kong_amount < buyback_amount && kong_amount > 980_000_u32);
+
+        // the pool is moved just before the buyback. the KONG amount is below the TWAP price, so the buyback fails and is
+        // returned to the Kong fees
+        let pool = pool_map::get_by_pool_id(pool.pool_id).unwrap();
+        pool_map::update(&StablePool {
+            balance_0: nat_multiply(&pool.balance_0, &Nat::from(2_u32)),
+            ..pool.clone()
+        });
+        let e = buyback_kong(0, &token, &buyback_amount, &kong_token, pool.pool_id, ts).unwrap_err();
+        assert!(e.contains("at TWAP price"), "{}", e);
+        assert_eq!(kong_fees(pool.pool_id).0, nat_add(&pool.kong_fee_0, &buyback_amount));
+    }
+}
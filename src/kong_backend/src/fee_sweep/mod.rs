@@ -0,0 +1,2 @@
+pub mod archive_to_kong_data;
+pub mod fee_sweep_timer;
@@ -77,7 +77,8 @@ mod tests {
             subaccount: Some(subaccount),
         };
         let address = Address::PrincipalId(account);
-        // address should include subaccount in its display but it doesn't so just check principal
+        // displayed as the ICRC-1 textual account, which parses back to the same account
         assert!(format!("{}", address).contains(&principal_text));
+        assert_eq!(address.to_string().parse::<Account>().unwrap(), account);
     }
 }
@@ -2,6 +2,7 @@ use candid::Principal;
 use ic_ledger_types::AccountIdentifier;
 use icrc_ledger_types::icrc1::account::Account;
 use regex::Regex;
+use std::str::FromStr;
 use std::sync::OnceLock;
 
 use crate::stable_token::{stable_token::StableToken, token::Token};
@@ -19,27 +20,46 @@ pub fn is_principal_id(address: &str) -> bool {
     regrex_principal_id.is_match(address)
 }
 
-pub fn get_address(token: &StableToken, address: &str) -> Result<Address, String> {
-    let regrex_princiapl_id = PRINCIPAL_ID_LOCK.get_or_init(|| Regex::new(PRINCIPAL_ID_REGEX).unwrap());
+/// legacy 64 hex AccountIdentifier of the ICP ledger
+pub fn is_account_id(address: &str) -> bool {
     let regrex_account_id = ACCOUNT_ID_LOCK.get_or_init(|| Regex::new(ACCOUNT_ID_REGEX).unwrap());
+    regrex_account_id.is_match(address)
+}
 
-    if regrex_princiapl_id.is_match(address) {
-        if !token.is_icrc1() {
-            return Err("Principal Id requires ICRC1 token".to_string());
-        }
+/// ICRC-1 textual encoding of an account with a subaccount, <principal>-<checksum>.<subaccount>
+pub fn is_account(address: &str) -> bool {
+    address.contains('.') && Account::from_str(address).is_ok()
+}
+
+/// parse a destination address, without checking it is supported by the token
+/// - principal id, for the default subaccount
+/// - ICRC-1 textual account with a subaccount
+/// - legacy AccountIdentifier, checksum is verified
+pub fn parse_address(address: &str) -> Result<Address, String> {
+    if is_principal_id(address) {
         Ok(Address::PrincipalId(Account::from(
             Principal::from_text(address).map_err(|e| e.to_string())?,
         )))
-    } else if regrex_account_id.is_match(address) {
-        if is_icp_token_id(token.token_id()) {
-            return Err("Account Id supported only for ICP token".to_string());
-        }
-        Ok(Address::AccountId(AccountIdentifier::from_hex(address).map_err(|e| e.to_string())?))
+    } else if is_account_id(address) {
+        Ok(Address::AccountId(AccountIdentifier::from_hex(address)?))
+    } else if address.contains('.') {
+        Ok(Address::PrincipalId(Account::from_str(address).map_err(|e| e.to_string())?))
     } else {
         Err("Invalid address format".to_string())
     }
 }
 
+/// parse a destination address for token
+/// principal ids and ICRC-1 accounts require an ICRC1 token, AccountIdentifiers are only supported by ICP
+pub fn get_address(token: &StableToken, address: &str) -> Result<Address, String> {
+    let address = parse_address(address)?;
+    match address {
+        Address::PrincipalId(_) if !token.is_icrc1() => Err("Principal Id requires ICRC1 token".to_string()),
+        Address::AccountId(_) if !is_icp_token_id(token.token_id()) => Err("Account Id supported only for ICP token".to_string()),
+        _ => Ok(address),
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -181,6 +201,42 @@ mod tests {
         assert!(!is_principal_id(""));
     }
 
+    #[test]
+    fn test_parse_address() {
+        let principal_text = "2ipq2-uqaaa-aaaar-qailq-cai";
+        let principal = Principal::from_text(principal_text).unwrap();
+        match parse_address(principal_text) {
+            Ok(Address::PrincipalId(account)) => assert_eq!(account, Account::from(principal)),
+            other => panic!("expected principal id, got {:?}", other),
+        }
+
+        let account = Account {
+            owner: principal,
+            subaccount: Some([1; 32]),
+        };
+        let account_text = account.to_string();
+        assert!(is_account(&account_text));
+        match parse_address(&account_text) {
+            Ok(Address::PrincipalId(parsed)) => assert_eq!(parsed, account),
+            other => panic!("expected account with subaccount, got {:?}", other),
+        }
+        // the checksum of the textual account must be valid
+        let (principal_checksum, subaccount) = account_text.split_once('.').unwrap();
+        let (principal_part, _) = principal_checksum.rsplit_once('-').unwrap();
+        assert!(parse_address(&format!("{}-aaaaaaa.{}", principal_part, subaccount)).is_err());
+
+        let account_id_text = "da29b27beb16a842882149b5380ff3b20f701c33ca8fddbecdb5201c600e0f0e";
+        match parse_address(account_id_text) {
+            Ok(Address::AccountId(account_id)) => assert_eq!(account_id.to_hex(), account_id_text),
+            other => panic!("expected account id, got {:?}", other),
+        }
+        // the checksum of the account id must be valid
+        assert!(parse_address("ea29b27beb16a842882149b5380ff3b20f701c33ca8fddbecdb5201c600e0f0e").is_err());
+
+        assert!(parse_address("not-an-address").is_err());
+        assert!(parse_address("").is_err());
+    }
+
     #[test]
     fn test_is_principal_id_account_id_format() {
         // This is a valid account id, not a principal id
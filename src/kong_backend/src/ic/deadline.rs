@@ -0,0 +1,56 @@
+use super::get_time::get_time;
+
+use crate::stable_request::{request_map, status::StatusCode};
+
+/// check the deadline (nanoseconds since the Unix epoch) of a request has not passed
+pub fn check_deadline(deadline: Option<u64>) -> Result<(), String> {
+    match deadline {
+        Some(deadline) if get_time() > deadline => Err(format!("Deadline {} exceeded", deadline_to_string(deadline))),
+        _ => Ok(()),
+    }
+}
+
+/// check the deadline once the request is being processed and update its status if it has passed
+/// the caller is responsible for returning any tokens already received
+pub fn check_request_deadline(request_id: u64, deadline: Option<u64>) -> Result<(), String> {
+    check_deadline(deadline).inspect_err(|e| {
+        request_map::update_status(request_id, StatusCode::DeadlineExceeded, Some(e));
+    })
+}
+
+/// deadline as a UTC date and time, e.g. 2024-01-31 23:59:59 UTC
+pub fn deadline_to_string(deadline: u64) -> String {
+    let secs = deadline / 1_000_000_000;
+    let (days, secs_of_day) = (secs / 86_400, secs % 86_400);
+    // civil date from days since 1970-01-01 (Howard Hinnant's algorithm)
+    let z = days + 719_468;
+    let era = z / 146_097;
+    let doe = z - era * 146_097;
+    let yoe = (doe - doe / 1_460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + if month <= 2 { 1 } else { 0 };
+    format!(
+        "{:04}-{:02}-{:02} {:02}:{:02}:{:02} UTC",
+        year,
+        month,
+        day,
+        secs_of_day / 3_600,
+        secs_of_day % 3_600 / 60,
+        secs_of_day % 60
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_deadline_to_string() {
+        assert_eq!(deadline_to_string(0), "1970-01-01 00:00:00 UTC");
+        assert_eq!(deadline_to_string(1_706_745_599_000_000_000), "2024-01-31 23:59:59 UTC");
+        assert_eq!(deadline_to_string(1_709_164_800_500_000_000), "2024-02-29 00:00:00 UTC");
+    }
+}
@@ -3,6 +3,15 @@
 /// # Returns
 ///
 /// * `u64` - The current time in nanoseconds since the Unix epoch.
+#[cfg(not(test))]
 pub fn get_time() -> u64 {
     ic_cdk::api::time()
 }
+
+/// unit tests run outside of a canister, so the system time is used
+#[cfg(test)]
+pub fn get_time() -> u64 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map_or(0, |duration| duration.as_nanos() as u64)
+}
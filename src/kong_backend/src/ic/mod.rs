@@ -2,6 +2,7 @@ pub mod address;
 pub mod address_helpers;
 pub mod canister_address;
 pub mod ckusdt;
+pub mod deadline;
 pub mod get_time;
 pub mod guards;
 pub mod icp;
@@ -85,6 +85,20 @@ pub async fn icrc1_transfer(
     }
 }
 
+/// Burns ICRC1 tokens held by the backend canister by sending them to the ledger's minting account.
+/// burns are charged no fee, so the whole amount is burned
+pub async fn icrc1_burn(amount: &Nat, token: &StableToken) -> Result<Nat, String> {
+    let id = *token.canister_id().ok_or("Invalid principal id")?;
+
+    let minting_account = ic_cdk::call::<(), (Option<Account>,)>(id, "icrc1_minting_account", ())
+        .await
+        .map_err(|e| e.1)?
+        .0
+        .ok_or("Token has no minting account")?;
+
+    icrc1_transfer(amount, &minting_account, token, None).await
+}
+
 // icrc2_transfer_from using principal id's where from_principal_id has issued an icrc2_approve
 pub async fn icrc2_transfer_from(
     token: &StableToken,
@@ -1,24 +1,37 @@
 pub mod add_liquidity;
 pub mod add_liquidity_amounts;
+pub mod add_liquidity_single;
 pub mod add_pool;
 pub mod add_token;
+pub mod batch;
 pub mod canister;
+pub mod certified;
 pub mod chains;
 pub mod claims;
 pub mod controllers;
+pub mod deposits;
+pub mod fee_sweep;
 pub mod helpers;
 pub mod ic;
+pub mod limit_orders;
+pub mod lp_ledger;
 pub mod pools;
 pub mod remove_liquidity;
 pub mod remove_liquidity_amounts;
+pub mod remove_liquidity_single;
 pub mod requests;
+pub mod reward_campaigns;
 pub mod send;
 pub mod stable_claim;
+pub mod stable_deposit;
 pub mod stable_kong_settings;
+pub mod stable_limit_order;
 pub mod stable_lp_token;
 pub mod stable_memory;
 pub mod stable_pool;
+pub mod stable_pool_observation;
 pub mod stable_request;
+pub mod stable_reward_campaign;
 pub mod stable_token;
 pub mod stable_transfer;
 pub mod stable_tx;
@@ -27,8 +40,10 @@ pub mod swap;
 pub mod swap_amounts;
 pub mod tokens;
 pub mod transfers;
+pub mod twap;
 pub mod user;
 pub mod user_balances;
+pub mod user_fee_tiers;
 
 pub const APP_NAME: &str = "Kong Swap";
 pub const APP_VERSION: &str = "v0.0.20";
@@ -0,0 +1,168 @@
+use candid::Principal;
+use ic_cdk::update;
+use icrc_ledger_types::icrc1::account::Account;
+
+use super::limit_order_reply::LimitOrderReply;
+use super::limit_order_reply_helpers::to_limit_order_reply;
+
+use crate::helpers::nat_helpers::{nat_subtract, nat_zero};
+use crate::ic::address::Address;
+use crate::ic::get_time::get_time;
+use crate::ic::guards::not_in_maintenance_mode;
+use crate::ic::transfer::icrc1_transfer;
+use crate::stable_claim::{claim_map, stable_claim::StableClaim};
+use crate::stable_limit_order::limit_order_map;
+use crate::stable_limit_order::stable_limit_order::{LimitOrderStatus, StableLimitOrder};
+use crate::stable_request::{reply::Reply, request::Request, request_map, stable_request::StableRequest, status::StatusCode};
+use crate::stable_token::{token::Token, token_map};
+use crate::stable_transfer::{stable_transfer::StableTransfer, transfer_map, tx_id::TxId};
+use crate::stable_user::user_map;
+
+/// Cancel an open limit order and return the escrowed pay token
+#[update(guard = "not_in_maintenance_mode")]
+async fn cancel_limit_order(order_id: u64) -> Result<LimitOrderReply, String> {
+    let user_id = user_map::get_by_caller()?.ok_or("User not found")?.user_id;
+    let order = limit_order_map::get_by_order_id(order_id)
+        .filter(|order| order.user_id == user_id)
+        .ok_or(format!("Limit order #{} not found", order_id))?;
+    if order.status != LimitOrderStatus::Open {
+        Err(format!("Limit order #{} is {}", order_id, order.status))?
+    }
+
+    let ts = get_time();
+    let request_id = request_map::insert(&StableRequest::new(user_id, &Request::CancelLimitOrder(order_id), ts));
+    let order = close_limit_order(request_id, &order, LimitOrderStatus::Cancelled, ts).await;
+
+    Ok(to_limit_order_reply(&order))
+}
+
+/// close an open limit order with status Cancelled or Expired and return the escrowed pay token to the user
+/// any failure to send the pay token is saved as a claim
+pub async fn close_limit_order(request_id: u64, order: &StableLimitOrder, status: LimitOrderStatus, ts: u64) -> StableLimitOrder {
+    request_map::update_status(request_id, StatusCode::Start, None);
+
+    // close the order first so it can not be filled while the pay token is returned
+    let mut order = StableLimitOrder { status, ..order.clone() };
+    limit_order_map::update(&order);
+
+    request_map::update_status(request_id, StatusCode::ReturnPayToken, None);
+
+    let to_principal_id = user_map::get_by_user_id(order.user_id)
+        .and_then(|user| Principal::from_text(user.principal_id).ok())
+        .map(Account::from);
+    let send_result = match (token_map::get_by_token_id(order.pay_token_id), to_principal_id) {
+        (Some(pay_token), Some(to_principal_id)) => {
+            let pay_amount_with_gas = nat_subtract(&order.pay_amount, &pay_token.fee()).unwrap_or(nat_zero());
+            icrc1_transfer(&pay_amount_with_gas, &to_principal_id, &pay_token, None)
+                .await
+                .map(|tx_id| (tx_id, pay_amount_with_gas))
+        }
+        (None, _) => Err("Pay token not found".to_string()),
+        (_, None) => Err("User principal id not found".to_string()),
+    };
+    match send_result {
+        Ok((tx_id, amount)) => {
+            let transfer_id = transfer_map::insert(&StableTransfer {
+                transfer_id: 0,
+                request_id,
+                is_send: false,
+                amount,
+                token_id: order.pay_token_id,
+                tx_id: TxId::BlockIndex(tx_id),
+                ts,
+            });
+            order.transfer_ids.push(transfer_id);
+            request_map::update_status(request_id, StatusCode::ReturnPayTokenSuccess, None);
+        }
+        Err(e) => {
+            let claim = StableClaim::new(
+                order.user_id,
+                order.pay_token_id,
+                &order.pay_amount,
+                Some(request_id),
+                to_principal_id.map(Address::PrincipalId),
+                ts,
+            );
+            let claim_id = claim_map::insert(&claim);
+            order.claim_ids.push(claim_id);
+            request_map::update_status(
+                request_id,
+                StatusCode::ReturnPayTokenFailed,
+                Some(&format!("Saved as claim #{}. {}", claim_id, e)),
+            );
+        }
+    }
+    limit_order_map::update(&order);
+
+    request_map::update_reply(request_id, Reply::LimitOrder(to_limit_order_reply(&order)));
+    request_map::update_status(request_id, StatusCode::Success, None);
+
+    order
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use candid::Nat;
+    use futures::executor::block_on;
+    use std::panic::{catch_unwind, AssertUnwindSafe};
+
+    use crate::helpers::nat_helpers::nat_is_zero;
+    use crate::lp_ledger::lp_ledger_helpers::tests::insert_user;
+    use crate::stable_pool::pool_map::tests::insert_pool;
+
+    const USER_ID: u32 = 100;
+
+    /// open order of the user to swap 1_000 T1 for T2
+    fn insert_order(user_id: u32, ts: u64) -> StableLimitOrder {
+        let to_address = Address::PrincipalId(Account::from(Principal::anonymous()));
+        let order = StableLimitOrder::new(
+            user_id,
+            1,
+            &Nat::from(1_000_u32),
+            2,
+            &Nat::from(1_u32),
+            &to_address,
+            None,
+            0,
+            &[],
+            ts,
+        );
+        limit_order_map::get_by_order_id(limit_order_map::insert(&order)).unwrap()
+    }
+
+    fn close(order: &StableLimitOrder, ts: u64) -> StableLimitOrder {
+        let request_id = request_map::insert(&StableRequest::new(order.user_id, &Request::CancelLimitOrder(order.order_id), ts));
+        block_on(close_limit_order(request_id, order, LimitOrderStatus::Cancelled, ts))
+    }
+
+    #[test]
+    fn test_close_limit_order_before_refund() {
+        insert_pool(1_000_000);
+        let ts = get_time();
+        insert_user(USER_ID, 0, ts);
+        let order = insert_order(USER_ID, ts);
+        assert_eq!(limit_order_map::get_open_amount(1), order.pay_amount);
+
+        // the order is closed before the pay token is returned, which calls the ledger and so panics outside a canister
+        assert!(catch_unwind(AssertUnwindSafe(|| close(&order, ts))).is_err());
+        let closed_order = limit_order_map::get_by_order_id(order.order_id).unwrap();
+        assert_eq!(closed_order.status, LimitOrderStatus::Cancelled);
+        assert!(closed_order.transfer_ids.is_empty() && closed_order.claim_ids.is_empty());
+        // so the timer no longer fills it and its pay token is no longer escrowed
+        assert!(limit_order_map::get_open_order_ids().is_empty());
+        assert!(nat_is_zero(&limit_order_map::get_open_amount(1)));
+    }
+
+    #[test]
+    fn test_close_limit_order_claim() {
+        insert_pool(1_000_000);
+        let ts = get_time();
+        // the pay token can not be returned to a user without a principal id, so the whole pay amount is saved as a claim
+        let order = close(&insert_order(USER_ID, ts), ts);
+        assert_eq!(order.status, LimitOrderStatus::Cancelled);
+        assert_eq!(order.claim_ids.len(), 1);
+        let claim = claim_map::get_by_claim_id(order.claim_ids[0]).unwrap();
+        assert_eq!((claim.user_id, claim.token_id, claim.amount), (USER_ID, 1, order.pay_amount));
+    }
+}
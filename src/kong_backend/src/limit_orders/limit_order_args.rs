@@ -0,0 +1,14 @@
+use candid::{CandidType, Nat};
+use serde::{Deserialize, Serialize};
+
+/// Data structure for the arguments of the `place_limit_order` function.
+/// Used in StableRequest
+#[derive(CandidType, Debug, Clone, Serialize, Deserialize)]
+pub struct PlaceLimitOrderArgs {
+    pub pay_token: String,
+    pub pay_amount: Nat,
+    pub receive_token: String,
+    pub receive_amount: Nat, // minimum receive amount after fees and gas. limit price is receive_amount / pay_amount
+    pub receive_address: Option<String>,
+    pub expires_at: Option<u64>, // order is cancelled and the pay token returned after this time (nanoseconds)
+}
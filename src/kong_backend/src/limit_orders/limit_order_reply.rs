@@ -0,0 +1,26 @@
+use candid::{CandidType, Nat};
+use serde::{Deserialize, Serialize};
+
+use crate::transfers::transfer_reply::TransferIdReply;
+
+#[derive(CandidType, Debug, Clone, Serialize, Deserialize)]
+pub struct LimitOrderReply {
+    pub order_id: u64,
+    pub request_id: u64,
+    pub status: String,
+    pub pay_chain: String,
+    pub pay_address: String,
+    pub pay_symbol: String,
+    pub pay_amount: Nat,
+    pub receive_chain: String,
+    pub receive_address: String,
+    pub receive_symbol: String,
+    pub receive_amount: Nat, // minimum receive amount after fees and gas
+    pub price: f64,          // limit price
+    pub to_address: String,
+    pub expires_at: Option<u64>,
+    pub tx_id: Option<u64>, // swap tx when the order is filled
+    pub transfer_ids: Vec<TransferIdReply>,
+    pub claim_ids: Vec<u64>,
+    pub ts: u64,
+}
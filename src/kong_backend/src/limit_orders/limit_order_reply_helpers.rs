@@ -0,0 +1,66 @@
+use num::BigRational;
+
+use super::limit_order_reply::LimitOrderReply;
+
+use crate::helpers::math_helpers::price_rounded;
+use crate::helpers::nat_helpers::{nat_is_zero, nat_to_bigint, nat_to_decimal_precision};
+use crate::stable_limit_order::stable_limit_order::StableLimitOrder;
+use crate::stable_token::token::Token;
+use crate::stable_token::token_map;
+use crate::transfers::transfer_reply_helpers::to_transfer_ids;
+
+pub fn to_limit_order_reply(order: &StableLimitOrder) -> LimitOrderReply {
+    let pay_token = token_map::get_by_token_id(order.pay_token_id);
+    let (pay_chain, pay_address, pay_symbol) = pay_token.as_ref().map_or_else(
+        || {
+            (
+                "Pay chain not found".to_string(),
+                "Pay address not found".to_string(),
+                "Pay symbol not found".to_string(),
+            )
+        },
+        |token| (token.chain().to_string(), token.address(), token.symbol().to_string()),
+    );
+    let receive_token = token_map::get_by_token_id(order.receive_token_id);
+    let (receive_chain, receive_address, receive_symbol) = receive_token.as_ref().map_or_else(
+        || {
+            (
+                "Receive chain not found".to_string(),
+                "Receive address not found".to_string(),
+                "Receive symbol not found".to_string(),
+            )
+        },
+        |token| (token.chain().to_string(), token.address(), token.symbol().to_string()),
+    );
+    // price = receive_amount / pay_amount in the same decimal precision
+    let price = match (pay_token, receive_token) {
+        (Some(pay_token), Some(receive_token)) if !nat_is_zero(&order.pay_amount) => {
+            let max_decimals = std::cmp::max(pay_token.decimals(), receive_token.decimals());
+            let pay_amount = nat_to_decimal_precision(&order.pay_amount, pay_token.decimals(), max_decimals);
+            let receive_amount = nat_to_decimal_precision(&order.receive_amount, receive_token.decimals(), max_decimals);
+            price_rounded(&BigRational::new(nat_to_bigint(&receive_amount), nat_to_bigint(&pay_amount))).unwrap_or(0_f64)
+        }
+        _ => 0_f64,
+    };
+
+    LimitOrderReply {
+        order_id: order.order_id,
+        request_id: order.request_id,
+        status: order.status.to_string(),
+        pay_chain,
+        pay_address,
+        pay_symbol,
+        pay_amount: order.pay_amount.clone(),
+        receive_chain,
+        receive_address,
+        receive_symbol,
+        receive_amount: order.receive_amount.clone(),
+        price,
+        to_address: order.to_address.to_string(),
+        expires_at: order.expires_at,
+        tx_id: order.tx_id,
+        transfer_ids: to_transfer_ids(&order.transfer_ids),
+        claim_ids: order.claim_ids.clone(),
+        ts: order.ts,
+    }
+}
@@ -0,0 +1,189 @@
+use super::cancel_limit_order::close_limit_order;
+
+use crate::ic::{get_time::get_time, guards::not_in_maintenance_mode};
+use crate::stable_limit_order::limit_order_map;
+use crate::stable_limit_order::stable_limit_order::{LimitOrderStatus, StableLimitOrder};
+use crate::stable_request::{request::Request, request_map, stable_request::StableRequest, status::StatusCode};
+use crate::stable_token::{token::Token, token_map};
+use crate::swap::archive_to_kong_data::archive_to_kong_data;
+use crate::swap::send_receive_token::send_receive_token;
+use crate::swap::swap_amounts::user_swap_amounts;
+use crate::swap::swap_args::SwapArgs;
+use crate::swap::update_liquidity_pool::update_liquidity_pool;
+
+// limit price is enforced by the receive_amount of the order, so slippage is not checked
+const LIMIT_ORDER_MAX_SLIPPAGE: f64 = 100.0;
+
+/// Expire open limit orders past their expiry time and fill open limit orders whose limit price has been crossed
+/// orders are only scanned for fills after a pool price or the open orders have changed
+pub async fn process_limit_orders_timer() {
+    if not_in_maintenance_mode().is_err() {
+        return;
+    }
+
+    let ts = get_time();
+    let scan_pending = limit_order_map::take_scan_pending();
+
+    // oldest orders are filled first
+    for order_id in limit_order_map::get_open_order_ids() {
+        // order may have been filled or cancelled since the snapshot of order_ids
+        let order = match limit_order_map::get_by_order_id(order_id) {
+            Some(order) if order.status == LimitOrderStatus::Open => order,
+            _ => continue,
+        };
+
+        if order.expires_at.is_some_and(|expires_at| expires_at <= ts) {
+            let request_id = request_map::insert(&StableRequest::new(order.user_id, &Request::CancelLimitOrder(order_id), ts));
+            close_limit_order(request_id, &order, LimitOrderStatus::Expired, ts).await;
+            continue;
+        }
+
+        if scan_pending {
+            fill_limit_order(&order, ts).await;
+        }
+    }
+}
+
+/// fill the order through the pools if swapping pay_amount returns at least receive_amount after fees and gas
+/// the pool update is synchronous so the order is filled at the price it was checked at
+async fn fill_limit_order(order: &StableLimitOrder, ts: u64) {
+    let (Some(pay_token), Some(receive_token)) = (
+        token_map::get_by_token_id(order.pay_token_id),
+        token_map::get_by_token_id(order.receive_token_id),
+    ) else {
+        return;
+    };
+    if pay_token.is_removed() || receive_token.is_removed() {
+        return;
+    }
+
+    // check the limit price has been crossed before registering a request. the timer is the caller, so the fee level of
+    // the order's user is passed in
+    match user_swap_amounts(order.user_id, &pay_token, &order.pay_amount, &receive_token) {
+        Ok((receive_amount_with_fees_and_gas, ..)) if receive_amount_with_fees_and_gas >= order.receive_amount => (),
+        _ => return,
+    }
+
+    let to_address = order.to_address.to_string();
+    let args = SwapArgs {
+        pay_token: pay_token.address_with_chain(),
+        pay_amount: order.pay_amount.clone(),
+        pay_tx_id: None,
+        receive_token: receive_token.address_with_chain(),
+        receive_amount: Some(order.receive_amount.clone()),
+        receive_address: Some(to_address),
+        max_slippage: Some(LIMIT_ORDER_MAX_SLIPPAGE),
+        referred_by: None,
+        use_deposit: None,
+        deadline: None,
+    };
+    let request_id = request_map::insert(&StableRequest::new(order.user_id, &Request::Swap(args), ts));
+    request_map::update_status(request_id, StatusCode::Start, None);
+
+    let (receive_amount_with_fees_and_gas, mid_price, price, slippage, swaps) = match update_liquidity_pool(
+        request_id,
+        order.user_id,
+        &pay_token,
+        &order.pay_amount,
+        &receive_token,
+        Some(&order.receive_amount),
+        LIMIT_ORDER_MAX_SLIPPAGE,
+        None,
+    ) {
+        Ok(swap) => swap,
+        Err(_) => {
+            // order stays open and is tried again on the next price change
+            request_map::update_status(request_id, StatusCode::Failed, None);
+            return;
+        }
+    };
+    request_map::update_status(request_id, StatusCode::SwapSuccess, None);
+
+    // mark the order as filled before sending the receive token
+    let mut order = StableLimitOrder {
+        status: LimitOrderStatus::Filled,
+        fill_request_id: Some(request_id),
+        ..order.clone()
+    };
+    limit_order_map::update(&order);
+
+    // escrow transfer is the pay token transfer of the swap
+    let mut transfer_ids = order.transfer_ids.clone();
+    let reply = send_receive_token(
+        request_id,
+        order.user_id,
+        &pay_token,
+        &order.pay_amount,
+        &receive_token,
+        &receive_amount_with_fees_and_gas,
+        &order.to_address,
+        &mut transfer_ids,
+        mid_price,
+        price,
+        slippage,
+        &swaps,
+        ts,
+    )
+    .await;
+
+    order.tx_id = Some(reply.tx_id);
+    order.transfer_ids = transfer_ids;
+    order.claim_ids.extend(reply.claim_ids);
+    limit_order_map::update(&order);
+
+    request_map::update_status(request_id, StatusCode::Success, None);
+    let _ = archive_to_kong_data(request_id);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use candid::Nat;
+    use futures::executor::block_on;
+    use std::panic::{catch_unwind, AssertUnwindSafe};
+
+    use crate::helpers::nat_helpers::nat_add;
+    use crate::ic::address::Address;
+    use crate::lp_ledger::lp_ledger_helpers::tests::insert_user;
+    use crate::stable_pool::pool_map;
+    use crate::stable_pool::pool_map::tests::insert_pool;
+    use crate::swap::swap_amounts::user_swap_amounts;
+
+    const USER_ID: u32 = 100;
+
+    /// open order to swap 1_000 T1 for at least receive_amount T2
+    fn insert_order(to_address: &Address, receive_amount: &Nat, ts: u64) -> StableLimitOrder {
+        let order = StableLimitOrder::new(USER_ID, 1, &Nat::from(1_000_u32), 2, receive_amount, to_address, None, 0, &[], ts);
+        limit_order_map::get_by_order_id(limit_order_map::insert(&order)).unwrap()
+    }
+
+    #[test]
+    fn test_fill_limit_order_receive_amount() {
+        let pool = insert_pool(1_000_000);
+        let ts = get_time();
+        let to_address = Address::PrincipalId(insert_user(USER_ID, 0, ts));
+        let (receive_amount, ..) = user_swap_amounts(USER_ID, &pool.token_0(), &Nat::from(1_000_u32), &pool.token_1()).unwrap();
+
+        // the order is not filled while swapping pay_amount returns less than receive_amount
+        let order = insert_order(&to_address, &nat_add(&receive_amount, &Nat::from(1_u32)), ts);
+        block_on(fill_limit_order(&order, ts));
+        assert_eq!(
+            limit_order_map::get_by_order_id(order.order_id).unwrap().status,
+            LimitOrderStatus::Open
+        );
+        assert_eq!(pool_map::get_by_pool_id(pool.pool_id).unwrap().balance_0, pool.balance_0);
+
+        // the order is filled once it returns receive_amount. the swap is applied and the order marked filled before the
+        // receive token is sent, which calls the ledger and so panics outside a canister
+        let order = insert_order(&to_address, &receive_amount, ts);
+        assert!(catch_unwind(AssertUnwindSafe(|| block_on(fill_limit_order(&order, ts)))).is_err());
+        let filled_order = limit_order_map::get_by_order_id(order.order_id).unwrap();
+        assert_eq!(filled_order.status, LimitOrderStatus::Filled);
+        assert!(filled_order.fill_request_id.is_some());
+        assert_eq!(
+            pool_map::get_by_pool_id(pool.pool_id).unwrap().balance_0,
+            nat_add(&pool.balance_0, &order.pay_amount)
+        );
+        assert_eq!(limit_order_map::get_open_amount(1), Nat::from(1_000_u32));
+    }
+}
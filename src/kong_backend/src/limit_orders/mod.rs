@@ -0,0 +1,8 @@
+pub mod cancel_limit_order;
+pub mod limit_order_args;
+pub mod limit_order_reply;
+pub mod limit_order_reply_helpers;
+#[allow(clippy::module_inception)]
+pub mod limit_orders;
+pub mod limit_orders_timer;
+pub mod place_limit_order;
@@ -0,0 +1,146 @@
+use candid::Nat;
+use ic_cdk::update;
+use icrc_ledger_types::icrc1::account::Account;
+use std::time::Duration;
+
+use super::limit_order_args::PlaceLimitOrderArgs;
+use super::limit_order_reply::LimitOrderReply;
+use super::limit_order_reply_helpers::to_limit_order_reply;
+
+use crate::helpers::nat_helpers::nat_is_zero;
+use crate::ic::address::Address;
+use crate::ic::address_helpers::get_address;
+use crate::ic::get_time::get_time;
+use crate::ic::guards::not_in_maintenance_mode;
+use crate::ic::id::caller_id;
+use crate::ic::transfer::icrc2_transfer_from;
+use crate::stable_kong_settings::kong_settings_map;
+use crate::stable_limit_order::limit_order_map;
+use crate::stable_limit_order::stable_limit_order::StableLimitOrder;
+use crate::stable_request::{reply::Reply, request::Request, request_map, stable_request::StableRequest, status::StatusCode};
+use crate::stable_token::{stable_token::StableToken, token::Token, token_map};
+use crate::stable_transfer::{stable_transfer::StableTransfer, transfer_map, tx_id::TxId};
+use crate::stable_user::banned_user_map::is_banned_user;
+use crate::stable_user::user_map;
+use crate::swap::swap_amounts::swap_amounts;
+
+/// Place a limit order. The pay token is escrowed with icrc2_transfer_from and the order is filled
+/// through the pools once swapping pay_amount returns at least receive_amount after fees and gas
+#[update(guard = "not_in_maintenance_mode")]
+async fn place_limit_order(args: PlaceLimitOrderArgs) -> Result<LimitOrderReply, String> {
+    let (user_id, pay_token, pay_amount, receive_token, receive_amount, to_address) = check_arguments(&args).await?;
+    let ts = get_time();
+    let request_id = request_map::insert(&StableRequest::new(user_id, &Request::PlaceLimitOrder(args.clone()), ts));
+
+    request_map::update_status(request_id, StatusCode::Start, None);
+
+    let transfer_id = transfer_from_token(request_id, &caller_id(), &pay_token, &pay_amount, ts)
+        .await
+        .inspect_err(|_| {
+            request_map::update_status(request_id, StatusCode::Failed, None);
+        })?;
+
+    let order_id = limit_order_map::insert(&StableLimitOrder::new(
+        user_id,
+        pay_token.token_id(),
+        &pay_amount,
+        receive_token.token_id(),
+        &receive_amount,
+        &to_address,
+        args.expires_at,
+        request_id,
+        &[transfer_id],
+        ts,
+    ));
+    let order = limit_order_map::get_by_order_id(order_id).ok_or(format!("Req #{} failed. Limit order not found", request_id))?;
+    let reply = to_limit_order_reply(&order);
+    request_map::update_reply(request_id, Reply::LimitOrder(reply.clone()));
+    request_map::update_status(request_id, StatusCode::Success, None);
+
+    Ok(reply)
+}
+
+async fn check_arguments(args: &PlaceLimitOrderArgs) -> Result<(u32, StableToken, Nat, StableToken, Nat, Address), String> {
+    let pay_token = token_map::get_by_token(&args.pay_token)?;
+    if pay_token.is_removed() {
+        Err("Pay token is suspended or removed".to_string())?;
+    }
+    if !pay_token.is_icrc2() {
+        Err("Pay token must support ICRC2".to_string())?;
+    }
+    let pay_amount = args.pay_amount.clone();
+    if nat_is_zero(&pay_amount) {
+        Err("Pay amount is zero".to_string())?;
+    }
+
+    let receive_token = token_map::get_by_token(&args.receive_token)?;
+    if receive_token.is_removed() {
+        Err("Receive token is suspended or removed".to_string())?;
+    }
+    let receive_amount = args.receive_amount.clone();
+    if nat_is_zero(&receive_amount) {
+        Err("Receive amount is zero".to_string())?;
+    }
+
+    // use specified address or default to caller's principal id
+    let to_address = match args.receive_address {
+        Some(ref address) => get_address(&receive_token, address)?,
+        None => Address::PrincipalId(caller_id()),
+    };
+
+    if let Some(expires_at) = args.expires_at {
+        if expires_at <= get_time() {
+            Err("Expiry time is in the past".to_string())?;
+        }
+    }
+
+    // make sure there is a swap path between the tokens
+    swap_amounts(&pay_token, Some(&pay_amount), &receive_token)?;
+
+    // make sure user is registered, if not create a new user
+    let user_id = user_map::insert(None)?;
+    // check if user is banned
+    if let Some(banned_until) = is_banned_user(user_id) {
+        let now = get_time();
+        if banned_until > now {
+            let duration_ns = Duration::from_nanos(banned_until - now);
+            let duration_min = duration_ns.as_secs() / 60;
+            Err(format!("Too many consecutive errors. User is banned for {} minutes", duration_min))?;
+        }
+    }
+
+    Ok((user_id, pay_token, pay_amount, receive_token, receive_amount, to_address))
+}
+
+/// escrow the pay token in Kong
+async fn transfer_from_token(
+    request_id: u64,
+    from_principal_id: &Account,
+    token: &StableToken,
+    amount: &Nat,
+    ts: u64,
+) -> Result<u64, String> {
+    let kong_backend = kong_settings_map::get().kong_backend;
+
+    request_map::update_status(request_id, StatusCode::SendPayToken, None);
+
+    match icrc2_transfer_from(token, amount, from_principal_id, &kong_backend).await {
+        Ok(tx_id) => {
+            let transfer_id = transfer_map::insert(&StableTransfer {
+                transfer_id: 0,
+                request_id,
+                is_send: true,
+                amount: amount.clone(),
+                token_id: token.token_id(),
+                tx_id: TxId::BlockIndex(tx_id),
+                ts,
+            });
+            request_map::update_status(request_id, StatusCode::SendPayTokenSuccess, None);
+            Ok(transfer_id)
+        }
+        Err(e) => {
+            request_map::update_status(request_id, StatusCode::SendPayTokenFailed, Some(&e));
+            Err(format!("Req #{} failed. Pay token transfer_from failed. {}", request_id, e))
+        }
+    }
+}
@@ -0,0 +1,232 @@
+use candid::{Nat, Principal};
+use ic_cdk::{query, update};
+use icrc_ledger_types::icrc::generic_metadata_value::MetadataValue;
+use icrc_ledger_types::icrc1::account::Account;
+use icrc_ledger_types::icrc1::transfer::{TransferArg, TransferError};
+
+use super::lp_ledger_helpers::{
+    check_created_at_time, get_balances, get_ledger_lp_token, get_lp_token, get_user_id, insert_user_id, normalize_account,
+    GENERIC_ERROR_CODE, MAX_MEMO_LENGTH,
+};
+
+use crate::helpers::nat_helpers::{nat_is_zero, nat_zero};
+use crate::ic::get_time::get_time;
+use crate::ic::guards::not_in_maintenance_mode;
+use crate::stable_lp_token::lp_token_map;
+use crate::stable_lp_token::stable_lp_block::{LPBlockOp, StableLPBlock};
+use crate::stable_lp_token::transfer::transfer_from_user_id;
+use crate::stable_token::lp_token::LPToken;
+
+fn generic_error(message: String) -> TransferError {
+    TransferError::GenericError {
+        error_code: Nat::from(GENERIC_ERROR_CODE),
+        message,
+    }
+}
+
+/// ICRC-1 metadata of the LP token. LP token transfers have no fee
+pub fn metadata(token: &LPToken) -> Vec<(String, MetadataValue)> {
+    vec![
+        ("icrc1:name".to_string(), MetadataValue::Text(token.name())),
+        ("icrc1:symbol".to_string(), MetadataValue::Text(token.symbol.clone())),
+        ("icrc1:decimals".to_string(), MetadataValue::Nat(Nat::from(token.decimals))),
+        ("icrc1:fee".to_string(), MetadataValue::Nat(nat_zero())),
+    ]
+}
+
+/// ICRC-1 metadata of the LP token's ledger
+#[query(hidden = true, guard = "not_in_maintenance_mode")]
+fn lp_ledger_metadata(lp_token_id: u32) -> Result<Vec<(String, MetadataValue)>, String> {
+    let token = get_lp_token(lp_token_id)?;
+    Ok(metadata(&token))
+}
+
+/// ICRC-1 balance of an account in the LP token's ledger
+#[query(hidden = true, guard = "not_in_maintenance_mode")]
+fn lp_ledger_balance_of(lp_token_id: u32, account: Account) -> Result<Nat, String> {
+    let token = get_lp_token(lp_token_id)?;
+    Ok(get_user_id(&account).map_or_else(nat_zero, |user_id| get_balances(token.token_id, user_id, get_time()).0))
+}
+
+/// ICRC-1 total supply of the LP token
+#[query(hidden = true, guard = "not_in_maintenance_mode")]
+fn lp_ledger_total_supply(lp_token_id: u32) -> Result<Nat, String> {
+    let token = get_lp_token(lp_token_id)?;
+    Ok(lp_token_map::get_total_supply(token.token_id))
+}
+
+/// ICRC-1 transfer of the LP tokens of owner, called by the LP token's ledger with the caller of icrc1_transfer as owner
+#[update(hidden = true, guard = "not_in_maintenance_mode")]
+fn lp_ledger_transfer(lp_token_id: u32, owner: Principal, args: TransferArg) -> Result<Nat, TransferError> {
+    let token = get_ledger_lp_token(lp_token_id).map_err(generic_error)?;
+    transfer(&token, owner, args, get_time())
+}
+
+/// ICRC-1 transfer of the LP tokens of owner at ts
+/// - transfers have no fee and locked LP tokens can not be transferred
+/// - transfers with created_at_time are deduplicated
+pub fn transfer(token: &LPToken, owner: Principal, args: TransferArg, ts: u64) -> Result<Nat, TransferError> {
+    let from = normalize_account(&Account {
+        owner,
+        subaccount: args.from_subaccount,
+    });
+    let to = normalize_account(&args.to);
+    if args.fee.as_ref().is_some_and(|fee| !nat_is_zero(fee)) {
+        Err(TransferError::BadFee { expected_fee: nat_zero() })?
+    }
+    let memo = args.memo.map(|memo| memo.0.into_vec());
+    if memo.as_ref().is_some_and(|memo| memo.len() > MAX_MEMO_LENGTH) {
+        Err(generic_error(format!("Memo must be at most {} bytes", MAX_MEMO_LENGTH)))?
+    }
+
+    let block = StableLPBlock {
+        from: Some(from),
+        to: Some(to),
+        memo,
+        created_at_time: args.created_at_time,
+        ..StableLPBlock::new(token.token_id, LPBlockOp::Transfer, &args.amount, ts)
+    };
+    check_created_at_time(&block)?;
+
+    let from_user_id = get_user_id(&from).ok_or(TransferError::InsufficientFunds { balance: nat_zero() })?;
+    let (_, unlocked_balance) = get_balances(token.token_id, from_user_id, ts);
+    if unlocked_balance < args.amount {
+        Err(TransferError::InsufficientFunds { balance: unlocked_balance })?
+    }
+    let to_user_id = insert_user_id(&to).map_err(generic_error)?;
+
+    transfer_from_user_id(from_user_id, to_user_id, block)
+        .map(|(_, block_idx)| Nat::from(block_idx))
+        .map_err(generic_error)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use icrc_ledger_types::icrc1::transfer::Memo;
+
+    use crate::lp_ledger::lp_ledger_helpers::tests::{insert_account_user, insert_lp_token, insert_user};
+    use crate::stable_lp_token::lp_block_map;
+
+    fn transfer_arg(to: Account, amount: u32, created_at_time: Option<u64>) -> TransferArg {
+        TransferArg {
+            from_subaccount: None,
+            to,
+            fee: None,
+            created_at_time,
+            memo: None,
+            amount: Nat::from(amount),
+        }
+    }
+
+    #[test]
+    fn test_transfer() {
+        let ts = get_time();
+        let token = insert_lp_token();
+        let from = insert_user(1, 1_000, ts);
+        let to = insert_user(2, 0, ts);
+
+        let block_idx = transfer(&token, from.owner, transfer_arg(to, 400, None), ts).unwrap();
+        assert_eq!(get_balances(token.token_id, 1, ts).0, Nat::from(600_u32));
+        assert_eq!(get_balances(token.token_id, 2, ts).0, Nat::from(400_u32));
+        let block = lp_block_map::get_blocks(token.token_id, 0, u64::MAX).pop().unwrap();
+        assert_eq!(Nat::from(block.block_idx), block_idx);
+        assert_eq!(block.btype(), "1xfer");
+        assert_eq!(block.from, Some(from));
+        assert_eq!(block.to, Some(to));
+
+        assert_eq!(
+            transfer(&token, from.owner, transfer_arg(to, 601, None), ts),
+            Err(TransferError::InsufficientFunds {
+                balance: Nat::from(600_u32)
+            })
+        );
+        assert_eq!(
+            transfer(
+                &token,
+                from.owner,
+                TransferArg {
+                    fee: Some(Nat::from(1_u32)),
+                    ..transfer_arg(to, 1, None)
+                },
+                ts
+            ),
+            Err(TransferError::BadFee { expected_fee: nat_zero() })
+        );
+    }
+
+    #[test]
+    fn test_transfer_subaccount() {
+        let ts = get_time();
+        let token = insert_lp_token();
+        let from = insert_user(1, 1_000, ts);
+        let subaccount_user_id = 2;
+        let subaccount = insert_account_user(
+            subaccount_user_id,
+            Account {
+                owner: from.owner,
+                subaccount: Some([1; 32]),
+            },
+            0,
+            ts,
+        );
+
+        // LP tokens of a subaccount are held apart from the default subaccount and can be sent from it
+        transfer(&token, from.owner, transfer_arg(subaccount, 400, None), ts).unwrap();
+        assert_eq!(get_user_id(&subaccount), Some(subaccount_user_id));
+        assert_eq!(get_balances(token.token_id, 1, ts).0, Nat::from(600_u32));
+        assert_eq!(get_balances(token.token_id, subaccount_user_id, ts).0, Nat::from(400_u32));
+
+        let subaccount_transfer_arg = TransferArg {
+            from_subaccount: subaccount.subaccount,
+            ..transfer_arg(from, 100, None)
+        };
+        transfer(&token, from.owner, subaccount_transfer_arg, ts).unwrap();
+        assert_eq!(get_balances(token.token_id, 1, ts).0, Nat::from(700_u32));
+        let block = lp_block_map::get_blocks(token.token_id, 0, u64::MAX).pop().unwrap();
+        assert_eq!(block.from, Some(subaccount));
+        assert_eq!(lp_block_map::user_account(subaccount_user_id), Some(subaccount));
+
+        // the default subaccount is the principal id's account
+        let default_subaccount = Account {
+            owner: from.owner,
+            subaccount: Some([0; 32]),
+        };
+        assert_eq!(get_user_id(&default_subaccount), Some(1));
+    }
+
+    #[test]
+    fn test_transfer_created_at_time() {
+        let ts = get_time();
+        let token = insert_lp_token();
+        let from = insert_user(1, 1_000, ts);
+        let to = insert_user(2, 0, ts);
+
+        let block_idx = transfer(&token, from.owner, transfer_arg(to, 100, Some(ts)), ts).unwrap();
+        // the same transfer is rejected, a transfer with another memo is not the same transfer
+        assert_eq!(
+            transfer(&token, from.owner, transfer_arg(to, 100, Some(ts)), ts + 1),
+            Err(TransferError::Duplicate { duplicate_of: block_idx })
+        );
+        let memo_arg = TransferArg {
+            memo: Some(Memo::from(vec![1])),
+            ..transfer_arg(to, 100, Some(ts))
+        };
+        assert!(transfer(&token, from.owner, memo_arg, ts + 1).is_ok());
+
+        assert_eq!(
+            transfer(
+                &token,
+                from.owner,
+                transfer_arg(to, 100, Some(ts)),
+                ts + 2 * lp_block_map::TX_WINDOW
+            ),
+            Err(TransferError::TooOld)
+        );
+        assert_eq!(
+            transfer(&token, from.owner, transfer_arg(to, 100, Some(ts + lp_block_map::TX_WINDOW)), ts),
+            Err(TransferError::CreatedInFuture { ledger_time: ts })
+        );
+        assert_eq!(get_balances(token.token_id, 2, ts).0, Nat::from(200_u32));
+    }
+}
@@ -0,0 +1,309 @@
+use candid::{Nat, Principal};
+use ic_cdk::{query, update};
+use icrc_ledger_types::icrc1::account::Account;
+use icrc_ledger_types::icrc2::allowance::{Allowance, AllowanceArgs};
+use icrc_ledger_types::icrc2::approve::{ApproveArgs, ApproveError};
+use icrc_ledger_types::icrc2::transfer_from::{TransferFromArgs, TransferFromError};
+
+use super::lp_ledger_helpers::{
+    check_created_at_time, get_balances, get_ledger_lp_token, get_lp_token, get_user_id, insert_user_id, normalize_account,
+    GENERIC_ERROR_CODE, MAX_MEMO_LENGTH,
+};
+
+use crate::helpers::nat_helpers::{nat_is_zero, nat_subtract, nat_zero};
+use crate::ic::get_time::get_time;
+use crate::ic::guards::not_in_maintenance_mode;
+use crate::stable_lp_token::lp_allowance_map;
+use crate::stable_lp_token::lp_block_map;
+use crate::stable_lp_token::stable_lp_allowance::StableLPAllowance;
+use crate::stable_lp_token::stable_lp_block::{LPBlockOp, StableLPBlock};
+use crate::stable_lp_token::transfer::transfer_from_user_id;
+use crate::stable_token::lp_token::LPToken;
+
+/// ICRC-2 approval of spender over the LP tokens of owner, called by the LP token's ledger with the caller of icrc2_approve
+/// as owner
+#[update(hidden = true, guard = "not_in_maintenance_mode")]
+fn lp_ledger_approve(lp_token_id: u32, owner: Principal, args: ApproveArgs) -> Result<Nat, ApproveError> {
+    let token = get_ledger_lp_token(lp_token_id).map_err(|message| ApproveError::GenericError {
+        error_code: Nat::from(GENERIC_ERROR_CODE),
+        message,
+    })?;
+    approve(&token, owner, args, get_time())
+}
+
+/// ICRC-2 approval of spender over the LP tokens of owner at ts. approvals with created_at_time are deduplicated
+pub fn approve(token: &LPToken, owner: Principal, args: ApproveArgs, ts: u64) -> Result<Nat, ApproveError> {
+    let generic_error = |message: String| ApproveError::GenericError {
+        error_code: Nat::from(GENERIC_ERROR_CODE),
+        message,
+    };
+    let from = normalize_account(&Account {
+        owner,
+        subaccount: args.from_subaccount,
+    });
+    let spender = normalize_account(&args.spender);
+    if spender == from {
+        Err(generic_error("Can not approve self".to_string()))?
+    }
+    if args.fee.as_ref().is_some_and(|fee| !nat_is_zero(fee)) {
+        Err(ApproveError::BadFee { expected_fee: nat_zero() })?
+    }
+    if args.expires_at.is_some_and(|expires_at| expires_at <= ts) {
+        Err(ApproveError::Expired { ledger_time: ts })?
+    }
+    let memo = args.memo.map(|memo| memo.0.into_vec());
+    if memo.as_ref().is_some_and(|memo| memo.len() > MAX_MEMO_LENGTH) {
+        Err(generic_error(format!("Memo must be at most {} bytes", MAX_MEMO_LENGTH)))?
+    }
+
+    let block = StableLPBlock {
+        from: Some(from),
+        spender: Some(spender),
+        expires_at: args.expires_at,
+        expected_allowance: args.expected_allowance.clone(),
+        memo,
+        created_at_time: args.created_at_time,
+        ..StableLPBlock::new(token.token_id, LPBlockOp::Approve, &args.amount, ts)
+    };
+    check_created_at_time(&block)?;
+
+    // make sure user is registered, if not create a new user
+    let user_id = insert_user_id(&from).map_err(generic_error)?;
+    let current_allowance =
+        lp_allowance_map::get(token.token_id, user_id, &spender, ts).map_or_else(nat_zero, |allowance| allowance.amount);
+    if args
+        .expected_allowance
+        .as_ref()
+        .is_some_and(|expected_allowance| *expected_allowance != current_allowance)
+    {
+        Err(ApproveError::AllowanceChanged { current_allowance })?
+    }
+
+    lp_allowance_map::update(
+        token.token_id,
+        user_id,
+        &spender,
+        &StableLPAllowance {
+            amount: args.amount.clone(),
+            expires_at: args.expires_at,
+            ts,
+        },
+    );
+    let block_idx = lp_block_map::insert(block);
+
+    Ok(Nat::from(block_idx))
+}
+
+/// ICRC-2 allowance of spender over the LP tokens of account
+#[query(hidden = true, guard = "not_in_maintenance_mode")]
+fn lp_ledger_allowance(lp_token_id: u32, args: AllowanceArgs) -> Result<Allowance, String> {
+    let token = get_lp_token(lp_token_id)?;
+    Ok(allowance(&token, &args, get_time()))
+}
+
+pub fn allowance(token: &LPToken, args: &AllowanceArgs, ts: u64) -> Allowance {
+    let allowance = get_user_id(&args.account)
+        .and_then(|user_id| lp_allowance_map::get(token.token_id, user_id, &normalize_account(&args.spender), ts));
+    allowance.map_or_else(
+        || Allowance {
+            allowance: nat_zero(),
+            expires_at: None,
+        },
+        |allowance| Allowance {
+            allowance: allowance.amount,
+            expires_at: allowance.expires_at,
+        },
+    )
+}
+
+/// ICRC-2 transfer of LP tokens by spender, called by the LP token's ledger with the caller of icrc2_transfer_from as spender
+#[update(hidden = true, guard = "not_in_maintenance_mode")]
+fn lp_ledger_transfer_from(lp_token_id: u32, spender: Principal, args: TransferFromArgs) -> Result<Nat, TransferFromError> {
+    let token = get_ledger_lp_token(lp_token_id).map_err(|message| TransferFromError::GenericError {
+        error_code: Nat::from(GENERIC_ERROR_CODE),
+        message,
+    })?;
+    transfer_from(&token, spender, args, get_time())
+}
+
+/// ICRC-2 transfer of LP tokens by spender at ts. locked LP tokens can not be transferred
+/// transfers with created_at_time are deduplicated
+pub fn transfer_from(token: &LPToken, spender: Principal, args: TransferFromArgs, ts: u64) -> Result<Nat, TransferFromError> {
+    let generic_error = |message: String| TransferFromError::GenericError {
+        error_code: Nat::from(GENERIC_ERROR_CODE),
+        message,
+    };
+    let spender = normalize_account(&Account {
+        owner: spender,
+        subaccount: args.spender_subaccount,
+    });
+    if args.fee.as_ref().is_some_and(|fee| !nat_is_zero(fee)) {
+        Err(TransferFromError::BadFee { expected_fee: nat_zero() })?
+    }
+    let memo = args.memo.map(|memo| memo.0.into_vec());
+    if memo.as_ref().is_some_and(|memo| memo.len() > MAX_MEMO_LENGTH) {
+        Err(generic_error(format!("Memo must be at most {} bytes", MAX_MEMO_LENGTH)))?
+    }
+
+    let block = StableLPBlock {
+        from: Some(normalize_account(&args.from)),
+        to: Some(normalize_account(&args.to)),
+        spender: Some(spender),
+        memo,
+        created_at_time: args.created_at_time,
+        ..StableLPBlock::new(token.token_id, LPBlockOp::Transfer, &args.amount, ts)
+    };
+    check_created_at_time(&block)?;
+
+    let from_user_id = get_user_id(&args.from).ok_or(TransferFromError::InsufficientFunds { balance: nat_zero() })?;
+    let allowance = lp_allowance_map::get(token.token_id, from_user_id, &spender, ts)
+        .ok_or(TransferFromError::InsufficientAllowance { allowance: nat_zero() })?;
+    if allowance.amount < args.amount {
+        Err(TransferFromError::InsufficientAllowance {
+            allowance: allowance.amount.clone(),
+        })?
+    }
+    let (_, unlocked_balance) = get_balances(token.token_id, from_user_id, ts);
+    if unlocked_balance < args.amount {
+        Err(TransferFromError::InsufficientFunds { balance: unlocked_balance })?
+    }
+    let to_user_id = insert_user_id(&args.to).map_err(generic_error)?;
+
+    let (_, block_idx) = transfer_from_user_id(from_user_id, to_user_id, block).map_err(generic_error)?;
+
+    // use up the allowance
+    lp_allowance_map::update(
+        token.token_id,
+        from_user_id,
+        &spender,
+        &StableLPAllowance {
+            amount: nat_subtract(&allowance.amount, &args.amount).unwrap_or(nat_zero()),
+            ts,
+            ..allowance
+        },
+    );
+
+    Ok(Nat::from(block_idx))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::lp_ledger::lp_ledger_helpers::tests::{insert_lp_token, insert_user};
+
+    fn approve_args(spender: Account, amount: u32) -> ApproveArgs {
+        ApproveArgs {
+            from_subaccount: None,
+            spender,
+            amount: Nat::from(amount),
+            expected_allowance: None,
+            expires_at: None,
+            fee: None,
+            memo: None,
+            created_at_time: None,
+        }
+    }
+
+    fn transfer_from_args(from: Account, to: Account, amount: u32) -> TransferFromArgs {
+        TransferFromArgs {
+            spender_subaccount: None,
+            from,
+            to,
+            amount: Nat::from(amount),
+            fee: None,
+            memo: None,
+            created_at_time: None,
+        }
+    }
+
+    #[test]
+    fn test_approve() {
+        let ts = get_time();
+        let token = insert_lp_token();
+        let owner = insert_user(1, 1_000, ts);
+        let spender = insert_user(2, 0, ts);
+        let allowance_args = AllowanceArgs { account: owner, spender };
+
+        approve(&token, owner.owner, approve_args(spender, 300), ts).unwrap();
+        assert_eq!(allowance(&token, &allowance_args, ts).allowance, Nat::from(300_u32));
+
+        // expected_allowance must match the current allowance
+        assert_eq!(
+            approve(
+                &token,
+                owner.owner,
+                ApproveArgs {
+                    expected_allowance: Some(Nat::from(100_u32)),
+                    ..approve_args(spender, 500)
+                },
+                ts
+            ),
+            Err(ApproveError::AllowanceChanged {
+                current_allowance: Nat::from(300_u32)
+            })
+        );
+        // expired allowances are zero
+        approve(
+            &token,
+            owner.owner,
+            ApproveArgs {
+                expires_at: Some(ts + 10),
+                created_at_time: Some(ts),
+                ..approve_args(spender, 500)
+            },
+            ts,
+        )
+        .unwrap();
+        assert_eq!(allowance(&token, &allowance_args, ts + 9).allowance, Nat::from(500_u32));
+        assert_eq!(allowance(&token, &allowance_args, ts + 10).allowance, nat_zero());
+        // the same approval is a duplicate
+        assert!(matches!(
+            approve(
+                &token,
+                owner.owner,
+                ApproveArgs {
+                    expires_at: Some(ts + 10),
+                    created_at_time: Some(ts),
+                    ..approve_args(spender, 500)
+                },
+                ts + 1,
+            ),
+            Err(ApproveError::Duplicate { .. })
+        ));
+    }
+
+    #[test]
+    fn test_transfer_from() {
+        let ts = get_time();
+        let token = insert_lp_token();
+        let owner = insert_user(1, 1_000, ts);
+        let spender = insert_user(2, 0, ts);
+        let to = insert_user(3, 0, ts);
+
+        assert_eq!(
+            transfer_from(&token, spender.owner, transfer_from_args(owner, to, 100), ts),
+            Err(TransferFromError::InsufficientAllowance { allowance: nat_zero() })
+        );
+
+        approve(&token, owner.owner, approve_args(spender, 300), ts).unwrap();
+        let block_idx = transfer_from(&token, spender.owner, transfer_from_args(owner, to, 200), ts).unwrap();
+        assert_eq!(get_balances(token.token_id, 1, ts).0, Nat::from(800_u32));
+        assert_eq!(get_balances(token.token_id, 3, ts).0, Nat::from(200_u32));
+        let allowance_args = AllowanceArgs { account: owner, spender };
+        assert_eq!(allowance(&token, &allowance_args, ts).allowance, Nat::from(100_u32));
+
+        // transfer_from is logged as an ICRC-2 transfer with the spender
+        let block = lp_block_map::get_blocks(token.token_id, 0, u64::MAX).pop().unwrap();
+        assert_eq!(Nat::from(block.block_idx), block_idx);
+        assert_eq!(block.btype(), "2xfer");
+        assert_eq!(block.spender, Some(spender));
+
+        assert_eq!(
+            transfer_from(&token, spender.owner, transfer_from_args(owner, to, 101), ts),
+            Err(TransferFromError::InsufficientAllowance {
+                allowance: Nat::from(100_u32)
+            })
+        );
+    }
+}
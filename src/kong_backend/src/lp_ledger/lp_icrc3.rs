@@ -0,0 +1,37 @@
+use candid::Nat;
+use ic_cdk::query;
+use icrc_ledger_types::icrc3::blocks::{BlockWithId, GetBlocksRequest, GetBlocksResult};
+
+use super::lp_ledger_helpers::get_lp_token;
+
+use crate::ic::guards::not_in_maintenance_mode;
+use crate::stable_lp_token::lp_block_map;
+
+// max number of blocks returned per call
+pub const MAX_BLOCKS: u64 = 1_000;
+
+/// ICRC-3 blocks of the LP token's ledger. all blocks are held by Kong so there are no archived blocks
+#[query(hidden = true, guard = "not_in_maintenance_mode")]
+fn lp_ledger_get_blocks(lp_token_id: u32, args: Vec<GetBlocksRequest>) -> Result<GetBlocksResult, String> {
+    let token = get_lp_token(lp_token_id)?;
+
+    let mut blocks = Vec::new();
+    for request in args {
+        let (start, length) = request.as_start_and_length()?;
+        let length = std::cmp::min(length, MAX_BLOCKS.saturating_sub(blocks.len() as u64));
+        blocks.extend(
+            lp_block_map::get_blocks(token.token_id, start, length)
+                .into_iter()
+                .map(|block| BlockWithId {
+                    id: Nat::from(block.block_idx),
+                    block: block.to_icrc3_value(),
+                }),
+        );
+    }
+
+    Ok(GetBlocksResult {
+        log_length: Nat::from(lp_block_map::get_log_length(token.token_id)),
+        blocks,
+        archived_blocks: Vec::new(),
+    })
+}
@@ -0,0 +1,117 @@
+use candid::{CandidType, Encode, Principal};
+use ic_cdk::api::management_canister::main::{
+    create_canister, install_code, CanisterInstallMode, CanisterSettings, CreateCanisterArgument, InstallCodeArgument,
+};
+use std::cell::RefCell;
+use std::collections::BTreeSet;
+
+use super::lp_ledger_helpers::get_lp_token;
+
+use crate::ic::logging::error_log;
+use crate::stable_kong_settings::kong_settings_map;
+use crate::stable_lp_token::stable_lp_ledger_wasm::StableLPLedgerWasm;
+use crate::stable_memory::LP_LEDGER_WASM;
+use crate::stable_token::lp_token::LPToken;
+use crate::stable_token::stable_token::StableToken;
+use crate::stable_token::token_map;
+use crate::stable_user::user_map;
+
+// cycles each LP token ledger is created with
+const LP_LEDGER_CYCLES: u128 = 1_000_000_000_000;
+
+thread_local! {
+    // token_ids of the LP tokens with a ledger being created, so only one ledger is created for each LP token
+    static CREATING_LEDGERS: RefCell<BTreeSet<u32>> = RefCell::default();
+}
+
+/// init args of kong_lp_ledger
+#[derive(CandidType)]
+struct LPLedgerSettings {
+    kong_backend: Principal,
+    lp_token_id: u32,
+}
+
+pub fn get_wasm() -> Vec<u8> {
+    LP_LEDGER_WASM.with(|w| w.borrow().get().0.clone())
+}
+
+pub fn set_wasm(wasm: Vec<u8>) -> Result<(), String> {
+    LP_LEDGER_WASM.with(|w| {
+        w.borrow_mut()
+            .set(StableLPLedgerWasm(wasm))
+            .map(|_| ())
+            .map_err(|e| format!("Failed to save LP ledger wasm: {:?}", e))
+    })
+}
+
+/// create the kong_lp_ledger canister of the LP token and set it as the LP token's ledger. returns the ledger id
+/// kong_backend and the King Kong users are the controllers of the ledger
+pub async fn create_ledger(lp_token_id: u32) -> Result<String, String> {
+    let token = get_lp_token(lp_token_id)?;
+    if let Some(ledger_id) = token.ledger_id {
+        Err(format!("Token {} already has ledger {}", token.symbol, ledger_id))?
+    }
+    let wasm_module = get_wasm();
+    if wasm_module.is_empty() {
+        Err("LP ledger wasm not set".to_string())?
+    }
+    if !CREATING_LEDGERS.with(|c| c.borrow_mut().insert(lp_token_id)) {
+        Err(format!("Ledger of token {} is already being created", token.symbol))?
+    }
+    let ledger_id = install_ledger(lp_token_id, wasm_module).await;
+    CREATING_LEDGERS.with(|c| c.borrow_mut().remove(&lp_token_id));
+    let ledger_id = ledger_id?;
+
+    // LP token may have changed while the ledger was created
+    let token = get_lp_token(lp_token_id)?;
+    token_map::update(&StableToken::LP(LPToken {
+        ledger_id: Some(ledger_id.clone()),
+        ..token
+    }));
+    Ok(ledger_id)
+}
+
+/// create the ledger of a new LP token in the background. ledgers are only created once the ledger wasm is set
+pub fn spawn_create_ledger(lp_token_id: u32) {
+    if LP_LEDGER_WASM.with(|w| w.borrow().get().0.is_empty()) {
+        return;
+    }
+    ic_cdk::spawn(async move {
+        if let Err(e) = create_ledger(lp_token_id).await {
+            error_log(&format!("Failed to create ledger of LP token #{}. {}", lp_token_id, e));
+        }
+    });
+}
+
+async fn install_ledger(lp_token_id: u32, wasm_module: Vec<u8>) -> Result<String, String> {
+    let mut controllers: Vec<Principal> = kong_settings_map::get()
+        .kingkong
+        .iter()
+        .filter_map(|user_id| Principal::from_text(user_map::get_by_user_id(*user_id)?.principal_id).ok())
+        .collect();
+    controllers.push(ic_cdk::id());
+    let settings = CanisterSettings {
+        controllers: Some(controllers),
+        ..Default::default()
+    };
+    let canister_id = create_canister(CreateCanisterArgument { settings: Some(settings) }, LP_LEDGER_CYCLES)
+        .await
+        .map_err(|e| format!("Failed to create ledger. {}", e.1))?
+        .0
+        .canister_id;
+
+    let arg = Encode!(&LPLedgerSettings {
+        kong_backend: ic_cdk::id(),
+        lp_token_id,
+    })
+    .map_err(|e| format!("Failed to encode ledger settings. {}", e))?;
+    install_code(InstallCodeArgument {
+        mode: CanisterInstallMode::Install,
+        canister_id,
+        wasm_module,
+        arg,
+    })
+    .await
+    .map_err(|e| format!("Failed to install ledger {}. {}", canister_id, e.1))?;
+    Ok(canister_id.to_text())
+}
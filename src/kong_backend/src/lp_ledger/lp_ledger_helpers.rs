@@ -0,0 +1,232 @@
+use candid::Nat;
+use icrc_ledger_types::icrc1::account::Account;
+use icrc_ledger_types::icrc1::transfer::TransferError;
+use icrc_ledger_types::icrc2::approve::ApproveError;
+use icrc_ledger_types::icrc2::transfer_from::TransferFromError;
+
+use crate::helpers::nat_helpers::nat_zero;
+use crate::ic::id::caller_principal_id;
+use crate::stable_lp_token::lp_block_map::{self, PERMITTED_DRIFT, TX_WINDOW};
+use crate::stable_lp_token::lp_token_map;
+use crate::stable_lp_token::stable_lp_block::StableLPBlock;
+use crate::stable_token::lp_token::LPToken;
+use crate::stable_token::stable_token::StableToken;
+use crate::stable_token::token_map;
+use crate::stable_user::user_map;
+
+// error_code of GenericError replies
+pub const GENERIC_ERROR_CODE: u32 = 1;
+// max length of the memo of a transaction
+pub const MAX_MEMO_LENGTH: usize = 32;
+
+/// LP token by token_id
+pub fn get_lp_token(lp_token_id: u32) -> Result<LPToken, String> {
+    match token_map::get_by_token_id(lp_token_id) {
+        Some(StableToken::LP(token)) => Ok(token),
+        _ => Err("LP token not found".to_string()),
+    }
+}
+
+/// LP token of the ledger calling. only the ledger of an LP token can move its LP tokens
+pub fn get_ledger_lp_token(lp_token_id: u32) -> Result<LPToken, String> {
+    let token = get_lp_token(lp_token_id)?;
+    if token.ledger_id.as_deref() != Some(caller_principal_id().as_str()) {
+        Err("Caller is not the ledger of the LP token".to_string())?
+    }
+    Ok(token)
+}
+
+fn is_default_subaccount(account: &Account) -> bool {
+    *account.effective_subaccount() == [0; 32]
+}
+
+/// account with the default subaccount as None, so the same account is always logged and compared the same
+pub fn normalize_account(account: &Account) -> Account {
+    Account {
+        owner: account.owner,
+        subaccount: account.subaccount.filter(|_| !is_default_subaccount(account)),
+    }
+}
+
+/// principal id of the user holding the LP tokens of the account
+/// - the default subaccount is held by the user of the principal id
+/// - other subaccounts are held by a user with the ICRC-1 text encoding of the account as principal id
+pub fn account_principal_id(account: &Account) -> String {
+    normalize_account(account).to_string()
+}
+
+/// user_id of the account, None if the account is not a registered user
+pub fn get_user_id(account: &Account) -> Option<u32> {
+    user_map::get_by_principal_id(&account_principal_id(account))
+        .ok()
+        .flatten()
+        .map(|user| user.user_id)
+}
+
+/// user_id of the account, registering the account's user if it is not registered
+pub fn insert_user_id(account: &Account) -> Result<u32, String> {
+    user_map::insert_by_principal_id(&account_principal_id(account))
+}
+
+/// balance of the user and the part of it that is not locked
+pub fn get_balances(token_id: u32, user_id: u32, ts: u64) -> (Nat, Nat) {
+    lp_token_map::get_by_token_id_by_user_id(token_id, user_id).map_or_else(
+        || (nat_zero(), nat_zero()),
+        |lp_token| (lp_token.amount.clone(), lp_token.unlocked_amount(ts)),
+    )
+}
+
+/// errors of the created_at_time checks of a transaction, shared by transfer, approve and transfer_from
+#[derive(Debug, PartialEq)]
+pub enum TxTimeError {
+    TooOld,
+    CreatedInFuture { ledger_time: u64 },
+    Duplicate { duplicate_of: Nat },
+}
+
+/// ICRC-1 deduplication of the transaction of the block, at the time of the block
+/// - created_at_time must be within TX_WINDOW + PERMITTED_DRIFT before and PERMITTED_DRIFT after the ledger time
+/// - the same transaction can not be logged twice with the same created_at_time
+pub fn check_created_at_time(block: &StableLPBlock) -> Result<(), TxTimeError> {
+    let Some(created_at_time) = block.created_at_time else {
+        return Ok(());
+    };
+    let ts = block.ts;
+    if created_at_time.saturating_add(TX_WINDOW + PERMITTED_DRIFT) < ts {
+        Err(TxTimeError::TooOld)?
+    }
+    if created_at_time > ts.saturating_add(PERMITTED_DRIFT) {
+        Err(TxTimeError::CreatedInFuture { ledger_time: ts })?
+    }
+    if let Some(block_idx) = lp_block_map::find_duplicate(block) {
+        Err(TxTimeError::Duplicate {
+            duplicate_of: Nat::from(block_idx),
+        })?
+    }
+    Ok(())
+}
+
+impl From<TxTimeError> for TransferError {
+    fn from(e: TxTimeError) -> Self {
+        match e {
+            TxTimeError::TooOld => TransferError::TooOld,
+            TxTimeError::CreatedInFuture { ledger_time } => TransferError::CreatedInFuture { ledger_time },
+            TxTimeError::Duplicate { duplicate_of } => TransferError::Duplicate { duplicate_of },
+        }
+    }
+}
+
+impl From<TxTimeError> for ApproveError {
+    fn from(e: TxTimeError) -> Self {
+        match e {
+            TxTimeError::TooOld => ApproveError::TooOld,
+            TxTimeError::CreatedInFuture { ledger_time } => ApproveError::CreatedInFuture { ledger_time },
+            TxTimeError::Duplicate { duplicate_of } => ApproveError::Duplicate { duplicate_of },
+        }
+    }
+}
+
+impl From<TxTimeError> for TransferFromError {
+    fn from(e: TxTimeError) -> Self {
+        match e {
+            TxTimeError::TooOld => TransferFromError::TooOld,
+            TxTimeError::CreatedInFuture { ledger_time } => TransferFromError::CreatedInFuture { ledger_time },
+            TxTimeError::Duplicate { duplicate_of } => TransferFromError::Duplicate { duplicate_of },
+        }
+    }
+}
+
+#[cfg(test)]
+pub mod tests {
+    use super::*;
+    use candid::Principal;
+
+    use crate::stable_kong_settings::kong_settings_map;
+    use crate::stable_lp_token::stable_lp_block::LPBlockOp;
+    use crate::stable_lp_token::stable_lp_token::StableLPToken;
+    use crate::stable_memory::{TOKEN_MAP, USER_MAP};
+    use crate::stable_token::stable_token::StableTokenId;
+    use crate::stable_user::principal_id_map;
+    use crate::stable_user::stable_user::{FeeLevelSource, StableUser, StableUserId};
+
+    pub const LP_TOKEN_ID: u32 = 3;
+
+    /// insert the LP token with its ledger
+    pub fn insert_lp_token() -> LPToken {
+        // settings are initialized from the stable maps, so must be before any of them are borrowed
+        kong_settings_map::get();
+        let token = LPToken {
+            token_id: LP_TOKEN_ID,
+            symbol: "A_B".to_string(),
+            address: "1_2".to_string(),
+            decimals: 8,
+            is_removed: false,
+            ledger_id: Some(Principal::from_slice(&[9]).to_text()),
+        };
+        TOKEN_MAP.with(|m| m.borrow_mut().insert(StableTokenId(LP_TOKEN_ID), StableToken::LP(token.clone())));
+        token
+    }
+
+    /// register the user of a principal id with amount of the LP token. returns the account of the user
+    pub fn insert_user(user_id: u32, amount: u64, ts: u64) -> Account {
+        let owner = Principal::from_slice(&[user_id as u8, 1]);
+        insert_account_user(user_id, Account::from(owner), amount, ts)
+    }
+
+    /// register the user holding the LP tokens of the account with amount of the LP token
+    pub fn insert_account_user(user_id: u32, account: Account, amount: u64, ts: u64) -> Account {
+        let user = StableUser {
+            user_id,
+            principal_id: account_principal_id(&account),
+            my_referral_code: format!("USER{}", user_id),
+            referred_by: None,
+            referred_by_expires_at: None,
+            fee_level: 0,
+            fee_level_expires_at: None,
+            fee_level_source: FeeLevelSource::Tier,
+        };
+        principal_id_map::insert_principal_id(&user);
+        USER_MAP.with(|m| m.borrow_mut().insert(StableUserId(user_id), user));
+        if amount > 0 {
+            lp_token_map::insert(&StableLPToken::new(user_id, LP_TOKEN_ID, Nat::from(amount), ts)).unwrap();
+        }
+        account
+    }
+
+    #[test]
+    fn test_check_created_at_time() {
+        let ts = 10 * TX_WINDOW;
+        let block = |created_at_time: u64| StableLPBlock {
+            created_at_time: Some(created_at_time),
+            ..StableLPBlock::new(LP_TOKEN_ID, LPBlockOp::Mint, &Nat::from(1_u32), ts)
+        };
+        assert_eq!(check_created_at_time(&block(ts - TX_WINDOW - PERMITTED_DRIFT)), Ok(()));
+        assert_eq!(
+            check_created_at_time(&block(ts - TX_WINDOW - PERMITTED_DRIFT - 1)),
+            Err(TxTimeError::TooOld)
+        );
+        assert_eq!(check_created_at_time(&block(ts + PERMITTED_DRIFT)), Ok(()));
+        assert_eq!(
+            check_created_at_time(&block(ts + PERMITTED_DRIFT + 1)),
+            Err(TxTimeError::CreatedInFuture { ledger_time: ts })
+        );
+
+        // the same transaction is a duplicate of the logged block
+        let block_idx = lp_block_map::insert(block(ts));
+        assert_eq!(
+            check_created_at_time(&block(ts)),
+            Err(TxTimeError::Duplicate {
+                duplicate_of: Nat::from(block_idx)
+            })
+        );
+        assert_eq!(check_created_at_time(&block(ts - 1)), Ok(()));
+        // transactions without created_at_time are not deduplicated
+        assert_eq!(
+            check_created_at_time(&StableLPBlock {
+                created_at_time: None,
+                ..block(ts)
+            }),
+            Ok(())
+        );
+    }
+}
@@ -0,0 +1,188 @@
+use candid::{CandidType, Nat};
+use ic_cdk::query;
+use icrc_ledger_types::icrc::generic_metadata_value::MetadataValue;
+use icrc_ledger_types::icrc::generic_value::ICRC3Value;
+use icrc_ledger_types::icrc1::account::Account;
+use icrc_ledger_types::icrc2::allowance::Allowance;
+use serde::Deserialize;
+
+use super::lp_icrc1::metadata;
+use super::lp_icrc3::MAX_BLOCKS;
+use super::lp_ledger_helpers::get_lp_token;
+
+use crate::helpers::nat_helpers::nat_is_zero;
+use crate::ic::get_time::get_time;
+use crate::ic::guards::not_in_maintenance_mode;
+use crate::stable_lp_token::lp_allowance_map;
+use crate::stable_lp_token::lp_block_map::{self, user_account};
+use crate::stable_lp_token::lp_token_map;
+use crate::stable_lp_token::stable_lp_block::StableLPBlock;
+use crate::stable_token::lp_token::LPToken;
+
+/// balances and allowances of the LP token after the first log_length blocks
+/// the ledger starts from the snapshot and applies the blocks from log_length to keep its own balances and allowances
+#[derive(CandidType, Debug, Clone, Deserialize)]
+pub struct LPLedgerSnapshot {
+    pub metadata: Vec<(String, MetadataValue)>,
+    pub log_length: u64,
+    pub balances: Vec<(Account, Nat)>,
+    pub allowances: Vec<(Account, Account, Allowance)>, // (owner, spender, allowance)
+}
+
+/// block of the LP token's ledger with the accounts and amount the ledger applies to its balances and allowances
+#[derive(CandidType, Debug, Clone, Deserialize)]
+pub struct LPLedgerBlock {
+    pub id: u64,
+    pub btype: String,
+    pub from: Option<Account>,
+    pub to: Option<Account>,
+    pub spender: Option<Account>,
+    pub amount: Nat,
+    pub expires_at: Option<u64>,
+    pub block: ICRC3Value,
+}
+
+impl From<&StableLPBlock> for LPLedgerBlock {
+    fn from(block: &StableLPBlock) -> Self {
+        Self {
+            id: block.block_idx,
+            btype: block.btype().to_string(),
+            from: block.from,
+            to: block.to,
+            spender: block.spender,
+            amount: block.amount.clone(),
+            expires_at: block.expires_at,
+            block: block.to_icrc3_value(),
+        }
+    }
+}
+
+#[derive(CandidType, Debug, Clone, Deserialize)]
+pub struct LPLedgerBlocks {
+    pub metadata: Vec<(String, MetadataValue)>,
+    pub log_length: u64,
+    pub blocks: Vec<LPLedgerBlock>,
+}
+
+/// snapshot of the LP token's balances and allowances, the start of the ledger's own state
+#[query(hidden = true, guard = "not_in_maintenance_mode")]
+fn lp_ledger_snapshot(lp_token_id: u32) -> Result<LPLedgerSnapshot, String> {
+    let token = get_lp_token(lp_token_id)?;
+    Ok(snapshot(&token, get_time()))
+}
+
+pub fn snapshot(token: &LPToken, ts: u64) -> LPLedgerSnapshot {
+    let balances = lp_token_map::get_all_by_token_id(token.token_id)
+        .into_iter()
+        .filter(|lp_token| !nat_is_zero(&lp_token.amount))
+        .filter_map(|lp_token| Some((user_account(lp_token.user_id)?, lp_token.amount)))
+        .collect();
+    let allowances = lp_allowance_map::get_by_token_id(token.token_id, ts)
+        .into_iter()
+        .filter_map(|(user_id, spender, allowance)| {
+            Some((
+                user_account(user_id)?,
+                spender,
+                Allowance {
+                    allowance: allowance.amount,
+                    expires_at: allowance.expires_at,
+                },
+            ))
+        })
+        .collect();
+    LPLedgerSnapshot {
+        metadata: metadata(token),
+        log_length: lp_block_map::get_log_length(token.token_id),
+        balances,
+        allowances,
+    }
+}
+
+/// blocks of the LP token's ledger from start, at most length blocks, for the ledger to apply to its balances and allowances
+#[query(hidden = true, guard = "not_in_maintenance_mode")]
+fn lp_ledger_blocks(lp_token_id: u32, start: u64, length: u64) -> Result<LPLedgerBlocks, String> {
+    let token = get_lp_token(lp_token_id)?;
+    Ok(LPLedgerBlocks {
+        metadata: metadata(&token),
+        log_length: lp_block_map::get_log_length(token.token_id),
+        blocks: lp_block_map::get_blocks(token.token_id, start, std::cmp::min(length, MAX_BLOCKS))
+            .iter()
+            .map(LPLedgerBlock::from)
+            .collect(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use icrc_ledger_types::icrc1::transfer::TransferArg;
+    use icrc_ledger_types::icrc2::approve::ApproveArgs;
+
+    use crate::lp_ledger::lp_icrc1::transfer;
+    use crate::lp_ledger::lp_icrc2::approve;
+    use crate::lp_ledger::lp_ledger_helpers::tests::{insert_account_user, insert_lp_token, insert_user};
+
+    #[test]
+    fn test_snapshot() {
+        let ts = get_time();
+        let token = insert_lp_token();
+        let owner = insert_user(1, 1_000, ts);
+        let spender = insert_user(2, 0, ts);
+        let to = insert_account_user(
+            3,
+            Account {
+                owner: owner.owner,
+                subaccount: Some([1; 32]),
+            },
+            0,
+            ts,
+        );
+
+        approve(
+            &token,
+            owner.owner,
+            ApproveArgs {
+                from_subaccount: None,
+                spender,
+                amount: Nat::from(300_u32),
+                expected_allowance: None,
+                expires_at: None,
+                fee: None,
+                memo: None,
+                created_at_time: None,
+            },
+            ts,
+        )
+        .unwrap();
+        transfer(
+            &token,
+            owner.owner,
+            TransferArg {
+                from_subaccount: None,
+                to,
+                fee: None,
+                created_at_time: None,
+                memo: None,
+                amount: Nat::from(400_u32),
+            },
+            ts,
+        )
+        .unwrap();
+
+        // the subaccount holds its LP tokens apart from the default subaccount
+        let snapshot = snapshot(&token, ts);
+        assert_eq!(snapshot.log_length, 2);
+        assert_eq!(snapshot.balances, vec![(owner, Nat::from(600_u32)), (to, Nat::from(400_u32))]);
+        assert_eq!(snapshot.allowances.len(), 1);
+        assert_eq!((snapshot.allowances[0].0, snapshot.allowances[0].1), (owner, spender));
+        assert_eq!(snapshot.allowances[0].2.allowance, Nat::from(300_u32));
+
+        // blocks have the accounts the ledger applies them to
+        let blocks = lp_block_map::get_blocks(token.token_id, 0, u64::MAX);
+        let blocks: Vec<LPLedgerBlock> = blocks.iter().map(LPLedgerBlock::from).collect();
+        assert_eq!(blocks[0].btype, "2approve");
+        assert_eq!(blocks[0].spender, Some(spender));
+        assert_eq!(blocks[1].btype, "1xfer");
+        assert_eq!((blocks[1].from, blocks[1].to), (Some(owner), Some(to)));
+    }
+}
@@ -0,0 +1,6 @@
+pub mod lp_icrc1;
+pub mod lp_icrc2;
+pub mod lp_icrc3;
+pub mod lp_ledger_canister;
+pub mod lp_ledger_helpers;
+pub mod lp_ledger_sync;
@@ -18,6 +18,30 @@ pub struct PoolReply {
     pub lp_fee_1: Nat,
     pub price: f64,
     pub lp_fee_bps: u8,
+    pub fee_tier: u8, // fee tier in basis points. pools of the same pair are distinguished by fee tier
     pub lp_token_symbol: String,
     pub is_removed: bool,
+    pub pool_type: PoolTypeReply,
+}
+
+#[derive(CandidType, Debug, Clone, Serialize, Deserialize)]
+pub enum PoolTypeReply {
+    ConstantProduct,
+    Concentrated(ConcentratedPoolReply),
+    StableSwap(StableSwapPoolReply),
+}
+
+#[derive(CandidType, Debug, Clone, Serialize, Deserialize)]
+pub struct ConcentratedPoolReply {
+    pub tick_spacing: u16,
+    pub tick: i32,
+    pub liquidity: Nat,
+    pub num_positions: u32,
+}
+
+#[derive(CandidType, Debug, Clone, Serialize, Deserialize)]
+pub struct StableSwapPoolReply {
+    pub amp: u64, // current amp
+    pub future_amp: u64,
+    pub future_amp_ts: u64, // time amp reaches future_amp
 }
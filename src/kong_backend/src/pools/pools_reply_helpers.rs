@@ -1,5 +1,7 @@
-use super::pools_reply::PoolReply;
+use super::pools_reply::{ConcentratedPoolReply, PoolReply, PoolTypeReply, StableSwapPoolReply};
 
+use crate::ic::get_time::get_time;
+use crate::stable_pool::pool_type::PoolType;
 use crate::stable_pool::stable_pool::StablePool;
 use crate::stable_token::token::Token;
 use crate::stable_token::token_map;
@@ -44,7 +46,26 @@ pub fn to_pool_reply(pool: &StablePool) -> PoolReply {
         lp_fee_1: pool.lp_fee_1.clone(),
         price: pool.get_price_as_f64().unwrap_or(0_f64),
         lp_fee_bps: pool.lp_fee_bps,
+        fee_tier: pool.fee_tier_bps(),
         lp_token_symbol,
         is_removed: pool.is_removed,
+        pool_type: to_pool_type_reply(&pool.pool_type),
+    }
+}
+
+fn to_pool_type_reply(pool_type: &PoolType) -> PoolTypeReply {
+    match pool_type {
+        PoolType::ConstantProduct => PoolTypeReply::ConstantProduct,
+        PoolType::Concentrated(concentrated_pool) => PoolTypeReply::Concentrated(ConcentratedPoolReply {
+            tick_spacing: concentrated_pool.tick_spacing,
+            tick: concentrated_pool.tick,
+            liquidity: concentrated_pool.liquidity.clone(),
+            num_positions: concentrated_pool.num_positions as u32,
+        }),
+        PoolType::StableSwap(stable_swap) => PoolTypeReply::StableSwap(StableSwapPoolReply {
+            amp: stable_swap.amp(get_time()),
+            future_amp: stable_swap.future_amp,
+            future_amp_ts: stable_swap.future_amp_ts,
+        }),
     }
 }
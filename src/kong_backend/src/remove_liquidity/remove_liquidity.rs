@@ -1,24 +1,32 @@
 use candid::Nat;
 use ic_cdk::update;
-use icrc_ledger_types::icrc1::account::Account;
 
 use super::remove_liquidity_args::RemoveLiquidityArgs;
 use super::remove_liquidity_reply::RemoveLiquidityReply;
 use super::remove_liquidity_reply_helpers::{to_remove_liquidity_reply, to_remove_liquidity_reply_failed};
 
+use crate::deposits::deposits::credit_deposits;
 use crate::helpers::nat_helpers::{nat_add, nat_divide, nat_is_zero, nat_multiply, nat_subtract, nat_zero};
-use crate::ic::{address::Address, get_time::get_time, guards::not_in_maintenance_mode, id::caller_id, transfer::icrc1_transfer};
+use crate::ic::{
+    address::Address,
+    address_helpers::get_address,
+    deadline::check_deadline,
+    get_time::get_time,
+    guards::not_in_maintenance_mode,
+    id::caller_id,
+    transfer::{icp_transfer, icrc1_transfer},
+};
 use crate::stable_claim::{claim_map, stable_claim::StableClaim};
 use crate::stable_kong_settings::kong_settings_map;
-use crate::stable_lp_token::{lp_token_map, stable_lp_token::StableLPToken};
-use crate::stable_pool::{pool_map, stable_pool::StablePool};
+use crate::stable_lp_token::{lp_block_map, lp_token_lock, lp_token_map, stable_lp_token::StableLPToken};
+use crate::stable_pool::{pool_map, pool_type::PoolType, stable_pool::StablePool};
 use crate::stable_request::{reply::Reply, request::Request, request_map, stable_request::StableRequest, status::StatusCode};
 use crate::stable_token::{stable_token::StableToken, token::Token};
 use crate::stable_transfer::{stable_transfer::StableTransfer, transfer_map, tx_id::TxId};
 use crate::stable_tx::{remove_liquidity_tx::RemoveLiquidityTx, stable_tx::StableTx, tx_map};
 use crate::stable_user::user_map;
 
-enum TokenIndex {
+pub enum TokenIndex {
     Token0,
     Token1,
 }
@@ -28,6 +36,7 @@ enum TokenIndex {
 ///   allow the backend canister to icrc2_transfer_from. Note, the approve transaction will incur
 ///   gas fees - which is 1 for LP tokens. However, the icrc2_transfer_from to the backend canister is considered
 ///   a burn and does not incur gas fees.
+/// - with use_deposit, token_0 and token_1 are credited to the user's deposit instead of being sent
 ///
 /// Notes regarding gas:
 ///   - payout_amount_0, payout_lp_fee_0, payout_amount_1, payout_lp_fee_1 does not include gas fees
@@ -35,15 +44,17 @@ enum TokenIndex {
 pub async fn remove_liquidity(args: RemoveLiquidityArgs) -> Result<RemoveLiquidityReply, String> {
     let (user_id, pool, remove_lp_token_amount, payout_amount_0, payout_lp_fee_0, payout_amount_1, payout_lp_fee_1) =
         check_arguments(&args).await?;
+    let to_address = get_to_address(&args, &pool)?;
     let ts = get_time();
-    let request_id = request_map::insert(&StableRequest::new(user_id, &Request::RemoveLiquidity(args), ts));
-    let caller_id = caller_id();
+    let request_id = request_map::insert(&StableRequest::new(user_id, &Request::RemoveLiquidity(args.clone()), ts));
 
     let result = match process_remove_liquidity(
         request_id,
         user_id,
-        &caller_id,
+        &to_address,
+        args.use_deposit == Some(true),
         &pool,
+        args.position_id,
         &remove_lp_token_amount,
         &payout_amount_0,
         &payout_lp_fee_0,
@@ -67,23 +78,26 @@ pub async fn remove_liquidity(args: RemoveLiquidityArgs) -> Result<RemoveLiquidi
     result
 }
 
-/// used by remove_lp_positions() to remove_liquidity for user_id and tokens returned to to_principal_id
+/// used by remove_lp_positions() to remove_liquidity for user_id and tokens returned to to_address
 pub async fn remove_liquidity_from_pool(
     args: RemoveLiquidityArgs,
     user_id: u32,
-    to_principal_id: &Account,
+    to_address: &Address,
 ) -> Result<RemoveLiquidityReply, String> {
+    // kingkong removing all LP positions of a pool ignores locks
     let (pool, remove_lp_token_amount, payout_amount_0, payout_lp_fee_0, payout_amount_1, payout_lp_fee_1) =
-        check_arguments_with_user(&args, user_id).await?;
+        check_arguments_with_user(&args, user_id, false)?;
     let ts = get_time();
-    let request_id = request_map::insert(&StableRequest::new(user_id, &Request::RemoveLiquidity(args), ts));
+    let request_id = request_map::insert(&StableRequest::new(user_id, &Request::RemoveLiquidity(args.clone()), ts));
     request_map::update_status(request_id, StatusCode::RemoveLiquidityFromPool, None);
 
     let result = match process_remove_liquidity(
         request_id,
         user_id,
-        to_principal_id,
+        to_address,
+        false,
         &pool,
+        args.position_id,
         &remove_lp_token_amount,
         &payout_amount_0,
         &payout_lp_fee_0,
@@ -111,16 +125,18 @@ pub async fn remove_liquidity_from_pool(
 pub async fn remove_liquidity_async(args: RemoveLiquidityArgs) -> Result<u64, String> {
     let (user_id, pool, remove_lp_token_amount, payout_amount_0, payout_lp_fee_0, payout_amount_1, payout_lp_fee_1) =
         check_arguments(&args).await?;
+    let to_address = get_to_address(&args, &pool)?;
     let ts = get_time();
-    let request_id = request_map::insert(&StableRequest::new(user_id, &Request::RemoveLiquidity(args), ts));
-    let caller_id = caller_id();
+    let request_id = request_map::insert(&StableRequest::new(user_id, &Request::RemoveLiquidity(args.clone()), ts));
 
     ic_cdk::spawn(async move {
         match process_remove_liquidity(
             request_id,
             user_id,
-            &caller_id,
+            &to_address,
+            args.use_deposit == Some(true),
             &pool,
+            args.position_id,
             &remove_lp_token_amount,
             &payout_amount_0,
             &payout_lp_fee_0,
@@ -145,7 +161,7 @@ async fn check_arguments(args: &RemoveLiquidityArgs) -> Result<(u32, StablePool,
     // make sure user is not anonymous and exists
     let user_id = user_map::get_by_caller()?.ok_or("Insufficient LP balance")?.user_id;
     let (pool, remove_lp_token_amount, payout_amount_0, payout_lp_fee_0, payout_amount_1, payout_lp_fee_1) =
-        check_arguments_with_user(args, user_id).await?;
+        check_arguments_with_user(args, user_id, true)?;
 
     Ok((
         user_id,
@@ -158,10 +174,29 @@ async fn check_arguments(args: &RemoveLiquidityArgs) -> Result<(u32, StablePool,
     ))
 }
 
+/// use specified address or default to caller's principal id. the address must be supported by both tokens of the pool
+fn get_to_address(args: &RemoveLiquidityArgs, pool: &StablePool) -> Result<Address, String> {
+    match args.receive_address {
+        Some(ref address) => {
+            if args.use_deposit == Some(true) {
+                Err("Receive address not supported with use_deposit".to_string())?
+            }
+            get_address(&pool.token_0(), address)?;
+            get_address(&pool.token_1(), address)
+        }
+        None => Ok(Address::PrincipalId(caller_id())),
+    }
+}
+
 #[allow(clippy::type_complexity)]
-async fn check_arguments_with_user(args: &RemoveLiquidityArgs, user_id: u32) -> Result<(StablePool, Nat, Nat, Nat, Nat, Nat), String> {
-    // Pool
-    let pool = pool_map::get_by_tokens(&args.token_0, &args.token_1)?;
+pub fn check_arguments_with_user(
+    args: &RemoveLiquidityArgs,
+    user_id: u32,
+    check_lock: bool,
+) -> Result<(StablePool, Nat, Nat, Nat, Nat, Nat), String> {
+    // Pool, with the LP token locks that expired settled
+    let pool = pool_map::get_by_tokens_and_fee_tier(&args.token_0, &args.token_1, args.fee_tier)?;
+    let pool = lp_token_map::save_expired_locks(pool, get_time());
     // Token0
     let balance_0 = &pool.balance_0;
     // Token1
@@ -174,17 +209,51 @@ async fn check_arguments_with_user(args: &RemoveLiquidityArgs, user_id: u32) ->
         Err("Zero balances in pool".to_string())?
     }
 
-    // Check the user has enough LP tokens
-    let user_lp_token_amount =
-        lp_token_map::get_by_token_id_by_user_id(lp_token_id, user_id).map_or_else(nat_zero, |lp_token| lp_token.amount);
-    let remove_lp_token_amount = if user_lp_token_amount == nat_zero() || args.remove_lp_token_amount > user_lp_token_amount {
-        Err("User has insufficient LP balance".to_string())?
-    } else {
-        args.remove_lp_token_amount.clone()
+    let (remove_lp_token_amount, lock) = match &pool.pool_type {
+        PoolType::ConstantProduct | PoolType::StableSwap(_) => {
+            // Check the user has enough LP tokens
+            let user_lp_token = lp_token_map::get_by_token_id_by_user_id(lp_token_id, user_id);
+            let user_lp_token_amount = user_lp_token.as_ref().map_or_else(nat_zero, |lp_token| lp_token.amount.clone());
+            if user_lp_token_amount == nat_zero() || args.remove_lp_token_amount > user_lp_token_amount {
+                Err("User has insufficient LP balance".to_string())?
+            }
+            // locked LP tokens can not be removed until they unlock
+            if let Some(lp_token) = user_lp_token.as_ref().filter(|_| check_lock) {
+                if args.remove_lp_token_amount > lp_token.unlocked_amount(get_time()) {
+                    let unlock_ts = lp_token.lock.as_ref().map_or(0, |lock| lock.unlock_ts);
+                    Err(format!("LP tokens are locked until {}", unlock_ts))?
+                }
+            }
+            (
+                args.remove_lp_token_amount.clone(),
+                user_lp_token.and_then(|lp_token| lp_token.lock),
+            )
+        }
+        PoolType::Concentrated(concentrated_pool) => {
+            // Check the user owns the position and it has enough liquidity
+            let position = args
+                .position_id
+                .and_then(|position_id| concentrated_pool.get_position(position_id))
+                .filter(|position| position.user_id == user_id)
+                .ok_or("Position not found".to_string())?;
+            if nat_is_zero(&args.remove_lp_token_amount) || args.remove_lp_token_amount > position.liquidity {
+                Err("User has insufficient position liquidity".to_string())?
+            }
+            (args.remove_lp_token_amount.clone(), None)
+        }
     };
 
-    // calculate the payout amounts.
-    let (payout_amount_0, payout_lp_fee_0, payout_amount_1, payout_lp_fee_1) = calculate_amounts(&pool, &args.remove_lp_token_amount)?;
+    // calculate the payout amounts. the boost fees earned by the user's lock are paid out with the LP fees
+    let (payout_amount_0, payout_lp_fee_0, payout_amount_1, payout_lp_fee_1) =
+        calculate_amounts(&pool, args.position_id, &args.remove_lp_token_amount)?;
+    let (boost_fee_0, boost_fee_1) = lp_token_lock::boost_fees(&pool, lock.as_ref());
+    let payout_lp_fee_0 = nat_add(&payout_lp_fee_0, &boost_fee_0);
+    let payout_lp_fee_1 = nat_add(&payout_lp_fee_1, &boost_fee_1);
+
+    // the LP tokens are only removed after these checks, so there is nothing to return
+    check_deadline(args.deadline)?;
+    check_min_amount(&payout_amount_0, &payout_lp_fee_0, args.min_amount_0.as_ref(), "token_0")?;
+    check_min_amount(&payout_amount_1, &payout_lp_fee_1, args.min_amount_1.as_ref(), "token_1")?;
 
     Ok((
         pool,
@@ -196,7 +265,33 @@ async fn check_arguments_with_user(args: &RemoveLiquidityArgs, user_id: u32) ->
     ))
 }
 
-pub fn calculate_amounts(pool: &StablePool, remove_lp_token_amount: &Nat) -> Result<(Nat, Nat, Nat, Nat), String> {
+fn check_min_amount(payout_amount: &Nat, payout_lp_fee: &Nat, min_amount: Option<&Nat>, token: &str) -> Result<(), String> {
+    let amount = nat_add(payout_amount, payout_lp_fee);
+    match min_amount {
+        Some(min_amount) if amount < *min_amount => Err(format!("{} amount {} is less than minimum {}", token, amount, min_amount)),
+        _ => Ok(()),
+    }
+}
+
+/// for concentrated pools, remove_lp_token_amount is the liquidity removed from position_id and all fees of the position are paid out
+pub fn calculate_amounts(
+    pool: &StablePool,
+    position_id: Option<u64>,
+    remove_lp_token_amount: &Nat,
+) -> Result<(Nat, Nat, Nat, Nat), String> {
+    if let PoolType::Concentrated(concentrated_pool) = &pool.pool_type {
+        let position_id = position_id.ok_or("Position id required for concentrated pool")?;
+        let (payout_amount_0, payout_lp_fee_0, payout_amount_1, payout_lp_fee_1) =
+            concentrated_pool.clone().remove_position(position_id, remove_lp_token_amount)?;
+        // make sure rounding never pays out more than the pool has
+        return Ok((
+            std::cmp::min(payout_amount_0, pool.balance_0.clone()),
+            std::cmp::min(payout_lp_fee_0, pool.lp_fee_0.clone()),
+            std::cmp::min(payout_amount_1, pool.balance_1.clone()),
+            std::cmp::min(payout_lp_fee_1, pool.lp_fee_1.clone()),
+        ));
+    }
+
     // Token0
     let balance_0 = &pool.balance_0;
     let lp_fee_0 = &pool.lp_fee_0;
@@ -206,6 +301,7 @@ pub fn calculate_amounts(pool: &StablePool, remove_lp_token_amount: &Nat) -> Res
     // LP token
     let lp_token = pool.lp_token();
     let lp_token_id = lp_token.token_id();
+    // the boosted share of locked LP tokens is credited to their locks as it accrues, so is not in lp_fee_0 and lp_fee_1
     let lp_total_supply = lp_token_map::get_total_supply(lp_token_id);
 
     // calculate user's payout in token_0
@@ -232,8 +328,10 @@ pub fn calculate_amounts(pool: &StablePool, remove_lp_token_amount: &Nat) -> Res
 async fn process_remove_liquidity(
     request_id: u64,
     user_id: u32,
-    to_principal_id: &Account,
+    to_address: &Address,
+    to_deposit: bool,
     pool: &StablePool,
+    position_id: Option<u64>,
     remove_lp_token_amount: &Nat,
     payout_amount_0: &Nat,
     payout_lp_fee_0: &Nat,
@@ -241,26 +339,40 @@ async fn process_remove_liquidity(
     payout_lp_fee_1: &Nat,
     ts: u64,
 ) -> Result<RemoveLiquidityReply, String> {
-    // LP token
-    let lp_token = pool.lp_token();
-
     request_map::update_status(request_id, StatusCode::Start, None);
 
-    // remove LP tokens from user's ledger
-    let transfer_lp_token = remove_lp_token(request_id, user_id, &lp_token, remove_lp_token_amount, ts);
-    if transfer_lp_token.is_err() {
-        return_tokens(request_id, user_id, pool, &transfer_lp_token, remove_lp_token_amount, ts);
-        Err(format!("Req #{} failed. {}", request_id, transfer_lp_token.unwrap_err()))?
-    }
+    remove_from_pool(
+        request_id,
+        user_id,
+        pool,
+        position_id,
+        remove_lp_token_amount,
+        payout_amount_0,
+        payout_lp_fee_0,
+        payout_amount_1,
+        payout_lp_fee_1,
+        ts,
+    )?;
 
-    // update liquidity pool with new removed amounts
-    update_liquidity_pool(request_id, pool, payout_amount_0, payout_lp_fee_0, payout_amount_1, payout_lp_fee_1);
+    if to_deposit {
+        return Ok(credit_payout_tokens(
+            request_id,
+            user_id,
+            pool,
+            payout_amount_0,
+            payout_lp_fee_0,
+            payout_amount_1,
+            payout_lp_fee_1,
+            remove_lp_token_amount,
+            ts,
+        ));
+    }
 
     // successful, add tx and update request with reply
     send_payout_tokens(
         request_id,
         user_id,
-        to_principal_id,
+        to_address,
         pool,
         payout_amount_0,
         payout_lp_fee_0,
@@ -272,7 +384,62 @@ async fn process_remove_liquidity(
     .await
 }
 
-fn remove_lp_token(request_id: u64, user_id: u32, lp_token: &StableToken, remove_lp_token_amount: &Nat, ts: u64) -> Result<(), String> {
+/// remove the user's LP tokens and the payout amounts from the pool
+/// if anything fails, the LP tokens are returned to the user
+#[allow(clippy::too_many_arguments)]
+pub fn remove_from_pool(
+    request_id: u64,
+    user_id: u32,
+    pool: &StablePool,
+    position_id: Option<u64>,
+    remove_lp_token_amount: &Nat,
+    payout_amount_0: &Nat,
+    payout_lp_fee_0: &Nat,
+    payout_amount_1: &Nat,
+    payout_lp_fee_1: &Nat,
+    ts: u64,
+) -> Result<(), String> {
+    // LP token
+    let lp_token = pool.lp_token();
+    // the boost fees of the user's lock are moved to the LP fees of the pool when the LP tokens are removed
+    let mut pool = pool.clone();
+
+    // remove LP tokens from user's ledger. concentrated pools remove the liquidity from the position instead
+    if pool.pool_type.has_lp_token() {
+        let transfer_lp_token = remove_lp_token(request_id, user_id, &mut pool, &lp_token, remove_lp_token_amount, ts);
+        if transfer_lp_token.is_err() {
+            return_tokens(request_id, user_id, &pool, &transfer_lp_token, remove_lp_token_amount, ts);
+            Err(format!("Req #{} failed. {}", request_id, transfer_lp_token.unwrap_err()))?
+        }
+    }
+
+    // update liquidity pool with new removed amounts
+    if let Err(e) = update_liquidity_pool(
+        request_id,
+        &pool,
+        position_id,
+        remove_lp_token_amount,
+        payout_amount_0,
+        payout_lp_fee_0,
+        payout_amount_1,
+        payout_lp_fee_1,
+    ) {
+        // only concentrated pools can fail, no LP tokens to return
+        return_tokens(request_id, user_id, &pool, &Err(e.clone()), remove_lp_token_amount, ts);
+        Err(format!("Req #{} failed. {}", request_id, e))?
+    }
+
+    Ok(())
+}
+
+fn remove_lp_token(
+    request_id: u64,
+    user_id: u32,
+    pool: &mut StablePool,
+    lp_token: &StableToken,
+    remove_lp_token_amount: &Nat,
+    ts: u64,
+) -> Result<(), String> {
     // LP token
     let lp_token_id = lp_token.token_id();
 
@@ -292,12 +459,19 @@ fn remove_lp_token(request_id: u64, user_id: u32, lp_token: &StableToken, remove
                     Err(message)?
                 }
             };
+            // locked LP tokens are only removed by kingkong, the lock can not exceed the remaining amount
+            let lock = lp_token
+                .lock
+                .as_ref()
+                .map(|lock| lp_token_lock::remove_from_lock(pool, lock, &amount));
             let new_user_lp_token = StableLPToken {
                 amount,
                 ts,
+                lock,
                 ..lp_token.clone()
             };
             lp_token_map::update(&new_user_lp_token);
+            lp_block_map::log_burn(lp_token_id, user_id, remove_lp_token_amount, ts);
             request_map::update_status(request_id, StatusCode::UpdateUserLPTokenAmountSuccess, None);
             Ok(())
         }
@@ -321,24 +495,47 @@ fn return_lp_token(user_id: u32, lp_token: &StableToken, remove_lp_token_amount:
                 ..lp_token.clone()
             };
             lp_token_map::update(&new_user_lp_token);
+            // burned LP tokens are minted back
+            lp_block_map::log_mint(lp_token_id, user_id, remove_lp_token_amount, ts);
             Ok(())
         }
         None => Err("Unable to find LP tokens balance".to_string())?,
     }
 }
 
-fn update_liquidity_pool(request_id: u64, pool: &StablePool, amount_0: &Nat, lp_fee_0: &Nat, amount_1: &Nat, lp_fee_1: &Nat) {
+#[allow(clippy::too_many_arguments)]
+fn update_liquidity_pool(
+    request_id: u64,
+    pool: &StablePool,
+    position_id: Option<u64>,
+    remove_lp_token_amount: &Nat,
+    amount_0: &Nat,
+    lp_fee_0: &Nat,
+    amount_1: &Nat,
+    lp_fee_1: &Nat,
+) -> Result<(), String> {
     request_map::update_status(request_id, StatusCode::UpdatePoolAmounts, None);
 
-    let update_pool = StablePool {
+    let mut pool = pool.clone();
+    pool.update_price_cumulative(get_time());
+    let mut update_pool = StablePool {
         balance_0: nat_subtract(&pool.balance_0, amount_0).unwrap_or(nat_zero()),
         lp_fee_0: nat_subtract(&pool.lp_fee_0, lp_fee_0).unwrap_or(nat_zero()),
         balance_1: nat_subtract(&pool.balance_1, amount_1).unwrap_or(nat_zero()),
         lp_fee_1: nat_subtract(&pool.lp_fee_1, lp_fee_1).unwrap_or(nat_zero()),
         ..pool.clone()
     };
+    if let PoolType::Concentrated(ref mut concentrated_pool) = update_pool.pool_type {
+        let position_id = position_id.ok_or("Position id required for concentrated pool")?;
+        if let Err(e) = concentrated_pool.remove_position(position_id, remove_lp_token_amount) {
+            request_map::update_status(request_id, StatusCode::UpdatePoolAmountsFailed, Some(&e));
+            Err(e)?
+        }
+    }
     pool_map::update(&update_pool);
     request_map::update_status(request_id, StatusCode::UpdatePoolAmountsSuccess, None);
+
+    Ok(())
 }
 
 // send payout tokens to user and final balance integrity checks
@@ -347,10 +544,10 @@ fn update_liquidity_pool(request_id: u64, pool: &StablePool, amount_0: &Nat, lp_
 // - check the actual balances of the canister vs. expected balances in stable memory
 // - update successsful request reply
 #[allow(clippy::too_many_arguments)]
-async fn send_payout_tokens(
+pub async fn send_payout_tokens(
     request_id: u64,
     user_id: u32,
-    to_principal_id: &Account,
+    to_address: &Address,
     pool: &StablePool,
     payout_amount_0: &Nat,
     payout_lp_fee_0: &Nat,
@@ -371,7 +568,7 @@ async fn send_payout_tokens(
     transfer_token(
         request_id,
         user_id,
-        to_principal_id,
+        to_address,
         TokenIndex::Token0,
         &token_0,
         payout_amount_0,
@@ -386,7 +583,7 @@ async fn send_payout_tokens(
     transfer_token(
         request_id,
         user_id,
-        to_principal_id,
+        to_address,
         TokenIndex::Token1,
         &token_1,
         payout_amount_1,
@@ -397,6 +594,54 @@ async fn send_payout_tokens(
     )
     .await;
 
+    let remove_liquidity_tx = RemoveLiquidityTx {
+        to_address: Some(to_address.clone()),
+        ..RemoveLiquidityTx::new_success(
+            pool.pool_id,
+            user_id,
+            request_id,
+            payout_amount_0,
+            payout_lp_fee_0,
+            payout_amount_1,
+            payout_lp_fee_1,
+            remove_lp_token_amount,
+            &transfer_ids,
+            &claim_ids,
+            ts,
+        )
+    };
+    let tx_id = tx_map::insert(&StableTx::RemoveLiquidity(remove_liquidity_tx.clone()));
+    let reply = match tx_map::get_by_user_and_token_id(Some(tx_id), None, None, None).first() {
+        Some(StableTx::RemoveLiquidity(remove_liquidity_tx)) => to_remove_liquidity_reply(remove_liquidity_tx),
+        _ => to_remove_liquidity_reply_failed(pool.pool_id, request_id, ts),
+    };
+    request_map::update_reply(request_id, Reply::RemoveLiquidity(reply.clone()));
+
+    Ok(reply)
+}
+
+/// credit the payout tokens to the user's deposit instead of sending them
+#[allow(clippy::too_many_arguments)]
+fn credit_payout_tokens(
+    request_id: u64,
+    user_id: u32,
+    pool: &StablePool,
+    payout_amount_0: &Nat,
+    payout_lp_fee_0: &Nat,
+    payout_amount_1: &Nat,
+    payout_lp_fee_1: &Nat,
+    remove_lp_token_amount: &Nat,
+    ts: u64,
+) -> RemoveLiquidityReply {
+    let payout_0 = nat_add(payout_amount_0, payout_lp_fee_0);
+    let payout_1 = nat_add(payout_amount_1, payout_lp_fee_1);
+    credit_deposits(
+        request_id,
+        user_id,
+        &[(pool.token_id_0, &payout_0), (pool.token_id_1, &payout_1)],
+        ts,
+    );
+
     let remove_liquidity_tx = RemoveLiquidityTx::new_success(
         pool.pool_id,
         user_id,
@@ -406,25 +651,25 @@ async fn send_payout_tokens(
         payout_amount_1,
         payout_lp_fee_1,
         remove_lp_token_amount,
-        &transfer_ids,
-        &claim_ids,
+        &[],
+        &[],
         ts,
     );
     let tx_id = tx_map::insert(&StableTx::RemoveLiquidity(remove_liquidity_tx.clone()));
-    let reply = match tx_map::get_by_user_and_token_id(Some(tx_id), None, None, None).first() {
-        Some(StableTx::RemoveLiquidity(remove_liquidity_tx)) => to_remove_liquidity_reply(remove_liquidity_tx),
-        _ => to_remove_liquidity_reply_failed(pool.pool_id, request_id, ts),
-    };
+    let reply = to_remove_liquidity_reply(&RemoveLiquidityTx {
+        tx_id,
+        ..remove_liquidity_tx
+    });
     request_map::update_reply(request_id, Reply::RemoveLiquidity(reply.clone()));
 
-    Ok(reply)
+    reply
 }
 
 #[allow(clippy::too_many_arguments)]
-async fn transfer_token(
+pub async fn transfer_token(
     request_id: u64,
     user_id: u32,
-    to_principal_id: &Account,
+    to_address: &Address,
     token_index: TokenIndex,
     token: &StableToken,
     payout_amount: &Nat,
@@ -444,7 +689,10 @@ async fn transfer_token(
         TokenIndex::Token1 => request_map::update_status(request_id, StatusCode::ReceiveToken1, None),
     };
 
-    match icrc1_transfer(&amount_with_gas, to_principal_id, token, None).await {
+    match match to_address {
+        Address::AccountId(to_account_id) => icp_transfer(&amount_with_gas, to_account_id, token, None).await,
+        Address::PrincipalId(to_principal_id) => icrc1_transfer(&amount_with_gas, to_principal_id, token, None).await,
+    } {
         Ok(block_id) => {
             let transfer_id = transfer_map::insert(&StableTransfer {
                 transfer_id: 0,
@@ -462,14 +710,7 @@ async fn transfer_token(
             };
         }
         Err(e) => {
-            let claim = StableClaim::new(
-                user_id,
-                token_id,
-                &amount,
-                Some(request_id),
-                Some(Address::PrincipalId(*to_principal_id)),
-                ts,
-            );
+            let claim = StableClaim::new(user_id, token_id, &amount, Some(request_id), Some(to_address.clone()), ts);
             let claim_id = claim_map::insert(&claim);
             claim_ids.push(claim_id);
             let message = format!("Saved as claim #{}. {}", claim_id, e);
@@ -509,7 +750,7 @@ fn return_tokens(
     request_map::update_reply(request_id, Reply::RemoveLiquidity(reply));
 }
 
-fn archive_to_kong_data(request_id: u64) -> Result<(), String> {
+pub fn archive_to_kong_data(request_id: u64) -> Result<(), String> {
     if !kong_settings_map::get().archive_to_kong_data {
         return Ok(());
     }
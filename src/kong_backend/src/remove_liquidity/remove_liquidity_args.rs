@@ -7,5 +7,12 @@ use serde::{Deserialize, Serialize};
 pub struct RemoveLiquidityArgs {
     pub token_0: String,
     pub token_1: String,
-    pub remove_lp_token_amount: Nat,
+    pub remove_lp_token_amount: Nat,     // liquidity to remove from the position for concentrated pools
+    pub position_id: Option<u64>,        // position of a concentrated pool
+    pub use_deposit: Option<bool>,       // receive token_0 and token_1 to the user's deposit instead of ledger transfers
+    pub receive_address: Option<String>, // send token_0 and token_1 to this principal id, ICRC-1 account or account id instead of the caller
+    pub fee_tier: Option<u8>,            // fee tier of the pool. None for the first pool of the pair
+    pub min_amount_0: Option<Nat>,       // minimum token_0 to receive, including LP fees
+    pub min_amount_1: Option<Nat>,       // minimum token_1 to receive, including LP fees
+    pub deadline: Option<u64>,           // nanoseconds since the Unix epoch. rejected after the deadline
 }
@@ -1,6 +1,7 @@
 use candid::{CandidType, Nat};
 use serde::{Deserialize, Serialize};
 
+use crate::swap::swap_reply::SwapTxReply;
 use crate::transfers::transfer_reply::TransferIdReply;
 
 #[derive(CandidType, Debug, Clone, Serialize, Deserialize)]
@@ -22,8 +23,12 @@ pub struct RemoveLiquidityReply {
     pub amount_1: Nat,
     pub lp_fee_1: Nat,
     pub remove_lp_token_amount: Nat,
+    #[serde(default)]
+    pub swap_txs: Vec<SwapTxReply>,
     pub transfer_ids: Vec<TransferIdReply>,
     pub claim_ids: Vec<u64>,
+    #[serde(default)]
+    pub to_address: Option<String>, // resolved destination of token_0 and token_1
     pub ts: u64,
 }
 
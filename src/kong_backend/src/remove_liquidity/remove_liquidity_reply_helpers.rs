@@ -4,6 +4,7 @@ use crate::helpers::nat_helpers::nat_zero;
 use crate::stable_pool::pool_map;
 use crate::stable_tx::remove_liquidity_tx::RemoveLiquidityTx;
 use crate::stable_tx::status_tx::StatusTx;
+use crate::swap::swap_reply_helpers::to_txs;
 use crate::transfers::transfer_reply_helpers::to_transfer_ids;
 
 fn get_pool_info(pool_id: u32) -> (String, String, String, String, String, String, String) {
@@ -51,8 +52,10 @@ pub fn to_remove_liquidity_reply(remove_liquidity_tx: &RemoveLiquidityTx) -> Rem
         amount_1: remove_liquidity_tx.amount_1.clone(),
         lp_fee_1: remove_liquidity_tx.lp_fee_1.clone(),
         remove_lp_token_amount: remove_liquidity_tx.remove_lp_token_amount.clone(),
+        swap_txs: to_txs(&remove_liquidity_tx.swap_txs, remove_liquidity_tx.ts),
         transfer_ids: to_transfer_ids(&remove_liquidity_tx.transfer_ids),
         claim_ids: remove_liquidity_tx.claim_ids.clone(),
+        to_address: remove_liquidity_tx.to_address.as_ref().map(|address| address.to_string()),
         ts: remove_liquidity_tx.ts,
     }
 }
@@ -75,8 +78,10 @@ pub fn to_remove_liquidity_reply_failed(pool_id: u32, request_id: u64, ts: u64)
         amount_1: nat_zero(),
         lp_fee_1: nat_zero(),
         remove_lp_token_amount: nat_zero(),
+        swap_txs: Vec::new(),
         transfer_ids: Vec::new(), // if failed, transfer_ids is empty as no tokens are returned
         claim_ids: Vec::new(),    // if failed, claims_ids is empty as no LP tokens are returned
+        to_address: None,
         ts,
     }
 }
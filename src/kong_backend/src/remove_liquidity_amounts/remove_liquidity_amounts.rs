@@ -9,7 +9,11 @@ use crate::stable_pool::pool_map;
 use crate::stable_token::token::Token;
 
 #[query(guard = "not_in_maintenance_mode")]
-fn remove_liquidity_amounts(token_0: String, token_1: String, remove_lp_token_amount: Nat) -> Result<RemoveLiquidityAmountsReply, String> {
+pub fn remove_liquidity_amounts(
+    token_0: String,
+    token_1: String,
+    remove_lp_token_amount: Nat,
+) -> Result<RemoveLiquidityAmountsReply, String> {
     // Pool
     let pool = pool_map::get_by_tokens(&token_0, &token_1)?;
     let symbol = pool.symbol();
@@ -24,7 +28,7 @@ fn remove_liquidity_amounts(token_0: String, token_1: String, remove_lp_token_am
     let address_1 = token_1.address();
     let symbol_1 = token_1.symbol();
 
-    let (amount_0, lp_fee_0, amount_1, lp_fee_1) = calculate_amounts(&pool, &remove_lp_token_amount)?;
+    let (amount_0, lp_fee_0, amount_1, lp_fee_1) = calculate_amounts(&pool, None, &remove_lp_token_amount)?;
 
     Ok(RemoveLiquidityAmountsReply {
         symbol,
@@ -0,0 +1,58 @@
+use candid::Nat;
+use ic_cdk::query;
+
+use super::remove_liquidity_amounts::remove_liquidity_amounts;
+use super::remove_liquidity_single_amounts_reply::RemoveLiquiditySingleAmountsReply;
+
+use crate::add_liquidity_single::add_liquidity_single::is_token_0;
+use crate::helpers::nat_helpers::nat_add;
+use crate::ic::guards::not_in_maintenance_mode;
+use crate::remove_liquidity_single::remove_liquidity_single::{calculate_amounts, pool_after_remove, swap_amount};
+use crate::stable_pool::pool_map;
+use crate::stable_token::token::Token;
+
+/// Remove liquidity from a pool into a single token
+///
+/// Calculate the amounts of remove_liquidity_amounts() and the swap of the other token through the pool for receive_token
+///
+/// The arguments should be passed to remove_liquidity_single() to execute the actual transaction
+#[query(guard = "not_in_maintenance_mode")]
+fn remove_liquidity_single_amounts(
+    token_0: String,
+    token_1: String,
+    remove_lp_token_amount: Nat,
+    receive_token: String,
+) -> Result<RemoveLiquiditySingleAmountsReply, String> {
+    let pool = pool_map::get_by_tokens(&token_0, &token_1)?;
+    let receive_token_0 = is_token_0(&pool, &receive_token)?;
+    let receive_token = if receive_token_0 { pool.token_0() } else { pool.token_1() };
+
+    let remove_liquidity = remove_liquidity_amounts(token_0, token_1, remove_lp_token_amount)?;
+    let payout_amounts = (
+        remove_liquidity.amount_0.clone(),
+        remove_liquidity.lp_fee_0.clone(),
+        remove_liquidity.amount_1.clone(),
+        remove_liquidity.lp_fee_1.clone(),
+    );
+
+    // swap the other token with the state of the pool after the liquidity is removed
+    let swap_amount = swap_amount(receive_token_0, &payout_amounts);
+    let (swap_receive_amount, slippage, _) =
+        calculate_amounts(&pool_after_remove(&pool, &payout_amounts), receive_token_0, &swap_amount, None)?;
+    let payout_amount = if receive_token_0 {
+        nat_add(&remove_liquidity.amount_0, &remove_liquidity.lp_fee_0)
+    } else {
+        nat_add(&remove_liquidity.amount_1, &remove_liquidity.lp_fee_1)
+    };
+
+    Ok(RemoveLiquiditySingleAmountsReply {
+        receive_chain: receive_token.chain(),
+        receive_address: receive_token.address(),
+        receive_symbol: receive_token.symbol(),
+        receive_amount: nat_add(&payout_amount, &swap_receive_amount),
+        swap_amount,
+        swap_receive_amount,
+        slippage,
+        remove_liquidity,
+    })
+}
@@ -0,0 +1,16 @@
+use candid::{CandidType, Nat};
+use serde::{Deserialize, Serialize};
+
+use super::remove_liquidity_amounts_reply::RemoveLiquidityAmountsReply;
+
+#[derive(CandidType, Clone, Debug, Serialize, Deserialize)]
+pub struct RemoveLiquiditySingleAmountsReply {
+    pub receive_chain: String,
+    pub receive_address: String,
+    pub receive_symbol: String,
+    pub receive_amount: Nat, // total payout in receive token, including LP fees and the swap. does not include gas fees
+    pub swap_amount: Nat,    // amount of the other token swapped for receive token
+    pub swap_receive_amount: Nat, // amount of receive token received from the swap
+    pub slippage: f64,
+    pub remove_liquidity: RemoveLiquidityAmountsReply, // amounts removed from the pool before the swap
+}
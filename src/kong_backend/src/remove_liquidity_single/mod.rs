@@ -0,0 +1,3 @@
+#[allow(clippy::module_inception)]
+pub mod remove_liquidity_single;
+pub mod remove_liquidity_single_args;
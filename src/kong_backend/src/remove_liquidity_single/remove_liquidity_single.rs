@@ -0,0 +1,313 @@
+use candid::Nat;
+use ic_cdk::update;
+
+use super::remove_liquidity_single_args::RemoveLiquiditySingleArgs;
+
+use crate::add_liquidity_single::add_liquidity_single::is_token_0;
+use crate::helpers::nat_helpers::{nat_add, nat_is_zero, nat_subtract, nat_zero};
+use crate::ic::{address::Address, get_time::get_time, guards::not_in_maintenance_mode, id::caller_id};
+use crate::remove_liquidity::remove_liquidity::{
+    archive_to_kong_data, check_arguments_with_user, remove_from_pool, send_payout_tokens, transfer_token, TokenIndex,
+};
+use crate::remove_liquidity::remove_liquidity_args::RemoveLiquidityArgs;
+use crate::remove_liquidity::remove_liquidity_reply::RemoveLiquidityReply;
+use crate::remove_liquidity::remove_liquidity_reply_helpers::{to_remove_liquidity_reply, to_remove_liquidity_reply_failed};
+use crate::stable_kong_settings::kong_settings_map;
+use crate::stable_pool::{pool_map, stable_pool::StablePool};
+use crate::stable_request::{reply::Reply, request::Request, request_map, stable_request::StableRequest, status::StatusCode};
+use crate::stable_token::token::Token;
+use crate::stable_tx::{remove_liquidity_tx::RemoveLiquidityTx, stable_tx::StableTx, tx_map};
+use crate::stable_user::user_map;
+use crate::swap::swap_amounts::pool_swap_amounts;
+use crate::swap::swap_calc::SwapCalc;
+use crate::swap::update_liquidity_pool::update_pools;
+
+/// payout_amount_0, payout_lp_fee_0, payout_amount_1, payout_lp_fee_1 of the removed liquidity
+pub type PayoutAmounts = (Nat, Nat, Nat, Nat);
+
+/// Remove liquidity from a pool into a single token (zap out)
+///
+/// - the LP tokens are removed as with remove_liquidity() and the payout of the other token is swapped through the pool
+///   for receive_token, so the user receives the whole payout in receive_token
+/// - if the swap fails, both tokens are paid out as with remove_liquidity()
+///
+/// Arguments: RemoveLiquiditySingleArgs
+///  token_0, token_1: tokens of the pool
+///  remove_lp_token_amount: amount of LP tokens to remove
+///  receive_token: token_0 or token_1, the token received by the user
+///  max_slippage: max slippage of the internal swap, default_max_slippage if not specified
+///
+/// Returns: RemoveLiquidityReply, with the internal swap in swap_txs
+#[update(guard = "not_in_maintenance_mode")]
+pub async fn remove_liquidity_single(args: RemoveLiquiditySingleArgs) -> Result<RemoveLiquidityReply, String> {
+    let (user_id, pool, receive_token_0, max_slippage, payout_amounts) = check_arguments(&args).await?;
+    let ts = get_time();
+    let request_id = request_map::insert(&StableRequest::new(user_id, &Request::RemoveLiquiditySingle(args.clone()), ts));
+
+    let result = match process_remove_liquidity_single(
+        request_id,
+        user_id,
+        &pool,
+        receive_token_0,
+        max_slippage,
+        &args.remove_lp_token_amount,
+        &payout_amounts,
+        ts,
+    )
+    .await
+    {
+        Ok(reply) => {
+            request_map::update_status(request_id, StatusCode::Success, None);
+            Ok(reply)
+        }
+        Err(e) => {
+            request_map::update_status(request_id, StatusCode::Failed, None);
+            Err(e)
+        }
+    };
+    _ = archive_to_kong_data(request_id);
+
+    result
+}
+
+/// remove liquidity into a single token asynchronously. same as remove_liquidity_single() but returns the request_id immediately
+#[update(guard = "not_in_maintenance_mode")]
+pub async fn remove_liquidity_single_async(args: RemoveLiquiditySingleArgs) -> Result<u64, String> {
+    let (user_id, pool, receive_token_0, max_slippage, payout_amounts) = check_arguments(&args).await?;
+    let ts = get_time();
+    let request_id = request_map::insert(&StableRequest::new(user_id, &Request::RemoveLiquiditySingle(args.clone()), ts));
+
+    ic_cdk::spawn(async move {
+        match process_remove_liquidity_single(
+            request_id,
+            user_id,
+            &pool,
+            receive_token_0,
+            max_slippage,
+            &args.remove_lp_token_amount,
+            &payout_amounts,
+            ts,
+        )
+        .await
+        {
+            Ok(_) => request_map::update_status(request_id, StatusCode::Success, None),
+            Err(_) => request_map::update_status(request_id, StatusCode::Failed, None),
+        };
+        _ = archive_to_kong_data(request_id);
+    });
+
+    Ok(request_id)
+}
+
+/// returns (user_id, pool, receive_token_0, max_slippage, payout_amounts)
+async fn check_arguments(args: &RemoveLiquiditySingleArgs) -> Result<(u32, StablePool, bool, f64, PayoutAmounts), String> {
+    // make sure user is not anonymous and exists
+    let user_id = user_map::get_by_caller()?.ok_or("Insufficient LP balance")?.user_id;
+
+    let pool = pool_map::get_by_tokens_and_fee_tier(&args.token_0, &args.token_1, args.fee_tier)?;
+    let receive_token_0 = is_token_0(&pool, &args.receive_token)?;
+    let receive_token = if receive_token_0 { pool.token_0() } else { pool.token_1() };
+    if receive_token.is_removed() {
+        Err("Receive token is suspended or removed".to_string())?
+    }
+
+    let remove_liquidity_args = RemoveLiquidityArgs {
+        token_0: args.token_0.clone(),
+        token_1: args.token_1.clone(),
+        remove_lp_token_amount: args.remove_lp_token_amount.clone(),
+        position_id: None,
+        use_deposit: None,
+        receive_address: None,
+        fee_tier: args.fee_tier,
+        min_amount_0: None,
+        min_amount_1: None,
+        deadline: None,
+    };
+    let (pool, _, payout_amount_0, payout_lp_fee_0, payout_amount_1, payout_lp_fee_1) =
+        check_arguments_with_user(&remove_liquidity_args, user_id, true)?;
+    let payout_amounts = (payout_amount_0, payout_lp_fee_0, payout_amount_1, payout_lp_fee_1);
+
+    // make sure the swap is valid with the state of the pool after the liquidity is removed
+    let max_slippage = args.max_slippage.unwrap_or(kong_settings_map::get().default_max_slippage);
+    let swap_amount = swap_amount(receive_token_0, &payout_amounts);
+    calculate_amounts(
+        &pool_after_remove(&pool, &payout_amounts),
+        receive_token_0,
+        &swap_amount,
+        Some(max_slippage),
+    )?;
+
+    Ok((user_id, pool, receive_token_0, max_slippage, payout_amounts))
+}
+
+/// the payout of the other token is swapped for receive token
+pub fn swap_amount(receive_token_0: bool, payout_amounts: &PayoutAmounts) -> Nat {
+    let (payout_amount_0, payout_lp_fee_0, payout_amount_1, payout_lp_fee_1) = payout_amounts;
+    if receive_token_0 {
+        nat_add(payout_amount_1, payout_lp_fee_1)
+    } else {
+        nat_add(payout_amount_0, payout_lp_fee_0)
+    }
+}
+
+/// state of the pool after the payout amounts are removed
+pub fn pool_after_remove(pool: &StablePool, payout_amounts: &PayoutAmounts) -> StablePool {
+    let (payout_amount_0, payout_lp_fee_0, payout_amount_1, payout_lp_fee_1) = payout_amounts;
+    StablePool {
+        balance_0: nat_subtract(&pool.balance_0, payout_amount_0).unwrap_or(nat_zero()),
+        lp_fee_0: nat_subtract(&pool.lp_fee_0, payout_lp_fee_0).unwrap_or(nat_zero()),
+        balance_1: nat_subtract(&pool.balance_1, payout_amount_1).unwrap_or(nat_zero()),
+        lp_fee_1: nat_subtract(&pool.lp_fee_1, payout_lp_fee_1).unwrap_or(nat_zero()),
+        ..pool.clone()
+    }
+}
+
+/// calculate the internal swap of a single token remove liquidity, swap_amount of the other token is swapped for receive token
+/// max_slippage - if None, slippage is not checked
+///
+/// returns (receive_amount, slippage, swaps)
+pub fn calculate_amounts(
+    pool: &StablePool,
+    receive_token_0: bool,
+    swap_amount: &Nat,
+    max_slippage: Option<f64>,
+) -> Result<(Nat, f64, Vec<SwapCalc>), String> {
+    if nat_is_zero(swap_amount) {
+        // nothing to swap
+        return Ok((nat_zero(), 0.0, Vec::new()));
+    }
+    let (receive_amount, _, _, slippage, swaps) = pool_swap_amounts(pool, !receive_token_0, swap_amount)?;
+    if let Some(max_slippage) = max_slippage {
+        if slippage > max_slippage {
+            Err(format!("Slippage exceeded. Swap of {} has {}% slippage", pool.symbol(), slippage))?
+        }
+    }
+
+    Ok((receive_amount, slippage, swaps))
+}
+
+#[allow(clippy::too_many_arguments)]
+async fn process_remove_liquidity_single(
+    request_id: u64,
+    user_id: u32,
+    pool: &StablePool,
+    receive_token_0: bool,
+    max_slippage: f64,
+    remove_lp_token_amount: &Nat,
+    payout_amounts: &PayoutAmounts,
+    ts: u64,
+) -> Result<RemoveLiquidityReply, String> {
+    let (payout_amount_0, payout_lp_fee_0, payout_amount_1, payout_lp_fee_1) = payout_amounts;
+    let to_address = Address::PrincipalId(caller_id());
+
+    request_map::update_status(request_id, StatusCode::Start, None);
+
+    remove_from_pool(
+        request_id,
+        user_id,
+        pool,
+        None,
+        remove_lp_token_amount,
+        payout_amount_0,
+        payout_lp_fee_0,
+        payout_amount_1,
+        payout_lp_fee_1,
+        ts,
+    )?;
+
+    // swap the payout of the other token for receive token with the latest state of the pool
+    let swap_amount = swap_amount(receive_token_0, payout_amounts);
+    let (receive_amount, swaps) = match swap_payout_token(request_id, pool.pool_id, receive_token_0, &swap_amount, max_slippage) {
+        Ok(swap) => swap,
+        Err(e) => {
+            // pay out both tokens as with remove_liquidity()
+            _ = send_payout_tokens(
+                request_id,
+                user_id,
+                &to_address,
+                pool,
+                payout_amount_0,
+                payout_lp_fee_0,
+                payout_amount_1,
+                payout_lp_fee_1,
+                remove_lp_token_amount,
+                ts,
+            )
+            .await;
+            return Err(format!("Req #{} failed. Paid out both tokens. {}", request_id, e));
+        }
+    };
+
+    // send the payout and the received amount of the swap to the user
+    let mut transfer_ids = Vec::new();
+    let mut claim_ids = Vec::new();
+    let (token_index, token, payout_amount, payout_lp_fee) = if receive_token_0 {
+        (TokenIndex::Token0, pool.token_0(), payout_amount_0, payout_lp_fee_0)
+    } else {
+        (TokenIndex::Token1, pool.token_1(), payout_amount_1, payout_lp_fee_1)
+    };
+    transfer_token(
+        request_id,
+        user_id,
+        &to_address,
+        token_index,
+        &token,
+        &nat_add(payout_amount, &receive_amount),
+        payout_lp_fee,
+        &mut transfer_ids,
+        &mut claim_ids,
+        ts,
+    )
+    .await;
+
+    let remove_liquidity_tx = RemoveLiquidityTx {
+        swap_txs: swaps,
+        ..RemoveLiquidityTx::new_success(
+            pool.pool_id,
+            user_id,
+            request_id,
+            payout_amount_0,
+            payout_lp_fee_0,
+            payout_amount_1,
+            payout_lp_fee_1,
+            remove_lp_token_amount,
+            &transfer_ids,
+            &claim_ids,
+            ts,
+        )
+    };
+    let tx_id = tx_map::insert(&StableTx::RemoveLiquidity(remove_liquidity_tx));
+    let reply = match tx_map::get_by_user_and_token_id(Some(tx_id), None, None, None).first() {
+        Some(StableTx::RemoveLiquidity(remove_liquidity_tx)) => to_remove_liquidity_reply(remove_liquidity_tx),
+        _ => to_remove_liquidity_reply_failed(pool.pool_id, request_id, ts),
+    };
+    request_map::update_reply(request_id, Reply::RemoveLiquidity(reply.clone()));
+
+    Ok(reply)
+}
+
+/// swap swap_amount of the other token through the pool for receive token
+/// returns (receive_amount, swaps)
+fn swap_payout_token(
+    request_id: u64,
+    pool_id: u32,
+    receive_token_0: bool,
+    swap_amount: &Nat,
+    max_slippage: f64,
+) -> Result<(Nat, Vec<SwapCalc>), String> {
+    request_map::update_status(request_id, StatusCode::SwapSingleToken, None);
+
+    let result = pool_map::get_by_pool_id(pool_id)
+        .ok_or(format!("Pool #{} not found", pool_id))
+        .and_then(|pool| calculate_amounts(&pool, receive_token_0, swap_amount, Some(max_slippage)))
+        .and_then(|(receive_amount, _, swaps)| {
+            update_pools(request_id, &swaps)?;
+            Ok((receive_amount, swaps))
+        });
+    match result {
+        Ok(_) => request_map::update_status(request_id, StatusCode::SwapSingleTokenSuccess, None),
+        Err(ref e) => request_map::update_status(request_id, StatusCode::SwapSingleTokenFailed, Some(e)),
+    };
+
+    result
+}
@@ -0,0 +1,14 @@
+use candid::{CandidType, Nat};
+use serde::{Deserialize, Serialize};
+
+/// Data structure for the arguments of the `remove_liquidity_single` function.
+/// Used in StableRequest
+#[derive(CandidType, Debug, Clone, Serialize, Deserialize)]
+pub struct RemoveLiquiditySingleArgs {
+    pub token_0: String,
+    pub token_1: String,
+    pub remove_lp_token_amount: Nat,
+    pub receive_token: String, // token_0 or token_1. the other token is swapped through the pool for receive_token
+    pub max_slippage: Option<f64>, // max slippage of the internal swap
+    pub fee_tier: Option<u8>,  // fee tier of the pool. None for the first pool of the pair
+}
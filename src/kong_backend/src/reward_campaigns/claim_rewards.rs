@@ -0,0 +1,90 @@
+use ic_cdk::update;
+
+use crate::claims::claim_reply::ClaimReply;
+use crate::claims::process_claim::process_new_claims;
+use crate::ic::address::Address;
+use crate::ic::get_time::get_time;
+use crate::ic::guards::not_in_maintenance_mode;
+use crate::ic::id::caller_id;
+use crate::stable_claim::claim_map;
+use crate::stable_claim::stable_claim::StableClaim;
+use crate::stable_reward_campaign::reward_campaign_map;
+use crate::stable_user::user_map;
+
+/// Claim all liquidity mining rewards of the caller
+/// rewards are saved as claims and sent to the caller. claims that fail to send are retried by the claims timer
+#[update(guard = "not_in_maintenance_mode")]
+async fn claim_rewards() -> Result<Vec<ClaimReply>, String> {
+    let user_id = user_map::get_by_caller()?.ok_or("User not found")?.user_id;
+    let ts = get_time();
+    let to_address = Address::PrincipalId(caller_id());
+
+    let claim_ids = insert_reward_claims(user_id, &to_address, ts);
+    if claim_ids.is_empty() {
+        Err("No rewards to claim".to_string())?
+    }
+
+    Ok(process_new_claims(user_id, &claim_ids, &to_address, ts).await)
+}
+
+/// rewards are taken and saved as claims before any inter-canister call so they can only be claimed once
+/// returns the claim_ids, one for each campaign with rewards
+fn insert_reward_claims(user_id: u32, to_address: &Address, ts: u64) -> Vec<u64> {
+    reward_campaign_map::take_rewards(user_id, ts)
+        .into_iter()
+        .map(|(campaign, amount)| {
+            let mut claim = StableClaim::new(user_id, campaign.reward_token_id, &amount, None, Some(to_address.clone()), ts);
+            claim.desc = Some(format!("Liquidity mining rewards of campaign #{}", campaign.campaign_id));
+            claim_map::insert(&claim)
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use candid::{Nat, Principal};
+
+    use crate::helpers::nat_helpers::nat_is_zero;
+    use crate::stable_lp_token::{lp_token_map, stable_lp_token::StableLPToken};
+    use crate::stable_pool::pool_map::tests::{insert_pool, LP_TOKEN_ID};
+    use crate::stable_reward_campaign::stable_reward_campaign::StableRewardCampaign;
+
+    const USER_ID: u32 = 100;
+
+    #[test]
+    fn test_insert_reward_claims() {
+        let pool = insert_pool(1_000_000);
+        let start_ts = get_time();
+        let end_ts = start_ts + 1_000_000_000_000;
+        let campaign_id = reward_campaign_map::insert(&StableRewardCampaign::new(
+            LP_TOKEN_ID,
+            pool.token_id_0,
+            &Nat::from(1_000_000_u32),
+            start_ts,
+            end_ts,
+            start_ts,
+        ));
+        let to_address = Address::PrincipalId(Principal::anonymous().into());
+
+        // no rewards before the user holds any LP tokens
+        assert!(insert_reward_claims(USER_ID, &to_address, end_ts).is_empty());
+
+        // the only LP earns all the rewards streamed while holding the LP tokens
+        lp_token_map::insert(&StableLPToken::new(USER_ID, LP_TOKEN_ID, Nat::from(10_u32), start_ts)).unwrap();
+        let claim_ids = insert_reward_claims(USER_ID, &to_address, end_ts);
+        assert_eq!(claim_ids.len(), 1);
+        let claim = claim_map::get_by_claim_id(claim_ids[0]).unwrap();
+        let campaign = reward_campaign_map::get_by_campaign_id(campaign_id).unwrap();
+        assert_eq!(claim.token_id, pool.token_id_0);
+        assert!(!nat_is_zero(&claim.amount));
+        assert_eq!(claim.amount, campaign.distributed_amount);
+
+        // rewards can only be claimed once
+        assert!(insert_reward_claims(USER_ID, &to_address, end_ts).is_empty());
+        assert_eq!(
+            reward_campaign_map::get_outstanding_amount(pool.token_id_0),
+            campaign.reward_amount - claim.amount
+        );
+    }
+}
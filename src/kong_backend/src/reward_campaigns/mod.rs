@@ -0,0 +1,5 @@
+pub mod claim_rewards;
+pub mod reward_campaign_reply;
+pub mod reward_campaign_reply_helpers;
+#[allow(clippy::module_inception)]
+pub mod reward_campaigns;
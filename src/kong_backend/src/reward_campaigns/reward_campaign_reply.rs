@@ -0,0 +1,17 @@
+use candid::{CandidType, Nat};
+use serde::{Deserialize, Serialize};
+
+#[derive(CandidType, Debug, Clone, Serialize, Deserialize)]
+pub struct RewardCampaignReply {
+    pub campaign_id: u64,
+    pub symbol: String, // pool symbol
+    pub reward_chain: String,
+    pub reward_symbol: String,
+    pub reward_address: String,
+    pub reward_amount: Nat,
+    pub distributed_amount: Nat,
+    pub start_ts: u64,
+    pub end_ts: u64,
+    pub is_active: bool,
+    pub ts: u64,
+}
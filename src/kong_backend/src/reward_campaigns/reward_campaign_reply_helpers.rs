@@ -0,0 +1,34 @@
+use super::reward_campaign_reply::RewardCampaignReply;
+
+use crate::stable_pool::pool_map;
+use crate::stable_reward_campaign::stable_reward_campaign::StableRewardCampaign;
+use crate::stable_token::token::Token;
+use crate::stable_token::token_map;
+
+pub fn to_reward_campaign_reply(campaign: &StableRewardCampaign, ts: u64) -> RewardCampaignReply {
+    let symbol = pool_map::get_by_lp_token_id(campaign.lp_token_id).map_or_else(|| "Pool not found".to_string(), |pool| pool.symbol());
+    let (reward_chain, reward_symbol, reward_address) = token_map::get_by_token_id(campaign.reward_token_id).map_or_else(
+        || {
+            (
+                "Reward chain not found".to_string(),
+                "Reward symbol not found".to_string(),
+                "Reward address not found".to_string(),
+            )
+        },
+        |token| (token.chain().to_string(), token.symbol().to_string(), token.address()),
+    );
+
+    RewardCampaignReply {
+        campaign_id: campaign.campaign_id,
+        symbol,
+        reward_chain,
+        reward_symbol,
+        reward_address,
+        reward_amount: campaign.reward_amount.clone(),
+        distributed_amount: campaign.distributed_amount.clone(),
+        start_ts: campaign.start_ts,
+        end_ts: campaign.end_ts,
+        is_active: campaign.is_active(ts),
+        ts: campaign.ts,
+    }
+}
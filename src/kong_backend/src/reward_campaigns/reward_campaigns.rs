@@ -0,0 +1,18 @@
+use ic_cdk::query;
+
+use super::reward_campaign_reply::RewardCampaignReply;
+use super::reward_campaign_reply_helpers::to_reward_campaign_reply;
+
+use crate::ic::get_time::get_time;
+use crate::ic::guards::not_in_maintenance_mode;
+use crate::stable_reward_campaign::reward_campaign_map;
+
+/// Return all liquidity mining reward campaigns, newest first
+#[query(guard = "not_in_maintenance_mode")]
+fn reward_campaigns() -> Result<Vec<RewardCampaignReply>, String> {
+    let ts = get_time();
+    Ok(reward_campaign_map::get()
+        .iter()
+        .map(|campaign| to_reward_campaign_reply(campaign, ts))
+        .collect())
+}
@@ -1,12 +1,13 @@
 use candid::Nat;
 use ic_cdk::update;
+use icrc_ledger_types::icrc1::account::Account;
 
 use super::send_args::SendArgs;
 use super::send_reply::SendReply;
 use super::send_reply_helpers::{to_send_reply, to_send_reply_failed};
 
 use crate::chains::chains::LP_CHAIN;
-use crate::ic::{get_time::get_time, guards::not_in_maintenance_mode};
+use crate::ic::{address::Address, address_helpers::parse_address, get_time::get_time, guards::not_in_maintenance_mode};
 use crate::stable_kong_settings::kong_settings_map;
 use crate::stable_lp_token::transfer::transfer;
 use crate::stable_request::request_map;
@@ -31,8 +32,12 @@ async fn send(args: SendArgs) -> Result<SendReply, String> {
     let lp_token_chain = LP_CHAIN;
     let lp_token_symbol = lp_token.symbol;
 
-    // to user
-    let to_user = user_map::get_by_principal_id(&args.to_address)
+    // to user. LP tokens are held by users, so only principal ids are supported
+    let to_principal_id = match parse_address(&args.to_address)? {
+        Address::PrincipalId(Account { owner, subaccount: None }) => owner.to_text(),
+        _ => Err("LP tokens can only be sent to a principal id".to_string())?,
+    };
+    let to_user = user_map::get_by_principal_id(&to_principal_id)
         .ok()
         .flatten()
         .ok_or("User not found")?;
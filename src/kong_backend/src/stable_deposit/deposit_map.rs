@@ -0,0 +1,65 @@
+use candid::Nat;
+
+use super::stable_deposit::{StableDeposit, StableDepositId};
+
+use crate::helpers::nat_helpers::{nat_add, nat_subtract, nat_zero};
+use crate::stable_memory::DEPOSIT_MAP;
+
+/// deposit balance of a user in a token
+pub fn get_balance(user_id: u32, token_id: u32) -> Nat {
+    DEPOSIT_MAP.with(|m| {
+        m.borrow()
+            .get(&StableDepositId(user_id, token_id))
+            .map_or_else(nat_zero, |deposit| deposit.amount)
+    })
+}
+
+/// all deposits of a user
+pub fn get_by_user_id(user_id: u32) -> Vec<StableDeposit> {
+    DEPOSIT_MAP.with(|m| {
+        m.borrow()
+            .range(StableDepositId(user_id, 0)..=StableDepositId(user_id, u32::MAX))
+            .map(|(_, v)| v)
+            .collect()
+    })
+}
+
+/// deposits of a token of all users
+pub fn get_by_token_id(token_id: u32) -> Vec<StableDeposit> {
+    DEPOSIT_MAP.with(|m| m.borrow().iter().filter(|(k, _)| k.1 == token_id).map(|(_, v)| v).collect())
+}
+
+/// total deposits of a token of all users
+pub fn get_total_amount(token_id: u32) -> Nat {
+    DEPOSIT_MAP.with(|m| {
+        m.borrow()
+            .iter()
+            .filter(|(k, _)| k.1 == token_id)
+            .fold(nat_zero(), |acc, (_, v)| nat_add(&acc, &v.amount))
+    })
+}
+
+/// credit amount of token_id to the deposit of the user
+pub fn credit(user_id: u32, token_id: u32, amount: &Nat, ts: u64) {
+    DEPOSIT_MAP.with(|m| {
+        let mut map = m.borrow_mut();
+        let key = StableDepositId(user_id, token_id);
+        let mut deposit = map.get(&key).unwrap_or_else(|| StableDeposit::new(user_id, token_id, ts));
+        deposit.amount = nat_add(&deposit.amount, amount);
+        deposit.ts = ts;
+        map.insert(key, deposit);
+    });
+}
+
+/// debit amount of token_id from the deposit of the user. errors if the deposit is insufficient
+pub fn debit(user_id: u32, token_id: u32, amount: &Nat, ts: u64) -> Result<(), String> {
+    DEPOSIT_MAP.with(|m| {
+        let mut map = m.borrow_mut();
+        let key = StableDepositId(user_id, token_id);
+        let mut deposit = map.get(&key).ok_or("Insufficient deposit balance")?;
+        deposit.amount = nat_subtract(&deposit.amount, amount).ok_or("Insufficient deposit balance")?;
+        deposit.ts = ts;
+        map.insert(key, deposit);
+        Ok(())
+    })
+}
@@ -0,0 +1,3 @@
+pub mod deposit_map;
+#[allow(clippy::module_inception)]
+pub mod stable_deposit;
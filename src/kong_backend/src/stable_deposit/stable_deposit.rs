@@ -0,0 +1,54 @@
+use candid::{CandidType, Nat};
+use ic_stable_structures::{storable::Bound, Storable};
+use serde::{Deserialize, Serialize};
+
+use crate::helpers::nat_helpers::nat_zero;
+
+/// (user_id, token_id)
+#[derive(CandidType, Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+pub struct StableDepositId(pub u32, pub u32);
+
+impl Storable for StableDepositId {
+    fn to_bytes(&self) -> std::borrow::Cow<'_, [u8]> {
+        serde_cbor::to_vec(self).unwrap().into()
+    }
+
+    fn from_bytes(bytes: std::borrow::Cow<'_, [u8]>) -> Self {
+        serde_cbor::from_slice(&bytes).unwrap()
+    }
+
+    const BOUND: Bound = Bound::Unbounded;
+}
+
+/// internal balance of a user in a token held by Kong. deposited with deposit() and used by swaps and liquidity
+/// operations with use_deposit, so no ledger transfers or gas fees are needed until it is withdrawn
+#[derive(CandidType, Debug, Clone, Serialize, Deserialize)]
+pub struct StableDeposit {
+    pub user_id: u32,
+    pub token_id: u32,
+    pub amount: Nat,
+    pub ts: u64,
+}
+
+impl StableDeposit {
+    pub fn new(user_id: u32, token_id: u32, ts: u64) -> Self {
+        Self {
+            user_id,
+            token_id,
+            amount: nat_zero(),
+            ts,
+        }
+    }
+}
+
+impl Storable for StableDeposit {
+    fn to_bytes(&self) -> std::borrow::Cow<'_, [u8]> {
+        serde_cbor::to_vec(self).unwrap().into()
+    }
+
+    fn from_bytes(bytes: std::borrow::Cow<'_, [u8]>) -> Self {
+        serde_cbor::from_slice(&bytes).unwrap()
+    }
+
+    const BOUND: Bound = Bound::Unbounded;
+}
@@ -121,3 +121,43 @@ pub fn inc_lp_token_map_idx() -> u64 {
         lp_token_map_idx
     })
 }
+
+pub fn inc_limit_order_map_idx() -> u64 {
+    KONG_SETTINGS.with(|s| {
+        let mut map = s.borrow_mut();
+        let kong_settings = map.get();
+        let limit_order_map_idx = kong_settings.limit_order_map_idx + 1;
+        let new_kong_settings = StableKongSettings {
+            limit_order_map_idx,
+            ..kong_settings.clone()
+        };
+        _ = map.set(new_kong_settings);
+        limit_order_map_idx
+    })
+}
+
+pub fn inc_reward_campaign_map_idx() -> u64 {
+    KONG_SETTINGS.with(|s| {
+        let mut map = s.borrow_mut();
+        let kong_settings = map.get();
+        let reward_campaign_map_idx = kong_settings.reward_campaign_map_idx + 1;
+        let new_kong_settings = StableKongSettings {
+            reward_campaign_map_idx,
+            ..kong_settings.clone()
+        };
+        _ = map.set(new_kong_settings);
+        reward_campaign_map_idx
+    })
+}
+
+pub fn set_user_volume_tx_idx(user_volume_tx_idx: u64) {
+    KONG_SETTINGS.with(|s| {
+        let mut map = s.borrow_mut();
+        let kong_settings = map.get();
+        let new_kong_settings = StableKongSettings {
+            user_volume_tx_idx,
+            ..kong_settings.clone()
+        };
+        _ = map.set(new_kong_settings);
+    });
+}
@@ -1,4 +1,4 @@
-use candid::{CandidType, Principal};
+use candid::{CandidType, Nat, Principal};
 use ic_stable_structures::{storable::Bound, Storable};
 use icrc_ledger_types::icrc1::account::Account;
 use serde::{Deserialize, Serialize};
@@ -10,9 +10,10 @@ use crate::ic::{
     icp::{ICP_ADDRESS, ICP_ADDRESS_WITH_CHAIN, ICP_SYMBOL, ICP_SYMBOL_WITH_CHAIN, ICP_TOKEN_ID},
 };
 use crate::stable_memory::{
-    CLAIM_MAP, LP_TOKEN_MAP, POOL_MAP, REQUEST_ARCHIVE_MAP, REQUEST_MAP, TOKEN_MAP, TRANSFER_ARCHIVE_MAP, TRANSFER_MAP, TX_ARCHIVE_MAP,
-    TX_MAP, USER_MAP,
+    CLAIM_MAP, LIMIT_ORDER_MAP, LP_TOKEN_MAP, POOL_MAP, REQUEST_ARCHIVE_MAP, REQUEST_MAP, REWARD_CAMPAIGN_MAP, TOKEN_MAP,
+    TRANSFER_ARCHIVE_MAP, TRANSFER_MAP, TX_ARCHIVE_MAP, TX_MAP, USER_MAP,
 };
+use crate::user_fee_tiers::user_fee_tier::UserFeeTier;
 
 #[derive(CandidType, Debug, Clone, Serialize, Deserialize)]
 pub struct StableKongSettings {
@@ -47,6 +48,62 @@ pub struct StableKongSettings {
     pub txs_archive_interval_secs: u64,
     pub transfers_archive_interval_secs: u64,
     pub archive_to_kong_data: bool,
+    #[serde(default = "default_max_swap_hops")]
+    pub max_swap_hops: u8, // max number of pools a swap can be routed through
+    #[serde(default = "default_max_swap_legs")]
+    pub max_swap_legs: u8, // max number of paths a swap can be split into
+    #[serde(default)]
+    pub limit_order_map_idx: u64, // counter for LIMIT_ORDER_MAP
+    #[serde(default = "default_limit_orders_interval_secs")]
+    pub limit_orders_interval_secs: u64,
+    #[serde(default)]
+    pub reward_campaign_map_idx: u64, // counter for REWARD_CAMPAIGN_MAP
+    #[serde(default = "default_referral_fee_pct")]
+    pub referral_fee_pct: u8, // percent of Kong's fee of swaps by referred users credited to the referrer
+    #[serde(default = "default_listing_deposit")]
+    pub listing_deposit: Nat, // ckUSDT deposit to list a new token with add_pool. refunded when King Kong approves the token
+    #[serde(default = "default_fee_sweep_interval_secs")]
+    pub fee_sweep_interval_secs: u64,
+    #[serde(default)]
+    pub fee_treasury: Option<Account>, // account Kong fees are swept to. sweeps are off if not set
+    #[serde(default)]
+    pub fee_buyback_pct: u8, // percent of swept Kong fees swapped into KONG and burned. the rest goes to fee_treasury
+    #[serde(default)]
+    pub kong_token_id: Option<u32>, // token_id of KONG for the buyback and fee tiers
+    #[serde(default)]
+    pub fee_tiers: Vec<UserFeeTier>, // fee schedule of user fee levels. fee tiers are off if empty
+    #[serde(default = "default_fee_tiers_interval_secs")]
+    pub fee_tiers_interval_secs: u64,
+    #[serde(default)]
+    pub user_volume_tx_idx: u64, // last tx_id added to the user volumes
+}
+
+fn default_max_swap_hops() -> u8 {
+    3
+}
+
+fn default_max_swap_legs() -> u8 {
+    3
+}
+
+fn default_limit_orders_interval_secs() -> u64 {
+    10
+}
+
+fn default_referral_fee_pct() -> u8 {
+    20
+}
+
+fn default_listing_deposit() -> Nat {
+    Nat::from(100_000_000_u64) // 100 ckUSDT
+}
+
+fn default_fee_sweep_interval_secs() -> u64 {
+    86400
+}
+
+fn default_fee_tiers_interval_secs() -> u64 {
+    3600
 }
 
 impl Default for StableKongSettings {
@@ -56,6 +113,8 @@ impl Default for StableKongSettings {
         let pool_map_idx = POOL_MAP.with(|m| m.borrow().iter().map(|(k, _)| k.0).max().unwrap_or(0));
         let claim_map_idx = CLAIM_MAP.with(|m| m.borrow().iter().map(|(k, _)| k.0).max().unwrap_or(0));
         let lp_token_map_idx = LP_TOKEN_MAP.with(|m| m.borrow().iter().map(|(k, _)| k.0).max().unwrap_or(0));
+        let limit_order_map_idx = LIMIT_ORDER_MAP.with(|m| m.borrow().iter().map(|(k, _)| k.0).max().unwrap_or(0));
+        let reward_campaign_map_idx = REWARD_CAMPAIGN_MAP.with(|m| m.borrow().iter().map(|(k, _)| k.0).max().unwrap_or(0));
         let request_map_idx = cmp::max(
             REQUEST_MAP.with(|m| m.borrow().iter().map(|(k, _)| k.0).max().unwrap_or(0)),
             REQUEST_ARCHIVE_MAP.with(|m| m.borrow().iter().map(|(k, _)| k.0).max().unwrap_or(0)),
@@ -100,6 +159,20 @@ impl Default for StableKongSettings {
             txs_archive_interval_secs: 3600,             // archive txs every hour
             transfers_archive_interval_secs: 3600,       // archive transfers every hour
             archive_to_kong_data: false,                 // replicate to kong_data
+            max_swap_hops: default_max_swap_hops(),
+            max_swap_legs: default_max_swap_legs(),
+            limit_order_map_idx,
+            limit_orders_interval_secs: default_limit_orders_interval_secs(), // check limit orders every 10 seconds
+            reward_campaign_map_idx,
+            referral_fee_pct: default_referral_fee_pct(),
+            listing_deposit: default_listing_deposit(),
+            fee_sweep_interval_secs: default_fee_sweep_interval_secs(), // sweep Kong fees once a day
+            fee_treasury: None,
+            fee_buyback_pct: 0,
+            kong_token_id: None,
+            fee_tiers: Vec::new(),
+            fee_tiers_interval_secs: default_fee_tiers_interval_secs(), // refresh user fee levels every hour
+            user_volume_tx_idx: 0,
         }
     }
 }
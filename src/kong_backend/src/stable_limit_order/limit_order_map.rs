@@ -0,0 +1,75 @@
+use candid::Nat;
+
+use super::stable_limit_order::{LimitOrderStatus, StableLimitOrder, StableLimitOrderId};
+
+use crate::helpers::nat_helpers::{nat_add, nat_zero};
+use crate::stable_kong_settings::kong_settings_map;
+use crate::stable_memory::{LIMIT_ORDERS_SCAN_PENDING, LIMIT_ORDER_MAP};
+
+pub fn get_by_order_id(order_id: u64) -> Option<StableLimitOrder> {
+    LIMIT_ORDER_MAP.with(|m| m.borrow().get(&StableLimitOrderId(order_id)))
+}
+
+/// all orders of a user, newest first
+pub fn get_by_user_id(user_id: u32) -> Vec<StableLimitOrder> {
+    LIMIT_ORDER_MAP.with(|m| {
+        m.borrow()
+            .iter()
+            .rev()
+            .filter_map(|(_, v)| if v.user_id == user_id { Some(v) } else { None })
+            .collect()
+    })
+}
+
+/// order_ids of all open orders, oldest first
+pub fn get_open_order_ids() -> Vec<u64> {
+    LIMIT_ORDER_MAP.with(|m| {
+        m.borrow()
+            .iter()
+            .filter_map(|(_, v)| {
+                if v.status == LimitOrderStatus::Open {
+                    Some(v.order_id)
+                } else {
+                    None
+                }
+            })
+            .collect()
+    })
+}
+
+/// pay token of token_id escrowed by open orders
+pub fn get_open_amount(token_id: u32) -> Nat {
+    LIMIT_ORDER_MAP.with(|m| {
+        m.borrow()
+            .iter()
+            .filter(|(_, v)| v.status == LimitOrderStatus::Open && v.pay_token_id == token_id)
+            .fold(nat_zero(), |acc, (_, v)| nat_add(&acc, &v.pay_amount))
+    })
+}
+
+pub fn insert(order: &StableLimitOrder) -> u64 {
+    let order_id = LIMIT_ORDER_MAP.with(|m| {
+        let mut map = m.borrow_mut();
+        let order_id = kong_settings_map::inc_limit_order_map_idx();
+        let insert_order = StableLimitOrder { order_id, ..order.clone() };
+        map.insert(StableLimitOrderId(order_id), insert_order);
+        order_id
+    });
+    // new order needs to be checked against the current pool prices
+    set_scan_pending();
+    order_id
+}
+
+pub fn update(order: &StableLimitOrder) {
+    LIMIT_ORDER_MAP.with(|m| m.borrow_mut().insert(StableLimitOrderId(order.order_id), order.clone()));
+}
+
+/// flag that pool prices or open orders changed and open orders need to be scanned for fills
+pub fn set_scan_pending() {
+    LIMIT_ORDERS_SCAN_PENDING.with(|s| *s.borrow_mut() = true);
+}
+
+/// returns if a scan is pending and resets the flag
+pub fn take_scan_pending() -> bool {
+    LIMIT_ORDERS_SCAN_PENDING.with(|s| s.replace(false))
+}
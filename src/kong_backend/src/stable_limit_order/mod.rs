@@ -0,0 +1,3 @@
+pub mod limit_order_map;
+#[allow(clippy::module_inception)]
+pub mod stable_limit_order;
@@ -0,0 +1,106 @@
+use candid::{CandidType, Nat};
+use ic_stable_structures::{storable::Bound, Storable};
+use serde::{Deserialize, Serialize};
+
+use crate::ic::address::Address;
+
+#[derive(CandidType, Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+pub struct StableLimitOrderId(pub u64);
+
+impl Storable for StableLimitOrderId {
+    fn to_bytes(&self) -> std::borrow::Cow<'_, [u8]> {
+        serde_cbor::to_vec(self).unwrap().into()
+    }
+
+    fn from_bytes(bytes: std::borrow::Cow<'_, [u8]>) -> Self {
+        serde_cbor::from_slice(&bytes).unwrap()
+    }
+
+    const BOUND: Bound = Bound::Unbounded;
+}
+
+#[derive(CandidType, Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum LimitOrderStatus {
+    Open,
+    Filled,
+    Cancelled,
+    Expired,
+}
+
+impl std::fmt::Display for LimitOrderStatus {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            LimitOrderStatus::Open => write!(f, "Open"),
+            LimitOrderStatus::Filled => write!(f, "Filled"),
+            LimitOrderStatus::Cancelled => write!(f, "Cancelled"),
+            LimitOrderStatus::Expired => write!(f, "Expired"),
+        }
+    }
+}
+
+/// limit order with the pay token escrowed in Kong
+/// the order is filled when swapping pay_amount returns at least receive_amount after fees and gas
+#[derive(CandidType, Debug, Clone, Serialize, Deserialize)]
+pub struct StableLimitOrder {
+    pub order_id: u64,
+    pub user_id: u32,
+    pub status: LimitOrderStatus,
+    pub pay_token_id: u32,
+    pub pay_amount: Nat,
+    pub receive_token_id: u32,
+    pub receive_amount: Nat, // minimum receive amount after fees and gas
+    pub to_address: Address,
+    pub expires_at: Option<u64>,
+    pub request_id: u64,              // request that placed the order
+    pub fill_request_id: Option<u64>, // request that filled the order
+    pub tx_id: Option<u64>,           // swap tx of the fill
+    pub transfer_ids: Vec<u64>,       // escrow transfer and any return transfers
+    pub claim_ids: Vec<u64>,
+    pub ts: u64,
+}
+
+impl StableLimitOrder {
+    #[allow(clippy::too_many_arguments)]
+    pub fn new(
+        user_id: u32,
+        pay_token_id: u32,
+        pay_amount: &Nat,
+        receive_token_id: u32,
+        receive_amount: &Nat,
+        to_address: &Address,
+        expires_at: Option<u64>,
+        request_id: u64,
+        transfer_ids: &[u64],
+        ts: u64,
+    ) -> Self {
+        Self {
+            order_id: 0, // will be set with insert into LIMIT_ORDER_MAP
+            user_id,
+            status: LimitOrderStatus::Open,
+            pay_token_id,
+            pay_amount: pay_amount.clone(),
+            receive_token_id,
+            receive_amount: receive_amount.clone(),
+            to_address: to_address.clone(),
+            expires_at,
+            request_id,
+            fill_request_id: None,
+            tx_id: None,
+            transfer_ids: transfer_ids.to_vec(),
+            claim_ids: Vec::new(),
+            ts,
+        }
+    }
+}
+
+impl Storable for StableLimitOrder {
+    fn to_bytes(&self) -> std::borrow::Cow<'_, [u8]> {
+        serde_cbor::to_vec(self).unwrap().into()
+    }
+
+    fn from_bytes(bytes: std::borrow::Cow<'_, [u8]>) -> Self {
+        serde_cbor::from_slice(&bytes).unwrap()
+    }
+
+    const BOUND: Bound = Bound::Unbounded;
+}
@@ -0,0 +1,39 @@
+use icrc_ledger_types::icrc1::account::Account;
+use std::str::FromStr;
+
+use super::stable_lp_allowance::{StableLPAllowance, StableLPAllowanceId};
+
+use crate::helpers::nat_helpers::nat_is_zero;
+use crate::stable_memory::LP_ALLOWANCE_MAP;
+
+/// allowance of spender over the LP tokens of the user. expired allowances are None
+pub fn get(token_id: u32, user_id: u32, spender: &Account, ts: u64) -> Option<StableLPAllowance> {
+    LP_ALLOWANCE_MAP
+        .with(|m| m.borrow().get(&StableLPAllowanceId(token_id, user_id, spender.to_string())))
+        .filter(|allowance| !allowance.is_expired(ts))
+}
+
+/// allowances over the LP tokens of every user as (user_id of the owner, spender, allowance). expired allowances are skipped
+pub fn get_by_token_id(token_id: u32, ts: u64) -> Vec<(u32, Account, StableLPAllowance)> {
+    LP_ALLOWANCE_MAP.with(|m| {
+        m.borrow()
+            .range(StableLPAllowanceId(token_id, 0, String::new())..)
+            .take_while(|(k, _)| k.0 == token_id)
+            .filter(|(_, v)| !v.is_expired(ts))
+            .filter_map(|(k, v)| Some((k.1, Account::from_str(&k.2).ok()?, v)))
+            .collect()
+    })
+}
+
+/// set the allowance, removing it if the amount is zero
+pub fn update(token_id: u32, user_id: u32, spender: &Account, allowance: &StableLPAllowance) {
+    let key = StableLPAllowanceId(token_id, user_id, spender.to_string());
+    LP_ALLOWANCE_MAP.with(|m| {
+        let mut map = m.borrow_mut();
+        if nat_is_zero(&allowance.amount) {
+            map.remove(&key);
+        } else {
+            map.insert(key, allowance.clone());
+        }
+    });
+}
@@ -0,0 +1,181 @@
+use candid::Nat;
+use icrc_ledger_types::icrc1::account::Account;
+use std::cell::RefCell;
+use std::str::FromStr;
+
+use super::stable_lp_block::{LPBlockOp, StableLPBlock, StableLPBlockId};
+
+use crate::stable_memory::LP_BLOCK_MAP;
+use crate::stable_user::user_map;
+
+// transactions with a created_at_time are deduplicated for TX_WINDOW. PERMITTED_DRIFT is the allowed clock skew of the caller
+pub const TX_WINDOW: u64 = 24 * 60 * 60 * 1_000_000_000;
+pub const PERMITTED_DRIFT: u64 = 2 * 60 * 1_000_000_000;
+
+thread_local! {
+    // blocks logged by a batch while it is staged. appended to LP_BLOCK_MAP only if every operation of the batch succeeds
+    static STAGED_BLOCKS: RefCell<Option<Vec<StableLPBlock>>> = const { RefCell::new(None) };
+}
+
+/// number of blocks in the ledger of the LP token
+pub fn get_log_length(token_id: u32) -> u64 {
+    LP_BLOCK_MAP.with(|m| {
+        m.borrow()
+            .range(StableLPBlockId(token_id, 0)..=StableLPBlockId(token_id, u64::MAX))
+            .next_back()
+            .map_or(0, |(k, _)| k.1 + 1)
+    })
+}
+
+/// blocks of the LP token from start, at most length blocks
+pub fn get_blocks(token_id: u32, start: u64, length: u64) -> Vec<StableLPBlock> {
+    LP_BLOCK_MAP.with(|m| {
+        m.borrow()
+            .range(StableLPBlockId(token_id, start)..=StableLPBlockId(token_id, u64::MAX))
+            .take(length as usize)
+            .map(|(_, v)| v)
+            .collect()
+    })
+}
+
+/// account of a user. the principal id of a user holding the LP tokens of a subaccount is the ICRC-1 text encoding of the account
+pub fn user_account(user_id: u32) -> Option<Account> {
+    let user = user_map::get_by_user_id(user_id)?;
+    Account::from_str(&user.principal_id).ok()
+}
+
+/// append the block to the ledger of its LP token, chaining it to the last block. returns the block index
+/// while a batch is staged, the block is queued and the index it will be appended at is returned
+pub fn insert(block: StableLPBlock) -> u64 {
+    let staged_idx = STAGED_BLOCKS.with(|s| {
+        s.borrow_mut().as_mut().map(|blocks| {
+            let token_id = block.token_id;
+            let staged_count = blocks.iter().filter(|staged_block| staged_block.token_id == token_id).count() as u64;
+            blocks.push(block.clone());
+            get_log_length(token_id) + staged_count
+        })
+    });
+    if let Some(block_idx) = staged_idx {
+        return block_idx;
+    }
+
+    LP_BLOCK_MAP.with(|m| {
+        let mut map = m.borrow_mut();
+        let token_id = block.token_id;
+        let last_block = map
+            .range(StableLPBlockId(token_id, 0)..=StableLPBlockId(token_id, u64::MAX))
+            .next_back()
+            .map(|(_, v)| v);
+        let block_idx = last_block.as_ref().map_or(0, |last_block| last_block.block_idx + 1);
+        let insert_block = StableLPBlock {
+            block_idx,
+            phash: last_block.map(|last_block| last_block.hash()),
+            ..block
+        };
+        map.insert(StableLPBlockId(token_id, block_idx), insert_block);
+        block_idx
+    })
+}
+
+/// stage the blocks logged in memory, so the operations of a batch can be discarded if one fails
+pub fn stage() {
+    STAGED_BLOCKS.with(|s| *s.borrow_mut() = Some(Vec::new()));
+}
+
+/// append the staged blocks in the order they were logged
+pub fn commit_staged() {
+    let blocks = STAGED_BLOCKS.with(|s| s.borrow_mut().take()).unwrap_or_default();
+    blocks.into_iter().for_each(|block| {
+        insert(block);
+    });
+}
+
+/// drop the staged blocks
+pub fn discard_staged() {
+    STAGED_BLOCKS.with(|s| *s.borrow_mut() = None);
+}
+
+/// block index of a duplicate of the block's transaction. only transactions with a created_at_time are deduplicated
+/// a duplicate has the same created_at_time, so only the blocks from created_at_time - PERMITTED_DRIFT are searched
+pub fn find_duplicate(block: &StableLPBlock) -> Option<u64> {
+    let created_at_time = block.created_at_time?;
+    LP_BLOCK_MAP.with(|m| {
+        m.borrow()
+            .range(StableLPBlockId(block.token_id, 0)..=StableLPBlockId(block.token_id, u64::MAX))
+            .rev()
+            .take_while(|(_, v)| v.ts.saturating_add(PERMITTED_DRIFT) >= created_at_time)
+            .find(|(_, v)| v.is_same_tx(block))
+            .map(|(k, _)| k.1)
+    })
+}
+
+/// log LP tokens minted to the user when adding liquidity
+pub fn log_mint(token_id: u32, user_id: u32, amount: &Nat, ts: u64) -> u64 {
+    insert(StableLPBlock {
+        to: user_account(user_id),
+        ..StableLPBlock::new(token_id, LPBlockOp::Mint, amount, ts)
+    })
+}
+
+/// log LP tokens burned from the user when removing liquidity
+pub fn log_burn(token_id: u32, user_id: u32, amount: &Nat, ts: u64) -> u64 {
+    insert(StableLPBlock {
+        from: user_account(user_id),
+        ..StableLPBlock::new(token_id, LPBlockOp::Burn, amount, ts)
+    })
+}
+
+/// block of a transfer of LP tokens between users, from send
+pub fn transfer_block(token_id: u32, from_user_id: u32, to_user_id: u32, amount: &Nat, ts: u64) -> StableLPBlock {
+    StableLPBlock {
+        from: user_account(from_user_id),
+        to: user_account(to_user_id),
+        ..StableLPBlock::new(token_id, LPBlockOp::Transfer, amount, ts)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const TOKEN_ID: u32 = 5;
+
+    #[test]
+    fn test_block_log() {
+        let ts = 10 * TX_WINDOW;
+        let amount = Nat::from(100_u32);
+        // users are not registered, so mint and burn blocks have no accounts
+        assert_eq!(log_mint(TOKEN_ID, 1, &amount, ts), 0);
+        let transfer = StableLPBlock {
+            created_at_time: Some(ts),
+            ..transfer_block(TOKEN_ID, 1, 2, &amount, ts + 1)
+        };
+        assert_eq!(insert(transfer.clone()), 1);
+        assert_eq!(log_burn(TOKEN_ID, 2, &amount, ts + 2), 2);
+        assert_eq!(get_log_length(TOKEN_ID), 3);
+        assert_eq!(get_log_length(TOKEN_ID + 1), 0);
+
+        // each block is chained to the hash of the previous block
+        let blocks = get_blocks(TOKEN_ID, 0, u64::MAX);
+        assert_eq!(blocks.iter().map(|block| block.block_idx).collect::<Vec<_>>(), vec![0, 1, 2]);
+        assert_eq!(blocks[0].phash, None);
+        assert_eq!(blocks[1].phash, Some(blocks[0].hash()));
+        assert_eq!(blocks[2].phash, Some(blocks[1].hash()));
+        assert_eq!(
+            get_blocks(TOKEN_ID, 1, 1).iter().map(|block| block.block_idx).collect::<Vec<_>>(),
+            vec![1]
+        );
+        assert!(get_blocks(TOKEN_ID, 3, 10).is_empty());
+
+        // only the transfer with created_at_time is deduplicated
+        assert_eq!(find_duplicate(&transfer), Some(1));
+        assert_eq!(
+            find_duplicate(&StableLPBlock {
+                created_at_time: Some(ts + 1),
+                ..transfer.clone()
+            }),
+            None
+        );
+        assert_eq!(find_duplicate(&StableLPBlock::new(TOKEN_ID, LPBlockOp::Mint, &amount, ts)), None);
+    }
+}
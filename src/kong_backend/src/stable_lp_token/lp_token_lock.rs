@@ -0,0 +1,291 @@
+use candid::{CandidType, Nat};
+use num::BigUint;
+use num_traits::One;
+use serde::{Deserialize, Serialize};
+
+use crate::helpers::nat_helpers::{nat_add, nat_divide, nat_is_zero, nat_multiply, nat_subtract, nat_zero};
+use crate::stable_pool::stable_pool::StablePool;
+
+// LP tokens can be locked for up to 1 year, earning up to 2x their share of the LP fees
+pub const MAX_LOCK_SECS: u64 = 365 * 24 * 60 * 60;
+pub const MAX_BOOST_PCT: u32 = 200;
+
+/// LP tokens that can not be removed or transferred until unlock_ts
+/// while locked, they earn boost_pct percent of their pro-rata share of the pool's LP fees
+/// - the extra share is credited to the lock as it accrues, from the pool's fee growth per boosted share, and is
+///   paid out with the next removal of liquidity, including after the lock expires
+#[derive(CandidType, Debug, Clone, Serialize, Deserialize)]
+pub struct StableLPTokenLock {
+    pub amount: Nat,
+    pub unlock_ts: u64,
+    pub boost_pct: u32, // 100 = no boost
+    #[serde(default)]
+    pub is_boosting: bool, // boost is counted in the pool's boost_supply, until the expiry of the lock is settled
+    #[serde(default)]
+    pub boost_fee_growth_0_last_x128: Nat, // pool's boost_fee_growth_0_x128 at the last settlement
+    #[serde(default)]
+    pub boost_fee_growth_1_last_x128: Nat,
+    #[serde(default)]
+    pub boost_fee_0: Nat, // boost fees earned and not yet paid out
+    #[serde(default)]
+    pub boost_fee_1: Nat,
+}
+
+pub fn q128() -> Nat {
+    Nat(BigUint::one() << 128)
+}
+
+impl StableLPTokenLock {
+    pub fn new(amount: &Nat, lock_secs: u64, ts: u64) -> Self {
+        Self {
+            amount: amount.clone(),
+            unlock_ts: ts + lock_secs * 1_000_000_000,
+            boost_pct: boost_pct(lock_secs),
+            is_boosting: false,
+            boost_fee_growth_0_last_x128: nat_zero(),
+            boost_fee_growth_1_last_x128: nat_zero(),
+            boost_fee_0: nat_zero(),
+            boost_fee_1: nat_zero(),
+        }
+    }
+
+    pub fn is_locked(&self, ts: u64) -> bool {
+        ts < self.unlock_ts
+    }
+
+    /// add amount to the lock. the lock is extended to the later unlock time and keeps the higher boost
+    /// an expired lock is replaced. boost fees earned are kept
+    pub fn add(&self, amount: &Nat, lock_secs: u64, ts: u64) -> Self {
+        let lock = StableLPTokenLock {
+            boost_fee_0: self.boost_fee_0.clone(),
+            boost_fee_1: self.boost_fee_1.clone(),
+            ..StableLPTokenLock::new(amount, lock_secs, ts)
+        };
+        if !self.is_locked(ts) {
+            return lock;
+        }
+        Self {
+            amount: nat_add(&self.amount, amount),
+            unlock_ts: std::cmp::max(self.unlock_ts, lock.unlock_ts),
+            boost_pct: std::cmp::max(self.boost_pct, lock.boost_pct),
+            ..lock
+        }
+    }
+
+    /// extra weight of the locked amount in the LP fee share while boosting, amount * (boost_pct - 100) / 100
+    pub fn boost_amount(&self) -> Nat {
+        if !self.is_boosting {
+            return nat_zero();
+        }
+        let boost_pct = Nat::from(self.boost_pct.saturating_sub(100));
+        nat_divide(&nat_multiply(&self.amount, &boost_pct), &Nat::from(100_u32)).unwrap_or(nat_zero())
+    }
+
+    /// credit the boost fees earned since the last settlement at the pool's fee growth per boosted share
+    pub fn settle(&mut self, pool: &StablePool) {
+        let boost_amount = self.boost_amount();
+        let earned = |fee_growth: &Nat, fee_growth_last: &Nat| {
+            let fee_growth_delta = nat_subtract(fee_growth, fee_growth_last).unwrap_or(nat_zero());
+            nat_divide(&nat_multiply(&boost_amount, &fee_growth_delta), &q128()).unwrap_or(nat_zero())
+        };
+        self.boost_fee_0 = nat_add(
+            &self.boost_fee_0,
+            &earned(&pool.boost_fee_growth_0_x128, &self.boost_fee_growth_0_last_x128),
+        );
+        self.boost_fee_1 = nat_add(
+            &self.boost_fee_1,
+            &earned(&pool.boost_fee_growth_1_x128, &self.boost_fee_growth_1_last_x128),
+        );
+        self.boost_fee_growth_0_last_x128 = pool.boost_fee_growth_0_x128.clone();
+        self.boost_fee_growth_1_last_x128 = pool.boost_fee_growth_1_x128.clone();
+    }
+
+    /// settle the lock and stop its boost once it has expired. returns true if the lock changed
+    /// the pool's boost_supply is reduced by the boost of the lock
+    pub fn settle_expiry(&mut self, pool: &mut StablePool, ts: u64) -> bool {
+        if !self.is_boosting || self.is_locked(ts) {
+            return false;
+        }
+        self.settle(pool);
+        pool.boost_supply = nat_subtract(&pool.boost_supply, &self.boost_amount()).unwrap_or(nat_zero());
+        self.is_boosting = false;
+        true
+    }
+}
+
+/// lock amount of LP tokens for lock_secs, adding to the existing lock if any
+/// the existing lock is settled first and the pool's boost_supply is updated with the change of boost
+pub fn add_lock(pool: &mut StablePool, lock: Option<&StableLPTokenLock>, amount: &Nat, lock_secs: u64, ts: u64) -> StableLPTokenLock {
+    let (mut new_lock, old_boost_amount) = match lock {
+        Some(lock) => {
+            let mut lock = lock.clone();
+            lock.settle(pool);
+            (lock.add(amount, lock_secs, ts), lock.boost_amount())
+        }
+        None => (StableLPTokenLock::new(amount, lock_secs, ts), nat_zero()),
+    };
+    new_lock.is_boosting = new_lock.boost_pct > 100;
+    new_lock.boost_fee_growth_0_last_x128 = pool.boost_fee_growth_0_x128.clone();
+    new_lock.boost_fee_growth_1_last_x128 = pool.boost_fee_growth_1_x128.clone();
+    let boost_supply = nat_subtract(&pool.boost_supply, &old_boost_amount).unwrap_or(nat_zero());
+    pool.boost_supply = nat_add(&boost_supply, &new_lock.boost_amount());
+    if new_lock.is_boosting {
+        pool.boost_expiry_ts = Some(pool.boost_expiry_ts.map_or(new_lock.unlock_ts, |ts| ts.min(new_lock.unlock_ts)));
+    }
+    new_lock
+}
+
+/// settle the lock, take out its boost fees and reduce the locked amount to at most amount
+/// the boost fees are moved to the pool's LP fees so they are paid out with the LP fees of the removal
+pub fn remove_from_lock(pool: &mut StablePool, lock: &StableLPTokenLock, amount: &Nat) -> StableLPTokenLock {
+    let mut lock = lock.clone();
+    lock.settle(pool);
+    let old_boost_amount = lock.boost_amount();
+    lock.amount = std::cmp::min(lock.amount, amount.clone());
+    let boost_supply = nat_subtract(&pool.boost_supply, &old_boost_amount).unwrap_or(nat_zero());
+    pool.boost_supply = nat_add(&boost_supply, &lock.boost_amount());
+
+    let boost_fee_0 = std::mem::replace(&mut lock.boost_fee_0, nat_zero()).min(pool.boost_fee_0.clone());
+    pool.boost_fee_0 = nat_subtract(&pool.boost_fee_0, &boost_fee_0).unwrap_or(nat_zero());
+    pool.lp_fee_0 = nat_add(&pool.lp_fee_0, &boost_fee_0);
+    let boost_fee_1 = std::mem::replace(&mut lock.boost_fee_1, nat_zero()).min(pool.boost_fee_1.clone());
+    pool.boost_fee_1 = nat_subtract(&pool.boost_fee_1, &boost_fee_1).unwrap_or(nat_zero());
+    pool.lp_fee_1 = nat_add(&pool.lp_fee_1, &boost_fee_1);
+    lock
+}
+
+/// boost fees of the lock if it was settled now, capped at the pool's boost fees
+pub fn boost_fees(pool: &StablePool, lock: Option<&StableLPTokenLock>) -> (Nat, Nat) {
+    let Some(lock) = lock else {
+        return (nat_zero(), nat_zero());
+    };
+    let mut lock = lock.clone();
+    lock.settle(pool);
+    (
+        std::cmp::min(lock.boost_fee_0, pool.boost_fee_0.clone()),
+        std::cmp::min(lock.boost_fee_1, pool.boost_fee_1.clone()),
+    )
+}
+
+/// credit the boosted share of the LP fee of a swap to the pool's locks. returns the LP fee left for all LPs
+/// boost_fee = lp_fee * boost_supply / (lp_total_supply + boost_supply)
+pub fn credit_boost_fee(pool: &mut StablePool, lp_fee: &Nat, lp_total_supply: &Nat, is_token_0: bool) -> Nat {
+    if nat_is_zero(&pool.boost_supply) {
+        return lp_fee.clone();
+    }
+    let numerator = nat_multiply(lp_fee, &pool.boost_supply);
+    let boost_fee = nat_divide(&numerator, &nat_add(lp_total_supply, &pool.boost_supply)).unwrap_or(nat_zero());
+    let fee_growth = nat_divide(&nat_multiply(&boost_fee, &q128()), &pool.boost_supply).unwrap_or(nat_zero());
+    if is_token_0 {
+        pool.boost_fee_0 = nat_add(&pool.boost_fee_0, &boost_fee);
+        pool.boost_fee_growth_0_x128 = nat_add(&pool.boost_fee_growth_0_x128, &fee_growth);
+    } else {
+        pool.boost_fee_1 = nat_add(&pool.boost_fee_1, &boost_fee);
+        pool.boost_fee_growth_1_x128 = nat_add(&pool.boost_fee_growth_1_x128, &fee_growth);
+    }
+    nat_subtract(lp_fee, &boost_fee).unwrap_or(nat_zero())
+}
+
+/// boost increases linearly with the lock period, from 100% to MAX_BOOST_PCT at MAX_LOCK_SECS
+pub fn boost_pct(lock_secs: u64) -> u32 {
+    let lock_secs = std::cmp::min(lock_secs, MAX_LOCK_SECS);
+    100 + ((MAX_BOOST_PCT - 100) as u64 * lock_secs / MAX_LOCK_SECS) as u32
+}
+
+pub fn check_lock_secs(lock_secs: u64) -> Result<(), String> {
+    if lock_secs == 0 {
+        Err("Lock period must be greater than 0 secs".to_string())?
+    }
+    if lock_secs > MAX_LOCK_SECS {
+        Err(format!("Lock period must be at most {} secs", MAX_LOCK_SECS))?
+    }
+    Ok(())
+}
+
+/// amount of LP tokens that can be removed or transferred
+pub fn unlocked_amount(amount: &Nat, lock: Option<&StableLPTokenLock>, ts: u64) -> Nat {
+    match lock {
+        Some(lock) if lock.is_locked(ts) => nat_subtract(amount, &lock.amount).unwrap_or(nat_zero()),
+        _ => amount.clone(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::stable_pool::pool_type::PoolType;
+
+    const SECS: u64 = 1_000_000_000;
+
+    #[test]
+    fn test_boost_pct() {
+        assert_eq!(boost_pct(0), 100);
+        assert_eq!(boost_pct(MAX_LOCK_SECS / 2), 150);
+        assert_eq!(boost_pct(MAX_LOCK_SECS), 200);
+        assert_eq!(boost_pct(MAX_LOCK_SECS * 2), 200);
+    }
+
+    #[test]
+    fn test_lock() {
+        let mut pool = StablePool::new(1, 2, 30, 0, 3, PoolType::ConstantProduct, None);
+        let mut lock = add_lock(&mut pool, None, &Nat::from(1_000_u32), MAX_LOCK_SECS / 2, 0);
+        assert_eq!(lock.boost_amount(), Nat::from(500_u32));
+        assert_eq!(pool.boost_supply, Nat::from(500_u32));
+        assert_eq!(unlocked_amount(&Nat::from(1_500_u32), Some(&lock), 0), Nat::from(500_u32));
+
+        // expired lock stops boosting once settled and is fully unlocked
+        assert!(!lock.settle_expiry(&mut pool, lock.unlock_ts - 1));
+        assert!(lock.settle_expiry(&mut pool, lock.unlock_ts));
+        assert_eq!(lock.boost_amount(), nat_zero());
+        assert!(nat_is_zero(&pool.boost_supply));
+        assert_eq!(
+            unlocked_amount(&Nat::from(1_500_u32), Some(&lock), lock.unlock_ts),
+            Nat::from(1_500_u32)
+        );
+
+        // adding to an active lock keeps the later unlock time and higher boost
+        let added = lock.add(&Nat::from(1_000_u32), 60, 10 * SECS);
+        assert_eq!(added.amount, Nat::from(2_000_u32));
+        assert_eq!(added.unlock_ts, lock.unlock_ts);
+        assert_eq!(added.boost_pct, 150);
+    }
+
+    #[test]
+    fn test_boost_fee_split() {
+        // user A locks 1,024 LP tokens for the max boost, user B has 1,024 unlocked
+        let mut pool = StablePool::new(1, 2, 30, 0, 3, PoolType::ConstantProduct, None);
+        let lp_total_supply = Nat::from(2_048_u32);
+        let mut lock = add_lock(&mut pool, None, &Nat::from(1_024_u32), MAX_LOCK_SECS, 0);
+        assert_eq!(pool.boost_supply, Nat::from(1_024_u32));
+
+        // swap while locked. boost_fee = 384 * 1,024 / (2,048 + 1,024)
+        let lp_fee = credit_boost_fee(&mut pool, &Nat::from(384_u32), &lp_total_supply, true);
+        pool.lp_fee_0 = nat_add(&pool.lp_fee_0, &lp_fee);
+        assert_eq!(lp_fee, Nat::from(256_u32));
+        assert_eq!(pool.boost_fee_0, Nat::from(128_u32));
+
+        // unlock, then swap again. the expired lock no longer earns a boost
+        assert!(lock.settle_expiry(&mut pool, lock.unlock_ts));
+        assert_eq!(lock.boost_fee_0, Nat::from(128_u32));
+        let lp_fee = credit_boost_fee(&mut pool, &Nat::from(384_u32), &lp_total_supply, true);
+        pool.lp_fee_0 = nat_add(&pool.lp_fee_0, &lp_fee);
+        assert_eq!(pool.lp_fee_0, Nat::from(640_u32));
+        assert_eq!(pool.boost_fee_0, Nat::from(128_u32));
+
+        // A removes all LP tokens. share of the LP fees by plain total supply, plus the boost earned while locked
+        let lp_share = nat_divide(&nat_multiply(&pool.lp_fee_0, &Nat::from(1_024_u32)), &lp_total_supply).unwrap();
+        let (boost_fee_0, boost_fee_1) = boost_fees(&pool, Some(&lock));
+        assert_eq!(lp_share, Nat::from(320_u32));
+        assert_eq!(boost_fee_0, Nat::from(128_u32));
+        assert!(nat_is_zero(&boost_fee_1));
+        let payout_a = nat_add(&lp_share, &boost_fee_0);
+        let lock = remove_from_lock(&mut pool, &lock, &nat_zero());
+        assert!(nat_is_zero(&lock.boost_fee_0));
+        assert!(nat_is_zero(&pool.boost_fee_0));
+        pool.lp_fee_0 = nat_subtract(&pool.lp_fee_0, &payout_a).unwrap();
+        assert_eq!(payout_a, Nat::from(448_u32));
+
+        // B is left with its plain share of the LP fees
+        assert_eq!(pool.lp_fee_0, Nat::from(320_u32));
+    }
+}
@@ -1,13 +1,24 @@
 use candid::Nat;
+use std::cell::RefCell;
+use std::collections::BTreeMap;
 
 use super::stable_lp_token::{StableLPToken, StableLPTokenId};
 
+use crate::certified::certified_tree;
 use crate::helpers::nat_helpers::{nat_add, nat_zero};
+use crate::ic::get_time::get_time;
 use crate::ic::logging::error_log;
 use crate::stable_kong_settings::kong_settings_map;
 use crate::stable_memory::LP_TOKEN_MAP;
+use crate::stable_pool::{pool_map, stable_pool::StablePool};
+use crate::stable_reward_campaign::reward_campaign_map;
 use crate::stable_user::user_map;
 
+thread_local! {
+    // LP tokens inserted or updated by a batch while it is staged. written to LP_TOKEN_MAP only if every operation of the batch succeeds
+    static STAGED_LP_TOKENS: RefCell<Option<BTreeMap<u64, StableLPToken>>> = const { RefCell::new(None) };
+}
+
 /// get lp_token of the caller
 pub fn get_by_token_id(token_id: u32) -> Option<StableLPToken> {
     let user_id = user_map::get_by_caller().ok().flatten()?.user_id;
@@ -16,36 +27,92 @@ pub fn get_by_token_id(token_id: u32) -> Option<StableLPToken> {
 
 /// get lp_token for specific user and token
 pub fn get_by_token_id_by_user_id(token_id: u32, user_id: u32) -> Option<StableLPToken> {
-    LP_TOKEN_MAP.with(|m| {
-        m.borrow().iter().find_map(|(_, v)| {
-            if v.user_id == user_id && v.token_id == token_id {
-                return Some(v);
-            }
-            None
-        })
-    })
+    find(|v| v.user_id == user_id && v.token_id == token_id).into_iter().next()
 }
 
 /// get lp_token for specific user
 pub fn get_by_user_id(user_id: u32) -> Vec<StableLPToken> {
-    LP_TOKEN_MAP.with(|m| {
-        m.borrow()
-            .iter()
-            .filter_map(|(_, v)| if v.user_id == user_id { Some(v) } else { None })
-            .collect()
-    })
+    find(|v| v.user_id == user_id)
+}
+
+/// get lp_token of every user holding the token
+pub fn get_all_by_token_id(token_id: u32) -> Vec<StableLPToken> {
+    find(|v| v.token_id == token_id)
 }
 
 pub fn get_total_supply(token_id: u32) -> Nat {
+    get_all_by_token_id(token_id)
+        .iter()
+        .fold(nat_zero(), |acc, v| nat_add(&acc, &v.amount))
+}
+
+// LP tokens matching the filter, including the LP tokens staged by a batch
+fn find(filter: impl Fn(&StableLPToken) -> bool) -> Vec<StableLPToken> {
+    let staged = STAGED_LP_TOKENS.with(|s| s.borrow().clone()).unwrap_or_default();
     LP_TOKEN_MAP.with(|m| {
         m.borrow()
             .iter()
-            .filter_map(|(_, v)| if v.token_id == token_id { Some(v.amount) } else { None })
-            .fold(nat_zero(), |acc, x| nat_add(&acc, &x))
+            .filter(|(k, _)| !staged.contains_key(&k.0))
+            .map(|(_, v)| v)
+            .chain(staged.values().cloned())
+            .filter(|v| filter(v))
+            .collect()
     })
 }
 
+/// settle the locks of the pool's LP token that expired by ts. returns the pool and the LP tokens whose locks were settled,
+/// for the caller to save together with the pool
+/// the LP tokens are only scanned once the earliest unlock time of the pool's boosting locks has passed
+/// called before the pool's LP fees or locks change, so expired locks stop earning boost fees
+pub fn settle_expired_locks(mut pool: StablePool, ts: u64) -> (StablePool, Vec<StableLPToken>) {
+    if !pool.boost_expiry_ts.is_some_and(|boost_expiry_ts| boost_expiry_ts <= ts) {
+        return (pool, Vec::new());
+    }
+    let lp_tokens = find(|v| v.token_id == pool.lp_token_id && v.lock.as_ref().is_some_and(|lock| lock.is_boosting));
+    let mut settled_lp_tokens = Vec::new();
+    let mut boost_expiry_ts: Option<u64> = None;
+    for mut lp_token in lp_tokens {
+        let Some(ref mut lock) = lp_token.lock else {
+            continue;
+        };
+        if lock.settle_expiry(&mut pool, ts) {
+            settled_lp_tokens.push(lp_token);
+        } else {
+            boost_expiry_ts = Some(boost_expiry_ts.map_or(lock.unlock_ts, |expiry_ts| expiry_ts.min(lock.unlock_ts)));
+        }
+    }
+    pool.boost_expiry_ts = boost_expiry_ts;
+    (pool, settled_lp_tokens)
+}
+
+/// settle the locks of the pool's LP token that expired by ts and save the pool and LP tokens if they changed
+pub fn save_expired_locks(pool: StablePool, ts: u64) -> StablePool {
+    let boost_expired = pool.boost_expiry_ts.is_some_and(|boost_expiry_ts| boost_expiry_ts <= ts);
+    let (pool, lp_tokens) = settle_expired_locks(pool, ts);
+    if boost_expired {
+        for lp_token in &lp_tokens {
+            update(lp_token);
+        }
+        pool_map::update(&pool);
+    }
+    pool
+}
+
 pub fn insert(lp_token: &StableLPToken) -> Result<u64, String> {
+    // new LP tokens of a staged batch get a provisional id from u64::MAX down, so no LP token id is allocated unless the
+    // batch is committed
+    let staged_lp_token_id = STAGED_LP_TOKENS.with(|s| s.borrow().as_ref().map(|lp_tokens| u64::MAX - lp_tokens.len() as u64));
+    if let Some(lp_token_id) = staged_lp_token_id {
+        stage_update(&StableLPToken {
+            lp_token_id,
+            ..lp_token.clone()
+        });
+        return Ok(lp_token_id);
+    }
+
+    // new LP tokens start earning rewards from now
+    reward_campaign_map::update_rewards(lp_token.token_id, lp_token.user_id, &nat_zero(), get_time());
+
     let insert_lp_token = LP_TOKEN_MAP.with(|m| {
         let mut map = m.borrow_mut();
         let lp_token_id = kong_settings_map::inc_lp_token_map_idx();
@@ -56,16 +123,61 @@ pub fn insert(lp_token: &StableLPToken) -> Result<u64, String> {
         map.insert(StableLPTokenId(lp_token_id), insert_lp_token.clone());
         insert_lp_token
     });
+    certified_tree::update_lp_token(&insert_lp_token);
 
     let _ = archive_to_kong_data(&insert_lp_token);
     Ok(insert_lp_token.lp_token_id)
 }
 
 pub fn update(lp_token: &StableLPToken) {
+    if stage_update(lp_token) {
+        return;
+    }
+
+    // settle the rewards earned by the LP token amount before the change
+    let amount = LP_TOKEN_MAP
+        .with(|m| m.borrow().get(&StableLPTokenId(lp_token.lp_token_id)))
+        .map_or_else(nat_zero, |lp_token| lp_token.amount);
+    reward_campaign_map::update_rewards(lp_token.token_id, lp_token.user_id, &amount, get_time());
+
     LP_TOKEN_MAP.with(|m| m.borrow_mut().insert(StableLPTokenId(lp_token.lp_token_id), lp_token.clone()));
+    certified_tree::update_lp_token(lp_token);
     _ = archive_to_kong_data(lp_token);
 }
 
+/// stage the LP token inserts and updates in memory, so the operations of a batch can be discarded if one fails
+pub fn stage() {
+    STAGED_LP_TOKENS.with(|s| *s.borrow_mut() = Some(BTreeMap::new()));
+}
+
+/// write the staged LP tokens. new LP tokens are inserted with the next LP token ids
+pub fn commit_staged() {
+    let lp_tokens = STAGED_LP_TOKENS.with(|s| s.borrow_mut().take()).unwrap_or_default();
+    for lp_token in lp_tokens.values() {
+        if LP_TOKEN_MAP.with(|m| m.borrow().contains_key(&StableLPTokenId(lp_token.lp_token_id))) {
+            update(lp_token);
+        } else {
+            _ = insert(lp_token);
+        }
+    }
+}
+
+/// drop the staged LP tokens
+pub fn discard_staged() {
+    STAGED_LP_TOKENS.with(|s| *s.borrow_mut() = None);
+}
+
+// returns true if the LP token update was staged
+fn stage_update(lp_token: &StableLPToken) -> bool {
+    STAGED_LP_TOKENS.with(|s| match s.borrow_mut().as_mut() {
+        Some(lp_tokens) => {
+            lp_tokens.insert(lp_token.lp_token_id, lp_token.clone());
+            true
+        }
+        None => false,
+    })
+}
+
 pub fn archive_to_kong_data(lp_token: &StableLPToken) -> Result<(), String> {
     if !kong_settings_map::get().archive_to_kong_data {
         return Ok(());
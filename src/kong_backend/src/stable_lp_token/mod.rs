@@ -1,4 +1,10 @@
+pub mod lp_allowance_map;
+pub mod lp_block_map;
+pub mod lp_token_lock;
 pub mod lp_token_map;
+pub mod stable_lp_allowance;
+pub mod stable_lp_block;
+pub mod stable_lp_ledger_wasm;
 #[allow(clippy::module_inception)]
 pub mod stable_lp_token;
 pub mod transfer;
@@ -0,0 +1,45 @@
+use candid::{CandidType, Nat};
+use ic_stable_structures::{storable::Bound, Storable};
+use serde::{Deserialize, Serialize};
+
+/// (token_id of the LP token, user_id of the owner, ICRC-1 text encoding of the spender's account)
+#[derive(CandidType, Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+pub struct StableLPAllowanceId(pub u32, pub u32, pub String);
+
+impl Storable for StableLPAllowanceId {
+    fn to_bytes(&self) -> std::borrow::Cow<'_, [u8]> {
+        serde_cbor::to_vec(self).unwrap().into()
+    }
+
+    fn from_bytes(bytes: std::borrow::Cow<'_, [u8]>) -> Self {
+        serde_cbor::from_slice(&bytes).unwrap()
+    }
+
+    const BOUND: Bound = Bound::Unbounded;
+}
+
+/// ICRC-2 allowance of a spender over a user's LP tokens
+#[derive(CandidType, Debug, Clone, Serialize, Deserialize)]
+pub struct StableLPAllowance {
+    pub amount: Nat,
+    pub expires_at: Option<u64>,
+    pub ts: u64,
+}
+
+impl StableLPAllowance {
+    pub fn is_expired(&self, ts: u64) -> bool {
+        self.expires_at.is_some_and(|expires_at| expires_at <= ts)
+    }
+}
+
+impl Storable for StableLPAllowance {
+    fn to_bytes(&self) -> std::borrow::Cow<'_, [u8]> {
+        serde_cbor::to_vec(self).unwrap().into()
+    }
+
+    fn from_bytes(bytes: std::borrow::Cow<'_, [u8]>) -> Self {
+        serde_cbor::from_slice(&bytes).unwrap()
+    }
+
+    const BOUND: Bound = Bound::Unbounded;
+}
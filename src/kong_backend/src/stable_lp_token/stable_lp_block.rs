@@ -0,0 +1,188 @@
+use candid::{CandidType, Nat};
+use ic_stable_structures::{storable::Bound, Storable};
+use icrc_ledger_types::icrc::generic_value::{ICRC3Map, ICRC3Value};
+use icrc_ledger_types::icrc1::account::Account;
+use serde::{Deserialize, Serialize};
+use serde_bytes::ByteBuf;
+
+/// (token_id of the LP token, block index)
+#[derive(CandidType, Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+pub struct StableLPBlockId(pub u32, pub u64);
+
+impl Storable for StableLPBlockId {
+    fn to_bytes(&self) -> std::borrow::Cow<'_, [u8]> {
+        serde_cbor::to_vec(self).unwrap().into()
+    }
+
+    fn from_bytes(bytes: std::borrow::Cow<'_, [u8]>) -> Self {
+        serde_cbor::from_slice(&bytes).unwrap()
+    }
+
+    const BOUND: Bound = Bound::Unbounded;
+}
+
+#[derive(CandidType, Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum LPBlockOp {
+    Mint,
+    Burn,
+    Transfer,
+    Approve,
+}
+
+/// ICRC-3 block of an LP token's ledger. every LP mint, burn, transfer and approval is logged as a block
+/// blocks of each LP token are chained by the hash of their parent block
+#[derive(CandidType, Debug, Clone, Serialize, Deserialize)]
+pub struct StableLPBlock {
+    pub token_id: u32,
+    pub block_idx: u64,
+    pub op: LPBlockOp,
+    pub from: Option<Account>,
+    pub to: Option<Account>,
+    pub spender: Option<Account>,
+    pub amount: Nat,
+    pub expires_at: Option<u64>, // approvals only
+    #[serde(default)]
+    pub expected_allowance: Option<Nat>, // approvals only
+    pub memo: Option<Vec<u8>>,
+    #[serde(default)]
+    pub created_at_time: Option<u64>, // created_at_time of the transaction, used to deduplicate it
+    pub phash: Option<Vec<u8>>, // hash of the parent block, None for the first block
+    pub ts: u64,
+}
+
+fn account_value(account: &Account) -> ICRC3Value {
+    let mut value = vec![ICRC3Value::Blob(ByteBuf::from(account.owner.as_slice().to_vec()))];
+    if let Some(subaccount) = account.subaccount {
+        value.push(ICRC3Value::Blob(ByteBuf::from(subaccount.to_vec())));
+    }
+    ICRC3Value::Array(value)
+}
+
+impl StableLPBlock {
+    pub fn new(token_id: u32, op: LPBlockOp, amount: &Nat, ts: u64) -> Self {
+        Self {
+            token_id,
+            block_idx: 0,
+            op,
+            from: None,
+            to: None,
+            spender: None,
+            amount: amount.clone(),
+            expires_at: None,
+            expected_allowance: None,
+            memo: None,
+            created_at_time: None,
+            phash: None,
+            ts,
+        }
+    }
+
+    /// ICRC-3 block type. transfers by a spender are ICRC-2 transfer_from blocks
+    pub fn btype(&self) -> &'static str {
+        match self.op {
+            LPBlockOp::Mint => "1mint",
+            LPBlockOp::Burn => "1burn",
+            LPBlockOp::Transfer if self.spender.is_some() => "2xfer",
+            LPBlockOp::Transfer => "1xfer",
+            LPBlockOp::Approve => "2approve",
+        }
+    }
+
+    /// true if the block has the same transaction as block. blocks of the same transaction are duplicates
+    pub fn is_same_tx(&self, block: &StableLPBlock) -> bool {
+        self.token_id == block.token_id
+            && self.op == block.op
+            && self.from == block.from
+            && self.to == block.to
+            && self.spender == block.spender
+            && self.amount == block.amount
+            && self.expires_at == block.expires_at
+            && self.expected_allowance == block.expected_allowance
+            && self.memo == block.memo
+            && self.created_at_time == block.created_at_time
+    }
+
+    pub fn to_icrc3_value(&self) -> ICRC3Value {
+        let mut tx = ICRC3Map::new();
+        tx.insert("amt".to_string(), ICRC3Value::Nat(self.amount.clone()));
+        if let Some(from) = &self.from {
+            tx.insert("from".to_string(), account_value(from));
+        }
+        if let Some(to) = &self.to {
+            tx.insert("to".to_string(), account_value(to));
+        }
+        if let Some(spender) = &self.spender {
+            tx.insert("spender".to_string(), account_value(spender));
+        }
+        if let Some(expires_at) = self.expires_at {
+            tx.insert("expires_at".to_string(), ICRC3Value::Nat(Nat::from(expires_at)));
+        }
+        if let Some(expected_allowance) = &self.expected_allowance {
+            tx.insert("expected_allowance".to_string(), ICRC3Value::Nat(expected_allowance.clone()));
+        }
+        if let Some(memo) = &self.memo {
+            tx.insert("memo".to_string(), ICRC3Value::Blob(ByteBuf::from(memo.clone())));
+        }
+        if let Some(created_at_time) = self.created_at_time {
+            tx.insert("ts".to_string(), ICRC3Value::Nat(Nat::from(created_at_time)));
+        }
+
+        let mut block = ICRC3Map::new();
+        block.insert("btype".to_string(), ICRC3Value::Text(self.btype().to_string()));
+        block.insert("ts".to_string(), ICRC3Value::Nat(Nat::from(self.ts)));
+        if let Some(phash) = &self.phash {
+            block.insert("phash".to_string(), ICRC3Value::Blob(ByteBuf::from(phash.clone())));
+        }
+        block.insert("tx".to_string(), ICRC3Value::Map(tx));
+        ICRC3Value::Map(block)
+    }
+
+    pub fn hash(&self) -> Vec<u8> {
+        self.to_icrc3_value().hash().to_vec()
+    }
+}
+
+impl Storable for StableLPBlock {
+    fn to_bytes(&self) -> std::borrow::Cow<'_, [u8]> {
+        serde_cbor::to_vec(self).unwrap().into()
+    }
+
+    fn from_bytes(bytes: std::borrow::Cow<'_, [u8]>) -> Self {
+        serde_cbor::from_slice(&bytes).unwrap()
+    }
+
+    const BOUND: Bound = Bound::Unbounded;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use candid::Principal;
+
+    #[test]
+    fn test_to_icrc3_value() {
+        let to = Account::from(Principal::anonymous());
+        let block = StableLPBlock {
+            to: Some(to),
+            ..StableLPBlock::new(1, LPBlockOp::Mint, &Nat::from(100_u32), 1)
+        };
+        let ICRC3Value::Map(value) = block.to_icrc3_value() else {
+            panic!("block is not a map");
+        };
+        assert_eq!(value.get("btype"), Some(&ICRC3Value::Text("1mint".to_string())));
+        assert_eq!(value.get("phash"), None);
+        let Some(ICRC3Value::Map(tx)) = value.get("tx") else {
+            panic!("tx is not a map");
+        };
+        assert_eq!(tx.get("amt"), Some(&ICRC3Value::Nat(Nat::from(100_u32))));
+        assert_eq!(tx.get("to"), Some(&account_value(&to)));
+
+        // next block is chained to the hash of the block
+        let next_block = StableLPBlock {
+            block_idx: 1,
+            phash: Some(block.hash()),
+            ..block.clone()
+        };
+        assert_ne!(next_block.hash(), block.hash());
+    }
+}
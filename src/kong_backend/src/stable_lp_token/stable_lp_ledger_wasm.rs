@@ -0,0 +1,18 @@
+use ic_stable_structures::{storable::Bound, Storable};
+use std::borrow::Cow;
+
+/// wasm module of the kong_lp_ledger canister, installed in the ledger created for each LP token
+#[derive(Debug, Clone, Default)]
+pub struct StableLPLedgerWasm(pub Vec<u8>);
+
+impl Storable for StableLPLedgerWasm {
+    fn to_bytes(&self) -> Cow<'_, [u8]> {
+        Cow::Borrowed(&self.0)
+    }
+
+    fn from_bytes(bytes: Cow<'_, [u8]>) -> Self {
+        Self(bytes.into_owned())
+    }
+
+    const BOUND: Bound = Bound::Unbounded;
+}
@@ -2,6 +2,8 @@ use candid::{CandidType, Nat};
 use ic_stable_structures::{storable::Bound, Storable};
 use serde::{Deserialize, Serialize};
 
+use super::lp_token_lock::{unlocked_amount, StableLPTokenLock};
+
 #[derive(CandidType, Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
 pub struct StableLPTokenId(pub u64);
 
@@ -24,6 +26,8 @@ pub struct StableLPToken {
     pub token_id: u32,    // token id of the token
     pub amount: Nat,      // amount the user holds of the token
     pub ts: u64,          // timestamp of the last token update
+    #[serde(default)]
+    pub lock: Option<StableLPTokenLock>, // locked part of amount
 }
 
 impl StableLPToken {
@@ -34,8 +38,14 @@ impl StableLPToken {
             token_id,
             amount,
             ts,
+            lock: None,
         }
     }
+
+    /// amount of LP tokens that can be removed or transferred
+    pub fn unlocked_amount(&self, ts: u64) -> Nat {
+        unlocked_amount(&self.amount, self.lock.as_ref(), ts)
+    }
 }
 
 impl Storable for StableLPToken {
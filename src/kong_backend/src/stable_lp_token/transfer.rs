@@ -1,11 +1,13 @@
 use candid::Nat;
 
-use super::lp_token_map;
+use super::lp_block_map;
 use super::lp_token_map::{get_by_token_id_by_user_id, insert, update};
+use super::stable_lp_block::StableLPBlock;
 use super::stable_lp_token::StableLPToken;
 
 use crate::helpers::nat_helpers::{nat_add, nat_subtract};
 use crate::ic::get_time::get_time;
+use crate::stable_user::user_map;
 
 /// transfer LP token from caller to another user
 ///
@@ -18,13 +20,33 @@ use crate::ic::get_time::get_time;
 /// StableLPToken - updated LP token of the caller
 /// Err - if LP token not found or not enough LP token
 pub fn transfer(token_id: u32, to_user_id: u32, amount: &Nat) -> Result<StableLPToken, String> {
-    let ts = get_time();
+    let from_user_id = user_map::get_by_caller().ok().flatten().ok_or("Not enough LP token")?.user_id;
+    let block = lp_block_map::transfer_block(token_id, from_user_id, to_user_id, amount, get_time());
+    transfer_from_user_id(from_user_id, to_user_id, block).map(|(from_user, _)| from_user)
+}
+
+/// transfer LP token between users and log the transfer in the LP token's ledger
+///
+/// # Arguments
+/// block - transfer block of the LP token's ledger with the LP token, amount, accounts and time of the transfer
+///
+/// # Returns
+/// (StableLPToken, u64) - updated LP token of from_user_id and the block index of the transfer
+/// Err - if LP token not found or not enough unlocked LP token
+pub fn transfer_from_user_id(from_user_id: u32, to_user_id: u32, block: StableLPBlock) -> Result<(StableLPToken, u64), String> {
+    let token_id = block.token_id;
+    let amount = &block.amount;
+    let ts = block.ts;
 
-    let from_user = match lp_token_map::get_by_token_id(token_id) {
+    let from_user = match get_by_token_id_by_user_id(token_id, from_user_id) {
         Some(from_user_lp_token) => {
             if from_user_lp_token.amount < *amount {
                 return Err("Not enough LP token".to_string());
             }
+            // locked LP tokens are non-transferable
+            if from_user_lp_token.unlocked_amount(ts) < *amount {
+                return Err("Not enough unlocked LP token".to_string());
+            }
             let amount = nat_subtract(&from_user_lp_token.amount, amount).ok_or("Error calculating new user balance")?;
             StableLPToken {
                 amount,
@@ -47,5 +69,7 @@ pub fn transfer(token_id: u32, to_user_id: u32, amount: &Nat) -> Result<StableLP
         insert(&StableLPToken::new(to_user_id, token_id, amount.clone(), ts))?;
     }
 
-    Ok(from_user)
+    let block_idx = lp_block_map::insert(block);
+
+    Ok((from_user, block_idx))
 }
@@ -4,15 +4,27 @@ use std::cell::RefCell;
 use std::collections::BTreeMap;
 
 use crate::stable_claim::stable_claim::{StableClaim, StableClaimId};
+use crate::stable_deposit::stable_deposit::{StableDeposit, StableDepositId};
 use crate::stable_kong_settings::stable_kong_settings::StableKongSettings;
+use crate::stable_limit_order::stable_limit_order::{StableLimitOrder, StableLimitOrderId};
+use crate::stable_lp_token::stable_lp_allowance::{StableLPAllowance, StableLPAllowanceId};
+use crate::stable_lp_token::stable_lp_block::{StableLPBlock, StableLPBlockId};
+use crate::stable_lp_token::stable_lp_ledger_wasm::StableLPLedgerWasm;
 use crate::stable_lp_token::stable_lp_token::{StableLPToken, StableLPTokenId};
+use crate::stable_pool::concentrated_pool::{ConcentratedPosition, StablePositionId, StableTickId, TickInfo};
 use crate::stable_pool::stable_pool::{StablePool, StablePoolId};
+use crate::stable_pool_observation::stable_pool_observation::{StablePoolObservation, StablePoolObservationId};
 use crate::stable_request::stable_request::{StableRequest, StableRequestId};
+use crate::stable_reward_campaign::stable_reward_campaign::{
+    StableRewardCampaign, StableRewardCampaignId, StableUserReward, StableUserRewardId,
+};
 use crate::stable_token::stable_token::{StableToken, StableTokenId};
 use crate::stable_transfer::stable_transfer::{StableTransfer, StableTransferId};
 use crate::stable_tx::stable_tx::{StableTx, StableTxId};
 use crate::stable_user::banned_user_map::BannedUser;
+use crate::stable_user::stable_referral_reward::{StableReferralReward, StableReferralRewardId};
 use crate::stable_user::stable_user::{StableUser, StableUserId};
+use crate::stable_user::stable_user_volume::{StableUserVolume, StableUserVolumeId};
 
 type Memory = VirtualMemory<DefaultMemoryImpl>;
 
@@ -26,6 +38,18 @@ pub const REQUEST_MEMORY_ID: MemoryId = MemoryId::new(26);
 pub const TRANSFER_MEMORY_ID: MemoryId = MemoryId::new(27);
 pub const CLAIM_MEMORY_ID: MemoryId = MemoryId::new(28);
 pub const LP_TOKEN_MEMORY_ID: MemoryId = MemoryId::new(29);
+pub const LIMIT_ORDER_MEMORY_ID: MemoryId = MemoryId::new(30);
+pub const POOL_OBSERVATION_MEMORY_ID: MemoryId = MemoryId::new(31);
+pub const REWARD_CAMPAIGN_MEMORY_ID: MemoryId = MemoryId::new(32);
+pub const USER_REWARD_MEMORY_ID: MemoryId = MemoryId::new(33);
+pub const REFERRAL_REWARD_MEMORY_ID: MemoryId = MemoryId::new(34);
+pub const LP_BLOCK_MEMORY_ID: MemoryId = MemoryId::new(35);
+pub const LP_ALLOWANCE_MEMORY_ID: MemoryId = MemoryId::new(36);
+pub const DEPOSIT_MEMORY_ID: MemoryId = MemoryId::new(37);
+pub const USER_VOLUME_MEMORY_ID: MemoryId = MemoryId::new(38);
+pub const CONCENTRATED_TICK_MEMORY_ID: MemoryId = MemoryId::new(39);
+pub const CONCENTRATED_POSITION_MEMORY_ID: MemoryId = MemoryId::new(40);
+pub const LP_LEDGER_WASM_MEMORY_ID: MemoryId = MemoryId::new(41);
 // archives
 pub const TX_ARCHIVE_MEMORY_ID: MemoryId = MemoryId::new(204);
 pub const REQUEST_ARCHIVE_MEMORY_ID: MemoryId = MemoryId::new(205);
@@ -38,6 +62,9 @@ thread_local! {
     // static variable to list of temporary banned users
     pub static BANNED_USERS: RefCell<BTreeMap<u32, BannedUser>> = RefCell::default();
 
+    // static variable set when pool prices or open limit orders change. starts as true so orders are scanned after an upgrade
+    pub static LIMIT_ORDERS_SCAN_PENDING: RefCell<bool> = const { RefCell::new(true) };
+
     // MEMORY_MANAGER is given management of the entire stable memory. Given a 'MemoryId', it can
     // return a memory that can be used by stable structures
     pub static MEMORY_MANAGER: RefCell<MemoryManager<DefaultMemoryImpl>> =
@@ -88,6 +115,66 @@ thread_local! {
         RefCell::new(StableBTreeMap::init(memory_manager.get(LP_TOKEN_MEMORY_ID)))
     });
 
+    // stable memory for storing limit orders of users
+    pub static LIMIT_ORDER_MAP: RefCell<StableBTreeMap<StableLimitOrderId, StableLimitOrder, Memory>> = with_memory_manager(|memory_manager| {
+        RefCell::new(StableBTreeMap::init(memory_manager.get(LIMIT_ORDER_MEMORY_ID)))
+    });
+
+    // stable memory for storing the ring buffers of price observations of pools
+    pub static POOL_OBSERVATION_MAP: RefCell<StableBTreeMap<StablePoolObservationId, StablePoolObservation, Memory>> = with_memory_manager(|memory_manager| {
+        RefCell::new(StableBTreeMap::init(memory_manager.get(POOL_OBSERVATION_MEMORY_ID)))
+    });
+
+    // stable memory for storing liquidity mining reward campaigns
+    pub static REWARD_CAMPAIGN_MAP: RefCell<StableBTreeMap<StableRewardCampaignId, StableRewardCampaign, Memory>> = with_memory_manager(|memory_manager| {
+        RefCell::new(StableBTreeMap::init(memory_manager.get(REWARD_CAMPAIGN_MEMORY_ID)))
+    });
+
+    // stable memory for storing rewards of users in reward campaigns
+    pub static USER_REWARD_MAP: RefCell<StableBTreeMap<StableUserRewardId, StableUserReward, Memory>> = with_memory_manager(|memory_manager| {
+        RefCell::new(StableBTreeMap::init(memory_manager.get(USER_REWARD_MEMORY_ID)))
+    });
+
+    // stable memory for storing referral rewards of referrers
+    pub static REFERRAL_REWARD_MAP: RefCell<StableBTreeMap<StableReferralRewardId, StableReferralReward, Memory>> = with_memory_manager(|memory_manager| {
+        RefCell::new(StableBTreeMap::init(memory_manager.get(REFERRAL_REWARD_MEMORY_ID)))
+    });
+
+    // stable memory for storing the ICRC-3 blocks of the LP token ledgers
+    pub static LP_BLOCK_MAP: RefCell<StableBTreeMap<StableLPBlockId, StableLPBlock, Memory>> = with_memory_manager(|memory_manager| {
+        RefCell::new(StableBTreeMap::init(memory_manager.get(LP_BLOCK_MEMORY_ID)))
+    });
+
+    // stable memory for storing the ICRC-2 allowances of LP tokens
+    pub static LP_ALLOWANCE_MAP: RefCell<StableBTreeMap<StableLPAllowanceId, StableLPAllowance, Memory>> = with_memory_manager(|memory_manager| {
+        RefCell::new(StableBTreeMap::init(memory_manager.get(LP_ALLOWANCE_MEMORY_ID)))
+    });
+
+    // stable memory for storing the internal deposit balances of users
+    pub static DEPOSIT_MAP: RefCell<StableBTreeMap<StableDepositId, StableDeposit, Memory>> = with_memory_manager(|memory_manager| {
+        RefCell::new(StableBTreeMap::init(memory_manager.get(DEPOSIT_MEMORY_ID)))
+    });
+
+    // stable memory for storing the daily swap volumes of users for their fee tiers
+    pub static USER_VOLUME_MAP: RefCell<StableBTreeMap<StableUserVolumeId, StableUserVolume, Memory>> = with_memory_manager(|memory_manager| {
+        RefCell::new(StableBTreeMap::init(memory_manager.get(USER_VOLUME_MEMORY_ID)))
+    });
+
+    // stable memory for storing the initialized ticks of concentrated pools
+    pub static CONCENTRATED_TICK_MAP: RefCell<StableBTreeMap<StableTickId, TickInfo, Memory>> = with_memory_manager(|memory_manager| {
+        RefCell::new(StableBTreeMap::init(memory_manager.get(CONCENTRATED_TICK_MEMORY_ID)))
+    });
+
+    // stable memory for storing the LP positions of concentrated pools
+    pub static CONCENTRATED_POSITION_MAP: RefCell<StableBTreeMap<StablePositionId, ConcentratedPosition, Memory>> = with_memory_manager(|memory_manager| {
+        RefCell::new(StableBTreeMap::init(memory_manager.get(CONCENTRATED_POSITION_MEMORY_ID)))
+    });
+
+    // stable memory for storing the wasm module of the LP token ledgers
+    pub static LP_LEDGER_WASM: RefCell<StableCell<StableLPLedgerWasm, Memory>> = with_memory_manager(|memory_manager| {
+        RefCell::new(StableCell::init(memory_manager.get(LP_LEDGER_WASM_MEMORY_ID), StableLPLedgerWasm::default()).expect("Failed to initialize LP ledger wasm"))
+    });
+
     //
     // Archive Stable Memory
     //
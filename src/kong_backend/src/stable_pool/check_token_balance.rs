@@ -6,11 +6,16 @@ use crate::helpers::nat_helpers::nat_zero;
 use crate::ic::ledger::get_balance;
 use crate::stable_claim::claim_map;
 use crate::stable_claim::stable_claim::ClaimStatus;
+use crate::stable_deposit::deposit_map;
 use crate::stable_kong_settings::kong_settings_map;
+use crate::stable_limit_order::limit_order_map;
 use crate::stable_memory::CLAIM_MAP;
 use crate::stable_memory::POOL_MAP;
+use crate::stable_reward_campaign::reward_campaign_map;
 use crate::stable_token::stable_token::StableToken;
 use crate::stable_token::token::Token;
+use crate::stable_token::token_map;
+use crate::stable_user::referral_reward_map;
 
 #[derive(CandidType, Clone, Deserialize, Serialize)]
 pub struct PoolExpectedBalance {
@@ -18,6 +23,7 @@ pub struct PoolExpectedBalance {
     pub balance: Nat,
     pub lp_fee: Nat,
     pub kong_fee: Nat,
+    pub invariant_error: Option<String>, // error if the pool's balances do not satisfy its invariant
 }
 
 #[derive(CandidType, Clone, Deserialize, Serialize)]
@@ -25,6 +31,11 @@ pub struct ExpectedBalance {
     pub balance: Nat,
     pub pool_balances: Vec<PoolExpectedBalance>,
     pub unclaimed_claims: Nat,
+    pub referral_rewards: Nat,  // referral rewards not yet paid out, taken from Kong's fee
+    pub deposits: Nat,          // internal deposit balances of users
+    pub reward_campaigns: Nat,  // liquidity mining rewards funded and not yet claimed
+    pub open_limit_orders: Nat, // pay token escrowed by open limit orders
+    pub listing_deposits: Nat,  // ckUSDT deposits held for tokens on probation
 }
 
 /// token balance check
@@ -41,28 +52,37 @@ pub async fn check_token_balance(token: &StableToken) -> Result<(StableToken, Na
         balance: nat_zero(),
         pool_balances: Vec::new(),
         unclaimed_claims: nat_zero(),
+        referral_rewards: referral_reward_map::get_unpaid_amount(token_id),
+        deposits: deposit_map::get_total_amount(token_id),
+        reward_campaigns: reward_campaign_map::get_outstanding_amount(token_id),
+        open_limit_orders: limit_order_map::get_open_amount(token_id),
+        listing_deposits: token_map::get_listing_deposit_amount(token_id),
     };
     // iterate over all pools and sum up the balances
     POOL_MAP.with(|m| {
         let map = m.borrow();
         for (_, v) in map.iter() {
             if v.token_0().token_id() == token_id {
-                // expected_balance += v.balance_0 + v.lp_fee_0 + v.kong_fee_0;
-                expected_balance.balance += nat_add(&nat_add(&v.balance_0, &v.lp_fee_0), &v.kong_fee_0);
+                // expected_balance += v.balance_0 + v.lp_fee_0 + v.kong_fee_0 + v.boost_fee_0;
+                expected_balance.balance += nat_add(&nat_add(&nat_add(&v.balance_0, &v.lp_fee_0), &v.kong_fee_0), &v.boost_fee_0);
+                let invariant_error = v.check_invariant().err();
                 expected_balance.pool_balances.push(PoolExpectedBalance {
                     pool_symbol: v.symbol(),
                     balance: v.balance_0,
                     lp_fee: v.lp_fee_0,
                     kong_fee: v.kong_fee_0,
+                    invariant_error,
                 })
             } else if v.token_1().token_id() == token_id {
-                // expected_balance += v.balance_1 + v.lp_fee_1 + v.kong_fee_1;
-                expected_balance.balance += nat_add(&nat_add(&v.balance_1, &v.lp_fee_1), &v.kong_fee_1);
+                // expected_balance += v.balance_1 + v.lp_fee_1 + v.kong_fee_1 + v.boost_fee_1;
+                expected_balance.balance += nat_add(&nat_add(&nat_add(&v.balance_1, &v.lp_fee_1), &v.kong_fee_1), &v.boost_fee_1);
+                let invariant_error = v.check_invariant().err();
                 expected_balance.pool_balances.push(PoolExpectedBalance {
                     pool_symbol: v.symbol(),
                     balance: v.balance_1,
                     lp_fee: v.lp_fee_1,
                     kong_fee: v.kong_fee_1,
+                    invariant_error,
                 })
             }
         }
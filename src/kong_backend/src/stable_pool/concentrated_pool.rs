@@ -0,0 +1,705 @@
+use candid::{CandidType, Nat};
+use ic_stable_structures::{storable::Bound, Storable};
+use num::BigUint;
+use num_traits::One;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+use super::concentrated_pool_map;
+
+use crate::helpers::nat_helpers::{
+    nat_add, nat_divide, nat_divide_as_f64, nat_is_zero, nat_multiply, nat_multiply_f64, nat_sqrt, nat_subtract, nat_zero,
+};
+
+pub const MIN_TICK: i32 = -887_272;
+pub const MAX_TICK: i32 = 887_272;
+
+/// Concentrated liquidity state of a pool
+/// - prices are the raw token_1 / token_0 amounts, stored as sqrt(price) in Q64.96 fixed point
+/// - LPs provide liquidity between 2 ticks, price at tick i is 1.0001^i
+/// - fee growth is stored as fee per unit of liquidity in Q128 fixed point, modulo 2^256
+/// - ticks and positions are in their own stable maps keyed by pool_id. changes are kept in the pool until
+///   commit(), which pool_map::update() calls, so swaps on a clone of the pool for quotes write nothing
+#[derive(CandidType, Debug, Clone, Serialize, Deserialize)]
+pub struct ConcentratedPool {
+    pub pool_id: u32, // set by pool_map::insert()
+    pub tick_spacing: u16,
+    pub sqrt_price_x96: Nat,
+    pub tick: i32,
+    pub liquidity: Nat, // liquidity active at the current tick
+    pub fee_growth_global_0_x128: Nat,
+    pub fee_growth_global_1_x128: Nat,
+    pub num_positions: u64,
+    pub next_position_id: u64,
+    // uncommitted changes to the ticks and positions, None if removed
+    #[serde(skip)]
+    ticks: BTreeMap<i32, Option<TickInfo>>,
+    #[serde(skip)]
+    positions: BTreeMap<u64, Option<ConcentratedPosition>>,
+}
+
+/// initialized tick of a concentrated pool
+#[derive(CandidType, Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+pub struct StableTickId(pub u32, pub i32); // pool_id, tick
+
+impl Storable for StableTickId {
+    fn to_bytes(&self) -> std::borrow::Cow<'_, [u8]> {
+        serde_cbor::to_vec(self).unwrap().into()
+    }
+
+    fn from_bytes(bytes: std::borrow::Cow<'_, [u8]>) -> Self {
+        serde_cbor::from_slice(&bytes).unwrap()
+    }
+
+    const BOUND: Bound = Bound::Unbounded;
+}
+
+/// LP position of a concentrated pool
+#[derive(CandidType, Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+pub struct StablePositionId(pub u32, pub u64); // pool_id, position_id
+
+impl Storable for StablePositionId {
+    fn to_bytes(&self) -> std::borrow::Cow<'_, [u8]> {
+        serde_cbor::to_vec(self).unwrap().into()
+    }
+
+    fn from_bytes(bytes: std::borrow::Cow<'_, [u8]>) -> Self {
+        serde_cbor::from_slice(&bytes).unwrap()
+    }
+
+    const BOUND: Bound = Bound::Unbounded;
+}
+
+#[derive(CandidType, Debug, Clone, Serialize, Deserialize)]
+pub struct TickInfo {
+    pub liquidity_lower: Nat, // liquidity of positions with tick_lower at this tick
+    pub liquidity_upper: Nat, // liquidity of positions with tick_upper at this tick
+    pub fee_growth_outside_0_x128: Nat,
+    pub fee_growth_outside_1_x128: Nat,
+}
+
+#[derive(CandidType, Debug, Clone, Serialize, Deserialize)]
+pub struct ConcentratedPosition {
+    pub position_id: u64,
+    pub user_id: u32,
+    pub tick_lower: i32,
+    pub tick_upper: i32,
+    pub liquidity: Nat,
+    pub fee_growth_inside_0_last_x128: Nat,
+    pub fee_growth_inside_1_last_x128: Nat,
+    pub ts: u64,
+}
+
+impl Storable for TickInfo {
+    fn to_bytes(&self) -> std::borrow::Cow<'_, [u8]> {
+        serde_cbor::to_vec(self).unwrap().into()
+    }
+
+    fn from_bytes(bytes: std::borrow::Cow<'_, [u8]>) -> Self {
+        serde_cbor::from_slice(&bytes).unwrap()
+    }
+
+    const BOUND: Bound = Bound::Unbounded;
+}
+
+impl Storable for ConcentratedPosition {
+    fn to_bytes(&self) -> std::borrow::Cow<'_, [u8]> {
+        serde_cbor::to_vec(self).unwrap().into()
+    }
+
+    fn from_bytes(bytes: std::borrow::Cow<'_, [u8]>) -> Self {
+        serde_cbor::from_slice(&bytes).unwrap()
+    }
+
+    const BOUND: Bound = Bound::Unbounded;
+}
+
+fn q96() -> Nat {
+    Nat(BigUint::one() << 96)
+}
+
+fn q128() -> Nat {
+    Nat(BigUint::one() << 128)
+}
+
+// fee growth values wrap around 2^256
+fn fee_growth_modulo() -> Nat {
+    Nat(BigUint::one() << 256)
+}
+
+fn wrapping_add(n1: &Nat, n2: &Nat) -> Nat {
+    Nat(nat_add(n1, n2).0 % fee_growth_modulo().0)
+}
+
+fn wrapping_sub(n1: &Nat, n2: &Nat) -> Nat {
+    Nat(nat_subtract(&nat_add(n1, &fee_growth_modulo()), n2).unwrap_or(nat_zero()).0 % fee_growth_modulo().0)
+}
+
+fn divide(numerator: &Nat, denominator: &Nat, round_up: bool) -> Nat {
+    let quotient = nat_divide(numerator, denominator).unwrap_or(nat_zero());
+    if round_up && nat_multiply(&quotient, denominator) != *numerator {
+        nat_add(&quotient, &Nat::from(1_u8))
+    } else {
+        quotient
+    }
+}
+
+/// sqrt(1.0001^tick) in Q64.96
+pub fn sqrt_price_at_tick(tick: i32) -> Nat {
+    let sqrt_price = 1.0001_f64.powf(tick as f64 / 2.0);
+    nat_multiply_f64(&q96(), sqrt_price).unwrap_or(nat_zero())
+}
+
+/// largest tick where sqrt_price_at_tick(tick) <= sqrt_price_x96
+pub fn tick_at_sqrt_price(sqrt_price_x96: &Nat) -> i32 {
+    let sqrt_price = nat_divide_as_f64(sqrt_price_x96, &q96()).unwrap_or(0_f64);
+    if sqrt_price <= 0_f64 {
+        return MIN_TICK;
+    }
+    // estimate with f64 and then correct any rounding errors
+    let mut tick = ((2.0 * sqrt_price.ln() / 1.0001_f64.ln()).floor() as i32).clamp(MIN_TICK, MAX_TICK);
+    while tick > MIN_TICK && sqrt_price_at_tick(tick) > *sqrt_price_x96 {
+        tick -= 1;
+    }
+    while tick < MAX_TICK && sqrt_price_at_tick(tick + 1) <= *sqrt_price_x96 {
+        tick += 1;
+    }
+    tick
+}
+
+/// amount of token_0 between sqrt_price_a and sqrt_price_b (a < b)
+/// amount_0 = liquidity * (sqrt_price_b - sqrt_price_a) / (sqrt_price_a * sqrt_price_b)
+fn amount_0_delta(sqrt_price_a: &Nat, sqrt_price_b: &Nat, liquidity: &Nat, round_up: bool) -> Nat {
+    let sqrt_price_delta = nat_subtract(sqrt_price_b, sqrt_price_a).unwrap_or(nat_zero());
+    let numerator = nat_multiply(&nat_multiply(liquidity, &q96()), &sqrt_price_delta);
+    let denominator = nat_multiply(sqrt_price_a, sqrt_price_b);
+    divide(&numerator, &denominator, round_up)
+}
+
+/// amount of token_1 between sqrt_price_a and sqrt_price_b (a < b)
+/// amount_1 = liquidity * (sqrt_price_b - sqrt_price_a)
+fn amount_1_delta(sqrt_price_a: &Nat, sqrt_price_b: &Nat, liquidity: &Nat, round_up: bool) -> Nat {
+    let sqrt_price_delta = nat_subtract(sqrt_price_b, sqrt_price_a).unwrap_or(nat_zero());
+    divide(&nat_multiply(liquidity, &sqrt_price_delta), &q96(), round_up)
+}
+
+fn liquidity_for_amount_0(sqrt_price_a: &Nat, sqrt_price_b: &Nat, amount_0: &Nat) -> Nat {
+    let sqrt_price_delta = nat_subtract(sqrt_price_b, sqrt_price_a).unwrap_or(nat_zero());
+    let numerator = nat_multiply(&nat_multiply(amount_0, sqrt_price_a), sqrt_price_b);
+    divide(&numerator, &nat_multiply(&sqrt_price_delta, &q96()), false)
+}
+
+fn liquidity_for_amount_1(sqrt_price_a: &Nat, sqrt_price_b: &Nat, amount_1: &Nat) -> Nat {
+    let sqrt_price_delta = nat_subtract(sqrt_price_b, sqrt_price_a).unwrap_or(nat_zero());
+    divide(&nat_multiply(amount_1, &q96()), &sqrt_price_delta, false)
+}
+
+impl TickInfo {
+    // by convention, all fee growth happened below the tick when it is initialized at or below the current tick
+    fn new(is_below_current_tick: bool, fee_growth_global_0_x128: &Nat, fee_growth_global_1_x128: &Nat) -> Self {
+        let (fee_growth_outside_0_x128, fee_growth_outside_1_x128) = if is_below_current_tick {
+            (fee_growth_global_0_x128.clone(), fee_growth_global_1_x128.clone())
+        } else {
+            (nat_zero(), nat_zero())
+        };
+        Self {
+            liquidity_lower: nat_zero(),
+            liquidity_upper: nat_zero(),
+            fee_growth_outside_0_x128,
+            fee_growth_outside_1_x128,
+        }
+    }
+}
+
+impl ConcentratedPool {
+    /// new concentrated pool with the initial price set by the ratio of amount_1 / amount_0
+    pub fn new(tick_spacing: u16, amount_0: &Nat, amount_1: &Nat) -> Result<Self, String> {
+        if tick_spacing == 0 {
+            Err("Tick spacing must be greater than 0")?
+        }
+        if nat_is_zero(amount_0) || nat_is_zero(amount_1) {
+            Err("Invalid zero amounts")?
+        }
+        // sqrt_price_x96 = sqrt(amount_1 / amount_0) * 2^96
+        let numerator = nat_multiply(&nat_multiply(amount_1, &q96()), &q96());
+        let sqrt_price_x96 = nat_sqrt(&nat_divide(&numerator, amount_0).ok_or("Invalid amount_0")?);
+        if sqrt_price_x96 < sqrt_price_at_tick(MIN_TICK) || sqrt_price_x96 >= sqrt_price_at_tick(MAX_TICK) {
+            Err("Initial price out of range")?
+        }
+
+        Ok(Self {
+            pool_id: 0,
+            tick_spacing,
+            tick: tick_at_sqrt_price(&sqrt_price_x96),
+            sqrt_price_x96,
+            liquidity: nat_zero(),
+            fee_growth_global_0_x128: nat_zero(),
+            fee_growth_global_1_x128: nat_zero(),
+            num_positions: 0,
+            next_position_id: 0,
+            ticks: BTreeMap::new(),
+            positions: BTreeMap::new(),
+        })
+    }
+
+    fn get_tick(&self, tick: i32) -> Option<TickInfo> {
+        match self.ticks.get(&tick) {
+            Some(tick_info) => tick_info.clone(),
+            None => concentrated_pool_map::get_tick(self.pool_id, tick),
+        }
+    }
+
+    /// next initialized tick in the direction of a swap
+    fn next_tick(&self, zero_for_one: bool) -> Option<i32> {
+        let is_removed = |tick: i32| matches!(self.ticks.get(&tick), Some(None));
+        if zero_for_one {
+            let stored_tick = concentrated_pool_map::find_tick_at_or_below(self.pool_id, self.tick, is_removed);
+            let changed_tick = self.ticks.range(..=self.tick).rev().find(|(_, v)| v.is_some()).map(|(k, _)| *k);
+            stored_tick.max(changed_tick)
+        } else {
+            let stored_tick = concentrated_pool_map::find_tick_above(self.pool_id, self.tick, is_removed);
+            let changed_tick = self.ticks.range(self.tick + 1..).find(|(_, v)| v.is_some()).map(|(k, _)| *k);
+            match (stored_tick, changed_tick) {
+                (Some(stored_tick), Some(changed_tick)) => Some(stored_tick.min(changed_tick)),
+                (stored_tick, changed_tick) => stored_tick.or(changed_tick),
+            }
+        }
+    }
+
+    pub fn get_position(&self, position_id: u64) -> Option<ConcentratedPosition> {
+        match self.positions.get(&position_id) {
+            Some(position) => position.clone(),
+            None => concentrated_pool_map::get_position(self.pool_id, position_id),
+        }
+    }
+
+    /// all positions of the pool
+    pub fn get_positions(&self) -> Vec<ConcentratedPosition> {
+        let mut positions: BTreeMap<u64, ConcentratedPosition> = concentrated_pool_map::get_positions(self.pool_id)
+            .into_iter()
+            .map(|position| (position.position_id, position))
+            .collect();
+        for (position_id, position) in self.positions.iter() {
+            match position {
+                Some(position) => positions.insert(*position_id, position.clone()),
+                None => positions.remove(position_id),
+            };
+        }
+        positions.into_values().collect()
+    }
+
+    /// write the changes to the ticks and positions to stable memory
+    pub fn commit(&self) {
+        for (tick, tick_info) in self.ticks.iter() {
+            concentrated_pool_map::update_tick(self.pool_id, *tick, tick_info.as_ref());
+        }
+        for (position_id, position) in self.positions.iter() {
+            concentrated_pool_map::update_position(self.pool_id, *position_id, position.as_ref());
+        }
+    }
+
+    /// lowest tick aligned to the tick spacing
+    pub fn min_tick(&self) -> i32 {
+        -self.max_tick()
+    }
+
+    /// highest tick aligned to the tick spacing
+    pub fn max_tick(&self) -> i32 {
+        MAX_TICK / self.tick_spacing as i32 * self.tick_spacing as i32
+    }
+
+    /// price of token_0 in token_1 at tick, adjusted for token decimals
+    pub fn tick_to_price(tick: i32, decimals_0: u8, decimals_1: u8) -> f64 {
+        1.0001_f64.powf(tick as f64) * 10_f64.powi(decimals_0 as i32 - decimals_1 as i32)
+    }
+
+    /// convert a price range (in token_1 per token_0, adjusted for token decimals) to ticks aligned to the tick spacing
+    /// - if min_price or max_price is not specified, the full range is used
+    pub fn ticks_for_price_range(
+        &self,
+        min_price: Option<f64>,
+        max_price: Option<f64>,
+        decimals_0: u8,
+        decimals_1: u8,
+    ) -> Result<(i32, i32), String> {
+        let price_to_tick = |price: f64| -> Result<i32, String> {
+            // raw_price = price * 10^decimals_1 / 10^decimals_0
+            let raw_price = price * 10_f64.powi(decimals_1 as i32 - decimals_0 as i32);
+            if !raw_price.is_finite() || raw_price <= 0_f64 {
+                Err(format!("Invalid price {}", price))?
+            }
+            let tick = (raw_price.ln() / 1.0001_f64.ln()).floor() as i64;
+            let tick_spacing = self.tick_spacing as i64;
+            let tick = tick.div_euclid(tick_spacing) * tick_spacing;
+            Ok(tick.clamp(self.min_tick() as i64, self.max_tick() as i64) as i32)
+        };
+
+        let tick_lower = match min_price {
+            Some(price) => price_to_tick(price)?,
+            None => self.min_tick(),
+        };
+        let tick_upper = match max_price {
+            Some(price) => price_to_tick(price)?,
+            None => self.max_tick(),
+        };
+        if tick_lower >= tick_upper {
+            Err("Invalid price range. min_price must be less than max_price")?
+        }
+
+        Ok((tick_lower, tick_upper))
+    }
+
+    /// maximum liquidity that can be provided between tick_lower and tick_upper with amount_0 and amount_1
+    pub fn liquidity_for_amounts(&self, tick_lower: i32, tick_upper: i32, amount_0: &Nat, amount_1: &Nat) -> Nat {
+        let sqrt_price_lower = sqrt_price_at_tick(tick_lower);
+        let sqrt_price_upper = sqrt_price_at_tick(tick_upper);
+
+        if self.sqrt_price_x96 <= sqrt_price_lower {
+            // price below the range, only token_0
+            liquidity_for_amount_0(&sqrt_price_lower, &sqrt_price_upper, amount_0)
+        } else if self.sqrt_price_x96 < sqrt_price_upper {
+            let liquidity_0 = liquidity_for_amount_0(&self.sqrt_price_x96, &sqrt_price_upper, amount_0);
+            let liquidity_1 = liquidity_for_amount_1(&sqrt_price_lower, &self.sqrt_price_x96, amount_1);
+            std::cmp::min(liquidity_0, liquidity_1)
+        } else {
+            // price above the range, only token_1
+            liquidity_for_amount_1(&sqrt_price_lower, &sqrt_price_upper, amount_1)
+        }
+    }
+
+    /// liquidity that can be provided between tick_lower and tick_upper with amount of token_0 (is_token_0) or token_1
+    pub fn liquidity_for_amount(&self, tick_lower: i32, tick_upper: i32, amount: &Nat, is_token_0: bool) -> Nat {
+        let sqrt_price_lower = sqrt_price_at_tick(tick_lower);
+        let sqrt_price_upper = sqrt_price_at_tick(tick_upper);
+        let sqrt_price = self
+            .sqrt_price_x96
+            .clone()
+            .clamp(sqrt_price_lower.clone(), sqrt_price_upper.clone());
+
+        if is_token_0 {
+            liquidity_for_amount_0(&sqrt_price, &sqrt_price_upper, amount)
+        } else {
+            liquidity_for_amount_1(&sqrt_price_lower, &sqrt_price, amount)
+        }
+    }
+
+    /// amounts of token_0 and token_1 for liquidity between tick_lower and tick_upper at the current price
+    /// round_up when the amounts are paid into the pool, round down when paid out
+    pub fn amounts_for_liquidity(&self, tick_lower: i32, tick_upper: i32, liquidity: &Nat, round_up: bool) -> (Nat, Nat) {
+        let sqrt_price_lower = sqrt_price_at_tick(tick_lower);
+        let sqrt_price_upper = sqrt_price_at_tick(tick_upper);
+
+        if self.sqrt_price_x96 <= sqrt_price_lower {
+            (
+                amount_0_delta(&sqrt_price_lower, &sqrt_price_upper, liquidity, round_up),
+                nat_zero(),
+            )
+        } else if self.sqrt_price_x96 < sqrt_price_upper {
+            (
+                amount_0_delta(&self.sqrt_price_x96, &sqrt_price_upper, liquidity, round_up),
+                amount_1_delta(&sqrt_price_lower, &self.sqrt_price_x96, liquidity, round_up),
+            )
+        } else {
+            (
+                nat_zero(),
+                amount_1_delta(&sqrt_price_lower, &sqrt_price_upper, liquidity, round_up),
+            )
+        }
+    }
+
+    /// fee growth per unit of liquidity between tick_lower and tick_upper
+    fn fee_growth_inside(&self, tick_lower: i32, tick_upper: i32) -> (Nat, Nat) {
+        let fee_growth_inside = |fee_growth_global: &Nat, fee_growth_outside: fn(&TickInfo) -> &Nat| -> Nat {
+            let lower_outside = self
+                .get_tick(tick_lower)
+                .map_or_else(nat_zero, |tick| fee_growth_outside(&tick).clone());
+            let upper_outside = self
+                .get_tick(tick_upper)
+                .map_or_else(nat_zero, |tick| fee_growth_outside(&tick).clone());
+            let below = if self.tick >= tick_lower {
+                lower_outside
+            } else {
+                wrapping_sub(fee_growth_global, &lower_outside)
+            };
+            let above = if self.tick < tick_upper {
+                upper_outside
+            } else {
+                wrapping_sub(fee_growth_global, &upper_outside)
+            };
+            wrapping_sub(&wrapping_sub(fee_growth_global, &below), &above)
+        };
+
+        (
+            fee_growth_inside(&self.fee_growth_global_0_x128, |tick| &tick.fee_growth_outside_0_x128),
+            fee_growth_inside(&self.fee_growth_global_1_x128, |tick| &tick.fee_growth_outside_1_x128),
+        )
+    }
+
+    /// uncollected fees of a position in token_0 and token_1
+    pub fn position_fees(&self, position: &ConcentratedPosition) -> (Nat, Nat) {
+        let (fee_growth_inside_0, fee_growth_inside_1) = self.fee_growth_inside(position.tick_lower, position.tick_upper);
+        let fee_growth_0 = wrapping_sub(&fee_growth_inside_0, &position.fee_growth_inside_0_last_x128);
+        let fee_growth_1 = wrapping_sub(&fee_growth_inside_1, &position.fee_growth_inside_1_last_x128);
+        (
+            divide(&nat_multiply(&position.liquidity, &fee_growth_0), &q128(), false),
+            divide(&nat_multiply(&position.liquidity, &fee_growth_1), &q128(), false),
+        )
+    }
+
+    /// add a new position for user_id between tick_lower and tick_upper. returns the position_id
+    pub fn add_position(&mut self, user_id: u32, tick_lower: i32, tick_upper: i32, liquidity: &Nat, ts: u64) -> u64 {
+        for (tick, is_lower) in [(tick_lower, true), (tick_upper, false)] {
+            let mut tick_info = self
+                .get_tick(tick)
+                .unwrap_or_else(|| TickInfo::new(tick <= self.tick, &self.fee_growth_global_0_x128, &self.fee_growth_global_1_x128));
+            if is_lower {
+                tick_info.liquidity_lower = nat_add(&tick_info.liquidity_lower, liquidity);
+            } else {
+                tick_info.liquidity_upper = nat_add(&tick_info.liquidity_upper, liquidity);
+            }
+            self.ticks.insert(tick, Some(tick_info));
+        }
+        if tick_lower <= self.tick && self.tick < tick_upper {
+            self.liquidity = nat_add(&self.liquidity, liquidity);
+        }
+
+        let (fee_growth_inside_0_last_x128, fee_growth_inside_1_last_x128) = self.fee_growth_inside(tick_lower, tick_upper);
+        self.next_position_id += 1;
+        self.num_positions += 1;
+        let position_id = self.next_position_id;
+        self.positions.insert(
+            position_id,
+            Some(ConcentratedPosition {
+                position_id,
+                user_id,
+                tick_lower,
+                tick_upper,
+                liquidity: liquidity.clone(),
+                fee_growth_inside_0_last_x128,
+                fee_growth_inside_1_last_x128,
+                ts,
+            }),
+        );
+
+        position_id
+    }
+
+    /// remove liquidity from a position and collect all its fees
+    ///
+    /// returns (amount_0, lp_fee_0, amount_1, lp_fee_1)
+    pub fn remove_position(&mut self, position_id: u64, liquidity: &Nat) -> Result<(Nat, Nat, Nat, Nat), String> {
+        let position = self
+            .get_position(position_id)
+            .ok_or(format!("Position #{} not found", position_id))?;
+        let remaining_liquidity = nat_subtract(&position.liquidity, liquidity).ok_or("Insufficient position liquidity")?;
+
+        let (lp_fee_0, lp_fee_1) = self.position_fees(&position);
+        let (fee_growth_inside_0_last_x128, fee_growth_inside_1_last_x128) =
+            self.fee_growth_inside(position.tick_lower, position.tick_upper);
+        let (amount_0, amount_1) = self.amounts_for_liquidity(position.tick_lower, position.tick_upper, liquidity, false);
+
+        for (tick, is_lower) in [(position.tick_lower, true), (position.tick_upper, false)] {
+            if let Some(mut tick_info) = self.get_tick(tick) {
+                if is_lower {
+                    tick_info.liquidity_lower = nat_subtract(&tick_info.liquidity_lower, liquidity).unwrap_or(nat_zero());
+                } else {
+                    tick_info.liquidity_upper = nat_subtract(&tick_info.liquidity_upper, liquidity).unwrap_or(nat_zero());
+                }
+                if nat_is_zero(&tick_info.liquidity_lower) && nat_is_zero(&tick_info.liquidity_upper) {
+                    self.ticks.insert(tick, None);
+                } else {
+                    self.ticks.insert(tick, Some(tick_info));
+                }
+            }
+        }
+        if position.tick_lower <= self.tick && self.tick < position.tick_upper {
+            self.liquidity = nat_subtract(&self.liquidity, liquidity).unwrap_or(nat_zero());
+        }
+
+        if nat_is_zero(&remaining_liquidity) {
+            self.num_positions = self.num_positions.saturating_sub(1);
+            self.positions.insert(position_id, None);
+        } else {
+            self.positions.insert(
+                position_id,
+                Some(ConcentratedPosition {
+                    liquidity: remaining_liquidity,
+                    fee_growth_inside_0_last_x128,
+                    fee_growth_inside_1_last_x128,
+                    ..position
+                }),
+            );
+        }
+
+        Ok((amount_0, lp_fee_0, amount_1, lp_fee_1))
+    }
+
+    fn cross_tick(&mut self, tick: i32, zero_for_one: bool) -> Result<(), String> {
+        if let Some(mut tick_info) = self.get_tick(tick) {
+            tick_info.fee_growth_outside_0_x128 = wrapping_sub(&self.fee_growth_global_0_x128, &tick_info.fee_growth_outside_0_x128);
+            tick_info.fee_growth_outside_1_x128 = wrapping_sub(&self.fee_growth_global_1_x128, &tick_info.fee_growth_outside_1_x128);
+            // moving down, positions ending at the tick become active and positions starting at the tick become inactive
+            let (liquidity_in, liquidity_out) = if zero_for_one {
+                (&tick_info.liquidity_upper, &tick_info.liquidity_lower)
+            } else {
+                (&tick_info.liquidity_lower, &tick_info.liquidity_upper)
+            };
+            self.liquidity = nat_subtract(&nat_add(&self.liquidity, liquidity_in), liquidity_out).ok_or("Invalid pool liquidity")?;
+            self.ticks.insert(tick, Some(tick_info));
+        }
+        Ok(())
+    }
+
+    /// swap amount_in through the ticks. zero_for_one is true when paying token_0 and receiving token_1
+    /// lp_fee_share is (lp_fee, receive_amount) used to credit the LP fee to the active liquidity of each step
+    ///
+    /// returns the amount out before any fees
+    pub fn swap(&mut self, zero_for_one: bool, amount_in: &Nat, lp_fee_share: Option<(&Nat, &Nat)>) -> Result<Nat, String> {
+        let mut amount_remaining = amount_in.clone();
+        let mut amount_out = nat_zero();
+
+        while !nat_is_zero(&amount_remaining) {
+            // next initialized tick in the direction of the swap. if none, there is no more liquidity
+            let next_tick = self.next_tick(zero_for_one).ok_or("Insufficient liquidity in pool")?;
+            let sqrt_price_target = sqrt_price_at_tick(next_tick);
+
+            let (amount_in_step, amount_out_step, sqrt_price_next) = if zero_for_one {
+                let max_amount_in = amount_0_delta(&sqrt_price_target, &self.sqrt_price_x96, &self.liquidity, true);
+                if amount_remaining >= max_amount_in {
+                    let amount_out_step = amount_1_delta(&sqrt_price_target, &self.sqrt_price_x96, &self.liquidity, false);
+                    (max_amount_in, amount_out_step, sqrt_price_target.clone())
+                } else {
+                    // sqrt_price_next = liquidity * sqrt_price / (liquidity + amount_in * sqrt_price)
+                    let liquidity_x96 = nat_multiply(&self.liquidity, &q96());
+                    let numerator = nat_multiply(&liquidity_x96, &self.sqrt_price_x96);
+                    let denominator = nat_add(&liquidity_x96, &nat_multiply(&amount_remaining, &self.sqrt_price_x96));
+                    let sqrt_price_next = divide(&numerator, &denominator, true);
+                    let amount_out_step = amount_1_delta(&sqrt_price_next, &self.sqrt_price_x96, &self.liquidity, false);
+                    (amount_remaining.clone(), amount_out_step, sqrt_price_next)
+                }
+            } else {
+                let max_amount_in = amount_1_delta(&self.sqrt_price_x96, &sqrt_price_target, &self.liquidity, true);
+                if amount_remaining >= max_amount_in {
+                    let amount_out_step = amount_0_delta(&self.sqrt_price_x96, &sqrt_price_target, &self.liquidity, false);
+                    (max_amount_in, amount_out_step, sqrt_price_target.clone())
+                } else {
+                    // sqrt_price_next = sqrt_price + amount_in / liquidity
+                    let sqrt_price_delta = divide(&nat_multiply(&amount_remaining, &q96()), &self.liquidity, false);
+                    let sqrt_price_next = nat_add(&self.sqrt_price_x96, &sqrt_price_delta);
+                    let amount_out_step = amount_0_delta(&self.sqrt_price_x96, &sqrt_price_next, &self.liquidity, false);
+                    (amount_remaining.clone(), amount_out_step, sqrt_price_next)
+                }
+            };
+
+            // credit the LP fee of this step to the active liquidity
+            if let Some((lp_fee, receive_amount)) = lp_fee_share {
+                if !nat_is_zero(&self.liquidity) {
+                    let lp_fee_step = divide(&nat_multiply(&amount_out_step, lp_fee), receive_amount, false);
+                    let fee_growth = divide(&nat_multiply(&lp_fee_step, &q128()), &self.liquidity, false);
+                    if zero_for_one {
+                        self.fee_growth_global_1_x128 = wrapping_add(&self.fee_growth_global_1_x128, &fee_growth);
+                    } else {
+                        self.fee_growth_global_0_x128 = wrapping_add(&self.fee_growth_global_0_x128, &fee_growth);
+                    }
+                }
+            }
+
+            amount_remaining = nat_subtract(&amount_remaining, &amount_in_step).unwrap_or(nat_zero());
+            amount_out = nat_add(&amount_out, &amount_out_step);
+            self.sqrt_price_x96 = sqrt_price_next;
+            if self.sqrt_price_x96 == sqrt_price_target {
+                self.cross_tick(next_tick, zero_for_one)?;
+                self.tick = if zero_for_one { next_tick - 1 } else { next_tick };
+            } else {
+                self.tick = tick_at_sqrt_price(&self.sqrt_price_x96);
+            }
+        }
+
+        Ok(amount_out)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_tick_at_sqrt_price() {
+        for tick in [-887_000, -20_000, -1, 0, 1, 6_931, 500_000] {
+            assert_eq!(tick_at_sqrt_price(&sqrt_price_at_tick(tick)), tick);
+            assert_eq!(
+                tick_at_sqrt_price(&nat_subtract(&sqrt_price_at_tick(tick), &Nat::from(1_u8)).unwrap()),
+                tick - 1
+            );
+        }
+    }
+
+    #[test]
+    fn test_full_range_swap() {
+        let amount_0 = Nat::from(1_000_000_000_000_u64);
+        let amount_1 = Nat::from(4_000_000_000_000_u64);
+        let mut pool = ConcentratedPool::new(10, &amount_0, &amount_1).unwrap();
+        let (tick_lower, tick_upper) = (pool.min_tick(), pool.max_tick());
+        let liquidity = pool.liquidity_for_amounts(tick_lower, tick_upper, &amount_0, &amount_1);
+        pool.add_position(1, tick_lower, tick_upper, &liquidity, 0);
+
+        // full range liquidity behaves as constant product
+        let amount_in = Nat::from(10_000_000_000_u64);
+        let amount_out = pool.swap(true, &amount_in, None).unwrap();
+        let expected = nat_divide(&nat_multiply(&amount_in, &amount_1), &nat_add(&amount_0, &amount_in)).unwrap();
+        let diff = nat_divide_as_f64(&expected, &amount_out).unwrap() - 1.0;
+        assert!(diff.abs() < 0.0001);
+    }
+
+    #[test]
+    fn test_position_fees() {
+        let amount_0 = Nat::from(1_000_000_000_u64);
+        let amount_1 = Nat::from(1_000_000_000_u64);
+        let mut pool = ConcentratedPool::new(1, &amount_0, &amount_1).unwrap();
+        let liquidity = pool.liquidity_for_amounts(-100, 100, &amount_0, &amount_1);
+        let position_id = pool.add_position(1, -100, 100, &liquidity, 0);
+        // not enough liquidity in the range
+        assert!(pool.clone().swap(true, &Nat::from(100_000_000_000_u64), None).is_err());
+
+        // LP fee is in token_0 when paying token_1
+        let amount_in = Nat::from(1_000_000_u64);
+        let amount_out = pool.clone().swap(false, &amount_in, None).unwrap();
+        let lp_fee = Nat::from(3_000_u64);
+        pool.swap(false, &amount_in, Some((&lp_fee, &amount_out))).unwrap();
+        let (_, lp_fee_0, _, lp_fee_1) = pool.remove_position(position_id, &liquidity).unwrap();
+        assert!(nat_is_zero(&lp_fee_1));
+        assert!(lp_fee_0 <= lp_fee && lp_fee_0 >= 2_999_u64);
+        assert!(pool.get_positions().is_empty() && pool.num_positions == 0 && nat_is_zero(&pool.liquidity));
+        assert!(pool.next_tick(true).is_none() && pool.next_tick(false).is_none());
+    }
+
+    #[test]
+    fn test_commit() {
+        let amount_0 = Nat::from(1_000_000_000_u64);
+        let amount_1 = Nat::from(1_000_000_000_u64);
+        let mut pool = ConcentratedPool::new(1, &amount_0, &amount_1).unwrap();
+        pool.pool_id = 1;
+        let liquidity = pool.liquidity_for_amounts(-100, 100, &amount_0, &amount_1);
+        let position_id = pool.add_position(1, -100, 100, &liquidity, 0);
+        // the pool is stored without its ticks and positions, nothing is written until commit
+        let stored_pool =
+            |pool: &ConcentratedPool| -> ConcentratedPool { serde_cbor::from_slice(&serde_cbor::to_vec(pool).unwrap()).unwrap() };
+        assert!(stored_pool(&pool).get_position(position_id).is_none());
+        pool.commit();
+        let mut pool = stored_pool(&pool);
+        assert_eq!(pool.get_positions().len(), 1);
+        assert_eq!((pool.next_tick(true), pool.next_tick(false)), (Some(-100), Some(100)));
+
+        // quotes on a clone do not change the stored ticks and positions
+        let amount_in = Nat::from(1_000_000_u64);
+        let amount_out = pool.clone().swap(true, &amount_in, None).unwrap();
+        pool.clone().remove_position(position_id, &liquidity).unwrap();
+        assert_eq!(pool.get_position(position_id).unwrap().liquidity, liquidity);
+        assert_eq!(pool.swap(true, &amount_in, None).unwrap(), amount_out);
+
+        pool.remove_position(position_id, &liquidity).unwrap();
+        assert!(pool.get_positions().is_empty() && concentrated_pool_map::get_positions(1).len() == 1);
+        pool.commit();
+        assert!(concentrated_pool_map::get_positions(1).is_empty() && concentrated_pool_map::get_tick(1, 100).is_none());
+    }
+}
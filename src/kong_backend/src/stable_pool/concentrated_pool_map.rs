@@ -0,0 +1,65 @@
+use super::concentrated_pool::{ConcentratedPosition, StablePositionId, StableTickId, TickInfo, MAX_TICK, MIN_TICK};
+
+use crate::stable_memory::{CONCENTRATED_POSITION_MAP, CONCENTRATED_TICK_MAP};
+
+pub fn get_tick(pool_id: u32, tick: i32) -> Option<TickInfo> {
+    CONCENTRATED_TICK_MAP.with(|m| m.borrow().get(&StableTickId(pool_id, tick)))
+}
+
+/// highest initialized tick of the pool at or below tick, skipping the ticks where skip is true
+pub fn find_tick_at_or_below(pool_id: u32, tick: i32, skip: impl Fn(i32) -> bool) -> Option<i32> {
+    CONCENTRATED_TICK_MAP.with(|m| {
+        m.borrow()
+            .range(StableTickId(pool_id, MIN_TICK)..=StableTickId(pool_id, tick))
+            .rev()
+            .map(|(k, _)| k.1)
+            .find(|tick| !skip(*tick))
+    })
+}
+
+/// lowest initialized tick of the pool above tick, skipping the ticks where skip is true
+pub fn find_tick_above(pool_id: u32, tick: i32, skip: impl Fn(i32) -> bool) -> Option<i32> {
+    if tick >= MAX_TICK {
+        return None;
+    }
+    CONCENTRATED_TICK_MAP.with(|m| {
+        m.borrow()
+            .range(StableTickId(pool_id, tick + 1)..=StableTickId(pool_id, MAX_TICK))
+            .map(|(k, _)| k.1)
+            .find(|tick| !skip(*tick))
+    })
+}
+
+pub fn update_tick(pool_id: u32, tick: i32, tick_info: Option<&TickInfo>) {
+    CONCENTRATED_TICK_MAP.with(|m| {
+        let mut map = m.borrow_mut();
+        match tick_info {
+            Some(tick_info) => map.insert(StableTickId(pool_id, tick), tick_info.clone()),
+            None => map.remove(&StableTickId(pool_id, tick)),
+        }
+    });
+}
+
+pub fn get_position(pool_id: u32, position_id: u64) -> Option<ConcentratedPosition> {
+    CONCENTRATED_POSITION_MAP.with(|m| m.borrow().get(&StablePositionId(pool_id, position_id)))
+}
+
+/// all positions of a pool
+pub fn get_positions(pool_id: u32) -> Vec<ConcentratedPosition> {
+    CONCENTRATED_POSITION_MAP.with(|m| {
+        m.borrow()
+            .range(StablePositionId(pool_id, 0)..=StablePositionId(pool_id, u64::MAX))
+            .map(|(_, v)| v)
+            .collect()
+    })
+}
+
+pub fn update_position(pool_id: u32, position_id: u64, position: Option<&ConcentratedPosition>) {
+    CONCENTRATED_POSITION_MAP.with(|m| {
+        let mut map = m.borrow_mut();
+        match position {
+            Some(position) => map.insert(StablePositionId(pool_id, position_id), position.clone()),
+            None => map.remove(&StablePositionId(pool_id, position_id)),
+        }
+    });
+}
@@ -0,0 +1,120 @@
+use candid::CandidType;
+use num::{BigRational, Signed, ToPrimitive, Zero};
+use serde::{Deserialize, Serialize};
+
+/// dynamic LP fee of a pool, set by kingkong
+/// each trade direction has a volatility accumulator fed by the price moves of recent swaps in that direction,
+/// decaying by half every decay_secs. the LP fee of a swap is
+/// min_fee_bps + volatility_bps * volatility_multiplier_pct / 100, bounded by [min_fee_bps, max_fee_bps]
+/// so swaps following a run of swaps in the same direction pay more than swaps against it
+#[derive(CandidType, Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct DynamicFee {
+    pub min_fee_bps: u8,
+    pub max_fee_bps: u8,
+    pub volatility_multiplier_pct: u32,
+    pub decay_secs: u64,
+    pub volatility_0_bps: u64, // price moves of swaps paying token_0
+    pub volatility_1_bps: u64, // price moves of swaps paying token_1
+    pub volatility_ts: u64,    // last time the accumulators were updated
+}
+
+impl DynamicFee {
+    pub fn new(min_fee_bps: u8, max_fee_bps: u8, volatility_multiplier_pct: u32, decay_secs: u64) -> Result<Self, String> {
+        if min_fee_bps > max_fee_bps {
+            Err("Min fee must be less than or equal to max fee".to_string())?
+        }
+        if decay_secs == 0 {
+            Err("Decay secs must be greater than 0".to_string())?
+        }
+        Ok(Self {
+            min_fee_bps,
+            max_fee_bps,
+            volatility_multiplier_pct,
+            decay_secs,
+            volatility_0_bps: 0,
+            volatility_1_bps: 0,
+            volatility_ts: 0,
+        })
+    }
+
+    fn decayed_volatility(&self, volatility_bps: u64, ts: u64) -> u64 {
+        let elapsed_secs = ts.saturating_sub(self.volatility_ts) / 1_000_000_000;
+        let half_lives = elapsed_secs as f64 / self.decay_secs as f64;
+        (volatility_bps as f64 * 0.5_f64.powf(half_lives)) as u64
+    }
+
+    /// LP fee in basis points for a swap paying token_0 (pay_token_0 = true) or token_1
+    pub fn fee_bps(&self, pay_token_0: bool, ts: u64) -> u8 {
+        let volatility_bps = if pay_token_0 {
+            self.volatility_0_bps
+        } else {
+            self.volatility_1_bps
+        };
+        let volatility_fee_bps = self.decayed_volatility(volatility_bps, ts) * self.volatility_multiplier_pct as u64 / 100;
+        let fee_bps = (self.min_fee_bps as u64).saturating_add(volatility_fee_bps);
+        fee_bps.min(self.max_fee_bps as u64) as u8
+    }
+
+    /// add the price move of a swap to the accumulator of its direction, decaying both accumulators to ts
+    pub fn update(&mut self, pay_token_0: bool, price_before: &BigRational, price_after: &BigRational, ts: u64) {
+        self.volatility_0_bps = self.decayed_volatility(self.volatility_0_bps, ts);
+        self.volatility_1_bps = self.decayed_volatility(self.volatility_1_bps, ts);
+        self.volatility_ts = ts;
+
+        if price_before.is_zero() {
+            return;
+        }
+        // price move = |price_after / price_before - 1| in basis points
+        let move_bps = ((price_after / price_before - BigRational::from_integer(1.into())).abs()
+            * BigRational::from_integer(10_000.into()))
+        .to_u64()
+        .unwrap_or(u64::MAX);
+        if pay_token_0 {
+            self.volatility_0_bps = self.volatility_0_bps.saturating_add(move_bps);
+        } else {
+            self.volatility_1_bps = self.volatility_1_bps.saturating_add(move_bps);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SECS: u64 = 1_000_000_000;
+
+    #[test]
+    fn test_fee_bps() {
+        let mut dynamic_fee = DynamicFee::new(10, 150, 50, 60).unwrap();
+        assert_eq!(dynamic_fee.fee_bps(true, 0), 10);
+
+        // 2% price move paying token_0
+        dynamic_fee.update(
+            true,
+            &BigRational::from_integer(100.into()),
+            &BigRational::from_integer(98.into()),
+            100 * SECS,
+        );
+        assert_eq!(dynamic_fee.volatility_0_bps, 200);
+        assert_eq!(dynamic_fee.fee_bps(true, 100 * SECS), 100 + 10);
+        // other direction is unaffected
+        assert_eq!(dynamic_fee.fee_bps(false, 100 * SECS), 10);
+        // half-life of 60 secs
+        assert_eq!(dynamic_fee.fee_bps(true, 160 * SECS), 50 + 10);
+
+        // bounded by max_fee_bps
+        dynamic_fee.update(
+            true,
+            &BigRational::from_integer(100.into()),
+            &BigRational::from_integer(80.into()),
+            160 * SECS,
+        );
+        assert_eq!(dynamic_fee.fee_bps(true, 160 * SECS), 150);
+    }
+
+    #[test]
+    fn test_new() {
+        assert!(DynamicFee::new(30, 20, 100, 60).is_err());
+        assert!(DynamicFee::new(20, 30, 100, 0).is_err());
+    }
+}
@@ -1,4 +1,10 @@
 pub mod check_token_balance;
+pub mod concentrated_pool;
+pub mod concentrated_pool_map;
+pub mod dynamic_fee;
 pub mod pool_map;
+pub mod pool_type;
+pub mod price_oracle;
 #[allow(clippy::module_inception)]
 pub mod stable_pool;
+pub mod stable_swap_pool;
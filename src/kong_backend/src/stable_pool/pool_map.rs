@@ -1,49 +1,74 @@
+use std::cell::RefCell;
+use std::collections::BTreeMap;
 use wildmatch::WildMatch;
 
+use crate::certified::certified_tree;
 use crate::ic::logging::error_log;
 use crate::stable_kong_settings::kong_settings_map;
+use crate::stable_limit_order::limit_order_map;
 use crate::stable_memory::POOL_MAP;
+use crate::stable_pool::pool_type::PoolType;
 use crate::stable_pool::stable_pool::{StablePool, StablePoolId};
+use crate::stable_pool_observation::pool_observation_map;
 use crate::stable_token::stable_token::StableToken;
-use crate::stable_token::token::Token;
+use crate::stable_token::token::{fee_tier_suffix, Token};
 use crate::stable_token::token_map;
 
+thread_local! {
+    // pools updated by a batch while it is staged. written to POOL_MAP only if every operation of the batch succeeds
+    static STAGED_POOLS: RefCell<Option<BTreeMap<u32, StablePool>>> = const { RefCell::new(None) };
+}
+
+// symbol is Symbol_Symbol, or Symbol_Symbol_FeeTier for the additional fee tiers of a pair
 fn symbol_with_chain(symbol: &str) -> Result<String, String> {
     let mut symbols = symbol.split('_');
     let symbol_0 = symbols.next().ok_or_else(|| format!("Invalid symbol {}", symbol))?;
     let symbol_1 = symbols.next().ok_or_else(|| format!("Invalid symbol {}", symbol))?;
+    let fee_tier = parse_fee_tier(symbols.next()).ok_or_else(|| format!("Invalid symbol {}", symbol))?;
     if symbols.next().is_some() {
         return Err(format!("Invalid symbol {}", symbol));
     }
 
     Ok(format!(
-        "{}_{}",
+        "{}_{}{}",
         token_map::symbol_with_chain(symbol_0)?,
-        token_map::symbol_with_chain(symbol_1)?
+        token_map::symbol_with_chain(symbol_1)?,
+        fee_tier_suffix(fee_tier)
     ))
 }
 
+// address is Address_Address, or Address_Address_FeeTier for the additional fee tiers of a pair
 fn address_with_chain(address: &str) -> Result<String, String> {
     let mut addresses = address.split('_');
     let address_0 = addresses.next().ok_or_else(|| format!("Invalid address {}", address))?;
     let address_1 = addresses.next().ok_or_else(|| format!("Invalid address {}", address))?;
+    let fee_tier = parse_fee_tier(addresses.next()).ok_or_else(|| format!("Invalid address {}", address))?;
     if addresses.next().is_some() {
         return Err(format!("Invalid address {}", address));
     }
 
     Ok(format!(
-        "{}_{}",
+        "{}_{}{}",
         token_map::address_with_chain(address_0)?,
-        token_map::address_with_chain(address_1)?
+        token_map::address_with_chain(address_1)?,
+        fee_tier_suffix(fee_tier)
     ))
 }
 
+/// returns None if the fee tier is not a valid number, Some(None) if there is no fee tier
+fn parse_fee_tier(fee_tier: Option<&str>) -> Option<Option<u8>> {
+    match fee_tier {
+        Some(fee_tier) => fee_tier.parse::<u8>().ok().map(Some),
+        None => Some(None),
+    }
+}
+
 pub fn symbol(token_0: &StableToken, token_1: &StableToken) -> String {
     format!("{}_{}", token_0.symbol(), token_1.symbol())
 }
 
 pub fn get_by_pool_id(pool_id: u32) -> Option<StablePool> {
-    POOL_MAP.with(|m| m.borrow().get(&StablePoolId(pool_id)))
+    POOL_MAP.with(|m| m.borrow().get(&StablePoolId(pool_id))).map(staged)
 }
 
 pub fn get_by_token_wildcard(token: &str) -> Vec<StablePool> {
@@ -51,7 +76,8 @@ pub fn get_by_token_wildcard(token: &str) -> Vec<StablePool> {
     POOL_MAP.with(|m| {
         m.borrow()
             .iter()
-            .filter_map(|(_, v)| {
+            .map(|(_, v)| staged(v))
+            .filter_map(|v| {
                 if search_token.matches(v.symbol().as_str())
                     || search_token.matches(v.address().as_str())
                     || search_token.matches(v.symbol_with_chain().as_str())
@@ -82,7 +108,7 @@ fn get_by_symbol(symbol: &str) -> Result<StablePool, String> {
     let symbol_with_chain = symbol_with_chain(symbol)?;
     POOL_MAP
         .with(|m| {
-            m.borrow().iter().find_map(|(_, v)| {
+            m.borrow().iter().map(|(_, v)| staged(v)).find_map(|v| {
                 if v.symbol_with_chain() == symbol_with_chain {
                     return Some(v);
                 }
@@ -98,7 +124,7 @@ fn get_by_address(address: &str) -> Result<StablePool, String> {
     let address_with_chain = address_with_chain(address)?;
     POOL_MAP
         .with(|m| {
-            m.borrow().iter().find_map(|(_, v)| {
+            m.borrow().iter().map(|(_, v)| staged(v)).find_map(|v| {
                 if v.address_with_chain() == address_with_chain {
                     return Some(v);
                 }
@@ -108,21 +134,56 @@ fn get_by_address(address: &str) -> Result<StablePool, String> {
         .ok_or_else(|| format!("Pool {} not found", address_with_chain))
 }
 
+/// get the first pool of a pair
 pub fn get_by_token_ids(token_id_0: u32, token_id_1: u32) -> Option<StablePool> {
+    get_by_token_ids_and_fee_tier(token_id_0, token_id_1, None)
+}
+
+/// get the pool of a pair in a fee tier. fee_tier None is the first pool of the pair
+/// the first pool of a pair also matches the fee tier of its LP fee
+pub fn get_by_token_ids_and_fee_tier(token_id_0: u32, token_id_1: u32, fee_tier: Option<u8>) -> Option<StablePool> {
     POOL_MAP.with(|m| {
-        m.borrow().iter().find_map(|(_, v)| {
-            if v.token_id_0 == token_id_0 && v.token_id_1 == token_id_1 {
-                return Some(v);
+        m.borrow().iter().map(|(_, v)| staged(v)).find_map(|v| {
+            if v.token_id_0 != token_id_0 || v.token_id_1 != token_id_1 {
+                return None;
+            }
+            match fee_tier {
+                None if v.fee_tier.is_none() => Some(v),
+                Some(fee_tier) if v.fee_tier_bps() == fee_tier => Some(v),
+                _ => None,
             }
-            None
         })
     })
 }
 
+/// get all the fee tier pools of a pair
+pub fn get_all_by_token_ids(token_id_0: u32, token_id_1: u32) -> Vec<StablePool> {
+    POOL_MAP.with(|m| {
+        m.borrow()
+            .iter()
+            .map(|(_, v)| staged(v))
+            .filter_map(|v| {
+                if v.token_id_0 == token_id_0 && v.token_id_1 == token_id_1 {
+                    Some(v)
+                } else {
+                    None
+                }
+            })
+            .collect()
+    })
+}
+
+/// get the first pool of a pair
 pub fn get_by_tokens(token_0: &str, token_1: &str) -> Result<StablePool, String> {
+    get_by_tokens_and_fee_tier(token_0, token_1, None)
+}
+
+/// get the pool of a pair in a fee tier. fee_tier None is the first pool of the pair
+pub fn get_by_tokens_and_fee_tier(token_0: &str, token_1: &str, fee_tier: Option<u8>) -> Result<StablePool, String> {
     let token_0: StableToken = token_map::get_by_token(token_0)?;
     let token_1 = token_map::get_by_token(token_1)?;
-    get_by_token_ids(token_0.token_id(), token_1.token_id()).ok_or_else(|| format!("Pool {} not found", symbol(&token_0, &token_1)))
+    get_by_token_ids_and_fee_tier(token_0.token_id(), token_1.token_id(), fee_tier)
+        .ok_or_else(|| format!("Pool {}{} not found", symbol(&token_0, &token_1), fee_tier_suffix(fee_tier)))
 }
 
 /// Get pool by LP token's id.
@@ -130,7 +191,8 @@ pub fn get_by_lp_token_id(lp_token_id: u32) -> Option<StablePool> {
     POOL_MAP.with(|m| {
         m.borrow()
             .iter()
-            .find_map(|(_, v)| if v.lp_token_id == lp_token_id { Some(v) } else { None })
+            .map(|(_, v)| staged(v))
+            .find_map(|v| if v.lp_token_id == lp_token_id { Some(v) } else { None })
     })
 }
 
@@ -139,12 +201,13 @@ pub fn get() -> Vec<StablePool> {
     POOL_MAP.with(|m| {
         m.borrow()
             .iter()
-            .filter_map(|(_, v)| if !v.is_removed { Some(v) } else { None })
+            .map(|(_, v)| staged(v))
+            .filter_map(|v| if !v.is_removed { Some(v) } else { None })
             .collect()
     })
 }
 
-/// check if pool exists
+/// check if any pool of the pair exists, in either order
 pub fn exists(token_0: &StableToken, token_1: &StableToken) -> bool {
     POOL_MAP.with(|m| {
         m.borrow().iter().any(|(_, v)| {
@@ -155,27 +218,83 @@ pub fn exists(token_0: &StableToken, token_1: &StableToken) -> bool {
 }
 
 pub fn insert(pool: &StablePool) -> Result<u32, String> {
-    if exists(&pool.token_0(), &pool.token_1()) {
+    // the first pool of a pair can not exist in either order. an additional fee tier can not duplicate a tier of the pair
+    let pool_exists = match pool.fee_tier {
+        None => exists(&pool.token_0(), &pool.token_1()),
+        Some(fee_tier) => get_by_token_ids_and_fee_tier(pool.token_id_0, pool.token_id_1, Some(fee_tier)).is_some(),
+    };
+    if pool_exists {
         Err(format!("Pool {} already exists", pool.symbol()))?
     }
 
     let insert_pool = POOL_MAP.with(|m| {
         let mut map = m.borrow_mut();
         let pool_id = kong_settings_map::inc_pool_map_idx();
-        let insert_pool = StablePool { pool_id, ..pool.clone() };
+        let mut insert_pool = StablePool { pool_id, ..pool.clone() };
+        if let PoolType::Concentrated(ref mut concentrated_pool) = insert_pool.pool_type {
+            concentrated_pool.pool_id = pool_id;
+            concentrated_pool.commit();
+        }
         map.insert(StablePoolId(pool_id), insert_pool.clone());
         insert_pool
     });
+    certified_tree::update_pool(&insert_pool);
 
     let _ = archive_to_kong_data(&insert_pool);
     Ok(insert_pool.pool_id)
 }
 
 pub fn update(pool: &StablePool) {
+    if stage_update(pool) {
+        return;
+    }
+    // write the ticks and positions changed by the update
+    if let PoolType::Concentrated(concentrated_pool) = &pool.pool_type {
+        concentrated_pool.commit();
+    }
     POOL_MAP.with(|m| m.borrow_mut().insert(StablePoolId(pool.pool_id), pool.clone()));
+    certified_tree::update_pool(pool);
+    // record the cumulative prices for TWAP
+    pool_observation_map::observe(pool);
+    // pool price may have changed, open limit orders need to be checked
+    limit_order_map::set_scan_pending();
     let _ = archive_to_kong_data(pool);
 }
 
+/// stage the pool updates in memory, so the operations of a batch can be discarded if one fails
+pub fn stage() {
+    STAGED_POOLS.with(|s| *s.borrow_mut() = Some(BTreeMap::new()));
+}
+
+/// write the staged pools
+pub fn commit_staged() {
+    let pools = STAGED_POOLS.with(|s| s.borrow_mut().take()).unwrap_or_default();
+    pools.values().for_each(update);
+}
+
+/// drop the staged pools, including the ticks and positions cached by concentrated pools
+pub fn discard_staged() {
+    STAGED_POOLS.with(|s| *s.borrow_mut() = None);
+}
+
+// returns true if the pool update was staged
+fn stage_update(pool: &StablePool) -> bool {
+    STAGED_POOLS.with(|s| match s.borrow_mut().as_mut() {
+        Some(pools) => {
+            pools.insert(pool.pool_id, pool.clone());
+            true
+        }
+        None => false,
+    })
+}
+
+// the staged version of the pool, if it has been updated by the batch
+fn staged(pool: StablePool) -> StablePool {
+    STAGED_POOLS
+        .with(|s| s.borrow().as_ref().and_then(|pools| pools.get(&pool.pool_id).cloned()))
+        .unwrap_or(pool)
+}
+
 pub fn remove(pool_id: u32) -> Result<(), String> {
     let pool = get_by_pool_id(pool_id).ok_or_else(|| format!("Pool #{} not found", pool_id))?;
 
@@ -232,3 +351,71 @@ fn archive_to_kong_data(pool: &StablePool) -> Result<(), String> {
 
     Ok(())
 }
+
+#[cfg(test)]
+pub mod tests {
+    use super::*;
+    use candid::{Nat, Principal};
+
+    use crate::helpers::nat_helpers::nat_zero;
+    use crate::stable_memory::TOKEN_MAP;
+    use crate::stable_token::{ic_token::ICToken, lp_token::LPToken, stable_token::StableTokenId};
+
+    /// LP token of the pool inserted by insert_pool
+    pub const LP_TOKEN_ID: u32 = 3;
+
+    /// IC token with symbol T{token_id} and no fee
+    pub fn ic_token(token_id: u32) -> StableToken {
+        StableToken::IC(ICToken {
+            token_id,
+            name: format!("Token {}", token_id),
+            symbol: format!("T{}", token_id),
+            canister_id: Principal::from_slice(&[token_id as u8]),
+            decimals: 8,
+            fee: nat_zero(),
+            icrc1: true,
+            icrc2: true,
+            icrc3: true,
+            is_removed: false,
+            on_probation: false,
+            listing_deposit: None,
+        })
+    }
+
+    pub fn insert_token(token: StableToken) {
+        // settings are initialized from the stable maps, so must be before any of them are borrowed
+        kong_settings_map::get();
+        TOKEN_MAP.with(|m| m.borrow_mut().insert(StableTokenId(token.token_id()), token));
+    }
+
+    /// pool of the IC tokens token_id_0 and token_id_1 with balance of each token, not inserted in POOL_MAP
+    pub fn pool(pool_id: u32, token_id_0: u32, token_id_1: u32, balance: u64) -> StablePool {
+        insert_token(ic_token(token_id_0));
+        insert_token(ic_token(token_id_1));
+        StablePool {
+            pool_id,
+            balance_0: Nat::from(balance),
+            balance_1: Nat::from(balance),
+            ..StablePool::new(token_id_0, token_id_1, 30, 10, 1_000 + pool_id, PoolType::ConstantProduct, None)
+        }
+    }
+
+    /// T1_T2 pool with balance of each token and its LP token, returns the pool
+    pub fn insert_pool(balance: u64) -> StablePool {
+        let pool = pool(0, 1, 2, balance);
+        insert_token(StableToken::LP(LPToken {
+            token_id: LP_TOKEN_ID,
+            symbol: "T1_T2".to_string(),
+            address: "1_2".to_string(),
+            decimals: 8,
+            is_removed: false,
+            ledger_id: None,
+        }));
+        let pool_id = insert(&StablePool {
+            lp_token_id: LP_TOKEN_ID,
+            ..pool
+        })
+        .unwrap();
+        get_by_pool_id(pool_id).unwrap()
+    }
+}
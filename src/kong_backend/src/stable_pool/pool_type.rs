@@ -0,0 +1,20 @@
+use candid::CandidType;
+use serde::{Deserialize, Serialize};
+
+use super::concentrated_pool::ConcentratedPool;
+use super::stable_swap_pool::StableSwapPool;
+
+#[derive(CandidType, Debug, Clone, Default, Serialize, Deserialize)]
+pub enum PoolType {
+    #[default]
+    ConstantProduct,
+    Concentrated(ConcentratedPool),
+    StableSwap(StableSwapPool),
+}
+
+impl PoolType {
+    /// concentrated pools track LP positions instead of minting LP tokens
+    pub fn has_lp_token(&self) -> bool {
+        !matches!(self, PoolType::Concentrated(_))
+    }
+}
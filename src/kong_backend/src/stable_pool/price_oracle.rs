@@ -0,0 +1,96 @@
+use candid::Nat;
+use num::{BigInt, BigRational, Zero};
+
+use crate::helpers::nat_helpers::{nat_add, nat_divide, nat_multiply, nat_subtract, nat_to_bigint};
+
+// cumulative prices are stored as Q112 fixed point numbers
+const Q112_BITS: usize = 112;
+
+fn price_to_q112(price: &BigRational) -> Nat {
+    if price.denom().is_zero() {
+        return Nat::from(0_u8);
+    }
+    let q112 = (price.numer() << Q112_BITS) / price.denom();
+    match q112.to_biguint() {
+        Some(q112) => Nat::from(q112),
+        None => Nat::from(0_u8),
+    }
+}
+
+/// add price * elapsed_secs to the cumulative prices
+/// price_0_cumulative accumulates the price of token_0 in token_1, price_1_cumulative the price of token_1 in token_0
+pub fn accumulate(price_0_cumulative: &Nat, price_1_cumulative: &Nat, price: &BigRational, elapsed_secs: u64) -> (Nat, Nat) {
+    if price.is_zero() || elapsed_secs == 0 {
+        return (price_0_cumulative.clone(), price_1_cumulative.clone());
+    }
+    let elapsed_secs = Nat::from(elapsed_secs);
+    let price_0 = price_to_q112(price);
+    let price_1 = price_to_q112(&price.recip());
+    (
+        nat_add(price_0_cumulative, &nat_multiply(&price_0, &elapsed_secs)),
+        nat_add(price_1_cumulative, &nat_multiply(&price_1, &elapsed_secs)),
+    )
+}
+
+/// cumulative price at ts_secs, linearly interpolated between the observations (ts_secs, cumulative) sorted by time
+/// returns None if ts_secs is outside the observed period
+pub fn cumulative_at(observations: &[(u64, Nat)], ts_secs: u64) -> Option<Nat> {
+    let index = observations.partition_point(|(ts, _)| *ts <= ts_secs);
+    if index == 0 {
+        None?
+    }
+    let (before_ts, before_cumulative) = &observations[index - 1];
+    if *before_ts == ts_secs {
+        return Some(before_cumulative.clone());
+    }
+    let (after_ts, after_cumulative) = observations.get(index)?;
+    // cumulative prices only increase
+    let delta = nat_subtract(after_cumulative, before_cumulative)?;
+    let interpolated = nat_divide(
+        &nat_multiply(&delta, &Nat::from(ts_secs - before_ts)),
+        &Nat::from(after_ts - before_ts),
+    )?;
+    Some(nat_add(before_cumulative, &interpolated))
+}
+
+/// time-weighted average price between two cumulative prices window_secs apart
+pub fn twap(start_cumulative: &Nat, end_cumulative: &Nat, window_secs: u64) -> Option<BigRational> {
+    if window_secs == 0 {
+        None?
+    }
+    let delta = nat_subtract(end_cumulative, start_cumulative)?;
+    Some(BigRational::new(nat_to_bigint(&delta), BigInt::from(window_secs) << Q112_BITS))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use num_traits::ToPrimitive;
+
+    #[test]
+    fn test_accumulate_and_twap() {
+        let zero = Nat::from(0_u8);
+        // price 2.0 for 10 secs then 4.0 for 30 secs
+        let (c0, c1) = accumulate(&zero, &zero, &BigRational::from_integer(2.into()), 10);
+        let (c0, c1) = accumulate(&c0, &c1, &BigRational::from_integer(4.into()), 30);
+
+        assert_eq!(twap(&zero, &c0, 40).unwrap().to_f64().unwrap(), 3.5);
+        assert_eq!(twap(&zero, &c1, 40).unwrap().to_f64().unwrap(), 0.3125);
+    }
+
+    #[test]
+    fn test_cumulative_at() {
+        let observations = vec![
+            (100, Nat::from(1_000_u32)),
+            (110, Nat::from(2_000_u32)),
+            (130, Nat::from(2_400_u32)),
+        ];
+
+        assert_eq!(cumulative_at(&observations, 99), None);
+        assert_eq!(cumulative_at(&observations, 100), Some(Nat::from(1_000_u32)));
+        assert_eq!(cumulative_at(&observations, 105), Some(Nat::from(1_500_u32)));
+        assert_eq!(cumulative_at(&observations, 120), Some(Nat::from(2_200_u32)));
+        assert_eq!(cumulative_at(&observations, 130), Some(Nat::from(2_400_u32)));
+        assert_eq!(cumulative_at(&observations, 131), None);
+    }
+}
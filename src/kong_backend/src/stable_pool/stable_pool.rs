@@ -4,11 +4,17 @@ use num::BigRational;
 use serde::{Deserialize, Serialize};
 
 use crate::helpers::math_helpers::price_rounded;
-use crate::helpers::nat_helpers::{nat_add, nat_is_zero, nat_to_bigint, nat_to_decimal_precision, nat_zero};
+use crate::helpers::nat_helpers::{nat_add, nat_is_zero, nat_multiply, nat_to_bigint, nat_to_decimal_precision, nat_zero};
+use crate::ic::get_time::get_time;
 use crate::stable_token::stable_token::StableToken;
-use crate::stable_token::token::Token;
+use crate::stable_token::token::{fee_tier_suffix, Token};
 use crate::stable_token::token_map;
 
+use super::dynamic_fee::DynamicFee;
+use super::pool_type::PoolType;
+use super::price_oracle;
+use super::stable_swap_pool;
+
 #[derive(CandidType, Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
 pub struct StablePoolId(pub u32);
 
@@ -40,6 +46,30 @@ pub struct StablePool {
     pub lp_token_id: u32, // token id of the LP token
     #[serde(default = "false_bool")]
     pub is_removed: bool,
+    #[serde(default)]
+    pub pool_type: PoolType,
+    #[serde(default)]
+    pub price_0_cumulative: Nat, // sum of price of token_0 in token_1 x seconds, Q112 fixed point
+    #[serde(default)]
+    pub price_1_cumulative: Nat, // sum of price of token_1 in token_0 x seconds, Q112 fixed point
+    #[serde(default)]
+    pub price_cumulative_secs: u64, // time in seconds of the last update to the cumulative prices
+    #[serde(default)]
+    pub dynamic_fee: Option<DynamicFee>, // if set, LP fee varies with recent volatility instead of lp_fee_bps
+    #[serde(default)]
+    pub fee_tier: Option<u8>, // fee tier in basis points of an additional pool of the pair, None for the first pool of the pair
+    #[serde(default)]
+    pub boost_supply: Nat, // extra weight of the boosting LP token locks in the LP fee share
+    #[serde(default)]
+    pub boost_expiry_ts: Option<u64>, // earliest unlock time of the boosting locks, or earlier
+    #[serde(default)]
+    pub boost_fee_0: Nat, // LP fees credited to the locks and not yet paid out
+    #[serde(default)]
+    pub boost_fee_1: Nat,
+    #[serde(default)]
+    pub boost_fee_growth_0_x128: Nat, // boost fees per unit of boost_supply, Q128 fixed point
+    #[serde(default)]
+    pub boost_fee_growth_1_x128: Nat,
 }
 
 fn false_bool() -> bool {
@@ -47,7 +77,15 @@ fn false_bool() -> bool {
 }
 
 impl StablePool {
-    pub fn new(token_id_0: u32, token_id_1: u32, lp_fee_bps: u8, kong_fee_bps: u8, lp_token_id: u32) -> Self {
+    pub fn new(
+        token_id_0: u32,
+        token_id_1: u32,
+        lp_fee_bps: u8,
+        kong_fee_bps: u8,
+        lp_token_id: u32,
+        pool_type: PoolType,
+        fee_tier: Option<u8>,
+    ) -> Self {
         Self {
             pool_id: 0,
             token_id_0,
@@ -62,27 +100,54 @@ impl StablePool {
             kong_fee_bps,
             lp_token_id,
             is_removed: false,
+            pool_type,
+            price_0_cumulative: nat_zero(),
+            price_1_cumulative: nat_zero(),
+            price_cumulative_secs: 0,
+            dynamic_fee: None,
+            fee_tier,
+            boost_supply: nat_zero(),
+            boost_expiry_ts: None,
+            boost_fee_0: nat_zero(),
+            boost_fee_1: nat_zero(),
+            boost_fee_growth_0_x128: nat_zero(),
+            boost_fee_growth_1_x128: nat_zero(),
         }
     }
 
+    /// fee tier of the pool in basis points. the first pool of a pair is in the tier of its LP fee
+    pub fn fee_tier_bps(&self) -> u8 {
+        self.fee_tier.unwrap_or(self.lp_fee_bps)
+    }
+
     pub fn symbol(&self) -> String {
-        format!("{}_{}", self.symbol_0(), self.symbol_1())
+        format!("{}_{}{}", self.symbol_0(), self.symbol_1(), fee_tier_suffix(self.fee_tier))
     }
 
     pub fn symbol_with_chain(&self) -> String {
-        format!("{}_{}", self.token_0().symbol_with_chain(), self.token_1().symbol_with_chain())
+        format!(
+            "{}_{}{}",
+            self.token_0().symbol_with_chain(),
+            self.token_1().symbol_with_chain(),
+            fee_tier_suffix(self.fee_tier)
+        )
     }
 
     pub fn address(&self) -> String {
-        format!("{}_{}", self.address_0(), self.address_1())
+        format!("{}_{}{}", self.address_0(), self.address_1(), fee_tier_suffix(self.fee_tier))
     }
 
     pub fn address_with_chain(&self) -> String {
-        format!("{}_{}", self.token_0().address_with_chain(), self.token_1().address_with_chain())
+        format!(
+            "{}_{}{}",
+            self.token_0().address_with_chain(),
+            self.token_1().address_with_chain(),
+            fee_tier_suffix(self.fee_tier)
+        )
     }
 
     pub fn name(&self) -> String {
-        format!("{}_{} Liquidity Pool", self.symbol_0(), self.symbol_1())
+        format!("{} Liquidity Pool", self.symbol())
     }
 
     pub fn token_0(&self) -> StableToken {
@@ -122,6 +187,10 @@ impl StablePool {
     }
 
     pub fn get_price(&self) -> Option<BigRational> {
+        if let PoolType::Concentrated(concentrated_pool) = &self.pool_type {
+            return self.get_concentrated_price(&concentrated_pool.sqrt_price_x96);
+        }
+
         let reserve_0 = nat_add(&self.balance_0, &self.lp_fee_0);
         let reserve_1 = nat_add(&self.balance_1, &self.lp_fee_1);
         if nat_is_zero(&reserve_0) {
@@ -131,15 +200,83 @@ impl StablePool {
         let token_0 = self.token_0();
         let token_1 = self.token_1();
         let max_decimals = std::cmp::max(token_0.decimals(), token_1.decimals());
-        let reserve_0 = nat_to_bigint(&nat_to_decimal_precision(&reserve_0, token_0.decimals(), max_decimals));
-        let reserve_1 = nat_to_bigint(&nat_to_decimal_precision(&reserve_1, token_1.decimals(), max_decimals));
+        let reserve_0 = nat_to_decimal_precision(&reserve_0, token_0.decimals(), max_decimals);
+        let reserve_1 = nat_to_decimal_precision(&reserve_1, token_1.decimals(), max_decimals);
+
+        if let PoolType::StableSwap(stable_swap) = &self.pool_type {
+            return stable_swap_pool::get_price(&reserve_0, &reserve_1, stable_swap.amp(get_time()));
+        }
+
+        Some(BigRational::new(nat_to_bigint(&reserve_1), nat_to_bigint(&reserve_0)))
+    }
+
+    // price = sqrt_price_x96^2 / 2^192, converted to max_decimals precision
+    fn get_concentrated_price(&self, sqrt_price_x96: &Nat) -> Option<BigRational> {
+        let token_0 = self.token_0();
+        let token_1 = self.token_1();
+        let max_decimals = std::cmp::max(token_0.decimals(), token_1.decimals());
+        let numerator = nat_to_decimal_precision(&nat_multiply(sqrt_price_x96, sqrt_price_x96), token_1.decimals(), max_decimals);
+        let denominator = nat_to_decimal_precision(&Nat::from(1_u8), token_0.decimals(), max_decimals);
 
-        Some(BigRational::new(reserve_1, reserve_0))
+        Some(BigRational::new(nat_to_bigint(&numerator), nat_to_bigint(&denominator) << 192))
     }
 
     pub fn get_price_as_f64(&self) -> Option<f64> {
         price_rounded(&self.get_price()?)
     }
+
+    /// LP fee in basis points for a swap paying token_0 (pay_token_0 = true) or token_1
+    pub fn swap_lp_fee_bps(&self, pay_token_0: bool, ts: u64) -> u8 {
+        match &self.dynamic_fee {
+            Some(dynamic_fee) => dynamic_fee.fee_bps(pay_token_0, ts),
+            None => self.lp_fee_bps,
+        }
+    }
+
+    /// accumulate the current price over the time since the last update
+    /// must be called before the pool's balances change so the price before the change is accumulated
+    pub fn update_price_cumulative(&mut self, ts: u64) {
+        let ts_secs = ts / 1_000_000_000;
+        if ts_secs <= self.price_cumulative_secs {
+            return;
+        }
+        (self.price_0_cumulative, self.price_1_cumulative) = self.price_cumulative_at(ts_secs);
+        self.price_cumulative_secs = ts_secs;
+    }
+
+    /// cumulative prices extrapolated to ts_secs with the current price
+    pub fn price_cumulative_at(&self, ts_secs: u64) -> (Nat, Nat) {
+        // accumulation starts from the first update of the pool
+        if self.price_cumulative_secs == 0 || ts_secs <= self.price_cumulative_secs {
+            return (self.price_0_cumulative.clone(), self.price_1_cumulative.clone());
+        }
+        match self.get_price() {
+            Some(price) => price_oracle::accumulate(
+                &self.price_0_cumulative,
+                &self.price_1_cumulative,
+                &price,
+                ts_secs - self.price_cumulative_secs,
+            ),
+            None => (self.price_0_cumulative.clone(), self.price_1_cumulative.clone()),
+        }
+    }
+
+    /// checks the pool's balances satisfy the invariant of its pool type
+    /// only StableSwap pools are checked, the invariant must converge and be within the constant product and constant sum bounds
+    pub fn check_invariant(&self) -> Result<(), String> {
+        if let PoolType::StableSwap(stable_swap) = &self.pool_type {
+            let token_0 = self.token_0();
+            let token_1 = self.token_1();
+            let max_decimals = std::cmp::max(token_0.decimals(), token_1.decimals());
+            let reserve_0 = nat_to_decimal_precision(&nat_add(&self.balance_0, &self.lp_fee_0), token_0.decimals(), max_decimals);
+            let reserve_1 = nat_to_decimal_precision(&nat_add(&self.balance_1, &self.lp_fee_1), token_1.decimals(), max_decimals);
+            if !nat_is_zero(&reserve_0) || !nat_is_zero(&reserve_1) {
+                stable_swap_pool::check_invariant(&reserve_0, &reserve_1, stable_swap.amp(get_time()))?;
+            }
+        }
+
+        Ok(())
+    }
 }
 
 impl Storable for StablePool {
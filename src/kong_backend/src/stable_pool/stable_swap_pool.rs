@@ -0,0 +1,247 @@
+use candid::{CandidType, Nat};
+use num::BigRational;
+use serde::{Deserialize, Serialize};
+
+use crate::helpers::nat_helpers::{nat_add, nat_divide, nat_is_zero, nat_multiply, nat_sqrt, nat_subtract, nat_to_bigint, nat_zero};
+
+pub const MIN_AMP: u64 = 1;
+pub const MAX_AMP: u64 = 1_000_000;
+pub const MAX_AMP_CHANGE: u64 = 10; // amp can change by at most 10x in a single ramp
+
+const MAX_ITERATIONS: usize = 255;
+
+/// StableSwap (Curve) state of a pool
+/// - invariant is A * n^n * (x + y) + D = A * n^n * D + D^3 / (n^n * x * y) with n = 2
+/// - amp (A) can be ramped linearly from initial_amp to future_amp between initial_amp_ts and future_amp_ts
+/// - reserves are expected in the same decimal precision
+#[derive(CandidType, Debug, Clone, Serialize, Deserialize)]
+pub struct StableSwapPool {
+    pub initial_amp: u64,
+    pub future_amp: u64,
+    pub initial_amp_ts: u64,
+    pub future_amp_ts: u64,
+}
+
+// A * n^n
+fn amp_n_n(amp: u64) -> Nat {
+    Nat::from(amp * 4)
+}
+
+fn converged(n1: &Nat, n2: &Nat) -> bool {
+    let diff = if n1 > n2 { nat_subtract(n1, n2) } else { nat_subtract(n2, n1) };
+    diff.is_some_and(|diff| diff <= 1_u8)
+}
+
+/// invariant D of the pool. solved with Newton's method
+/// D = (Ann * S + n * D_P) * D / ((Ann - 1) * D + (n + 1) * D_P) where D_P = D^3 / (4 * x * y)
+pub fn get_d(reserve_0: &Nat, reserve_1: &Nat, amp: u64) -> Result<Nat, String> {
+    if nat_is_zero(reserve_0) || nat_is_zero(reserve_1) {
+        Err("Zero balances in pool")?
+    }
+
+    let ann = amp_n_n(amp);
+    let sum = nat_add(reserve_0, reserve_1);
+    let mut d = sum.clone();
+    for _ in 0..MAX_ITERATIONS {
+        let d_p = nat_divide(&nat_multiply(&d, &d), &nat_multiply(reserve_0, &Nat::from(2_u8))).ok_or("Invalid D")?;
+        let d_p = nat_divide(&nat_multiply(&d_p, &d), &nat_multiply(reserve_1, &Nat::from(2_u8))).ok_or("Invalid D")?;
+        let numerator = nat_multiply(&nat_add(&nat_multiply(&ann, &sum), &nat_multiply(&d_p, &Nat::from(2_u8))), &d);
+        let denominator = nat_add(
+            &nat_multiply(&nat_subtract(&ann, &Nat::from(1_u8)).ok_or("Invalid amp")?, &d),
+            &nat_multiply(&d_p, &Nat::from(3_u8)),
+        );
+        let d_prev = d;
+        d = nat_divide(&numerator, &denominator).ok_or("Invalid D")?;
+        if converged(&d, &d_prev) {
+            return Ok(d);
+        }
+    }
+
+    Err("StableSwap invariant did not converge".to_string())
+}
+
+/// reserve y that keeps the invariant D for reserve x. solved with Newton's method
+/// y = (y^2 + c) / (2 * y + b - D) where b = x + D / Ann and c = D^3 / (4 * x * Ann)
+pub fn get_y(reserve_x: &Nat, d: &Nat, amp: u64) -> Result<Nat, String> {
+    if nat_is_zero(reserve_x) {
+        Err("Zero balances in pool")?
+    }
+
+    let ann = amp_n_n(amp);
+    let c = nat_divide(&nat_multiply(d, d), &nat_multiply(reserve_x, &Nat::from(2_u8))).ok_or("Invalid y")?;
+    let c = nat_divide(&nat_multiply(&c, d), &nat_multiply(&ann, &Nat::from(2_u8))).ok_or("Invalid y")?;
+    let b = nat_add(reserve_x, &nat_divide(d, &ann).ok_or("Invalid y")?);
+    let mut y = d.clone();
+    for _ in 0..MAX_ITERATIONS {
+        let numerator = nat_add(&nat_multiply(&y, &y), &c);
+        let denominator = nat_subtract(&nat_add(&nat_multiply(&y, &Nat::from(2_u8)), &b), d).ok_or("Invalid y")?;
+        let y_prev = y;
+        y = nat_divide(&numerator, &denominator).ok_or("Invalid y")?;
+        if converged(&y, &y_prev) {
+            return Ok(y);
+        }
+    }
+
+    Err("StableSwap invariant did not converge".to_string())
+}
+
+/// amount out for swapping amount_in, before any fees
+/// amount_out is rounded down by 1 so rounding errors are in favor of the pool
+pub fn swap_amount(reserve_in: &Nat, reserve_out: &Nat, amount_in: &Nat, amp: u64) -> Result<Nat, String> {
+    let d = get_d(reserve_in, reserve_out, amp)?;
+    let y = get_y(&nat_add(reserve_in, amount_in), &d, amp)?;
+    let amount_out = nat_subtract(reserve_out, &y).unwrap_or(nat_zero());
+    Ok(nat_subtract(&amount_out, &Nat::from(1_u8)).unwrap_or(nat_zero()))
+}
+
+/// marginal price of token_0 in token_1, -dy/dx of the invariant
+/// price = (4 * Ann * x^2 * y^2 + D^3 * y) / (4 * Ann * x^2 * y^2 + D^3 * x)
+pub fn get_price(reserve_0: &Nat, reserve_1: &Nat, amp: u64) -> Option<BigRational> {
+    let d = get_d(reserve_0, reserve_1, amp).ok()?;
+    let d_3 = nat_multiply(&nat_multiply(&d, &d), &d);
+    let x_y = nat_multiply(reserve_0, reserve_1);
+    let ann_x_y = nat_multiply(&nat_multiply(&amp_n_n(amp), &Nat::from(4_u8)), &nat_multiply(&x_y, &x_y));
+    let numerator = nat_add(&ann_x_y, &nat_multiply(&d_3, reserve_1));
+    let denominator = nat_add(&ann_x_y, &nat_multiply(&d_3, reserve_0));
+
+    Some(BigRational::new(nat_to_bigint(&numerator), nat_to_bigint(&denominator)))
+}
+
+/// checks the reserves satisfy the invariant
+/// - D is between the constant product bound 2 * sqrt(x * y) and the constant sum bound x + y
+/// - solving the invariant for reserve_1 gives back reserve_1
+pub fn check_invariant(reserve_0: &Nat, reserve_1: &Nat, amp: u64) -> Result<Nat, String> {
+    let d = get_d(reserve_0, reserve_1, amp)?;
+
+    let tolerance = Nat::from(2_u8);
+    let constant_product_d = nat_multiply(&nat_sqrt(&nat_multiply(reserve_0, reserve_1)), &Nat::from(2_u8));
+    let constant_sum_d = nat_add(reserve_0, reserve_1);
+    if nat_add(&d, &tolerance) < constant_product_d || d > nat_add(&constant_sum_d, &tolerance) {
+        Err(format!(
+            "Invariant D {} out of bounds [{}, {}]",
+            d, constant_product_d, constant_sum_d
+        ))?
+    }
+
+    let y = get_y(reserve_0, &d, amp)?;
+    // allow for rounding errors of the Newton iterations relative to the size of the reserves
+    let tolerance = nat_add(
+        &tolerance,
+        &nat_divide(reserve_1, &Nat::from(1_000_000_000_u64)).unwrap_or(nat_zero()),
+    );
+    let diff = if y > *reserve_1 {
+        nat_subtract(&y, reserve_1)
+    } else {
+        nat_subtract(reserve_1, &y)
+    }
+    .unwrap_or(nat_zero());
+    if diff > tolerance {
+        Err(format!("Invariant D {} gives balance {} instead of {}", d, y, reserve_1))?
+    }
+
+    Ok(d)
+}
+
+impl StableSwapPool {
+    pub fn new(amp: u64) -> Result<Self, String> {
+        if !(MIN_AMP..=MAX_AMP).contains(&amp) {
+            Err(format!("Amp must be between {} and {}", MIN_AMP, MAX_AMP))?
+        }
+        Ok(Self {
+            initial_amp: amp,
+            future_amp: amp,
+            initial_amp_ts: 0,
+            future_amp_ts: 0,
+        })
+    }
+
+    /// amp at time ts, linearly interpolated while ramping
+    pub fn amp(&self, ts: u64) -> u64 {
+        if ts >= self.future_amp_ts || self.future_amp_ts <= self.initial_amp_ts {
+            return self.future_amp;
+        }
+        let elapsed = (ts.saturating_sub(self.initial_amp_ts)) as u128;
+        let duration = (self.future_amp_ts - self.initial_amp_ts) as u128;
+        let (initial_amp, future_amp) = (self.initial_amp as u128, self.future_amp as u128);
+        let amp = if future_amp > initial_amp {
+            initial_amp + (future_amp - initial_amp) * elapsed / duration
+        } else {
+            initial_amp - (initial_amp - future_amp) * elapsed / duration
+        };
+        amp as u64
+    }
+
+    /// ramp amp from the current amp to future_amp, reaching it at future_amp_ts
+    /// future_amp_ts <= ts changes amp immediately
+    pub fn ramp_amp(&mut self, future_amp: u64, future_amp_ts: u64, ts: u64) -> Result<(), String> {
+        if !(MIN_AMP..=MAX_AMP).contains(&future_amp) {
+            Err(format!("Amp must be between {} and {}", MIN_AMP, MAX_AMP))?
+        }
+        let amp = self.amp(ts);
+        if future_amp > amp * MAX_AMP_CHANGE || amp > future_amp * MAX_AMP_CHANGE {
+            Err(format!("Amp can change by at most {}x", MAX_AMP_CHANGE))?
+        }
+
+        self.initial_amp = amp;
+        self.future_amp = future_amp;
+        self.initial_amp_ts = ts;
+        self.future_amp_ts = std::cmp::max(future_amp_ts, ts);
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_get_d() {
+        // balanced pool, D = x + y
+        let reserve = Nat::from(1_000_000_000_000_u64);
+        let d = get_d(&reserve, &reserve, 100).unwrap();
+        assert!(converged(&d, &Nat::from(2_000_000_000_000_u64)));
+        // imbalanced pool, D is between the constant product and constant sum bounds
+        let (reserve_0, reserve_1) = (Nat::from(1_000_000_000_000_u64), Nat::from(500_000_000_000_u64));
+        assert!(check_invariant(&reserve_0, &reserve_1, 100).is_ok());
+        assert!(check_invariant(&reserve_0, &reserve_1, 1).is_ok());
+    }
+
+    #[test]
+    fn test_swap_amount() {
+        let reserve = Nat::from(1_000_000_000_000_u64);
+        let amount_in = Nat::from(10_000_000_000_u64);
+        // higher amp gives less slippage, both less than constant product
+        let amount_out_100 = swap_amount(&reserve, &reserve, &amount_in, 100).unwrap();
+        let amount_out_10 = swap_amount(&reserve, &reserve, &amount_in, 10).unwrap();
+        let constant_product_out = nat_divide(&nat_multiply(&amount_in, &reserve), &nat_add(&reserve, &amount_in)).unwrap();
+        assert!(amount_out_100 < amount_in);
+        assert!(amount_out_100 > amount_out_10);
+        assert!(amount_out_10 > constant_product_out);
+        // invariant is kept after the swap
+        let d_before = get_d(&reserve, &reserve, 100).unwrap();
+        let reserve_out = nat_subtract(&reserve, &amount_out_100).unwrap();
+        let d_after = get_d(&nat_add(&reserve, &amount_in), &reserve_out, 100).unwrap();
+        assert!(d_after >= d_before);
+    }
+
+    #[test]
+    fn test_get_price() {
+        let reserve = Nat::from(1_000_000_u64);
+        assert_eq!(get_price(&reserve, &reserve, 100).unwrap(), BigRational::from_integer(1.into()));
+        // more token_0 in the pool makes token_0 cheaper
+        let price = get_price(&Nat::from(2_000_000_u64), &reserve, 100).unwrap();
+        assert!(price < BigRational::from_integer(1.into()));
+    }
+
+    #[test]
+    fn test_ramp_amp() {
+        let mut pool = StableSwapPool::new(100).unwrap();
+        assert!(pool.ramp_amp(2_000, 200, 100).is_err());
+        pool.ramp_amp(200, 200, 100).unwrap();
+        assert_eq!(pool.amp(100), 100);
+        assert_eq!(pool.amp(150), 150);
+        assert_eq!(pool.amp(300), 200);
+        pool.ramp_amp(50, 0, 300).unwrap();
+        assert_eq!(pool.amp(300), 50);
+    }
+}
@@ -0,0 +1,3 @@
+pub mod pool_observation_map;
+#[allow(clippy::module_inception)]
+pub mod stable_pool_observation;
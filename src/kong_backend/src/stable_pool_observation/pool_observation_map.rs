@@ -0,0 +1,50 @@
+use super::stable_pool_observation::{StablePoolObservation, StablePoolObservationId};
+
+use crate::stable_memory::POOL_OBSERVATION_MAP;
+use crate::stable_pool::stable_pool::StablePool;
+
+// each pool keeps a ring buffer of at most one observation per interval, 1440 x 60 secs = 24 hours of history
+pub const OBSERVATION_INTERVAL_SECS: u64 = 60;
+pub const MAX_OBSERVATIONS_PER_POOL: u64 = 1_440;
+
+fn slot(ts_secs: u64) -> u32 {
+    ((ts_secs / OBSERVATION_INTERVAL_SECS) % MAX_OBSERVATIONS_PER_POOL) as u32
+}
+
+/// observations of a pool, oldest first
+pub fn get_by_pool_id(pool_id: u32) -> Vec<StablePoolObservation> {
+    let mut observations: Vec<StablePoolObservation> = POOL_OBSERVATION_MAP.with(|m| {
+        m.borrow()
+            .range(StablePoolObservationId(pool_id, 0)..StablePoolObservationId(pool_id + 1, 0))
+            .map(|(_, v)| v)
+            .collect()
+    });
+    observations.sort_by_key(|observation| observation.ts_secs);
+    observations
+}
+
+/// record the pool's cumulative prices if there is no observation yet in the current interval
+/// the slot of the interval is overwritten once the ring buffer wraps around
+pub fn observe(pool: &StablePool) {
+    if pool.price_cumulative_secs == 0 {
+        return;
+    }
+    let id = StablePoolObservationId(pool.pool_id, slot(pool.price_cumulative_secs));
+    POOL_OBSERVATION_MAP.with(|m| {
+        let mut map = m.borrow_mut();
+        if let Some(observation) = map.get(&id) {
+            if observation.ts_secs / OBSERVATION_INTERVAL_SECS == pool.price_cumulative_secs / OBSERVATION_INTERVAL_SECS {
+                return;
+            }
+        }
+        map.insert(
+            id,
+            StablePoolObservation {
+                pool_id: pool.pool_id,
+                price_0_cumulative: pool.price_0_cumulative.clone(),
+                price_1_cumulative: pool.price_1_cumulative.clone(),
+                ts_secs: pool.price_cumulative_secs,
+            },
+        );
+    });
+}
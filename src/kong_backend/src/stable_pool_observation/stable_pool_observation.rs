@@ -0,0 +1,40 @@
+use candid::{CandidType, Nat};
+use ic_stable_structures::{storable::Bound, Storable};
+use serde::{Deserialize, Serialize};
+
+/// slot in the ring buffer of observations of a pool
+#[derive(CandidType, Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+pub struct StablePoolObservationId(pub u32, pub u32); // pool_id, slot
+
+impl Storable for StablePoolObservationId {
+    fn to_bytes(&self) -> std::borrow::Cow<'_, [u8]> {
+        serde_cbor::to_vec(self).unwrap().into()
+    }
+
+    fn from_bytes(bytes: std::borrow::Cow<'_, [u8]>) -> Self {
+        serde_cbor::from_slice(&bytes).unwrap()
+    }
+
+    const BOUND: Bound = Bound::Unbounded;
+}
+
+/// cumulative prices of a pool at a point in time
+#[derive(CandidType, Debug, Clone, Serialize, Deserialize)]
+pub struct StablePoolObservation {
+    pub pool_id: u32,
+    pub price_0_cumulative: Nat,
+    pub price_1_cumulative: Nat,
+    pub ts_secs: u64,
+}
+
+impl Storable for StablePoolObservation {
+    fn to_bytes(&self) -> std::borrow::Cow<'_, [u8]> {
+        serde_cbor::to_vec(self).unwrap().into()
+    }
+
+    fn from_bytes(bytes: std::borrow::Cow<'_, [u8]>) -> Self {
+        serde_cbor::from_slice(&bytes).unwrap()
+    }
+
+    const BOUND: Bound = Bound::Unbounded;
+}
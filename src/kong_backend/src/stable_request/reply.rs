@@ -3,7 +3,10 @@ use serde::{Deserialize, Serialize};
 
 use crate::add_liquidity::add_liquidity_reply::AddLiquidityReply;
 use crate::add_pool::add_pool_reply::AddPoolReply;
+use crate::batch::batch_reply::BatchReply;
 use crate::claims::claim_reply::ClaimReply;
+use crate::deposits::deposits_reply::DepositReply;
+use crate::limit_orders::limit_order_reply::LimitOrderReply;
 use crate::remove_liquidity::remove_liquidity_reply::RemoveLiquidityReply;
 use crate::send::send_reply::SendReply;
 use crate::swap::swap_reply::SwapReply;
@@ -17,4 +20,8 @@ pub enum Reply {
     Swap(SwapReply),
     Claim(ClaimReply),
     Send(SendReply),
+    LimitOrder(LimitOrderReply),
+    Batch(BatchReply),
+    Deposit(DepositReply),
+    Withdraw(DepositReply),
 }
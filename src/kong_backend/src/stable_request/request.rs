@@ -2,8 +2,13 @@ use candid::CandidType;
 use serde::{Deserialize, Serialize};
 
 use crate::add_liquidity::add_liquidity_args::AddLiquidityArgs;
+use crate::add_liquidity_single::add_liquidity_single_args::AddLiquiditySingleArgs;
 use crate::add_pool::add_pool_args::AddPoolArgs;
+use crate::batch::batch_args::BatchArgs;
+use crate::deposits::deposits_args::{DepositArgs, WithdrawArgs};
+use crate::limit_orders::limit_order_args::PlaceLimitOrderArgs;
 use crate::remove_liquidity::remove_liquidity_args::RemoveLiquidityArgs;
+use crate::remove_liquidity_single::remove_liquidity_single_args::RemoveLiquiditySingleArgs;
 use crate::send::send_args::SendArgs;
 use crate::swap::swap_args::SwapArgs;
 
@@ -15,4 +20,12 @@ pub enum Request {
     Swap(SwapArgs),
     Claim(u64),
     Send(SendArgs),
+    PlaceLimitOrder(PlaceLimitOrderArgs),
+    CancelLimitOrder(u64),
+    AddLiquiditySingle(AddLiquiditySingleArgs),
+    RemoveLiquiditySingle(RemoveLiquiditySingleArgs),
+    Batch(BatchArgs),
+    Deposit(DepositArgs),
+    Withdraw(WithdrawArgs),
+    FeeSweep(u32), // token_id of the Kong fees swept
 }
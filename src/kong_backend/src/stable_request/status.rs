@@ -23,6 +23,12 @@ pub enum StatusCode {
     AddToken0,
     AddToken0Success,
     AddToken0Failed,
+    PayListingDeposit,
+    PayListingDepositSuccess,
+    PayListingDepositFailed,
+    ReturnListingDeposit,
+    ReturnListingDepositSuccess,
+    ReturnListingDepositFailed,
     AddLPToken,
     AddLPTokenSuccess,
     AddLPTokenFailed,
@@ -88,6 +94,42 @@ pub enum StatusCode {
     ReturnPayToken,
     ReturnPayTokenSuccess,
     ReturnPayTokenFailed,
+    // single token liquidity
+    SwapSingleToken,
+    SwapSingleTokenSuccess,
+    SwapSingleTokenFailed,
+    // batch
+    BatchStep,
+    BatchStepSuccess,
+    BatchStepFailed,
+    SendBatchToken,
+    SendBatchTokenSuccess,
+    SendBatchTokenFailed,
+    ReturnBatchToken,
+    // deposits
+    DepositToken,
+    DepositTokenSuccess,
+    DepositTokenFailed,
+    WithdrawToken,
+    WithdrawTokenSuccess,
+    WithdrawTokenFailed,
+    DebitDeposit,
+    DebitDepositSuccess,
+    DebitDepositFailed,
+    CreditDepositSuccess,
+    // fee sweep
+    SweepKongFee,
+    SweepKongFeeSuccess,
+    SendTreasuryToken,
+    SendTreasuryTokenSuccess,
+    SendTreasuryTokenFailed,
+    BuybackToken,
+    BuybackTokenSuccess,
+    BuybackTokenFailed,
+    BurnToken,
+    BurnTokenSuccess,
+    BurnTokenFailed,
+    ReturnKongFee,
     // claim
     ClaimToken,
     ClaimTokenSuccess,
@@ -107,6 +149,9 @@ pub enum StatusCode {
     SendLPTokenToUser,
     SendLPTokenToUserSuccess,
     SendLPTokenToUserFailed,
+    // limits
+    DeadlineExceeded,
+    MinLPTokenAmountNotMet,
     // general
     Success,
     Failed,
@@ -119,6 +164,12 @@ impl std::fmt::Display for StatusCode {
             StatusCode::AddToken0 => write!(f, "Adding token 0"),
             StatusCode::AddToken0Success => write!(f, "Token 0 added"),
             StatusCode::AddToken0Failed => write!(f, "Failed adding token 0"),
+            StatusCode::PayListingDeposit => write!(f, "Paying listing deposit"),
+            StatusCode::PayListingDepositSuccess => write!(f, "Listing deposit paid"),
+            StatusCode::PayListingDepositFailed => write!(f, "Failed paying listing deposit"),
+            StatusCode::ReturnListingDeposit => write!(f, "Returning listing deposit"),
+            StatusCode::ReturnListingDepositSuccess => write!(f, "Listing deposit returned"),
+            StatusCode::ReturnListingDepositFailed => write!(f, "Failed returning listing deposit"),
             StatusCode::AddLPToken => write!(f, "Adding LP token"),
             StatusCode::AddLPTokenSuccess => write!(f, "LP token added"),
             StatusCode::AddLPTokenFailed => write!(f, "Failed adding LP token"),
@@ -181,6 +232,38 @@ impl std::fmt::Display for StatusCode {
             StatusCode::ReturnPayToken => write!(f, "Returning pay token"),
             StatusCode::ReturnPayTokenSuccess => write!(f, "Pay token returned"),
             StatusCode::ReturnPayTokenFailed => write!(f, "Failing returning pay token"),
+            StatusCode::SwapSingleToken => write!(f, "Swapping single token"),
+            StatusCode::SwapSingleTokenSuccess => write!(f, "Single token swapped"),
+            StatusCode::SwapSingleTokenFailed => write!(f, "Failed swapping single token"),
+            StatusCode::BatchStep => write!(f, "Executing batch operation"),
+            StatusCode::BatchStepSuccess => write!(f, "Batch operation executed"),
+            StatusCode::BatchStepFailed => write!(f, "Failed executing batch operation"),
+            StatusCode::SendBatchToken => write!(f, "Sending batch token"),
+            StatusCode::SendBatchTokenSuccess => write!(f, "Batch token sent"),
+            StatusCode::SendBatchTokenFailed => write!(f, "Failed sending batch token"),
+            StatusCode::ReturnBatchToken => write!(f, "Returning batch token"),
+            StatusCode::DepositToken => write!(f, "Depositing token"),
+            StatusCode::DepositTokenSuccess => write!(f, "Token deposited"),
+            StatusCode::DepositTokenFailed => write!(f, "Failed depositing token"),
+            StatusCode::WithdrawToken => write!(f, "Withdrawing token"),
+            StatusCode::WithdrawTokenSuccess => write!(f, "Token withdrawn"),
+            StatusCode::WithdrawTokenFailed => write!(f, "Failed withdrawing token"),
+            StatusCode::DebitDeposit => write!(f, "Debiting deposit"),
+            StatusCode::DebitDepositSuccess => write!(f, "Deposit debited"),
+            StatusCode::DebitDepositFailed => write!(f, "Failed debiting deposit"),
+            StatusCode::CreditDepositSuccess => write!(f, "Deposit credited"),
+            StatusCode::SweepKongFee => write!(f, "Sweeping Kong fees"),
+            StatusCode::SweepKongFeeSuccess => write!(f, "Kong fees swept"),
+            StatusCode::SendTreasuryToken => write!(f, "Sending token to fee treasury"),
+            StatusCode::SendTreasuryTokenSuccess => write!(f, "Token sent to fee treasury"),
+            StatusCode::SendTreasuryTokenFailed => write!(f, "Failed sending token to fee treasury"),
+            StatusCode::BuybackToken => write!(f, "Buying back KONG"),
+            StatusCode::BuybackTokenSuccess => write!(f, "KONG bought back"),
+            StatusCode::BuybackTokenFailed => write!(f, "Failed buying back KONG"),
+            StatusCode::BurnToken => write!(f, "Burning KONG"),
+            StatusCode::BurnTokenSuccess => write!(f, "KONG burned"),
+            StatusCode::BurnTokenFailed => write!(f, "Failed burning KONG"),
+            StatusCode::ReturnKongFee => write!(f, "Returning Kong fees to pool"),
             StatusCode::ClaimToken => write!(f, "Claiming token"),
             StatusCode::ClaimTokenSuccess => write!(f, "Token claimed"),
             StatusCode::ClaimTokenFailed => write!(f, "Failed claiming token"),
@@ -196,6 +279,8 @@ impl std::fmt::Display for StatusCode {
             StatusCode::SendLPTokenToUser => write!(f, "Sending LP token to user"),
             StatusCode::SendLPTokenToUserSuccess => write!(f, "LP token sent to user"),
             StatusCode::SendLPTokenToUserFailed => write!(f, "Failed sending LP token to user"),
+            StatusCode::DeadlineExceeded => write!(f, "Deadline exceeded"),
+            StatusCode::MinLPTokenAmountNotMet => write!(f, "Minimum LP token amount not met"),
             StatusCode::Success => write!(f, "Success"),
             StatusCode::Failed => write!(f, "Failed"),
         }
@@ -0,0 +1,3 @@
+pub mod reward_campaign_map;
+#[allow(clippy::module_inception)]
+pub mod stable_reward_campaign;
@@ -0,0 +1,180 @@
+use candid::Nat;
+
+use super::stable_reward_campaign::{StableRewardCampaign, StableRewardCampaignId, StableUserReward, StableUserRewardId};
+
+use crate::helpers::nat_helpers::{nat_add, nat_is_zero, nat_subtract, nat_zero};
+use crate::stable_kong_settings::kong_settings_map;
+use crate::stable_lp_token::lp_token_map;
+use crate::stable_memory::{REWARD_CAMPAIGN_MAP, USER_REWARD_MAP};
+
+pub fn get_by_campaign_id(campaign_id: u64) -> Option<StableRewardCampaign> {
+    REWARD_CAMPAIGN_MAP.with(|m| m.borrow().get(&StableRewardCampaignId(campaign_id)))
+}
+
+/// all campaigns, newest first
+pub fn get() -> Vec<StableRewardCampaign> {
+    REWARD_CAMPAIGN_MAP.with(|m| m.borrow().iter().rev().map(|(_, v)| v).collect())
+}
+
+pub fn get_by_lp_token_id(lp_token_id: u32) -> Vec<StableRewardCampaign> {
+    REWARD_CAMPAIGN_MAP.with(|m| {
+        m.borrow()
+            .iter()
+            .filter_map(|(_, v)| if v.lp_token_id == lp_token_id { Some(v) } else { None })
+            .collect()
+    })
+}
+
+pub fn insert(campaign: &StableRewardCampaign) -> u64 {
+    REWARD_CAMPAIGN_MAP.with(|m| {
+        let mut map = m.borrow_mut();
+        let campaign_id = kong_settings_map::inc_reward_campaign_map_idx();
+        let insert_campaign = StableRewardCampaign {
+            campaign_id,
+            ..campaign.clone()
+        };
+        map.insert(StableRewardCampaignId(campaign_id), insert_campaign);
+        campaign_id
+    })
+}
+
+/// rewards of token_id held by Kong for all campaigns, the funded reward amounts less the rewards claimed
+/// claimed rewards are saved as claims until they are sent
+pub fn get_outstanding_amount(token_id: u32) -> Nat {
+    let campaigns: Vec<StableRewardCampaign> = REWARD_CAMPAIGN_MAP.with(|m| {
+        m.borrow()
+            .iter()
+            .filter_map(|(_, v)| if v.reward_token_id == token_id { Some(v) } else { None })
+            .collect()
+    });
+    campaigns.iter().fold(nat_zero(), |acc, campaign| {
+        let claimed_amount = USER_REWARD_MAP.with(|m| {
+            m.borrow()
+                .range(StableUserRewardId(campaign.campaign_id, 0)..=StableUserRewardId(campaign.campaign_id, u32::MAX))
+                .fold(nat_zero(), |acc, (_, v)| nat_add(&acc, &v.claimed_amount))
+        });
+        nat_add(&acc, &nat_subtract(&campaign.reward_amount, &claimed_amount).unwrap_or(nat_zero()))
+    })
+}
+
+fn update(campaign: &StableRewardCampaign) {
+    REWARD_CAMPAIGN_MAP.with(|m| {
+        m.borrow_mut()
+            .insert(StableRewardCampaignId(campaign.campaign_id), campaign.clone())
+    });
+}
+
+fn get_user_reward(campaign_id: u64, user_id: u32, ts: u64) -> StableUserReward {
+    USER_REWARD_MAP
+        .with(|m| m.borrow().get(&StableUserRewardId(campaign_id, user_id)))
+        .unwrap_or_else(|| StableUserReward::new(campaign_id, user_id, ts))
+}
+
+fn update_user_reward(user_reward: &StableUserReward) {
+    USER_REWARD_MAP.with(|m| {
+        m.borrow_mut().insert(
+            StableUserRewardId(user_reward.campaign_id, user_reward.user_id),
+            user_reward.clone(),
+        )
+    });
+}
+
+/// accrue the campaigns of the LP token and settle the user's rewards with the LP token amount before it changes
+/// called by lp_token_map on every LP token balance change
+pub fn update_rewards(lp_token_id: u32, user_id: u32, lp_token_amount: &Nat, ts: u64) {
+    let campaigns = get_by_lp_token_id(lp_token_id);
+    if campaigns.is_empty() {
+        return;
+    }
+    let total_supply = lp_token_map::get_total_supply(lp_token_id);
+    for mut campaign in campaigns {
+        campaign.accrue(&total_supply, ts);
+        update(&campaign);
+        let mut user_reward = get_user_reward(campaign.campaign_id, user_id, ts);
+        user_reward.settle(&campaign, lp_token_amount, ts);
+        update_user_reward(&user_reward);
+    }
+}
+
+/// the user's unclaimed rewards of every campaign, including rewards streamed since the last settlement
+pub fn get_unclaimed_rewards(lp_token_id: u32, user_id: u32, lp_token_amount: &Nat, ts: u64) -> Vec<(StableRewardCampaign, Nat)> {
+    let total_supply = lp_token_map::get_total_supply(lp_token_id);
+    get_by_lp_token_id(lp_token_id)
+        .into_iter()
+        .filter_map(|mut campaign| {
+            campaign.accrue(&total_supply, ts);
+            let user_reward = get_user_reward(campaign.campaign_id, user_id, ts);
+            let amount = nat_add(&user_reward.accrued_amount, &user_reward.pending_amount(&campaign, lp_token_amount));
+            if nat_is_zero(&amount) {
+                None
+            } else {
+                Some((campaign, amount))
+            }
+        })
+        .collect()
+}
+
+/// settle and take all unclaimed rewards of the user, returned as (campaign, amount)
+pub fn take_rewards(user_id: u32, ts: u64) -> Vec<(StableRewardCampaign, Nat)> {
+    let mut rewards = Vec::new();
+    for lp_token in lp_token_map::get_by_user_id(user_id) {
+        update_rewards(lp_token.token_id, user_id, &lp_token.amount, ts);
+    }
+    // rewards of campaigns whose LP tokens the user no longer holds are also taken
+    let user_rewards: Vec<StableUserReward> = USER_REWARD_MAP.with(|m| {
+        m.borrow()
+            .iter()
+            .filter_map(|(_, v)| {
+                if v.user_id == user_id && !nat_is_zero(&v.accrued_amount) {
+                    Some(v)
+                } else {
+                    None
+                }
+            })
+            .collect()
+    });
+    for mut user_reward in user_rewards {
+        let Some(campaign) = get_by_campaign_id(user_reward.campaign_id) else {
+            continue;
+        };
+        let amount = user_reward.accrued_amount.clone();
+        user_reward.claimed_amount = nat_add(&user_reward.claimed_amount, &amount);
+        user_reward.accrued_amount = nat_zero();
+        user_reward.ts = ts;
+        update_user_reward(&user_reward);
+        rewards.push((campaign, amount));
+    }
+    rewards
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn insert_campaign(campaign_id: u64, reward_token_id: u32, reward_amount: u32) {
+        let campaign = StableRewardCampaign {
+            campaign_id,
+            ..StableRewardCampaign::new(1, reward_token_id, &Nat::from(reward_amount), 0, 100, 0)
+        };
+        REWARD_CAMPAIGN_MAP.with(|m| m.borrow_mut().insert(StableRewardCampaignId(campaign_id), campaign));
+    }
+
+    #[test]
+    fn test_get_outstanding_amount() {
+        insert_campaign(1, 2, 1_000);
+        insert_campaign(2, 2, 500);
+        insert_campaign(3, 3, 700);
+        // claimed rewards are no longer held for the campaign
+        let mut user_reward = StableUserReward::new(1, 10, 0);
+        user_reward.claimed_amount = Nat::from(300_u32);
+        update_user_reward(&user_reward);
+        // accrued rewards are still outstanding until claimed
+        let mut user_reward = StableUserReward::new(2, 10, 0);
+        user_reward.accrued_amount = Nat::from(200_u32);
+        update_user_reward(&user_reward);
+
+        assert_eq!(get_outstanding_amount(2), Nat::from(1_200_u32));
+        assert_eq!(get_outstanding_amount(3), Nat::from(700_u32));
+        assert_eq!(get_outstanding_amount(4), nat_zero());
+    }
+}
@@ -0,0 +1,194 @@
+use candid::{CandidType, Nat};
+use ic_stable_structures::{storable::Bound, Storable};
+use serde::{Deserialize, Serialize};
+
+use crate::helpers::nat_helpers::{nat_add, nat_divide, nat_is_zero, nat_multiply, nat_subtract, nat_zero};
+
+// reward_per_share is scaled by 10^18 to keep precision for LP tokens with large supplies
+fn reward_per_share_scale() -> Nat {
+    Nat::from(1_000_000_000_000_000_000_u128)
+}
+
+#[derive(CandidType, Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+pub struct StableRewardCampaignId(pub u64);
+
+impl Storable for StableRewardCampaignId {
+    fn to_bytes(&self) -> std::borrow::Cow<'_, [u8]> {
+        serde_cbor::to_vec(self).unwrap().into()
+    }
+
+    fn from_bytes(bytes: std::borrow::Cow<'_, [u8]>) -> Self {
+        serde_cbor::from_slice(&bytes).unwrap()
+    }
+
+    const BOUND: Bound = Bound::Unbounded;
+}
+
+/// liquidity mining campaign streaming reward_amount of a token to the LPs of a pool between start_ts and end_ts
+/// pro-rata to their LP token amount over time
+#[derive(CandidType, Debug, Clone, Serialize, Deserialize)]
+pub struct StableRewardCampaign {
+    pub campaign_id: u64,
+    pub lp_token_id: u32, // LP token of the pool whose LPs earn the rewards
+    pub reward_token_id: u32,
+    pub reward_amount: Nat,
+    pub start_ts: u64,
+    pub end_ts: u64,
+    pub reward_per_share: Nat,   // rewards per LP token accumulated since start_ts, scaled by 10^18
+    pub distributed_amount: Nat, // rewards accrued to LPs. rewards streamed while there are no LPs are not distributed
+    pub last_update_ts: u64,
+    pub ts: u64,
+}
+
+impl StableRewardCampaign {
+    pub fn new(lp_token_id: u32, reward_token_id: u32, reward_amount: &Nat, start_ts: u64, end_ts: u64, ts: u64) -> Self {
+        Self {
+            campaign_id: 0,
+            lp_token_id,
+            reward_token_id,
+            reward_amount: reward_amount.clone(),
+            start_ts,
+            end_ts,
+            reward_per_share: nat_zero(),
+            distributed_amount: nat_zero(),
+            last_update_ts: ts,
+            ts,
+        }
+    }
+
+    /// accumulate the rewards streamed since the last update, shared by total_supply LP tokens
+    /// must be called before total_supply changes
+    pub fn accrue(&mut self, total_supply: &Nat, ts: u64) {
+        let from_ts = std::cmp::max(self.last_update_ts, self.start_ts);
+        let to_ts = std::cmp::min(ts, self.end_ts);
+        if to_ts > from_ts && !nat_is_zero(total_supply) {
+            // streamed = reward_amount * (to_ts - from_ts) / (end_ts - start_ts)
+            let numerator = nat_multiply(&self.reward_amount, &Nat::from(to_ts - from_ts));
+            let streamed = nat_divide(&numerator, &Nat::from(self.end_ts - self.start_ts)).unwrap_or(nat_zero());
+            let numerator = nat_multiply(&streamed, &reward_per_share_scale());
+            let reward_per_share = nat_divide(&numerator, total_supply).unwrap_or(nat_zero());
+            self.reward_per_share = nat_add(&self.reward_per_share, &reward_per_share);
+            self.distributed_amount = nat_add(&self.distributed_amount, &streamed);
+        }
+        self.last_update_ts = std::cmp::max(self.last_update_ts, ts);
+    }
+
+    pub fn is_active(&self, ts: u64) -> bool {
+        self.start_ts <= ts && ts < self.end_ts
+    }
+}
+
+impl Storable for StableRewardCampaign {
+    fn to_bytes(&self) -> std::borrow::Cow<'_, [u8]> {
+        serde_cbor::to_vec(self).unwrap().into()
+    }
+
+    fn from_bytes(bytes: std::borrow::Cow<'_, [u8]>) -> Self {
+        serde_cbor::from_slice(&bytes).unwrap()
+    }
+
+    const BOUND: Bound = Bound::Unbounded;
+}
+
+#[derive(CandidType, Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+pub struct StableUserRewardId(pub u64, pub u32); // campaign_id, user_id
+
+impl Storable for StableUserRewardId {
+    fn to_bytes(&self) -> std::borrow::Cow<'_, [u8]> {
+        serde_cbor::to_vec(self).unwrap().into()
+    }
+
+    fn from_bytes(bytes: std::borrow::Cow<'_, [u8]>) -> Self {
+        serde_cbor::from_slice(&bytes).unwrap()
+    }
+
+    const BOUND: Bound = Bound::Unbounded;
+}
+
+/// rewards of a user in a campaign
+#[derive(CandidType, Debug, Clone, Serialize, Deserialize)]
+pub struct StableUserReward {
+    pub campaign_id: u64,
+    pub user_id: u32,
+    pub reward_per_share_paid: Nat, // campaign's reward_per_share when the rewards were last settled
+    pub accrued_amount: Nat,        // settled rewards not yet claimed
+    pub claimed_amount: Nat,
+    pub ts: u64,
+}
+
+impl StableUserReward {
+    pub fn new(campaign_id: u64, user_id: u32, ts: u64) -> Self {
+        Self {
+            campaign_id,
+            user_id,
+            reward_per_share_paid: nat_zero(),
+            accrued_amount: nat_zero(),
+            claimed_amount: nat_zero(),
+            ts,
+        }
+    }
+
+    /// settle the rewards earned by lp_token_amount since the last settlement
+    /// must be called before the user's LP token amount changes
+    pub fn settle(&mut self, campaign: &StableRewardCampaign, lp_token_amount: &Nat, ts: u64) {
+        self.accrued_amount = nat_add(&self.accrued_amount, &self.pending_amount(campaign, lp_token_amount));
+        self.reward_per_share_paid = campaign.reward_per_share.clone();
+        self.ts = ts;
+    }
+
+    /// rewards earned by lp_token_amount since the last settlement
+    pub fn pending_amount(&self, campaign: &StableRewardCampaign, lp_token_amount: &Nat) -> Nat {
+        let reward_per_share = nat_subtract(&campaign.reward_per_share, &self.reward_per_share_paid).unwrap_or(nat_zero());
+        nat_divide(&nat_multiply(lp_token_amount, &reward_per_share), &reward_per_share_scale()).unwrap_or(nat_zero())
+    }
+}
+
+impl Storable for StableUserReward {
+    fn to_bytes(&self) -> std::borrow::Cow<'_, [u8]> {
+        serde_cbor::to_vec(self).unwrap().into()
+    }
+
+    fn from_bytes(bytes: std::borrow::Cow<'_, [u8]>) -> Self {
+        serde_cbor::from_slice(&bytes).unwrap()
+    }
+
+    const BOUND: Bound = Bound::Unbounded;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_accrue_and_settle() {
+        // 1_000 rewards over 100 ns
+        let mut campaign = StableRewardCampaign::new(1, 2, &Nat::from(1_000_u32), 100, 200, 50);
+        let mut user_a = StableUserReward::new(0, 1, 50);
+        let mut user_b = StableUserReward::new(0, 2, 50);
+
+        // nothing streams before start_ts
+        campaign.accrue(&Nat::from(10_u32), 100);
+        assert_eq!(campaign.reward_per_share, nat_zero());
+
+        // user_a holds 10 LP tokens alone for the first half
+        campaign.accrue(&Nat::from(10_u32), 150);
+        user_b.settle(&campaign, &nat_zero(), 150);
+        // user_b adds 30 LP tokens, both hold until the end
+        campaign.accrue(&Nat::from(40_u32), 300);
+        user_a.settle(&campaign, &Nat::from(10_u32), 300);
+        user_b.settle(&campaign, &Nat::from(30_u32), 300);
+
+        assert_eq!(user_a.accrued_amount, Nat::from(500_u32 + 125));
+        assert_eq!(user_b.accrued_amount, Nat::from(375_u32));
+        assert_eq!(campaign.distributed_amount, Nat::from(1_000_u32));
+    }
+
+    #[test]
+    fn test_no_lps() {
+        let mut campaign = StableRewardCampaign::new(1, 2, &Nat::from(1_000_u32), 100, 200, 100);
+        // rewards are not distributed while there are no LPs
+        campaign.accrue(&nat_zero(), 150);
+        campaign.accrue(&Nat::from(10_u32), 200);
+        assert_eq!(campaign.distributed_amount, Nat::from(500_u32));
+    }
+}
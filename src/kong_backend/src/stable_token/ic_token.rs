@@ -2,7 +2,7 @@ use candid::{CandidType, Nat, Principal};
 use serde::{Deserialize, Serialize};
 
 use crate::chains::chains::IC_CHAIN;
-use crate::ic::ledger::{get_decimals, get_fee, get_name, get_supported_standards, get_symbol};
+use crate::ic::ledger::{get_decimals, get_fee, get_name, get_supported_standards, get_symbol, StandardRecord};
 
 #[derive(CandidType, Debug, Clone, Serialize, Deserialize)]
 pub struct ICToken {
@@ -17,25 +17,69 @@ pub struct ICToken {
     pub icrc3: bool,
     #[serde(default = "false_bool")]
     pub is_removed: bool,
+    #[serde(default)]
+    pub on_probation: bool, // permissionless listing waiting for King Kong to approve or reject
+    #[serde(default)]
+    pub listing_deposit: Option<ListingDeposit>, // deposit held while on probation
+}
+
+/// refundable anti-spam deposit paid by the user who listed the token
+#[derive(CandidType, Debug, Clone, Serialize, Deserialize)]
+pub struct ListingDeposit {
+    pub user_id: u32,
+    pub token_id: u32,
+    pub amount: Nat,
+    pub ts: u64,
 }
 
 fn false_bool() -> bool {
     false
 }
 
+const MAX_NAME_LENGTH: usize = 64;
+const MAX_SYMBOL_LENGTH: usize = 20;
+const MAX_DECIMALS: u8 = 18;
+
 impl ICToken {
+    /// new token to be listed. the ledger must pass the metadata and supported standards checks
     pub async fn new(canister_id: &Principal) -> Result<Self, String> {
+        let name = get_name(canister_id).await?;
+        let symbol = get_symbol(canister_id).await?;
+        let decimals = get_decimals(canister_id).await?;
+        let fee = get_fee(canister_id).await?;
+        validate_metadata(&name, &symbol, decimals)?;
+        let supported_standards = get_supported_standards(canister_id)
+            .await
+            .map_err(|e| format!("Failed to get supported standards of {}: {}", symbol, e))?;
+        let (icrc1, icrc2, icrc3) = supports_standards(&supported_standards);
+        if !icrc1 {
+            Err(format!("Token {} does not support ICRC-1", symbol))?
+        }
+        Ok(Self {
+            token_id: 0,
+            name,
+            symbol,
+            canister_id: *canister_id,
+            decimals,
+            fee,
+            icrc1,
+            icrc2,
+            icrc3,
+            is_removed: false,
+            on_probation: false,
+            listing_deposit: None,
+        })
+    }
+
+    /// refreshed metadata of an already listed token. the checks of new() are not applied so tokens listed before
+    /// them can still be updated
+    pub async fn refresh(canister_id: &Principal) -> Result<Self, String> {
         let name = get_name(canister_id).await?;
         let symbol = get_symbol(canister_id).await?;
         let decimals = get_decimals(canister_id).await?;
         let fee = get_fee(canister_id).await?;
         let (icrc1, icrc2, icrc3) = match get_supported_standards(canister_id).await {
-            Ok(supported_standards) => {
-                let icrc1 = supported_standards.iter().any(|standard| standard.name == "ICRC-1");
-                let icrc2 = supported_standards.iter().any(|standard| standard.name == "ICRC-2");
-                let icrc3 = supported_standards.iter().any(|standard| standard.name == "ICRC-3");
-                (icrc1, icrc2, icrc3)
-            }
+            Ok(supported_standards) => supports_standards(&supported_standards),
             Err(_) => (true, false, false), // should at least support ICRC-1 if it made it this far
         };
         Ok(Self {
@@ -49,6 +93,8 @@ impl ICToken {
             icrc2,
             icrc3,
             is_removed: false,
+            on_probation: false,
+            listing_deposit: None,
         })
     }
 
@@ -56,3 +102,46 @@ impl ICToken {
         IC_CHAIN.to_string()
     }
 }
+
+/// (icrc1, icrc2, icrc3) support of the ledger's supported standards
+fn supports_standards(supported_standards: &[StandardRecord]) -> (bool, bool, bool) {
+    let icrc1 = supported_standards.iter().any(|standard| standard.name == "ICRC-1");
+    let icrc2 = supported_standards.iter().any(|standard| standard.name == "ICRC-2");
+    let icrc3 = supported_standards.iter().any(|standard| standard.name == "ICRC-3");
+    (icrc1, icrc2, icrc3)
+}
+
+/// checks the ledger metadata is sane before the token can be listed
+pub fn validate_metadata(name: &str, symbol: &str, decimals: u8) -> Result<(), String> {
+    if name.trim().is_empty() || name.len() > MAX_NAME_LENGTH {
+        Err(format!("Token name must be 1 to {} characters", MAX_NAME_LENGTH))?
+    }
+    if symbol.is_empty() || symbol.len() > MAX_SYMBOL_LENGTH {
+        Err(format!("Token symbol must be 1 to {} characters", MAX_SYMBOL_LENGTH))?
+    }
+    // symbols are used in LP token symbols (Symbol0_Symbol1) and in Chain.Symbol lookups
+    if symbol.chars().any(|c| c.is_whitespace() || c.is_control() || c == '_' || c == '.') {
+        Err(format!("Invalid token symbol {}", symbol))?
+    }
+    if decimals > MAX_DECIMALS {
+        Err(format!("Token decimals cannot be more than {}", MAX_DECIMALS))?
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_validate_metadata() {
+        assert!(validate_metadata("Internet Computer", "ICP", 8).is_ok());
+        assert!(validate_metadata("ckETH", "ckETH", 18).is_ok());
+        assert!(validate_metadata(" ", "ICP", 8).is_err());
+        assert!(validate_metadata("Internet Computer", "", 8).is_err());
+        assert!(validate_metadata("Internet Computer", "IC P", 8).is_err());
+        assert!(validate_metadata("Internet Computer", "ICP_ckUSDT", 8).is_err());
+        assert!(validate_metadata("Internet Computer", "IC.ICP", 8).is_err());
+        assert!(validate_metadata("Internet Computer", "ICP", 19).is_err());
+    }
+}
@@ -18,6 +18,8 @@ pub struct LPToken {
     pub decimals: u8,
     #[serde(default = "false_bool")]
     pub is_removed: bool,
+    #[serde(default)]
+    pub ledger_id: Option<String>, // canister id of the LP token's ICRC-1/ICRC-2/ICRC-3 ledger (kong_lp_ledger)
 }
 
 fn false_bool() -> bool {
@@ -25,17 +27,19 @@ fn false_bool() -> bool {
 }
 
 impl LPToken {
-    pub fn new(token_0: &StableToken, token_1: &StableToken) -> Self {
-        let symbol = token::symbol(token_0, token_1);
-        // LP token's address is the combination of token_0's token_id and token_1's token_id
+    /// fee_tier - fee tier of the pool if it is an additional fee tier of the pair, None for the first pool of the pair
+    pub fn new(token_0: &StableToken, token_1: &StableToken, fee_tier: Option<u8>) -> Self {
+        let symbol = format!("{}{}", token::symbol(token_0, token_1), token::fee_tier_suffix(fee_tier));
+        // LP token's address is the combination of token_0's token_id, token_1's token_id and the fee tier
         // which is unique making it a unique identifier for the LP token
-        let address = token::address(token_0, token_1);
+        let address = format!("{}{}", token::address(token_0, token_1), token::fee_tier_suffix(fee_tier));
         Self {
             token_id: 0,
             symbol,
             address,
             decimals: LP_DECIMALS,
             is_removed: false,
+            ledger_id: None,
         }
     }
 
@@ -126,3 +126,9 @@ pub fn symbol(token_0: &StableToken, token_1: &StableToken) -> String {
 pub fn address(token_0: &StableToken, token_1: &StableToken) -> String {
     format!("{}_{}", token_0.token_id(), token_1.token_id())
 }
+
+/// suffix of the symbol and address of a pool and its LP token for the additional fee tiers of a pair
+/// the first pool of a pair has no fee tier so its symbol stays Symbol_Symbol
+pub fn fee_tier_suffix(fee_tier: Option<u8>) -> String {
+    fee_tier.map(|fee_tier| format!("_{}", fee_tier)).unwrap_or_default()
+}
@@ -1,3 +1,5 @@
+use candid::Nat;
+use std::collections::BTreeSet;
 use wildmatch::WildMatch;
 
 use super::ic_token::ICToken;
@@ -5,7 +7,9 @@ use super::lp_token::LPToken;
 use super::token::Token;
 use super::token_map;
 
+use crate::certified::certified_tree;
 use crate::chains::chains::IC_CHAIN;
+use crate::helpers::nat_helpers::{nat_add, nat_zero};
 use crate::ic::address_helpers::is_principal_id;
 use crate::ic::logging::error_log;
 use crate::stable_kong_settings::kong_settings_map;
@@ -20,15 +24,16 @@ pub fn symbol_with_chain(symbol: &str) -> Result<String, String> {
     }
 
     // symbol without chain prefix, check if symbol is on multiple chains
-    let tokens = TOKEN_MAP.with(|m| {
+    // a removed token can have the same symbol as a listed token on the same chain
+    let symbols_with_chain = TOKEN_MAP.with(|m| {
         m.borrow()
             .iter()
-            .filter_map(|(_, v)| if v.symbol() == symbol { Some(v) } else { None })
-            .collect::<Vec<StableToken>>()
+            .filter_map(|(_, v)| if v.symbol() == symbol { Some(v.symbol_with_chain()) } else { None })
+            .collect::<BTreeSet<String>>()
     });
-    match tokens.len() {
+    match symbols_with_chain.len() {
         0 => Err("Symbol not found".to_string()),
-        1 => Ok(tokens.first().ok_or("Symbol not found")?.symbol_with_chain()),
+        1 => Ok(symbols_with_chain.first().ok_or("Symbol not found")?.clone()),
         _ => Err("Symbol on multiple chains, specify chain explicitly".to_string()),
     }
 }
@@ -111,16 +116,24 @@ pub fn get_by_token(token: &str) -> Result<StableToken, String> {
 fn get_by_symbol(symbol: &str) -> Result<StableToken, String> {
     // will return error if symbol is not unique
     let symbol_with_chain = symbol_with_chain(symbol)?;
-    TOKEN_MAP
-        .with(|m| {
-            m.borrow().iter().find_map(|(_, v)| {
+    let tokens = TOKEN_MAP.with(|m| {
+        m.borrow()
+            .iter()
+            .filter_map(|(_, v)| {
                 if v.symbol_with_chain() == symbol_with_chain {
                     Some(v)
                 } else {
                     None
                 }
             })
-        })
+            .collect::<Vec<StableToken>>()
+    });
+    // the listed token takes precedence over removed tokens with the same symbol
+    tokens
+        .iter()
+        .find(|v| !v.is_removed())
+        .or(tokens.first())
+        .cloned()
         .ok_or("Token not found".to_string())
 }
 
@@ -162,11 +175,34 @@ pub fn get() -> Vec<StableToken> {
     })
 }
 
+/// listing deposits of token_id held for tokens on probation
+pub fn get_listing_deposit_amount(token_id: u32) -> Nat {
+    TOKEN_MAP.with(|m| {
+        m.borrow().iter().fold(nat_zero(), |acc, (_, v)| match v {
+            StableToken::IC(ICToken {
+                listing_deposit: Some(deposit),
+                ..
+            }) if deposit.token_id == token_id => nat_add(&acc, &deposit.amount),
+            _ => acc,
+        })
+    })
+}
+
 // token's address is the unique identifier
 pub fn exists(address_with_chain: &str) -> bool {
     TOKEN_MAP.with(|m| m.borrow().iter().any(|(_, v)| v.address_with_chain() == address_with_chain))
 }
 
+// symbols of listed tokens must be unique within a chain so Chain.Symbol lookups are not ambiguous
+// symbols of removed tokens can be reused, as the listed token is looked up first
+pub fn symbol_exists(symbol_with_chain: &str) -> bool {
+    TOKEN_MAP.with(|m| {
+        m.borrow()
+            .iter()
+            .any(|(_, v)| !v.is_removed() && v.symbol_with_chain() == symbol_with_chain)
+    })
+}
+
 pub fn insert(token: &StableToken) -> Result<u32, String> {
     if exists(&token.address_with_chain()) {
         Err("Token already exists")?
@@ -182,6 +218,7 @@ pub fn insert(token: &StableToken) -> Result<u32, String> {
         map.insert(StableTokenId(token_id), insert_token.clone());
         insert_token
     });
+    certified_tree::update_token(&insert_token);
 
     let _ = archive_to_kong_data(&insert_token);
     Ok(insert_token.token_id())
@@ -189,6 +226,7 @@ pub fn insert(token: &StableToken) -> Result<u32, String> {
 
 pub fn update(token: &StableToken) {
     TOKEN_MAP.with(|m| m.borrow_mut().insert(StableTokenId(token.token_id()), token.clone()));
+    certified_tree::update_token(token);
     let _ = archive_to_kong_data(token);
 }
 
@@ -255,6 +293,7 @@ fn archive_to_kong_data(token: &StableToken) -> Result<(), String> {
 mod tests {
     use super::*;
     use crate::chains::chains::LP_CHAIN;
+    use crate::stable_token::ic_token::ListingDeposit;
 
     #[test]
     fn test_get_chain() {
@@ -312,4 +351,72 @@ mod tests {
         assert_eq!(get_address(""), None);
         assert_eq!(get_address(&format!("{}.{}", IC_CHAIN, invalid_principal)), None);
     }
+
+    #[test]
+    fn test_symbol_of_removed_token() {
+        let token = |token_id: u32, canister_id: &str, is_removed: bool| ICToken {
+            token_id,
+            name: "Token".to_string(),
+            symbol: "TKN".to_string(),
+            canister_id: candid::Principal::from_text(canister_id).unwrap(),
+            decimals: 8,
+            fee: candid::Nat::from(10_000_u32),
+            icrc1: true,
+            icrc2: true,
+            icrc3: false,
+            is_removed,
+            on_probation: false,
+            listing_deposit: None,
+        };
+        TOKEN_MAP.with(|m| {
+            let mut map = m.borrow_mut();
+            map.insert(StableTokenId(1), StableToken::IC(token(1, "ryjl3-tyaaa-aaaaa-aaaba-cai", true)));
+        });
+        // the symbol of a removed token can be reused
+        assert!(!symbol_exists("IC.TKN"));
+        // but the removed token can still be found by symbol
+        assert_eq!(get_by_token("TKN").unwrap().token_id(), 1);
+
+        TOKEN_MAP.with(|m| {
+            let mut map = m.borrow_mut();
+            map.insert(StableTokenId(2), StableToken::IC(token(2, "mxzaz-hqaaa-aaaar-qaada-cai", false)));
+        });
+        assert!(symbol_exists("IC.TKN"));
+        // the listed token takes precedence
+        assert_eq!(get_by_token("TKN").unwrap().token_id(), 2);
+        assert_eq!(get_by_token("IC.TKN").unwrap().token_id(), 2);
+    }
+
+    #[test]
+    fn test_get_listing_deposit_amount() {
+        let token = |token_id: u32, listing_deposit: Option<ListingDeposit>| ICToken {
+            token_id,
+            name: "Token".to_string(),
+            symbol: format!("TKN{}", token_id),
+            canister_id: candid::Principal::from_slice(&[token_id as u8]),
+            decimals: 8,
+            fee: nat_zero(),
+            icrc1: true,
+            icrc2: true,
+            icrc3: false,
+            is_removed: false,
+            on_probation: listing_deposit.is_some(),
+            listing_deposit,
+        };
+        let deposit = |amount: u32| ListingDeposit {
+            user_id: 100,
+            token_id: 1,
+            amount: Nat::from(amount),
+            ts: 0,
+        };
+        TOKEN_MAP.with(|m| {
+            let mut map = m.borrow_mut();
+            map.insert(StableTokenId(2), StableToken::IC(token(2, Some(deposit(1_000)))));
+            map.insert(StableTokenId(3), StableToken::IC(token(3, Some(deposit(500)))));
+            // approved or rejected tokens no longer hold a deposit
+            map.insert(StableTokenId(4), StableToken::IC(token(4, None)));
+        });
+        assert_eq!(get_listing_deposit_amount(1), Nat::from(1_500_u32));
+        assert_eq!(get_listing_deposit_amount(2), nat_zero());
+    }
 }
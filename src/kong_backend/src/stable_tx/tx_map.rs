@@ -3,20 +3,28 @@ use std::ops::Bound;
 
 use super::add_liquidity_tx::AddLiquidityTx;
 use super::add_pool_tx::AddPoolTx;
+use super::fee_sweep_tx::FeeSweepTx;
 use super::remove_liquidity_tx::RemoveLiquidityTx;
 use super::send_tx::SendTx;
-use super::stable_tx::StableTx::{AddLiquidity, AddPool, RemoveLiquidity, Send, Swap};
+use super::stable_tx::StableTx::{AddLiquidity, AddPool, FeeSweep, RemoveLiquidity, Send, Swap};
 use super::stable_tx::{StableTx, StableTxId};
 use super::swap_tx::SwapTx;
 use super::tx::Tx;
 
 use crate::ic::logging::error_log;
 use crate::stable_kong_settings::kong_settings_map;
-use crate::stable_memory::TX_MAP;
+use crate::stable_memory::{TX_ARCHIVE_MAP, TX_MAP};
 use crate::stable_pool::pool_map;
 
 const MAX_TXS: usize = 20;
 
+/// get tx by tx_id, from the archive if it is no longer in TX_MAP
+pub fn get_by_tx_id(tx_id: u64) -> Option<StableTx> {
+    TX_MAP
+        .with(|m| m.borrow().get(&StableTxId(tx_id)))
+        .or_else(|| TX_ARCHIVE_MAP.with(|m| m.borrow().get(&StableTxId(tx_id))))
+}
+
 /// get txs filtered by user_id and token_id
 /// if you call get_by_user_and_token_id(None, None, None) it will return all txs
 pub fn get_by_user_and_token_id(
@@ -78,6 +86,16 @@ pub fn get_by_user_and_token_id(
                                 return Some(v.clone());
                             }
                         }
+                        StableTx::FeeSweep(ref fee_sweep_tx) => {
+                            if fee_sweep_tx.token_id == token_id {
+                                return Some(v.clone());
+                            }
+                            for tx in fee_sweep_tx.txs.iter() {
+                                if tx.pay_token_id == token_id || tx.receive_token_id == token_id {
+                                    return Some(v.clone());
+                                }
+                            }
+                        }
                     }
                     return None;
                 }
@@ -98,6 +116,7 @@ pub fn insert(tx: &StableTx) -> u64 {
             RemoveLiquidity(tx) => RemoveLiquidity(RemoveLiquidityTx { tx_id, ..tx.clone() }),
             Swap(tx) => Swap(SwapTx { tx_id, ..tx.clone() }),
             Send(tx) => Send(SendTx { tx_id, ..tx.clone() }),
+            FeeSweep(tx) => FeeSweep(FeeSweepTx { tx_id, ..tx.clone() }),
         };
         map.insert(StableTxId(tx_id), insert_tx);
         tx_id
@@ -1,6 +1,10 @@
 pub mod banned_user_map;
 pub mod principal_id_map;
 mod referral_code;
+pub mod referral_reward_map;
+pub mod stable_referral_reward;
 #[allow(clippy::module_inception)]
 pub mod stable_user;
+pub mod stable_user_volume;
 pub mod user_map;
+pub mod user_volume_map;
@@ -0,0 +1,99 @@
+use candid::Nat;
+use std::cell::RefCell;
+
+use super::stable_referral_reward::{StableReferralReward, StableReferralRewardId};
+
+use crate::helpers::nat_helpers::{nat_add, nat_zero};
+use crate::stable_memory::REFERRAL_REWARD_MAP;
+use crate::stable_token::token::Token;
+use crate::stable_token::token_map;
+
+/// referral reward credited by a batch while it is staged, as (user_id, token_id, amount, ts)
+type StagedCredit = (u32, u32, Nat, u64);
+
+thread_local! {
+    static STAGED_CREDITS: RefCell<Option<Vec<StagedCredit>>> = const { RefCell::new(None) };
+}
+
+/// all referral rewards of a referrer
+pub fn get_by_user_id(user_id: u32) -> Vec<StableReferralReward> {
+    REFERRAL_REWARD_MAP.with(|m| {
+        m.borrow()
+            .range(StableReferralRewardId(user_id, 0)..=StableReferralRewardId(user_id, u32::MAX))
+            .map(|(_, v)| v)
+            .collect()
+    })
+}
+
+/// total referral rewards of a token not yet paid out
+pub fn get_unpaid_amount(token_id: u32) -> Nat {
+    REFERRAL_REWARD_MAP.with(|m| {
+        m.borrow()
+            .iter()
+            .filter(|(k, _)| k.1 == token_id)
+            .fold(nat_zero(), |acc, (_, v)| nat_add(&acc, &v.amount))
+    })
+}
+
+/// credit amount of token_id to the referral rewards of the referrer
+pub fn credit(user_id: u32, token_id: u32, amount: &Nat, ts: u64) {
+    let staged = STAGED_CREDITS.with(|s| {
+        s.borrow_mut()
+            .as_mut()
+            .map(|credits| credits.push((user_id, token_id, amount.clone(), ts)))
+            .is_some()
+    });
+    if staged {
+        return;
+    }
+
+    REFERRAL_REWARD_MAP.with(|m| {
+        let mut map = m.borrow_mut();
+        let key = StableReferralRewardId(user_id, token_id);
+        let mut referral_reward = map.get(&key).unwrap_or_else(|| StableReferralReward::new(user_id, token_id, ts));
+        referral_reward.amount = nat_add(&referral_reward.amount, amount);
+        referral_reward.total_amount = nat_add(&referral_reward.total_amount, amount);
+        referral_reward.ts = ts;
+        map.insert(key, referral_reward);
+    });
+}
+
+/// stage the referral rewards credited in memory, so the operations of a batch can be discarded if one fails
+pub fn stage() {
+    STAGED_CREDITS.with(|s| *s.borrow_mut() = Some(Vec::new()));
+}
+
+/// credit the staged referral rewards
+pub fn commit_staged() {
+    let credits = STAGED_CREDITS.with(|s| s.borrow_mut().take()).unwrap_or_default();
+    for (user_id, token_id, amount, ts) in credits {
+        credit(user_id, token_id, &amount, ts);
+    }
+}
+
+/// drop the staged referral rewards
+pub fn discard_staged() {
+    STAGED_CREDITS.with(|s| *s.borrow_mut() = None);
+}
+
+/// take the unpaid referral rewards of the referrer that are larger than the token's fee, returned as (token_id, amount)
+/// smaller rewards are kept until they are worth sending
+pub fn take_rewards(user_id: u32, ts: u64) -> Vec<(u32, Nat)> {
+    let mut rewards = Vec::new();
+    for mut referral_reward in get_by_user_id(user_id) {
+        let Some(token) = token_map::get_by_token_id(referral_reward.token_id) else {
+            continue;
+        };
+        if referral_reward.amount <= token.fee() {
+            continue;
+        }
+        rewards.push((referral_reward.token_id, referral_reward.amount.clone()));
+        referral_reward.amount = nat_zero();
+        referral_reward.ts = ts;
+        REFERRAL_REWARD_MAP.with(|m| {
+            m.borrow_mut()
+                .insert(StableReferralRewardId(user_id, referral_reward.token_id), referral_reward)
+        });
+    }
+    rewards
+}
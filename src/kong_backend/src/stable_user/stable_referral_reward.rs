@@ -0,0 +1,55 @@
+use candid::{CandidType, Nat};
+use ic_stable_structures::{storable::Bound, Storable};
+use serde::{Deserialize, Serialize};
+
+use crate::helpers::nat_helpers::nat_zero;
+
+/// (user_id of the referrer, token_id of the reward)
+#[derive(CandidType, Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+pub struct StableReferralRewardId(pub u32, pub u32);
+
+impl Storable for StableReferralRewardId {
+    fn to_bytes(&self) -> std::borrow::Cow<'_, [u8]> {
+        serde_cbor::to_vec(self).unwrap().into()
+    }
+
+    fn from_bytes(bytes: std::borrow::Cow<'_, [u8]>) -> Self {
+        serde_cbor::from_slice(&bytes).unwrap()
+    }
+
+    const BOUND: Bound = Bound::Unbounded;
+}
+
+/// referral rewards of a referrer in a token, credited from Kong's fee of swaps by the users it referred
+#[derive(CandidType, Debug, Clone, Serialize, Deserialize)]
+pub struct StableReferralReward {
+    pub user_id: u32, // user_id of the referrer
+    pub token_id: u32,
+    pub amount: Nat,       // rewards not yet paid out
+    pub total_amount: Nat, // rewards earned since the first referral swap
+    pub ts: u64,
+}
+
+impl StableReferralReward {
+    pub fn new(user_id: u32, token_id: u32, ts: u64) -> Self {
+        Self {
+            user_id,
+            token_id,
+            amount: nat_zero(),
+            total_amount: nat_zero(),
+            ts,
+        }
+    }
+}
+
+impl Storable for StableReferralReward {
+    fn to_bytes(&self) -> std::borrow::Cow<'_, [u8]> {
+        serde_cbor::to_vec(self).unwrap().into()
+    }
+
+    fn from_bytes(bytes: std::borrow::Cow<'_, [u8]>) -> Self {
+        serde_cbor::from_slice(&bytes).unwrap()
+    }
+
+    const BOUND: Bound = Bound::Unbounded;
+}
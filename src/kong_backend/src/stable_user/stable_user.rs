@@ -32,7 +32,16 @@ impl Storable for StableUserId {
     const BOUND: Bound = Bound::Unbounded;
 }
 
+/// what set the fee level of a user
+#[derive(CandidType, Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum FeeLevelSource {
+    #[default]
+    Tier, // recomputed by the user fee tiers timer
+    Manual, // set by the admin with update_user, kept by the user fee tiers timer
+}
+
 #[derive(CandidType, Debug, Clone, Serialize, Deserialize)]
+#[serde(from = "StableUserRecord")]
 pub struct StableUser {
     pub user_id: u32,
     pub principal_id: String,
@@ -43,6 +52,41 @@ pub struct StableUser {
     // so 0 = no discount, 100 = pays no lp_fee on swaps
     pub fee_level: u8,
     pub fee_level_expires_at: Option<u64>,
+    pub fee_level_source: FeeLevelSource,
+}
+
+/// StableUser as stored. users stored before fee_level_source have no source
+#[derive(Deserialize)]
+struct StableUserRecord {
+    user_id: u32,
+    principal_id: String,
+    my_referral_code: String,
+    referred_by: Option<u32>,
+    referred_by_expires_at: Option<u64>,
+    fee_level: u8,
+    fee_level_expires_at: Option<u64>,
+    fee_level_source: Option<FeeLevelSource>,
+}
+
+impl From<StableUserRecord> for StableUser {
+    fn from(user: StableUserRecord) -> Self {
+        // fee levels of users stored before fee_level_source were set by the admin, so the user fee tiers timer keeps them
+        let fee_level_source = user.fee_level_source.unwrap_or(if user.fee_level > 0 {
+            FeeLevelSource::Manual
+        } else {
+            FeeLevelSource::Tier
+        });
+        StableUser {
+            user_id: user.user_id,
+            principal_id: user.principal_id,
+            my_referral_code: user.my_referral_code,
+            referred_by: user.referred_by,
+            referred_by_expires_at: user.referred_by_expires_at,
+            fee_level: user.fee_level,
+            fee_level_expires_at: user.fee_level_expires_at,
+            fee_level_source,
+        }
+    }
 }
 
 impl Default for StableUser {
@@ -55,6 +99,7 @@ impl Default for StableUser {
             referred_by_expires_at: None,
             fee_level: 0,
             fee_level_expires_at: None,
+            fee_level_source: FeeLevelSource::Tier,
         }
     }
 }
@@ -70,3 +115,47 @@ impl Storable for StableUser {
 
     const BOUND: Bound = Bound::Unbounded;
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // StableUser as stored before fee_level_source
+    #[derive(Serialize)]
+    struct LegacyStableUser {
+        user_id: u32,
+        principal_id: String,
+        my_referral_code: String,
+        referred_by: Option<u32>,
+        referred_by_expires_at: Option<u64>,
+        fee_level: u8,
+        fee_level_expires_at: Option<u64>,
+    }
+
+    fn legacy_user(fee_level: u8) -> StableUser {
+        let user = LegacyStableUser {
+            user_id: 100,
+            principal_id: "aaaaa-aa".to_string(),
+            my_referral_code: "ABCD".to_string(),
+            referred_by: None,
+            referred_by_expires_at: None,
+            fee_level,
+            fee_level_expires_at: None,
+        };
+        StableUser::from_bytes(serde_cbor::to_vec(&user).unwrap().into())
+    }
+
+    #[test]
+    fn test_upgrade_fee_level_source() {
+        // fee levels set before the upgrade are kept as manual, users without a fee level follow the tiers
+        assert_eq!(legacy_user(40).fee_level_source, FeeLevelSource::Manual);
+        assert_eq!(legacy_user(0).fee_level_source, FeeLevelSource::Tier);
+
+        // stored users keep their source
+        let user = StableUser {
+            fee_level_source: FeeLevelSource::Tier,
+            ..legacy_user(10)
+        };
+        assert_eq!(StableUser::from_bytes(user.to_bytes()).fee_level_source, FeeLevelSource::Tier);
+    }
+}
@@ -1,6 +1,6 @@
 use super::principal_id_map;
 use super::referral_code::{generate_referral_code, REFERRAL_INTERVAL};
-use super::stable_user::{StableUser, StableUserId};
+use super::stable_user::{FeeLevelSource, StableUser, StableUserId};
 
 use crate::ic::id::{caller_principal_id, principal_id_is_not_anonymous};
 use crate::ic::logging::error_log;
@@ -68,6 +68,31 @@ pub fn get_user_by_referral_code(referral_code: &str) -> Option<StableUser> {
     })
 }
 
+/// return user_id of the referrer of the user if the referral has not expired
+pub fn get_active_referrer_id(user_id: u32, ts: u64) -> Option<u32> {
+    let user = get_by_user_id(user_id)?;
+    match user.referred_by_expires_at {
+        Some(referred_by_expires_at) if referred_by_expires_at > ts => user.referred_by,
+        _ => None,
+    }
+}
+
+/// return number of users referred by the user
+pub fn get_referral_count(user_id: u32) -> u32 {
+    USER_MAP.with(|m| m.borrow().iter().filter(|(_, v)| v.referred_by == Some(user_id)).count() as u32)
+}
+
+/// return user_ids of users with a fee level set by the user fee tiers
+pub fn get_fee_tier_user_ids() -> Vec<u32> {
+    USER_MAP.with(|m| {
+        m.borrow()
+            .iter()
+            .filter(|(_, v)| v.fee_level > 0 && v.fee_level_source == FeeLevelSource::Tier)
+            .map(|(k, _)| k.0)
+            .collect()
+    })
+}
+
 pub fn insert(referred_by: Option<&str>) -> Result<u32, String> {
     let mut update = false;
     let user = match get_by_caller() {
@@ -89,6 +114,7 @@ pub fn insert(referred_by: Option<&str>) -> Result<u32, String> {
                 if now > fee_level_expires_at {
                     user.fee_level = 0;
                     user.fee_level_expires_at = None;
+                    user.fee_level_source = FeeLevelSource::Tier;
                     update = true;
                 }
             }
@@ -130,6 +156,36 @@ pub fn insert(referred_by: Option<&str>) -> Result<u32, String> {
     Ok(user.user_id)
 }
 
+/// return user_id of principal_id, registering a new user without a referrer if principal_id is not a known user
+/// used when LP tokens are sent to a principal id through the LP token ledgers
+pub fn insert_by_principal_id(principal_id: &str) -> Result<u32, String> {
+    if let Some(user) = get_by_principal_id(principal_id)? {
+        return Ok(user.user_id);
+    }
+
+    let mut rng = get_pseudo_seed()?;
+    let user = StableUser {
+        user_id: kong_settings_map::inc_user_map_idx(),
+        principal_id: principal_id.to_string(),
+        my_referral_code: generate_referral_code(&mut rng),
+        ..Default::default()
+    };
+    principal_id_map::insert_principal_id(&user);
+    USER_MAP.with(|m| {
+        m.borrow_mut().insert(StableUserId(user.user_id), user.clone());
+    });
+    _ = archive_to_kong_data(&user);
+
+    Ok(user.user_id)
+}
+
+pub fn update(user: &StableUser) {
+    USER_MAP.with(|m| {
+        m.borrow_mut().insert(StableUserId(user.user_id), user.clone());
+    });
+    _ = archive_to_kong_data(user);
+}
+
 pub fn archive_to_kong_data(user: &StableUser) -> Result<(), String> {
     if !kong_settings_map::get().archive_to_kong_data {
         return Ok(());
@@ -0,0 +1,66 @@
+use candid::Nat;
+use std::collections::BTreeMap;
+
+use super::stable_user_volume::{StableUserVolume, StableUserVolumeId};
+
+use crate::helpers::nat_helpers::{nat_add, nat_zero};
+use crate::stable_memory::USER_VOLUME_MAP;
+
+pub const ONE_DAY_NANOSECS: u64 = 86_400_000_000_000;
+
+/// day since the epoch of a timestamp
+pub fn to_day(ts: u64) -> u32 {
+    (ts / ONE_DAY_NANOSECS) as u32
+}
+
+/// swap volume of a user from from_day
+pub fn get_volume(user_id: u32, from_day: u32) -> Nat {
+    USER_VOLUME_MAP.with(|m| {
+        m.borrow()
+            .range(StableUserVolumeId(user_id, from_day)..=StableUserVolumeId(user_id, u32::MAX))
+            .fold(nat_zero(), |acc, (_, v)| nat_add(&acc, &v.volume))
+    })
+}
+
+/// swap volume of every user with volume from from_day
+pub fn get_volumes(from_day: u32) -> BTreeMap<u32, Nat> {
+    let mut volumes = BTreeMap::new();
+    USER_VOLUME_MAP.with(|m| {
+        m.borrow().iter().filter(|(k, _)| k.1 >= from_day).for_each(|(k, v)| {
+            let volume = volumes.entry(k.0).or_insert_with(nat_zero);
+            *volume = nat_add(volume, &v.volume);
+        })
+    });
+    volumes
+}
+
+/// add volume to the user's volume of the day
+pub fn add(user_id: u32, day: u32, volume: &Nat) {
+    USER_VOLUME_MAP.with(|m| {
+        let mut map = m.borrow_mut();
+        let key = StableUserVolumeId(user_id, day);
+        let user_volume = map.get(&key).unwrap_or(StableUserVolume {
+            user_id,
+            day,
+            volume: nat_zero(),
+        });
+        map.insert(
+            key,
+            StableUserVolume {
+                volume: nat_add(&user_volume.volume, volume),
+                ..user_volume
+            },
+        );
+    });
+}
+
+/// remove the volumes of the days before day
+pub fn remove_before(day: u32) {
+    USER_VOLUME_MAP.with(|m| {
+        let mut map = m.borrow_mut();
+        let remove_list = map.iter().filter(|(k, _)| k.1 < day).map(|(k, _)| k).collect::<Vec<_>>();
+        remove_list.iter().for_each(|k| {
+            map.remove(k);
+        });
+    });
+}
@@ -1,30 +1,65 @@
 use candid::Nat;
 
-use super::swap_amounts::swap_amounts;
+use super::swap_amounts::{internal_swap_amounts, user_swap_amounts};
 use super::swap_calc::SwapCalc;
 
 use crate::helpers::nat_helpers::{nat_is_zero, nat_to_decimals_f64};
 use crate::stable_token::{stable_token::StableToken, token::Token};
 
+/// user_id - user making the swap, whose fee level is used
 pub fn calculate_amounts(
+    user_id: u32,
     pay_token: &StableToken,
     pay_amount: &Nat,
     receive_token: &StableToken,
     user_receive_amount: Option<&Nat>,
     user_max_slippage: f64,
 ) -> Result<(Nat, f64, f64, f64, Vec<SwapCalc>), String> {
-    let (receive_amount_with_fees_and_gas, price, mid_price, slippage, txs) = swap_amounts(pay_token, Some(pay_amount), receive_token)?;
+    let (receive_amount_with_fees_and_gas, price, mid_price, slippage, txs) =
+        user_swap_amounts(user_id, pay_token, pay_amount, receive_token)?;
+    check_amounts(
+        &receive_amount_with_fees_and_gas,
+        receive_token,
+        slippage,
+        user_receive_amount,
+        user_max_slippage,
+    )?;
 
+    Ok((receive_amount_with_fees_and_gas, mid_price, price, slippage, txs))
+}
+
+/// same as calculate_amounts() but the receive token stays in the canister, so no gas fee is taken
+/// returns (receive_amount, price, mid_price, slippage, txs)
+pub fn calculate_internal_amounts(
+    pay_token: &StableToken,
+    pay_amount: &Nat,
+    receive_token: &StableToken,
+    user_receive_amount: Option<&Nat>,
+    user_max_slippage: f64,
+) -> Result<(Nat, f64, f64, f64, Vec<SwapCalc>), String> {
+    let (receive_amount, price, mid_price, slippage, txs) = internal_swap_amounts(pay_token, pay_amount, receive_token)?;
+    check_amounts(&receive_amount, receive_token, slippage, user_receive_amount, user_max_slippage)?;
+
+    Ok((receive_amount, price, mid_price, slippage, txs))
+}
+
+fn check_amounts(
+    receive_amount_with_fees_and_gas: &Nat,
+    receive_token: &StableToken,
+    slippage: f64,
+    user_receive_amount: Option<&Nat>,
+    user_max_slippage: f64,
+) -> Result<(), String> {
     // make sure receive_amount is not zero
-    if nat_is_zero(&receive_amount_with_fees_and_gas) {
+    if nat_is_zero(receive_amount_with_fees_and_gas) {
         Err("Receive amount is zero".to_string())?;
     }
 
     // check if receive_amount_with_fees_and_gas is within user's specified
     if let Some(user_receive_amount) = user_receive_amount {
-        if receive_amount_with_fees_and_gas < *user_receive_amount {
+        if receive_amount_with_fees_and_gas < user_receive_amount {
             let decimals = receive_token.decimals();
-            let receive_amount_with_fees_and_gas_f64 = nat_to_decimals_f64(decimals, &receive_amount_with_fees_and_gas).unwrap_or(0_f64);
+            let receive_amount_with_fees_and_gas_f64 = nat_to_decimals_f64(decimals, receive_amount_with_fees_and_gas).unwrap_or(0_f64);
             Err(format!(
                 "Insufficient receive amount. Can only receive {} {} with {}% slippage",
                 receive_amount_with_fees_and_gas_f64,
@@ -37,7 +72,7 @@ pub fn calculate_amounts(
     // check if slippage is within user's specified
     if slippage > user_max_slippage {
         let decimals = receive_token.decimals();
-        let receive_amount_with_fees_and_gas_f64 = nat_to_decimals_f64(decimals, &receive_amount_with_fees_and_gas).unwrap_or(0_f64);
+        let receive_amount_with_fees_and_gas_f64 = nat_to_decimals_f64(decimals, receive_amount_with_fees_and_gas).unwrap_or(0_f64);
         Err(format!(
             "Slippage exceeded. Can only receive {} {} with {}% slippage",
             receive_amount_with_fees_and_gas_f64,
@@ -46,5 +81,5 @@ pub fn calculate_amounts(
         ))?
     }
 
-    Ok((receive_amount_with_fees_and_gas, mid_price, price, slippage, txs))
+    Ok(())
 }
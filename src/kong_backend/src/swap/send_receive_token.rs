@@ -75,21 +75,24 @@ pub async fn send_receive_token(
         }
     }
 
-    let swap_tx = SwapTx::new_success(
-        user_id,
-        request_id,
-        pay_token_id,
-        pay_amount,
-        receive_token_id,
-        receive_amount,
-        mid_price,
-        price,
-        slippage,
-        txs,
-        transfer_ids,
-        &claim_ids,
-        ts,
-    );
+    let swap_tx = SwapTx {
+        to_address: Some(to_address.clone()),
+        ..SwapTx::new_success(
+            user_id,
+            request_id,
+            pay_token_id,
+            pay_amount,
+            receive_token_id,
+            receive_amount,
+            mid_price,
+            price,
+            slippage,
+            txs,
+            transfer_ids,
+            &claim_ids,
+            ts,
+        )
+    };
     let tx_id = tx_map::insert(&StableTx::Swap(swap_tx.clone()));
     let reply = match tx_map::get_by_user_and_token_id(Some(tx_id), None, None, None).first() {
         Some(StableTx::Swap(swap_tx)) => to_swap_reply(swap_tx),
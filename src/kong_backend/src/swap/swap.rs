@@ -1,6 +1,7 @@
 use ic_cdk::update;
 
 use super::swap_args::SwapArgs;
+use super::swap_deposit::swap_deposit;
 use super::swap_reply::SwapReply;
 use super::swap_transfer::{swap_transfer, swap_transfer_async};
 use super::swap_transfer_from::{swap_transfer_from, swap_transfer_from_async};
@@ -11,6 +12,10 @@ use crate::ic::guards::not_in_maintenance_mode;
 /// Swap tokens
 #[update(guard = "not_in_maintenance_mode")]
 pub async fn swap(args: SwapArgs) -> Result<SwapReply, String> {
+    // swap against the user's deposit
+    if args.use_deposit == Some(true) {
+        return swap_deposit(args);
+    }
     // determine if using icrc2_approve+icrc2_transfer_from or icrc1_transfer method
     match args.pay_tx_id {
         None => swap_transfer_from(args).await,
@@ -21,6 +26,10 @@ pub async fn swap(args: SwapArgs) -> Result<SwapReply, String> {
 /// Swap tokens asynchronously
 #[update(guard = "not_in_maintenance_mode")]
 pub async fn swap_async(args: SwapArgs) -> Result<u64, String> {
+    // swaps against the deposit complete immediately
+    if args.use_deposit == Some(true) {
+        return swap_deposit(args).map(|reply| reply.request_id);
+    }
     // determine if using icrc2_approve+icrc2_transfer_from or icrc1_transfer method
     match args.pay_tx_id {
         None => swap_transfer_from_async(args).await,
@@ -2,6 +2,7 @@ use candid::Nat;
 use num::rational::BigRational;
 use num::{FromPrimitive, One, Zero};
 use num_traits::ToPrimitive;
+use std::collections::{BTreeMap, VecDeque};
 
 use super::swap_calc::SwapCalc;
 
@@ -9,13 +10,16 @@ use crate::helpers::math_helpers::price_rounded;
 use crate::helpers::math_helpers::round_f64;
 use crate::helpers::nat_helpers::nat_zero;
 use crate::helpers::nat_helpers::{
-    nat_add, nat_divide, nat_is_zero, nat_multiply, nat_multiply_f64, nat_subtract, nat_to_decimal_precision,
+    nat_add, nat_divide, nat_is_zero, nat_multiply, nat_multiply_f64, nat_subtract, nat_to_bigint, nat_to_decimal_precision, nat_to_u64,
 };
+use crate::ic::get_time::get_time;
+use crate::stable_kong_settings::kong_settings_map;
 use crate::stable_pool::pool_map;
+use crate::stable_pool::pool_type::PoolType;
 use crate::stable_pool::stable_pool::StablePool;
+use crate::stable_pool::stable_swap_pool;
 use crate::stable_token::stable_token::StableToken;
 use crate::stable_token::token::Token;
-use crate::stable_token::token_map;
 use crate::stable_user::user_map;
 
 /// calculate the receive_amount of a swap using mid price
@@ -38,10 +42,64 @@ pub fn swap_mid_price(pay_token: &StableToken, receive_token: &StableToken) -> R
 /// pay_token - pay token
 /// pay_amount - amount of pay token. pay_amount is None if only mid price is requested
 /// receive_token - receive token
+#[allow(clippy::complexity)]
 pub fn swap_amounts(
     pay_token: &StableToken,
     pay_amount: Option<&Nat>,
     receive_token: &StableToken,
+) -> Result<(Nat, f64, f64, f64, Vec<SwapCalc>), String> {
+    // if pay_amount is None, user_fee_level is None as only mid_price is needed
+    let user_fee_level = pay_amount.map(|_| caller_fee_level());
+    route_swap_amounts(pay_token, pay_amount, receive_token, user_fee_level, true)
+}
+
+/// same as swap_amounts() with the fee level of user_id instead of the caller
+/// used when the swap is not made by the caller, e.g. limit orders filled by the timer
+#[allow(clippy::complexity)]
+pub fn user_swap_amounts(
+    user_id: u32,
+    pay_token: &StableToken,
+    pay_amount: &Nat,
+    receive_token: &StableToken,
+) -> Result<(Nat, f64, f64, f64, Vec<SwapCalc>), String> {
+    let user_fee_level = user_map::get_by_user_id(user_id).unwrap_or_default().fee_level;
+    route_swap_amounts(pay_token, Some(pay_amount), receive_token, Some(user_fee_level), true)
+}
+
+/// calculate the receive_amount of a swap whose received tokens stay in the canister, so no gas fee is taken
+/// used for the intermediate swaps of batch requests
+#[allow(clippy::complexity)]
+pub fn internal_swap_amounts(
+    pay_token: &StableToken,
+    pay_amount: &Nat,
+    receive_token: &StableToken,
+) -> Result<(Nat, f64, f64, f64, Vec<SwapCalc>), String> {
+    route_swap_amounts(pay_token, Some(pay_amount), receive_token, Some(caller_fee_level()), false)
+}
+
+/// same as internal_swap_amounts() for swaps made by Kong itself without a caller, e.g. the KONG buyback of the fee sweep
+#[allow(clippy::complexity)]
+pub fn system_swap_amounts(
+    pay_token: &StableToken,
+    pay_amount: &Nat,
+    receive_token: &StableToken,
+) -> Result<(Nat, f64, f64, f64, Vec<SwapCalc>), String> {
+    route_swap_amounts(pay_token, Some(pay_amount), receive_token, Some(0), false)
+}
+
+fn caller_fee_level() -> u8 {
+    user_map::get_by_caller().ok().flatten().unwrap_or_default().fee_level
+}
+
+/// user_fee_level - fee level of the user making the swap, None if pay_amount is None
+/// charge_gas_fee - if false, the receive token is not sent out so the swap does not take the gas fee
+#[allow(clippy::complexity)]
+fn route_swap_amounts(
+    pay_token: &StableToken,
+    pay_amount: Option<&Nat>,
+    receive_token: &StableToken,
+    user_fee_level: Option<u8>,
+    charge_gas_fee: bool,
 ) -> Result<(Nat, f64, f64, f64, Vec<SwapCalc>), String> {
     let pay_token_id = pay_token.token_id();
     let receive_token_id = receive_token.token_id();
@@ -53,409 +111,439 @@ pub fn swap_amounts(
         return Ok((receive_amount, 1.0, 1.0, 0.0, Vec::new()));
     }
 
-    // if pay_amount is None, user_fee_level is None as only mid_price is needed
-    let user_fee_level = pay_amount.map(|_| user_map::get_by_caller().ok().flatten().unwrap_or_default().fee_level);
+    let kong_settings = kong_settings_map::get();
+    let ts = get_time();
+    let graph = SwapGraph::new(pool_map::get());
+    let paths = graph.find_paths(pay_token_id, receive_token_id, kong_settings.max_swap_hops);
+    let (swaps, swap_error) = paths_swap_amounts(&paths, pay_amount, user_fee_level, charge_gas_fee, ts);
 
-    // swaps stores all the swap permutations
-    let mut swaps: Vec<(Nat, f64, f64, f64, Vec<SwapCalc>)> = Vec::new();
+    let Some(pay_amount) = pay_amount else {
+        // return the swap with the highest mid_price
+        // paths are in order of number of hops, so on a tie the path with the fewest hops is kept
+        return swaps
+            .into_iter()
+            .map(|(_, swap)| swap)
+            .reduce(|max, swap| if swap.2 > max.2 { swap } else { max })
+            .ok_or_else(|| swap_error.unwrap_or_else(|| "Invalid swap".to_string()));
+    };
 
-    // 1-step swap
-    one_step_swaps(pay_token_id, pay_amount, receive_token_id, user_fee_level, &mut swaps)?;
+    let split_paths: Vec<_> = swaps.iter().map(|(path_idx, _)| paths[*path_idx].as_slice()).collect();
+    let split_swap = split_swap_amounts(
+        pay_token,
+        pay_amount,
+        receive_token,
+        &split_paths,
+        user_fee_level,
+        kong_settings.max_swap_legs,
+        charge_gas_fee,
+        ts,
+    );
+
+    // return the swap with the highest receive amount, either the best single path or the split swap
+    let max_swap = swaps.into_iter().map(|(_, swap)| swap).next();
+    match (max_swap, split_swap) {
+        (Some(max_swap), Some(split_swap)) if split_swap.0 > max_swap.0 => Ok(split_swap),
+        (Some(max_swap), _) => Ok(max_swap),
+        (None, _) => Err(swap_error.unwrap_or_else(|| "Invalid swap".to_string())),
+    }
+}
 
-    // 2-step swap
-    two_step_swaps(pay_token_id, pay_amount, receive_token_id, user_fee_level, &mut swaps)?;
+/// max number of candidate paths priced by the router. paths are found in order of number of hops, so the cap drops
+/// the longest paths first
+const MAX_SWAP_PATHS: usize = 32;
 
-    // 3-step swap
-    three_step_swaps(pay_token_id, pay_amount, receive_token_id, user_fee_level, &mut swaps)?;
+/// a hop of a swap path. index of the pair and true if the hop pays token_0 of the pair
+type SwapHop = (usize, bool);
 
-    let max_swap = if pay_amount.is_none() {
-        // return the swap with the highest mid_price
-        swaps
+/// a hop of a swap path. the fee tier pools of the pair and true if the hop pays token_0 of the pair
+/// the hop swaps through the fee tier with the best price
+type SwapPathHop<'a> = (&'a [StablePool], bool);
+
+/// the pools grouped by pair and the hops from each token. built once per swap so the path search does not read or
+/// clone the pools again
+struct SwapGraph {
+    pairs: Vec<Vec<StablePool>>,
+    hops: BTreeMap<u32, Vec<SwapHop>>, // token_id -> hops to the tokens paired with it
+}
+
+impl SwapGraph {
+    fn new(pools: Vec<StablePool>) -> Self {
+        // group the fee tier pools of each pair, so the paths are found between pairs and each hop picks its best fee tier
+        let mut pairs: Vec<Vec<StablePool>> = Vec::new();
+        let mut pair_idxs: BTreeMap<(u32, u32), usize> = BTreeMap::new();
+        for pool in pools {
+            let pair_idx = *pair_idxs.entry((pool.token_id_0, pool.token_id_1)).or_insert_with(|| {
+                pairs.push(Vec::new());
+                pairs.len() - 1
+            });
+            pairs[pair_idx].push(pool);
+        }
+
+        let mut hops: BTreeMap<u32, Vec<SwapHop>> = BTreeMap::new();
+        for (pair_idx, pools) in pairs.iter().enumerate() {
+            hops.entry(pools[0].token_id_0).or_default().push((pair_idx, true));
+            hops.entry(pools[0].token_id_1).or_default().push((pair_idx, false));
+        }
+
+        SwapGraph { pairs, hops }
+    }
+
+    /// token received by a hop. if the hop pays token_0, it receives token_1 and vice versa
+    fn hop_receive_token_id(&self, (pair_idx, is_token_0): SwapHop) -> u32 {
+        let pool = &self.pairs[pair_idx][0];
+        if is_token_0 {
+            pool.token_id_1
+        } else {
+            pool.token_id_0
+        }
+    }
+
+    /// find up to MAX_SWAP_PATHS paths from pay_token to receive_token through at most max_hops pairs
+    /// a path never goes through the same token twice. paths are returned in order of number of hops
+    fn find_paths(&self, pay_token_id: u32, receive_token_id: u32, max_hops: u8) -> Vec<Vec<SwapPathHop<'_>>> {
+        let max_hops = max_hops.max(1) as usize;
+
+        // number of hops from each token to receive_token. used to prune paths that cannot reach receive_token in time
+        let mut distances = BTreeMap::from([(receive_token_id, 0_usize)]);
+        let mut queue = VecDeque::from([receive_token_id]);
+        while let Some(token_id) = queue.pop_front() {
+            let distance = distances[&token_id];
+            if distance >= max_hops {
+                continue;
+            }
+            for hop in self.hops.get(&token_id).into_iter().flatten() {
+                let next_token_id = self.hop_receive_token_id(*hop);
+                if let std::collections::btree_map::Entry::Vacant(entry) = distances.entry(next_token_id) {
+                    entry.insert(distance + 1);
+                    queue.push_back(next_token_id);
+                }
+            }
+        }
+
+        // search the paths of 1 hop, then of 2 hops and so on, until max_hops or MAX_SWAP_PATHS paths are found
+        let mut paths = Vec::new();
+        let mut search = PathSearch {
+            graph: self,
+            distances: &distances,
+            receive_token_id,
+            path: Vec::new(),
+            visited: vec![pay_token_id],
+            paths: &mut paths,
+        };
+        for num_hops in 1..=max_hops {
+            search.search(pay_token_id, num_hops);
+            if search.paths.len() >= MAX_SWAP_PATHS {
+                break;
+            }
+        }
+
+        paths
             .into_iter()
-            .max_by(|a, b| a.2.partial_cmp(&b.2).unwrap())
-            .ok_or("Invalid swap")?
-    } else {
-        // return the swap with the highest receive amount
-        swaps.into_iter().max_by(|a, b| a.0.cmp(&b.0)).ok_or("Invalid swap")?
-    };
+            .map(|path| {
+                path.into_iter()
+                    .map(|(pair_idx, is_token_0)| (self.pairs[pair_idx].as_slice(), is_token_0))
+                    .collect()
+            })
+            .collect()
+    }
+}
 
-    Ok(max_swap)
+/// depth-first search of the paths with a given number of hops
+struct PathSearch<'a> {
+    graph: &'a SwapGraph,
+    distances: &'a BTreeMap<u32, usize>,
+    receive_token_id: u32,
+    path: Vec<SwapHop>,
+    visited: Vec<u32>,
+    paths: &'a mut Vec<Vec<SwapHop>>,
 }
 
-/// returns (receive_amount_with_gas_and_fees, price, mid_price, slippage, swap)
+impl PathSearch<'_> {
+    fn search(&mut self, token_id: u32, num_hops: usize) {
+        let remaining_hops = num_hops - self.path.len() - 1;
+        for hop in self.graph.hops.get(&token_id).into_iter().flatten() {
+            if self.paths.len() >= MAX_SWAP_PATHS {
+                return;
+            }
+            let next_token_id = self.graph.hop_receive_token_id(*hop);
+            if self.visited.contains(&next_token_id) {
+                continue;
+            }
+            // skip tokens that cannot reach receive_token with the remaining hops
+            match self.distances.get(&next_token_id) {
+                Some(distance) if *distance <= remaining_hops => (),
+                _ => continue,
+            }
+
+            if next_token_id == self.receive_token_id {
+                // shorter paths were found by the previous searches
+                if remaining_hops == 0 {
+                    let mut path = self.path.clone();
+                    path.push(*hop);
+                    self.paths.push(path);
+                }
+                continue;
+            }
+            if remaining_hops == 0 {
+                continue;
+            }
+            self.path.push(*hop);
+            self.visited.push(next_token_id);
+            self.search(next_token_id, num_hops);
+            self.visited.pop();
+            self.path.pop();
+        }
+    }
+}
+
+/// the swap of every path that can be swapped, along with the index of its path, and the first error encountered
+/// with a pay_amount, the swaps are ranked by receive amount, highest first. sort is stable so on a tie the path with
+/// the fewest hops is first
 #[allow(clippy::complexity)]
-fn one_step_swaps(
-    pay_token_id: u32,
+fn paths_swap_amounts(
+    paths: &[Vec<SwapPathHop>],
     pay_amount: Option<&Nat>,
-    receive_token_id: u32,
     user_fee_level: Option<u8>,
-    swaps: &mut Vec<(Nat, f64, f64, f64, Vec<SwapCalc>)>,
-) -> Result<(), String> {
-    let swap = if let Some(pool) = pool_map::get_by_token_ids(pay_token_id, receive_token_id) {
-        swap_amount_0(&pool, pay_amount, user_fee_level, None, None)?
-    } else if let Some(pool) = pool_map::get_by_token_ids(receive_token_id, pay_token_id) {
-        swap_amount_1(&pool, pay_amount, user_fee_level, None, None)?
-    } else {
-        return Ok(());
-    };
+    charge_gas_fee: bool,
+    ts: u64,
+) -> (Vec<(usize, (Nat, f64, f64, f64, Vec<SwapCalc>))>, Option<String>) {
+    let mut swaps = Vec::new();
+    let mut swap_error = None;
+    for (path_idx, path) in paths.iter().enumerate() {
+        match path_swap_amounts(path, pay_amount, user_fee_level, charge_gas_fee, ts) {
+            Ok(swap) => swaps.push((path_idx, swap)),
+            Err(e) => {
+                swap_error.get_or_insert(e);
+            }
+        }
+    }
+    if pay_amount.is_some() {
+        swaps.sort_by(|a, b| b.1 .0.cmp(&a.1 .0));
+    }
+    (swaps, swap_error)
+}
 
+/// calculate the swap along a path of pools
+/// returns (receive_amount_with_gas_and_fees, price, mid_price, slippage, swaps)
+#[allow(clippy::complexity)]
+fn path_swap_amounts(
+    path: &[SwapPathHop],
+    pay_amount: Option<&Nat>,
+    user_fee_level: Option<u8>,
+    charge_gas_fee: bool,
+    ts: u64,
+) -> Result<(Nat, f64, f64, f64, Vec<SwapCalc>), String> {
+    let swaps = path_swaps(path, pay_amount, user_fee_level, charge_gas_fee, ts)?;
+    swaps_amounts(swaps, pay_amount)
+}
+
+/// calculate the swap of pay_amount through a single pool. pay_token_0 is true if paying token_0 of the pool
+/// used for the internal swap of single token liquidity, the received tokens stay in the canister so no gas fee is taken
+#[allow(clippy::complexity)]
+pub fn pool_swap_amounts(pool: &StablePool, pay_token_0: bool, pay_amount: &Nat) -> Result<(Nat, f64, f64, f64, Vec<SwapCalc>), String> {
+    let user_fee_level = user_map::get_by_caller().ok().flatten().unwrap_or_default().fee_level;
+    let swaps = path_swaps(
+        &[(std::slice::from_ref(pool), pay_token_0)],
+        Some(pay_amount),
+        Some(user_fee_level),
+        false,
+        get_time(),
+    )?;
+    swaps_amounts(swaps, Some(pay_amount))
+}
+
+/// returns the receive_amount, price, mid_price and slippage of the swaps of a path
+#[allow(clippy::complexity)]
+fn swaps_amounts(swaps: Vec<SwapCalc>, pay_amount: Option<&Nat>) -> Result<(Nat, f64, f64, f64, Vec<SwapCalc>), String> {
+    let mid_price = swaps_mid_price(&swaps);
+    let mid_price_f64 = price_rounded(&mid_price).ok_or("Invalid mid price")?;
     if pay_amount.is_none() {
         // if pay_amount is None, return the mid price
-        let mid_price = swap.get_mid_price().unwrap_or(BigRational::zero());
-        let mid_price_f64 = price_rounded(&mid_price).ok_or("Invalid mid price")?;
-        swaps.push((nat_zero(), mid_price_f64, mid_price_f64, 0.0, vec![swap]));
-    } else {
-        let receive_amount = swap.receive_amount_with_fees_and_gas();
-        let price = swap.get_price().unwrap_or(BigRational::zero());
-        let price_f64 = price_rounded(&price).ok_or("Invalid price")?;
-        let mid_price = swap.get_mid_price().unwrap_or(BigRational::zero());
-        let mid_price_f64 = price_rounded(&mid_price).ok_or("Invalid mid price")?;
-        let slippage_f64 = get_slippage(&price, &mid_price).unwrap_or(0_f64);
-        swaps.push((receive_amount, price_f64, mid_price_f64, slippage_f64, vec![swap]));
+        return Ok((nat_zero(), mid_price_f64, mid_price_f64, 0.0, swaps));
     }
 
-    Ok(())
+    let receive_amount = swaps
+        .last()
+        .map(|swap| swap.receive_amount_with_fees_and_gas())
+        .unwrap_or(nat_zero());
+    let price = swaps.iter().fold(BigRational::one(), |price, swap| {
+        price * swap.get_price().unwrap_or(BigRational::zero())
+    });
+    let price_f64 = price_rounded(&price).ok_or("Invalid price")?;
+    let slippage_f64 = get_slippage(&price, &mid_price).unwrap_or(0_f64);
+    Ok((receive_amount, price_f64, mid_price_f64, slippage_f64, swaps))
 }
 
-#[allow(clippy::complexity)]
-fn two_step_swaps(
-    pay_token_id: u32,
+/// calculate the swaps of each hop along a path of pairs
+/// each hop swaps through the fee tier pool of the pair with the highest receive amount, or the highest mid price if
+/// pay_amount is None. on a tie the first pool of the pair is used
+/// user_fee_level - fee level of the user making the swap, None if pay_amount is None
+/// charge_gas_fee - if false, the last hop does not take gas fees either
+fn path_swaps(
+    path: &[SwapPathHop],
     pay_amount: Option<&Nat>,
-    receive_token_id: u32,
     user_fee_level: Option<u8>,
-    swaps: &mut Vec<(Nat, f64, f64, f64, Vec<SwapCalc>)>,
-) -> Result<(), String> {
-    let ckusdt_token_id = token_map::get_ckusdt()?.token_id();
-    let icp_token_id = token_map::get_icp()?.token_id();
-
-    // test for 2-step swap via ckUSDT
-    // token0/ckUSDT -> ckUSDT/token1. make sure:
-    // 1) token0 != ckUSDT
-    // 2) token1 != ckUSDT
-    if pay_token_id != ckusdt_token_id && receive_token_id != ckusdt_token_id {
-        let pool1_ckusdt = pool_map::get_by_token_ids(pay_token_id, ckusdt_token_id);
-        let pool2_ckusdt = pool_map::get_by_token_ids(receive_token_id, ckusdt_token_id);
-        if pool1_ckusdt.is_some() && pool2_ckusdt.is_some() {
-            // 2-step swap
-            // split the LP fee between the two swaps. the "+ 1) / 2" will round up the integer
-            // 1st swap no gas fees as this is intermediate swap
-            // 2nd swap use standard gas fees
-            let pool1 = pool1_ckusdt.as_ref().unwrap();
-            let pool2 = pool2_ckusdt.as_ref().unwrap();
-            let swap1_lp_fee = (pool1.lp_fee_bps + 1) / 2;
-            // swap token0 to ckUSDT
-            let swap1 = swap_amount_0(pool1, pay_amount, user_fee_level, Some(swap1_lp_fee), Some(&nat_zero()))?;
-            let swap2_lp_fee = (pool2.lp_fee_bps + 1) / 2;
-            // swap ckUSDT to token1 (reverse order of pool)
-            let swap2 = swap_amount_1(
-                pool2,
-                Some(&swap1.receive_amount_with_fees_and_gas()),
-                user_fee_level,
-                Some(swap2_lp_fee),
-                None,
-            )?;
-            if pay_amount.is_none() {
-                // if pay_amount is None, return the mid price
-                let swap1_mid_price = swap1.get_mid_price().unwrap_or(BigRational::zero());
-                let swap2_mid_price = swap2.get_mid_price().unwrap_or(BigRational::zero());
-                let mid_price = swap1_mid_price * swap2_mid_price;
-                let mid_price_f64 = price_rounded(&mid_price).ok_or("Invalid mid price")?;
-                swaps.push((nat_zero(), mid_price_f64, mid_price_f64, 0.0, vec![swap1, swap2]));
+    charge_gas_fee: bool,
+    ts: u64,
+) -> Result<Vec<SwapCalc>, String> {
+    let num_hops = path.len();
+    let mut swaps: Vec<SwapCalc> = Vec::with_capacity(num_hops);
+
+    for (hop, (pools, is_token_0)) in path.iter().enumerate() {
+        // intermediate hops do not take gas fees, last hop uses standard gas fees
+        let zero_gas_fee = nat_zero();
+        let use_gas_fee = if hop < num_hops - 1 || !charge_gas_fee {
+            Some(&zero_gas_fee)
+        } else {
+            None
+        };
+        // first hop pays pay_amount, next hops pay what the previous hop received
+        let amount = swaps.last().map(|swap| swap.receive_amount_with_fees_and_gas());
+        let amount = if hop == 0 { pay_amount } else { amount.as_ref() };
+
+        let mut best_swap: Option<SwapCalc> = None;
+        let mut swap_error = None;
+        for pool in pools.iter() {
+            // multi-hop swaps split the LP fee between the hops. the "+ 1) / num_hops" will round up the integer
+            let use_lp_fee = if num_hops > 1 {
+                Some(pool.swap_lp_fee_bps(*is_token_0, ts).saturating_add(1) / num_hops as u8)
             } else {
-                let receive_amount = swap2.receive_amount_with_fees_and_gas();
-                let swap1_price = swap1.get_price().unwrap_or(BigRational::zero());
-                let swap2_price = swap2.get_price().unwrap_or(BigRational::zero());
-                let price = swap1_price * swap2_price;
-                let price_f64 = price_rounded(&price).ok_or("Invalid price")?;
-                let swap1_mid_price = swap1.get_mid_price().unwrap_or(BigRational::zero());
-                let swap2_mid_price = swap2.get_mid_price().unwrap_or(BigRational::zero());
-                let mid_price = swap1_mid_price * swap2_mid_price;
-                let mid_price_f64 = price_rounded(&mid_price).ok_or("Invalid mid price")?;
-                let slippage_f64 = get_slippage(&price, &mid_price).unwrap_or(0_f64);
-                swaps.push((receive_amount, price_f64, mid_price_f64, slippage_f64, vec![swap1, swap2]));
-            }
-        }
-    }
-
-    // test for 2-step swap via ICP
-    // token0/ICP -> ICP/token1. make sure:
-    // 1) token0 != ICP
-    // 2) token1 != ICP
-    if pay_token_id != icp_token_id && receive_token_id != icp_token_id {
-        let pool1_icp = pool_map::get_by_token_ids(pay_token_id, icp_token_id);
-        let pool2_icp = pool_map::get_by_token_ids(receive_token_id, icp_token_id);
-        if pool1_icp.is_some() && pool2_icp.is_some() {
-            // 2 pools: token0/ICP and token1/ICP routing token0 -> ICP -> token1
-            let pool1 = pool1_icp.as_ref().unwrap();
-            let pool2 = pool2_icp.as_ref().unwrap();
-            let swap1_lp_fee = (pool1.lp_fee_bps + 1) / 2;
-            // swap token0 to ICP
-            let swap1 = swap_amount_0(pool1, pay_amount, user_fee_level, Some(swap1_lp_fee), Some(&nat_zero()))?;
-            let swap2_lp_fee = (pool2.lp_fee_bps + 1) / 2;
-            // swap ICP to token1 (reverse order of pool)
-            let swap2 = swap_amount_1(
-                pool2,
-                Some(&swap1.receive_amount_with_fees_and_gas()),
-                user_fee_level,
-                Some(swap2_lp_fee),
-                None,
-            )?;
-            if pay_amount.is_none() {
-                // if pay_amount is None, return the mid price
-                let swap1_mid_price = swap1.get_mid_price().unwrap_or(BigRational::zero());
-                let swap2_mid_price = swap2.get_mid_price().unwrap_or(BigRational::zero());
-                let mid_price = swap1_mid_price * swap2_mid_price;
-                let mid_price_f64 = price_rounded(&mid_price).ok_or("Invalid mid price")?;
-                swaps.push((nat_zero(), mid_price_f64, mid_price_f64, 0.0, vec![swap1, swap2]));
+                None
+            };
+            let swap = if *is_token_0 {
+                swap_amount_0(pool, amount, user_fee_level, use_lp_fee, use_gas_fee, ts)
             } else {
-                let receive_amount = swap2.receive_amount_with_fees_and_gas();
-                let swap1_price = swap1.get_price().unwrap_or(BigRational::zero());
-                let swap2_price = swap2.get_price().unwrap_or(BigRational::zero());
-                let price = swap1_price * swap2_price;
-                let price_f64 = price_rounded(&price).ok_or("Invalid price")?;
-                let swap1_mid_price = swap1.get_mid_price().unwrap_or(BigRational::zero());
-                let swap2_mid_price = swap2.get_mid_price().unwrap_or(BigRational::zero());
-                let mid_price = swap1_mid_price * swap2_mid_price;
-                let mid_price_f64 = price_rounded(&mid_price).ok_or("Invalid mid price")?;
-                let slippage_f64 = get_slippage(&price, &mid_price).unwrap_or(0_f64);
-                swaps.push((receive_amount, price_f64, mid_price_f64, slippage_f64, vec![swap1, swap2]));
+                // swap is in reverse order of pool
+                swap_amount_1(pool, amount, user_fee_level, use_lp_fee, use_gas_fee, ts)
             };
+            match swap {
+                Ok(swap) => match &best_swap {
+                    Some(best) if !is_better_swap(&swap, best, amount.is_some()) => (),
+                    _ => best_swap = Some(swap),
+                },
+                Err(e) => {
+                    swap_error.get_or_insert(e);
+                }
+            }
         }
+        swaps.push(best_swap.ok_or_else(|| swap_error.unwrap_or_else(|| "Invalid swap".to_string()))?);
     }
 
-    // special case where pay token is ckUSDT so need to use
-    // ckUSDT/ICP -> token1/ICP
-    // because there's no ckUSDT/ICP pool so need to use ICP/ckUSDT pool
-    let pool1_icp_ckusdt = pool_map::get_by_token_ids(icp_token_id, ckusdt_token_id);
-    let pool2_icp = pool_map::get_by_token_ids(receive_token_id, icp_token_id);
-    if pay_token_id == ckusdt_token_id && pool1_icp_ckusdt.is_some() && pool2_icp.is_some() {
-        let pool1 = pool1_icp_ckusdt.as_ref().unwrap();
-        let pool2 = pool2_icp.as_ref().unwrap();
-        let swap1_lp_fee = (pool1.lp_fee_bps + 1) / 2;
-        // swap ckUSDT to ICP (reverse order of pool)
-        let swap1 = swap_amount_1(pool1, pay_amount, user_fee_level, Some(swap1_lp_fee), Some(&nat_zero()))?;
-        let swap2_lp_fee = (pool2.lp_fee_bps + 1) / 2;
-        // swap ICP to token1 (reverse order of pool)
-        let swap2 = swap_amount_1(
-            pool2,
-            Some(&swap1.receive_amount_with_fees_and_gas()),
-            user_fee_level,
-            Some(swap2_lp_fee),
-            None,
-        )?;
-        if pay_amount.is_none() {
-            // if pay_amount is None, return the mid price
-            let swap1_mid_price = swap1.get_mid_price().unwrap_or(BigRational::zero());
-            let swap2_mid_price = swap2.get_mid_price().unwrap_or(BigRational::zero());
-            let mid_price = swap1_mid_price * swap2_mid_price;
-            let mid_price_f64 = price_rounded(&mid_price).ok_or("Invalid mid price")?;
-            swaps.push((nat_zero(), mid_price_f64, mid_price_f64, 0.0, vec![swap1, swap2]));
-        } else {
-            let receive_amount = swap2.receive_amount_with_fees_and_gas();
-            let swap1_price = swap1.get_price().unwrap_or(BigRational::zero());
-            let swap2_price = swap2.get_price().unwrap_or(BigRational::zero());
-            let price = swap1_price * swap2_price;
-            let price_f64 = price_rounded(&price).ok_or("Invalid price")?;
-            let swap1_mid_price = swap1.get_mid_price().unwrap_or(BigRational::zero());
-            let swap2_mid_price = swap2.get_mid_price().unwrap_or(BigRational::zero());
-            let mid_price = swap1_mid_price * swap2_mid_price;
-            let mid_price_f64 = price_rounded(&mid_price).ok_or("Invalid mid price")?;
-            let slippage_f64 = get_slippage(&price, &mid_price).unwrap_or(0_f64);
-            swaps.push((receive_amount, price_f64, mid_price_f64, slippage_f64, vec![swap1, swap2]));
-        }
-    };
+    Ok(swaps)
+}
 
-    // special case where receieve token is ckUSDT so need to use
-    // token0/ICP -> ICP/ckUSDT
-    let pool1_icp = pool_map::get_by_token_ids(pay_token_id, icp_token_id);
-    let pool2_icp_ckusdt = pool_map::get_by_token_ids(icp_token_id, ckusdt_token_id);
-    if receive_token_id == ckusdt_token_id && pool1_icp.is_some() && pool2_icp_ckusdt.is_some() {
-        let pool1 = pool1_icp.as_ref().unwrap();
-        let pool2 = pool2_icp_ckusdt.as_ref().unwrap();
-        let swap1_lp_fee = (pool1.lp_fee_bps + 1) / 2;
-        // swap token0 to ICP
-        let swap1 = swap_amount_0(pool1, pay_amount, user_fee_level, Some(swap1_lp_fee), Some(&nat_zero()))?;
-        let swap2_lp_fee = (pool2.lp_fee_bps + 1) / 2;
-        // swap ICP to ckUSDT
-        let swap2 = swap_amount_0(
-            pool2,
-            Some(&swap1.receive_amount_with_fees_and_gas()),
-            user_fee_level,
-            Some(swap2_lp_fee),
-            None,
-        )?;
-        if pay_amount.is_none() {
-            // if pay_amount is None, return the mid price
-            let swap1_mid_price = swap1.get_mid_price().unwrap_or(BigRational::zero());
-            let swap2_mid_price = swap2.get_mid_price().unwrap_or(BigRational::zero());
-            let mid_price = swap1_mid_price * swap2_mid_price;
-            let mid_price_f64 = price_rounded(&mid_price).ok_or("Invalid mid price")?;
-            swaps.push((nat_zero(), mid_price_f64, mid_price_f64, 0.0, vec![swap1, swap2]));
-        } else {
-            let receive_amount = swap2.receive_amount_with_fees_and_gas();
-            let swap1_price = swap1.get_price().unwrap_or(BigRational::zero());
-            let swap2_price = swap2.get_price().unwrap_or(BigRational::zero());
-            let price = swap1_price * swap2_price;
-            let price_f64 = price_rounded(&price).ok_or("Invalid price")?;
-            let swap1_mid_price = swap1.get_mid_price().unwrap_or(BigRational::zero());
-            let swap2_mid_price = swap2.get_mid_price().unwrap_or(BigRational::zero());
-            let mid_price = swap1_mid_price * swap2_mid_price;
-            let mid_price_f64 = price_rounded(&mid_price).ok_or("Invalid mid price")?;
-            let slippage_f64 = get_slippage(&price, &mid_price).unwrap_or(0_f64);
-            swaps.push((receive_amount, price_f64, mid_price_f64, slippage_f64, vec![swap1, swap2]));
-        }
-    };
+/// true if swap through a fee tier pool is better than best. by receive amount, or by mid price if there is no pay amount
+fn is_better_swap(swap: &SwapCalc, best: &SwapCalc, has_pay_amount: bool) -> bool {
+    if has_pay_amount {
+        swap.receive_amount_with_fees_and_gas() > best.receive_amount_with_fees_and_gas()
+    } else {
+        swap.get_mid_price().unwrap_or(BigRational::zero()) > best.get_mid_price().unwrap_or(BigRational::zero())
+    }
+}
 
-    Ok(())
+fn swaps_mid_price(swaps: &[SwapCalc]) -> BigRational {
+    swaps.iter().fold(BigRational::one(), |mid_price, swap| {
+        mid_price * swap.get_mid_price().unwrap_or(BigRational::zero())
+    })
 }
 
+/// pay_amount is split between the legs in steps of 1 / SWAP_SPLIT_STEPS (5%)
+const SWAP_SPLIT_STEPS: u32 = 20;
+
+/// calculate a swap where pay_amount is split between several paths that do not share any pool
+/// the steps of pay_amount are allocated one at a time to the leg with the highest marginal receive amount
+/// returns None if the best allocation uses a single path
+///
+/// paths - candidate paths, ranked by receive amount, highest first
+/// max_legs - max number of paths pay_amount is split into
+/// user_fee_level - fee level of the user making the swap, None if pay_amount is None
+/// charge_gas_fee - if false, the largest leg does not take the gas fee either
 #[allow(clippy::complexity)]
-fn three_step_swaps(
-    pay_token_id: u32,
-    pay_amount: Option<&Nat>,
-    receive_token_id: u32,
+fn split_swap_amounts(
+    pay_token: &StableToken,
+    pay_amount: &Nat,
+    receive_token: &StableToken,
+    paths: &[&[SwapPathHop]],
     user_fee_level: Option<u8>,
-    swaps: &mut Vec<(Nat, f64, f64, f64, Vec<SwapCalc>)>,
-) -> Result<(), String> {
-    let ckusdt_token_id = token_map::get_ckusdt()?.token_id();
-    let icp_token_id = token_map::get_icp()?.token_id();
-    let pool2_icp_ckusdt = pool_map::get_by_token_ids(icp_token_id, ckusdt_token_id);
-
-    // token0/ckUSDT -> ckUSDT/ICP -> ICP/token1. make sure:
-    // 1) token0 != ckUSDT
-    // 2) token0 != ICP && token1 != ckUSDT
-    // 3) token1 != ICP
-    if pay_token_id != ckusdt_token_id
-        && (pay_token_id != icp_token_id && receive_token_id != ckusdt_token_id)
-        && receive_token_id != icp_token_id
-    {
-        let pool1_ckusdt = pool_map::get_by_token_ids(pay_token_id, ckusdt_token_id);
-        let pool3_icp = pool_map::get_by_token_ids(receive_token_id, icp_token_id);
-        if pool1_ckusdt.is_some() && pool2_icp_ckusdt.is_some() && pool3_icp.is_some() {
-            let pool1 = pool1_ckusdt.as_ref().unwrap();
-            let pool2 = pool2_icp_ckusdt.as_ref().unwrap();
-            let pool3 = pool3_icp.as_ref().unwrap();
-            let swap1_lp_fee = (pool1.lp_fee_bps + 1) / 3;
-            // swap token0 to ckUSDT
-            let swap1 = swap_amount_0(
-                pool1,
-                pay_amount,
-                user_fee_level,
-                Some(swap1_lp_fee),
-                Some(&nat_zero()), // swap1 do not take gas fees
-            )?;
-            let swap2_lp_fee = (pool2.lp_fee_bps + 1) / 3;
-            // swap ckUSDT to ICP (reverse order of pool)
-            let swap2 = swap_amount_1(
-                pool2,
-                Some(&swap1.receive_amount_with_fees_and_gas()),
-                user_fee_level,
-                Some(swap2_lp_fee),
-                Some(&nat_zero()), // swap2 do not take gas fees
-            )?;
-            let swap3_lp_fee = (pool3.lp_fee_bps + 1) / 3;
-            // swap ICP to token1 (reverse order of pool)
-            let swap3 = swap_amount_1(
-                pool3,
-                Some(&swap2.receive_amount_with_fees_and_gas()),
-                user_fee_level,
-                Some(swap3_lp_fee),
-                None,
-            )?;
-            if pay_amount.is_none() {
-                // if pay_amount is None, return the mid price
-                let swap1_mid_price = swap1.get_mid_price().unwrap_or(BigRational::zero());
-                let swap2_mid_price = swap2.get_mid_price().unwrap_or(BigRational::zero());
-                let swap3_mid_price = swap3.get_mid_price().unwrap_or(BigRational::zero());
-                let mid_price = swap1_mid_price * swap2_mid_price * swap3_mid_price;
-                let mid_price_f64 = price_rounded(&mid_price).ok_or("Invalid mid price")?;
-                swaps.push((nat_zero(), mid_price_f64, mid_price_f64, 0.0, vec![swap1, swap2, swap3]));
-            } else {
-                let receive_amount = swap3.receive_amount_with_fees_and_gas();
-                let swap1_price = swap1.get_price().unwrap_or(BigRational::zero());
-                let swap2_price = swap2.get_price().unwrap_or(BigRational::zero());
-                let swap3_price = swap3.get_price().unwrap_or(BigRational::zero());
-                let price = swap1_price * swap2_price * swap3_price;
-                let price_f64 = price_rounded(&price).ok_or("Invalid price")?;
-                let swap1_mid_price = swap1.get_mid_price().unwrap_or(BigRational::zero());
-                let swap2_mid_price = swap2.get_mid_price().unwrap_or(BigRational::zero());
-                let swap3_mid_price = swap3.get_mid_price().unwrap_or(BigRational::zero());
-                let mid_price = swap1_mid_price * swap2_mid_price * swap3_mid_price;
-                let mid_price_f64 = price_rounded(&mid_price).ok_or("Invalid mid price")?;
-                let slippage_f64 = get_slippage(&price, &mid_price).unwrap_or(0_f64);
-                swaps.push((receive_amount, price_f64, mid_price_f64, slippage_f64, vec![swap1, swap2, swap3]));
-            }
-        };
+    max_legs: u8,
+    charge_gas_fee: bool,
+    ts: u64,
+) -> Option<(Nat, f64, f64, f64, Vec<SwapCalc>)> {
+    // pick the best paths that do not share any pool, so the legs do not change each other's pools
+    let mut legs: Vec<&[SwapPathHop]> = Vec::new();
+    let mut leg_pool_ids: Vec<u32> = Vec::new();
+    for path in paths {
+        if legs.len() >= max_legs as usize {
+            break;
+        }
+        let mut path_pool_ids = path.iter().flat_map(|(pools, _)| pools.iter().map(|pool| pool.pool_id));
+        if path_pool_ids.any(|pool_id| leg_pool_ids.contains(&pool_id)) {
+            continue;
+        }
+        leg_pool_ids.extend(path.iter().flat_map(|(pools, _)| pools.iter().map(|pool| pool.pool_id)));
+        legs.push(path);
+    }
+    if legs.len() < 2 {
+        return None;
     }
 
-    // token0/ICP -> ICP/ckUSDT -> ckUSDT/token1. make sure:
-    // 1) token0 != ICP
-    // 2) token0 != ckUSDT && token1 != ICP
-    // 3) token1 != ckUSDT
-    if pay_token_id != icp_token_id
-        && (pay_token_id != ckusdt_token_id && receive_token_id != icp_token_id)
-        && receive_token_id != ckusdt_token_id
-    {
-        let pool1_icp = pool_map::get_by_token_ids(pay_token_id, icp_token_id);
-        let pool3_ckusdt = pool_map::get_by_token_ids(receive_token_id, ckusdt_token_id);
-        if pool1_icp.is_some() && pool2_icp_ckusdt.is_some() && pool3_ckusdt.is_some() {
-            let pool1 = pool1_icp.as_ref().unwrap();
-            let pool2 = pool2_icp_ckusdt.as_ref().unwrap();
-            let pool3 = pool3_ckusdt.as_ref().unwrap();
-            let swap1_lp_fee = (pool1.lp_fee_bps + 1) / 3;
-            // swap token0 to ICP
-            let swap1 = swap_amount_0(
-                pool1,
-                pay_amount,
-                user_fee_level,
-                Some(swap1_lp_fee),
-                Some(&nat_zero()), // swap1 do not take gas fees
-            )?;
-            let swap2_lp_fee = (pool2.lp_fee_bps + 1) / 3;
-            // swap ICP to ckUSDT
-            let swap2 = swap_amount_0(
-                pool2,
-                Some(&swap1.receive_amount_with_fees_and_gas()),
-                user_fee_level,
-                Some(swap2_lp_fee),
-                Some(&nat_zero()), // swap2 do not take gas fees
-            )?;
-            let swap3_lp_fee = (pool3.lp_fee_bps + 1) / 3;
-            // swap ckUSDT to token1 (reverse order of pool)
-            let swap3 = swap_amount_1(
-                pool3,
-                Some(&swap2.receive_amount_with_fees_and_gas()),
-                user_fee_level,
-                Some(swap3_lp_fee),
-                None,
-            )?;
-            if pay_amount.is_none() {
-                // if pay_amount is None, return the mid price
-                let swap1_mid_price = swap1.get_mid_price().unwrap_or(BigRational::zero());
-                let swap2_mid_price = swap2.get_mid_price().unwrap_or(BigRational::zero());
-                let swap3_mid_price = swap3.get_mid_price().unwrap_or(BigRational::zero());
-                let mid_price = swap1_mid_price * swap2_mid_price * swap3_mid_price;
-                let mid_price_f64 = price_rounded(&mid_price).ok_or("Invalid mid price")?;
-                swaps.push((nat_zero(), mid_price_f64, mid_price_f64, 0.0, vec![swap1, swap2, swap3]));
-            } else {
-                let receive_amount = swap3.receive_amount_with_fees_and_gas();
-                let swap1_price = swap1.get_price().unwrap_or(BigRational::zero());
-                let swap2_price = swap2.get_price().unwrap_or(BigRational::zero());
-                let swap3_price = swap3.get_price().unwrap_or(BigRational::zero());
-                let price = swap1_price * swap2_price * swap3_price;
-                let price_f64 = price_rounded(&price).ok_or("Invalid price")?;
-                let swap1_mid_price = swap1.get_mid_price().unwrap_or(BigRational::zero());
-                let swap2_mid_price = swap2.get_mid_price().unwrap_or(BigRational::zero());
-                let swap3_mid_price = swap3.get_mid_price().unwrap_or(BigRational::zero());
-                let mid_price = swap1_mid_price * swap2_mid_price * swap3_mid_price;
-                let mid_price_f64 = price_rounded(&mid_price).ok_or("Invalid mid price")?;
-                let slippage_f64 = get_slippage(&price, &mid_price).unwrap_or(0_f64);
-                swaps.push((receive_amount, price_f64, mid_price_f64, slippage_f64, vec![swap1, swap2, swap3]));
-            }
-        };
+    let leg_pay_amount = |steps: u32| nat_divide(&nat_multiply(pay_amount, &Nat::from(steps)), &Nat::from(SWAP_SPLIT_STEPS));
+
+    // gas fee is left out while allocating as the receive token is sent once for all the legs
+    let mut leg_steps = vec![0_u32; legs.len()];
+    let mut leg_receive_amounts = vec![nat_zero(); legs.len()];
+    for _ in 0..SWAP_SPLIT_STEPS {
+        let (leg, receive_amount, _) = legs
+            .iter()
+            .enumerate()
+            .filter_map(|(leg, path)| {
+                let amount = leg_pay_amount(leg_steps[leg] + 1)?;
+                let swaps = path_swaps(path, Some(&amount), user_fee_level, false, ts).ok()?;
+                let receive_amount = swaps.last()?.receive_amount_with_fees_and_gas();
+                let marginal_receive_amount = nat_subtract(&receive_amount, &leg_receive_amounts[leg]).unwrap_or(nat_zero());
+                Some((leg, receive_amount, marginal_receive_amount))
+            })
+            .reduce(|max, leg| if leg.2 > max.2 { leg } else { max })?;
+        leg_steps[leg] += 1;
+        leg_receive_amounts[leg] = receive_amount;
     }
 
-    Ok(())
+    // order the legs by allocation, largest first. the largest leg takes any rounding remainder and the gas fee
+    let mut allocations: Vec<(usize, u32)> = leg_steps.into_iter().enumerate().filter(|(_, steps)| *steps > 0).collect();
+    if allocations.len() < 2 {
+        return None;
+    }
+    allocations.sort_by(|a, b| b.1.cmp(&a.1));
+    let mut leg_pay_amounts: Vec<Nat> = allocations.iter().map(|(_, steps)| leg_pay_amount(*steps)).collect::<Option<_>>()?;
+    let allocated_amount = leg_pay_amounts.iter().fold(nat_zero(), |sum, amount| nat_add(&sum, amount));
+    let remainder = nat_subtract(pay_amount, &allocated_amount)?;
+    leg_pay_amounts[0] = nat_add(&leg_pay_amounts[0], &remainder);
+
+    let mut swaps = Vec::new();
+    let mut receive_amount = nat_zero();
+    let mut mid_price = None;
+    for (i, ((leg, _), amount)) in allocations.iter().zip(leg_pay_amounts.iter()).enumerate() {
+        let leg_swaps = path_swaps(legs[*leg], Some(amount), user_fee_level, i == 0 && charge_gas_fee, ts).ok()?;
+        receive_amount = nat_add(&receive_amount, &leg_swaps.last()?.receive_amount_with_fees_and_gas());
+        // mid price is the same for every path, up to the pools' spread. use the one of the largest leg
+        mid_price.get_or_insert_with(|| swaps_mid_price(&leg_swaps));
+        swaps.extend(leg_swaps);
+    }
+    let mid_price = mid_price?;
+    let mid_price_f64 = price_rounded(&mid_price)?;
+
+    // price = receive_amount / pay_amount, both in max_decimals precision
+    let max_decimals = std::cmp::max(pay_token.decimals(), receive_token.decimals());
+    let pay_amount_in_max_decimals = nat_to_bigint(&nat_to_decimal_precision(pay_amount, pay_token.decimals(), max_decimals));
+    let receive_amount_in_max_decimals = nat_to_bigint(&nat_to_decimal_precision(&receive_amount, receive_token.decimals(), max_decimals));
+    if pay_amount_in_max_decimals.is_zero() {
+        return None;
+    }
+    let price = BigRational::new(receive_amount_in_max_decimals, pay_amount_in_max_decimals);
+    let price_f64 = price_rounded(&price)?;
+    let slippage_f64 = get_slippage(&price, &mid_price).unwrap_or(0_f64);
+
+    Some((receive_amount, price_f64, mid_price_f64, slippage_f64, swaps))
 }
 
 /// Swap amount 0 of a given pool
@@ -466,6 +554,7 @@ fn swap_amount_0(
     user_fee_level: Option<u8>, // user specific fee level, 0 = 100% fee (no discount), 100 = 0% fee (max discount)
     use_lp_fee: Option<u8>,     // overwrite for LP fee in case of 2-legged synthetic swaps
     use_gas_fee: Option<&Nat>,  // overwrite for gas fee in case of synethetic swaps
+    ts: u64,
 ) -> Result<SwapCalc, String> {
     // Token 0
     let token_0 = pool.token_0();
@@ -485,6 +574,7 @@ fn swap_amount_0(
             receive_token_id: token_id_1,
             receive_amount: nat_zero(),
             lp_fee: nat_zero(),
+            lp_fee_bps: 0,
             gas_fee: nat_zero(),
         });
     }
@@ -499,6 +589,7 @@ fn swap_amount_0(
                 receive_token_id: token_id_1,
                 receive_amount: nat_zero(),
                 lp_fee: nat_zero(),
+                lp_fee_bps: 0,
                 gas_fee: nat_zero(),
             });
         }
@@ -511,10 +602,29 @@ fn swap_amount_0(
     let reserve_1_in_max_decimals = nat_to_decimal_precision(&reserve_1, token_1.decimals(), max_decimals);
     let amount_0_in_max_decimals = nat_to_decimal_precision(amount_0, token_0.decimals(), max_decimals);
 
-    // amount_1 = (amount_0 * reserve_1) / (reserve_0 + amount_0)
-    let numerator_in_max_decimals = nat_multiply(&amount_0_in_max_decimals, &reserve_1_in_max_decimals);
-    let denominator_in_max_decimals = nat_add(&reserve_0_in_max_decimals, &amount_0_in_max_decimals);
-    let amount_1_in_max_decimals = nat_divide(&numerator_in_max_decimals, &denominator_in_max_decimals).ok_or("Invalid amount_1")?;
+    let amount_1_in_max_decimals = match &pool.pool_type {
+        PoolType::ConstantProduct => {
+            // amount_1 = (amount_0 * reserve_1) / (reserve_0 + amount_0)
+            let numerator_in_max_decimals = nat_multiply(&amount_0_in_max_decimals, &reserve_1_in_max_decimals);
+            let denominator_in_max_decimals = nat_add(&reserve_0_in_max_decimals, &amount_0_in_max_decimals);
+            nat_divide(&numerator_in_max_decimals, &denominator_in_max_decimals).ok_or("Invalid amount_1")?
+        }
+        PoolType::Concentrated(concentrated_pool) => {
+            // swap through the ticks of a copy of the pool. amounts are in token precision
+            let amount_1 = concentrated_pool.clone().swap(true, amount_0, None)?;
+            nat_to_decimal_precision(&amount_1, token_1.decimals(), max_decimals)
+        }
+        PoolType::StableSwap(stable_swap) => {
+            // StableSwap invariant with the pool's current amp
+            let amp = stable_swap.amp(ts);
+            stable_swap_pool::swap_amount(
+                &reserve_0_in_max_decimals,
+                &reserve_1_in_max_decimals,
+                &amount_0_in_max_decimals,
+                amp,
+            )?
+        }
+    };
 
     // calculate the LP fees
     // any user fee discount. user.fee_level is 0 = 100% fee (no discount), 100 = 0% fee (max discount)
@@ -522,7 +632,10 @@ fn swap_amount_0(
     let user_lp_fee_pct = nat_subtract(&Nat::from(100_u8), &Nat::from(user_fee_level.unwrap_or(0_u8))).unwrap_or(Nat::from(100_u8));
     // user_lp_fee_bps = (user_lp_fee * user_lp_fee_pct) / 100 - user's fee level in bps with discount
     let user_lp_fee_bps = nat_divide(
-        &nat_multiply(&user_lp_fee_pct, &Nat::from(use_lp_fee.unwrap_or(pool.lp_fee_bps))),
+        &nat_multiply(
+            &user_lp_fee_pct,
+            &Nat::from(use_lp_fee.unwrap_or_else(|| pool.swap_lp_fee_bps(true, ts))),
+        ),
         &Nat::from(100_u8),
     )
     .ok_or("Invalid LP fee")?;
@@ -546,6 +659,7 @@ fn swap_amount_0(
         receive_token_id: token_id_1,
         receive_amount: amount_1,
         lp_fee,
+        lp_fee_bps: nat_to_u64(&user_lp_fee_bps).unwrap_or(0) as u8,
         gas_fee,
     })
 }
@@ -558,6 +672,7 @@ fn swap_amount_1(
     user_fee_level: Option<u8>,
     use_lp_fee: Option<u8>,
     use_gas_fee: Option<&Nat>,
+    ts: u64,
 ) -> Result<SwapCalc, String> {
     // Token 0
     let token_0 = pool.token_0();
@@ -577,6 +692,7 @@ fn swap_amount_1(
             receive_token_id: token_id_0,
             receive_amount: nat_zero(),
             lp_fee: nat_zero(),
+            lp_fee_bps: 0,
             gas_fee: nat_zero(),
         });
     }
@@ -591,6 +707,7 @@ fn swap_amount_1(
                 receive_token_id: token_id_0,
                 receive_amount: nat_zero(),
                 lp_fee: nat_zero(),
+                lp_fee_bps: 0,
                 gas_fee: nat_zero(),
             });
         }
@@ -603,16 +720,36 @@ fn swap_amount_1(
     let reserve_1_in_max_decimals = nat_to_decimal_precision(&reserve_1, token_1.decimals(), max_decimals);
     let amount_1_in_max_decimals = nat_to_decimal_precision(amount_1, token_1.decimals(), max_decimals);
 
-    // amount_0 = (amount_1 * reserve_0) / (reserve_1 + amount_1)
-    let numerator_in_max_decimals = nat_multiply(&amount_1_in_max_decimals, &reserve_0_in_max_decimals);
-    let denominator_in_max_decimals = nat_add(&reserve_1_in_max_decimals, &amount_1_in_max_decimals);
-    let amount_0_in_max_decimals = nat_divide(&numerator_in_max_decimals, &denominator_in_max_decimals).ok_or("Invalid amount_0")?;
+    let amount_0_in_max_decimals = match &pool.pool_type {
+        PoolType::ConstantProduct => {
+            // amount_0 = (amount_1 * reserve_0) / (reserve_1 + amount_1)
+            let numerator_in_max_decimals = nat_multiply(&amount_1_in_max_decimals, &reserve_0_in_max_decimals);
+            let denominator_in_max_decimals = nat_add(&reserve_1_in_max_decimals, &amount_1_in_max_decimals);
+            nat_divide(&numerator_in_max_decimals, &denominator_in_max_decimals).ok_or("Invalid amount_0")?
+        }
+        PoolType::Concentrated(concentrated_pool) => {
+            let amount_0 = concentrated_pool.clone().swap(false, amount_1, None)?;
+            nat_to_decimal_precision(&amount_0, token_0.decimals(), max_decimals)
+        }
+        PoolType::StableSwap(stable_swap) => {
+            let amp = stable_swap.amp(ts);
+            stable_swap_pool::swap_amount(
+                &reserve_1_in_max_decimals,
+                &reserve_0_in_max_decimals,
+                &amount_1_in_max_decimals,
+                amp,
+            )?
+        }
+    };
 
     // calculate the LP fees
     // user_lp_fee_pct = 100 - user.fee_level
     let user_lp_fee_pct = nat_subtract(&Nat::from(100_u8), &Nat::from(user_fee_level.unwrap_or(0_u8))).unwrap_or(Nat::from(100_u8));
     let user_lp_fee_bps = nat_divide(
-        &nat_multiply(&user_lp_fee_pct, &Nat::from(use_lp_fee.unwrap_or(pool.lp_fee_bps))),
+        &nat_multiply(
+            &user_lp_fee_pct,
+            &Nat::from(use_lp_fee.unwrap_or_else(|| pool.swap_lp_fee_bps(false, ts))),
+        ),
         &Nat::from(100_u8),
     )
     .ok_or("Invalid LP fee")?;
@@ -635,6 +772,7 @@ fn swap_amount_1(
         receive_token_id: token_id_0,
         receive_amount: amount_0,
         lp_fee,
+        lp_fee_bps: nat_to_u64(&user_lp_fee_bps).unwrap_or(0) as u8,
         gas_fee,
     })
 }
@@ -653,3 +791,69 @@ fn get_slippage(price_achieved: &BigRational, price_expected: &BigRational) -> O
         .abs();
     Some(round_f64(raw_slippage, 2)) // 2 decimals
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::stable_pool::pool_map::tests::pool;
+
+    fn path_pool_ids(path: &[SwapPathHop]) -> Vec<u32> {
+        path.iter().map(|(pools, _)| pools[0].pool_id).collect()
+    }
+
+    #[test]
+    fn test_multi_hop_best_path() {
+        // 1 -> 3 directly through a shallow pool, or through 2 with deep pools
+        let graph = SwapGraph::new(vec![
+            pool(1, 1, 3, 1_000_000),
+            pool(2, 1, 2, 1_000_000_000_000),
+            pool(3, 2, 3, 1_000_000_000_000),
+        ]);
+        let paths = graph.find_paths(1, 3, 3);
+        assert_eq!(
+            paths.iter().map(|path| path_pool_ids(path)).collect::<Vec<_>>(),
+            vec![vec![1], vec![2, 3]]
+        );
+
+        let pay_amount = Nat::from(1_000_000_u64);
+        let (swaps, swap_error) = paths_swap_amounts(&paths, Some(&pay_amount), None, true, 0);
+        assert!(swap_error.is_none());
+        // the 2 hop path through the deep pools receives more than the direct path
+        assert_eq!(swaps[0].0, 1);
+        assert!(swaps[0].1 .0 > swaps[1].1 .0);
+
+        // with a single hop allowed, only the direct path is found
+        let paths = graph.find_paths(1, 3, 1);
+        assert_eq!(paths.iter().map(|path| path_pool_ids(path)).collect::<Vec<_>>(), vec![vec![1]]);
+    }
+
+    #[test]
+    fn test_no_path() {
+        let graph = SwapGraph::new(vec![pool(1, 1, 2, 1_000_000), pool(2, 3, 4, 1_000_000), pool(3, 2, 5, 1_000_000)]);
+        // 1 and 3 are not connected
+        assert!(graph.find_paths(1, 3, 3).is_empty());
+        // 5 is 2 hops away
+        assert!(graph.find_paths(1, 5, 1).is_empty());
+        assert_eq!(graph.find_paths(1, 5, 2).len(), 1);
+        // unknown token
+        assert!(graph.find_paths(1, 99, 3).is_empty());
+    }
+
+    #[test]
+    fn test_max_swap_paths() {
+        // every pair of 8 tokens has a pool, so there are far more than MAX_SWAP_PATHS paths of up to 4 hops
+        let mut pools = Vec::new();
+        for token_id_0 in 1..=8 {
+            for token_id_1 in token_id_0 + 1..=8 {
+                pools.push(pool(pools.len() as u32 + 1, token_id_0, token_id_1, 1_000_000));
+            }
+        }
+        let graph = SwapGraph::new(pools);
+        let paths = graph.find_paths(1, 8, 4);
+        assert_eq!(paths.len(), MAX_SWAP_PATHS);
+        // the shortest paths are kept: the direct path, the 6 paths of 2 hops and then paths of 3 hops
+        assert_eq!(paths[0].len(), 1);
+        assert!(paths[1..7].iter().all(|path| path.len() == 2));
+        assert!(paths[7..].iter().all(|path| path.len() == 3));
+    }
+}
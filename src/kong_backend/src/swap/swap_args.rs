@@ -15,4 +15,6 @@ pub struct SwapArgs {
     pub receive_address: Option<String>,
     pub max_slippage: Option<f64>,
     pub referred_by: Option<String>,
+    pub use_deposit: Option<bool>, // pay from and receive to the user's deposit instead of ledger transfers
+    pub deadline: Option<u64>,     // nanoseconds since the Unix epoch. the swap is rejected and refunded after the deadline
 }
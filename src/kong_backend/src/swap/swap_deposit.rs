@@ -0,0 +1,166 @@
+use candid::Nat;
+
+use super::archive_to_kong_data::archive_to_kong_data;
+use super::calculate_amounts::calculate_internal_amounts;
+use super::swap_args::SwapArgs;
+use super::swap_calc::SwapCalc;
+use super::swap_reply::SwapReply;
+use super::swap_reply_helpers::to_swap_reply;
+use super::update_liquidity_pool::update_pools;
+
+use crate::deposits::deposits::{check_deposit, credit_deposits, debit_deposits};
+use crate::helpers::nat_helpers::nat_is_zero;
+use crate::ic::deadline::check_deadline;
+use crate::ic::get_time::get_time;
+use crate::stable_kong_settings::kong_settings_map;
+use crate::stable_request::{reply::Reply, request::Request, request_map, stable_request::StableRequest, status::StatusCode};
+use crate::stable_token::{stable_token::StableToken, token::Token, token_map};
+use crate::stable_tx::{stable_tx::StableTx, swap_tx::SwapTx, tx_map};
+use crate::stable_user::user_map;
+
+/// swap against the user's deposit. the pay token is debited from and the receive token credited to the deposit,
+/// so there are no ledger transfers, no gas fees and no inter-canister calls
+pub fn swap_deposit(args: SwapArgs) -> Result<SwapReply, String> {
+    let (user_id, pay_token, pay_amount, receive_token, max_slippage) = check_arguments(&args)?;
+    let ts = get_time();
+    let receive_amount = args.receive_amount.clone();
+    let request_id = request_map::insert(&StableRequest::new(user_id, &Request::Swap(args), ts));
+
+    let result = match process_swap(
+        request_id,
+        user_id,
+        &pay_token,
+        &pay_amount,
+        &receive_token,
+        receive_amount.as_ref(),
+        max_slippage,
+        ts,
+    ) {
+        Ok(reply) => {
+            request_map::update_status(request_id, StatusCode::Success, None);
+            Ok(reply)
+        }
+        Err(e) => {
+            request_map::update_status(request_id, StatusCode::Failed, None);
+            Err(e)
+        }
+    };
+    _ = archive_to_kong_data(request_id);
+
+    result
+}
+
+fn check_arguments(args: &SwapArgs) -> Result<(u32, StableToken, Nat, StableToken, f64), String> {
+    let pay_token = token_map::get_by_token(&args.pay_token)?;
+    if pay_token.is_removed() {
+        Err("Pay token is suspended or removed".to_string())?;
+    };
+    let pay_amount = args.pay_amount.clone();
+    if nat_is_zero(&pay_amount) {
+        Err("Pay amount is zero".to_string())?;
+    }
+
+    let receive_token = token_map::get_by_token(&args.receive_token)?;
+    if receive_token.is_removed() {
+        Err("Receive token is suspended or removed".to_string())?;
+    };
+
+    // deposits are moved internally, so no transfers or receive address
+    if args.pay_tx_id.is_some() {
+        Err("Pay tx_id not supported with use_deposit".to_string())?;
+    }
+    if args.receive_address.is_some() {
+        Err("Receive address not supported with use_deposit".to_string())?;
+    }
+    // the swap is synchronous, so the deadline only needs checking once
+    check_deadline(args.deadline)?;
+
+    // use specified max slippage or use default
+    let max_slippage = args.max_slippage.unwrap_or(kong_settings_map::get().default_max_slippage);
+
+    // make sure user is registered, if not create a new user with referred_by if specified
+    let user_id = user_map::insert(args.referred_by.as_deref())?;
+    check_deposit(user_id, &pay_token, &pay_amount)?;
+
+    Ok((user_id, pay_token, pay_amount, receive_token, max_slippage))
+}
+
+#[allow(clippy::too_many_arguments)]
+fn process_swap(
+    request_id: u64,
+    user_id: u32,
+    pay_token: &StableToken,
+    pay_amount: &Nat,
+    receive_token: &StableToken,
+    receive_amount: Option<&Nat>,
+    max_slippage: f64,
+    ts: u64,
+) -> Result<SwapReply, String> {
+    request_map::update_status(request_id, StatusCode::Start, None);
+
+    request_map::update_status(request_id, StatusCode::CalculatePoolAmounts, None);
+    let (receive_amount, price, mid_price, slippage, swaps) =
+        calculate_internal_amounts(pay_token, pay_amount, receive_token, receive_amount, max_slippage).inspect_err(|e| {
+            request_map::update_status(request_id, StatusCode::CalculatePoolAmountsFailed, Some(e));
+        })?;
+    request_map::update_status(request_id, StatusCode::CalculatePoolAmountsSuccess, None);
+
+    debit_deposits(request_id, user_id, &[(pay_token.token_id(), pay_amount)], ts)?;
+    if let Err(e) = update_pools(request_id, &swaps) {
+        // return the pay token to the deposit
+        credit_deposits(request_id, user_id, &[(pay_token.token_id(), pay_amount)], ts);
+        Err(format!("Req #{} failed. {}", request_id, e))?
+    }
+    request_map::update_status(request_id, StatusCode::SwapSuccess, None);
+    credit_deposits(request_id, user_id, &[(receive_token.token_id(), &receive_amount)], ts);
+
+    Ok(insert_swap_tx(
+        request_id,
+        user_id,
+        pay_token,
+        pay_amount,
+        receive_token,
+        &receive_amount,
+        mid_price,
+        price,
+        slippage,
+        &swaps,
+        ts,
+    ))
+}
+
+#[allow(clippy::too_many_arguments)]
+fn insert_swap_tx(
+    request_id: u64,
+    user_id: u32,
+    pay_token: &StableToken,
+    pay_amount: &Nat,
+    receive_token: &StableToken,
+    receive_amount: &Nat,
+    mid_price: f64,
+    price: f64,
+    slippage: f64,
+    swaps: &[SwapCalc],
+    ts: u64,
+) -> SwapReply {
+    let swap_tx = SwapTx::new_success(
+        user_id,
+        request_id,
+        pay_token.token_id(),
+        pay_amount,
+        receive_token.token_id(),
+        receive_amount,
+        mid_price,
+        price,
+        slippage,
+        swaps,
+        &[],
+        &[],
+        ts,
+    );
+    let tx_id = tx_map::insert(&StableTx::Swap(swap_tx.clone()));
+    let reply = to_swap_reply(&SwapTx { tx_id, ..swap_tx });
+    request_map::update_reply(request_id, Reply::Swap(reply.clone()));
+
+    reply
+}
@@ -0,0 +1,82 @@
+use candid::{CandidType, Nat};
+use serde::{Deserialize, Serialize};
+
+use super::swap_calc::SwapCalc;
+
+use crate::helpers::math_helpers::round_f64;
+use crate::helpers::nat_helpers::nat_divide_as_f64;
+
+/// A swap can be split into several legs, each routed through its own path of pools
+#[derive(CandidType, Debug, Clone, Serialize, Deserialize)]
+pub struct SwapLeg {
+    pub pool_ids: Vec<u32>, // pools of the path, in order
+    pub pay_amount: Nat,
+    pub receive_amount: Nat, // net of fees and gas
+    pub fraction: f64,       // fraction of the swap's pay_amount routed through the leg
+}
+
+impl SwapLeg {
+    /// group the txs of a swap into legs. every leg starts with a tx paying pay_token_id
+    /// as a path never goes through the same token twice
+    pub fn from_txs(pay_token_id: u32, pay_amount: &Nat, txs: &[SwapCalc]) -> Vec<SwapLeg> {
+        let mut legs: Vec<SwapLeg> = Vec::new();
+        for tx in txs {
+            match legs.last_mut() {
+                Some(leg) if tx.pay_token_id != pay_token_id => {
+                    leg.pool_ids.push(tx.pool_id);
+                    leg.receive_amount = tx.receive_amount_with_fees_and_gas();
+                }
+                _ => legs.push(SwapLeg {
+                    pool_ids: vec![tx.pool_id],
+                    pay_amount: tx.pay_amount.clone(),
+                    receive_amount: tx.receive_amount_with_fees_and_gas(),
+                    fraction: round_f64(nat_divide_as_f64(&tx.pay_amount, pay_amount).unwrap_or(0_f64), 4),
+                }),
+            }
+        }
+        legs
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn swap_calc(pool_id: u32, pay_token_id: u32, pay_amount: u64, receive_token_id: u32, receive_amount: u64) -> SwapCalc {
+        SwapCalc {
+            pool_id,
+            pay_token_id,
+            pay_amount: Nat::from(pay_amount),
+            receive_token_id,
+            receive_amount: Nat::from(receive_amount),
+            lp_fee: Nat::from(0_u64),
+            lp_fee_bps: 0,
+            gas_fee: Nat::from(0_u64),
+        }
+    }
+
+    #[test]
+    fn test_from_txs() {
+        // leg 1: token 1 -> token 2 -> token 3, leg 2: token 1 -> token 4 -> token 3
+        let txs = vec![
+            swap_calc(1, 1, 750, 2, 700),
+            swap_calc(2, 2, 700, 3, 650),
+            swap_calc(3, 1, 250, 4, 240),
+            swap_calc(4, 4, 240, 3, 230),
+        ];
+        let legs = SwapLeg::from_txs(1, &Nat::from(1_000_u64), &txs);
+        assert_eq!(legs.len(), 2);
+        assert_eq!(legs[0].pool_ids, vec![1, 2]);
+        assert_eq!(legs[0].pay_amount, Nat::from(750_u64));
+        assert_eq!(legs[0].receive_amount, Nat::from(650_u64));
+        assert_eq!(legs[0].fraction, 0.75);
+        assert_eq!(legs[1].pool_ids, vec![3, 4]);
+        assert_eq!(legs[1].receive_amount, Nat::from(230_u64));
+        assert_eq!(legs[1].fraction, 0.25);
+
+        // single path swap is a single leg
+        let legs = SwapLeg::from_txs(1, &Nat::from(750_u64), &txs[..2]);
+        assert_eq!(legs.len(), 1);
+        assert_eq!(legs[0].fraction, 1.0);
+    }
+}
@@ -22,6 +22,14 @@ pub struct SwapTxReply {
     pub ts: u64,
 }
 
+#[derive(CandidType, Debug, Clone, Serialize, Deserialize)]
+pub struct SwapLegReply {
+    pub pool_symbols: Vec<String>,
+    pub pay_amount: Nat,
+    pub receive_amount: Nat,
+    pub fraction: f64,
+}
+
 #[derive(CandidType, Debug, Clone, Serialize, Deserialize)]
 pub struct SwapReply {
     pub tx_id: u64,
@@ -41,8 +49,12 @@ pub struct SwapReply {
     pub price: f64,
     pub slippage: f64,
     pub txs: Vec<SwapTxReply>,
+    #[serde(default)]
+    pub legs: Vec<SwapLegReply>,
     pub transfer_ids: Vec<TransferIdReply>,
     pub claim_ids: Vec<u64>,
+    #[serde(default)]
+    pub to_address: Option<String>, // resolved destination of the receive token
     pub ts: u64,
 }
 
@@ -2,7 +2,8 @@ use candid::Nat;
 use num::{BigRational, Zero};
 
 use super::swap_calc::SwapCalc;
-use super::swap_reply::{SwapReply, SwapTxReply};
+use super::swap_leg::SwapLeg;
+use super::swap_reply::{SwapLegReply, SwapReply, SwapTxReply};
 
 use crate::helpers::math_helpers::price_rounded;
 use crate::helpers::nat_helpers::nat_zero;
@@ -43,10 +44,24 @@ fn to_swap_tx_reply(swap: &SwapCalc, ts: u64) -> Option<SwapTxReply> {
     })
 }
 
-fn to_txs(txs: &[SwapCalc], ts: u64) -> Vec<SwapTxReply> {
+pub fn to_txs(txs: &[SwapCalc], ts: u64) -> Vec<SwapTxReply> {
     txs.iter().filter_map(|tx| to_swap_tx_reply(tx, ts)).collect()
 }
 
+pub fn to_swap_leg_reply(leg: &SwapLeg) -> SwapLegReply {
+    let pool_symbols = leg
+        .pool_ids
+        .iter()
+        .map(|pool_id| pool_map::get_by_pool_id(*pool_id).map_or_else(|| "Pool not found".to_string(), |pool| pool.symbol()))
+        .collect();
+    SwapLegReply {
+        pool_symbols,
+        pay_amount: leg.pay_amount.clone(),
+        receive_amount: leg.receive_amount.clone(),
+        fraction: leg.fraction,
+    }
+}
+
 fn get_tokens_info(pay_token_id: u32, receive_token_id: u32) -> (String, String, String, String, String, String) {
     let pay_token = token_map::get_by_token_id(pay_token_id);
     let (pay_chain, pay_address, pay_symbol) = pay_token.map_or_else(
@@ -92,8 +107,10 @@ pub fn to_swap_reply(swap_tx: &SwapTx) -> SwapReply {
         price: swap_tx.price,
         slippage: swap_tx.slippage,
         txs: to_txs(&swap_tx.txs, swap_tx.ts),
+        legs: swap_tx.legs.iter().map(to_swap_leg_reply).collect(),
         transfer_ids: to_transfer_ids(&swap_tx.transfer_ids),
         claim_ids: swap_tx.claim_ids.clone(),
+        to_address: swap_tx.to_address.as_ref().map(|address| address.to_string()),
         ts: swap_tx.ts,
     }
 }
@@ -131,8 +148,10 @@ pub fn to_swap_reply_failed(
         price: 0_f64,
         slippage: 0_f64,
         txs: Vec::new(),
+        legs: Vec::new(),
         transfer_ids: to_transfer_ids(transfer_ids),
         claim_ids: claim_ids.to_vec(),
+        to_address: None,
         ts,
     }
 }
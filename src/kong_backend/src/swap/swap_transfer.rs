@@ -249,24 +249,32 @@ async fn process_swap(
         None => Address::PrincipalId(caller_id),
     };
 
-    let (receive_amount_with_fees_and_gas, mid_price, price, slippage, swaps) =
-        match update_liquidity_pool(request_id, pay_token, pay_amount, &receive_token, receive_amount, max_slippage) {
-            Ok((receive_amount, mid_price, price, slippage, swaps)) => (receive_amount, mid_price, price, slippage, swaps),
-            Err(e) => {
-                return_pay_token(
-                    request_id,
-                    user_id,
-                    &caller_id,
-                    pay_token,
-                    pay_amount,
-                    Some(&receive_token),
-                    transfer_ids,
-                    ts,
-                )
-                .await;
-                Err(format!("Req #{} failed. {}", request_id, e))?
-            }
-        };
+    let (receive_amount_with_fees_and_gas, mid_price, price, slippage, swaps) = match update_liquidity_pool(
+        request_id,
+        user_id,
+        pay_token,
+        pay_amount,
+        &receive_token,
+        receive_amount,
+        max_slippage,
+        args.deadline,
+    ) {
+        Ok((receive_amount, mid_price, price, slippage, swaps)) => (receive_amount, mid_price, price, slippage, swaps),
+        Err(e) => {
+            return_pay_token(
+                request_id,
+                user_id,
+                &caller_id,
+                pay_token,
+                pay_amount,
+                Some(&receive_token),
+                transfer_ids,
+                ts,
+            )
+            .await;
+            Err(format!("Req #{} failed. {}", request_id, e))?
+        }
+    };
 
     request_map::update_status(request_id, StatusCode::SwapSuccess, None);
 
@@ -14,6 +14,7 @@ use super::update_liquidity_pool::update_liquidity_pool;
 use crate::helpers::nat_helpers::nat_is_zero;
 use crate::ic::address::Address;
 use crate::ic::address_helpers::get_address;
+use crate::ic::deadline::check_deadline;
 use crate::ic::get_time::get_time;
 use crate::ic::id::caller_id;
 use crate::ic::transfer::icrc2_transfer_from;
@@ -28,6 +29,7 @@ pub async fn swap_transfer_from(args: SwapArgs) -> Result<SwapReply, String> {
     let (user_id, pay_token, pay_amount, receive_token, max_slippage, to_address) = check_arguments(&args).await?;
     let ts = get_time();
     let receive_amount = args.receive_amount.clone();
+    let deadline = args.deadline;
     let request_id = request_map::insert(&StableRequest::new(user_id, &Request::Swap(args), ts));
     let mut transfer_ids = Vec::new();
 
@@ -39,6 +41,7 @@ pub async fn swap_transfer_from(args: SwapArgs) -> Result<SwapReply, String> {
         &receive_token,
         receive_amount.as_ref(),
         max_slippage,
+        deadline,
         &mut transfer_ids,
         ts,
     )
@@ -77,6 +80,7 @@ pub async fn swap_transfer_from_async(args: SwapArgs) -> Result<u64, String> {
     let (user_id, pay_token, pay_amount, receive_token, max_slippage, to_address) = check_arguments(&args).await?;
     let ts = get_time();
     let receive_amount = args.receive_amount.clone();
+    let deadline = args.deadline;
     let request_id = request_map::insert(&StableRequest::new(user_id, &Request::Swap(args), ts));
 
     ic_cdk::spawn(async move {
@@ -90,6 +94,7 @@ pub async fn swap_transfer_from_async(args: SwapArgs) -> Result<u64, String> {
             &receive_token,
             receive_amount.as_ref(),
             max_slippage,
+            deadline,
             &mut transfer_ids,
             ts,
         )
@@ -151,6 +156,9 @@ async fn check_arguments(args: &SwapArgs) -> Result<(u32, StableToken, Nat, Stab
         Err("Pay amount is zero".to_string())?;
     }
 
+    // reject before transferring the pay token. checked again once the transfer_from is done
+    check_deadline(args.deadline)?;
+
     // check to make sure pay_tx_id is not specified
     if args.pay_tx_id.is_some() {
         Err("Pay tx_id not supported".to_string())?;
@@ -174,7 +182,14 @@ async fn check_arguments(args: &SwapArgs) -> Result<(u32, StableToken, Nat, Stab
 
     // calculate receive_amount and swaps. do after user_id is created as it will be needed to calculate the receive_amount (user fee level)
     // no needs to store the return values as it'll be called again in process_swap
-    calculate_amounts(&pay_token, &pay_amount, &receive_token, args.receive_amount.as_ref(), max_slippage)?;
+    calculate_amounts(
+        user_id,
+        &pay_token,
+        &pay_amount,
+        &receive_token,
+        args.receive_amount.as_ref(),
+        max_slippage,
+    )?;
 
     Ok((user_id, pay_token, pay_amount, receive_token, max_slippage, to_address))
 }
@@ -190,6 +205,7 @@ async fn process_swap(
     receive_token: &StableToken,
     receive_amount: Option<&Nat>,
     max_slippage: f64,
+    deadline: Option<u64>,
     transfer_ids: &mut Vec<u64>,
     ts: u64,
 ) -> Result<(Nat, f64, f64, f64, Vec<SwapCalc>), String> {
@@ -203,25 +219,33 @@ async fn process_swap(
         .map_err(|e| format!("Pay token transfer_from failed. {}", e))?;
 
     // re-calculate receive_amount and swaps with the latest pool state
-    let (receive_amount_with_fees_and_gas, mid_price, price, slippage, swaps) =
-        match update_liquidity_pool(request_id, pay_token, pay_amount, receive_token, receive_amount, max_slippage) {
-            Ok((receive_amount, mid_price, price, slippage, swaps)) => (receive_amount, mid_price, price, slippage, swaps),
-            Err(e) => {
-                // return pay token back to user
-                return_pay_token(
-                    request_id,
-                    user_id,
-                    &caller_id,
-                    pay_token,
-                    pay_amount,
-                    Some(receive_token),
-                    transfer_ids,
-                    ts,
-                )
-                .await;
-                Err(format!("Req #{} failed. {}", request_id, e))?
-            }
-        };
+    let (receive_amount_with_fees_and_gas, mid_price, price, slippage, swaps) = match update_liquidity_pool(
+        request_id,
+        user_id,
+        pay_token,
+        pay_amount,
+        receive_token,
+        receive_amount,
+        max_slippage,
+        deadline,
+    ) {
+        Ok((receive_amount, mid_price, price, slippage, swaps)) => (receive_amount, mid_price, price, slippage, swaps),
+        Err(e) => {
+            // return pay token back to user
+            return_pay_token(
+                request_id,
+                user_id,
+                &caller_id,
+                pay_token,
+                pay_amount,
+                Some(receive_token),
+                transfer_ids,
+                ts,
+            )
+            .await;
+            Err(format!("Req #{} failed. {}", request_id, e))?
+        }
+    };
 
     request_map::update_status(request_id, StatusCode::SwapSuccess, None);
 
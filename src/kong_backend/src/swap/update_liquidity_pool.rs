@@ -1,66 +1,43 @@
 use candid::Nat;
+use std::collections::BTreeMap;
 
 use super::calculate_amounts::calculate_amounts;
 use super::swap_calc::SwapCalc;
 
-use crate::helpers::nat_helpers::{nat_add, nat_divide, nat_multiply, nat_subtract, nat_zero};
+use crate::helpers::nat_helpers::{nat_add, nat_divide, nat_is_zero, nat_multiply, nat_subtract, nat_zero};
+use crate::ic::deadline::check_request_deadline;
+use crate::ic::get_time::get_time;
+use crate::stable_kong_settings::kong_settings_map;
+use crate::stable_lp_token::{lp_token_lock, lp_token_map};
 use crate::stable_pool::pool_map;
+use crate::stable_pool::pool_type::PoolType;
+use crate::stable_pool::stable_pool::StablePool;
 use crate::stable_request::request_map;
 use crate::stable_request::status::StatusCode;
 use crate::stable_token::stable_token::StableToken;
+use crate::stable_user::{referral_reward_map, user_map};
 
+/// the deadline is checked here as the pay token has been received by now and is returned by the caller on failure
+#[allow(clippy::too_many_arguments)]
 pub fn update_liquidity_pool(
     request_id: u64,
+    user_id: u32,
     pay_token: &StableToken,
     pay_amount: &Nat,
     receive_token: &StableToken,
     receive_amount: Option<&Nat>,
     max_slippage: f64,
+    deadline: Option<u64>,
 ) -> Result<(Nat, f64, f64, f64, Vec<SwapCalc>), String> {
+    check_request_deadline(request_id, deadline)?;
+
     request_map::update_status(request_id, StatusCode::CalculatePoolAmounts, None);
 
-    match calculate_amounts(pay_token, pay_amount, receive_token, receive_amount, max_slippage) {
+    match calculate_amounts(user_id, pay_token, pay_amount, receive_token, receive_amount, max_slippage) {
         Ok((receive_amount_with_fees_and_gas, price, mid_price, slippage, swaps)) => {
             request_map::update_status(request_id, StatusCode::CalculatePoolAmountsSuccess, None);
 
-            // update the pool, in some cases there could be multiple pools
-            request_map::update_status(request_id, StatusCode::UpdatePoolAmounts, None);
-            for swap in &swaps {
-                // refresh pool with the latest state
-                let mut pool = match pool_map::get_by_pool_id(swap.pool_id) {
-                    Some(pool) => pool,
-                    None => continue, // should not get here
-                };
-
-                if swap.receive_token_id == pool.token_id_1 {
-                    // user pays token_0 and receives token_1
-                    pool.balance_0 = nat_add(&pool.balance_0, &swap.pay_amount); // pay_amount is in token_0
-                    pool.balance_1 = nat_subtract(&pool.balance_1, &swap.receive_amount).unwrap_or(nat_zero()); // receive_amount is in token_1
-                                                                                                                // fees are in token_1. take out Kong's fee
-                                                                                                                // kong_fee_1 = lp_fee * kong_fee_bps / lp_fee_bps
-                                                                                                                // lp_fee_1 = lp_fee - kong_fee_1
-                    let numerator = nat_multiply(&swap.lp_fee, &Nat::from(pool.kong_fee_bps)); //swap.lp_fee is in token_1
-                    let kong_fee_1 = nat_divide(&numerator, &Nat::from(pool.lp_fee_bps)).unwrap_or(nat_zero());
-                    let lp_fee_1 = nat_subtract(&swap.lp_fee, &kong_fee_1).unwrap_or(nat_zero());
-                    pool.lp_fee_1 = nat_add(&pool.lp_fee_1, &lp_fee_1);
-                    pool.kong_fee_1 = nat_add(&pool.kong_fee_1, &kong_fee_1);
-                } else {
-                    // user pays token_1 and receives token_0
-                    pool.balance_1 = nat_add(&pool.balance_1, &swap.pay_amount); // pay_amount is in token_1
-                    pool.balance_0 = nat_subtract(&pool.balance_0, &swap.receive_amount).unwrap_or(nat_zero()); // receive_amount is in token_0
-                                                                                                                // fees are in token_0. take out Kong's fee
-                                                                                                                // kong_fee_0 = lp_fee * kong_fee_bps / lp_fee_bps
-                                                                                                                // lp_fee_0 = lp_fee - kong_fee_0
-                    let numerator = nat_multiply(&swap.lp_fee, &Nat::from(pool.kong_fee_bps)); //swap.lp_fee is in token_0
-                    let kong_fee_0 = nat_divide(&numerator, &Nat::from(pool.lp_fee_bps)).unwrap_or(nat_zero());
-                    let lp_fee_0 = nat_subtract(&swap.lp_fee, &kong_fee_0).unwrap_or(nat_zero());
-                    pool.lp_fee_0 = nat_add(&pool.lp_fee_0, &lp_fee_0);
-                    pool.kong_fee_0 = nat_add(&pool.kong_fee_0, &kong_fee_0);
-                }
-                pool_map::update(&pool);
-            }
-
-            request_map::update_status(request_id, StatusCode::UpdatePoolAmountsSuccess, None);
+            update_pools(request_id, &swaps)?;
 
             Ok((receive_amount_with_fees_and_gas, mid_price, price, slippage, swaps))
         }
@@ -70,3 +47,231 @@ pub fn update_liquidity_pool(
         }
     }
 }
+
+/// update the pools with the swaps, in some cases there could be multiple pools and multiple legs
+/// all the pools are calculated first and only saved if every swap applies, so all legs are applied atomically
+pub fn update_pools(request_id: u64, swaps: &[SwapCalc]) -> Result<(), String> {
+    request_map::update_status(request_id, StatusCode::UpdatePoolAmounts, None);
+    let mut pools: BTreeMap<u32, StablePool> = BTreeMap::new();
+    let ts = get_time();
+    // the referrer of the user is credited a share of Kong's fee while the referral is active
+    let referrer_id = request_map::get_by_request_id(request_id).and_then(|request| user_map::get_active_referrer_id(request.user_id, ts));
+    let referral_fee_pct = referrer_id.map(|_| kong_settings_map::get().referral_fee_pct);
+    let mut referral_fees = Vec::new();
+    let mut settled_lp_tokens = Vec::new();
+    for swap in swaps {
+        // refresh pool with the latest state
+        // expired LP token locks are settled before the swap's fees are shared
+        let pool = pools.remove(&swap.pool_id).or_else(|| {
+            pool_map::get_by_pool_id(swap.pool_id).map(|pool| {
+                let (pool, lp_tokens) = lp_token_map::settle_expired_locks(pool, ts);
+                settled_lp_tokens.extend(lp_tokens);
+                pool
+            })
+        });
+        let mut pool = match pool {
+            Some(pool) => pool,
+            None => {
+                let e = format!("Pool #{} not found", swap.pool_id);
+                request_map::update_status(request_id, StatusCode::UpdatePoolAmountsFailed, Some(&e));
+                return Err(e);
+            }
+        };
+        match apply_swap(&mut pool, swap, referral_fee_pct, ts) {
+            Ok(referral_fee) => referral_fees.push((swap.receive_token_id, referral_fee)),
+            Err(e) => {
+                request_map::update_status(request_id, StatusCode::UpdatePoolAmountsFailed, Some(&e));
+                return Err(e);
+            }
+        }
+        pools.insert(pool.pool_id, pool);
+    }
+    for pool in pools.values() {
+        pool_map::update(pool);
+    }
+    for lp_token in &settled_lp_tokens {
+        lp_token_map::update(lp_token);
+    }
+    if let Some(referrer_id) = referrer_id {
+        for (token_id, referral_fee) in referral_fees.iter().filter(|(_, referral_fee)| !nat_is_zero(referral_fee)) {
+            referral_reward_map::credit(referrer_id, *token_id, referral_fee, ts);
+        }
+    }
+    request_map::update_status(request_id, StatusCode::UpdatePoolAmountsSuccess, None);
+
+    Ok(())
+}
+
+/// apply a swap to the pool. returns the referral fee in the receive token, taken out of Kong's fee
+pub fn apply_swap(pool: &mut StablePool, swap: &SwapCalc, referral_fee_pct: Option<u8>, ts: u64) -> Result<Nat, String> {
+    // accumulate the price before the swap moves it
+    pool.update_price_cumulative(ts);
+    let price_before = pool.get_price();
+    let referral_fee = if swap.receive_token_id == pool.token_id_1 {
+        // user pays token_0 and receives token_1
+        pool.balance_0 = nat_add(&pool.balance_0, &swap.pay_amount); // pay_amount is in token_0
+        pool.balance_1 = nat_subtract(&pool.balance_1, &swap.receive_amount).unwrap_or(nat_zero()); // receive_amount is in token_1
+
+        // fees are in token_1. take out Kong's fee
+        let (kong_fee_1, lp_fee_1) = split_kong_fee(&swap.lp_fee, pool.kong_fee_bps, pool.lp_fee_bps); //swap.lp_fee is in token_1
+        let lp_fee_1 = share_boost_fee(pool, &lp_fee_1, false);
+        // referrer's share of Kong's fee
+        let (kong_fee_1, referral_fee_1) = split_referral_fee(&kong_fee_1, referral_fee_pct);
+        pool.lp_fee_1 = nat_add(&pool.lp_fee_1, &lp_fee_1);
+        pool.kong_fee_1 = nat_add(&pool.kong_fee_1, &kong_fee_1);
+        if let PoolType::Concentrated(ref mut concentrated_pool) = pool.pool_type {
+            // move the price through the ticks and credit the LP fee to the active positions
+            concentrated_pool.swap(true, &swap.pay_amount, Some((&lp_fee_1, &swap.receive_amount)))?;
+        }
+        referral_fee_1
+    } else {
+        // user pays token_1 and receives token_0
+        pool.balance_1 = nat_add(&pool.balance_1, &swap.pay_amount); // pay_amount is in token_1
+        pool.balance_0 = nat_subtract(&pool.balance_0, &swap.receive_amount).unwrap_or(nat_zero()); // receive_amount is in token_0
+
+        // fees are in token_0. take out Kong's fee
+        let (kong_fee_0, lp_fee_0) = split_kong_fee(&swap.lp_fee, pool.kong_fee_bps, pool.lp_fee_bps); //swap.lp_fee is in token_0
+        let lp_fee_0 = share_boost_fee(pool, &lp_fee_0, true);
+        let (kong_fee_0, referral_fee_0) = split_referral_fee(&kong_fee_0, referral_fee_pct);
+        pool.lp_fee_0 = nat_add(&pool.lp_fee_0, &lp_fee_0);
+        pool.kong_fee_0 = nat_add(&pool.kong_fee_0, &kong_fee_0);
+        if let PoolType::Concentrated(ref mut concentrated_pool) = pool.pool_type {
+            concentrated_pool.swap(false, &swap.pay_amount, Some((&lp_fee_0, &swap.receive_amount)))?;
+        }
+        referral_fee_0
+    };
+    // feed the price move of the swap into the dynamic fee of its direction
+    let pay_token_0 = swap.receive_token_id == pool.token_id_1;
+    if let (Some(price_before), Some(price_after)) = (price_before, pool.get_price()) {
+        if let Some(ref mut dynamic_fee) = pool.dynamic_fee {
+            dynamic_fee.update(pay_token_0, &price_before, &price_after, ts);
+        }
+    }
+
+    Ok(referral_fee)
+}
+
+/// split the fee charged by a swap into (Kong's fee, LP fee)
+/// Kong takes the same share of the fee charged as kong_fee_bps is of the pool's lp_fee_bps, so a dynamic fee, multi-hop
+/// split or user discount changes Kong's fee and the LP fee in proportion. Kong's fee is capped at the fee charged
+/// kong_fee = lp_fee * kong_fee_bps / lp_fee_bps
+fn split_kong_fee(lp_fee: &Nat, kong_fee_bps: u8, lp_fee_bps: u8) -> (Nat, Nat) {
+    let numerator = nat_multiply(lp_fee, &Nat::from(kong_fee_bps));
+    let kong_fee = nat_divide(&numerator, &Nat::from(lp_fee_bps))
+        .unwrap_or(nat_zero())
+        .min(lp_fee.clone());
+    (kong_fee.clone(), nat_subtract(lp_fee, &kong_fee).unwrap_or(nat_zero()))
+}
+
+/// credit the boosted share of the LP fee to the pool's locked LP tokens. returns the LP fee left for all LPs
+fn share_boost_fee(pool: &mut StablePool, lp_fee: &Nat, is_token_0: bool) -> Nat {
+    if nat_is_zero(&pool.boost_supply) {
+        return lp_fee.clone();
+    }
+    let lp_total_supply = lp_token_map::get_total_supply(pool.lp_token_id);
+    lp_token_lock::credit_boost_fee(pool, lp_fee, &lp_total_supply, is_token_0)
+}
+
+/// split Kong's fee into (Kong's fee, referral fee) with referral_fee_pct percent going to the referrer
+fn split_referral_fee(kong_fee: &Nat, referral_fee_pct: Option<u8>) -> (Nat, Nat) {
+    let Some(referral_fee_pct) = referral_fee_pct else {
+        return (kong_fee.clone(), nat_zero());
+    };
+    let numerator = nat_multiply(kong_fee, &Nat::from(std::cmp::min(referral_fee_pct, 100)));
+    let referral_fee = nat_divide(&numerator, &Nat::from(100_u8)).unwrap_or(nat_zero());
+    (nat_subtract(kong_fee, &referral_fee).unwrap_or(nat_zero()), referral_fee)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::stable_lp_token::lp_token_lock::MAX_LOCK_SECS;
+    use crate::stable_lp_token::stable_lp_token::StableLPToken;
+    use crate::stable_pool::pool_map::tests::{insert_pool, pool};
+
+    #[test]
+    fn test_split_kong_fee() {
+        // Kong's fee is the share of the fee charged that kong_fee_bps is of the pool's lp_fee_bps
+        let lp_fee = Nat::from(150_u32);
+        assert_eq!(split_kong_fee(&lp_fee, 10, 30), (Nat::from(50_u32), Nat::from(100_u32)));
+        assert_eq!(split_kong_fee(&lp_fee, 10, 15), (Nat::from(100_u32), Nat::from(50_u32)));
+        // Kong's fee is capped at the fee charged
+        assert_eq!(split_kong_fee(&lp_fee, 10, 5), (lp_fee.clone(), nat_zero()));
+        assert_eq!(split_kong_fee(&lp_fee, 10, 0), (nat_zero(), lp_fee.clone()));
+    }
+
+    #[test]
+    fn test_apply_swap_kong_fee() {
+        let mut pool = pool(1, 1, 2, 1_000_000);
+        let swap = SwapCalc {
+            pool_id: 1,
+            pay_token_id: 1,
+            pay_amount: Nat::from(10_000_u32),
+            receive_token_id: 2,
+            receive_amount: Nat::from(9_900_u32),
+            lp_fee: Nat::from(15_u32),
+            lp_fee_bps: 15,
+            gas_fee: nat_zero(),
+        };
+        // a user discount halves the fee charged, Kong's fee and the LP fee are both halved
+        apply_swap(&mut pool, &swap, None, 0).unwrap();
+        assert_eq!(
+            (pool.kong_fee_1.clone(), pool.lp_fee_1.clone()),
+            (Nat::from(5_u32), Nat::from(10_u32))
+        );
+        assert!(nat_is_zero(&pool.kong_fee_0) && nat_is_zero(&pool.lp_fee_0));
+
+        // each leg of a 3-hop swap is charged a third of the 30 bps LP fee, Kong still gets a third of it
+        let hop = SwapCalc {
+            lp_fee: Nat::from(10_u32),
+            lp_fee_bps: 10,
+            ..swap
+        };
+        apply_swap(&mut pool, &hop, None, 0).unwrap();
+        assert_eq!((pool.kong_fee_1, pool.lp_fee_1), (Nat::from(8_u32), Nat::from(17_u32)));
+    }
+
+    #[test]
+    fn test_update_pools_settles_expired_locks() {
+        let mut pool = insert_pool(1_000_000);
+        // a boosting lock that expired before the swaps
+        let lock = lp_token_lock::add_lock(&mut pool, None, &Nat::from(1_000_u32), MAX_LOCK_SECS, 0);
+        pool_map::update(&pool);
+        lp_token_map::insert(&StableLPToken {
+            lock: Some(lock),
+            ..StableLPToken::new(100, pool.lp_token_id, Nat::from(1_000_u32), 0)
+        })
+        .unwrap();
+        let swap = SwapCalc {
+            pool_id: pool.pool_id,
+            pay_token_id: 1,
+            pay_amount: Nat::from(1_000_u32),
+            receive_token_id: 2,
+            receive_amount: Nat::from(990_u32),
+            lp_fee: Nat::from(3_u32),
+            lp_fee_bps: 30,
+            gas_fee: nat_zero(),
+        };
+        let is_boosting = || {
+            lp_token_map::get_by_token_id_by_user_id(pool.lp_token_id, 100)
+                .and_then(|lp_token| lp_token.lock)
+                .is_some_and(|lock| lock.is_boosting)
+        };
+
+        // the settled locks are not saved when a leg of the swap fails
+        let missing_pool = SwapCalc {
+            pool_id: 99,
+            ..swap.clone()
+        };
+        assert!(update_pools(0, &[swap.clone(), missing_pool]).is_err());
+        assert!(is_boosting());
+        assert_eq!(pool_map::get_by_pool_id(pool.pool_id).unwrap().boost_supply, pool.boost_supply);
+
+        // and are saved with the pools when every leg applies
+        update_pools(0, &[swap]).unwrap();
+        assert!(!is_boosting());
+        let pool = pool_map::get_by_pool_id(pool.pool_id).unwrap();
+        assert!(nat_is_zero(&pool.boost_supply));
+        assert!(pool.boost_expiry_ts.is_none());
+    }
+}
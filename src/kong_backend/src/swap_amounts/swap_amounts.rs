@@ -8,16 +8,24 @@ use crate::ic::guards::not_in_maintenance_mode;
 use crate::stable_token::token::Token;
 use crate::stable_token::token_map;
 use crate::swap;
+use crate::swap::swap_calc::SwapCalc;
+use crate::swap::swap_leg::SwapLeg;
+use crate::swap::swap_reply_helpers::to_swap_leg_reply;
 
 #[query(guard = "not_in_maintenance_mode")]
 pub fn swap_amounts(pay_token: String, pay_amount: Nat, receive_token: String) -> Result<SwapAmountsReply, String> {
+    calculate_swap_amounts(&pay_token, pay_amount, &receive_token).map(|(swap_amounts, _)| swap_amounts)
+}
+
+/// swap amounts and the swaps through the pools they were calculated from
+pub fn calculate_swap_amounts(pay_token: &str, pay_amount: Nat, receive_token: &str) -> Result<(SwapAmountsReply, Vec<SwapCalc>), String> {
     // Pay token
-    let pay_token = token_map::get_by_token(&pay_token)?;
+    let pay_token = token_map::get_by_token(pay_token)?;
     let pay_chain = pay_token.chain();
     let pay_symbol = pay_token.symbol();
     let pay_address = pay_token.address();
     // Receive token
-    let receive_token = token_map::get_by_token(&receive_token)?;
+    let receive_token = token_map::get_by_token(receive_token)?;
     let receive_chain = receive_token.chain();
     let receive_symbol = receive_token.symbol();
     let receive_address = receive_token.address();
@@ -25,8 +33,12 @@ pub fn swap_amounts(pay_token: String, pay_amount: Nat, receive_token: String) -
     let (receive_amount, price, mid_price, slippage, txs) =
         swap::swap_amounts::swap_amounts(&pay_token, Some(&pay_amount), &receive_token)?;
     let swap_amounts_tx_reply: Vec<_> = txs.iter().filter_map(to_swap_amounts_tx_reply).collect();
+    let swap_legs_reply: Vec<_> = SwapLeg::from_txs(pay_token.token_id(), &pay_amount, &txs)
+        .iter()
+        .map(to_swap_leg_reply)
+        .collect();
 
-    Ok(SwapAmountsReply {
+    let swap_amounts = SwapAmountsReply {
         pay_chain,
         pay_symbol,
         pay_amount,
@@ -39,5 +51,8 @@ pub fn swap_amounts(pay_token: String, pay_amount: Nat, receive_token: String) -
         mid_price,
         slippage,
         txs: swap_amounts_tx_reply,
-    })
+        legs: swap_legs_reply,
+    };
+
+    Ok((swap_amounts, txs))
 }
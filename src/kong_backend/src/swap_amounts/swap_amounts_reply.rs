@@ -1,6 +1,8 @@
 use candid::{CandidType, Nat};
 use serde::{Deserialize, Serialize};
 
+use crate::swap::swap_reply::SwapLegReply;
+
 #[derive(CandidType, Clone, Debug, Serialize, Deserialize)]
 pub struct SwapAmountsTxReply {
     pub pool_symbol: String,
@@ -14,6 +16,7 @@ pub struct SwapAmountsTxReply {
     pub receive_amount: Nat,
     pub price: f64,
     pub lp_fee: Nat,
+    pub lp_fee_bps: u8, // effective LP fee in basis points
     pub gas_fee: Nat,
 }
 
@@ -31,4 +34,5 @@ pub struct SwapAmountsReply {
     pub mid_price: f64,
     pub slippage: f64,
     pub txs: Vec<SwapAmountsTxReply>,
+    pub legs: Vec<SwapLegReply>,
 }
@@ -31,6 +31,7 @@ pub fn to_swap_amounts_tx_reply(swap: &SwapCalc) -> Option<SwapAmountsTxReply> {
         receive_amount: swap.receive_amount_with_fees_and_gas(),
         price: price_f64,
         lp_fee: swap.lp_fee.clone(),
+        lp_fee_bps: swap.lp_fee_bps,
         gas_fee: swap.gas_fee.clone(),
     })
 }
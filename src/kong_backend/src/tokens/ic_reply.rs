@@ -14,4 +14,5 @@ pub struct ICReply {
     pub icrc2: bool,
     pub icrc3: bool,
     pub is_removed: bool,
+    pub on_probation: bool, // newly listed token not yet approved by King Kong
 }
@@ -13,4 +13,5 @@ pub struct LPReply {
     pub fee: Nat,
     pub total_supply: Nat,
     pub is_removed: bool,
+    pub ledger_id: Option<String>,
 }
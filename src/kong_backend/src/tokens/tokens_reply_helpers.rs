@@ -25,6 +25,7 @@ pub fn to_token_reply(token: &StableToken) -> TokensReply {
             fee: token.fee(),
             total_supply: lp_token_map::get_total_supply(token_id),
             is_removed: token.is_removed(),
+            ledger_id: lp_token.ledger_id.clone(),
         }),
         IC(ic_token) => TokensReply::IC(ICReply {
             token_id,
@@ -38,6 +39,7 @@ pub fn to_token_reply(token: &StableToken) -> TokensReply {
             icrc2: ic_token.icrc2,
             icrc3: ic_token.icrc3,
             is_removed: token.is_removed(),
+            on_probation: ic_token.on_probation,
         }),
     }
 }
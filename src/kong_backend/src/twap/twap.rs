@@ -0,0 +1,107 @@
+use candid::Nat;
+use ic_cdk::query;
+use num::BigRational;
+
+use super::twap_reply::TwapReply;
+
+use crate::helpers::math_helpers::price_rounded;
+use crate::ic::get_time::get_time;
+use crate::ic::guards::not_in_maintenance_mode;
+use crate::stable_pool::price_oracle;
+use crate::stable_pool::{pool_map, stable_pool::StablePool};
+use crate::stable_pool_observation::pool_observation_map::{self, MAX_OBSERVATIONS_PER_POOL, OBSERVATION_INTERVAL_SECS};
+
+/// Return the time-weighted average price of a pool over the last window_secs
+/// pool_symbol - pool symbol, e.g. "ckBTC_ckUSDT"
+/// window_secs - length of the averaging window in seconds, at most 24 hours
+#[query(guard = "not_in_maintenance_mode")]
+fn twap(pool_symbol: String, window_secs: u64) -> Result<TwapReply, String> {
+    if window_secs == 0 {
+        Err("Window must be greater than 0 secs".to_string())?
+    }
+    let max_window_secs = OBSERVATION_INTERVAL_SECS * MAX_OBSERVATIONS_PER_POOL;
+    if window_secs > max_window_secs {
+        Err(format!("Window must be at most {} secs", max_window_secs))?
+    }
+
+    let pool = pool_map::get_by_token(&pool_symbol)?;
+    let ts = get_time();
+    let (price, inverse_price) = get_twap(&pool, window_secs, ts)?;
+    let price = price_rounded(&price).ok_or("Invalid price")?;
+    let inverse_price = price_rounded(&inverse_price).ok_or("Invalid price")?;
+
+    Ok(TwapReply {
+        symbol: pool.symbol(),
+        window_secs,
+        price,
+        inverse_price,
+        ts,
+    })
+}
+
+/// time-weighted average prices (price, inverse_price) of the pool over the window_secs before ts
+/// price is the price of token_0 in token_1 and inverse_price of token_1 in token_0, at the decimal precision of the pool
+pub fn get_twap(pool: &StablePool, window_secs: u64, ts: u64) -> Result<(BigRational, BigRational), String> {
+    if pool.price_cumulative_secs == 0 {
+        Err(format!("Pool {} has no price history", pool.symbol()))?
+    }
+
+    let ts_secs = ts / 1_000_000_000;
+    let start_secs = ts_secs.saturating_sub(window_secs);
+
+    // observed cumulative prices, followed by the last update of the pool and extrapolated to now
+    let (price_0_cumulative, price_1_cumulative) = pool.price_cumulative_at(ts_secs);
+    let mut observations_0: Vec<(u64, Nat)> = Vec::new();
+    let mut observations_1: Vec<(u64, Nat)> = Vec::new();
+    for observation in pool_observation_map::get_by_pool_id(pool.pool_id) {
+        observations_0.push((observation.ts_secs, observation.price_0_cumulative));
+        observations_1.push((observation.ts_secs, observation.price_1_cumulative));
+    }
+    observations_0.push((pool.price_cumulative_secs, pool.price_0_cumulative.clone()));
+    observations_1.push((pool.price_cumulative_secs, pool.price_1_cumulative.clone()));
+    observations_0.push((ts_secs, price_0_cumulative.clone()));
+    observations_1.push((ts_secs, price_1_cumulative.clone()));
+
+    let not_enough_history = || format!("Pool {} does not have {} secs of price history", pool.symbol(), window_secs);
+    let start_price_0_cumulative = price_oracle::cumulative_at(&observations_0, start_secs).ok_or_else(not_enough_history)?;
+    let start_price_1_cumulative = price_oracle::cumulative_at(&observations_1, start_secs).ok_or_else(not_enough_history)?;
+
+    let price = price_oracle::twap(&start_price_0_cumulative, &price_0_cumulative, window_secs).ok_or("Invalid price")?;
+    let inverse_price = price_oracle::twap(&start_price_1_cumulative, &price_1_cumulative, window_secs).ok_or("Invalid price")?;
+    Ok((price, inverse_price))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use num_traits::ToPrimitive;
+
+    use crate::helpers::nat_helpers::nat_multiply;
+    use crate::stable_pool::pool_map::tests::pool;
+
+    const SECS: u64 = 1_000_000_000;
+
+    #[test]
+    fn test_get_twap() {
+        let ts = get_time();
+        let pool_id = pool_map::insert(&pool(0, 1, 2, 1_000_000)).unwrap();
+        let mut pool = pool_map::get_by_pool_id(pool_id).unwrap();
+        assert!(get_twap(&pool, 100, ts).is_err());
+
+        // price 1.0 for 100 secs, then 2.0 for the last 100 secs
+        pool.price_cumulative_secs = ts / SECS - 200;
+        pool_map::update(&pool);
+        pool.update_price_cumulative(ts - 100 * SECS);
+        pool.balance_1 = nat_multiply(&pool.balance_1, &Nat::from(2_u32));
+        pool_map::update(&pool);
+
+        let twap = |window_secs| {
+            let (price, inverse_price) = get_twap(&pool, window_secs, ts).unwrap();
+            (price.to_f64().unwrap(), inverse_price.to_f64().unwrap())
+        };
+        assert_eq!(twap(200), (1.5, 0.75));
+        assert_eq!(twap(100), (2.0, 0.5));
+        // the window can not start before the first observation
+        assert!(get_twap(&pool, 201, ts).is_err());
+    }
+}
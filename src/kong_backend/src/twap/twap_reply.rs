@@ -0,0 +1,11 @@
+use candid::CandidType;
+use serde::{Deserialize, Serialize};
+
+#[derive(CandidType, Debug, Clone, Serialize, Deserialize)]
+pub struct TwapReply {
+    pub symbol: String,
+    pub window_secs: u64,
+    pub price: f64,         // time-weighted average price of token_0 in token_1
+    pub inverse_price: f64, // time-weighted average price of token_1 in token_0
+    pub ts: u64,
+}
@@ -0,0 +1,35 @@
+use ic_cdk::update;
+
+use crate::claims::claim_reply::ClaimReply;
+use crate::claims::process_claim::process_new_claims;
+use crate::ic::address::Address;
+use crate::ic::get_time::get_time;
+use crate::ic::guards::not_in_maintenance_mode;
+use crate::ic::id::caller_id;
+use crate::stable_claim::claim_map;
+use crate::stable_claim::stable_claim::StableClaim;
+use crate::stable_user::{referral_reward_map, user_map};
+
+/// Claim the referral rewards of the caller
+/// rewards are saved as claims and sent to the caller. claims that fail to send are retried by the claims timer
+#[update(guard = "not_in_maintenance_mode")]
+async fn claim_referral_rewards() -> Result<Vec<ClaimReply>, String> {
+    let user_id = user_map::get_by_caller()?.ok_or("User not found")?.user_id;
+    let ts = get_time();
+    let to_address = Address::PrincipalId(caller_id());
+
+    // rewards are taken and saved as claims before any inter-canister call so they can only be claimed once
+    let claim_ids: Vec<u64> = referral_reward_map::take_rewards(user_id, ts)
+        .into_iter()
+        .map(|(token_id, amount)| {
+            let mut claim = StableClaim::new(user_id, token_id, &amount, None, Some(to_address.clone()), ts);
+            claim.desc = Some("Referral rewards".to_string());
+            claim_map::insert(&claim)
+        })
+        .collect();
+    if claim_ids.is_empty() {
+        Err("No referral rewards to claim".to_string())?
+    }
+
+    Ok(process_new_claims(user_id, &claim_ids, &to_address, ts).await)
+}
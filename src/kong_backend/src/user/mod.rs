@@ -1,3 +1,4 @@
+pub mod claim_referral_rewards;
 pub mod get_user;
 pub mod user_reply;
 pub mod user_reply_helpers;
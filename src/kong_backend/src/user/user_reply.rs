@@ -1,4 +1,4 @@
-use candid::CandidType;
+use candid::{CandidType, Nat};
 use serde::{Deserialize, Serialize};
 
 #[derive(CandidType, Clone, Debug, Serialize, Deserialize)]
@@ -11,4 +11,25 @@ pub struct UserReply {
     pub referred_by_expires_at: Option<u64>,
     pub fee_level: u8,
     pub fee_level_expires_at: Option<u64>,
+    pub volume_30d: Nat,                         // swap volume in ckUSDT counted for the fee tiers
+    pub next_fee_tier: Option<NextFeeTierReply>, // next fee tier and progress to it
+    pub referral_count: u32,                     // number of users referred by the user
+    pub referral_rewards: Vec<ReferralRewardReply>,
+}
+
+#[derive(CandidType, Clone, Debug, Serialize, Deserialize)]
+pub struct ReferralRewardReply {
+    pub chain: String,
+    pub symbol: String,
+    pub address: String,
+    pub amount: Nat,       // not yet claimed
+    pub total_amount: Nat, // earned in total
+}
+
+#[derive(CandidType, Clone, Debug, Serialize, Deserialize)]
+pub struct NextFeeTierReply {
+    pub fee_level: u8,
+    pub min_volume: Nat,
+    pub min_kong: Option<Nat>,
+    pub volume_needed: Nat, // 30-day swap volume in ckUSDT still needed to qualify
 }
@@ -1,10 +1,17 @@
 use candid::Principal;
 
-use super::user_reply::UserReply;
+use super::user_reply::{NextFeeTierReply, ReferralRewardReply, UserReply};
 
-use crate::ic::id::principal_to_account_id;
+use crate::helpers::nat_helpers::{nat_subtract, nat_zero};
+
+use crate::ic::{get_time::get_time, id::principal_to_account_id};
+use crate::stable_kong_settings::kong_settings_map;
+use crate::stable_token::token::Token;
+use crate::stable_token::token_map;
 use crate::stable_user::stable_user::StableUser;
-use crate::stable_user::user_map;
+use crate::stable_user::{referral_reward_map, user_map, user_volume_map};
+use crate::user_fee_tiers::user_fee_tier::get_next_fee_tier;
+use crate::user_fee_tiers::user_fee_tiers_timer::volume_from_day;
 
 pub fn to_user_reply(user: &StableUser) -> UserReply {
     let principal = Principal::from_text(&user.principal_id).unwrap();
@@ -13,6 +20,26 @@ pub fn to_user_reply(user: &StableUser) -> UserReply {
     let referred_by = user
         .referred_by
         .and_then(|referred_user| user_map::get_by_user_id(referred_user).map(|referred_user| referred_user.my_referral_code));
+    let referral_rewards = referral_reward_map::get_by_user_id(user.user_id)
+        .into_iter()
+        .filter_map(|referral_reward| {
+            let token = token_map::get_by_token_id(referral_reward.token_id)?;
+            Some(ReferralRewardReply {
+                chain: token.chain(),
+                symbol: token.symbol(),
+                address: token.address(),
+                amount: referral_reward.amount,
+                total_amount: referral_reward.total_amount,
+            })
+        })
+        .collect();
+    let volume_30d = user_volume_map::get_volume(user.user_id, volume_from_day(get_time()));
+    let next_fee_tier = get_next_fee_tier(&kong_settings_map::get().fee_tiers, user.fee_level).map(|fee_tier| NextFeeTierReply {
+        fee_level: fee_tier.fee_level,
+        min_volume: fee_tier.min_volume.clone(),
+        min_kong: fee_tier.min_kong.clone(),
+        volume_needed: nat_subtract(&fee_tier.min_volume, &volume_30d).unwrap_or(nat_zero()),
+    });
     UserReply {
         user_id: user.user_id,
         principal_id: user.principal_id.clone(),
@@ -22,5 +49,9 @@ pub fn to_user_reply(user: &StableUser) -> UserReply {
         referred_by_expires_at: user.referred_by_expires_at,
         fee_level: user.fee_level,
         fee_level_expires_at: user.fee_level_expires_at,
+        volume_30d,
+        next_fee_tier,
+        referral_count: user_map::get_referral_count(user.user_id),
+        referral_rewards,
     }
 }
@@ -0,0 +1,13 @@
+use candid::{CandidType, Nat};
+use serde::{Deserialize, Serialize};
+
+#[derive(CandidType, Clone, Debug, Serialize, Deserialize)]
+pub struct DepositBalanceReply {
+    pub chain: String,
+    pub symbol: String,
+    pub address: String,
+    pub amount: Nat, // raw amount with the token's decimal precision
+    pub balance: f64,
+    pub usd_balance: f64,
+    pub ts: u64,
+}
@@ -18,5 +18,19 @@ pub struct LPReply {
     pub address_1: String,
     pub amount_1: f64,
     pub usd_amount_1: f64,
+    pub locked_balance: f64,         // LP tokens that can not be removed or transferred until unlock_ts
+    pub unlock_ts: Option<u64>,      // None if no LP tokens are locked
+    pub boost_pct: Option<u32>,      // boost of the locked LP tokens' share of the LP fees, 100 = no boost
+    pub rewards: Vec<LPRewardReply>, // unclaimed liquidity mining rewards
     pub ts: u64,
 }
+
+#[derive(CandidType, Clone, Debug, Serialize, Deserialize)]
+pub struct LPRewardReply {
+    pub campaign_id: u64,
+    pub chain: String,
+    pub symbol: String,
+    pub address: String,
+    pub amount: f64,
+    pub usd_amount: f64,
+}
@@ -1,4 +1,6 @@
+pub mod deposit_reply;
 pub mod lp_reply;
+pub mod position_reply;
 #[allow(clippy::module_inception)]
 pub mod user_balances;
 pub mod user_balances_reply;
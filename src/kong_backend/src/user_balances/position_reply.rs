@@ -0,0 +1,25 @@
+use candid::{CandidType, Nat};
+use serde::{Deserialize, Serialize};
+
+#[derive(CandidType, Clone, Debug, Serialize, Deserialize)]
+pub struct PositionReply {
+    pub symbol: String,
+    pub name: String,
+    pub position_id: u64,
+    pub liquidity: Nat,
+    pub min_price: f64,
+    pub max_price: f64,
+    pub in_range: bool,
+    pub usd_balance: f64,
+    pub chain_0: String,
+    pub symbol_0: String,
+    pub address_0: String,
+    pub amount_0: f64, // includes uncollected LP fees
+    pub usd_amount_0: f64,
+    pub chain_1: String,
+    pub symbol_1: String,
+    pub address_1: String,
+    pub amount_1: f64,
+    pub usd_amount_1: f64,
+    pub ts: u64,
+}
@@ -1,14 +1,24 @@
 use ic_cdk::query;
 
-use super::lp_reply::LPReply;
+use super::deposit_reply::DepositBalanceReply;
+use super::lp_reply::{LPReply, LPRewardReply};
+use super::position_reply::PositionReply;
 use super::user_balances_reply::UserBalancesReply;
 
 use crate::helpers::nat_helpers::{nat_add, nat_divide, nat_multiply, nat_to_decimals_f64, nat_zero};
 use crate::ic::ckusdt::{ckusdt_amount, to_ckusdt_decimals_f64};
 use crate::ic::get_time::get_time;
 use crate::ic::guards::not_in_maintenance_mode;
+use crate::stable_deposit::deposit_map;
+use crate::stable_deposit::stable_deposit::StableDeposit;
+use crate::stable_lp_token::lp_token_lock;
 use crate::stable_lp_token::lp_token_map;
 use crate::stable_lp_token::stable_lp_token::StableLPToken;
+use crate::stable_pool::concentrated_pool::{ConcentratedPool, ConcentratedPosition};
+use crate::stable_pool::pool_map;
+use crate::stable_pool::pool_type::PoolType;
+use crate::stable_pool::stable_pool::StablePool;
+use crate::stable_reward_campaign::reward_campaign_map;
 use crate::stable_token::stable_token::StableToken;
 use crate::stable_token::token::Token;
 use crate::stable_token::token_map;
@@ -31,6 +41,26 @@ pub async fn user_balances(principal_id: String) -> Result<Vec<UserBalancesReply
         }
     });
 
+    pool_map::get().iter().for_each(|pool| {
+        if let PoolType::Concentrated(concentrated_pool) = &pool.pool_type {
+            concentrated_pool
+                .get_positions()
+                .iter()
+                .filter(|position| position.user_id == user_id)
+                .for_each(|position| {
+                    if let Some(reply) = to_user_balance_position_reply(pool, concentrated_pool, position, ts) {
+                        user_balances.push(reply);
+                    }
+                });
+        }
+    });
+
+    deposit_map::get_by_user_id(user_id).iter().for_each(|deposit| {
+        if let Some(reply) = to_user_balance_deposit_reply(deposit, ts) {
+            user_balances.push(reply);
+        }
+    });
+
     Ok(user_balances)
 }
 
@@ -57,21 +87,24 @@ fn to_user_balance_lp_token_reply(lp_token: &StableLPToken, ts: u64) -> Option<U
     // convert balance to real number
     let balance = nat_to_decimals_f64(token.decimals, &user_lp_token_balance)?;
 
-    // user_amount_0 = reserve0 * user_lp_token_balance / lp_token_total_supply
+    // boost fees earned by the lock of the LP tokens
+    let (boost_fee_0, boost_fee_1) = lp_token_lock::boost_fees(&pool, lp_token.lock.as_ref());
+
+    // user_amount_0 = reserve0 * user_lp_token_balance / lp_token_total_supply + boost_fee_0
     let token_0 = pool.token_0();
     let reserve0 = nat_add(&pool.balance_0, &pool.lp_fee_0);
     let numerator = nat_multiply(&reserve0, &user_lp_token_balance);
-    let raw_amount_0 = nat_divide(&numerator, &lp_token_total_supply).unwrap_or(nat_zero());
+    let raw_amount_0 = nat_add(&nat_divide(&numerator, &lp_token_total_supply).unwrap_or(nat_zero()), &boost_fee_0);
     let amount_0 = nat_to_decimals_f64(token_0.decimals(), &raw_amount_0)?;
     let usd_amount_0 = ckusdt_amount(&token_0, &raw_amount_0)
         .and_then(|amount_0| to_ckusdt_decimals_f64(&amount_0).ok_or("Error converting amount 0 to ckUSDT".to_string()))
         .unwrap_or(0_f64);
 
-    // user_amount_1 = reserve1 * user_lp_token_balance / lp_token_total_supply
+    // user_amount_1 = reserve1 * user_lp_token_balance / lp_token_total_supply + boost_fee_1
     let token_1 = pool.token_1();
     let reserve1 = nat_add(&pool.balance_1, &pool.lp_fee_1);
     let numerator = nat_multiply(&reserve1, &user_lp_token_balance);
-    let raw_amount_1 = nat_divide(&numerator, &lp_token_total_supply).unwrap_or(nat_zero());
+    let raw_amount_1 = nat_add(&nat_divide(&numerator, &lp_token_total_supply).unwrap_or(nat_zero()), &boost_fee_1);
     let amount_1 = nat_to_decimals_f64(token_1.decimals(), &raw_amount_1)?;
     let usd_amount_1 = ckusdt_amount(&token_1, &raw_amount_1)
         .and_then(|amount_1| to_ckusdt_decimals_f64(&amount_1).ok_or("Error converting amount 1 to ckUSDT".to_string()))
@@ -79,6 +112,33 @@ fn to_user_balance_lp_token_reply(lp_token: &StableLPToken, ts: u64) -> Option<U
 
     let usd_balance = usd_amount_0 + usd_amount_1;
 
+    // active lock of the LP tokens
+    let lock = lp_token.lock.as_ref().filter(|lock| lock.is_locked(ts));
+    let locked_balance = match lock {
+        Some(lock) => nat_to_decimals_f64(token.decimals, &lock.amount)?,
+        None => 0_f64,
+    };
+
+    // unclaimed liquidity mining rewards
+    let rewards = reward_campaign_map::get_unclaimed_rewards(token_id, lp_token.user_id, &lp_token.amount, ts)
+        .into_iter()
+        .filter_map(|(campaign, raw_amount)| {
+            let reward_token = token_map::get_by_token_id(campaign.reward_token_id)?;
+            let amount = nat_to_decimals_f64(reward_token.decimals(), &raw_amount)?;
+            let usd_amount = ckusdt_amount(&reward_token, &raw_amount)
+                .and_then(|amount| to_ckusdt_decimals_f64(&amount).ok_or("Error converting reward amount to ckUSDT".to_string()))
+                .unwrap_or(0_f64);
+            Some(LPRewardReply {
+                campaign_id: campaign.campaign_id,
+                chain: reward_token.chain(),
+                symbol: reward_token.symbol(),
+                address: reward_token.address(),
+                amount,
+                usd_amount,
+            })
+        })
+        .collect();
+
     Some(UserBalancesReply::LP(LPReply {
         name: token.name(),
         symbol: token.symbol.clone(),
@@ -95,6 +155,83 @@ fn to_user_balance_lp_token_reply(lp_token: &StableLPToken, ts: u64) -> Option<U
         address_1: token_1.address(),
         amount_1,
         usd_amount_1,
+        locked_balance,
+        unlock_ts: lock.map(|lock| lock.unlock_ts),
+        boost_pct: lock.map(|lock| lock.boost_pct),
+        rewards,
+        ts,
+    }))
+}
+
+fn to_user_balance_position_reply(
+    pool: &StablePool,
+    concentrated_pool: &ConcentratedPool,
+    position: &ConcentratedPosition,
+    ts: u64,
+) -> Option<UserBalancesReply> {
+    let token_0 = pool.token_0();
+    let token_1 = pool.token_1();
+
+    // principal amounts at the current price plus uncollected fees
+    let (principal_0, principal_1) =
+        concentrated_pool.amounts_for_liquidity(position.tick_lower, position.tick_upper, &position.liquidity, false);
+    let (lp_fee_0, lp_fee_1) = concentrated_pool.position_fees(position);
+
+    let raw_amount_0 = nat_add(&principal_0, &lp_fee_0);
+    let amount_0 = nat_to_decimals_f64(token_0.decimals(), &raw_amount_0)?;
+    let usd_amount_0 = ckusdt_amount(&token_0, &raw_amount_0)
+        .and_then(|amount_0| to_ckusdt_decimals_f64(&amount_0).ok_or("Error converting amount 0 to ckUSDT".to_string()))
+        .unwrap_or(0_f64);
+
+    let raw_amount_1 = nat_add(&principal_1, &lp_fee_1);
+    let amount_1 = nat_to_decimals_f64(token_1.decimals(), &raw_amount_1)?;
+    let usd_amount_1 = ckusdt_amount(&token_1, &raw_amount_1)
+        .and_then(|amount_1| to_ckusdt_decimals_f64(&amount_1).ok_or("Error converting amount 1 to ckUSDT".to_string()))
+        .unwrap_or(0_f64);
+
+    let usd_balance = usd_amount_0 + usd_amount_1;
+
+    Some(UserBalancesReply::Position(PositionReply {
+        symbol: pool.symbol(),
+        name: pool.name(),
+        position_id: position.position_id,
+        liquidity: position.liquidity.clone(),
+        min_price: ConcentratedPool::tick_to_price(position.tick_lower, token_0.decimals(), token_1.decimals()),
+        max_price: ConcentratedPool::tick_to_price(position.tick_upper, token_0.decimals(), token_1.decimals()),
+        in_range: position.tick_lower <= concentrated_pool.tick && concentrated_pool.tick < position.tick_upper,
+        usd_balance,
+        chain_0: token_0.chain(),
+        symbol_0: token_0.symbol(),
+        address_0: token_0.address(),
+        amount_0,
+        usd_amount_0,
+        chain_1: token_1.chain(),
+        symbol_1: token_1.symbol(),
+        address_1: token_1.address(),
+        amount_1,
+        usd_amount_1,
+        ts,
+    }))
+}
+
+fn to_user_balance_deposit_reply(deposit: &StableDeposit, ts: u64) -> Option<UserBalancesReply> {
+    // filter out deposits with zero balance
+    if deposit.amount == nat_zero() {
+        return None;
+    }
+    let token = token_map::get_by_token_id(deposit.token_id)?;
+    let balance = nat_to_decimals_f64(token.decimals(), &deposit.amount)?;
+    let usd_balance = ckusdt_amount(&token, &deposit.amount)
+        .and_then(|amount| to_ckusdt_decimals_f64(&amount).ok_or("Error converting deposit to ckUSDT".to_string()))
+        .unwrap_or(0_f64);
+
+    Some(UserBalancesReply::Deposit(DepositBalanceReply {
+        chain: token.chain(),
+        symbol: token.symbol(),
+        address: token.address(),
+        amount: deposit.amount.clone(),
+        balance,
+        usd_balance,
         ts,
     }))
 }
@@ -1,9 +1,13 @@
 use candid::CandidType;
 use serde::{Deserialize, Serialize};
 
+use super::deposit_reply::DepositBalanceReply;
 use super::lp_reply::LPReply;
+use super::position_reply::PositionReply;
 
 #[derive(CandidType, Clone, Debug, Serialize, Deserialize)]
 pub enum UserBalancesReply {
-    LP(LPReply), // only return LP token balances for now
+    LP(LPReply),
+    Position(PositionReply),      // positions of concentrated pools
+    Deposit(DepositBalanceReply), // internal deposit balances of tokens
 }
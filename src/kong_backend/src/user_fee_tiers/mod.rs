@@ -0,0 +1,2 @@
+pub mod user_fee_tier;
+pub mod user_fee_tiers_timer;
@@ -0,0 +1,82 @@
+use candid::{CandidType, Nat};
+use serde::{Deserialize, Serialize};
+use std::cmp::min;
+
+/// tier of the fee schedule. a user gets the fee_level of the highest tier it qualifies for
+#[derive(CandidType, Debug, Clone, Serialize, Deserialize)]
+pub struct UserFeeTier {
+    pub fee_level: u8,         // 0 = no discount, 100 = pays no lp_fee on swaps
+    pub min_volume: Nat,       // 30-day swap volume in ckUSDT to qualify
+    pub min_kong: Option<Nat>, // or KONG held in the user's deposit to qualify
+}
+
+impl UserFeeTier {
+    pub fn is_qualified(&self, volume: &Nat, kong_balance: &Nat) -> bool {
+        *volume >= self.min_volume || self.min_kong.as_ref().is_some_and(|min_kong| kong_balance >= min_kong)
+    }
+}
+
+/// fee level of the highest tier qualified for by volume or KONG balance. 0 if none
+pub fn get_fee_level(fee_tiers: &[UserFeeTier], volume: &Nat, kong_balance: &Nat) -> u8 {
+    fee_tiers
+        .iter()
+        .filter(|fee_tier| fee_tier.is_qualified(volume, kong_balance))
+        .map(|fee_tier| min(fee_tier.fee_level, 100))
+        .max()
+        .unwrap_or(0)
+}
+
+/// the next tier above fee_level
+pub fn get_next_fee_tier(fee_tiers: &[UserFeeTier], fee_level: u8) -> Option<&UserFeeTier> {
+    fee_tiers
+        .iter()
+        .filter(|fee_tier| fee_tier.fee_level > fee_level)
+        .min_by_key(|fee_tier| fee_tier.fee_level)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn fee_tiers() -> Vec<UserFeeTier> {
+        vec![
+            UserFeeTier {
+                fee_level: 10,
+                min_volume: Nat::from(1_000_u32),
+                min_kong: Some(Nat::from(500_u32)),
+            },
+            UserFeeTier {
+                fee_level: 50,
+                min_volume: Nat::from(100_000_u32),
+                min_kong: None,
+            },
+            UserFeeTier {
+                fee_level: 25,
+                min_volume: Nat::from(10_000_u32),
+                min_kong: Some(Nat::from(5_000_u32)),
+            },
+        ]
+    }
+
+    #[test]
+    fn test_get_fee_level() {
+        let fee_tiers = fee_tiers();
+        assert_eq!(get_fee_level(&fee_tiers, &Nat::from(999_u32), &Nat::from(0_u32)), 0);
+        assert_eq!(get_fee_level(&fee_tiers, &Nat::from(1_000_u32), &Nat::from(0_u32)), 10);
+        assert_eq!(get_fee_level(&fee_tiers, &Nat::from(50_000_u32), &Nat::from(0_u32)), 25);
+        assert_eq!(get_fee_level(&fee_tiers, &Nat::from(0_u32), &Nat::from(5_000_u32)), 25);
+        // KONG does not qualify for a tier without min_kong
+        assert_eq!(get_fee_level(&fee_tiers, &Nat::from(0_u32), &Nat::from(1_000_000_u32)), 25);
+        assert_eq!(get_fee_level(&fee_tiers, &Nat::from(100_000_u32), &Nat::from(0_u32)), 50);
+        assert_eq!(get_fee_level(&[], &Nat::from(100_000_u32), &Nat::from(0_u32)), 0);
+    }
+
+    #[test]
+    fn test_get_next_fee_tier() {
+        let fee_tiers = fee_tiers();
+        assert_eq!(get_next_fee_tier(&fee_tiers, 0).map(|fee_tier| fee_tier.fee_level), Some(10));
+        assert_eq!(get_next_fee_tier(&fee_tiers, 10).map(|fee_tier| fee_tier.fee_level), Some(25));
+        assert_eq!(get_next_fee_tier(&fee_tiers, 25).map(|fee_tier| fee_tier.fee_level), Some(50));
+        assert!(get_next_fee_tier(&fee_tiers, 50).is_none());
+    }
+}
@@ -0,0 +1,161 @@
+use candid::Nat;
+use std::cmp::min;
+use std::collections::{BTreeMap, BTreeSet};
+
+use super::user_fee_tier::{get_fee_level, UserFeeTier};
+
+use crate::helpers::nat_helpers::nat_zero;
+use crate::ic::{ckusdt::ckusdt_amount, get_time::get_time, guards::not_in_maintenance_mode};
+use crate::stable_deposit::deposit_map;
+use crate::stable_kong_settings::kong_settings_map;
+use crate::stable_token::token_map;
+use crate::stable_tx::{stable_tx::StableTx, status_tx::StatusTx, tx_map};
+use crate::stable_user::{stable_user::FeeLevelSource, user_map, user_volume_map};
+
+// number of days of swap volume counted for the fee tiers
+pub const VOLUME_DAYS: u32 = 30;
+// max number of txs added to the user volumes in one run. the rest are added on the next runs
+const MAX_TXS: u64 = 20_000;
+
+/// first day counted in the swap volume at ts
+pub fn volume_from_day(ts: u64) -> u32 {
+    user_volume_map::to_day(ts).saturating_sub(VOLUME_DAYS - 1)
+}
+
+/// Refresh the fee levels of users from the fee tiers in the kong settings
+///
+/// - swaps since the last run are added to the daily swap volumes of the users, valued in ckUSDT
+/// - a user gets the fee level of the highest tier its 30-day swap volume or KONG deposit qualifies for
+/// - manual fee levels are kept, or until they expire if set with an expiry. fee tiers are off if there are no tiers
+pub fn process_user_fee_tiers_timer() {
+    if not_in_maintenance_mode().is_err() {
+        return;
+    }
+
+    let kong_settings = kong_settings_map::get();
+    if kong_settings.fee_tiers.is_empty() {
+        return;
+    }
+
+    let ts = get_time();
+    update_user_volumes(kong_settings.user_volume_tx_idx, kong_settings.tx_map_idx, ts);
+    update_fee_levels(&kong_settings.fee_tiers, kong_settings.kong_token_id, ts);
+}
+
+fn update_user_volumes(user_volume_tx_idx: u64, tx_map_idx: u64, ts: u64) {
+    let from_day = volume_from_day(ts);
+    let end_tx_id = min(tx_map_idx, user_volume_tx_idx + MAX_TXS);
+    for tx_id in user_volume_tx_idx + 1..=end_tx_id {
+        let Some(StableTx::Swap(swap_tx)) = tx_map::get_by_tx_id(tx_id) else {
+            continue;
+        };
+        if swap_tx.status != StatusTx::Success {
+            continue;
+        }
+        let day = user_volume_map::to_day(swap_tx.ts);
+        if day < from_day {
+            continue;
+        }
+        let Some(volume) =
+            token_map::get_by_token_id(swap_tx.pay_token_id).and_then(|pay_token| ckusdt_amount(&pay_token, &swap_tx.pay_amount).ok())
+        else {
+            continue;
+        };
+        user_volume_map::add(swap_tx.user_id, day, &volume);
+    }
+    kong_settings_map::set_user_volume_tx_idx(end_tx_id);
+
+    user_volume_map::remove_before(from_day);
+}
+
+fn update_fee_levels(fee_tiers: &[UserFeeTier], kong_token_id: Option<u32>, ts: u64) {
+    let volumes = user_volume_map::get_volumes(volume_from_day(ts));
+    let kong_balances = kong_token_id.map_or_else(BTreeMap::new, |kong_token_id| {
+        deposit_map::get_by_token_id(kong_token_id)
+            .into_iter()
+            .map(|deposit| (deposit.user_id, deposit.amount))
+            .collect::<BTreeMap<u32, Nat>>()
+    });
+
+    // users with volume or KONG may move up a tier and users in a tier may move down
+    let user_ids = volumes
+        .keys()
+        .chain(kong_balances.keys())
+        .copied()
+        .chain(user_map::get_fee_tier_user_ids())
+        .collect::<BTreeSet<u32>>();
+    for user_id in user_ids {
+        let Some(mut user) = user_map::get_by_user_id(user_id) else {
+            continue;
+        };
+        let mut update = false;
+        match user.fee_level_expires_at {
+            Some(fee_level_expires_at) if fee_level_expires_at > ts => continue,
+            Some(_) => {
+                // expired fee level falls back to the fee tiers
+                user.fee_level_expires_at = None;
+                user.fee_level_source = FeeLevelSource::Tier;
+                update = true;
+            }
+            None if user.fee_level_source == FeeLevelSource::Manual => continue,
+            None => (),
+        }
+        let volume = volumes.get(&user_id).cloned().unwrap_or_else(nat_zero);
+        let kong_balance = kong_balances.get(&user_id).cloned().unwrap_or_else(nat_zero);
+        let fee_level = get_fee_level(fee_tiers, &volume, &kong_balance);
+        if fee_level != user.fee_level {
+            user.fee_level = fee_level;
+            update = true;
+        }
+        if update {
+            user_map::update(&user);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::stable_memory::USER_MAP;
+    use crate::stable_user::stable_user::{StableUser, StableUserId};
+
+    fn insert_user(user_id: u32, fee_level: u8, fee_level_source: FeeLevelSource) {
+        let user = StableUser {
+            user_id,
+            principal_id: format!("user-{}", user_id),
+            my_referral_code: format!("USER{}", user_id),
+            referred_by: None,
+            referred_by_expires_at: None,
+            fee_level,
+            fee_level_expires_at: None,
+            fee_level_source,
+        };
+        USER_MAP.with(|m| m.borrow_mut().insert(StableUserId(user_id), user));
+    }
+
+    #[test]
+    fn test_manual_fee_level_kept() {
+        // settings are initialized from the stable maps, so must be before any of them are borrowed
+        kong_settings_map::get();
+        let fee_tiers = vec![UserFeeTier {
+            fee_level: 25,
+            min_volume: Nat::from(10_000_u32),
+            min_kong: None,
+        }];
+        let ts = get_time();
+        insert_user(100, 10, FeeLevelSource::Tier);
+        insert_user(101, 40, FeeLevelSource::Manual);
+        insert_user(102, 10, FeeLevelSource::Tier);
+        for user_id in [100, 101] {
+            user_volume_map::add(user_id, user_volume_map::to_day(ts), &Nat::from(50_000_u32));
+        }
+
+        update_fee_levels(&fee_tiers, None, ts);
+        let fee_level = |user_id| user_map::get_by_user_id(user_id).unwrap().fee_level;
+        // tier levels are recomputed, moving up with volume or down without, the manual level is kept
+        assert_eq!(fee_level(100), 25);
+        assert_eq!(fee_level(101), 40);
+        assert_eq!(fee_level(102), 0);
+    }
+}
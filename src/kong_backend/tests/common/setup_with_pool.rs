@@ -10,7 +10,7 @@ use super::setup::{setup_ic_environment, CONTROLLER_PEM_FILE};
 // Import kong_backend types needed for setup
 use kong_backend::add_pool::add_pool_args::AddPoolArgs;
 use kong_backend::add_pool::add_pool_reply::AddPoolReply;
-use kong_backend::pools::pools_reply::PoolReply;
+use kong_backend::pools::pools_reply::{PoolReply, PoolTypeReply};
 use kong_backend::stable_transfer::tx_id::TxId;
 
 // Use the token constants from the default setup
@@ -189,6 +189,8 @@ pub fn setup_swap_test_environment() -> Result<SwapTestSetup> {
         amount_1: Nat::from(base_liquidity_b),
         tx_id_1: Some(TxId::BlockIndex(token_b_tx_id)),
         lp_fee_bps: Some(30),
+        tick_spacing: None,
+        amp: None,
     };
     let add_pool_payload = encode_one(&add_pool_args).expect("Failed to encode add_pool args");
     let add_pool_response = ic
@@ -220,8 +222,10 @@ pub fn setup_swap_test_environment() -> Result<SwapTestSetup> {
         lp_fee_1: Nat::from(0u64), // not provided in AddPoolReply
         price: 0.0, // not provided in AddPoolReply
         lp_fee_bps: add_pool_reply.lp_fee_bps,
+        fee_tier: add_pool_reply.lp_fee_bps,
         lp_token_symbol: add_pool_reply.lp_token_symbol.clone(),
         is_removed: add_pool_reply.is_removed,
+        pool_type: PoolTypeReply::ConstantProduct,
     };
 
     // Create accounts for Kong backend and empty (for testing)
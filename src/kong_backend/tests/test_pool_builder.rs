@@ -467,6 +467,8 @@ impl TestSetup {
             amount_1: token_b_amount,
             tx_id_1: tx_id_b.map(TxId::BlockIndex),
             lp_fee_bps: config.lp_fee_bps,
+            tick_spacing: None,
+            amp: None,
         };
         
         let add_pool_payload = encode_one(&add_pool_args).expect("Failed to encode add_pool_args");
@@ -654,6 +656,8 @@ pub fn run_pool_test(config: PoolTestConfig) -> Result<()> {
             amount_1: setup.token_b_liquidity_amount.clone(),
             tx_id_1: other_user_tx_id_b.map(TxId::BlockIndex), // Use other user's tx ID
             lp_fee_bps: None,
+            tick_spacing: None,
+            amp: None,
         };
         
         let add_pool_payload = encode_one(&add_pool_args).expect("Failed to encode add_pool_args");
@@ -889,6 +893,8 @@ fn test_add_pool_with_other_user_tx_id() {
         amount_1: token_b_amount,
         tx_id_1: Some(TxId::BlockIndex(tx_id_b)),  // Using OTHER user's tx ID
         lp_fee_bps: None,
+        tick_spacing: None,
+        amp: None,
     };
     
     let add_pool_payload = encode_one(&add_pool_args).expect("Failed to encode add_pool_args");
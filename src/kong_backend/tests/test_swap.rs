@@ -107,6 +107,8 @@ fn test_swap_approve_transfer_from_a_to_b() {
         receive_address: Some(user_principal.to_text()),             // Explicitly set receive address
         max_slippage: Some(50.0),                                    // Explicitly allow up to 50% slippage for this test
         referred_by: None,
+        use_deposit: None,
+        deadline: None,
     };
     let swap_payload_approve = encode_one(&swap_args_approve).expect("Failed to encode swap_args_approve ");
 
@@ -286,6 +288,8 @@ fn test_swap_direct_transfer_a_to_b() {
         receive_address: Some(user_principal.to_text()),           // Explicitly set receive address
         max_slippage: Some(50.0),                                  // Explicitly allow up to 50% slippage
         referred_by: None,
+        use_deposit: None,
+        deadline: None,
     };
     let swap_payload_direct_a = encode_one(&swap_args_direct_a).expect("Failed to encode swap_args_direct_a ");
 
@@ -453,6 +457,8 @@ fn test_swap_direct_transfer_b_to_a() {
         receive_address: Some(user_principal.to_text()),        // Explicitly set receive address
         max_slippage: Some(50.0),                               // Explicitly allow up to 50% slippage
         referred_by: None,
+        use_deposit: None,
+        deadline: None,
     };
     let swap_payload_direct_b = encode_one(&swap_args_direct_b).expect("Failed to encode swap_args_direct_b ");
 
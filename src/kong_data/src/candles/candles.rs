@@ -0,0 +1,34 @@
+use ic_cdk::query;
+use std::str::FromStr;
+
+use super::candles_reply::CandleReply;
+use super::candles_reply_helpers::to_candle_reply;
+
+use crate::ic::get_time::get_time;
+use crate::ic::guards::not_in_maintenance_mode;
+use crate::stable_candle::candle_map;
+use crate::stable_candle::stable_candle::CandleInterval;
+use crate::stable_pool::pool_map;
+
+const MAX_CANDLES: usize = 1_000;
+
+/// OHLCV candles of the pool from the one containing from to the one containing to, oldest first
+/// - interval is 1m, 5m, 1h or 1d
+/// - to defaults to now, from defaults to MAX_CANDLES intervals before to
+/// - returns at most MAX_CANDLES. for the next page, call again with from = start_ts + interval duration of the last
+///   candle. from is rounded down to the start of its candle, so start_ts + 1 would return the last candle again
+/// - intervals without swaps have no candle. 1m candles are only kept for 7 days
+#[query(guard = "not_in_maintenance_mode")]
+fn candles(pool: String, interval: String, from: Option<u64>, to: Option<u64>) -> Result<Vec<CandleReply>, String> {
+    let pool = pool_map::get_by_symbol(&pool)?;
+    let interval = CandleInterval::from_str(&interval)?;
+    let to = to.unwrap_or_else(get_time);
+    let from = from.unwrap_or_else(|| to.saturating_sub(interval.duration() * (MAX_CANDLES as u64 - 1)));
+
+    let candles = candle_map::get(pool.pool_id, interval, from, to, MAX_CANDLES)
+        .iter()
+        .map(to_candle_reply)
+        .collect();
+
+    Ok(candles)
+}
@@ -0,0 +1,16 @@
+use super::candles_reply::CandleReply;
+
+use crate::stable_candle::stable_candle::StableCandle;
+
+pub fn to_candle_reply(candle: &StableCandle) -> CandleReply {
+    CandleReply {
+        start_ts: candle.start_ts,
+        open: candle.open,
+        high: candle.high,
+        low: candle.low,
+        close: candle.close,
+        volume_0: candle.volume_0.clone(),
+        volume_1: candle.volume_1.clone(),
+        num_swaps: candle.num_swaps,
+    }
+}
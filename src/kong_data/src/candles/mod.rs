@@ -0,0 +1,5 @@
+#[allow(clippy::module_inception)]
+pub mod candles;
+pub mod candles_reply;
+pub mod candles_reply_helpers;
+pub mod update_candles;
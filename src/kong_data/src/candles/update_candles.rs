@@ -0,0 +1,96 @@
+use num_traits::Zero;
+use std::cell::Cell;
+
+use crate::helpers::math_helpers::price_rounded;
+use crate::helpers::nat_helpers::nat_zero;
+use crate::ic::get_time::get_time;
+use crate::stable_candle::candle_map;
+use crate::stable_memory::TX_MAP;
+use crate::stable_pool::{pool_map, stable_pool::StablePool};
+use crate::stable_token_volume::token_volume_map;
+use crate::stable_tx::{stable_tx::StableTx, stable_tx::StableTxId, status_tx::StatusTx, tx::Tx};
+use crate::swap::swap_calc::SwapCalc;
+
+thread_local! {
+    // next tx_id of a running backfill. txs from it are added to the candles by the backfill, not when they are replicated in
+    // kept on the heap, so a backfill interrupted by an upgrade must be restarted
+    static BACKFILL_TX_ID: Cell<Option<u64>> = const { Cell::new(None) };
+}
+
+/// add a tx to the candles when it is first replicated in, unless a running backfill will add it
+pub fn add_tx(tx: &StableTx) {
+    if BACKFILL_TX_ID.get().is_some_and(|backfill_tx_id| tx.tx_id() >= backfill_tx_id) {
+        return;
+    }
+    update_candles(tx);
+}
+
+/// rebuild the candles and token volumes from the txs in TX_MAP, num_txs at a time
+/// - tx_id None clears the candles and token volumes and starts from the first tx
+/// - otherwise continues from tx_id, which must be the next tx_id returned by the previous call
+/// - returns the next tx_id, or None once every tx is added
+pub fn backfill_candles(tx_id: Option<u64>, num_txs: usize) -> Option<u64> {
+    let start_tx_id = match tx_id {
+        Some(tx_id) => tx_id,
+        None => {
+            candle_map::clear();
+            token_volume_map::clear();
+            0
+        }
+    };
+    let txs: Vec<StableTx> = TX_MAP.with(|m| {
+        m.borrow()
+            .range(StableTxId(start_tx_id)..)
+            .take(num_txs + 1)
+            .map(|(_, v)| v)
+            .collect()
+    });
+    let next_tx_id = txs.get(num_txs).map(|tx| tx.tx_id());
+    txs.iter().take(num_txs).for_each(update_candles);
+    BACKFILL_TX_ID.set(next_tx_id);
+    next_tx_id
+}
+
+/// add the swaps of a tx to the candles of their pools and the daily volumes of their tokens
+/// must only be called once for each tx
+fn update_candles(tx: &StableTx) {
+    let StableTx::Swap(swap_tx) = tx else {
+        return;
+    };
+    if swap_tx.status != StatusTx::Success {
+        return;
+    }
+
+    // a multi-hop or split swap is a swap through each of its pools
+    for swap in &swap_tx.txs {
+        let Some(pool) = pool_map::get_by_pool_id(swap.pool_id) else {
+            continue;
+        };
+        let Some(price) = get_price(&pool, swap) else {
+            continue;
+        };
+        // volumes are the amounts paid and received by the user
+        let receive_amount = swap.receive_amount_with_fees_and_gas();
+        let (amount_0, amount_1) = if swap.pay_token_id == pool.token_id_0 {
+            (&swap.pay_amount, &receive_amount)
+        } else {
+            (&receive_amount, &swap.pay_amount)
+        };
+        candle_map::add_swap(pool.pool_id, price, amount_0, amount_1, swap_tx.ts, get_time());
+        token_volume_map::add_swap(swap.pay_token_id, &swap.pay_amount, &nat_zero(), swap_tx.ts);
+        token_volume_map::add_swap(swap.receive_token_id, &receive_amount, &swap.lp_fee, swap_tx.ts);
+    }
+}
+
+/// price of the swap as token_0 in token_1, the same as the pool's price
+fn get_price(pool: &StablePool, swap: &SwapCalc) -> Option<f64> {
+    let price = swap.get_price()?;
+    if price.is_zero() {
+        None?
+    }
+    if swap.pay_token_id == pool.token_id_0 {
+        price_rounded(&price)
+    } else {
+        price_rounded(&price.recip())
+    }
+}
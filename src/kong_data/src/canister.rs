@@ -6,6 +6,7 @@ use serde::Deserialize;
 
 use super::{APP_NAME, APP_VERSION};
 
+use crate::candles::candles_reply::CandleReply;
 use crate::ic::id::caller_principal_id;
 use crate::ic::logging::info_log;
 use crate::stable_db_update::db_update_map::{max_db_update_id, DB_UPDATE_ID};
@@ -13,7 +14,18 @@ use crate::stable_user::principal_id_map::create_principal_id_map;
 
 // list of query calls
 // a bit hard-coded but shouldn't change often
-static QUERY_METHODS: [&str; 5] = ["icrc1_name", "icrc10_supported_standards", "tokens", "pools", "txs"];
+static QUERY_METHODS: [&str; 10] = [
+    "icrc1_name",
+    "icrc10_supported_standards",
+    "tokens",
+    "token_volumes",
+    "pools",
+    "pool_stats",
+    "candles",
+    "txs",
+    "db_updates",
+    "db_update_consumers",
+];
 
 #[init]
 fn init() {
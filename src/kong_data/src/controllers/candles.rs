@@ -0,0 +1,17 @@
+use ic_cdk::update;
+
+use crate::candles::update_candles;
+use crate::ic::guards::caller_is_kingkong;
+
+const MAX_TXS: usize = 1_000;
+
+/// rebuild the candles and token volumes from the txs already replicated, e.g. the txs from before candles were added
+/// call first without tx_id, then with the returned next tx_id until all txs are added
+#[update(hidden = true, guard = "caller_is_kingkong")]
+fn backfill_candles(tx_id: Option<u64>, num_txs: Option<u16>) -> Result<String, String> {
+    let num_txs = num_txs.map_or(MAX_TXS, |n| n as usize);
+    match update_candles::backfill_candles(tx_id, num_txs) {
+        Some(next_tx_id) => Ok(format!("Candles backfilled. Next tx_id #{}", next_tx_id)),
+        None => Ok("Candles backfilled".to_string()),
+    }
+}
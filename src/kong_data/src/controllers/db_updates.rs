@@ -1,5 +1,6 @@
 use ic_cdk::{query, update};
 
+use crate::stable_db_update::db_update_map;
 use crate::stable_db_update::stable_db_update::{StableDBUpdate, StableDBUpdateId};
 use crate::stable_memory::DB_UPDATE_MAP;
 
@@ -26,20 +27,10 @@ fn backup_db_updates(db_update_id: Option<u64>) -> Result<String, String> {
     })
 }
 
+/// remove db updates older than ts. db updates not yet acknowledged by every registered consumer are kept
 #[update(hidden = true)]
 fn remove_db_updates(ts: u64) -> Result<String, String> {
-    DB_UPDATE_MAP.with(|m| {
-        let mut map = m.borrow_mut();
-        let keys_to_remove: Vec<_> = map
-            .iter()
-            .filter(|(_, v)| v.ts < ts)
-            .map(|(k, _)| k)
-            .take(MAX_REMOVE_DB_UPDATES)
-            .collect();
-        keys_to_remove.iter().for_each(|k| {
-            map.remove(k);
-        });
-    });
+    db_update_map::remove(Some(ts), MAX_REMOVE_DB_UPDATES);
 
     Ok("DB updates removed".to_string())
 }
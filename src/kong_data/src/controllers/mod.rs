@@ -1,3 +1,4 @@
+mod candles;
 mod claims;
 mod db_updates;
 mod event_store;
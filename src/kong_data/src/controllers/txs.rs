@@ -1,6 +1,7 @@
 use ic_cdk::{query, update};
 use std::collections::BTreeMap;
 
+use crate::candles::update_candles::add_tx;
 use crate::ic::get_time::get_time;
 use crate::ic::guards::{caller_is_kingkong, caller_is_kong_backend};
 use crate::stable_db_update::db_update_map;
@@ -45,7 +46,10 @@ fn update_txs(stable_txs_json: String) -> Result<String, String> {
     TX_MAP.with(|tx_map| {
         let mut map = tx_map.borrow_mut();
         for (k, v) in txs {
-            map.insert(k, v);
+            // only new txs are added to the candles, so txs can be replicated again
+            if map.insert(k, v.clone()).is_none() {
+                add_tx(&v);
+            }
         }
     });
 
@@ -61,7 +65,9 @@ fn update_tx(stable_tx_json: String) -> Result<String, String> {
 
     TX_MAP.with(|tx_map| {
         let mut map = tx_map.borrow_mut();
-        map.insert(StableTxId(tx.tx_id()), tx.clone());
+        if map.insert(StableTxId(tx.tx_id()), tx.clone()).is_none() {
+            add_tx(&tx);
+        }
     });
 
     // add to UpdateMap for archiving to database
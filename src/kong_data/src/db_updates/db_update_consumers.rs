@@ -0,0 +1,44 @@
+use ic_cdk::{query, update};
+
+use super::db_updates_reply::DBUpdateConsumerReply;
+use super::db_updates_reply_helpers::to_db_update_consumer_reply;
+
+use crate::ic::guards::caller_is_kingkong;
+use crate::stable_db_update::db_update_consumer_map;
+use crate::stable_db_update::db_update_map;
+
+const MAX_REMOVE_DB_UPDATES: usize = 20_000;
+
+/// registered consumers of the db updates. lag is the number of db updates a consumer has not acknowledged
+#[query(hidden = true, guard = "caller_is_kingkong")]
+fn db_update_consumers() -> Result<Vec<DBUpdateConsumerReply>, String> {
+    Ok(db_update_consumer_map::get().iter().map(to_db_update_consumer_reply).collect())
+}
+
+/// register a consumer of the db updates. db updates are kept until every registered consumer has acknowledged them
+/// registering an existing consumer returns it unchanged
+#[update(hidden = true, guard = "caller_is_kingkong")]
+fn register_db_update_consumer(consumer_id: String) -> Result<DBUpdateConsumerReply, String> {
+    if consumer_id.is_empty() {
+        Err("Consumer id is empty".to_string())?
+    }
+    let consumer = db_update_consumer_map::register(&consumer_id);
+    Ok(to_db_update_consumer_reply(&consumer))
+}
+
+/// unregister a consumer. db updates it has not acknowledged may then be removed
+#[update(hidden = true, guard = "caller_is_kingkong")]
+fn unregister_db_update_consumer(consumer_id: String) -> Result<DBUpdateConsumerReply, String> {
+    let consumer = db_update_consumer_map::unregister(&consumer_id)?;
+    db_update_map::remove(None, MAX_REMOVE_DB_UPDATES);
+    Ok(to_db_update_consumer_reply(&consumer))
+}
+
+/// acknowledge that the consumer has processed all the db updates up to and including db_update_id
+/// db updates acknowledged by every registered consumer are removed
+#[update(hidden = true, guard = "caller_is_kingkong")]
+fn ack_db_updates(consumer_id: String, db_update_id: u64) -> Result<DBUpdateConsumerReply, String> {
+    let consumer = db_update_consumer_map::ack(&consumer_id, db_update_id)?;
+    db_update_map::remove(None, MAX_REMOVE_DB_UPDATES);
+    Ok(to_db_update_consumer_reply(&consumer))
+}
@@ -0,0 +1,24 @@
+use ic_cdk::query;
+
+use super::db_updates_reply::DBUpdatesReply;
+
+use crate::stable_db_update::db_update_map;
+
+const MAX_DB_UPDATES: usize = 1_000;
+
+/// db updates after cursor, oldest first
+/// - cursor is the last db_update_id the caller has processed. None starts from the oldest db update
+/// - returns at most limit db updates, up to MAX_DB_UPDATES. for the next page, call again with next_cursor
+/// - errors if db updates after cursor have already been removed
+#[query(hidden = true)]
+fn db_updates(cursor: Option<u64>, limit: Option<u32>) -> Result<DBUpdatesReply, String> {
+    let limit = limit.map_or(MAX_DB_UPDATES, |limit| (limit as usize).min(MAX_DB_UPDATES));
+    let (db_updates, has_more) = db_update_map::get(cursor, limit)?;
+    let next_cursor = db_updates.last().map_or(cursor.unwrap_or(0), |db_update| db_update.db_update_id);
+
+    Ok(DBUpdatesReply {
+        db_updates,
+        next_cursor,
+        has_more,
+    })
+}
@@ -0,0 +1,14 @@
+use super::db_updates_reply::DBUpdateConsumerReply;
+
+use crate::stable_db_update::db_update_map;
+use crate::stable_db_update::stable_db_update_consumer::StableDBUpdateConsumer;
+
+pub fn to_db_update_consumer_reply(consumer: &StableDBUpdateConsumer) -> DBUpdateConsumerReply {
+    DBUpdateConsumerReply {
+        consumer_id: consumer.consumer_id.clone(),
+        acked_db_update_id: consumer.acked_db_update_id,
+        registered_ts: consumer.registered_ts,
+        acked_ts: consumer.acked_ts,
+        lag: db_update_map::max_db_update_id().saturating_sub(consumer.acked_db_update_id),
+    }
+}
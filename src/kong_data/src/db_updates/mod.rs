@@ -0,0 +1,5 @@
+pub mod db_update_consumers;
+#[allow(clippy::module_inception)]
+pub mod db_updates;
+pub mod db_updates_reply;
+pub mod db_updates_reply_helpers;
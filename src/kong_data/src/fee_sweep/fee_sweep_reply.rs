@@ -0,0 +1,16 @@
+use candid::{CandidType, Nat};
+use serde::{Deserialize, Serialize};
+
+#[derive(CandidType, Debug, Clone, Serialize, Deserialize)]
+pub struct FeeSweepReply {
+    pub tx_id: u64,
+    pub request_id: u64,
+    pub status: String,
+    pub chain: String,
+    pub symbol: String,
+    pub amount: Nat,
+    pub treasury_amount: Nat,
+    pub buyback_amount: Nat,
+    pub burn_amount: Nat,
+    pub ts: u64,
+}
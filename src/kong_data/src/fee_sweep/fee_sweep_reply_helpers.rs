@@ -0,0 +1,23 @@
+use super::fee_sweep_reply::FeeSweepReply;
+
+use crate::stable_token::token::Token;
+use crate::stable_token::token_map;
+use crate::stable_tx::fee_sweep_tx::FeeSweepTx;
+
+pub fn to_fee_sweep_reply(fee_sweep_tx: &FeeSweepTx) -> FeeSweepReply {
+    let (chain, symbol) = token_map::get_by_token_id(fee_sweep_tx.token_id)
+        .map(|token| (token.chain(), token.symbol()))
+        .unwrap_or(("Token chain not found".to_string(), "Token symbol not found".to_string()));
+    FeeSweepReply {
+        tx_id: fee_sweep_tx.tx_id,
+        request_id: fee_sweep_tx.request_id,
+        status: fee_sweep_tx.status.to_string(),
+        chain,
+        symbol,
+        amount: fee_sweep_tx.amount.clone(),
+        treasury_amount: fee_sweep_tx.treasury_amount.clone(),
+        buyback_amount: fee_sweep_tx.buyback_amount.clone(),
+        burn_amount: fee_sweep_tx.burn_amount.clone(),
+        ts: fee_sweep_tx.ts,
+    }
+}
@@ -0,0 +1,2 @@
+pub mod fee_sweep_reply;
+pub mod fee_sweep_reply_helpers;
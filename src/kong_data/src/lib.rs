@@ -1,15 +1,19 @@
 mod add_liquidity;
 mod add_pool;
+mod candles;
 mod canister;
 mod chains;
 mod claims;
 mod controllers;
+mod db_updates;
+mod fee_sweep;
 mod helpers;
 mod ic;
 mod pools;
 mod remove_liquidity;
 mod requests;
 mod send;
+mod stable_candle;
 mod stable_claim;
 mod stable_db_update;
 mod stable_kong_settings;
@@ -18,6 +22,7 @@ mod stable_memory;
 mod stable_pool;
 mod stable_request;
 mod stable_token;
+mod stable_token_volume;
 mod stable_transfer;
 mod stable_tx;
 mod stable_user;
@@ -1,3 +1,5 @@
+pub mod pool_stats;
+pub mod pool_stats_reply;
 #[allow(clippy::module_inception)]
 pub mod pools;
 pub mod pools_reply;
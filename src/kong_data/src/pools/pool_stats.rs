@@ -0,0 +1,51 @@
+use ic_cdk::query;
+
+use super::pool_stats_reply::PoolStatsReply;
+
+use crate::helpers::math_helpers::round_f64;
+use crate::helpers::nat_helpers::{nat_add, nat_zero};
+use crate::ic::get_time::get_time;
+use crate::ic::guards::not_in_maintenance_mode;
+use crate::stable_candle::candle_map;
+use crate::stable_candle::stable_candle::CandleInterval;
+use crate::stable_pool::pool_map;
+
+/// 24 hour stats of the pool from its 5 minute candles
+#[query(guard = "not_in_maintenance_mode")]
+fn pool_stats(pool: String) -> Result<PoolStatsReply, String> {
+    let pool = pool_map::get_by_symbol(&pool)?;
+    let ts = get_time();
+    let candles = candle_map::get(
+        pool.pool_id,
+        CandleInterval::FiveMinutes,
+        ts.saturating_sub(CandleInterval::OneDay.duration()),
+        ts,
+        usize::MAX,
+    );
+
+    let price = pool.get_price_as_f64().unwrap_or(0_f64);
+    let open_24h = candles.first().map_or(price, |candle| candle.open);
+    let close_24h = candles.last().map_or(price, |candle| candle.close);
+    let price_change_24h_pct = if open_24h > 0_f64 {
+        round_f64((close_24h - open_24h) / open_24h * 100_f64, 2)
+    } else {
+        0_f64
+    };
+
+    Ok(PoolStatsReply {
+        pool_id: pool.pool_id,
+        symbol: pool.symbol(),
+        balance_0: pool.balance_0.clone(),
+        balance_1: pool.balance_1.clone(),
+        price,
+        open_24h,
+        high_24h: candles.iter().map(|candle| candle.high).reduce(f64::max).unwrap_or(price),
+        low_24h: candles.iter().map(|candle| candle.low).reduce(f64::min).unwrap_or(price),
+        close_24h,
+        price_change_24h_pct,
+        volume_0_24h: candles.iter().fold(nat_zero(), |acc, candle| nat_add(&acc, &candle.volume_0)),
+        volume_1_24h: candles.iter().fold(nat_zero(), |acc, candle| nat_add(&acc, &candle.volume_1)),
+        num_swaps_24h: candles.iter().map(|candle| candle.num_swaps).sum(),
+        ts,
+    })
+}
@@ -0,0 +1,20 @@
+use candid::{CandidType, Nat};
+use serde::{Deserialize, Serialize};
+
+#[derive(CandidType, Debug, Clone, Serialize, Deserialize)]
+pub struct PoolStatsReply {
+    pub pool_id: u32,
+    pub symbol: String,
+    pub balance_0: Nat,
+    pub balance_1: Nat,
+    pub price: f64,    // current price of the pool
+    pub open_24h: f64, // first swap price of the last 24 hours
+    pub high_24h: f64,
+    pub low_24h: f64,
+    pub close_24h: f64,            // last swap price of the last 24 hours
+    pub price_change_24h_pct: f64, // change from open_24h to close_24h in percent
+    pub volume_0_24h: Nat,
+    pub volume_1_24h: Nat,
+    pub num_swaps_24h: u64,
+    pub ts: u64,
+}
@@ -26,6 +26,8 @@ pub struct RemoveLiquidityReply {
     pub remove_lp_token_amount: Nat,
     pub transfer_ids: Vec<TransferIdReply>,
     pub claim_ids: Vec<u64>,
+    #[serde(default)]
+    pub to_address: Option<String>, // resolved destination of token_0 and token_1
     pub ts: u64,
 }
 
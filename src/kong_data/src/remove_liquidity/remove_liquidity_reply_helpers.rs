@@ -51,6 +51,7 @@ pub fn to_remove_liquidity_reply(remove_liquidity_tx: &RemoveLiquidityTx) -> Rem
         remove_lp_token_amount: remove_liquidity_tx.remove_lp_token_amount.clone(),
         transfer_ids: to_transfer_ids(&remove_liquidity_tx.transfer_ids),
         claim_ids: remove_liquidity_tx.claim_ids.clone(),
+        to_address: remove_liquidity_tx.to_address.as_ref().map(|address| address.to_string()),
         ts: remove_liquidity_tx.ts,
     }
 }
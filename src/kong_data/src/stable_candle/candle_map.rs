@@ -0,0 +1,143 @@
+use candid::Nat;
+
+use super::stable_candle::{CandleInterval, StableCandle, StableCandleId};
+
+use crate::stable_memory::CANDLE_MAP;
+
+// max number of candles removed at a time when they are past their retention
+const MAX_REMOVE_CANDLES: usize = 100;
+
+/// add a swap through the pool to its candle of every interval, then remove the pool's candles past their retention
+/// candles that would already be past their retention at now are not added, e.g. when old txs are backfilled
+pub fn add_swap(pool_id: u32, price: f64, amount_0: &Nat, amount_1: &Nat, ts: u64, now: u64) {
+    CANDLE_MAP.with(|m| {
+        let mut map = m.borrow_mut();
+        for interval in CandleInterval::ALL {
+            if interval.retention().is_some_and(|retention| ts < now.saturating_sub(retention)) {
+                continue;
+            }
+            let candle_id = StableCandleId {
+                pool_id,
+                interval,
+                start_ts: interval.start_ts(ts),
+            };
+            let candle = match map.get(&candle_id) {
+                Some(mut candle) => {
+                    candle.add_swap(price, amount_0, amount_1, ts);
+                    candle
+                }
+                None => StableCandle::new(pool_id, interval, price, amount_0, amount_1, ts),
+            };
+            map.insert(candle_id, candle);
+        }
+    });
+    remove_expired(pool_id, now);
+}
+
+/// remove the candles of the pool past the retention of their interval, at most MAX_REMOVE_CANDLES of each interval
+pub fn remove_expired(pool_id: u32, now: u64) {
+    for interval in CandleInterval::ALL {
+        let Some(retention) = interval.retention() else {
+            continue;
+        };
+        let start_id = StableCandleId {
+            pool_id,
+            interval,
+            start_ts: 0,
+        };
+        let end_id = StableCandleId {
+            pool_id,
+            interval,
+            start_ts: interval.start_ts(now.saturating_sub(retention)),
+        };
+        CANDLE_MAP.with(|m| {
+            let mut map = m.borrow_mut();
+            let expired_ids: Vec<StableCandleId> = map.range(start_id..end_id).take(MAX_REMOVE_CANDLES).map(|(k, _)| k).collect();
+            for candle_id in expired_ids {
+                map.remove(&candle_id);
+            }
+        });
+    }
+}
+
+/// remove all candles, before they are backfilled
+pub fn clear() {
+    CANDLE_MAP.with(|m| m.borrow_mut().clear_new());
+}
+
+/// candles of the pool from the one containing start_ts to the one containing end_ts, oldest first
+pub fn get(pool_id: u32, interval: CandleInterval, start_ts: u64, end_ts: u64, num_candles: usize) -> Vec<StableCandle> {
+    if start_ts > end_ts {
+        return Vec::new();
+    }
+    let start_id = StableCandleId {
+        pool_id,
+        interval,
+        start_ts: interval.start_ts(start_ts),
+    };
+    let end_id = StableCandleId {
+        pool_id,
+        interval,
+        start_ts: end_ts,
+    };
+    CANDLE_MAP.with(|m| m.borrow().range(start_id..=end_id).take(num_candles).map(|(_, v)| v).collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const NOW: u64 = 1_706_745_599_500_000_000;
+
+    #[test]
+    fn test_get_pages() {
+        let pool_id = 1;
+        let interval = CandleInterval::OneHour;
+        let amount = Nat::from(1_u32);
+        let from = NOW - 10 * interval.duration();
+        for hour in 0..10 {
+            add_swap(pool_id, 1.0 + hour as f64, &amount, &amount, from + hour * interval.duration(), NOW);
+        }
+
+        // page through 3 candles at a time, the next page starts one interval after the last candle
+        let mut start_ts = from;
+        let mut pages = Vec::new();
+        loop {
+            let candles = get(pool_id, interval, start_ts, NOW, 3);
+            let Some(last_candle) = candles.last() else {
+                break;
+            };
+            start_ts = last_candle.start_ts + interval.duration();
+            pages.push(candles.iter().map(|candle| candle.close).collect::<Vec<_>>());
+        }
+        assert_eq!(
+            pages,
+            vec![vec![1.0, 2.0, 3.0], vec![4.0, 5.0, 6.0], vec![7.0, 8.0, 9.0], vec![10.0]]
+        );
+
+        // start_ts + 1 is in the last candle, which is returned again
+        let candles = get(pool_id, interval, from, NOW, 3);
+        let candles_after = get(pool_id, interval, candles[2].start_ts + 1, NOW, 3);
+        assert_eq!(candles_after[0].start_ts, candles[2].start_ts);
+    }
+
+    #[test]
+    fn test_one_minute_retention() {
+        let pool_id = 2;
+        let amount = Nat::from(1_u32);
+        let retention = CandleInterval::OneMinute.retention().unwrap();
+        let one_minute_candles = |now| get(pool_id, CandleInterval::OneMinute, 0, now, usize::MAX).len();
+
+        // swaps already past the retention only add to the longer intervals
+        add_swap(pool_id, 1.0, &amount, &amount, NOW - retention - 1, NOW);
+        assert_eq!(one_minute_candles(NOW), 0);
+        assert_eq!(get(pool_id, CandleInterval::OneDay, 0, NOW, usize::MAX).len(), 1);
+
+        // 1m candles are removed once they are past the retention, the other intervals are kept
+        add_swap(pool_id, 1.0, &amount, &amount, NOW, NOW);
+        assert_eq!(one_minute_candles(NOW), 1);
+        remove_expired(pool_id, NOW + retention + CandleInterval::OneMinute.duration());
+        assert_eq!(one_minute_candles(NOW), 0);
+        assert_eq!(get(pool_id, CandleInterval::FiveMinutes, 0, NOW, usize::MAX).len(), 2);
+    }
+}
@@ -0,0 +1,3 @@
+pub mod candle_map;
+#[allow(clippy::module_inception)]
+pub mod stable_candle;
@@ -0,0 +1,188 @@
+use candid::{CandidType, Nat};
+use ic_stable_structures::{storable::Bound, Storable};
+use serde::{Deserialize, Serialize};
+use std::str::FromStr;
+
+use crate::helpers::nat_helpers::nat_add;
+
+const ONE_MINUTE_NS: u64 = 60 * 1_000_000_000;
+
+#[derive(CandidType, Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+pub enum CandleInterval {
+    OneMinute,
+    FiveMinutes,
+    OneHour,
+    OneDay,
+}
+
+impl CandleInterval {
+    pub const ALL: [CandleInterval; 4] = [
+        CandleInterval::OneMinute,
+        CandleInterval::FiveMinutes,
+        CandleInterval::OneHour,
+        CandleInterval::OneDay,
+    ];
+
+    /// length of the interval in nanoseconds
+    pub fn duration(&self) -> u64 {
+        match self {
+            CandleInterval::OneMinute => ONE_MINUTE_NS,
+            CandleInterval::FiveMinutes => 5 * ONE_MINUTE_NS,
+            CandleInterval::OneHour => 60 * ONE_MINUTE_NS,
+            CandleInterval::OneDay => 24 * 60 * ONE_MINUTE_NS,
+        }
+    }
+
+    /// how long the candles of the interval are kept. None is forever
+    pub fn retention(&self) -> Option<u64> {
+        match self {
+            CandleInterval::OneMinute => Some(7 * CandleInterval::OneDay.duration()),
+            _ => None,
+        }
+    }
+
+    /// start of the candle the timestamp falls in. candles are aligned to the Unix epoch (UTC)
+    pub fn start_ts(&self, ts: u64) -> u64 {
+        ts - ts % self.duration()
+    }
+}
+
+impl FromStr for CandleInterval {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "1m" => Ok(CandleInterval::OneMinute),
+            "5m" => Ok(CandleInterval::FiveMinutes),
+            "1h" => Ok(CandleInterval::OneHour),
+            "1d" => Ok(CandleInterval::OneDay),
+            _ => Err(format!("Invalid interval {}. Must be 1m, 5m, 1h or 1d", s)),
+        }
+    }
+}
+
+impl std::fmt::Display for CandleInterval {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            CandleInterval::OneMinute => write!(f, "1m"),
+            CandleInterval::FiveMinutes => write!(f, "5m"),
+            CandleInterval::OneHour => write!(f, "1h"),
+            CandleInterval::OneDay => write!(f, "1d"),
+        }
+    }
+}
+
+// field order is the sort order. candles of a pool and interval are sorted by start time
+#[derive(CandidType, Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+pub struct StableCandleId {
+    pub pool_id: u32,
+    pub interval: CandleInterval,
+    pub start_ts: u64,
+}
+
+impl Storable for StableCandleId {
+    fn to_bytes(&self) -> std::borrow::Cow<'_, [u8]> {
+        serde_cbor::to_vec(self).unwrap().into()
+    }
+
+    fn from_bytes(bytes: std::borrow::Cow<'_, [u8]>) -> Self {
+        serde_cbor::from_slice(&bytes).unwrap()
+    }
+
+    const BOUND: Bound = Bound::Unbounded;
+}
+
+/// OHLCV candle of a pool. prices are of token_0 in token_1, volumes are in each token of the pool
+#[derive(CandidType, Debug, Clone, Serialize, Deserialize)]
+pub struct StableCandle {
+    pub pool_id: u32,
+    pub interval: CandleInterval,
+    pub start_ts: u64, // start of the candle
+    pub open: f64,
+    pub high: f64,
+    pub low: f64,
+    pub close: f64,
+    pub volume_0: Nat,
+    pub volume_1: Nat,
+    pub num_swaps: u64,
+    pub open_ts: u64,  // timestamp of the swap of the open price
+    pub close_ts: u64, // timestamp of the swap of the close price
+}
+
+impl StableCandle {
+    pub fn new(pool_id: u32, interval: CandleInterval, price: f64, amount_0: &Nat, amount_1: &Nat, ts: u64) -> Self {
+        Self {
+            pool_id,
+            interval,
+            start_ts: interval.start_ts(ts),
+            open: price,
+            high: price,
+            low: price,
+            close: price,
+            volume_0: amount_0.clone(),
+            volume_1: amount_1.clone(),
+            num_swaps: 1,
+            open_ts: ts,
+            close_ts: ts,
+        }
+    }
+
+    /// add a swap to the candle. swaps can be replicated out of order, so the open and close are by the timestamp of the swap
+    pub fn add_swap(&mut self, price: f64, amount_0: &Nat, amount_1: &Nat, ts: u64) {
+        if ts < self.open_ts {
+            self.open = price;
+            self.open_ts = ts;
+        }
+        if ts >= self.close_ts {
+            self.close = price;
+            self.close_ts = ts;
+        }
+        self.high = self.high.max(price);
+        self.low = self.low.min(price);
+        self.volume_0 = nat_add(&self.volume_0, amount_0);
+        self.volume_1 = nat_add(&self.volume_1, amount_1);
+        self.num_swaps += 1;
+    }
+}
+
+impl Storable for StableCandle {
+    fn to_bytes(&self) -> std::borrow::Cow<'_, [u8]> {
+        serde_cbor::to_vec(self).unwrap().into()
+    }
+
+    fn from_bytes(bytes: std::borrow::Cow<'_, [u8]>) -> Self {
+        serde_cbor::from_slice(&bytes).unwrap()
+    }
+
+    const BOUND: Bound = Bound::Unbounded;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_candle_start_ts() {
+        // 2024-01-31 23:59:59.5 UTC
+        let ts = 1_706_745_599_500_000_000;
+        assert_eq!(CandleInterval::OneMinute.start_ts(ts), 1_706_745_540_000_000_000);
+        assert_eq!(CandleInterval::FiveMinutes.start_ts(ts), 1_706_745_300_000_000_000);
+        assert_eq!(CandleInterval::OneHour.start_ts(ts), 1_706_742_000_000_000_000);
+        assert_eq!(CandleInterval::OneDay.start_ts(ts), 1_706_659_200_000_000_000);
+        assert_eq!(
+            CandleInterval::OneDay.start_ts(1_706_659_200_000_000_000),
+            1_706_659_200_000_000_000
+        );
+    }
+
+    #[test]
+    fn test_candle_add_swap_out_of_order() {
+        let amount = Nat::from(1_u32);
+        let mut candle = StableCandle::new(1, CandleInterval::OneMinute, 2.0, &amount, &amount, 20);
+        candle.add_swap(3.0, &amount, &amount, 30);
+        candle.add_swap(1.0, &amount, &amount, 10);
+        assert_eq!((candle.open, candle.high, candle.low, candle.close), (1.0, 3.0, 1.0, 3.0));
+        assert_eq!(candle.volume_0, Nat::from(3_u32));
+        assert_eq!(candle.num_swaps, 3);
+    }
+}
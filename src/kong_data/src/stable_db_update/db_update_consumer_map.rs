@@ -0,0 +1,119 @@
+use std::sync::atomic::Ordering;
+
+use super::db_update_map::{self, DB_UPDATE_ID};
+use super::stable_db_update_consumer::{StableDBUpdateConsumer, StableDBUpdateConsumerId};
+
+use crate::ic::get_time::get_time;
+use crate::stable_memory::DB_UPDATE_CONSUMER_MAP;
+
+pub fn get_by_consumer_id(consumer_id: &str) -> Option<StableDBUpdateConsumer> {
+    DB_UPDATE_CONSUMER_MAP.with(|m| m.borrow().get(&StableDBUpdateConsumerId(consumer_id.to_string())))
+}
+
+pub fn get() -> Vec<StableDBUpdateConsumer> {
+    DB_UPDATE_CONSUMER_MAP.with(|m| m.borrow().iter().map(|(_, v)| v).collect())
+}
+
+/// lowest db_update_id acknowledged by all the consumers. None if there are no consumers
+pub fn min_acked_db_update_id() -> Option<u64> {
+    DB_UPDATE_CONSUMER_MAP.with(|m| m.borrow().iter().map(|(_, v)| v.acked_db_update_id).min())
+}
+
+/// register a consumer. a new consumer has acknowledged none of the db updates still in memory
+/// registering an existing consumer returns it unchanged
+pub fn register(consumer_id: &str) -> StableDBUpdateConsumer {
+    if let Some(consumer) = get_by_consumer_id(consumer_id) {
+        return consumer;
+    }
+    let ts = get_time();
+    let consumer = StableDBUpdateConsumer {
+        consumer_id: consumer_id.to_string(),
+        acked_db_update_id: db_update_map::min_db_update_id().map_or(DB_UPDATE_ID.load(Ordering::SeqCst), |id| id - 1),
+        registered_ts: ts,
+        acked_ts: ts,
+    };
+    DB_UPDATE_CONSUMER_MAP.with(|m| {
+        m.borrow_mut()
+            .insert(StableDBUpdateConsumerId(consumer_id.to_string()), consumer.clone())
+    });
+    consumer
+}
+
+pub fn unregister(consumer_id: &str) -> Result<StableDBUpdateConsumer, String> {
+    DB_UPDATE_CONSUMER_MAP
+        .with(|m| m.borrow_mut().remove(&StableDBUpdateConsumerId(consumer_id.to_string())))
+        .ok_or(format!("Consumer {} not registered", consumer_id))
+}
+
+/// acknowledge all the db updates up to and including db_update_id. acknowledgements can only move forward
+pub fn ack(consumer_id: &str, db_update_id: u64) -> Result<StableDBUpdateConsumer, String> {
+    let mut consumer = get_by_consumer_id(consumer_id).ok_or(format!("Consumer {} not registered", consumer_id))?;
+    if db_update_id < consumer.acked_db_update_id {
+        Err(format!(
+            "Consumer {} already acknowledged db_update_id {}",
+            consumer_id, consumer.acked_db_update_id
+        ))?
+    }
+    let last_db_update_id = DB_UPDATE_ID.load(Ordering::SeqCst);
+    if db_update_id > last_db_update_id {
+        Err(format!(
+            "db_update_id {} is after the last db_update_id {}",
+            db_update_id, last_db_update_id
+        ))?
+    }
+    consumer.acked_db_update_id = db_update_id;
+    consumer.acked_ts = get_time();
+    DB_UPDATE_CONSUMER_MAP.with(|m| {
+        m.borrow_mut()
+            .insert(StableDBUpdateConsumerId(consumer_id.to_string()), consumer.clone())
+    });
+    Ok(consumer)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::stable_db_update::stable_db_update::{StableDBUpdate, StableMemory};
+    use crate::stable_kong_settings::stable_kong_settings::StableKongSettings;
+
+    fn insert_db_update() -> u64 {
+        db_update_map::insert(&StableDBUpdate {
+            db_update_id: 0,
+            stable_memory: StableMemory::KongSettings(StableKongSettings::default()),
+            ts: get_time(),
+        })
+    }
+
+    #[test]
+    fn test_register() {
+        let first_id = insert_db_update();
+        let last_id = insert_db_update();
+
+        // a new consumer has acknowledged none of the db updates in memory
+        let consumer = register("consumer");
+        assert_eq!(consumer.acked_db_update_id, first_id - 1);
+
+        // registering again returns the existing consumer unchanged
+        ack("consumer", last_id).unwrap();
+        let consumer = register("consumer");
+        assert_eq!(consumer.acked_db_update_id, last_id);
+        assert_eq!(get().len(), 1);
+    }
+
+    #[test]
+    fn test_ack() {
+        let first_id = insert_db_update();
+        let last_id = insert_db_update();
+        register("consumer");
+
+        let consumer = ack("consumer", last_id).unwrap();
+        assert_eq!(consumer.acked_db_update_id, last_id);
+        assert_eq!(min_acked_db_update_id(), Some(last_id));
+        // acknowledging the same db update again is allowed, going backwards is not
+        assert!(ack("consumer", last_id).is_ok());
+        assert!(ack("consumer", first_id).is_err());
+        assert!(ack("consumer", u64::MAX).is_err());
+        assert!(ack("unknown", last_id).is_err());
+        assert_eq!(get_by_consumer_id("consumer").unwrap().acked_db_update_id, last_id);
+    }
+}
@@ -1,6 +1,7 @@
 use crate::stable_memory::DB_UPDATE_MAP;
 use std::sync::atomic::{AtomicU64, Ordering};
 
+use super::db_update_consumer_map;
 use super::stable_db_update::{StableDBUpdate, StableDBUpdateId};
 
 pub static DB_UPDATE_ID: AtomicU64 = AtomicU64::new(0);
@@ -9,6 +10,11 @@ pub fn max_db_update_id() -> u64 {
     DB_UPDATE_MAP.with(|m| m.borrow().last_key_value().map_or(0, |(k, _)| k.0))
 }
 
+/// oldest db_update_id still in memory. None if there are no db updates
+pub fn min_db_update_id() -> Option<u64> {
+    DB_UPDATE_MAP.with(|m| m.borrow().first_key_value().map(|(k, _)| k.0))
+}
+
 pub fn insert(db_update: &StableDBUpdate) -> u64 {
     DB_UPDATE_MAP.with(|m| {
         let mut map = m.borrow_mut();
@@ -21,3 +27,130 @@ pub fn insert(db_update: &StableDBUpdate) -> u64 {
         db_update_id
     })
 }
+
+/// db updates after cursor, oldest first, and whether there are more after them
+/// - cursor is the last db_update_id the caller has processed. None starts from the oldest db update
+/// - errors if db updates after cursor have already been removed, as the caller would miss them
+pub fn get(cursor: Option<u64>, num_db_updates: usize) -> Result<(Vec<StableDBUpdate>, bool), String> {
+    if let (Some(cursor), Some(min_db_update_id)) = (cursor, min_db_update_id()) {
+        if cursor + 1 < min_db_update_id {
+            Err(format!(
+                "db_update_id {} has been removed. Oldest db_update_id is {}",
+                cursor + 1,
+                min_db_update_id
+            ))?
+        }
+    }
+    let start_id = StableDBUpdateId(cursor.map_or(0, |cursor| cursor + 1));
+    DB_UPDATE_MAP.with(|m| {
+        let mut db_updates = m
+            .borrow()
+            .range(start_id..)
+            .take(num_db_updates + 1)
+            .map(|(_, v)| v)
+            .collect::<Vec<StableDBUpdate>>();
+        let has_more = db_updates.len() > num_db_updates;
+        db_updates.truncate(num_db_updates);
+        Ok((db_updates, has_more))
+    })
+}
+
+/// remove the oldest db updates that every registered consumer has acknowledged and, if ts is given, are older than ts
+/// - without registered consumers only ts applies
+/// - the last db update is never removed so DB_UPDATE_ID can be restored from it on upgrade
+/// - returns the number of db updates removed
+pub fn remove(ts: Option<u64>, max_db_updates: usize) -> usize {
+    let acked_db_update_id = db_update_consumer_map::min_acked_db_update_id();
+    if acked_db_update_id.is_none() && ts.is_none() {
+        return 0;
+    }
+    let max_db_update_id = max_db_update_id();
+    DB_UPDATE_MAP.with(|m| {
+        let mut map = m.borrow_mut();
+        let keys_to_remove = map
+            .iter()
+            .take_while(|(k, v)| {
+                k.0 < max_db_update_id
+                    && acked_db_update_id.is_none_or(|acked_db_update_id| k.0 <= acked_db_update_id)
+                    && ts.is_none_or(|ts| v.ts < ts)
+            })
+            .take(max_db_updates)
+            .map(|(k, _)| k)
+            .collect::<Vec<StableDBUpdateId>>();
+        keys_to_remove.iter().for_each(|k| {
+            map.remove(k);
+        });
+        keys_to_remove.len()
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::stable_db_update::stable_db_update::StableMemory;
+    use crate::stable_kong_settings::stable_kong_settings::StableKongSettings;
+
+    fn insert_at(ts: u64) -> u64 {
+        insert(&StableDBUpdate {
+            db_update_id: 0,
+            stable_memory: StableMemory::KongSettings(StableKongSettings::default()),
+            ts,
+        })
+    }
+
+    fn db_update_ids(db_updates: &[StableDBUpdate]) -> Vec<u64> {
+        db_updates.iter().map(|db_update| db_update.db_update_id).collect()
+    }
+
+    #[test]
+    fn test_get_cursor() {
+        let ids = (1..=3).map(insert_at).collect::<Vec<_>>();
+
+        let (db_updates, has_more) = get(None, 2).unwrap();
+        assert_eq!(db_update_ids(&db_updates), ids[..2]);
+        assert!(has_more);
+        let (db_updates, has_more) = get(Some(ids[1]), 2).unwrap();
+        assert_eq!(db_update_ids(&db_updates), ids[2..]);
+        assert!(!has_more);
+
+        // without consumers only ts applies. the db updates older than ts 3 are removed
+        assert_eq!(remove(Some(3), 10), 2);
+        assert_eq!(min_db_update_id(), Some(ids[2]));
+        // a cursor behind the oldest db update would miss the removed ones
+        assert!(get(Some(ids[0]), 2).is_err());
+        let (db_updates, _) = get(Some(ids[1]), 2).unwrap();
+        assert_eq!(db_update_ids(&db_updates), ids[2..]);
+    }
+
+    #[test]
+    fn test_remove_keeps_last() {
+        let ids = (1..=2).map(insert_at).collect::<Vec<_>>();
+
+        // without consumers or ts nothing is removed
+        assert_eq!(remove(None, 10), 0);
+        assert_eq!(remove(Some(u64::MAX), 10), 1);
+        assert_eq!(remove(Some(u64::MAX), 10), 0);
+        assert_eq!(min_db_update_id(), Some(ids[1]));
+        assert_eq!(max_db_update_id(), ids[1]);
+    }
+
+    #[test]
+    fn test_consumer_blocks_remove() {
+        let ids = (1..=3).map(insert_at).collect::<Vec<_>>();
+        db_update_consumer_map::register("fast");
+        db_update_consumer_map::register("slow");
+
+        db_update_consumer_map::ack("fast", ids[2]).unwrap();
+        assert_eq!(remove(None, 10), 0);
+        assert_eq!(remove(Some(u64::MAX), 10), 0);
+
+        db_update_consumer_map::ack("slow", ids[0]).unwrap();
+        assert_eq!(remove(None, 10), 1);
+        assert_eq!(min_db_update_id(), Some(ids[1]));
+
+        // once the slow consumer is unregistered only the fast one applies, which still keeps the last db update
+        db_update_consumer_map::unregister("slow").unwrap();
+        assert_eq!(remove(None, 10), 1);
+        assert_eq!(min_db_update_id(), Some(ids[2]));
+    }
+}
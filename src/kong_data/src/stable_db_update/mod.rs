@@ -1,3 +1,5 @@
+pub mod db_update_consumer_map;
 pub mod db_update_map;
 #[allow(clippy::module_inception)]
 pub mod stable_db_update;
+pub mod stable_db_update_consumer;
@@ -0,0 +1,39 @@
+use candid::CandidType;
+use ic_stable_structures::{storable::Bound, Storable};
+use serde::{Deserialize, Serialize};
+
+#[derive(CandidType, Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+pub struct StableDBUpdateConsumerId(pub String);
+
+impl Storable for StableDBUpdateConsumerId {
+    fn to_bytes(&self) -> std::borrow::Cow<'_, [u8]> {
+        serde_cbor::to_vec(self).unwrap().into()
+    }
+
+    fn from_bytes(bytes: std::borrow::Cow<'_, [u8]>) -> Self {
+        serde_cbor::from_slice(&bytes).unwrap()
+    }
+
+    const BOUND: Bound = Bound::Unbounded;
+}
+
+/// a registered reader of the db updates. db updates are only removed once every consumer has acknowledged them
+#[derive(CandidType, Debug, Clone, Serialize, Deserialize)]
+pub struct StableDBUpdateConsumer {
+    pub consumer_id: String,
+    pub acked_db_update_id: u64, // last db_update_id the consumer has processed
+    pub registered_ts: u64,
+    pub acked_ts: u64,
+}
+
+impl Storable for StableDBUpdateConsumer {
+    fn to_bytes(&self) -> std::borrow::Cow<'_, [u8]> {
+        serde_cbor::to_vec(self).unwrap().into()
+    }
+
+    fn from_bytes(bytes: std::borrow::Cow<'_, [u8]>) -> Self {
+        serde_cbor::from_slice(&bytes).unwrap()
+    }
+
+    const BOUND: Bound = Bound::Unbounded;
+}
@@ -3,13 +3,16 @@ use ic_stable_structures::{DefaultMemoryImpl, StableBTreeMap, StableCell};
 use std::cell::RefCell;
 use std::collections::BTreeMap;
 
+use crate::stable_candle::stable_candle::{StableCandle, StableCandleId};
 use crate::stable_claim::stable_claim::{StableClaim, StableClaimId};
 use crate::stable_db_update::stable_db_update::{StableDBUpdate, StableDBUpdateId};
+use crate::stable_db_update::stable_db_update_consumer::{StableDBUpdateConsumer, StableDBUpdateConsumerId};
 use crate::stable_kong_settings::stable_kong_settings::StableKongSettings;
 use crate::stable_lp_token::stable_lp_token::{StableLPToken, StableLPTokenId};
 use crate::stable_pool::stable_pool::{StablePool, StablePoolId};
 use crate::stable_request::stable_request::{StableRequest, StableRequestId};
 use crate::stable_token::stable_token::{StableToken, StableTokenId};
+use crate::stable_token_volume::stable_token_volume::{StableTokenVolume, StableTokenVolumeId};
 use crate::stable_transfer::stable_transfer::{StableTransfer, StableTransferId};
 use crate::stable_tx::stable_tx::{StableTx, StableTxId};
 use crate::stable_user::stable_user::{StableUser, StableUserId};
@@ -25,8 +28,11 @@ pub const REQUEST_MEMORY_ID: MemoryId = MemoryId::new(5);
 pub const TRANSFER_MEMORY_ID: MemoryId = MemoryId::new(6);
 pub const CLAIM_MEMORY_ID: MemoryId = MemoryId::new(7);
 pub const LP_TOKEN_MEMORY_ID: MemoryId = MemoryId::new(8);
+pub const CANDLE_MEMORY_ID: MemoryId = MemoryId::new(9);
+pub const TOKEN_VOLUME_MEMORY_ID: MemoryId = MemoryId::new(10);
 
 pub const DB_UPDATE_MEMORY_ID: MemoryId = MemoryId::new(50);
+pub const DB_UPDATE_CONSUMER_MEMORY_ID: MemoryId = MemoryId::new(51);
 
 thread_local! {
     // static variable to store the map of principal_id to user_id
@@ -82,10 +88,25 @@ thread_local! {
         RefCell::new(StableBTreeMap::init(memory_manager.get(LP_TOKEN_MEMORY_ID)))
     });
 
+    // stable memory for storing OHLCV candles of the pools
+    pub static CANDLE_MAP: RefCell<StableBTreeMap<StableCandleId, StableCandle, Memory>> = with_memory_manager(|memory_manager| {
+        RefCell::new(StableBTreeMap::init(memory_manager.get(CANDLE_MEMORY_ID)))
+    });
+
+    // stable memory for storing daily swap volumes of the tokens
+    pub static TOKEN_VOLUME_MAP: RefCell<StableBTreeMap<StableTokenVolumeId, StableTokenVolume, Memory>> = with_memory_manager(|memory_manager| {
+        RefCell::new(StableBTreeMap::init(memory_manager.get(TOKEN_VOLUME_MEMORY_ID)))
+    });
+
     // stable memory for storing stable memory updates
     pub static DB_UPDATE_MAP: RefCell<StableBTreeMap<StableDBUpdateId, StableDBUpdate, Memory>> = with_memory_manager(|memory_manager| {
         RefCell::new(StableBTreeMap::init(memory_manager.get(DB_UPDATE_MEMORY_ID)))
     });
+
+    // stable memory for storing the consumers of the stable memory updates and what they have acknowledged
+    pub static DB_UPDATE_CONSUMER_MAP: RefCell<StableBTreeMap<StableDBUpdateConsumerId, StableDBUpdateConsumer, Memory>> = with_memory_manager(|memory_manager| {
+        RefCell::new(StableBTreeMap::init(memory_manager.get(DB_UPDATE_CONSUMER_MEMORY_ID)))
+    });
 }
 
 /// A helper function to access the memory manager.
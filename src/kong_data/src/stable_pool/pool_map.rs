@@ -26,6 +26,21 @@ pub fn get_by_token_wildcard(token: &str) -> Vec<StablePool> {
     })
 }
 
+/// get pool by its symbol or address, with or without chain. e.g. ckBTC_ckUSDT or IC.ckBTC_IC.ckUSDT
+pub fn get_by_symbol(pool: &str) -> Result<StablePool, String> {
+    POOL_MAP
+        .with(|m| {
+            m.borrow().iter().find_map(|(_, v)| {
+                if v.symbol() == pool || v.address() == pool || v.symbol_with_chain() == pool || v.address_with_chain() == pool {
+                    Some(v)
+                } else {
+                    None
+                }
+            })
+        })
+        .ok_or_else(|| format!("Pool {} not found", pool))
+}
+
 /// Get pool by LP token's id.
 pub fn get_by_lp_token_id(lp_token_id: u32) -> Option<StablePool> {
     POOL_MAP.with(|m| {
@@ -40,6 +40,8 @@ pub struct StablePool {
     pub lp_token_id: u32, // token id of the LP token
     #[serde(default = "false_bool")]
     pub is_removed: bool,
+    #[serde(default)]
+    pub fee_tier: Option<u8>, // fee tier in basis points of an additional pool of the pair, None for the first pool of the pair
 }
 
 fn false_bool() -> bool {
@@ -48,23 +50,38 @@ fn false_bool() -> bool {
 
 impl StablePool {
     pub fn symbol(&self) -> String {
-        format!("{}_{}", self.symbol_0(), self.symbol_1())
+        format!("{}_{}{}", self.symbol_0(), self.symbol_1(), self.fee_tier_suffix())
     }
 
     pub fn symbol_with_chain(&self) -> String {
-        format!("{}_{}", self.token_0().symbol_with_chain(), self.token_1().symbol_with_chain())
+        format!(
+            "{}_{}{}",
+            self.token_0().symbol_with_chain(),
+            self.token_1().symbol_with_chain(),
+            self.fee_tier_suffix()
+        )
     }
 
     pub fn address(&self) -> String {
-        format!("{}_{}", self.address_0(), self.address_1())
+        format!("{}_{}{}", self.address_0(), self.address_1(), self.fee_tier_suffix())
     }
 
     pub fn address_with_chain(&self) -> String {
-        format!("{}_{}", self.token_0().address_with_chain(), self.token_1().address_with_chain())
+        format!(
+            "{}_{}{}",
+            self.token_0().address_with_chain(),
+            self.token_1().address_with_chain(),
+            self.fee_tier_suffix()
+        )
     }
 
     pub fn name(&self) -> String {
-        format!("{}_{} Liquidity Pool", self.symbol_0(), self.symbol_1())
+        format!("{} Liquidity Pool", self.symbol())
+    }
+
+    /// additional fee tiers of a pair are Symbol_Symbol_FeeTier, the first pool of the pair is Symbol_Symbol
+    fn fee_tier_suffix(&self) -> String {
+        self.fee_tier.map(|fee_tier| format!("_{}", fee_tier)).unwrap_or_default()
     }
 
     pub fn token_0(&self) -> StableToken {
@@ -25,6 +25,21 @@ pub fn get_by_token_wildcard(token: &str) -> Vec<StableToken> {
     })
 }
 
+/// get token by its symbol or address, with or without chain. e.g. ckBTC or IC.ckBTC
+pub fn get_by_symbol(token: &str) -> Result<StableToken, String> {
+    TOKEN_MAP
+        .with(|m| {
+            m.borrow().iter().find_map(|(_, v)| {
+                if v.symbol() == token || v.address() == token || v.symbol_with_chain() == token || v.address_with_chain() == token {
+                    Some(v)
+                } else {
+                    None
+                }
+            })
+        })
+        .ok_or_else(|| format!("Token {} not found", token))
+}
+
 /// return all tokens
 pub fn get() -> Vec<StableToken> {
     TOKEN_MAP.with(|m| {
@@ -0,0 +1,3 @@
+#[allow(clippy::module_inception)]
+pub mod stable_token_volume;
+pub mod token_volume_map;
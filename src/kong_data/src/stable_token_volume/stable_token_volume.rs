@@ -0,0 +1,44 @@
+use candid::{CandidType, Nat};
+use ic_stable_structures::{storable::Bound, Storable};
+use serde::{Deserialize, Serialize};
+
+// field order is the sort order. volumes of a token are sorted by day
+#[derive(CandidType, Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+pub struct StableTokenVolumeId {
+    pub token_id: u32,
+    pub start_ts: u64,
+}
+
+impl Storable for StableTokenVolumeId {
+    fn to_bytes(&self) -> std::borrow::Cow<'_, [u8]> {
+        serde_cbor::to_vec(self).unwrap().into()
+    }
+
+    fn from_bytes(bytes: std::borrow::Cow<'_, [u8]>) -> Self {
+        serde_cbor::from_slice(&bytes).unwrap()
+    }
+
+    const BOUND: Bound = Bound::Unbounded;
+}
+
+/// daily swap volume of a token across all pools
+#[derive(CandidType, Debug, Clone, Serialize, Deserialize)]
+pub struct StableTokenVolume {
+    pub token_id: u32,
+    pub start_ts: u64,  // start of the day (UTC)
+    pub volume: Nat,    // amount paid and received in swaps
+    pub lp_fee: Nat,    // LP fees charged in the token
+    pub num_swaps: u64, // swaps through a pool of the token
+}
+
+impl Storable for StableTokenVolume {
+    fn to_bytes(&self) -> std::borrow::Cow<'_, [u8]> {
+        serde_cbor::to_vec(self).unwrap().into()
+    }
+
+    fn from_bytes(bytes: std::borrow::Cow<'_, [u8]>) -> Self {
+        serde_cbor::from_slice(&bytes).unwrap()
+    }
+
+    const BOUND: Bound = Bound::Unbounded;
+}
@@ -0,0 +1,50 @@
+use candid::Nat;
+
+use super::stable_token_volume::{StableTokenVolume, StableTokenVolumeId};
+
+use crate::helpers::nat_helpers::{nat_add, nat_zero};
+use crate::stable_candle::stable_candle::CandleInterval;
+use crate::stable_memory::TOKEN_VOLUME_MAP;
+
+/// add a swap through a pool of the token to the token's volume of the day
+pub fn add_swap(token_id: u32, amount: &Nat, lp_fee: &Nat, ts: u64) {
+    let volume_id = StableTokenVolumeId {
+        token_id,
+        start_ts: CandleInterval::OneDay.start_ts(ts),
+    };
+    TOKEN_VOLUME_MAP.with(|m| {
+        let mut map = m.borrow_mut();
+        let mut volume = map.get(&volume_id).unwrap_or(StableTokenVolume {
+            token_id,
+            start_ts: volume_id.start_ts,
+            volume: nat_zero(),
+            lp_fee: nat_zero(),
+            num_swaps: 0,
+        });
+        volume.volume = nat_add(&volume.volume, amount);
+        volume.lp_fee = nat_add(&volume.lp_fee, lp_fee);
+        volume.num_swaps += 1;
+        map.insert(volume_id, volume);
+    });
+}
+
+/// remove all daily volumes, before they are backfilled
+pub fn clear() {
+    TOKEN_VOLUME_MAP.with(|m| m.borrow_mut().clear_new());
+}
+
+/// daily volumes of the token from the day containing start_ts to the day containing end_ts, oldest first
+pub fn get(token_id: u32, start_ts: u64, end_ts: u64, num_days: usize) -> Vec<StableTokenVolume> {
+    if start_ts > end_ts {
+        return Vec::new();
+    }
+    let start_id = StableTokenVolumeId {
+        token_id,
+        start_ts: CandleInterval::OneDay.start_ts(start_ts),
+    };
+    let end_id = StableTokenVolumeId {
+        token_id,
+        start_ts: end_ts,
+    };
+    TOKEN_VOLUME_MAP.with(|m| m.borrow().range(start_id..=end_id).take(num_days).map(|(_, v)| v).collect())
+}
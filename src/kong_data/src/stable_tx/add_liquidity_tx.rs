@@ -3,6 +3,8 @@ use serde::{Deserialize, Serialize};
 
 use super::status_tx::StatusTx;
 
+use crate::swap::swap_calc::SwapCalc;
+
 #[derive(CandidType, Debug, Clone, Serialize, Deserialize)]
 pub struct AddLiquidityTx {
     pub tx_id: u64,
@@ -13,6 +15,8 @@ pub struct AddLiquidityTx {
     pub amount_0: Nat,
     pub amount_1: Nat,
     pub add_lp_token_amount: Nat,
+    #[serde(default)]
+    pub swap_txs: Vec<SwapCalc>, // internal swap of a single token add or remove liquidity
     pub transfer_ids: Vec<u64>,
     pub claim_ids: Vec<u64>,
     pub ts: u64,
@@ -0,0 +1,24 @@
+use candid::{CandidType, Nat};
+use serde::{Deserialize, Serialize};
+
+use super::status_tx::StatusTx;
+
+use crate::swap::swap_calc::SwapCalc;
+
+/// sweep of Kong's share of the swap fees of a token from the pools to the fee treasury and the KONG buyback
+#[derive(CandidType, Debug, Clone, Serialize, Deserialize)]
+pub struct FeeSweepTx {
+    pub tx_id: u64,
+    pub user_id: u32,
+    pub request_id: u64,
+    pub status: StatusTx,
+    pub token_id: u32,
+    pub amount: Nat,          // Kong fees swept from the pools
+    pub pool_ids: Vec<u32>,   // pools the Kong fees were swept from
+    pub treasury_amount: Nat, // sent to the fee treasury
+    pub buyback_amount: Nat,  // swapped into KONG
+    pub burn_amount: Nat,     // KONG burned
+    pub txs: Vec<SwapCalc>,   // swaps of the buyback
+    pub transfer_ids: Vec<u64>,
+    pub ts: u64,
+}
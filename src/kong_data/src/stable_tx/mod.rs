@@ -1,5 +1,6 @@
 pub mod add_liquidity_tx;
 pub mod add_pool_tx;
+pub mod fee_sweep_tx;
 pub mod remove_liquidity_tx;
 pub mod send_tx;
 #[allow(clippy::module_inception)]
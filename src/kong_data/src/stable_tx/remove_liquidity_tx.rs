@@ -3,6 +3,9 @@ use serde::{Deserialize, Serialize};
 
 use super::status_tx::StatusTx;
 
+use crate::ic::address::Address;
+use crate::swap::swap_calc::SwapCalc;
+
 #[derive(CandidType, Debug, Clone, Serialize, Deserialize)]
 pub struct RemoveLiquidityTx {
     pub tx_id: u64,
@@ -15,7 +18,11 @@ pub struct RemoveLiquidityTx {
     pub amount_1: Nat,
     pub lp_fee_1: Nat,
     pub remove_lp_token_amount: Nat,
+    #[serde(default)]
+    pub swap_txs: Vec<SwapCalc>, // internal swap of a single token add or remove liquidity
     pub transfer_ids: Vec<u64>,
     pub claim_ids: Vec<u64>,
+    #[serde(default)]
+    pub to_address: Option<Address>, // destination of token_0 and token_1. None if received to the deposit
     pub ts: u64,
 }
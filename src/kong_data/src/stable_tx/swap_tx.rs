@@ -2,7 +2,9 @@ use super::status_tx::StatusTx;
 use candid::{CandidType, Nat};
 use serde::{Deserialize, Serialize};
 
+use crate::ic::address::Address;
 use crate::swap::swap_calc::SwapCalc;
+use crate::swap::swap_leg::SwapLeg;
 
 #[derive(CandidType, Debug, Clone, Serialize, Deserialize)]
 pub struct SwapTx {
@@ -18,7 +20,11 @@ pub struct SwapTx {
     pub price: f64,
     pub slippage: f64,
     pub txs: Vec<SwapCalc>,
+    #[serde(default)]
+    pub legs: Vec<SwapLeg>, // txs grouped by path. a split swap has more than one leg
     pub transfer_ids: Vec<u64>,
     pub claim_ids: Vec<u64>,
+    #[serde(default)]
+    pub to_address: Option<Address>, // destination of the receive token. None if received to the deposit
     pub ts: u64,
 }
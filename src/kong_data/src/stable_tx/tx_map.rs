@@ -69,6 +69,16 @@ pub fn get_by_user_and_token_id(
                                 return Some(v.clone());
                             }
                         }
+                        StableTx::FeeSweep(ref fee_sweep_tx) => {
+                            if fee_sweep_tx.token_id == token_id {
+                                return Some(v.clone());
+                            }
+                            for tx in fee_sweep_tx.txs.iter() {
+                                if tx.pay_token_id == token_id || tx.receive_token_id == token_id {
+                                    return Some(v.clone());
+                                }
+                            }
+                        }
                     }
                     return None;
                 }
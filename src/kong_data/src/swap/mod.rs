@@ -1,4 +1,5 @@
 pub mod swap_args;
 pub mod swap_calc;
+pub mod swap_leg;
 pub mod swap_reply;
 pub mod swap_reply_helpers;
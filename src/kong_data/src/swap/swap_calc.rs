@@ -15,6 +15,8 @@ pub struct SwapCalc {
     pub receive_token_id: u32,
     pub receive_amount: Nat, // does not include any fees. used to keep a constant K with pay amount
     pub lp_fee: Nat,         // will be in receive_token
+    #[serde(default)]
+    pub lp_fee_bps: u8, // LP fee charged in basis points
     pub gas_fee: Nat,        // will be in receive_token
 }
 
@@ -0,0 +1,11 @@
+use candid::{CandidType, Nat};
+use serde::{Deserialize, Serialize};
+
+/// A swap can be split into several legs, each routed through its own path of pools
+#[derive(CandidType, Debug, Clone, Serialize, Deserialize)]
+pub struct SwapLeg {
+    pub pool_ids: Vec<u32>, // pools of the path, in order
+    pub pay_amount: Nat,
+    pub receive_amount: Nat, // net of fees and gas
+    pub fraction: f64,       // fraction of the swap's pay_amount routed through the leg
+}
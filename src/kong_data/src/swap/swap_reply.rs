@@ -45,6 +45,8 @@ pub struct SwapReply {
     pub txs: Vec<SwapTxReply>,
     pub transfer_ids: Vec<TransferIdReply>,
     pub claim_ids: Vec<u64>,
+    #[serde(default)]
+    pub to_address: Option<String>, // resolved destination of the receive token
     pub ts: u64,
 }
 
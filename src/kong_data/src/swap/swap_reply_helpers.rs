@@ -90,6 +90,7 @@ pub fn to_swap_reply(swap_tx: &SwapTx) -> SwapReply {
         txs: to_txs(&swap_tx.txs, swap_tx.ts),
         transfer_ids: to_transfer_ids(&swap_tx.transfer_ids),
         claim_ids: swap_tx.claim_ids.clone(),
+        to_address: swap_tx.to_address.as_ref().map(|address| address.to_string()),
         ts: swap_tx.ts,
     }
 }
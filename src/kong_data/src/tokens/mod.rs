@@ -1,5 +1,7 @@
 pub mod ic_reply;
 pub mod lp_reply;
+pub mod token_volumes;
+pub mod token_volumes_reply;
 #[allow(clippy::module_inception)]
 pub mod tokens;
 pub mod tokens_reply;
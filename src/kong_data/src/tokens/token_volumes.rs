@@ -0,0 +1,34 @@
+use ic_cdk::query;
+
+use super::token_volumes_reply::TokenVolumeReply;
+
+use crate::ic::get_time::get_time;
+use crate::ic::guards::not_in_maintenance_mode;
+use crate::stable_candle::stable_candle::CandleInterval;
+use crate::stable_token::token::Token;
+use crate::stable_token::token_map;
+use crate::stable_token_volume::token_volume_map;
+
+const MAX_DAYS: usize = 366;
+
+/// daily swap volumes of the token across all pools from the day containing from to the day containing to, oldest first
+/// - to defaults to now, from defaults to MAX_DAYS days before to
+/// - returns at most MAX_DAYS. for the next page, call again with from set to the start_ts of the last day + 1
+#[query(guard = "not_in_maintenance_mode")]
+fn token_volumes(token: String, from: Option<u64>, to: Option<u64>) -> Result<Vec<TokenVolumeReply>, String> {
+    let token = token_map::get_by_symbol(&token)?;
+    let to = to.unwrap_or_else(get_time);
+    let from = from.unwrap_or_else(|| to.saturating_sub(CandleInterval::OneDay.duration() * (MAX_DAYS as u64 - 1)));
+
+    let volumes = token_volume_map::get(token.token_id(), from, to, MAX_DAYS)
+        .into_iter()
+        .map(|volume| TokenVolumeReply {
+            start_ts: volume.start_ts,
+            volume: volume.volume,
+            lp_fee: volume.lp_fee,
+            num_swaps: volume.num_swaps,
+        })
+        .collect();
+
+    Ok(volumes)
+}
@@ -0,0 +1,10 @@
+use candid::{CandidType, Nat};
+use serde::{Deserialize, Serialize};
+
+#[derive(CandidType, Debug, Clone, Serialize, Deserialize)]
+pub struct TokenVolumeReply {
+    pub start_ts: u64, // start of the day (UTC)
+    pub volume: Nat,
+    pub lp_fee: Nat,
+    pub num_swaps: u64,
+}
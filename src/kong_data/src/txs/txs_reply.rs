@@ -3,6 +3,7 @@ use serde::{Deserialize, Serialize};
 
 use crate::add_liquidity::add_liquidity_reply::AddLiquidityReply;
 use crate::add_pool::add_pool_reply::AddPoolReply;
+use crate::fee_sweep::fee_sweep_reply::FeeSweepReply;
 use crate::remove_liquidity::remove_liquidity_reply::RemoveLiquidityReply;
 use crate::send::send_reply::SendReply;
 use crate::swap::swap_reply::SwapReply;
@@ -14,4 +15,5 @@ pub enum TxsReply {
     RemoveLiquidity(RemoveLiquidityReply),
     Swap(SwapReply),
     Send(SendReply),
+    FeeSweep(FeeSweepReply),
 }
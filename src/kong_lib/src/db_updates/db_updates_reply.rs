@@ -0,0 +1,20 @@
+use candid::CandidType;
+use serde::{Deserialize, Serialize};
+
+use crate::stable_db_update::stable_db_update::StableDBUpdate;
+
+#[derive(CandidType, Debug, Clone, Serialize, Deserialize)]
+pub struct DBUpdatesReply {
+    pub db_updates: Vec<StableDBUpdate>,
+    pub next_cursor: u64, // db_update_id of the last db update, or the cursor if there are none. cursor of the next call
+    pub has_more: bool,
+}
+
+#[derive(CandidType, Debug, Clone, Serialize, Deserialize)]
+pub struct DBUpdateConsumerReply {
+    pub consumer_id: String,
+    pub acked_db_update_id: u64,
+    pub registered_ts: u64,
+    pub acked_ts: u64,
+    pub lag: u64, // number of db updates not yet acknowledged
+}
@@ -0,0 +1 @@
+pub mod db_updates_reply;
@@ -4,6 +4,7 @@ pub mod add_pool;
 mod add_token;
 pub mod chains;
 mod claims;
+pub mod db_updates;
 pub mod helpers;
 pub mod ic;
 pub mod remove_liquidity;
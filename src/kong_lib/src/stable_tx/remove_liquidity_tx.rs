@@ -3,6 +3,8 @@ use serde::{Deserialize, Serialize};
 
 use super::status_tx::StatusTx;
 
+use crate::ic::address::Address;
+
 #[derive(CandidType, Debug, Clone, Serialize, Deserialize)]
 pub struct RemoveLiquidityTx {
     pub tx_id: u64,
@@ -17,6 +19,8 @@ pub struct RemoveLiquidityTx {
     pub remove_lp_token_amount: Nat,
     pub transfer_ids: Vec<u64>,
     pub claim_ids: Vec<u64>,
+    #[serde(default)]
+    pub to_address: Option<Address>, // destination of token_0 and token_1. None if received to the deposit
     pub ts: u64,
 }
 
@@ -48,6 +52,7 @@ impl RemoveLiquidityTx {
             remove_lp_token_amount: remove_lp_token_amount.clone(),
             transfer_ids: transfer_ids.to_vec(),
             claim_ids: claim_ids.to_vec(),
+            to_address: None,
             ts,
         }
     }
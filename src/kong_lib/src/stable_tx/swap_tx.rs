@@ -2,6 +2,7 @@ use super::status_tx::StatusTx;
 use candid::{CandidType, Nat};
 use serde::{Deserialize, Serialize};
 
+use crate::ic::address::Address;
 use crate::swap::swap_calc::SwapCalc;
 
 #[derive(CandidType, Debug, Clone, Serialize, Deserialize)]
@@ -20,6 +21,8 @@ pub struct SwapTx {
     pub txs: Vec<SwapCalc>,
     pub transfer_ids: Vec<u64>,
     pub claim_ids: Vec<u64>,
+    #[serde(default)]
+    pub to_address: Option<Address>, // destination of the receive token. None if received to the deposit
     pub ts: u64,
 }
 
@@ -55,6 +58,7 @@ impl SwapTx {
             txs: txs.to_vec(),
             transfer_ids: transfer_ids.to_vec(),
             claim_ids: claim_ids.to_vec(),
+            to_address: None,
             ts,
         }
     }
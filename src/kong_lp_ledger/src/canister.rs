@@ -0,0 +1,104 @@
+use candid::CandidType;
+use ic_cdk::api::call::{accept_message, method_name};
+use ic_cdk::{init, post_upgrade, pre_upgrade, query};
+use ic_cdk_macros::inspect_message;
+use serde::Deserialize;
+
+use super::APP_NAME;
+use crate::stable_memory::{get_settings, set_settings, LPLedgerSettings};
+use crate::sync::start_sync_timer;
+
+// a bit hard-coded but shouldn't change often
+static QUERY_METHODS: [&str; 15] = [
+    "icrc1_name",
+    "icrc1_symbol",
+    "icrc1_decimals",
+    "icrc1_fee",
+    "icrc1_metadata",
+    "icrc1_total_supply",
+    "icrc1_balance_of",
+    "icrc1_minting_account",
+    "icrc1_supported_standards",
+    "icrc10_supported_standards",
+    "icrc2_allowance",
+    "icrc3_get_blocks",
+    "icrc3_get_archives",
+    "icrc3_supported_block_types",
+    "icrc3_get_tip_certificate",
+];
+
+#[derive(CandidType, Debug, Clone, Deserialize)]
+pub struct SupportedStandard {
+    pub url: String,
+    pub name: String,
+}
+
+#[init]
+fn init(settings: LPLedgerSettings) {
+    ic_cdk::println!(
+        "{} canister has been initialized for LP token {} of {}",
+        APP_NAME,
+        settings.lp_token_id,
+        settings.kong_backend
+    );
+    set_settings(settings);
+    start_sync_timer();
+}
+
+#[pre_upgrade]
+fn pre_upgrade() {
+    ic_cdk::println!("{} canister is being upgraded", APP_NAME);
+}
+
+/// settings, balances, allowances and blocks are kept in stable memory across upgrades
+#[post_upgrade]
+fn post_upgrade() {
+    let settings = get_settings();
+    ic_cdk::println!("{} canister is upgraded for LP token {}", APP_NAME, settings.lp_token_id);
+    start_sync_timer();
+}
+
+/// inspect all ingress messages to the canister that are called as updates
+/// calling accept_message() will allow the message to be processed
+#[inspect_message]
+fn inspect_message() {
+    let method_name = method_name();
+    if QUERY_METHODS.contains(&method_name.as_str()) {
+        ic_cdk::trap(&format!("{} must be called as query", method_name));
+    }
+
+    accept_message();
+}
+
+fn supported_standards() -> Vec<SupportedStandard> {
+    vec![
+        SupportedStandard {
+            url: "https://github.com/dfinity/ICRC-1/tree/main/standards/ICRC-1".to_string(),
+            name: "ICRC-1".to_string(),
+        },
+        SupportedStandard {
+            url: "https://github.com/dfinity/ICRC-1/tree/main/standards/ICRC-2".to_string(),
+            name: "ICRC-2".to_string(),
+        },
+        SupportedStandard {
+            url: "https://github.com/dfinity/ICRC-1/tree/main/standards/ICRC-3".to_string(),
+            name: "ICRC-3".to_string(),
+        },
+        SupportedStandard {
+            url: "https://github.com/dfinity/ICRC/blob/main/ICRCs/ICRC-10/ICRC-10.md".to_string(),
+            name: "ICRC-10".to_string(),
+        },
+    ]
+}
+
+#[query]
+fn icrc1_supported_standards() -> Vec<SupportedStandard> {
+    supported_standards()
+}
+
+#[query]
+fn icrc10_supported_standards() -> Vec<SupportedStandard> {
+    supported_standards()
+}
+
+ic_cdk::export_candid!();
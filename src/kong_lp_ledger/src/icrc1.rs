@@ -0,0 +1,81 @@
+use candid::Nat;
+use ic_cdk::api::caller;
+use ic_cdk::{query, update};
+use icrc_ledger_types::icrc::generic_metadata_value::MetadataValue;
+use icrc_ledger_types::icrc1::account::Account;
+use icrc_ledger_types::icrc1::transfer::{TransferArg, TransferError};
+
+use crate::kong_backend;
+use crate::ledger;
+use crate::stable_memory::get_settings;
+use crate::sync;
+
+// LP tokens have 8 decimals
+const LP_DECIMALS: u8 = 8;
+
+/// metadata of the LP token as last synced from kong_backend
+#[query]
+fn icrc1_metadata() -> Vec<(String, MetadataValue)> {
+    ledger::metadata()
+}
+
+#[query]
+fn icrc1_name() -> String {
+    match ledger::metadata_value("icrc1:name") {
+        Some(MetadataValue::Text(name)) => name,
+        _ => String::new(),
+    }
+}
+
+#[query]
+fn icrc1_symbol() -> String {
+    match ledger::metadata_value("icrc1:symbol") {
+        Some(MetadataValue::Text(symbol)) => symbol,
+        _ => String::new(),
+    }
+}
+
+#[query]
+fn icrc1_decimals() -> u8 {
+    match ledger::metadata_value("icrc1:decimals") {
+        Some(MetadataValue::Nat(decimals)) => u8::try_from(decimals.0).unwrap_or(LP_DECIMALS),
+        _ => LP_DECIMALS,
+    }
+}
+
+/// LP token transfers have no fee
+#[query]
+fn icrc1_fee() -> Nat {
+    match ledger::metadata_value("icrc1:fee") {
+        Some(MetadataValue::Nat(fee)) => fee,
+        _ => Nat::from(0_u32),
+    }
+}
+
+#[query]
+fn icrc1_total_supply() -> Nat {
+    ledger::total_supply()
+}
+
+#[query]
+fn icrc1_balance_of(account: Account) -> Nat {
+    ledger::balance_of(&account)
+}
+
+/// LP tokens are minted and burned by kong_backend when liquidity is added and removed, not by transfers
+#[query]
+fn icrc1_minting_account() -> Option<Account> {
+    None
+}
+
+/// transfer of the caller's LP tokens. the transfer is made by kong_backend and synced before the reply
+#[update]
+async fn icrc1_transfer(args: TransferArg) -> Result<Nat, TransferError> {
+    let reply = kong_backend::update("lp_ledger_transfer", (get_settings().lp_token_id, caller(), args))
+        .await
+        .unwrap_or_else(|(error_code, message)| Err(TransferError::GenericError { error_code, message }));
+    if reply.is_ok() {
+        _ = sync::sync().await;
+    }
+    reply
+}
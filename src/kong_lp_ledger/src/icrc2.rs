@@ -0,0 +1,40 @@
+use candid::Nat;
+use ic_cdk::api::{caller, time};
+use ic_cdk::{query, update};
+use icrc_ledger_types::icrc2::allowance::{Allowance, AllowanceArgs};
+use icrc_ledger_types::icrc2::approve::{ApproveArgs, ApproveError};
+use icrc_ledger_types::icrc2::transfer_from::{TransferFromArgs, TransferFromError};
+
+use crate::kong_backend;
+use crate::ledger;
+use crate::stable_memory::get_settings;
+use crate::sync;
+
+/// approval of spender over the caller's LP tokens. the approval is made by kong_backend and synced before the reply
+#[update]
+async fn icrc2_approve(args: ApproveArgs) -> Result<Nat, ApproveError> {
+    let reply = kong_backend::update("lp_ledger_approve", (get_settings().lp_token_id, caller(), args))
+        .await
+        .unwrap_or_else(|(error_code, message)| Err(ApproveError::GenericError { error_code, message }));
+    if reply.is_ok() {
+        _ = sync::sync().await;
+    }
+    reply
+}
+
+#[query]
+fn icrc2_allowance(args: AllowanceArgs) -> Allowance {
+    ledger::allowance(&args.account, &args.spender, time())
+}
+
+/// transfer of LP tokens with the caller as spender. the transfer is made by kong_backend and synced before the reply
+#[update]
+async fn icrc2_transfer_from(args: TransferFromArgs) -> Result<Nat, TransferFromError> {
+    let reply = kong_backend::update("lp_ledger_transfer_from", (get_settings().lp_token_id, caller(), args))
+        .await
+        .unwrap_or_else(|(error_code, message)| Err(TransferFromError::GenericError { error_code, message }));
+    if reply.is_ok() {
+        _ = sync::sync().await;
+    }
+    reply
+}
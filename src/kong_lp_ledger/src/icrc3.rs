@@ -0,0 +1,53 @@
+use candid::Nat;
+use ic_cdk::query;
+use icrc_ledger_types::icrc3::archive::{GetArchivesArgs, ICRC3ArchiveInfo};
+use icrc_ledger_types::icrc3::blocks::{GetBlocksRequest, GetBlocksResult, ICRC3DataCertificate, SupportedBlockType};
+
+use crate::ledger;
+
+// max number of blocks returned per call
+const MAX_BLOCKS: u64 = 1_000;
+
+/// blocks synced from the LP token's block log in kong_backend. requests with a start or length too large are skipped
+#[query]
+fn icrc3_get_blocks(args: Vec<GetBlocksRequest>) -> GetBlocksResult {
+    let mut blocks = Vec::new();
+    for (start, length) in args.iter().filter_map(|request| request.as_start_and_length().ok()) {
+        let length = std::cmp::min(length, MAX_BLOCKS.saturating_sub(blocks.len() as u64));
+        blocks.extend(ledger::get_blocks(start, length));
+    }
+    GetBlocksResult {
+        log_length: Nat::from(ledger::log_length()),
+        blocks,
+        archived_blocks: Vec::new(),
+    }
+}
+
+/// all blocks are held by the ledger so there are no archives
+#[query]
+fn icrc3_get_archives(_args: GetArchivesArgs) -> Vec<ICRC3ArchiveInfo> {
+    Vec::new()
+}
+
+#[query]
+fn icrc3_supported_block_types() -> Vec<SupportedBlockType> {
+    [
+        ("1mint", "ICRC-1"),
+        ("1burn", "ICRC-1"),
+        ("1xfer", "ICRC-1"),
+        ("2xfer", "ICRC-2"),
+        ("2approve", "ICRC-2"),
+    ]
+    .into_iter()
+    .map(|(block_type, standard)| SupportedBlockType {
+        block_type: block_type.to_string(),
+        url: format!("https://github.com/dfinity/ICRC-1/tree/main/standards/{}", standard),
+    })
+    .collect()
+}
+
+/// blocks are not certified by the ledger, the LP tokens are certified by kong_backend in tokens_certified
+#[query]
+fn icrc3_get_tip_certificate() -> Option<ICRC3DataCertificate> {
+    None
+}
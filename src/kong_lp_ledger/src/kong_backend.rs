@@ -0,0 +1,70 @@
+use candid::utils::ArgumentEncoder;
+use candid::{CandidType, Nat};
+use icrc_ledger_types::icrc::generic_metadata_value::MetadataValue;
+use icrc_ledger_types::icrc::generic_value::ICRC3Value;
+use icrc_ledger_types::icrc1::account::Account;
+use icrc_ledger_types::icrc2::allowance::Allowance;
+use serde::de::DeserializeOwned;
+use serde::Deserialize;
+
+use crate::stable_memory::get_settings;
+
+// error_code of GenericError replies when kong_backend could not be called
+pub const GENERIC_ERROR_CODE: u32 = 1;
+
+/// balances and allowances of the LP token after the first log_length blocks
+#[derive(CandidType, Debug, Clone, Deserialize)]
+pub struct LPLedgerSnapshot {
+    pub metadata: Vec<(String, MetadataValue)>,
+    pub log_length: u64,
+    pub balances: Vec<(Account, Nat)>,
+    pub allowances: Vec<(Account, Account, Allowance)>, // (owner, spender, allowance)
+}
+
+/// block of the LP token's block log with the accounts and amount applied to the balances and allowances
+#[derive(CandidType, Debug, Clone, Deserialize)]
+pub struct LPLedgerBlock {
+    pub id: u64,
+    pub btype: String,
+    pub from: Option<Account>,
+    pub to: Option<Account>,
+    pub spender: Option<Account>,
+    pub amount: Nat,
+    pub expires_at: Option<u64>,
+    pub block: ICRC3Value,
+}
+
+#[derive(CandidType, Debug, Clone, Deserialize)]
+pub struct LPLedgerBlocks {
+    pub metadata: Vec<(String, MetadataValue)>,
+    pub log_length: u64,
+    pub blocks: Vec<LPLedgerBlock>,
+}
+
+/// call a method of kong_backend for the LP token that replies with a Result
+async fn call<A: ArgumentEncoder, T: CandidType + DeserializeOwned>(method: &str, args: A) -> Result<T, String> {
+    let kong_backend = get_settings().kong_backend;
+    ic_cdk::call::<A, (Result<T, String>,)>(kong_backend, method, args)
+        .await
+        .map_err(|e| format!("Failed to call {}. {}", method, e.1))?
+        .0
+}
+
+/// snapshot of the LP token's balances and allowances
+pub async fn snapshot() -> Result<LPLedgerSnapshot, String> {
+    call("lp_ledger_snapshot", (get_settings().lp_token_id,)).await
+}
+
+/// blocks of the LP token's block log from start, at most length blocks
+pub async fn blocks(start: u64, length: u64) -> Result<LPLedgerBlocks, String> {
+    call("lp_ledger_blocks", (get_settings().lp_token_id, start, length)).await
+}
+
+/// call an update of kong_backend for the LP token. call failures are returned as error_code and message
+pub async fn update<A: ArgumentEncoder, E: CandidType + DeserializeOwned>(method: &str, args: A) -> Result<Result<Nat, E>, (Nat, String)> {
+    let kong_backend = get_settings().kong_backend;
+    ic_cdk::call::<A, (Result<Nat, E>,)>(kong_backend, method, args)
+        .await
+        .map(|(reply,)| reply)
+        .map_err(|e| (Nat::from(GENERIC_ERROR_CODE), format!("Failed to call {}. {}", method, e.1)))
+}
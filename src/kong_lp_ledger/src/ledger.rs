@@ -0,0 +1,304 @@
+use candid::Nat;
+use icrc_ledger_types::icrc::generic_metadata_value::MetadataValue;
+use icrc_ledger_types::icrc1::account::Account;
+use icrc_ledger_types::icrc2::allowance::Allowance;
+use icrc_ledger_types::icrc3::blocks::BlockWithId;
+
+use crate::kong_backend::{LPLedgerBlock, LPLedgerSnapshot};
+use crate::stable_memory::{
+    get_ledger_state, set_ledger_state, LedgerState, StableAllowance, StableAllowanceId, StableBalance, StableBlock, ALLOWANCE_MAP,
+    BALANCE_MAP, BLOCK_MAP,
+};
+
+/// account with the default subaccount as None, so the same account always has the same key
+fn normalize_account(account: &Account) -> Account {
+    Account {
+        owner: account.owner,
+        subaccount: account.subaccount.filter(|subaccount| *subaccount != [0; 32]),
+    }
+}
+
+fn nat_subtract(a: &Nat, b: &Nat) -> Nat {
+    if a > b {
+        a.clone() - b.clone()
+    } else {
+        Nat::from(0_u32)
+    }
+}
+
+pub fn metadata() -> Vec<(String, MetadataValue)> {
+    get_ledger_state().metadata
+}
+
+pub fn metadata_value(key: &str) -> Option<MetadataValue> {
+    metadata().into_iter().find(|(k, _)| k == key).map(|(_, value)| value)
+}
+
+pub fn total_supply() -> Nat {
+    get_ledger_state().total_supply
+}
+
+pub fn balance_of(account: &Account) -> Nat {
+    BALANCE_MAP
+        .with(|m| m.borrow().get(&normalize_account(account)))
+        .map_or_else(|| Nat::from(0_u32), |balance| balance.0)
+}
+
+fn set_balance(account: &Account, amount: Nat) {
+    let account = normalize_account(account);
+    BALANCE_MAP.with(|m| {
+        let mut map = m.borrow_mut();
+        if amount == 0_u32 {
+            map.remove(&account);
+        } else {
+            map.insert(account, StableBalance(amount));
+        }
+    });
+}
+
+/// allowance of spender over the LP tokens of account at ts. expired allowances are zero
+pub fn allowance(account: &Account, spender: &Account, ts: u64) -> Allowance {
+    let key = StableAllowanceId(normalize_account(account), normalize_account(spender));
+    match ALLOWANCE_MAP.with(|m| m.borrow().get(&key)) {
+        Some(allowance) if allowance.expires_at.is_none_or(|expires_at| expires_at > ts) => Allowance {
+            allowance: allowance.amount,
+            expires_at: allowance.expires_at,
+        },
+        _ => Allowance {
+            allowance: Nat::from(0_u32),
+            expires_at: None,
+        },
+    }
+}
+
+fn set_allowance(account: &Account, spender: &Account, allowance: StableAllowance) {
+    let key = StableAllowanceId(normalize_account(account), normalize_account(spender));
+    ALLOWANCE_MAP.with(|m| {
+        let mut map = m.borrow_mut();
+        if allowance.amount == 0_u32 {
+            map.remove(&key);
+        } else {
+            map.insert(key, allowance);
+        }
+    });
+}
+
+/// number of blocks synced from kong_backend
+pub fn log_length() -> u64 {
+    BLOCK_MAP.with(|m| m.borrow().last_key_value().map_or(0, |(k, _)| k + 1))
+}
+
+/// blocks from start, at most length blocks
+pub fn get_blocks(start: u64, length: u64) -> Vec<BlockWithId> {
+    BLOCK_MAP.with(|m| {
+        m.borrow()
+            .range(start..)
+            .take(length as usize)
+            .map(|(id, block)| BlockWithId {
+                id: Nat::from(id),
+                block: block.0,
+            })
+            .collect()
+    })
+}
+
+pub fn is_snapshot_synced() -> bool {
+    get_ledger_state().snapshot_length.is_some()
+}
+
+pub fn set_metadata(metadata: Vec<(String, MetadataValue)>) {
+    set_ledger_state(LedgerState {
+        metadata,
+        ..get_ledger_state()
+    });
+}
+
+/// start the balances and allowances from the snapshot. the blocks before the snapshot's log_length are only logged
+/// ignored if the snapshot is already synced
+pub fn set_snapshot(snapshot: LPLedgerSnapshot) {
+    let mut state = get_ledger_state();
+    if state.snapshot_length.is_some() {
+        return;
+    }
+    let mut total_supply = Nat::from(0_u32);
+    for (account, amount) in snapshot.balances {
+        total_supply += amount.clone();
+        set_balance(&account, amount);
+    }
+    for (account, spender, allowance) in snapshot.allowances {
+        set_allowance(
+            &account,
+            &spender,
+            StableAllowance {
+                amount: allowance.allowance,
+                expires_at: allowance.expires_at,
+            },
+        );
+    }
+    state.metadata = snapshot.metadata;
+    state.snapshot_length = Some(snapshot.log_length);
+    state.total_supply = total_supply;
+    set_ledger_state(state);
+}
+
+/// log the blocks that follow the last synced block and apply the ones after the snapshot to the balances and allowances
+/// blocks already synced, e.g. by a sync that ran at the same time, are skipped
+pub fn append_blocks(blocks: Vec<LPLedgerBlock>) {
+    let Some(snapshot_length) = get_ledger_state().snapshot_length else {
+        return;
+    };
+    for block in blocks {
+        if block.id != log_length() {
+            continue;
+        }
+        if block.id >= snapshot_length {
+            apply_block(&block);
+        }
+        BLOCK_MAP.with(|m| m.borrow_mut().insert(block.id, StableBlock(block.block)));
+    }
+}
+
+fn apply_block(block: &LPLedgerBlock) {
+    let mut state = get_ledger_state();
+    match block.btype.as_str() {
+        "1mint" => {
+            if let Some(to) = &block.to {
+                set_balance(to, balance_of(to) + block.amount.clone());
+            }
+            state.total_supply += block.amount.clone();
+        }
+        "1burn" => {
+            if let Some(from) = &block.from {
+                set_balance(from, nat_subtract(&balance_of(from), &block.amount));
+            }
+            state.total_supply = nat_subtract(&state.total_supply, &block.amount);
+        }
+        "1xfer" | "2xfer" => {
+            if let (Some(from), Some(to)) = (&block.from, &block.to) {
+                set_balance(from, nat_subtract(&balance_of(from), &block.amount));
+                set_balance(to, balance_of(to) + block.amount.clone());
+                // transfers by a spender use up its allowance
+                if let Some(spender) = &block.spender {
+                    let key = StableAllowanceId(normalize_account(from), normalize_account(spender));
+                    if let Some(allowance) = ALLOWANCE_MAP.with(|m| m.borrow().get(&key)) {
+                        set_allowance(
+                            from,
+                            spender,
+                            StableAllowance {
+                                amount: nat_subtract(&allowance.amount, &block.amount),
+                                ..allowance
+                            },
+                        );
+                    }
+                }
+            }
+        }
+        "2approve" => {
+            if let (Some(from), Some(spender)) = (&block.from, &block.spender) {
+                set_allowance(
+                    from,
+                    spender,
+                    StableAllowance {
+                        amount: block.amount.clone(),
+                        expires_at: block.expires_at,
+                    },
+                );
+            }
+        }
+        _ => (),
+    }
+    set_ledger_state(state);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use candid::Principal;
+    use icrc_ledger_types::icrc::generic_value::ICRC3Value;
+
+    fn account(id: u8, subaccount: Option<[u8; 32]>) -> Account {
+        Account {
+            owner: Principal::from_slice(&[id, 1]),
+            subaccount,
+        }
+    }
+
+    fn block(id: u64, btype: &str, from: Option<Account>, to: Option<Account>, spender: Option<Account>, amount: u32) -> LPLedgerBlock {
+        LPLedgerBlock {
+            id,
+            btype: btype.to_string(),
+            from,
+            to,
+            spender,
+            amount: Nat::from(amount),
+            expires_at: None,
+            block: ICRC3Value::Nat(Nat::from(id)),
+        }
+    }
+
+    #[test]
+    fn test_sync_blocks() {
+        let owner = account(1, None);
+        let spender = account(2, None);
+        let to = account(1, Some([1; 32]));
+
+        // blocks before the snapshot are not applied again
+        set_snapshot(LPLedgerSnapshot {
+            metadata: vec![("icrc1:symbol".to_string(), MetadataValue::Text("A_B".to_string()))],
+            log_length: 1,
+            balances: vec![(owner, Nat::from(1_000_u32))],
+            allowances: Vec::new(),
+        });
+        append_blocks(vec![
+            block(0, "1mint", None, Some(owner), None, 1_000),
+            block(1, "2approve", Some(owner), None, Some(spender), 300),
+            block(2, "2xfer", Some(owner), Some(to), Some(spender), 200),
+            block(3, "1burn", Some(to), None, None, 50),
+        ]);
+        assert_eq!(log_length(), 4);
+        assert_eq!(balance_of(&owner), Nat::from(800_u32));
+        // the default subaccount is the same account as no subaccount
+        assert_eq!(balance_of(&account(1, Some([0; 32]))), Nat::from(800_u32));
+        assert_eq!(balance_of(&to), Nat::from(150_u32));
+        assert_eq!(total_supply(), Nat::from(950_u32));
+        assert_eq!(allowance(&owner, &spender, 0).allowance, Nat::from(100_u32));
+        assert_eq!(metadata_value("icrc1:symbol"), Some(MetadataValue::Text("A_B".to_string())));
+
+        // blocks already synced or not following the last block are skipped
+        append_blocks(vec![
+            block(3, "1burn", Some(to), None, None, 50),
+            block(5, "1burn", Some(to), None, None, 50),
+        ]);
+        assert_eq!(log_length(), 4);
+        assert_eq!(balance_of(&to), Nat::from(150_u32));
+        assert_eq!(
+            get_blocks(2, 10).iter().map(|block| block.id.clone()).collect::<Vec<_>>(),
+            vec![Nat::from(2_u32), Nat::from(3_u32)]
+        );
+
+        // a second snapshot is ignored
+        set_snapshot(LPLedgerSnapshot {
+            metadata: Vec::new(),
+            log_length: 0,
+            balances: Vec::new(),
+            allowances: Vec::new(),
+        });
+        assert_eq!(balance_of(&owner), Nat::from(800_u32));
+    }
+
+    #[test]
+    fn test_allowance_expiry() {
+        let owner = account(1, None);
+        let spender = account(2, Some([2; 32]));
+        set_allowance(
+            &owner,
+            &spender,
+            StableAllowance {
+                amount: Nat::from(300_u32),
+                expires_at: Some(10),
+            },
+        );
+        assert_eq!(allowance(&owner, &spender, 9).allowance, Nat::from(300_u32));
+        assert_eq!(allowance(&owner, &spender, 10).allowance, Nat::from(0_u32));
+    }
+}
@@ -0,0 +1,11 @@
+mod canister;
+mod icrc1;
+mod icrc2;
+mod icrc3;
+mod kong_backend;
+mod ledger;
+mod stable_memory;
+mod sync;
+
+pub const APP_NAME: &str = "Kong Swap LP Ledger";
+pub const APP_VERSION: &str = "v0.0.1";
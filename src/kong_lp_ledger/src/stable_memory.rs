@@ -0,0 +1,181 @@
+use candid::{CandidType, Decode, Encode, Nat, Principal};
+use ic_stable_structures::memory_manager::{MemoryId, MemoryManager, VirtualMemory};
+use ic_stable_structures::storable::Bound;
+use ic_stable_structures::{DefaultMemoryImpl, StableBTreeMap, StableCell, Storable};
+use icrc_ledger_types::icrc::generic_metadata_value::MetadataValue;
+use icrc_ledger_types::icrc::generic_value::ICRC3Value;
+use icrc_ledger_types::icrc1::account::Account;
+use serde::Deserialize;
+use std::borrow::Cow;
+use std::cell::RefCell;
+
+type Memory = VirtualMemory<DefaultMemoryImpl>;
+
+const SETTINGS_MEMORY_ID: MemoryId = MemoryId::new(0);
+const LEDGER_STATE_MEMORY_ID: MemoryId = MemoryId::new(1);
+const BALANCE_MEMORY_ID: MemoryId = MemoryId::new(2);
+const ALLOWANCE_MEMORY_ID: MemoryId = MemoryId::new(3);
+const BLOCK_MEMORY_ID: MemoryId = MemoryId::new(4);
+
+/// LP token served by the ledger. balances, allowances and blocks are synced from the LP token's block log in kong_backend
+#[derive(CandidType, Debug, Clone, Deserialize)]
+pub struct LPLedgerSettings {
+    pub kong_backend: Principal,
+    pub lp_token_id: u32,
+}
+
+impl Default for LPLedgerSettings {
+    fn default() -> Self {
+        Self {
+            kong_backend: Principal::anonymous(),
+            lp_token_id: 0,
+        }
+    }
+}
+
+impl Storable for LPLedgerSettings {
+    fn to_bytes(&self) -> Cow<'_, [u8]> {
+        Cow::Owned(Encode!(self).expect("Failed to encode LPLedgerSettings"))
+    }
+
+    fn from_bytes(bytes: Cow<'_, [u8]>) -> Self {
+        Decode!(bytes.as_ref(), Self).expect("Failed to decode LPLedgerSettings")
+    }
+
+    const BOUND: Bound = Bound::Unbounded;
+}
+
+/// metadata and total supply of the LP token, and the number of blocks the synced balances and allowances started from
+#[derive(CandidType, Debug, Clone, Default, Deserialize)]
+pub struct LedgerState {
+    pub metadata: Vec<(String, MetadataValue)>,
+    pub snapshot_length: Option<u64>, // None until the snapshot of the balances and allowances is synced
+    pub total_supply: Nat,
+}
+
+impl Storable for LedgerState {
+    fn to_bytes(&self) -> Cow<'_, [u8]> {
+        Cow::Owned(Encode!(self).expect("Failed to encode LedgerState"))
+    }
+
+    fn from_bytes(bytes: Cow<'_, [u8]>) -> Self {
+        Decode!(bytes.as_ref(), Self).expect("Failed to decode LedgerState")
+    }
+
+    const BOUND: Bound = Bound::Unbounded;
+}
+
+#[derive(CandidType, Debug, Clone, Deserialize)]
+pub struct StableBalance(pub Nat);
+
+impl Storable for StableBalance {
+    fn to_bytes(&self) -> Cow<'_, [u8]> {
+        Cow::Owned(Encode!(self).expect("Failed to encode StableBalance"))
+    }
+
+    fn from_bytes(bytes: Cow<'_, [u8]>) -> Self {
+        Decode!(bytes.as_ref(), Self).expect("Failed to decode StableBalance")
+    }
+
+    const BOUND: Bound = Bound::Unbounded;
+}
+
+/// (owner, spender)
+#[derive(CandidType, Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Deserialize)]
+pub struct StableAllowanceId(pub Account, pub Account);
+
+impl Storable for StableAllowanceId {
+    fn to_bytes(&self) -> Cow<'_, [u8]> {
+        Cow::Owned(Encode!(self).expect("Failed to encode StableAllowanceId"))
+    }
+
+    fn from_bytes(bytes: Cow<'_, [u8]>) -> Self {
+        Decode!(bytes.as_ref(), Self).expect("Failed to decode StableAllowanceId")
+    }
+
+    const BOUND: Bound = Bound::Unbounded;
+}
+
+#[derive(CandidType, Debug, Clone, Deserialize)]
+pub struct StableAllowance {
+    pub amount: Nat,
+    pub expires_at: Option<u64>,
+}
+
+impl Storable for StableAllowance {
+    fn to_bytes(&self) -> Cow<'_, [u8]> {
+        Cow::Owned(Encode!(self).expect("Failed to encode StableAllowance"))
+    }
+
+    fn from_bytes(bytes: Cow<'_, [u8]>) -> Self {
+        Decode!(bytes.as_ref(), Self).expect("Failed to decode StableAllowance")
+    }
+
+    const BOUND: Bound = Bound::Unbounded;
+}
+
+/// ICRC-3 block as logged by kong_backend
+#[derive(CandidType, Debug, Clone, Deserialize)]
+pub struct StableBlock(pub ICRC3Value);
+
+impl Storable for StableBlock {
+    fn to_bytes(&self) -> Cow<'_, [u8]> {
+        Cow::Owned(Encode!(self).expect("Failed to encode StableBlock"))
+    }
+
+    fn from_bytes(bytes: Cow<'_, [u8]>) -> Self {
+        Decode!(bytes.as_ref(), Self).expect("Failed to decode StableBlock")
+    }
+
+    const BOUND: Bound = Bound::Unbounded;
+}
+
+thread_local! {
+    // MEMORY_MANAGER is given management of the entire stable memory. Given a 'MemoryId', it can
+    // return a memory that can be used by stable structures
+    pub static MEMORY_MANAGER: RefCell<MemoryManager<DefaultMemoryImpl>> =
+        RefCell::new(MemoryManager::init(DefaultMemoryImpl::default()));
+
+    pub static SETTINGS: RefCell<StableCell<LPLedgerSettings, Memory>> = with_memory_manager(|memory_manager| {
+        RefCell::new(StableCell::init(memory_manager.get(SETTINGS_MEMORY_ID), LPLedgerSettings::default()).expect("Failed to initialize settings"))
+    });
+
+    pub static LEDGER_STATE: RefCell<StableCell<LedgerState, Memory>> = with_memory_manager(|memory_manager| {
+        RefCell::new(StableCell::init(memory_manager.get(LEDGER_STATE_MEMORY_ID), LedgerState::default()).expect("Failed to initialize ledger state"))
+    });
+
+    // balances by account, accounts with the default subaccount have subaccount None
+    pub static BALANCE_MAP: RefCell<StableBTreeMap<Account, StableBalance, Memory>> = with_memory_manager(|memory_manager| {
+        RefCell::new(StableBTreeMap::init(memory_manager.get(BALANCE_MEMORY_ID)))
+    });
+
+    pub static ALLOWANCE_MAP: RefCell<StableBTreeMap<StableAllowanceId, StableAllowance, Memory>> = with_memory_manager(|memory_manager| {
+        RefCell::new(StableBTreeMap::init(memory_manager.get(ALLOWANCE_MEMORY_ID)))
+    });
+
+    // blocks by block index
+    pub static BLOCK_MAP: RefCell<StableBTreeMap<u64, StableBlock, Memory>> = with_memory_manager(|memory_manager| {
+        RefCell::new(StableBTreeMap::init(memory_manager.get(BLOCK_MEMORY_ID)))
+    });
+}
+
+/// A helper function to access the memory manager.
+fn with_memory_manager<R>(f: impl FnOnce(&MemoryManager<DefaultMemoryImpl>) -> R) -> R {
+    MEMORY_MANAGER.with(|cell| f(&cell.borrow()))
+}
+
+pub fn get_settings() -> LPLedgerSettings {
+    SETTINGS.with(|s| s.borrow().get().clone())
+}
+
+pub fn set_settings(settings: LPLedgerSettings) {
+    SETTINGS.with(|s| s.borrow_mut().set(settings).expect("Failed to save settings"));
+}
+
+pub fn get_ledger_state() -> LedgerState {
+    LEDGER_STATE.with(|s| s.borrow().get().clone())
+}
+
+pub fn set_ledger_state(state: LedgerState) {
+    LEDGER_STATE.with(|s| s.borrow_mut().set(state).expect("Failed to save ledger state"));
+}
@@ -0,0 +1,40 @@
+use std::time::Duration;
+
+use crate::kong_backend;
+use crate::ledger;
+
+// interval of the sync of the balances, allowances and blocks with the LP token's block log in kong_backend
+const SYNC_INTERVAL_SECS: u64 = 10;
+// max number of blocks synced per call to kong_backend
+const MAX_SYNC_BLOCKS: u64 = 1_000;
+
+/// sync now and then every SYNC_INTERVAL_SECS
+pub fn start_sync_timer() {
+    ic_cdk_timers::set_timer(Duration::ZERO, || ic_cdk::spawn(sync_logged()));
+    ic_cdk_timers::set_timer_interval(Duration::from_secs(SYNC_INTERVAL_SECS), || ic_cdk::spawn(sync_logged()));
+}
+
+async fn sync_logged() {
+    if let Err(e) = sync().await {
+        ic_cdk::println!("Failed to sync ledger. {}", e);
+    }
+}
+
+/// bring the balances, allowances and blocks up to date with the LP token's block log in kong_backend
+/// - the first sync starts the balances and allowances from a snapshot, as LP tokens may have been minted before the block log
+/// - the blocks after the snapshot are then applied in order
+pub async fn sync() -> Result<(), String> {
+    if !ledger::is_snapshot_synced() {
+        ledger::set_snapshot(kong_backend::snapshot().await?);
+    }
+    loop {
+        let start = ledger::log_length();
+        let reply = kong_backend::blocks(start, MAX_SYNC_BLOCKS).await?;
+        ledger::set_metadata(reply.metadata);
+        ledger::append_blocks(reply.blocks);
+        let log_length = ledger::log_length();
+        if log_length == start || log_length >= reply.log_length {
+            return Ok(());
+        }
+    }
+}
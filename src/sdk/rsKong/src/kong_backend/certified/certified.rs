@@ -0,0 +1,229 @@
+use anyhow::{anyhow, Result};
+use candid::{encode_args, Decode, Encode, Nat};
+use ic_agent::hash_tree::{HashTree, LookupResult};
+use ic_agent::{lookup_value, Certificate};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use super::certified_hash::{hash_lp_token, hash_pool, hash_token, Hash};
+use super::certified_reply::{
+    CertifiedPoolReply, CertifiedPoolsReply, CertifiedSwapAmountsReply, CertifiedTokenReply, CertifiedTokensReply,
+    CertifiedUserBalancesReply,
+};
+
+use crate::kong_backend::KongBackend;
+
+const LABEL_LP_TOKENS: &[u8] = b"lp_tokens";
+const LABEL_POOLS: &[u8] = b"pools";
+const LABEL_TOKENS: &[u8] = b"tokens";
+
+impl KongBackend {
+    /// pools(opt wildcard) verified against the certified data of Kong backend
+    #[allow(dead_code)]
+    pub async fn pools_certified(&self, symbol: Option<&str>) -> Result<Vec<CertifiedPoolReply>> {
+        let results = self
+            .agent
+            .query(&self.principal_id, "pools_certified")
+            .with_arg(Encode!(&symbol)?)
+            .await?;
+        let reply = Decode!(results.as_slice(), Result<CertifiedPoolsReply, String>)?.map_err(|e| anyhow!(e))?;
+        let witness = self.verify_certificate(&reply.certificate, &reply.witness)?;
+        verify_pools(&witness, &reply.pools)?;
+        Ok(reply.pools)
+    }
+
+    /// tokens(opt wildcard) verified against the certified data of Kong backend
+    #[allow(dead_code)]
+    pub async fn tokens_certified(&self, symbol: Option<&str>) -> Result<Vec<CertifiedTokenReply>> {
+        let results = self
+            .agent
+            .query(&self.principal_id, "tokens_certified")
+            .with_arg(Encode!(&symbol)?)
+            .await?;
+        let reply = Decode!(results.as_slice(), Result<CertifiedTokensReply, String>)?.map_err(|e| anyhow!(e))?;
+        let witness = self.verify_certificate(&reply.certificate, &reply.witness)?;
+        verify_tokens(&witness, &reply.tokens)?;
+        Ok(reply.tokens)
+    }
+
+    /// LP token balances of principal_id and their pools verified against the certified data of Kong backend
+    #[allow(dead_code)]
+    pub async fn user_balances_certified(&self, principal_id: &str) -> Result<CertifiedUserBalancesReply> {
+        let results = self
+            .agent
+            .query(&self.principal_id, "user_balances_certified")
+            .with_arg(Encode!(&principal_id)?)
+            .await?;
+        let reply = Decode!(results.as_slice(), Result<CertifiedUserBalancesReply, String>)?.map_err(|e| anyhow!(e))?;
+        let witness = self.verify_certificate(&reply.certificate, &reply.witness)?;
+        for lp_token in &reply.lp_tokens {
+            let path = [LABEL_LP_TOKENS, principal_id.as_bytes(), &lp_token.token_id.to_be_bytes()];
+            verify_leaf(&witness, &path, &hash_lp_token(lp_token))
+                .map_err(|_| anyhow!("LP token {} of {} not certified", lp_token.token_id, principal_id))?;
+        }
+        verify_pools(&witness, &reply.pools)?;
+        Ok(reply)
+    }
+
+    /// swap_amounts() with the pools and tokens it was calculated from verified against the certified data of Kong backend
+    /// the swap amounts themselves are not certified and can be recalculated from the pools and tokens
+    #[allow(dead_code)]
+    pub async fn swap_amounts_certified(
+        &self,
+        pay_symbol: &str,
+        pay_amount: &Nat,
+        receive_symbol: &str,
+    ) -> Result<CertifiedSwapAmountsReply> {
+        let results = self
+            .agent
+            .query(&self.principal_id, "swap_amounts_certified")
+            .with_arg(encode_args((pay_symbol, pay_amount, receive_symbol))?)
+            .await?;
+        let reply = Decode!(results.as_slice(), Result<CertifiedSwapAmountsReply, String>)?.map_err(|e| anyhow!(e))?;
+        let witness = self.verify_certificate(&reply.certificate, &reply.witness)?;
+        verify_pools(&witness, &reply.pools)?;
+        verify_tokens(&witness, &reply.tokens)?;
+        Ok(reply)
+    }
+
+    /// verify the certificate is signed by the IC for Kong backend, is not older than certificate_max_age and the root hash
+    /// of the witness is its certified data
+    fn verify_certificate(&self, certificate: &[u8], witness: &[u8]) -> Result<HashTree<Vec<u8>>> {
+        let certificate: Certificate = serde_cbor::from_slice(certificate).map_err(|e| anyhow!("Failed to decode certificate: {}", e))?;
+        self.agent.verify(&certificate, self.principal_id)?;
+        let time = lookup_value(&certificate, [b"time".as_slice()])?;
+        let now = SystemTime::now().duration_since(UNIX_EPOCH)?;
+        verify_certificate_time(time, now, self.certificate_max_age)?;
+        let certified_data = lookup_value(
+            &certificate,
+            [b"canister".as_slice(), self.principal_id.as_slice(), b"certified_data".as_slice()],
+        )?;
+        let witness: HashTree<Vec<u8>> = serde_cbor::from_slice(witness).map_err(|e| anyhow!("Failed to decode witness: {}", e))?;
+        if witness.digest().as_slice() != certified_data {
+            return Err(anyhow!("Witness does not match certified data"));
+        }
+        Ok(witness)
+    }
+}
+
+/// time is the LEB128 encoded time of the certificate in nanoseconds since the epoch. a replica could otherwise answer
+/// with a validly signed but stale certificate
+fn verify_certificate_time(time: &[u8], now: Duration, max_age: Duration) -> Result<()> {
+    let time = Duration::from_nanos(decode_leb128(time)?);
+    let age = now.saturating_sub(time);
+    if age > max_age {
+        return Err(anyhow!("Certificate is {}s old, max age is {}s", age.as_secs(), max_age.as_secs()));
+    }
+    Ok(())
+}
+
+fn decode_leb128(bytes: &[u8]) -> Result<u64> {
+    let mut value: u64 = 0;
+    for (i, byte) in bytes.iter().enumerate().take(10) {
+        value |= u64::from(byte & 0x7f) << (7 * i);
+        if byte & 0x80 == 0 {
+            return Ok(value);
+        }
+    }
+    Err(anyhow!("Invalid certificate time"))
+}
+
+fn verify_pools(witness: &HashTree<Vec<u8>>, pools: &[CertifiedPoolReply]) -> Result<()> {
+    for pool in pools {
+        verify_leaf(witness, &[LABEL_POOLS, &pool.pool_id.to_be_bytes()], &hash_pool(pool))
+            .map_err(|_| anyhow!("Pool {} not certified", pool.pool_id))?;
+    }
+    Ok(())
+}
+
+fn verify_tokens(witness: &HashTree<Vec<u8>>, tokens: &[CertifiedTokenReply]) -> Result<()> {
+    for token in tokens {
+        verify_leaf(witness, &[LABEL_TOKENS, &token.token_id.to_be_bytes()], &hash_token(token))
+            .map_err(|_| anyhow!("Token {} not certified", token.symbol))?;
+    }
+    Ok(())
+}
+
+fn verify_leaf(witness: &HashTree<Vec<u8>>, path: &[&[u8]], hash: &Hash) -> Result<()> {
+    match witness.lookup_path(path) {
+        LookupResult::Found(leaf) if leaf == hash.as_slice() => Ok(()),
+        _ => Err(anyhow!("Leaf not certified")),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use candid::{CandidType, Principal};
+    use serde::de::DeserializeOwned;
+
+    use kong_backend::certified::{certified_hash as backend_hash, certified_reply_helpers, certified_tree};
+    use kong_backend::stable_pool::{pool_type::PoolType, stable_pool::StablePool};
+    use kong_backend::stable_token::{ic_token::ICToken, stable_token::StableToken};
+
+    /// the SDK reply of a kong_backend reply, as decoded from the canister
+    fn to_sdk_reply<T: CandidType, R: CandidType + DeserializeOwned>(reply: &T) -> R {
+        Decode!(&Encode!(reply).unwrap(), R).unwrap()
+    }
+
+    #[test]
+    fn test_certified_tree_parity() {
+        let pool = StablePool {
+            pool_id: 1,
+            balance_0: Nat::from(1_000_000_u64),
+            balance_1: Nat::from(2_000_000_u64),
+            lp_fee_0: Nat::from(300_u64),
+            ..StablePool::new(1, 2, 30, 10, 3, PoolType::ConstantProduct, None)
+        };
+        let token = StableToken::IC(ICToken {
+            token_id: 1,
+            name: "Token 1".to_string(),
+            symbol: "T1".to_string(),
+            canister_id: Principal::from_slice(&[1]),
+            decimals: 8,
+            fee: Nat::from(10_000_u64),
+            icrc1: true,
+            icrc2: true,
+            icrc3: true,
+            is_removed: false,
+            on_probation: false,
+            listing_deposit: None,
+        });
+        certified_tree::update_pool(&pool);
+        certified_tree::update_token(&token);
+        let witness: HashTree<Vec<u8>> = serde_cbor::from_slice(&certified_tree::witness(true, true, None).unwrap()).unwrap();
+
+        // the leaves are hashed the same as in kong_backend and are found in its witness
+        let backend_pool = certified_reply_helpers::to_certified_pool_reply(&pool);
+        let sdk_pool: CertifiedPoolReply = to_sdk_reply(&backend_pool);
+        assert_eq!(hash_pool(&sdk_pool), backend_hash::hash_pool(&backend_pool));
+        assert!(verify_pools(&witness, std::slice::from_ref(&sdk_pool)).is_ok());
+        let backend_token = certified_reply_helpers::to_certified_token_reply(&token);
+        let sdk_token: CertifiedTokenReply = to_sdk_reply(&backend_token);
+        assert_eq!(hash_token(&sdk_token), backend_hash::hash_token(&backend_token));
+        assert!(verify_tokens(&witness, std::slice::from_ref(&sdk_token)).is_ok());
+
+        // changed records are not certified
+        let changed_pool = CertifiedPoolReply {
+            balance_0: Nat::from(1_000_001_u64),
+            ..sdk_pool
+        };
+        assert!(verify_pools(&witness, &[changed_pool]).is_err());
+        let changed_token = CertifiedTokenReply { decimals: 6, ..sdk_token };
+        assert!(verify_tokens(&witness, &[changed_token]).is_err());
+    }
+
+    #[test]
+    fn test_verify_certificate_time() {
+        let max_age = Duration::from_secs(5 * 60);
+        // LEB128 of 624485 is e5 8e 26
+        assert_eq!(decode_leb128(&[0xe5, 0x8e, 0x26]).unwrap(), 624_485);
+        assert!(decode_leb128(&[0x80]).is_err());
+
+        let time = Duration::from_secs(1_700_000_000);
+        let mut leb128 = Vec::new();
+        Nat::from(time.as_nanos() as u64).encode(&mut leb128).unwrap();
+        assert!(verify_certificate_time(&leb128, time + max_age, max_age).is_ok());
+        assert!(verify_certificate_time(&leb128, time - Duration::from_secs(1), max_age).is_ok());
+        assert!(verify_certificate_time(&leb128, time + max_age + Duration::from_secs(1), max_age).is_err());
+    }
+}
@@ -0,0 +1,81 @@
+use candid::Nat;
+use sha2::{Digest, Sha256};
+
+use super::certified_reply::{CertifiedLPTokenReply, CertifiedPoolReply, CertifiedTokenReply};
+
+// leaves of the certified tree must be hashed the same as in kong_backend
+pub type Hash = [u8; 32];
+
+/// field values of a certified record. bools are hashed as nat 0 or 1
+enum Value<'a> {
+    Nat(Nat),
+    Text(&'a str),
+}
+
+/// leaf of pools/{pool_id}
+pub fn hash_pool(pool: &CertifiedPoolReply) -> Hash {
+    hash_of_map(&[
+        ("pool_id", Value::Nat(Nat::from(pool.pool_id))),
+        ("token_id_0", Value::Nat(Nat::from(pool.token_id_0))),
+        ("balance_0", Value::Nat(pool.balance_0.clone())),
+        ("lp_fee_0", Value::Nat(pool.lp_fee_0.clone())),
+        ("token_id_1", Value::Nat(Nat::from(pool.token_id_1))),
+        ("balance_1", Value::Nat(pool.balance_1.clone())),
+        ("lp_fee_1", Value::Nat(pool.lp_fee_1.clone())),
+        ("lp_fee_bps", Value::Nat(Nat::from(pool.lp_fee_bps))),
+        ("lp_token_id", Value::Nat(Nat::from(pool.lp_token_id))),
+        ("is_removed", Value::Nat(Nat::from(pool.is_removed as u8))),
+    ])
+}
+
+/// leaf of tokens/{token_id}
+pub fn hash_token(token: &CertifiedTokenReply) -> Hash {
+    hash_of_map(&[
+        ("token_id", Value::Nat(Nat::from(token.token_id))),
+        ("chain", Value::Text(&token.chain)),
+        ("address", Value::Text(&token.address)),
+        ("symbol", Value::Text(&token.symbol)),
+        ("decimals", Value::Nat(Nat::from(token.decimals))),
+        ("fee", Value::Nat(token.fee.clone())),
+        ("is_removed", Value::Nat(Nat::from(token.is_removed as u8))),
+    ])
+}
+
+/// leaf of lp_tokens/{principal_id}/{token_id}
+pub fn hash_lp_token(lp_token: &CertifiedLPTokenReply) -> Hash {
+    hash_of_map(&[
+        ("token_id", Value::Nat(Nat::from(lp_token.token_id))),
+        ("amount", Value::Nat(lp_token.amount.clone())),
+    ])
+}
+
+/// representation-independent hash of a map, as specified in the IC interface specification
+fn hash_of_map(fields: &[(&str, Value)]) -> Hash {
+    let mut field_hashes = fields
+        .iter()
+        .map(|(key, value)| [hash_bytes(key.as_bytes()), hash_value(value)].concat())
+        .collect::<Vec<Vec<u8>>>();
+    field_hashes.sort_unstable();
+
+    let mut hasher = Sha256::new();
+    for field_hash in field_hashes {
+        hasher.update(field_hash);
+    }
+    hasher.finalize().into()
+}
+
+fn hash_value(value: &Value) -> Hash {
+    match value {
+        Value::Nat(n) => {
+            // nats are hashed as their unsigned LEB128 encoding
+            let mut leb128 = Vec::new();
+            n.encode(&mut leb128).expect("Failed to encode nat");
+            hash_bytes(&leb128)
+        }
+        Value::Text(s) => hash_bytes(s.as_bytes()),
+    }
+}
+
+fn hash_bytes(bytes: &[u8]) -> Hash {
+    Sha256::digest(bytes).into()
+}
@@ -0,0 +1,66 @@
+use candid::{CandidType, Nat};
+use serde::Deserialize;
+
+use crate::kong_backend::swap_amounts::swap_amounts_reply::SwapAmountsReply;
+
+#[derive(CandidType, Debug, Clone, Deserialize)]
+pub struct CertifiedPoolReply {
+    pub pool_id: u32,
+    pub token_id_0: u32,
+    pub balance_0: Nat,
+    pub lp_fee_0: Nat,
+    pub token_id_1: u32,
+    pub balance_1: Nat,
+    pub lp_fee_1: Nat,
+    pub lp_fee_bps: u8,
+    pub lp_token_id: u32,
+    pub is_removed: bool,
+}
+
+#[derive(CandidType, Debug, Clone, Deserialize)]
+pub struct CertifiedTokenReply {
+    pub token_id: u32,
+    pub chain: String,
+    pub address: String,
+    pub symbol: String,
+    pub decimals: u8,
+    pub fee: Nat,
+    pub is_removed: bool,
+}
+
+#[derive(CandidType, Debug, Clone, Deserialize)]
+pub struct CertifiedLPTokenReply {
+    pub token_id: u32,
+    pub amount: Nat,
+}
+
+#[derive(CandidType, Debug, Clone, Deserialize)]
+pub struct CertifiedPoolsReply {
+    pub pools: Vec<CertifiedPoolReply>,
+    pub certificate: Vec<u8>,
+    pub witness: Vec<u8>,
+}
+
+#[derive(CandidType, Debug, Clone, Deserialize)]
+pub struct CertifiedTokensReply {
+    pub tokens: Vec<CertifiedTokenReply>,
+    pub certificate: Vec<u8>,
+    pub witness: Vec<u8>,
+}
+
+#[derive(CandidType, Debug, Clone, Deserialize)]
+pub struct CertifiedUserBalancesReply {
+    pub lp_tokens: Vec<CertifiedLPTokenReply>,
+    pub pools: Vec<CertifiedPoolReply>,
+    pub certificate: Vec<u8>,
+    pub witness: Vec<u8>,
+}
+
+#[derive(CandidType, Debug, Deserialize)]
+pub struct CertifiedSwapAmountsReply {
+    pub swap_amounts: SwapAmountsReply,
+    pub pools: Vec<CertifiedPoolReply>,
+    pub tokens: Vec<CertifiedTokenReply>,
+    pub certificate: Vec<u8>,
+    pub witness: Vec<u8>,
+}
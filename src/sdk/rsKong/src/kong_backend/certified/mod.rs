@@ -0,0 +1,4 @@
+#[allow(clippy::module_inception)]
+pub mod certified;
+pub mod certified_hash;
+pub mod certified_reply;
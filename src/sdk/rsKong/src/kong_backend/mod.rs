@@ -2,11 +2,13 @@ use anyhow::Result;
 use candid::{Decode, Encode, Principal};
 use ic_agent::Agent;
 use icrc_ledger_types::icrc1::account::Account;
+use std::time::Duration;
 use tokens::tokens_reply::TokensReply;
 
 pub mod add_liquidity;
 pub mod add_liquidity_amounts;
 mod canister;
+pub mod certified;
 pub mod claim;
 pub mod helpers;
 pub mod pools;
@@ -20,6 +22,8 @@ pub mod transfers;
 
 const KONG_BACKEND_STAGING: &str = "l4lgk-raaaa-aaaar-qahpq-cai";
 const KONG_BACKEND_PROD: &str = "2ipq2-uqaaa-aaaar-qailq-cai";
+// certificates of certified queries older than this are rejected as stale
+const DEFAULT_CERTIFICATE_MAX_AGE: Duration = Duration::from_secs(5 * 60);
 
 #[derive(Clone)]
 pub struct KongBackend {
@@ -27,6 +31,7 @@ pub struct KongBackend {
     principal_id: Principal,
     account_id: Account,
     tokens: Vec<TokensReply>,
+    certificate_max_age: Duration,
 }
 
 impl KongBackend {
@@ -41,11 +46,19 @@ impl KongBackend {
             principal_id,
             account_id: Account::from(principal_id),
             tokens: Vec::new(),
+            certificate_max_age: DEFAULT_CERTIFICATE_MAX_AGE,
         };
         _ = instance.tokens(None).await; // populate tokens
         instance
     }
 
+    /// max age of the certificates of certified queries. older certificates are rejected as stale
+    #[allow(dead_code)]
+    pub fn with_certificate_max_age(mut self, certificate_max_age: Duration) -> Self {
+        self.certificate_max_age = certificate_max_age;
+        self
+    }
+
     #[allow(dead_code)]
     pub async fn icrc1_name(&self) -> Result<String> {
         let icrc1_name = self